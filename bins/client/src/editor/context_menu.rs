@@ -0,0 +1,266 @@
+//! Right-click context menu on tiles, applied locally.
+//!
+//! The menu reuses the in-game [`ContextMenuAction`] buttons, but instead of
+//! sending tile toggle RPCs or floor requests to a server, the chosen action
+//! is written straight into the editor's [`TileGrid`]s and announced with
+//! [`TileMutated`] / [`FloorMutated`] so the tile visuals follow, exactly as
+//! [`paint_tiles`](super::painting::paint_tiles) does.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use interactions::ContextMenuAction;
+use tiles::{FloorKind, FloorMutated, TileGrid, TileKind, TileMutated};
+use ui::{UiTheme, WorldSpaceOverlay, build_button};
+
+use super::camera::EditorCamera;
+use super::grid;
+use super::spawns::{EditorSpawnMarker, marker_near};
+
+/// Structures offered in the menu, with their labels.
+const STRUCTURE_ENTRIES: [(&str, TileKind); 5] = [
+    ("Clear to Floor", TileKind::Floor),
+    ("Set Wall", TileKind::Wall),
+    ("Set Door", TileKind::Door { open: false }),
+    ("Set Ladder", TileKind::Ladder),
+    ("Open to Space", TileKind::Space),
+];
+
+/// Floor coverings offered in the menu, with their labels.
+const FLOOR_ENTRIES: [(&str, FloorKind); 2] = [
+    ("Bare Plating", FloorKind::Plating),
+    ("Lay Carpet", FloorKind::Carpet),
+];
+
+/// Root entity of the open editor context menu.  Present only while a menu
+/// is open.
+#[derive(Resource)]
+pub struct EditorMenu(Entity);
+
+/// Menu entries for the cell at `position` holding `structure` on `floor`:
+/// every structure and floor covering other than the current one.
+pub fn editor_menu_entries(
+    position: IVec2,
+    structure: TileKind,
+    floor: FloorKind,
+) -> Vec<(&'static str, ContextMenuAction)> {
+    let structures = STRUCTURE_ENTRIES
+        .into_iter()
+        .filter(|&(_, kind)| kind != structure)
+        .map(|(label, kind)| (label, ContextMenuAction::TileToggle { position, kind }));
+    let floors = FLOOR_ENTRIES
+        .into_iter()
+        .filter(|&(_, kind)| kind != floor)
+        .map(|(label, floor)| (label, ContextMenuAction::FloorToggle { position, floor }));
+    structures.chain(floors).collect()
+}
+
+/// System: right-click a cell to open its context menu.
+///
+/// Right-clicks on a spawn marker are left to
+/// [`delete_spawn_marker`](super::spawns::delete_spawn_marker).
+#[allow(clippy::too_many_arguments)]
+pub fn open_editor_menu(
+    mut commands: Commands,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<EditorCamera>>,
+    ui_interactions: Query<&Interaction>,
+    markers: Query<(Entity, &Transform), With<EditorSpawnMarker>>,
+    grid: Option<Res<TileGrid<TileKind>>>,
+    floors: Option<Res<TileGrid<FloorKind>>>,
+    active_menu: Option<Res<EditorMenu>>,
+    theme: Res<UiTheme>,
+) {
+    if !mouse_buttons.just_pressed(MouseButton::Right) {
+        return;
+    }
+    if ui_interactions.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+    let (Some(grid), Some(floors)) = (grid, floors) else {
+        return;
+    };
+
+    let Ok(window) = window_query.single() else {
+        return;
+    };
+    let Some(cursor_pos) = window.cursor_position() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let Ok(ray) = camera.viewport_to_world(camera_transform, cursor_pos) else {
+        return;
+    };
+    let Some((world_pos, grid_cell)) = grid::ray_to_grid_cell(ray) else {
+        return;
+    };
+    if marker_near(&markers, world_pos).is_some() {
+        return;
+    }
+    let Some(structure) = grid.get_copy(grid_cell) else {
+        return;
+    };
+    let floor = floors.get_copy(grid_cell).unwrap_or_default();
+
+    if let Some(menu) = active_menu {
+        commands.entity(menu.0).despawn();
+    }
+
+    let buttons: Vec<Entity> = editor_menu_entries(grid_cell, structure, floor)
+        .into_iter()
+        .map(|(label, action)| {
+            build_button(&theme)
+                .with_text(label)
+                .with_event(action)
+                .build(&mut commands)
+        })
+        .collect();
+
+    let menu_root = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(4.0)),
+                row_gap: Val::Px(2.0),
+                ..default()
+            },
+            BackgroundColor(theme.background),
+            WorldSpaceOverlay {
+                world_pos: Vec3::new(grid_cell.x as f32, 0.0, grid_cell.y as f32),
+            },
+        ))
+        .add_children(&buttons)
+        .id();
+    commands.insert_resource(EditorMenu(menu_root));
+}
+
+/// System: applies the chosen [`ContextMenuAction`] to the editor grids and
+/// closes the menu.  A left-click outside the menu closes it too.
+pub fn apply_editor_menu_actions(
+    mut commands: Commands,
+    mut actions: MessageReader<ContextMenuAction>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    ui_interactions: Query<&Interaction>,
+    mut grid: Option<ResMut<TileGrid<TileKind>>>,
+    mut floors: Option<ResMut<TileGrid<FloorKind>>>,
+    mut tile_mutations: MessageWriter<TileMutated>,
+    mut floor_mutations: MessageWriter<FloorMutated>,
+    active_menu: Option<Res<EditorMenu>>,
+) {
+    let mut chosen = false;
+    for action in actions.read() {
+        chosen = true;
+        match *action {
+            ContextMenuAction::TileToggle { position, kind } => {
+                let Some(ref mut grid) = grid else { continue };
+                if grid
+                    .get_copy(position)
+                    .is_some_and(|current| current != kind)
+                {
+                    grid.set(position, kind);
+                    tile_mutations.write(TileMutated { position, kind });
+                }
+            }
+            ContextMenuAction::FloorToggle { position, floor } => {
+                let Some(ref mut floors) = floors else {
+                    continue;
+                };
+                if floors
+                    .get_copy(position)
+                    .is_some_and(|current| current != floor)
+                {
+                    floors.set(position, floor);
+                    floor_mutations.write(FloorMutated { position, floor });
+                }
+            }
+            // Items, containers and drags only exist in a running game.
+            _ => {}
+        }
+    }
+
+    let clicked_away = mouse_buttons.just_pressed(MouseButton::Left)
+        && ui_interactions.iter().all(|i| *i == Interaction::None);
+    if let Some(menu) = active_menu
+        && (chosen || clicked_away)
+    {
+        commands.entity(menu.0).despawn();
+        commands.remove_resource::<EditorMenu>();
+    }
+}
+
+/// Closes the context menu when leaving the editor.
+pub fn close_editor_menu(mut commands: Commands, active_menu: Option<Res<EditorMenu>>) {
+    if let Some(menu) = active_menu {
+        commands.entity(menu.0).despawn();
+        commands.remove_resource::<EditorMenu>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn menu_offers_every_other_structure_and_floor() {
+        let position = IVec2::new(3, 4);
+        let entries = editor_menu_entries(position, TileKind::Wall, FloorKind::Carpet);
+        let labels: Vec<&str> = entries.iter().map(|(label, _)| *label).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "Clear to Floor",
+                "Set Door",
+                "Set Ladder",
+                "Open to Space",
+                "Bare Plating"
+            ]
+        );
+        assert!(matches!(
+            entries[0].1,
+            ContextMenuAction::TileToggle {
+                position: p,
+                kind: TileKind::Floor
+            } if p == position
+        ));
+    }
+
+    #[test]
+    fn chosen_action_edits_the_grid_locally() {
+        let mut app = App::new();
+        app.add_message::<ContextMenuAction>()
+            .add_message::<TileMutated>()
+            .add_message::<FloorMutated>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .insert_resource(TileGrid::<TileKind>::new_fill(4, 4, TileKind::Floor))
+            .insert_resource(TileGrid::<FloorKind>::new(4, 4))
+            .add_systems(Update, apply_editor_menu_actions);
+
+        let position = IVec2::new(1, 2);
+        app.world_mut()
+            .write_message(ContextMenuAction::TileToggle {
+                position,
+                kind: TileKind::Wall,
+            });
+        app.world_mut()
+            .write_message(ContextMenuAction::FloorToggle {
+                position,
+                floor: FloorKind::Carpet,
+            });
+        app.update();
+
+        let world = app.world();
+        assert_eq!(
+            world.resource::<TileGrid<TileKind>>().get_copy(position),
+            Some(TileKind::Wall)
+        );
+        assert_eq!(
+            world.resource::<TileGrid<FloorKind>>().get_copy(position),
+            Some(FloorKind::Carpet)
+        );
+        assert_eq!(world.resource::<Messages<TileMutated>>().len(), 1);
+        assert_eq!(world.resource::<Messages<FloorMutated>>().len(), 1);
+    }
+}
@@ -17,7 +17,7 @@
 use bevy::prelude::*;
 use shared::config::AppConfig;
 use things::{SpawnMarker, SpawnPoint, SpawnProperties, SpawnThingVisual, ThingRegistry};
-use tiles::{GridSize, Tile, TileGrid, TileKind};
+use tiles::{FloorKind, GridSize, Tile, TileChunk, TileGrid, TileKind};
 use world::{CURRENT_MAP_VERSION, MapFile, MapLayerRegistry, from_layer_value};
 
 use super::spawns::EditorSpawnMarker;
@@ -133,10 +133,11 @@ pub fn handle_load(world: &mut World) {
 
     // Clear existing editor world: remove tile grid, despawn tiles and markers.
     world.remove_resource::<TileGrid<TileKind>>();
+    world.remove_resource::<TileGrid<FloorKind>>();
     world.remove_resource::<GridSize>();
 
     let tile_entities: Vec<Entity> = world
-        .query_filtered::<Entity, With<Tile>>()
+        .query_filtered::<Entity, Or<(With<Tile>, With<TileChunk>)>>()
         .iter(world)
         .collect();
     for entity in tile_entities {
@@ -167,6 +168,7 @@ pub fn handle_load(world: &mut World) {
                     height: 32,
                 });
                 world.insert_resource(super::default_editor_grid());
+                world.insert_resource(TileGrid::<FloorKind>::new(32, 32));
             }
             Err(e) => {
                 error!("Editor: failed to load tiles layer: {e}");
@@ -175,6 +177,7 @@ pub fn handle_load(world: &mut World) {
                     height: 32,
                 });
                 world.insert_resource(super::default_editor_grid());
+                world.insert_resource(TileGrid::<FloorKind>::new(32, 32));
             }
         }
     } else {
@@ -183,6 +186,7 @@ pub fn handle_load(world: &mut World) {
             height: 32,
         });
         world.insert_resource(super::default_editor_grid());
+        world.insert_resource(TileGrid::<FloorKind>::new(32, 32));
     }
 
     // Load the spawns layer via SpawnThingVisual to create lightweight editor
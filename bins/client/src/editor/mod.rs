@@ -6,13 +6,19 @@
 //! - Entity palette: select a template (ball, can, toolbox), click a floor tile to place a spawn marker
 //! - Spawn markers are visible overlays, not simulated entities
 //! - Right-click on a spawn marker to delete it
-//! - Save/Load `.station.ron` files via `MapLayer` trait
+//! - Right-click on a tile for the context menu, applied locally (no server)
+//! - Save/Load `.station.ron` files via `MapLayer` trait, in the format the
+//!   server hosts; the configured map is opened on entering the editor
 
 use bevy::prelude::*;
 use shared::app_state::AppState;
-use tiles::{GridSize, Tile, TileFlags, TileGrid, TileKind};
+use shared::config::AppConfig;
+use tiles::{
+    FloorKind, GridSize, Tile, TileChunk, TileFlags, TileGrid, TileHistory, TileKind, UpperDecks,
+};
 
 pub mod camera;
+pub mod context_menu;
 pub mod grid;
 pub mod io;
 pub mod painting;
@@ -41,11 +47,15 @@ impl Plugin for EditorPlugin {
                 camera::spawn_editor_camera,
                 setup_editor_tilemap,
                 palette::spawn_palette_ui,
+                open_configured_map,
             ),
         );
 
         // OnExit: clean up editor world.
-        app.add_systems(OnExit(AppState::Editor), teardown_editor_world);
+        app.add_systems(
+            OnExit(AppState::Editor),
+            (context_menu::close_editor_menu, teardown_editor_world),
+        );
 
         // Update systems gated on Editor state — split into groups to stay
         // within Bevy's tuple size limits for `.run_if()`.
@@ -80,6 +90,17 @@ impl Plugin for EditorPlugin {
                 .run_if(in_editor.clone()),
         );
 
+        // Tile context menu.  Opening runs after marker deletion so a
+        // right-click on a marker never does both.
+        app.add_systems(
+            Update,
+            (
+                context_menu::open_editor_menu.after(spawns::delete_spawn_marker),
+                context_menu::apply_editor_menu_actions,
+            )
+                .run_if(in_editor.clone()),
+        );
+
         // Save/load and exit handler.
         app.add_systems(
             Update,
@@ -103,6 +124,18 @@ fn setup_editor_tilemap(mut commands: Commands) {
         height: size,
     });
     commands.insert_resource(default_editor_grid());
+    commands.insert_resource(TileGrid::<FloorKind>::new(size, size));
+}
+
+/// Loads the configured map file on entering the editor, if it exists, so
+/// the map the server hosts can be edited without hosting it.
+fn open_configured_map(
+    config: Res<AppConfig>,
+    mut load_events: MessageWriter<io::EditorLoadEvent>,
+) {
+    if std::path::Path::new(&config.world.map_path).exists() {
+        load_events.write(io::EditorLoadEvent);
+    }
 }
 
 /// Creates a default 32×32 tile grid with perimeter walls and floor interior.
@@ -128,14 +161,18 @@ pub fn default_editor_grid() -> TileGrid<TileKind> {
 fn teardown_editor_world(
     mut commands: Commands,
     tile_entities: Query<Entity, With<Tile>>,
+    tile_chunk_entities: Query<Entity, With<TileChunk>>,
     spawn_marker_entities: Query<Entity, With<spawns::EditorSpawnMarker>>,
 ) {
     commands.remove_resource::<TileGrid<TileKind>>();
+    commands.remove_resource::<TileGrid<FloorKind>>();
     commands.remove_resource::<GridSize>();
     commands.remove_resource::<TileFlags>();
+    commands.remove_resource::<UpperDecks>();
+    commands.remove_resource::<TileHistory>();
     commands.remove_resource::<camera::EditorOrbit>();
 
-    for entity in &tile_entities {
+    for entity in tile_entities.iter().chain(&tile_chunk_entities) {
         commands.entity(entity).despawn();
     }
     for entity in &spawn_marker_entities {
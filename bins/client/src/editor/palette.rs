@@ -42,6 +42,8 @@ pub enum EditorTool {
 pub enum EditorUiEvent {
     SelectFloor,
     SelectWall,
+    SelectDoor,
+    SelectLadder,
     SelectEntity(String, u16),
     Save,
     Load,
@@ -83,6 +85,8 @@ pub fn spawn_palette_ui(mut commands: Commands, registry: Res<ThingRegistry>) {
 
     let floor_btn = spawn_palette_button(&mut commands, "Floor", EditorUiEvent::SelectFloor);
     let wall_btn = spawn_palette_button(&mut commands, "Wall", EditorUiEvent::SelectWall);
+    let door_btn = spawn_palette_button(&mut commands, "Door", EditorUiEvent::SelectDoor);
+    let ladder_btn = spawn_palette_button(&mut commands, "Ladder", EditorUiEvent::SelectLadder);
 
     // Section: Entities — built from ThingRegistry::named_templates()
     let entity_header = commands
@@ -122,7 +126,14 @@ pub fn spawn_palette_ui(mut commands: Commands, registry: Res<ThingRegistry>) {
     let save_btn = spawn_palette_button(&mut commands, "Save", EditorUiEvent::Save);
     let load_btn = spawn_palette_button(&mut commands, "Load", EditorUiEvent::Load);
 
-    let mut children = vec![tile_header, floor_btn, wall_btn, entity_header];
+    let mut children = vec![
+        tile_header,
+        floor_btn,
+        wall_btn,
+        door_btn,
+        ladder_btn,
+        entity_header,
+    ];
     children.extend(entity_btns);
     children.extend([file_header, save_btn, load_btn]);
 
@@ -230,6 +241,18 @@ pub fn process_palette_events(
                 *tool = EditorTool::Tile;
                 info!("Editor: selected Wall tile");
             }
+            EditorUiEvent::SelectDoor => {
+                selected_tile.0 = TileKind::Door { open: false };
+                selected_entity.0 = None;
+                *tool = EditorTool::Tile;
+                info!("Editor: selected Door tile");
+            }
+            EditorUiEvent::SelectLadder => {
+                selected_tile.0 = TileKind::Ladder;
+                selected_entity.0 = None;
+                *tool = EditorTool::Tile;
+                info!("Editor: selected Ladder tile");
+            }
             EditorUiEvent::SelectEntity(name, kind) => {
                 selected_entity.0 = Some(EditorEntityTemplate {
                     name: name.clone(),
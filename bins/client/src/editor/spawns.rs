@@ -93,8 +93,7 @@ pub fn place_spawn_marker(
 
 /// System: right-click on a spawn marker to delete it.
 ///
-/// Checks all editor spawn markers and deletes the one closest to the cursor
-/// grid cell (within a 0.6 world-unit threshold).
+/// Deletes the marker found by [`marker_near`] under the cursor.
 pub fn delete_spawn_marker(
     mouse_buttons: Res<ButtonInput<MouseButton>>,
     window_query: Query<&Window, With<PrimaryWindow>>,
@@ -128,10 +127,21 @@ pub fn delete_spawn_marker(
         return;
     };
 
-    // Find the closest marker within threshold.
+    if let Some(entity) = marker_near(&markers, world_pos) {
+        commands.entity(entity).despawn();
+        info!("Editor: deleted spawn marker");
+    }
+}
+
+/// Returns the spawn marker closest to `world_pos` on the XZ plane, if one
+/// lies within 0.6 world units of it.
+pub fn marker_near(
+    markers: &Query<(Entity, &Transform), With<EditorSpawnMarker>>,
+    world_pos: Vec3,
+) -> Option<Entity> {
     let threshold = 0.6;
     let mut closest: Option<(Entity, f32)> = None;
-    for (entity, transform) in &markers {
+    for (entity, transform) in markers {
         let dist = Vec2::new(
             transform.translation.x - world_pos.x,
             transform.translation.z - world_pos.z,
@@ -141,9 +151,5 @@ pub fn delete_spawn_marker(
             closest = Some((entity, dist));
         }
     }
-
-    if let Some((entity, _)) = closest {
-        commands.entity(entity).despawn();
-        info!("Editor: deleted spawn marker");
-    }
+    closest.map(|(entity, _)| entity)
 }
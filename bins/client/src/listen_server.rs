@@ -0,0 +1,231 @@
+//! Hosting from the client: the server world runs as its own headless
+//! [`App`] on a background thread, ticking independently of the frame
+//! rate, and the client joins it like any other server.
+//!
+//! [`MenuEvent::Play`] starts the thread hosting on `network.port`, open to
+//! other players.  [`MenuEvent::PlaySolo`] hosts on an in-process
+//! [`Loopback`] instead: no socket or certificate, so it plays offline, and
+//! the local player is [`ClientId::LOCAL`](network::ClientId::LOCAL).
+//! Either way every server-side rule applies.  Once the server reports it
+//! is hosting the client connects to it, trusting the certificate the
+//! server reported without asking, and leaving the game (or quitting)
+//! shuts the server down and joins the thread.
+
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, Sender, TryRecvError, channel};
+use std::thread::JoinHandle;
+
+use bevy::prelude::*;
+use main_menu::MenuEvent;
+use network::{
+    CertFingerprint, KnownServers, Loopback, NetCommand, NetworkReceive, ServerEvent, local_address,
+};
+use shared::app_state::AppState;
+use shared::config::AppConfig;
+use shared::server_app::{LoopbackHost, ShutdownSignal, add_server_plugins};
+
+/// What the server thread reports back to the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ListenServerStatus {
+    /// `endpoint` is the first address the server listens on; `None` on a
+    /// loopback.
+    Hosting {
+        endpoint: Option<SocketAddr>,
+        fingerprint: Option<CertFingerprint>,
+    },
+    Failed(String),
+}
+
+/// Server-world resource: where to report [`ListenServerStatus`].
+#[derive(Resource)]
+struct StatusSender(Sender<ListenServerStatus>);
+
+/// Client-side handle of the running listen-server thread.  Dropping it
+/// shuts the server down and waits for the thread to finish.
+#[derive(Resource)]
+pub struct ListenServer {
+    /// Set for a solo game, hosted on this loopback only.
+    loopback: Option<Loopback>,
+    thread: Option<JoinHandle<()>>,
+    status: Mutex<Receiver<ListenServerStatus>>,
+    shutdown: ShutdownSignal,
+}
+
+impl ListenServer {
+    /// Builds the server world from `config` on a new thread and runs it,
+    /// hosting on `loopback` if given.
+    fn start(config: AppConfig, loopback: Option<Loopback>) -> std::io::Result<Self> {
+        let (status_tx, status_rx) = channel();
+        let host = loopback.clone().map(LoopbackHost);
+        let shutdown = ShutdownSignal::immediate();
+        let signal = shutdown.clone();
+        let thread = std::thread::Builder::new()
+            .name("listen-server".into())
+            .spawn(move || {
+                let mut app = App::new();
+                add_server_plugins(&mut app, &config, signal);
+                if let Some(host) = host {
+                    app.insert_resource(host);
+                }
+                app.insert_resource(StatusSender(status_tx));
+                app.add_systems(NetworkReceive, report_status);
+                app.run();
+            })?;
+        Ok(Self {
+            loopback,
+            thread: Some(thread),
+            status: Mutex::new(status_rx),
+            shutdown,
+        })
+    }
+
+    /// The next status report, if any.  A thread that went away without
+    /// reporting counts as failed.
+    fn poll(&self) -> Option<ListenServerStatus> {
+        let status = self.status.lock().ok()?;
+        match status.try_recv() {
+            Ok(status) => Some(status),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) if self.thread.is_some() => Some(
+                ListenServerStatus::Failed("listen-server thread exited".into()),
+            ),
+            Err(TryRecvError::Disconnected) => None,
+        }
+    }
+}
+
+impl Drop for ListenServer {
+    fn drop(&mut self) {
+        self.shutdown.request();
+        if let Some(thread) = self.thread.take()
+            && thread.join().is_err()
+        {
+            error!("Listen-server thread panicked");
+        }
+    }
+}
+
+/// Server-world system: reports the bound port, or why hosting failed.
+/// Errors after hosting started are left to the server's own log.
+fn report_status(
+    mut events: MessageReader<ServerEvent>,
+    sender: Res<StatusSender>,
+    mut hosting: Local<bool>,
+) {
+    for event in events.read() {
+        let status = match event {
+            ServerEvent::HostingStarted {
+                endpoints,
+                fingerprint,
+                ..
+            } => {
+                *hosting = true;
+                ListenServerStatus::Hosting {
+                    endpoint: endpoints.first().copied(),
+                    fingerprint: *fingerprint,
+                }
+            }
+            ServerEvent::Error(e) if !*hosting => ListenServerStatus::Failed(e.clone()),
+            _ => continue,
+        };
+        // The client stops listening once it has left the game.
+        let _ = sender.0.send(status);
+    }
+}
+
+/// Starts a listen server when the player picks "Play" or "Play Solo",
+/// replacing (and so shutting down) any previous one.
+fn start_listen_server(
+    mut commands: Commands,
+    mut menu_events: MessageReader<MenuEvent>,
+    config: Res<AppConfig>,
+) {
+    for event in menu_events.read() {
+        let loopback = match event {
+            MenuEvent::Play => None,
+            MenuEvent::PlaySolo => Some(Loopback::new()),
+            _ => continue,
+        };
+        commands.remove_resource::<ListenServer>();
+        match ListenServer::start(config.clone(), loopback) {
+            Ok(server) => commands.insert_resource(server),
+            Err(e) => error!("Failed to start the listen-server thread: {e}"),
+        }
+    }
+}
+
+/// Connects to the listen server once it is hosting; returns to the title
+/// screen if it fails.
+fn connect_to_listen_server(
+    mut commands: Commands,
+    server: Option<Res<ListenServer>>,
+    config: Res<AppConfig>,
+    known: Res<KnownServers>,
+    mut net_commands: MessageWriter<NetCommand>,
+    mut menu_events: MessageWriter<MenuEvent>,
+) {
+    let Some(server) = server else {
+        return;
+    };
+    match server.poll() {
+        Some(ListenServerStatus::Hosting {
+            endpoint,
+            fingerprint,
+        }) => {
+            let name = config.souls.player_name.clone();
+            let spectator = config.souls.spectator;
+            let command = match (&server.loopback, endpoint) {
+                (Some(loopback), _) => {
+                    info!("Listen-server: connecting over loopback");
+                    NetCommand::ConnectLoopback {
+                        loopback: loopback.clone(),
+                        name,
+                        spectator,
+                    }
+                }
+                (None, Some(endpoint)) => {
+                    let addr = local_address(endpoint);
+                    if let Some(fingerprint) = fingerprint {
+                        known.allow(addr, fingerprint);
+                    }
+                    info!("Listen-server: connecting to {addr}");
+                    NetCommand::Connect {
+                        addr,
+                        name,
+                        spectator,
+                    }
+                }
+                (None, None) => {
+                    error!("Listen-server reported no endpoint to connect to");
+                    return;
+                }
+            };
+            net_commands.write(command);
+        }
+        Some(ListenServerStatus::Failed(reason)) => {
+            error!("Listen-server failed: {reason}");
+            commands.remove_resource::<ListenServer>();
+            menu_events.write(MenuEvent::Title);
+        }
+        None => {}
+    }
+}
+
+/// Shuts the listen server down when the client is back in the main menu.
+fn stop_listen_server(mut commands: Commands) {
+    commands.remove_resource::<ListenServer>();
+}
+
+/// Runs hosted games on a background listen-server thread.
+pub struct ListenServerPlugin;
+
+impl Plugin for ListenServerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            NetworkReceive,
+            (start_listen_server, connect_to_listen_server).chain(),
+        );
+        app.add_systems(OnEnter(AppState::MainMenu), stop_listen_server);
+    }
+}
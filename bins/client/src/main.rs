@@ -1,22 +1,20 @@
-use std::net::SocketAddr;
-
 use bevy::log::LogPlugin;
 use bevy::prelude::*;
 use editor::EditorPlugin;
-use input::InputPlugin;
+use input::{InputMap, InputPlugin};
 use interactions::{ContextMenuAction, InteractionsPlugin};
 use items::{InteractionRange, ItemsPlugin};
 use main_menu::{MainMenuConfig, MainMenuPlugin, MenuEvent};
-use network::{Headless, NetCommand, NetworkPlugin, NetworkReceive, Server, ServerEvent};
+use network::{KnownServers, NetworkPlugin};
 use physics::{PhysicsDebugPlugin, PhysicsPlugin};
 use shared::app_state::AppState;
-use shared::config::AppConfig;
-use things::ThingsPlugin;
+use things::{ClientAuthoritySettings, ThingsPlugin};
 use tiles::TilesPlugin;
 use ui::UiPlugin;
-use world::{MapPath, WorldPlugin};
+use world::WorldPlugin;
 
 mod editor;
+mod listen_server;
 
 fn main() {
     let start_in_editor = std::env::args().any(|a| a == "--editor");
@@ -36,9 +34,12 @@ fn main() {
             .with_event::<MenuEvent>()
             .with_event::<ContextMenuAction>(),
     )
+    .insert_resource(app_config.network.cert_verification)
+    .insert_resource(known_servers(&app_config))
     .insert_resource(MainMenuConfig {
-        port: app_config.network.port,
+        join_addr: (app_config.network.join_address, app_config.network.port).into(),
         player_name: app_config.souls.player_name.clone(),
+        spectator: app_config.souls.spectator,
     })
     .add_plugins(MainMenuPlugin {
         state: AppState::MainMenu,
@@ -68,21 +69,61 @@ fn main() {
         app_config.atmospherics.pressure_force_scale,
         app_config.atmospherics.diffusion_rate,
     ))
+    .add_plugins(lighting::LightingPlugin::new(
+        AppState::InGame,
+        app_config.lighting.ambient,
+    ))
+    .add_plugins(power::PowerPlugin::<AppState>::in_state(AppState::InGame))
     .add_plugins(creatures::CreaturesPlugin)
+    .add_plugins(projectiles::ProjectilesPlugin)
+    .add_plugins(ai::AiPlugin::<AppState>::in_state(AppState::InGame))
     .add_plugins(souls::SoulsPlugin)
     .add_plugins(player::PlayerPlugin)
     .add_plugins(camera::CameraPlugin::<AppState>::in_state(AppState::InGame))
     .add_plugins(EditorPlugin)
-    .add_plugins(shared::templates::TemplatesPlugin)
+    .add_plugins(shared::templates::TemplatesPlugin {
+        template_dir: app_config.things.template_dir.clone(),
+    })
     .add_plugins(InputPlugin::<AppState>::in_state(AppState::InGame))
     .add_plugins(InteractionsPlugin::<AppState>::in_state(AppState::InGame))
     .add_plugins(ItemsPlugin)
+    .add_plugins(animation::AnimationPlugin)
+    .add_plugins(crafting::CraftingPlugin)
+    .add_plugins(shared::recipes::RecipesPlugin)
+    .add_plugins(research::ResearchPlugin)
+    .add_plugins(shared::tech::TechTreePlugin)
+    .add_plugins(access::AccessPlugin)
+    .add_plugins(roles::RolesPlugin)
+    .add_plugins(shared::role_defs::RoleDefsPlugin {
+        roles_file: app_config.souls.roles_file.clone(),
+    })
+    .add_plugins(events::EventsPlugin)
+    .add_plugins(shared::event_defs::EventDefsPlugin {
+        events_file: app_config.events.events_file.clone(),
+    })
+    .add_plugins(gamemode::VotePlugin::default())
+    .insert_resource(roles::PreferredRole(
+        Some(app_config.souls.role.clone()).filter(|role| !role.is_empty()),
+    ))
+    .add_plugins(shared::reload::HotReloadPlugin { watch: true })
+    .add_plugins(shared::diagnostics::DebugDiagnosticsPlugin {
+        panel: true,
+        log_interval: None,
+    })
     .insert_resource(InteractionRange(app_config.items.interaction_range))
-    .add_systems(NetworkReceive, listen_server_self_connect)
-    .add_systems(
-        OnEnter(AppState::Loading),
-        ensure_map_path_on_host.before(world::loader::load_map),
-    );
+    .insert_resource(creatures::MovementSettings::from(&app_config.movement))
+    .insert_resource(InputMap::with_overrides(&app_config.input.bindings))
+    .insert_resource(camera::CameraConfig {
+        follow_speed: app_config.camera.follow_speed,
+        offset: Vec3::from_array(app_config.camera.offset),
+        ..default()
+    })
+    .insert_resource(ClientAuthoritySettings {
+        enabled: app_config.physics.client_authority,
+        radius: app_config.physics.authority_radius,
+    })
+    .insert_resource(Time::<Fixed>::from_hz(app_config.simulation.tick_rate))
+    .add_plugins(listen_server::ListenServerPlugin);
 
     if start_in_editor {
         app.insert_state(AppState::Editor);
@@ -93,37 +134,15 @@ fn main() {
     app.run();
 }
 
-/// On a listen-server, inserts [`MapPath`] from config so [`world::loader::load_map`]
-/// can find the map file.  Runs before `load_map` on `OnEnter(Loading)`.
-///
-/// Pure clients (no [`Server`] resource) skip this — they receive world state
-/// over the network rather than loading from disk.
-fn ensure_map_path_on_host(world: &mut World) {
-    if world.contains_resource::<Server>() && !world.contains_resource::<MapPath>() {
-        let config = world.resource::<AppConfig>();
-        let map_path = config.world.map_path.clone();
-        world.insert_resource(MapPath::new(map_path));
-    }
-}
-
-/// On a listen-server (non-headless), automatically connects to self when hosting starts.
-fn listen_server_self_connect(
-    mut messages: MessageReader<ServerEvent>,
-    mut net_commands: MessageWriter<NetCommand>,
-    config: Res<AppConfig>,
-    headless: Option<Res<Headless>>,
-) {
-    if headless.is_some() {
-        return;
-    }
-    for event in messages.read() {
-        if let ServerEvent::HostingStarted { port } = event {
-            let addr: SocketAddr = ([127, 0, 0, 1], *port).into();
-            info!("Listen-server: connecting to self at {addr}");
-            net_commands.write(NetCommand::Connect {
-                addr,
-                name: config.souls.player_name.clone(),
-            });
-        }
+/// Server certificates pinned at `network.known_servers_path`, or kept in
+/// memory when it is empty or unreadable.
+fn known_servers(config: &shared::config::AppConfig) -> KnownServers {
+    let path = &config.network.known_servers_path;
+    if path.is_empty() {
+        return KnownServers::default();
     }
+    KnownServers::load(path).unwrap_or_else(|e| {
+        warn!("Failed to read known servers from {path}: {e}");
+        KnownServers::default()
+    })
 }
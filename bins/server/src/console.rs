@@ -0,0 +1,403 @@
+//! Admin commands typed into the dedicated server's terminal.
+//!
+//! Each line read from stdin is one command:
+//!
+//! - `reload`: re-read the thing templates and the config,
+//! - `reload templates` / `reload config`: re-read only one of them,
+//! - `manifest export <net id> <file>`: save a container and its contents
+//!   as a cargo manifest,
+//! - `manifest import <file> <x> <z>`: spawn the cargo of a manifest on a
+//!   cell,
+//! - `restart`: end the round and reset the station,
+//! - `map <file>`: the same, loading the things of another map,
+//! - `kick <client id>`: disconnect a player,
+//! - `list things [kind=<kind>] [within <tiles> of (<x>, <z>)]`: log the
+//!   things of a kind, near a cell, or both,
+//! - `despawn netid <net id>`: remove a thing,
+//! - `tune`: log every tunable and its value,
+//! - `tune <name> <value>`: change a tunable,
+//! - `zone rename <zone id> <name>`: rename a zone,
+//! - `rollback <n> [client id]`: revert the `n` most recent tile edits, or
+//!   only those of one player,
+//! - `help`: list the commands.
+//!
+//! Reloads are carried out by [`shared::reload`], manifests by
+//! [`items::ManifestRequest`], restarts and map changes by
+//! [`gamemode::RoundRequest`], kicks by [`NetServerSender::kick`], thing
+//! queries by [`things::ThingQuery`], tunables by
+//! [`network::TunableRequest`], zone renames by
+//! [`tiles::ZoneRenameRequest`] and rollbacks by
+//! [`tiles::TileRollbackRequest`].
+
+use std::io::BufRead;
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use bevy::prelude::*;
+use gamemode::RoundRequest;
+use items::ManifestRequest;
+use network::{ClientId, NetId, NetServerSender, TunableRequest};
+use shared::reload::ReloadRequest;
+use things::{ThingFilter, ThingQuery};
+use tiles::{TileRollbackRequest, ZoneId, ZoneRenameRequest};
+
+const HELP: &str = "commands: reload [templates|config], \
+    manifest export <net id> <file>, manifest import <file> <x> <z>, \
+    restart, map <file>, kick <client id>, \
+    list things [kind=<kind>] [within <tiles> of (<x>, <z>)], despawn netid <net id>, \
+    tune [<name> <value>], zone rename <zone id> <name>, \
+    rollback <n> [client id], help";
+
+/// Height above the floor at which imported cargo appears.
+const IMPORT_HEIGHT: f32 = 1.0;
+
+/// Reads admin commands from stdin.
+pub struct ConsolePlugin;
+
+/// Lines read from stdin by the console thread.
+#[derive(Resource)]
+struct ConsoleInput(Mutex<Receiver<String>>);
+
+/// A parsed admin command.
+#[derive(Debug, PartialEq)]
+pub enum AdminCommand {
+    Reload(Vec<ReloadRequest>),
+    Manifest(ManifestRequest),
+    Round(RoundRequest),
+    Kick(ClientId),
+    Things(ThingQuery),
+    Tune(TunableRequest),
+    RenameZone(ZoneRenameRequest),
+    Rollback(TileRollbackRequest),
+    Help,
+}
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = channel();
+        let spawned = std::thread::Builder::new()
+            .name("admin-console".into())
+            .spawn(move || {
+                for line in std::io::stdin().lock().lines() {
+                    let Ok(line) = line else { break };
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            error!("Console: failed to start the stdin reader: {e}");
+            return;
+        }
+        app.insert_resource(ConsoleInput(Mutex::new(receiver)));
+        app.add_systems(Update, run_console_commands);
+    }
+}
+
+/// Parses one line of input; `Err` explains what was wrong with it.
+pub fn parse_command(line: &str) -> Result<AdminCommand, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        ["reload"] => Ok(AdminCommand::Reload(vec![
+            ReloadRequest::Templates,
+            ReloadRequest::Config,
+        ])),
+        ["reload", "templates"] => Ok(AdminCommand::Reload(vec![ReloadRequest::Templates])),
+        ["reload", "config"] => Ok(AdminCommand::Reload(vec![ReloadRequest::Config])),
+        ["manifest", "export", net_id, path] => {
+            let net_id = net_id
+                .parse()
+                .map_err(|_| format!("\"{net_id}\" is not a net id"))?;
+            Ok(AdminCommand::Manifest(ManifestRequest::Export {
+                container: NetId(net_id),
+                path: path.into(),
+            }))
+        }
+        ["manifest", "import", path, x, z] => {
+            let (Ok(x), Ok(z)) = (x.parse::<i32>(), z.parse::<i32>()) else {
+                return Err(format!("\"{x} {z}\" is not a cell"));
+            };
+            Ok(AdminCommand::Manifest(ManifestRequest::Import {
+                path: path.into(),
+                position: Vec3::new(x as f32, IMPORT_HEIGHT, z as f32),
+            }))
+        }
+        ["restart"] => Ok(AdminCommand::Round(RoundRequest::Restart {
+            reason: "An admin restarted the round".into(),
+        })),
+        ["map", path] => Ok(AdminCommand::Round(RoundRequest::ChangeMap {
+            path: path.to_string(),
+        })),
+        ["kick", client] => client
+            .parse()
+            .map(|id| AdminCommand::Kick(ClientId(id)))
+            .map_err(|_| format!("\"{client}\" is not a client id")),
+        ["list", "things", filter @ ..] => parse_thing_filter(&filter.join(" "))
+            .map(|filter| AdminCommand::Things(ThingQuery::List(filter))),
+        ["despawn", "netid", net_id] => net_id
+            .parse()
+            .map(|id| AdminCommand::Things(ThingQuery::Despawn(NetId(id))))
+            .map_err(|_| format!("\"{net_id}\" is not a net id")),
+        ["tune"] => Ok(AdminCommand::Tune(TunableRequest::List)),
+        ["tune", name, value] => value
+            .parse()
+            .map(|value| {
+                AdminCommand::Tune(TunableRequest::Set {
+                    name: name.to_string(),
+                    value,
+                })
+            })
+            .map_err(|_| format!("\"{value}\" is not a number")),
+        ["zone", "rename", zone, name @ ..] if !name.is_empty() => zone
+            .parse()
+            .map(|id| {
+                AdminCommand::RenameZone(ZoneRenameRequest {
+                    zone: ZoneId(id),
+                    name: name.join(" "),
+                })
+            })
+            .map_err(|_| format!("\"{zone}\" is not a zone id")),
+        ["rollback", count, client @ ..] if client.len() <= 1 => {
+            let count = count
+                .parse()
+                .map_err(|_| format!("\"{count}\" is not a number of edits"))?;
+            let by = match client {
+                [client] => Some(ClientId(
+                    client
+                        .parse()
+                        .map_err(|_| format!("\"{client}\" is not a client id"))?,
+                )),
+                _ => None,
+            };
+            Ok(AdminCommand::Rollback(TileRollbackRequest { count, by }))
+        }
+        ["help"] => Ok(AdminCommand::Help),
+        _ => Err(format!("unknown command \"{}\"; {HELP}", line.trim())),
+    }
+}
+
+/// Parses the filter of `list things`, e.g. `kind=2 within 10 of (4, 7)`.
+fn parse_thing_filter(text: &str) -> Result<ThingFilter, String> {
+    let text = text.replace(['(', ')', ','], " ");
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut filter = ThingFilter::default();
+    let mut rest = words.as_slice();
+    while !rest.is_empty() {
+        rest = match rest {
+            [kind, tail @ ..] if kind.starts_with("kind=") => {
+                let kind = &kind["kind=".len()..];
+                filter.kind = Some(
+                    kind.parse()
+                        .map_err(|_| format!("\"{kind}\" is not a thing kind"))?,
+                );
+                tail
+            }
+            ["within", radius, "of", x, z, tail @ ..] => {
+                let (Ok(radius), Ok(x), Ok(z)) =
+                    (radius.parse::<f32>(), x.parse::<f32>(), z.parse::<f32>())
+                else {
+                    return Err(format!(
+                        "\"within {radius} of ({x}, {z})\" is not a distance from a cell"
+                    ));
+                };
+                filter.near = Some((Vec2::new(x, z), radius));
+                tail
+            }
+            _ => return Err(format!("unknown filter \"{}\"", rest.join(" "))),
+        };
+    }
+    Ok(filter)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_console_commands(
+    input: Res<ConsoleInput>,
+    mut reloads: MessageWriter<ReloadRequest>,
+    mut manifests: MessageWriter<ManifestRequest>,
+    mut rounds: MessageWriter<RoundRequest>,
+    mut thing_queries: MessageWriter<ThingQuery>,
+    mut tunables: MessageWriter<TunableRequest>,
+    mut zone_renames: MessageWriter<ZoneRenameRequest>,
+    mut rollbacks: MessageWriter<TileRollbackRequest>,
+    sender: Option<Res<NetServerSender>>,
+) {
+    let Ok(receiver) = input.0.lock() else {
+        return;
+    };
+    loop {
+        let line = match receiver.try_recv() {
+            Ok(line) => line,
+            Err(TryRecvError::Empty | TryRecvError::Disconnected) => return,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_command(&line) {
+            Ok(AdminCommand::Reload(requests)) => {
+                info!("Console: {}", line.trim());
+                reloads.write_batch(requests);
+            }
+            Ok(AdminCommand::Manifest(request)) => {
+                info!("Console: {}", line.trim());
+                manifests.write(request);
+            }
+            Ok(AdminCommand::Round(request)) => {
+                info!("Console: {}", line.trim());
+                rounds.write(request);
+            }
+            Ok(AdminCommand::Kick(client)) => {
+                info!("Console: {}", line.trim());
+                match &sender {
+                    Some(sender) => sender.kick(client, "Kicked by an admin"),
+                    None => warn!("Console: not hosting"),
+                }
+            }
+            Ok(AdminCommand::Things(query)) => {
+                info!("Console: {}", line.trim());
+                thing_queries.write(query);
+            }
+            Ok(AdminCommand::Tune(request)) => {
+                info!("Console: {}", line.trim());
+                tunables.write(request);
+            }
+            Ok(AdminCommand::RenameZone(request)) => {
+                info!("Console: {}", line.trim());
+                zone_renames.write(request);
+            }
+            Ok(AdminCommand::Rollback(request)) => {
+                info!("Console: {}", line.trim());
+                rollbacks.write(request);
+            }
+            Ok(AdminCommand::Help) => info!("Console: {HELP}"),
+            Err(e) => warn!("Console: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command_understands_reloads() {
+        assert_eq!(
+            parse_command("  reload config "),
+            Ok(AdminCommand::Reload(vec![ReloadRequest::Config]))
+        );
+        assert_eq!(
+            parse_command("reload"),
+            Ok(AdminCommand::Reload(vec![
+                ReloadRequest::Templates,
+                ReloadRequest::Config
+            ]))
+        );
+        assert!(parse_command("reload everything").is_err());
+    }
+
+    #[test]
+    fn parse_command_understands_manifests() {
+        assert_eq!(
+            parse_command("manifest export 42 cargo.ron"),
+            Ok(AdminCommand::Manifest(ManifestRequest::Export {
+                container: NetId(42),
+                path: "cargo.ron".into(),
+            }))
+        );
+        assert_eq!(
+            parse_command("manifest import cargo.ron 4 -2"),
+            Ok(AdminCommand::Manifest(ManifestRequest::Import {
+                path: "cargo.ron".into(),
+                position: Vec3::new(4.0, IMPORT_HEIGHT, -2.0),
+            }))
+        );
+        assert!(parse_command("manifest export crate cargo.ron").is_err());
+        assert!(parse_command("manifest import cargo.ron 4").is_err());
+    }
+
+    #[test]
+    fn parse_command_understands_round_commands() {
+        assert_eq!(
+            parse_command("map maps/cargo.station.ron"),
+            Ok(AdminCommand::Round(RoundRequest::ChangeMap {
+                path: "maps/cargo.station.ron".into(),
+            }))
+        );
+        assert_eq!(parse_command("kick 3"), Ok(AdminCommand::Kick(ClientId(3))));
+        assert!(parse_command("kick Ann").is_err());
+    }
+
+    #[test]
+    fn parse_command_understands_thing_queries() {
+        assert_eq!(
+            parse_command("list things kind=2 within 10 of (4,-7)"),
+            Ok(AdminCommand::Things(ThingQuery::List(ThingFilter {
+                kind: Some(2),
+                near: Some((Vec2::new(4.0, -7.0), 10.0)),
+            })))
+        );
+        assert_eq!(
+            parse_command("list things"),
+            Ok(AdminCommand::Things(ThingQuery::List(
+                ThingFilter::default()
+            )))
+        );
+        assert_eq!(
+            parse_command("despawn netid 12"),
+            Ok(AdminCommand::Things(ThingQuery::Despawn(NetId(12))))
+        );
+        assert!(parse_command("list things kind=crate").is_err());
+        assert!(parse_command("list things within 10 of (4)").is_err());
+        assert!(parse_command("despawn netid twelve").is_err());
+    }
+
+    #[test]
+    fn parse_command_understands_tunables() {
+        assert_eq!(
+            parse_command("tune"),
+            Ok(AdminCommand::Tune(TunableRequest::List))
+        );
+        assert_eq!(
+            parse_command("tune items.interaction_range 2.5"),
+            Ok(AdminCommand::Tune(TunableRequest::Set {
+                name: "items.interaction_range".into(),
+                value: 2.5,
+            }))
+        );
+        assert!(parse_command("tune items.interaction_range far").is_err());
+    }
+
+    #[test]
+    fn parse_command_understands_zone_renames() {
+        assert_eq!(
+            parse_command("zone rename 12 Cargo Bay"),
+            Ok(AdminCommand::RenameZone(ZoneRenameRequest {
+                zone: ZoneId(12),
+                name: "Cargo Bay".into(),
+            }))
+        );
+        assert!(parse_command("zone rename 12").is_err());
+        assert!(parse_command("zone rename medbay Medbay").is_err());
+    }
+
+    #[test]
+    fn parse_command_understands_rollbacks() {
+        assert_eq!(
+            parse_command("rollback 5"),
+            Ok(AdminCommand::Rollback(TileRollbackRequest {
+                count: 5,
+                by: None,
+            }))
+        );
+        assert_eq!(
+            parse_command("rollback 2 7"),
+            Ok(AdminCommand::Rollback(TileRollbackRequest {
+                count: 2,
+                by: Some(ClientId(7)),
+            }))
+        );
+        assert!(parse_command("rollback").is_err());
+        assert!(parse_command("rollback all").is_err());
+        assert!(parse_command("rollback 2 host").is_err());
+        assert!(parse_command("rollback 2 7 8").is_err());
+    }
+}
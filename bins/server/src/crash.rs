@@ -0,0 +1,62 @@
+//! Saving the world when the server panics.
+//!
+//! [`install_panic_hook`] logs panics through the app's logger, so they end
+//! up next to the rest of the server's output.  [`panic_safe_runner`] runs
+//! the app like `MinimalPlugins`' loop does, but catches a panicking frame,
+//! saves what it can with [`world::save_world_to`] and then lets the panic
+//! carry on.  The frame that panicked is lost and the world may be halfway
+//! through it, so the save goes to a separate `<save>.crash` file (see
+//! [`world::crash_path`]) and the last good save and its backups are left
+//! alone.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use bevy::app::PluginsState;
+use bevy::prelude::*;
+
+/// Logs panics as errors before the default hook reports them.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        error!("Server panicked: {info}");
+        default_hook(info);
+    }));
+}
+
+/// App runner that saves the world if a frame panics, then resumes the
+/// panic.
+pub fn panic_safe_runner(mut app: App) -> AppExit {
+    if app.plugins_state() != PluginsState::Cleaned {
+        while app.plugins_state() == PluginsState::Adding {
+            bevy::tasks::tick_global_task_pools_on_main_thread();
+        }
+        app.finish();
+        app.cleanup();
+    }
+    loop {
+        if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| app.update())) {
+            save_after_panic(app.world_mut());
+            panic::resume_unwind(panic);
+        }
+        if let Some(exit) = app.should_exit() {
+            return exit;
+        }
+    }
+}
+
+/// Saves the world left behind by a panicked frame to the crash file of the
+/// save path, if one is set.  A layer may find the world too broken to save
+/// and panic in turn; that is logged and the save given up.
+fn save_after_panic(world: &mut World) {
+    let Some(save_path) = world.get_resource::<world::SavePath>() else {
+        return;
+    };
+    let path = world::crash_path(Path::new(&save_path.0));
+    error!("Saving the world after a panic...");
+    match panic::catch_unwind(AssertUnwindSafe(|| world::save_world_to(world, &path, 0))) {
+        Ok(Ok(())) => error!("World saved to {path:?} after a panic; the last save is kept"),
+        Ok(Err(e)) => error!("Failed to save the world after a panic: {e}"),
+        Err(_) => error!("Saving the world panicked too; the last save is kept"),
+    }
+}
@@ -0,0 +1,251 @@
+//! Opt-in Prometheus metrics endpoint for dedicated servers.
+//!
+//! With `metrics.enabled` set, [`MetricsPlugin`] listens on
+//! `127.0.0.1:<metrics.port>` and answers every HTTP request with the
+//! current figures in the Prometheus text format:
+//!
+//! - entity and thing counts,
+//! - simulation tick time (the debug diagnostics'
+//!   [`FIXED_STEP_TIME`]) and gas-grid step time
+//!   ([`atmospherics::GAS_STEP_TIME`]), smoothed by Bevy's diagnostics,
+//! - bytes sent per network tick on each server→client stream and the frames
+//!   queued behind its budget,
+//! - connected clients.
+//!
+//! The figures are rendered on the game thread every [`REFRESH_INTERVAL`]
+//! and served from a dedicated thread, so a slow scraper never stalls the
+//! simulation.
+
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, DiagnosticsStore};
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use network::{ClientId, ServerEvent, StreamRegistry, StreamSaturation};
+use shared::diagnostics::FIXED_STEP_TIME;
+use things::Thing;
+
+/// How long a scrape may take to send its request before it is dropped.
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// How often the served figures are rendered again.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Serves metrics on `127.0.0.1:port`.
+pub struct MetricsPlugin {
+    pub port: u16,
+}
+
+/// The latest rendered figures, shared with the thread serving them.
+#[derive(Resource, Clone, Default)]
+struct MetricsBody(Arc<Mutex<String>>);
+
+/// Clients connected to the server.
+#[derive(Resource, Default)]
+struct ConnectedClients(HashSet<ClientId>);
+
+/// Figures reported by one scrape.
+#[derive(Debug, Default)]
+pub struct MetricsSnapshot {
+    pub entities: usize,
+    pub things: usize,
+    pub clients: usize,
+    pub tick_ms: Option<f64>,
+    pub gas_step_ms: Option<f64>,
+    pub streams: Vec<(&'static str, StreamSaturation)>,
+}
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConnectedClients>();
+        app.add_systems(
+            Update,
+            (
+                track_clients,
+                publish_metrics
+                    .run_if(resource_exists::<MetricsBody>.and(on_timer(REFRESH_INTERVAL))),
+            )
+                .chain(),
+        );
+
+        let body = MetricsBody::default();
+        let served = body.clone();
+        let server = TcpListener::bind((Ipv4Addr::LOCALHOST, self.port)).and_then(|listener| {
+            std::thread::Builder::new()
+                .name("metrics".into())
+                .spawn(move || serve_metrics(listener, served))
+        });
+        match server {
+            Ok(_) => {
+                info!("Metrics: serving on http://127.0.0.1:{}/metrics", self.port);
+                app.insert_resource(body);
+            }
+            Err(e) => error!("Metrics: failed to listen on port {}: {e}", self.port),
+        }
+    }
+}
+
+/// Renders `snapshot` in the Prometheus text exposition format.
+pub fn render_metrics(snapshot: &MetricsSnapshot) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, samples: &[(String, f64)]| {
+        out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} gauge\n"));
+        for (labels, value) in samples {
+            out.push_str(&format!("{name}{labels} {value}\n"));
+        }
+    };
+    let plain = |value: f64| vec![(String::new(), value)];
+    let optional = |value: Option<f64>| value.map_or_else(Vec::new, plain);
+
+    gauge(
+        "geostationary_entities",
+        "Entities in the server world.",
+        &plain(snapshot.entities as f64),
+    );
+    gauge(
+        "geostationary_things",
+        "Things (creatures, items, machines) in the server world.",
+        &plain(snapshot.things as f64),
+    );
+    gauge(
+        "geostationary_connected_clients",
+        "Clients connected to the server.",
+        &plain(snapshot.clients as f64),
+    );
+    gauge(
+        "geostationary_tick_time_ms",
+        "Smoothed time of one simulation tick and physics step, in milliseconds.",
+        &optional(snapshot.tick_ms),
+    );
+    gauge(
+        "geostationary_gas_step_time_ms",
+        "Smoothed time of one gas grid step, in milliseconds.",
+        &optional(snapshot.gas_step_ms),
+    );
+    let per_stream = |value: fn(&StreamSaturation) -> f64| {
+        snapshot
+            .streams
+            .iter()
+            .map(|(stream, saturation)| (format!("{{stream=\"{stream}\"}}"), value(saturation)))
+            .collect::<Vec<_>>()
+    };
+    gauge(
+        "geostationary_stream_sent_bytes",
+        "Bytes sent on a stream to its busiest client over the last network tick.",
+        &per_stream(|s| s.sent_bytes as f64),
+    );
+    gauge(
+        "geostationary_stream_queued_frames",
+        "Frames queued behind a stream's budget across all clients.",
+        &per_stream(|s| s.queued_frames as f64),
+    );
+    out
+}
+
+fn track_clients(mut events: MessageReader<ServerEvent>, mut clients: ResMut<ConnectedClients>) {
+    for event in events.read() {
+        match event {
+            ServerEvent::ClientConnected { id, .. } => {
+                clients.0.insert(*id);
+            }
+            ServerEvent::ClientDisconnected { id } => {
+                clients.0.remove(id);
+            }
+            ServerEvent::HostingStopped => clients.0.clear(),
+            _ => {}
+        }
+    }
+}
+
+/// Renders the current [`MetricsSnapshot`] into the [`MetricsBody`] served
+/// to scrapers.
+fn publish_metrics(
+    body: Res<MetricsBody>,
+    entities: Query<()>,
+    things: Query<(), With<Thing>>,
+    clients: Res<ConnectedClients>,
+    diagnostics: Res<DiagnosticsStore>,
+    registry: Res<StreamRegistry>,
+) {
+    let smoothed = |path: &DiagnosticPath| diagnostics.get(path).and_then(Diagnostic::smoothed);
+    let text = render_metrics(&MetricsSnapshot {
+        entities: entities.iter().count(),
+        things: things.iter().count(),
+        clients: clients.0.len(),
+        tick_ms: smoothed(&FIXED_STEP_TIME),
+        gas_step_ms: smoothed(&atmospherics::GAS_STEP_TIME),
+        streams: registry
+            .saturations()
+            .into_iter()
+            .map(|(_, name, saturation)| (name, saturation))
+            .collect(),
+    });
+    *body.0.lock().unwrap_or_else(|e| e.into_inner()) = text;
+}
+
+/// Body of the metrics thread: answers every scrape with the latest
+/// [`MetricsBody`], one connection at a time.
+fn serve_metrics(listener: TcpListener, body: MetricsBody) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Metrics: failed to accept a connection: {e}");
+                continue;
+            }
+        };
+        let text = body.0.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Err(e) = respond(stream, &text) {
+            debug!("Metrics: failed to answer a scrape: {e}");
+        }
+    }
+}
+
+/// Reads the request head and writes `body` as a plain-text response.
+fn respond(mut stream: TcpStream, body: &str) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request)?;
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_metrics_writes_prometheus_gauges() {
+        let text = render_metrics(&MetricsSnapshot {
+            entities: 120,
+            things: 14,
+            clients: 2,
+            tick_ms: Some(1.5),
+            gas_step_ms: None,
+            streams: vec![(
+                "tiles",
+                StreamSaturation {
+                    sent_bytes: 2048,
+                    queued_frames: 3,
+                    load: 0.0,
+                    paced_clients: 0,
+                },
+            )],
+        });
+
+        assert!(text.contains("# TYPE geostationary_entities gauge\ngeostationary_entities 120\n"));
+        assert!(text.contains("geostationary_connected_clients 2\n"));
+        assert!(text.contains("geostationary_tick_time_ms 1.5\n"));
+        // Unmeasured figures are declared but have no sample.
+        assert!(!text.contains("\ngeostationary_gas_step_time_ms "));
+        assert!(text.contains("geostationary_stream_sent_bytes{stream=\"tiles\"} 2048\n"));
+        assert!(text.contains("geostationary_stream_queued_frames{stream=\"tiles\"} 3\n"));
+    }
+}
@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
 use bevy::log::{Level, warn};
 use bevy::prelude::{Resource, default};
 use serde::Deserialize;
@@ -6,15 +10,29 @@ use ::config::{Config, Environment, File, FileFormat};
 
 const CONFIG_BASENAME: &str = "config";
 
+/// Files the config is read from, relative to the working directory.
+pub const CONFIG_FILES: [&str; 2] = ["config.toml", "config.ron"];
+
 #[derive(Debug, Clone, Deserialize, Resource)]
 pub struct AppConfig {
     pub network: NetworkConfig,
     pub window: WindowConfig,
     pub debug: DebugConfig,
     pub atmospherics: AtmosphericsConfig,
+    pub lighting: LightingConfig,
     pub souls: SoulsConfig,
     pub items: ItemsConfig,
+    pub things: ThingsConfig,
+    pub events: EventsConfig,
+    pub gamemode: GameModeConfig,
     pub world: WorldConfig,
+    pub physics: PhysicsConfig,
+    pub simulation: SimulationConfig,
+    pub metrics: MetricsConfig,
+    pub camera: CameraSettings,
+    pub movement: MovementConfig,
+    #[serde(default)]
+    pub input: InputConfig,
 }
 
 impl From<&AppConfig> for bevy::prelude::WindowPlugin {
@@ -32,6 +50,7 @@ impl From<&AppConfig> for bevy::log::LogPlugin {
     fn from(config: &AppConfig) -> Self {
         Self {
             level: parse_log_level(&config.debug.log_level),
+            custom_layer: crate::diagnostics::system_span_layer,
             ..default()
         }
     }
@@ -49,28 +68,84 @@ fn parse_log_level(s: &str) -> Level {
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
-            network: NetworkConfig { port: 7777 },
+            network: NetworkConfig {
+                port: 7777,
+                bind_addresses: Vec::new(),
+                join_address: Ipv4Addr::LOCALHOST.into(),
+                shutdown_delay: 5.0,
+                cert_path: String::new(),
+                key_path: String::new(),
+                cert_verification: network::CertVerification::TrustOnFirstUse,
+                known_servers_path: "known_servers.txt".to_string(),
+                max_client_frame_bytes: 64 << 10,
+                max_decode_failures: 20,
+                decode_failure_window: 10.0,
+            },
             window: WindowConfig {
                 title: "Geostationary".to_string(),
             },
             debug: DebugConfig {
                 physics_debug: false,
                 log_level: "info".to_string(),
+                diagnostics_log_seconds: 60.0,
             },
             atmospherics: AtmosphericsConfig {
                 standard_pressure: 101.325,
                 pressure_force_scale: 50.0,
                 diffusion_rate: atmospherics::DEFAULT_DIFFUSION_RATE,
             },
+            lighting: LightingConfig {
+                ambient: lighting::DEFAULT_AMBIENT_LIGHT,
+            },
             souls: SoulsConfig {
                 player_name: "Player".to_string(),
+                spectator: false,
+                role: String::new(),
+                roles_file: "assets/roles.ron".to_string(),
+                spawn_policy: souls::SpawnPolicy::RoundRobin,
+                full_map_minutes: souls::FULL_RELEVANCY_DURATION.as_secs_f32() / 60.0,
             },
             items: ItemsConfig {
                 interaction_range: 2.0,
             },
+            things: ThingsConfig {
+                template_dir: "assets/things".to_string(),
+            },
+            events: EventsConfig {
+                events_file: "assets/events.ron".to_string(),
+            },
+            gamemode: GameModeConfig {
+                mode: "sandbox".to_string(),
+                round_minutes: 30.0,
+                lobby_seconds: 0.0,
+                restart_seconds: 20.0,
+                vote_seconds: 30.0,
+                vote_quorum: 0.5,
+                vote_maps: Vec::new(),
+            },
             world: WorldConfig {
                 map_path: "assets/maps/default.station.ron".to_string(),
+                save_path: String::new(),
+                autosave_minutes: 5.0,
+                save_backups: 3,
             },
+            physics: PhysicsConfig {
+                client_authority: false,
+                authority_radius: 3.0,
+            },
+            simulation: SimulationConfig {
+                tick_rate: network::DEFAULT_TICK_RATE,
+            },
+            metrics: MetricsConfig {
+                enabled: false,
+                port: 9464,
+            },
+            camera: CameraSettings {
+                follow_speed: 2.0,
+                offset: [0.0, 10.0, 8.0],
+            },
+            movement: MovementConfig::from(&creatures::MovementSettings::default()),
+            input: InputConfig::default(),
         }
     }
 }
@@ -78,6 +153,40 @@ impl Default for AppConfig {
 #[derive(Debug, Clone, Deserialize)]
 pub struct NetworkConfig {
     pub port: u16,
+    /// Addresses the server listens on; empty listens on every IPv4 and
+    /// IPv6 interface.
+    pub bind_addresses: Vec<IpAddr>,
+    /// Address "Join" connects to, IPv4 or IPv6.
+    pub join_address: IpAddr,
+    /// Seconds between announcing a server shutdown and closing connections.
+    pub shutdown_delay: f32,
+    /// DER files of the certificate and private key the server hosts with,
+    /// generated when missing; empty generates a new certificate each run.
+    pub cert_path: String,
+    pub key_path: String,
+    /// How the client checks the certificates of servers it joins.
+    pub cert_verification: network::CertVerification,
+    /// File the client pins server certificates in; empty keeps them in
+    /// memory only.
+    pub known_servers_path: String,
+    /// Longest frame the server accepts from a client on streams without a
+    /// limit of their own; longer ones get the client kicked.
+    pub max_client_frame_bytes: usize,
+    /// Frames failing to decode a client may send within
+    /// `decode_failure_window` seconds before it is kicked.
+    pub max_decode_failures: usize,
+    pub decode_failure_window: f32,
+}
+
+impl NetworkConfig {
+    /// The limits these settings put on what clients send.
+    pub fn client_frame_limits(&self) -> network::ClientFrameLimits {
+        network::ClientFrameLimits {
+            max_frame_bytes: self.max_client_frame_bytes,
+            max_decode_failures: self.max_decode_failures,
+            decode_failure_window: Duration::from_secs_f32(self.decode_failure_window.max(0.0)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -89,6 +198,9 @@ pub struct WindowConfig {
 pub struct DebugConfig {
     pub physics_debug: bool,
     pub log_level: String,
+    /// Seconds between diagnostics reports in the server log; 0 disables
+    /// them.
+    pub diagnostics_log_seconds: f32,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -98,10 +210,37 @@ pub struct AtmosphericsConfig {
     pub diffusion_rate: f32,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct LightingConfig {
+    /// Brightness (0.0 to 1.0) that unlit parts of the map are drawn with.
+    pub ambient: f32,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct SoulsConfig {
     /// Display name shown above the player's creature.
     pub player_name: String,
+    /// Join as a spectator, flying an observer instead of a creature.
+    pub spectator: bool,
+    /// Role to ask for when joining, e.g. `"engineer"`; empty lets the
+    /// server choose.
+    pub role: String,
+    /// Path to the `.ron` list of roles the server assigns.
+    pub roles_file: String,
+    /// How the server picks the spawn point of a joining player.
+    pub spawn_policy: souls::SpawnPolicy,
+    /// Minutes a spectator's full map view lasts before it has to be asked
+    /// for again.
+    pub full_map_minutes: f32,
+}
+
+impl SoulsConfig {
+    /// The rules these settings put on spectators' full map view.
+    pub fn spectate_rules(&self) -> souls::SpectateRules {
+        souls::SpectateRules {
+            grant_duration: Duration::from_secs_f32(self.full_map_minutes.max(0.0) * 60.0),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -110,14 +249,134 @@ pub struct ItemsConfig {
     pub interaction_range: f32,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThingsConfig {
+    /// Directory of the `.ron` thing templates loaded on startup.
+    pub template_dir: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventsConfig {
+    /// Path to the `.ron` description of the timed world events.
+    pub events_file: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GameModeConfig {
+    /// Name of the game mode the server runs, e.g. `"sandbox"` or
+    /// `"survival"`.
+    pub mode: String,
+    /// Length of a round in timed modes, in minutes.
+    pub round_minutes: f32,
+    /// Seconds players wait in the lobby before a round starts.
+    pub lobby_seconds: f32,
+    /// Seconds the outcome of a round is shown before the station is reset
+    /// for the next one.
+    pub restart_seconds: f32,
+    /// Seconds a player vote stays open.
+    pub vote_seconds: f32,
+    /// Fraction of the players that must have voted for a vote to pass when
+    /// its time is up.
+    pub vote_quorum: f32,
+    /// Map files players may vote to change to; none disables map votes.
+    pub vote_maps: Vec<String>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct WorldConfig {
     /// Path to the `.station.ron` map file loaded on server startup.
     pub map_path: String,
+    /// Path the server saves the world to when it shuts down; empty disables
+    /// saving.
+    pub save_path: String,
+    /// Minutes between autosaves; 0 disables them.
+    pub autosave_minutes: f32,
+    /// Earlier saves kept next to `save_path`.
+    pub save_backups: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PhysicsConfig {
+    /// Let the server delegate simulation of props to the nearest client.
+    pub client_authority: bool,
+    /// Players within this distance of a prop compete for its authority.
+    pub authority_radius: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SimulationConfig {
+    /// Rate of the fixed simulation tick (and physics step) in Hz.
+    pub tick_rate: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetricsConfig {
+    /// Serve Prometheus metrics from the dedicated server.
+    pub enabled: bool,
+    /// Local port of the metrics endpoint.
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CameraSettings {
+    /// How quickly the camera catches up with its target.
+    pub follow_speed: f32,
+    /// Camera position relative to the player before zooming and orbiting.
+    pub offset: [f32; 3],
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MovementConfig {
+    /// Top speed of any creature or observer, in metres per second.
+    pub max_speed: f32,
+    /// Speed gained per second while moving.
+    pub acceleration: f32,
+    /// Speed lost per second after letting go.
+    pub friction: f32,
+    /// Fraction of acceleration and friction kept while off the ground.
+    pub air_control: f32,
+    /// Speed factor while sprinting.
+    pub sprint_multiplier: f32,
+    /// Speed factor while crouching.
+    pub crouch_multiplier: f32,
+}
+
+impl From<&creatures::MovementSettings> for MovementConfig {
+    fn from(settings: &creatures::MovementSettings) -> Self {
+        Self {
+            max_speed: settings.max_speed,
+            acceleration: settings.acceleration,
+            friction: settings.friction,
+            air_control: settings.air_control,
+            sprint_multiplier: settings.sprint_multiplier,
+            crouch_multiplier: settings.crouch_multiplier,
+        }
+    }
+}
+
+impl From<&MovementConfig> for creatures::MovementSettings {
+    fn from(config: &MovementConfig) -> Self {
+        Self {
+            max_speed: config.max_speed,
+            acceleration: config.acceleration,
+            friction: config.friction,
+            air_control: config.air_control,
+            sprint_multiplier: config.sprint_multiplier,
+            crouch_multiplier: config.crouch_multiplier,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct InputConfig {
+    /// Action name to the keys or mouse buttons bound to it, replacing that
+    /// action's default bindings.  Actions left out keep their defaults.
+    #[serde(default)]
+    pub bindings: HashMap<String, Vec<String>>,
 }
 
 pub fn load_config() -> AppConfig {
-    match load_config_inner() {
+    match try_load_config() {
         Ok(config) => config,
         Err(error) => {
             warn!("Failed to load config, using defaults: {error}");
@@ -126,14 +385,47 @@ pub fn load_config() -> AppConfig {
     }
 }
 
-fn load_config_inner() -> Result<AppConfig, ::config::ConfigError> {
+/// Reads the config, failing instead of falling back to the defaults.
+pub fn try_load_config() -> Result<AppConfig, ::config::ConfigError> {
     let defaults = AppConfig::default();
 
     let builder = Config::builder()
         .set_default("network.port", defaults.network.port)?
+        .set_default("network.bind_addresses", Vec::<String>::new())?
+        .set_default(
+            "network.join_address",
+            defaults.network.join_address.to_string(),
+        )?
+        .set_default(
+            "network.shutdown_delay",
+            defaults.network.shutdown_delay as f64,
+        )?
+        .set_default("network.cert_path", defaults.network.cert_path)?
+        .set_default("network.key_path", defaults.network.key_path)?
+        .set_default("network.cert_verification", "trust_on_first_use")?
+        .set_default(
+            "network.known_servers_path",
+            defaults.network.known_servers_path,
+        )?
+        .set_default(
+            "network.max_client_frame_bytes",
+            defaults.network.max_client_frame_bytes as u64,
+        )?
+        .set_default(
+            "network.max_decode_failures",
+            defaults.network.max_decode_failures as u64,
+        )?
+        .set_default(
+            "network.decode_failure_window",
+            defaults.network.decode_failure_window as f64,
+        )?
         .set_default("window.title", defaults.window.title)?
         .set_default("debug.physics_debug", defaults.debug.physics_debug)?
         .set_default("debug.log_level", defaults.debug.log_level)?
+        .set_default(
+            "debug.diagnostics_log_seconds",
+            defaults.debug.diagnostics_log_seconds as f64,
+        )?
         .set_default(
             "atmospherics.standard_pressure",
             defaults.atmospherics.standard_pressure as f64,
@@ -146,12 +438,79 @@ fn load_config_inner() -> Result<AppConfig, ::config::ConfigError> {
             "atmospherics.diffusion_rate",
             defaults.atmospherics.diffusion_rate as f64,
         )?
+        .set_default("lighting.ambient", defaults.lighting.ambient as f64)?
         .set_default("souls.player_name", defaults.souls.player_name)?
+        .set_default("souls.spectator", defaults.souls.spectator)?
+        .set_default("souls.role", defaults.souls.role)?
+        .set_default("souls.roles_file", defaults.souls.roles_file)?
+        .set_default("souls.spawn_policy", "round_robin")?
+        .set_default(
+            "souls.full_map_minutes",
+            defaults.souls.full_map_minutes as f64,
+        )?
         .set_default(
             "items.interaction_range",
             defaults.items.interaction_range as f64,
         )?
+        .set_default("things.template_dir", defaults.things.template_dir)?
+        .set_default("events.events_file", defaults.events.events_file)?
+        .set_default("gamemode.mode", defaults.gamemode.mode)?
+        .set_default(
+            "gamemode.round_minutes",
+            defaults.gamemode.round_minutes as f64,
+        )?
+        .set_default(
+            "gamemode.lobby_seconds",
+            defaults.gamemode.lobby_seconds as f64,
+        )?
+        .set_default(
+            "gamemode.restart_seconds",
+            defaults.gamemode.restart_seconds as f64,
+        )?
+        .set_default(
+            "gamemode.vote_seconds",
+            defaults.gamemode.vote_seconds as f64,
+        )?
+        .set_default("gamemode.vote_quorum", defaults.gamemode.vote_quorum as f64)?
+        .set_default("gamemode.vote_maps", defaults.gamemode.vote_maps)?
         .set_default("world.map_path", defaults.world.map_path)?
+        .set_default("world.save_path", defaults.world.save_path)?
+        .set_default(
+            "world.autosave_minutes",
+            defaults.world.autosave_minutes as f64,
+        )?
+        .set_default("world.save_backups", defaults.world.save_backups as u64)?
+        .set_default(
+            "physics.client_authority",
+            defaults.physics.client_authority,
+        )?
+        .set_default(
+            "physics.authority_radius",
+            defaults.physics.authority_radius as f64,
+        )?
+        .set_default("simulation.tick_rate", defaults.simulation.tick_rate)?
+        .set_default("metrics.enabled", defaults.metrics.enabled)?
+        .set_default("metrics.port", defaults.metrics.port)?
+        .set_default("camera.follow_speed", defaults.camera.follow_speed as f64)?
+        .set_default(
+            "camera.offset",
+            defaults.camera.offset.map(f64::from).to_vec(),
+        )?
+        .set_default("movement.max_speed", defaults.movement.max_speed as f64)?
+        .set_default(
+            "movement.acceleration",
+            defaults.movement.acceleration as f64,
+        )?
+        .set_default("movement.friction", defaults.movement.friction as f64)?
+        .set_default("movement.air_control", defaults.movement.air_control as f64)?
+        .set_default(
+            "movement.sprint_multiplier",
+            defaults.movement.sprint_multiplier as f64,
+        )?
+        .set_default(
+            "movement.crouch_multiplier",
+            defaults.movement.crouch_multiplier as f64,
+        )?
         .add_source(File::new(CONFIG_BASENAME, FileFormat::Toml).required(false))
         .add_source(File::new(CONFIG_BASENAME, FileFormat::Ron).required(false))
         .add_source(Environment::with_prefix("GEOSTATIONARY").separator("__"));
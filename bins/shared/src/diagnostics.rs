@@ -0,0 +1,514 @@
+//! Frame-time and world statistics for chasing performance problems.
+//!
+//! [`DebugDiagnosticsPlugin`] measures, through Bevy's diagnostics:
+//!
+//! - frame rate and frame time,
+//! - time spent in `Update` and `PostUpdate` ([`UPDATE_TIME`]) and in one
+//!   fixed step ([`FIXED_STEP_TIME`]), next to the gas grid step
+//!   ([`atmospherics::GAS_STEP_TIME`]).  The fixed step time is also what
+//!   the server's metrics endpoint reports as its tick time.
+//!
+//! Built with the `trace` feature, Bevy opens a span for every system run;
+//! [`system_span_layer`] times those spans, so the report can name the
+//! systems taking the most time.
+//!
+//! Together with a count of entities and archetypes, of the entities of the
+//! main modules and of the work waiting in queues ([`PendingItemEvents`],
+//! stream buffers), these make up a [`DiagnosticsReport`].  The server logs
+//! one periodically; clients show it in a panel that
+//! [`Action::ToggleDiagnostics`] cycles between hidden, collapsed to a
+//! single line, and expanded.
+
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticPath, Diagnostics, DiagnosticsStore, FrameTimeDiagnosticsPlugin,
+    RegisterDiagnostic,
+};
+use bevy::log::BoxedLayer;
+use bevy::log::tracing::field::{Field, Visit};
+use bevy::log::tracing::{Subscriber, span};
+use bevy::log::tracing_subscriber::Layer;
+use bevy::log::tracing_subscriber::layer::Context;
+use bevy::log::tracing_subscriber::registry::LookupSpan;
+use bevy::prelude::*;
+use creatures::Creature;
+use input::{Action, ActionInput};
+use items::{Item, PendingItemEvents};
+use network::{NetId, StreamRegistry};
+use things::Thing;
+use ui::UiTheme;
+
+use crate::app_state::AppState;
+
+/// Diagnostic: time spent in `Update` and `PostUpdate`, in milliseconds.
+pub const UPDATE_TIME: DiagnosticPath = DiagnosticPath::const_new("frame/update_time");
+
+/// Diagnostic: time of one fixed step, in milliseconds.
+pub const FIXED_STEP_TIME: DiagnosticPath = DiagnosticPath::const_new("frame/fixed_step_time");
+
+/// Seconds between refreshes of the panel while it is shown.
+const PANEL_REFRESH_SECS: f32 = 0.5;
+
+/// Archetypes listed in an expanded report.
+const LARGEST_ARCHETYPES: usize = 5;
+
+/// Systems listed in an expanded report.
+const SLOWEST_SYSTEMS: usize = 5;
+
+/// Counts the entities of one module.
+type ModuleCount = fn(&mut World) -> usize;
+
+/// Modules whose entities are counted, by a component all of them carry.
+const MODULE_COUNTS: &[(&str, ModuleCount)] = &[
+    ("things", count::<Thing>),
+    ("items", count::<Item>),
+    ("creatures", count::<Creature>),
+    ("replicated", count::<NetId>),
+    ("ui", count::<Node>),
+];
+
+/// Adds the diagnostics; see the module docs.
+pub struct DebugDiagnosticsPlugin {
+    /// Show the panel; for apps with a window and input.
+    pub panel: bool,
+    /// How often to log the report; `None` never does.
+    pub log_interval: Option<Duration>,
+}
+
+/// What the diagnostics panel shows.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticsPanel {
+    #[default]
+    Hidden,
+    /// Frame rate and entity count only.
+    Collapsed,
+    /// The whole report.
+    Expanded,
+}
+
+impl DiagnosticsPanel {
+    fn next(self) -> Self {
+        match self {
+            Self::Hidden => Self::Collapsed,
+            Self::Collapsed => Self::Expanded,
+            Self::Expanded => Self::Hidden,
+        }
+    }
+}
+
+/// The latest figures, refreshed while the panel is shown and whenever they
+/// are logged.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct DiagnosticsReport {
+    pub fps: Option<f64>,
+    pub frame_ms: Option<f64>,
+    pub update_ms: Option<f64>,
+    pub fixed_step_ms: Option<f64>,
+    pub gas_step_ms: Option<f64>,
+    pub entities: usize,
+    /// Archetypes holding at least one entity.
+    pub archetypes: usize,
+    /// Entity and component counts of the most populated archetypes.
+    pub largest_archetypes: Vec<(usize, usize)>,
+    /// Entities of each module in [`MODULE_COUNTS`].
+    pub modules: Vec<(&'static str, usize)>,
+    /// Work waiting in queues, by queue; empty queues are left out.
+    pub queues: Vec<(String, usize)>,
+    /// The systems that ran longest since the previous report, with their
+    /// mean time per run in milliseconds.  Empty without system spans.
+    pub slowest_systems: Vec<(String, f64)>,
+}
+
+impl DiagnosticsReport {
+    /// The report as text lines; `expanded` adds everything after the
+    /// first line.
+    pub fn lines(&self, expanded: bool) -> Vec<String> {
+        let ms = |value: Option<f64>| value.map_or("-".into(), |v| format!("{v:.1} ms"));
+        let mut lines = vec![format!(
+            "{} fps, {} per frame, {} entities",
+            self.fps.map_or("-".into(), |fps| format!("{fps:.0}")),
+            ms(self.frame_ms),
+            self.entities
+        )];
+        if !expanded {
+            return lines;
+        }
+        lines.push(format!(
+            "update {}, fixed step {}, gas step {}",
+            ms(self.update_ms),
+            ms(self.fixed_step_ms),
+            ms(self.gas_step_ms)
+        ));
+        let mut archetypes = format!("{} archetypes; largest:", self.archetypes);
+        for (entities, components) in &self.largest_archetypes {
+            let _ = write!(archetypes, " {entities}x{components}c");
+        }
+        lines.push(archetypes);
+        lines.push(join("entities: ", &self.modules));
+        if self.queues.is_empty() {
+            lines.push("queues: empty".into());
+        } else {
+            lines.push(join("queues: ", &self.queues));
+        }
+        if !self.slowest_systems.is_empty() {
+            let systems: Vec<String> = self
+                .slowest_systems
+                .iter()
+                .map(|(name, ms)| format!("{name} {ms:.2} ms"))
+                .collect();
+            lines.push(format!("slowest systems: {}", systems.join(", ")));
+        }
+        lines
+    }
+}
+
+fn join<N: std::fmt::Display>(prefix: &str, counts: &[(N, usize)]) -> String {
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(name, count)| format!("{name} {count}"))
+        .collect();
+    format!("{prefix}{}", counts.join(", "))
+}
+
+/// Time spent in each system since the report last took it, and how many
+/// times it ran, as measured by [`system_span_layer`].
+#[derive(Resource, Clone, Default)]
+pub struct SystemSpans(Arc<Mutex<HashMap<String, (Duration, u32)>>>);
+
+impl SystemSpans {
+    /// The [`SLOWEST_SYSTEMS`] systems with the most time spent, with their
+    /// mean time per run in milliseconds, starting over afterwards.
+    fn take_slowest(&self) -> Vec<(String, f64)> {
+        let spent = std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut spent: Vec<_> = spent.into_iter().collect();
+        spent.sort_by_key(|(_, (total, _))| std::cmp::Reverse(*total));
+        spent
+            .into_iter()
+            .take(SLOWEST_SYSTEMS)
+            .map(|(name, (total, runs))| (name, total.as_secs_f64() * 1000.0 / f64::from(runs)))
+            .collect()
+    }
+}
+
+/// For [`LogPlugin::custom_layer`](bevy::log::LogPlugin::custom_layer):
+/// a layer timing the span Bevy opens for every system run, feeding
+/// [`SystemSpans`].  Without the `trace` feature there are no such spans.
+pub fn system_span_layer(app: &mut App) -> Option<BoxedLayer> {
+    let spans = SystemSpans::default();
+    app.insert_resource(spans.clone());
+    Some(Box::new(SystemSpanLayer(spans)))
+}
+
+struct SystemSpanLayer(SystemSpans);
+
+/// Stored on a system's span: which system, and when its current run began.
+struct SystemRun {
+    name: String,
+    started: Option<Instant>,
+}
+
+/// Reads the `name` field of a system span.
+#[derive(Default)]
+struct SystemName(Option<String>);
+
+impl Visit for SystemName {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "name" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "name" {
+            self.0 = Some(format!("{value:?}"));
+        }
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SystemSpanLayer {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != "system" {
+            return;
+        }
+        let mut name = SystemName::default();
+        attrs.record(&mut name);
+        if let (Some(name), Some(span)) = (name.0, ctx.span(id)) {
+            span.extensions_mut().insert(SystemRun {
+                name,
+                started: None,
+            });
+        }
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id)
+            && let Some(run) = span.extensions_mut().get_mut::<SystemRun>()
+        {
+            run.started = Some(Instant::now());
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        let Some(run) = extensions.get_mut::<SystemRun>() else {
+            return;
+        };
+        let Some(started) = run.started.take() else {
+            return;
+        };
+        let mut spent = self.0.0.lock().unwrap_or_else(|e| e.into_inner());
+        let (total, runs) = spent.entry(run.name.clone()).or_default();
+        *total += started.elapsed();
+        *runs += 1;
+    }
+}
+
+/// When the phase being timed began.
+#[derive(Resource, Default)]
+struct PhaseStarts {
+    update: Option<Instant>,
+    fixed_step: Option<Instant>,
+}
+
+/// When to refresh and log the report next.
+#[derive(Resource)]
+struct ReportTimers {
+    panel: Timer,
+    log: Option<Timer>,
+}
+
+impl Plugin for DebugDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+        }
+        app.register_diagnostic(Diagnostic::new(UPDATE_TIME).with_suffix("ms"));
+        app.register_diagnostic(Diagnostic::new(FIXED_STEP_TIME).with_suffix("ms"));
+        app.init_resource::<PhaseStarts>();
+        app.init_resource::<DiagnosticsReport>();
+        app.insert_resource(ReportTimers {
+            panel: Timer::from_seconds(PANEL_REFRESH_SECS, TimerMode::Repeating),
+            log: self
+                .log_interval
+                .map(|interval| Timer::new(interval, TimerMode::Repeating)),
+        });
+        app.add_systems(FixedFirst, start_fixed_step);
+        app.add_systems(FixedLast, stop_fixed_step);
+        app.add_systems(
+            RunFixedMainLoop,
+            start_update.in_set(RunFixedMainLoopSystems::AfterFixedMainLoop),
+        );
+        app.add_systems(Last, (stop_update, refresh_report).chain());
+
+        if self.panel {
+            app.init_resource::<DiagnosticsPanel>();
+            app.add_systems(
+                Update,
+                (toggle_diagnostics_panel, update_diagnostics_panel)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+        }
+    }
+}
+
+fn count<C: Component>(world: &mut World) -> usize {
+    world.query_filtered::<(), With<C>>().iter(world).count()
+}
+
+fn start_fixed_step(mut starts: ResMut<PhaseStarts>) {
+    starts.fixed_step = Some(Instant::now());
+}
+
+fn stop_fixed_step(mut starts: ResMut<PhaseStarts>, mut diagnostics: Diagnostics) {
+    if let Some(started) = starts.fixed_step.take() {
+        diagnostics.add_measurement(&FIXED_STEP_TIME, || {
+            started.elapsed().as_secs_f64() * 1000.0
+        });
+    }
+}
+
+fn start_update(mut starts: ResMut<PhaseStarts>) {
+    starts.update = Some(Instant::now());
+}
+
+fn stop_update(mut starts: ResMut<PhaseStarts>, mut diagnostics: Diagnostics) {
+    if let Some(started) = starts.update.take() {
+        diagnostics.add_measurement(&UPDATE_TIME, || started.elapsed().as_secs_f64() * 1000.0);
+    }
+}
+
+/// Gathers a [`DiagnosticsReport`] from the world.
+pub fn collect_report(world: &mut World) -> DiagnosticsReport {
+    let smoothed = |world: &World, path: &DiagnosticPath| {
+        world
+            .get_resource::<DiagnosticsStore>()
+            .and_then(|store| store.get(path))
+            .and_then(Diagnostic::smoothed)
+    };
+    let mut populated: Vec<(usize, usize)> = world
+        .archetypes()
+        .iter()
+        .filter(|archetype| !archetype.is_empty())
+        .map(|archetype| (archetype.len() as usize, archetype.components().len()))
+        .collect();
+    populated.sort_by(|a, b| b.cmp(a));
+
+    let mut queues = Vec::new();
+    if let Some(pending) = world.get_resource::<PendingItemEvents>()
+        && !pending.0.is_empty()
+    {
+        queues.push(("pending item events".to_string(), pending.0.len()));
+    }
+    if let Some(registry) = world.get_resource::<StreamRegistry>() {
+        for (name, frames) in registry.buffered_frames() {
+            queues.push((format!("{name} received"), frames));
+        }
+        for (_, name, saturation) in registry.saturations() {
+            if saturation.queued_frames > 0 {
+                queues.push((format!("{name} queued"), saturation.queued_frames));
+            }
+        }
+    }
+
+    DiagnosticsReport {
+        fps: smoothed(world, &FrameTimeDiagnosticsPlugin::FPS),
+        frame_ms: smoothed(world, &FrameTimeDiagnosticsPlugin::FRAME_TIME),
+        update_ms: smoothed(world, &UPDATE_TIME),
+        fixed_step_ms: smoothed(world, &FIXED_STEP_TIME),
+        gas_step_ms: smoothed(world, &atmospherics::GAS_STEP_TIME),
+        entities: world.entities().count_spawned() as usize,
+        archetypes: populated.len(),
+        largest_archetypes: populated.into_iter().take(LARGEST_ARCHETYPES).collect(),
+        modules: MODULE_COUNTS
+            .iter()
+            .map(|(module, count)| (*module, count(world)))
+            .collect(),
+        queues,
+        slowest_systems: world
+            .get_resource::<SystemSpans>()
+            .map_or_else(Vec::new, SystemSpans::take_slowest),
+    }
+}
+
+/// Refreshes the [`DiagnosticsReport`] while the panel is shown, and logs
+/// it every log interval.
+fn refresh_report(world: &mut World) {
+    let delta = world.resource::<Time>().delta();
+    let shown = world
+        .get_resource::<DiagnosticsPanel>()
+        .is_some_and(|panel| *panel != DiagnosticsPanel::Hidden);
+    let mut timers = world.resource_mut::<ReportTimers>();
+    let refresh = shown && timers.panel.tick(delta).just_finished();
+    let log = timers
+        .log
+        .as_mut()
+        .is_some_and(|timer| timer.tick(delta).just_finished());
+    if !refresh && !log {
+        return;
+    }
+    let report = collect_report(world);
+    if log {
+        for line in report.lines(true) {
+            info!("Diagnostics: {line}");
+        }
+    }
+    world.insert_resource(report);
+}
+
+/// Cycles the panel on [`Action::ToggleDiagnostics`].
+fn toggle_diagnostics_panel(input: ActionInput, mut panel: ResMut<DiagnosticsPanel>) {
+    if input.just_pressed(Action::ToggleDiagnostics) {
+        *panel = panel.next();
+    }
+}
+
+/// Marker for the diagnostics panel's text node.
+#[derive(Component)]
+struct DiagnosticsPanelText;
+
+/// Shows the report in the bottom-left corner while the panel is not
+/// hidden.
+fn update_diagnostics_panel(
+    mut commands: Commands,
+    panel: Res<DiagnosticsPanel>,
+    report: Res<DiagnosticsReport>,
+    theme: Res<UiTheme>,
+    mut panel_q: Query<(&mut Text, &mut Visibility), With<DiagnosticsPanelText>>,
+) {
+    let Ok((mut text, mut visibility)) = panel_q.single_mut() else {
+        commands.spawn((
+            Text::default(),
+            TextFont::from_font_size(theme.font_size_small),
+            TextColor(theme.text),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(theme.background.with_alpha(0.8)),
+            Visibility::Hidden,
+            DiagnosticsPanelText,
+        ));
+        return;
+    };
+    if *panel == DiagnosticsPanel::Hidden {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+    let lines = report
+        .lines(*panel == DiagnosticsPanel::Expanded)
+        .join("\n");
+    if text.0 != lines {
+        text.0 = lines;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_counts_module_entities_and_archetypes() {
+        let mut world = World::new();
+        world.spawn(Thing { kind: 1 });
+        world.spawn((Thing { kind: 2 }, Item));
+        world.spawn(NetId(4));
+        world.init_resource::<PendingItemEvents>();
+
+        let report = collect_report(&mut world);
+        assert_eq!(report.entities, 3);
+        assert_eq!(report.archetypes, 3);
+        assert_eq!(
+            report.modules[..3],
+            [("things", 2), ("items", 1), ("creatures", 0)]
+        );
+        assert!(report.queues.is_empty());
+        assert_eq!(report.lines(false), vec!["- fps, - per frame, 3 entities"]);
+        assert_eq!(report.lines(true).len(), 5);
+    }
+
+    #[test]
+    fn system_spans_name_the_slowest_systems_once() {
+        let spans = SystemSpans::default();
+        spans.0.lock().unwrap().extend([
+            ("fast".to_string(), (Duration::from_millis(1), 1)),
+            ("slow".to_string(), (Duration::from_millis(8), 4)),
+        ]);
+        assert_eq!(
+            spans.take_slowest(),
+            vec![("slow".to_string(), 2.0), ("fast".to_string(), 1.0)]
+        );
+        assert!(spans.take_slowest().is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+//! World events described in a data file.
+//!
+//! The file named by `events.events_file` configures the timed world events
+//! the server runs.  Events left out do not happen:
+//!
+//! ```ron
+//! (
+//!     supply_drop: Some((
+//!         every_minutes: 10.0,
+//!         cargo_tile: (4, 9),
+//!         rolls: 4,
+//!         loot: [(template: "plasteel", weight: 3), (template: "can")],
+//!     )),
+//! )
+//! ```
+
+use std::path::Path;
+
+use bevy::prelude::*;
+use events::supply::{SUPPLY_DROP_EVENT, drop_supplies};
+use events::{SupplyDrop, WorldEventsExt};
+use serde::Deserialize;
+
+/// Every configurable world event.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct EventDefs {
+    #[serde(default)]
+    pub supply_drop: Option<SupplyDrop>,
+}
+
+/// Reads the events in `path`.  A file that cannot be read or parsed is
+/// logged and yields no events.
+pub fn load_event_defs(path: &Path) -> EventDefs {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            warn!("Events: cannot read {path:?}: {e}");
+            return EventDefs::default();
+        }
+    };
+    match ron::from_str::<EventDefs>(&contents) {
+        Ok(defs) => {
+            info!("Events: loaded {path:?}");
+            defs
+        }
+        Err(e) => {
+            error!("Events: failed to parse {path:?}: {e}");
+            EventDefs::default()
+        }
+    }
+}
+
+/// Schedules the events configured in `events_file`.  Add it after
+/// [`events::EventsPlugin`].
+pub struct EventDefsPlugin {
+    pub events_file: String,
+}
+
+impl Plugin for EventDefsPlugin {
+    fn build(&self, app: &mut App) {
+        let defs = load_event_defs(Path::new(&self.events_file));
+        if let Some(supply_drop) = defs.supply_drop {
+            app.add_world_event(SUPPLY_DROP_EVENT, supply_drop.interval(), drop_supplies);
+            app.insert_resource(supply_drop);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_defs_parse_with_defaults() {
+        let defs: EventDefs = ron::from_str(
+            r#"(
+                supply_drop: Some((
+                    every_minutes: 10.0,
+                    cargo_tile: (4, 9),
+                    rolls: 3,
+                    loot: [(template: "plasteel", weight: 3), (template: "can")],
+                )),
+            )"#,
+        )
+        .expect("events parse");
+        let supply_drop = defs.supply_drop.expect("supply drop configured");
+        assert_eq!(supply_drop.crate_template, "crate");
+        assert_eq!(supply_drop.cargo_tile, [4, 9]);
+        assert_eq!(supply_drop.loot[1].weight, 1);
+
+        let none: EventDefs = ron::from_str("()").expect("empty events parse");
+        assert_eq!(none, EventDefs::default());
+    }
+}
@@ -1,3 +1,11 @@
 pub mod app_state;
 pub mod config;
+pub mod diagnostics;
+pub mod event_defs;
+pub mod recipes;
+pub mod reload;
+pub mod role_defs;
+pub mod server_app;
+pub mod tech;
+pub mod template_defs;
 pub mod templates;
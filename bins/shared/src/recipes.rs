@@ -0,0 +1,57 @@
+//! The recipes of the game.
+//!
+//! Client and server both add [`RecipesPlugin`], so the [`RecipeBook`] holds
+//! the same recipes in the same order on each and a [`crafting::RecipeId`]
+//! sent over the wire names the same recipe.
+
+use bevy::prelude::*;
+use crafting::{ItemCount, Recipe, RecipeBook};
+
+/// Thing kind of the workbench, the station most recipes are made at.
+pub const WORKBENCH_KIND: u16 = 13;
+
+pub(crate) const CAN_KIND: u16 = 2;
+pub(crate) const TOOLBOX_KIND: u16 = 3;
+pub(crate) const PLASTEEL_KIND: u16 = 4;
+pub(crate) const LAMP_KIND: u16 = 8;
+
+/// Registers the recipes in the [`RecipeBook`].  Add it once, after
+/// [`crafting::CraftingPlugin`].
+pub struct RecipesPlugin;
+
+impl Plugin for RecipesPlugin {
+    fn build(&self, app: &mut App) {
+        let mut book = app.world_mut().get_resource_or_init::<RecipeBook>();
+        book.register(Recipe {
+            name: "Workbench".into(),
+            inputs: vec![ItemCount::new(PLASTEEL_KIND, 4)],
+            output: ItemCount::new(WORKBENCH_KIND, 1),
+            station: None,
+            craft_time: 3.0,
+        });
+        book.register(Recipe {
+            name: "Toolbox".into(),
+            inputs: vec![ItemCount::new(PLASTEEL_KIND, 2)],
+            output: ItemCount::new(TOOLBOX_KIND, 1),
+            station: Some(WORKBENCH_KIND),
+            craft_time: 2.0,
+        });
+        book.register(Recipe {
+            name: "Can".into(),
+            inputs: vec![ItemCount::new(PLASTEEL_KIND, 1)],
+            output: ItemCount::new(CAN_KIND, 1),
+            station: Some(WORKBENCH_KIND),
+            craft_time: 1.0,
+        });
+        book.register(Recipe {
+            name: "Lamp".into(),
+            inputs: vec![
+                ItemCount::new(CAN_KIND, 1),
+                ItemCount::new(PLASTEEL_KIND, 1),
+            ],
+            output: ItemCount::new(LAMP_KIND, 1),
+            station: Some(WORKBENCH_KIND),
+            craft_time: 2.0,
+        });
+    }
+}
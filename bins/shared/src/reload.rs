@@ -0,0 +1,178 @@
+//! Reloading thing templates and config values at runtime.
+//!
+//! A [`ReloadRequest`] re-reads either the data-driven templates (see
+//! [`crate::template_defs`]) or the config file, and applies the result to
+//! the running app:
+//!
+//! - templates replace the registered data-driven ones, so things spawned
+//!   afterwards use the new definitions,
+//! - config values replace [`AppConfig`] and the live resources built from
+//!   it: [`InteractionRange`], [`MovementSettings`] and the atmosphere's
+//!   pressure force scale.
+//!
+//! With [`HotReloadPlugin::watch`] set, the template and config files are
+//! polled for changes and reloaded as soon as they are saved.  Dedicated
+//! servers instead reload on an admin command.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use atmospherics::{AtmosInitConfig, PressureForceScale};
+use bevy::prelude::*;
+use creatures::MovementSettings;
+use items::InteractionRange;
+
+use crate::config::{AppConfig, CONFIG_FILES, try_load_config};
+use crate::template_defs::{TemplateSource, reload_templates};
+
+/// Seconds between checks of the watched files.
+pub const WATCH_INTERVAL_SECS: f32 = 1.0;
+
+/// Asks the app to re-read one kind of data file.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadRequest {
+    Templates,
+    Config,
+}
+
+/// Applies [`ReloadRequest`]s, and with `watch` set writes them whenever the
+/// template or config files change on disk.
+pub struct HotReloadPlugin {
+    pub watch: bool,
+}
+
+/// Modification times of the watched files at the last check.
+#[derive(Resource)]
+struct WatchedFiles {
+    timer: Timer,
+    /// `None` until the first check.
+    templates: Option<Vec<(PathBuf, SystemTime)>>,
+    config: Vec<(PathBuf, SystemTime)>,
+}
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ReloadRequest>();
+        app.add_systems(Update, apply_reload_requests);
+        if self.watch {
+            app.insert_resource(WatchedFiles {
+                timer: Timer::from_seconds(WATCH_INTERVAL_SECS, TimerMode::Repeating),
+                templates: None,
+                config: modification_times(CONFIG_FILES.iter().map(PathBuf::from)),
+            });
+            app.add_systems(Update, watch_data_files.before(apply_reload_requests));
+        }
+    }
+}
+
+/// Modification times of those `paths` that exist, in order.
+fn modification_times(paths: impl IntoIterator<Item = PathBuf>) -> Vec<(PathBuf, SystemTime)> {
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((path, modified))
+        })
+        .collect()
+}
+
+/// The `*.ron` files in `dir`, sorted.
+fn template_files(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Writes a [`ReloadRequest`] for every kind of file added, removed or
+/// saved since the last check.
+fn watch_data_files(
+    time: Res<Time>,
+    mut watched: ResMut<WatchedFiles>,
+    source: Option<Res<TemplateSource>>,
+    mut requests: MessageWriter<ReloadRequest>,
+) {
+    if !watched.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if let Some(source) = source {
+        let templates = modification_times(template_files(&source.dir));
+        // The first check only records the files loaded on startup.
+        if watched
+            .templates
+            .as_ref()
+            .is_some_and(|seen| *seen != templates)
+        {
+            requests.write(ReloadRequest::Templates);
+        }
+        watched.templates = Some(templates);
+    }
+
+    let config = modification_times(CONFIG_FILES.iter().map(PathBuf::from));
+    if watched.config != config {
+        requests.write(ReloadRequest::Config);
+        watched.config = config;
+    }
+}
+
+/// Applies pending [`ReloadRequest`]s.
+fn apply_reload_requests(world: &mut World) {
+    let mut requests: Vec<ReloadRequest> = world
+        .resource_mut::<Messages<ReloadRequest>>()
+        .drain()
+        .collect();
+    requests.dedup();
+    for request in requests {
+        match request {
+            ReloadRequest::Templates => reload_templates(world),
+            ReloadRequest::Config => match try_load_config() {
+                Ok(config) => {
+                    apply_config(world, config);
+                    info!("Config: reloaded");
+                }
+                Err(e) => error!("Config: failed to reload, keeping the current values: {e}"),
+            },
+        }
+    }
+}
+
+/// Replaces [`AppConfig`] with `config` and updates the resources that
+/// take their values from it.
+pub fn apply_config(world: &mut World, config: AppConfig) {
+    world.insert_resource(InteractionRange(config.items.interaction_range));
+    world.insert_resource(MovementSettings::from(&config.movement));
+    let scale = config.atmospherics.pressure_force_scale;
+    if let Some(mut live) = world.get_resource_mut::<PressureForceScale>() {
+        live.0 = scale;
+    }
+    if let Some(mut init) = world.get_resource_mut::<AtmosInitConfig>() {
+        init.pressure_force_scale = scale;
+    }
+    world.insert_resource(config);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_config_updates_live_resources() {
+        let mut world = World::new();
+        world.insert_resource(InteractionRange(2.0));
+        world.insert_resource(PressureForceScale(50.0));
+
+        let mut config = AppConfig::default();
+        config.items.interaction_range = 3.5;
+        config.atmospherics.pressure_force_scale = 10.0;
+        apply_config(&mut world, config);
+
+        assert_eq!(world.resource::<InteractionRange>().0, 3.5);
+        assert_eq!(world.resource::<PressureForceScale>().0, 10.0);
+        assert_eq!(world.resource::<AppConfig>().items.interaction_range, 3.5);
+    }
+}
@@ -0,0 +1,85 @@
+//! Roles described in a data file.
+//!
+//! The file named by `souls.roles_file` holds the list of [`RoleDef`]s the
+//! server assigns to joining players, in the order they are offered when a
+//! player asks for no role or a full one:
+//!
+//! ```ron
+//! [
+//!     (
+//!         name: "engineer",
+//!         display_name: "Station Engineer",
+//!         access: [Engineering],
+//!         equipment: ["toolbox", "plasteel"],
+//!     ),
+//! ]
+//! ```
+
+use std::path::Path;
+
+use bevy::prelude::*;
+use roles::{RoleBook, RoleDef};
+
+/// Reads the roles in `path`.  A file that cannot be read or parsed is
+/// logged and yields no roles.
+pub fn load_role_book(path: &Path) -> RoleBook {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            warn!("Roles: cannot read {path:?}: {e}");
+            return RoleBook::default();
+        }
+    };
+    match ron::from_str::<Vec<RoleDef>>(&contents) {
+        Ok(roles) => {
+            info!("Roles: loaded {} roles from {path:?}", roles.len());
+            RoleBook(roles)
+        }
+        Err(e) => {
+            error!("Roles: failed to parse {path:?}: {e}");
+            RoleBook::default()
+        }
+    }
+}
+
+/// Fills the [`RoleBook`] from `roles_file`.  Add it after
+/// [`roles::RolesPlugin`].
+pub struct RoleDefsPlugin {
+    pub roles_file: String,
+}
+
+impl Plugin for RoleDefsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_role_book(Path::new(&self.roles_file)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use access::AccessLevel;
+
+    #[test]
+    fn role_defs_parse_with_defaults() {
+        let roles: Vec<RoleDef> = ron::from_str(
+            r#"[
+                (
+                    name: "captain",
+                    display_name: "Captain",
+                    access: [Command],
+                    slots: Some(1),
+                ),
+                (name: "assistant", display_name: "Assistant"),
+            ]"#,
+        )
+        .expect("roles parse");
+        let book = RoleBook(roles);
+
+        let captain = book.get("captain").expect("captain is a role");
+        assert_eq!(captain.access, vec![AccessLevel::Command]);
+        assert_eq!(captain.slots, Some(1));
+        let assistant = book.get("assistant").expect("assistant is a role");
+        assert!(assistant.access.is_empty() && assistant.equipment.is_empty());
+        assert_eq!(assistant.slots, None);
+    }
+}
@@ -0,0 +1,286 @@
+//! The headless server world, shared by the dedicated server and the
+//! client's listen-server thread.
+//!
+//! [`add_server_plugins`] sets an [`App`] up as a dedicated server: minimal
+//! plugins, every gameplay plugin, hosting on startup, and a graceful
+//! shutdown once its [`ShutdownSignal`] is raised.  It hosts on
+//! `network.port`, or only on a [`Loopback`] given as a [`LoopbackHost`],
+//! presenting the certificate at `network.cert_path` when one is set.
+//! Logging is left to the caller, since only one logger may be installed per
+//! process.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use interactions::InteractionsPlugin;
+use items::{InteractionRange, ItemsPlugin};
+use network::{
+    Headless, Loopback, NetCommand, NetServerSender, NetworkPlugin, ServerEvent, ServerIdentity,
+};
+use physics::PhysicsPlugin;
+use things::{ClientAuthoritySettings, ThingsPlugin};
+use tiles::TilesPlugin;
+use world::{MapPath, SaveBackups, SavePath, WorldPlugin, WorldSaveRequested};
+
+use crate::app_state::AppState;
+use crate::config::AppConfig;
+
+/// Raised to shut the server down gracefully; see [`check_shutdown_signal`].
+/// Clones share the flag, so it can be raised from another thread or a
+/// signal handler.
+#[derive(Resource, Clone, Default)]
+pub struct ShutdownSignal {
+    requested: Arc<AtomicBool>,
+    /// Seconds between announcing the shutdown and closing connections;
+    /// `network.shutdown_delay` unless overridden.
+    delay: Option<Duration>,
+}
+
+impl ShutdownSignal {
+    /// A signal that closes connections right after announcing the
+    /// shutdown.
+    pub fn immediate() -> Self {
+        Self {
+            requested: Arc::default(),
+            delay: Some(Duration::ZERO),
+        }
+    }
+
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+}
+
+/// Makes the server host on an in-process [`Loopback`] instead of
+/// `network.port`, taking no remote players.  Insert before the app runs.
+#[derive(Resource, Clone, Debug)]
+pub struct LoopbackHost(pub Loopback);
+
+/// Marker resource inserted once graceful shutdown has been requested.
+#[derive(Resource)]
+struct ShuttingDown;
+
+/// Time until the next autosave; present while `world.save_path` and
+/// `world.autosave_minutes` are set.
+#[derive(Resource)]
+struct AutosaveTimer(Timer);
+
+/// Adds everything a headless server world needs to `app`, hosting on
+/// `network.port` as soon as it runs.  Raising `shutdown` stops it.
+pub fn add_server_plugins(app: &mut App, config: &AppConfig, shutdown: ShutdownSignal) {
+    app.insert_resource(config.clone());
+    app.insert_resource(shutdown);
+    app.insert_resource(MapPath::new(&config.world.map_path));
+    if !config.world.save_path.is_empty() {
+        app.insert_resource(SavePath::new(&config.world.save_path));
+        app.insert_resource(SaveBackups(config.world.save_backups));
+        if config.world.autosave_minutes > 0.0 {
+            app.insert_resource(AutosaveTimer(Timer::from_seconds(
+                config.world.autosave_minutes * 60.0,
+                TimerMode::Repeating,
+            )));
+        }
+    }
+    app.insert_resource(config.network.client_frame_limits());
+    if !config.network.cert_path.is_empty() && !config.network.key_path.is_empty() {
+        match ServerIdentity::load_or_generate(
+            Path::new(&config.network.cert_path),
+            Path::new(&config.network.key_path),
+        ) {
+            Ok(identity) => {
+                app.insert_resource(identity);
+            }
+            Err(e) => error!("Failed to load the server certificate, using a temporary one: {e}"),
+        }
+    }
+
+    // No window or rendering. Mesh/scene asset support is retained for physics.
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(bevy::transform::TransformPlugin)
+        .add_plugins(bevy::asset::AssetPlugin::default())
+        .add_plugins(bevy::mesh::MeshPlugin)
+        .add_plugins(bevy::scene::ScenePlugin)
+        .add_plugins(bevy::state::app::StatesPlugin)
+        .insert_resource(Headless)
+        .add_plugins(NetworkPlugin {
+            loading: AppState::Loading,
+            in_game: AppState::InGame,
+            disconnected: AppState::MainMenu,
+        })
+        .add_plugins(PhysicsPlugin)
+        .add_plugins(WorldPlugin {
+            loading: AppState::Loading,
+            in_game: AppState::InGame,
+        })
+        .add_plugins(TilesPlugin::in_state(AppState::InGame))
+        .add_plugins(ThingsPlugin::<AppState>::in_state(AppState::InGame))
+        .add_plugins(atmospherics::AtmosphericsPlugin::new(
+            AppState::Loading,
+            AppState::InGame,
+            config.atmospherics.standard_pressure,
+            config.atmospherics.pressure_force_scale,
+            config.atmospherics.diffusion_rate,
+        ))
+        .add_plugins(lighting::LightingPlugin::new(
+            AppState::InGame,
+            config.lighting.ambient,
+        ))
+        .add_plugins(power::PowerPlugin::<AppState>::in_state(AppState::InGame))
+        .add_plugins(creatures::CreaturesPlugin)
+        .add_plugins(projectiles::ProjectilesPlugin)
+        .add_plugins(ai::AiPlugin::<AppState>::in_state(AppState::InGame))
+        .add_plugins(souls::SoulsPlugin)
+        .add_plugins(crate::templates::TemplatesPlugin {
+            template_dir: config.things.template_dir.clone(),
+        })
+        .add_plugins(ItemsPlugin)
+        .add_plugins(crafting::CraftingPlugin)
+        .add_plugins(crate::recipes::RecipesPlugin)
+        .add_plugins(research::ResearchPlugin)
+        .add_plugins(crate::tech::TechTreePlugin)
+        .add_plugins(access::AccessPlugin)
+        .add_plugins(roles::RolesPlugin)
+        .add_plugins(crate::role_defs::RoleDefsPlugin {
+            roles_file: config.souls.roles_file.clone(),
+        })
+        .add_plugins(events::EventsPlugin)
+        .add_plugins(crate::event_defs::EventDefsPlugin {
+            events_file: config.events.events_file.clone(),
+        })
+        .add_plugins(gamemode::GameModePlugin {
+            mode: config.gamemode.mode.clone(),
+            round_length: Duration::from_secs_f32(config.gamemode.round_minutes.max(0.0) * 60.0),
+            timings: gamemode::RoundTimings {
+                lobby: Duration::from_secs_f32(config.gamemode.lobby_seconds.max(0.0)),
+                restart: Duration::from_secs_f32(config.gamemode.restart_seconds.max(0.0)),
+            },
+        })
+        .add_plugins(gamemode::VotePlugin {
+            settings: gamemode::VoteSettings {
+                duration: Duration::from_secs_f32(config.gamemode.vote_seconds.max(0.0)),
+                quorum: config.gamemode.vote_quorum.clamp(0.0, 1.0),
+                maps: config.gamemode.vote_maps.clone(),
+            },
+        })
+        .add_plugins(crate::reload::HotReloadPlugin { watch: false })
+        .add_plugins(crate::diagnostics::DebugDiagnosticsPlugin {
+            panel: false,
+            log_interval: (config.debug.diagnostics_log_seconds > 0.0)
+                .then(|| Duration::from_secs_f32(config.debug.diagnostics_log_seconds)),
+        })
+        .add_plugins(InteractionsPlugin::<AppState>::in_state(AppState::InGame))
+        .insert_resource(InteractionRange(config.items.interaction_range))
+        .insert_resource(config.souls.spawn_policy)
+        .insert_resource(config.souls.spectate_rules())
+        .insert_resource(creatures::MovementSettings::from(&config.movement))
+        .insert_resource(ClientAuthoritySettings {
+            enabled: config.physics.client_authority,
+            radius: config.physics.authority_radius,
+        })
+        .insert_resource(Time::<Fixed>::from_hz(config.simulation.tick_rate))
+        .insert_state(AppState::Loading)
+        .add_systems(Startup, host_on_startup)
+        .add_systems(Update, (check_shutdown_signal, save_on_shutdown))
+        .add_systems(
+            Update,
+            autosave
+                .run_if(resource_exists::<AutosaveTimer>)
+                .run_if(in_state(AppState::InGame)),
+        );
+}
+
+/// Startup system: auto-sends `NetCommand::Host` so the server begins listening immediately.
+fn host_on_startup(
+    mut net_commands: MessageWriter<NetCommand>,
+    config: Res<AppConfig>,
+    loopback: Option<Res<LoopbackHost>>,
+) {
+    if let Some(loopback) = loopback {
+        net_commands.write(NetCommand::HostLoopback(loopback.0.clone()));
+        info!("Headless server mode: auto-hosting on loopback");
+        return;
+    }
+    net_commands.write(NetCommand::Host {
+        port: config.network.port,
+        addresses: config.network.bind_addresses.clone(),
+    });
+    info!(
+        "Headless server mode: auto-hosting on port {}",
+        config.network.port
+    );
+}
+
+/// Checks for a raised [`ShutdownSignal`] and performs a graceful shutdown:
+///
+/// * **Phase 1** (first frame the signal is detected): send
+///   [`NetCommand::ShutdownServer`], which announces the shutdown to every
+///   client, refuses new joins and, after the signal's delay, closes all
+///   connections; then insert the [`ShuttingDown`] marker.
+/// * **Phase 2** (once [`NetServerSender`] is removed): request [`AppExit`].
+///   [`NetServerSender`] is removed by `drain_server_events` when it receives
+///   `ServerEvent::HostingStopped`, which the server task emits after closing
+///   the connections.
+fn check_shutdown_signal(
+    mut commands: Commands,
+    config: Res<AppConfig>,
+    signal: Res<ShutdownSignal>,
+    sender: Option<Res<NetServerSender>>,
+    shutting_down: Option<Res<ShuttingDown>>,
+    mut net_commands: MessageWriter<NetCommand>,
+    mut app_exit: MessageWriter<AppExit>,
+) {
+    if !signal.is_requested() {
+        return;
+    }
+
+    if shutting_down.is_none() {
+        // Phase 1: announce and schedule the shutdown.
+        let delay = signal.delay.unwrap_or(Duration::from_secs_f32(
+            config.network.shutdown_delay.max(0.0),
+        ));
+        net_commands.write(NetCommand::ShutdownServer {
+            message: "Server is shutting down".into(),
+            delay,
+        });
+        commands.insert_resource(ShuttingDown);
+        info!(
+            "Shutdown requested: notifying clients, closing connections in {}s...",
+            delay.as_secs_f32()
+        );
+    } else if sender.is_none() {
+        // Phase 2: hosting has stopped and every connection is closed.
+        app_exit.write(AppExit::Success);
+    }
+}
+
+/// Saves the world once a graceful shutdown starts; a no-op unless
+/// `world.save_path` is configured.
+fn save_on_shutdown(
+    mut events: MessageReader<ServerEvent>,
+    mut save: MessageWriter<WorldSaveRequested>,
+) {
+    for event in events.read() {
+        if matches!(event, ServerEvent::ShutdownStarted { .. }) {
+            save.write(WorldSaveRequested);
+        }
+    }
+}
+
+/// Saves the world every `world.autosave_minutes` while in game.
+fn autosave(
+    time: Res<Time>,
+    mut timer: ResMut<AutosaveTimer>,
+    mut save: MessageWriter<WorldSaveRequested>,
+) {
+    if timer.0.tick(time.delta()).just_finished() {
+        save.write(WorldSaveRequested);
+    }
+}
@@ -0,0 +1,44 @@
+//! The tech tree of the game.
+//!
+//! Like the recipes, client and server both add [`TechTreePlugin`], so the
+//! [`TechTree`] holds the same nodes in the same order on each and a
+//! [`research::TechId`] sent over the wire names the same node.  Walls,
+//! plating and the basic recipes need no research.
+
+use bevy::prelude::*;
+use crafting::ItemCount;
+use research::{TechNode, TechTree, Unlock};
+use tiles::{FloorKind, TileKind};
+
+use crate::recipes::{CAN_KIND, LAMP_KIND, PLASTEEL_KIND};
+
+/// Registers the nodes of the [`TechTree`].  Add it once, after
+/// [`research::ResearchPlugin`].
+pub struct TechTreePlugin;
+
+impl Plugin for TechTreePlugin {
+    fn build(&self, app: &mut App) {
+        let mut tree = app.world_mut().get_resource_or_init::<TechTree>();
+        let doors = tree.register(TechNode {
+            name: "Doors".into(),
+            points: 2,
+            inputs: vec![ItemCount::new(PLASTEEL_KIND, 2)],
+            requires: Vec::new(),
+            unlocks: vec![Unlock::Structure(TileKind::Door { open: false })],
+        });
+        tree.register(TechNode {
+            name: "Interior Design".into(),
+            points: 3,
+            inputs: Vec::new(),
+            requires: vec![doors],
+            unlocks: vec![Unlock::Floor(FloorKind::Carpet)],
+        });
+        tree.register(TechNode {
+            name: "Lighting".into(),
+            points: 4,
+            inputs: vec![ItemCount::new(CAN_KIND, 1)],
+            requires: Vec::new(),
+            unlocks: vec![Unlock::Item(LAMP_KIND)],
+        });
+    }
+}
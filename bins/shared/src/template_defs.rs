@@ -0,0 +1,462 @@
+//! Thing templates described in data files.
+//!
+//! Every `*.ron` file in the template directory (`things.template_dir`)
+//! holds one [`TemplateDef`]: its name and kind, how it looks (a primitive
+//! mesh or a scene), its collider, mass and body type, item flags, container
+//! capacity, required access level, melee stats, food value, whether it
+//! writes labels, the fluid it holds, gives or drains, the way it conveys
+//! items, display name and the item's catalog metadata (icon, volume, equip
+//! slots, hold offset).
+//! [`TemplatesPlugin`](crate::templates::TemplatesPlugin) registers them in
+//! the [`ThingRegistry`] next to the templates written in code, which remain
+//! the way to add things that need components the data format does not cover
+//! (creatures, lights, power, airlocks).
+//!
+//! ```ron
+//! (
+//!     name: "toolbox",
+//!     kind: 3,
+//!     display_name: Some("Toolbox"),
+//!     visual: Shape(shape: Cuboid(0.6, 0.3, 0.4), color: (0.8, 0.2, 0.2)),
+//!     collider: Some(Cuboid(0.3, 0.15, 0.2)),
+//!     item: true,
+//!     container: Some(6),
+//!     health: Some(25.0),
+//! )
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use access::{AccessLevel, AccessRestricted};
+use bevy::prelude::*;
+use items::{
+    Consumable, Container, Conveyor, Draggable, EquipSlot, FluidContainer, FluidDrain, FluidSource,
+    Grip, HoldOffset, Item, ItemCatalog, ItemKind, Labeler, MeleeStats, Stack,
+};
+use physics::{Collider, GameLayer, GravityScale, Mass, RigidBody};
+use serde::Deserialize;
+use things::{Health, ThingRegistry};
+
+/// A primitive shape.  The arguments are those of the Bevy primitive (for
+/// meshes) and the Avian collider constructor (for colliders) of the same
+/// name.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum ShapeDef {
+    Sphere(f32),
+    Cuboid(f32, f32, f32),
+    Cylinder(f32, f32),
+    Capsule(f32, f32),
+}
+
+impl ShapeDef {
+    pub fn mesh(self) -> Mesh {
+        match self {
+            ShapeDef::Sphere(radius) => Sphere::new(radius).into(),
+            ShapeDef::Cuboid(x, y, z) => Cuboid::new(x, y, z).into(),
+            ShapeDef::Cylinder(radius, height) => Cylinder::new(radius, height).into(),
+            ShapeDef::Capsule(radius, length) => Capsule3d::new(radius, length).into(),
+        }
+    }
+
+    pub fn collider(self) -> Collider {
+        match self {
+            ShapeDef::Sphere(radius) => Collider::sphere(radius),
+            ShapeDef::Cuboid(x, y, z) => Collider::cuboid(x, y, z),
+            ShapeDef::Cylinder(radius, height) => Collider::cylinder(radius, height),
+            ShapeDef::Capsule(radius, length) => Collider::capsule(radius, length),
+        }
+    }
+}
+
+/// How a thing looks.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum VisualDef {
+    /// A primitive mesh in a plain sRGB colour.
+    Shape {
+        shape: ShapeDef,
+        color: (f32, f32, f32),
+        #[serde(default)]
+        metallic: f32,
+    },
+    /// A scene asset, e.g. `"models/wrench.glb#Scene0"`.
+    Scene(String),
+}
+
+/// Physics body type of a thing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum BodyDef {
+    #[default]
+    Dynamic,
+    Static,
+    Kinematic,
+}
+
+impl From<BodyDef> for RigidBody {
+    fn from(body: BodyDef) -> Self {
+        match body {
+            BodyDef::Dynamic => RigidBody::Dynamic,
+            BodyDef::Static => RigidBody::Static,
+            BodyDef::Kinematic => RigidBody::Kinematic,
+        }
+    }
+}
+
+/// A thing template read from a data file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TemplateDef {
+    /// Template name, as used by the `"spawns"` map layer.
+    pub name: String,
+    pub kind: u16,
+    /// Name shown for the thing in menus and the inventory.
+    #[serde(default)]
+    pub display_name: Option<String>,
+    pub visual: VisualDef,
+    /// Dynamic bodies with a collider collide as items.
+    #[serde(default)]
+    pub collider: Option<ShapeDef>,
+    #[serde(default)]
+    pub body: BodyDef,
+    /// Mass in kilograms; computed from the collider when absent.
+    #[serde(default)]
+    pub mass: Option<f32>,
+    #[serde(default = "default_gravity_scale")]
+    pub gravity_scale: f32,
+    /// Can be picked up and stored.
+    #[serde(default)]
+    pub item: bool,
+    /// Can be dragged.
+    #[serde(default)]
+    pub draggable: bool,
+    /// Largest stack of this kind; stackable when present.
+    #[serde(default)]
+    pub max_stack: Option<u32>,
+    /// Asset path of the inventory icon.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Room taken up in a container, in litres.
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+    /// Slots the item can be equipped in besides the hands.
+    #[serde(default)]
+    pub equip_slots: Vec<EquipSlot>,
+    /// Where the item sits in a hand, e.g.
+    /// `hold_offset: (translation: (0.0, -0.2, 0.1), rotation: (90.0, 0.0, 0.0))`.
+    #[serde(default)]
+    pub hold_offset: HoldOffset,
+    /// Where the hands take hold of the item, e.g.
+    /// `grip: TwoHanded(primary: (0.0, 0.0, 0.25), secondary: (0.0, 0.0, -0.25))`.
+    #[serde(default)]
+    pub grip: Grip,
+    /// Slots of the thing's container, if it is one.
+    #[serde(default)]
+    pub container: Option<usize>,
+    #[serde(default)]
+    pub health: Option<f32>,
+    /// Access level needed to use the thing's container, or the door in its
+    /// cell.
+    #[serde(default)]
+    pub access: Option<AccessLevel>,
+    /// Reach, damage and cooldown when swung as a melee weapon.
+    #[serde(default)]
+    pub melee: Option<MeleeStats>,
+    /// Nutrition and hydration restored by eating or drinking the item.
+    #[serde(default)]
+    pub consumable: Option<Consumable>,
+    /// Writes custom labels on things while held.
+    #[serde(default)]
+    pub labeler: bool,
+    /// Fluid held, such as a bucket's or a tank's.
+    #[serde(default)]
+    pub fluid: Option<FluidContainer>,
+    /// Fluid given without end, such as a tap's.
+    #[serde(default)]
+    pub fluid_source: Option<FluidSource>,
+    /// Swallows any fluid poured into it.
+    #[serde(default)]
+    pub fluid_drain: bool,
+    /// Carries the loose items on its cell, such as a belt's.
+    #[serde(default)]
+    pub conveyor: Option<Conveyor>,
+}
+
+fn default_gravity_scale() -> f32 {
+    1.0
+}
+
+fn default_volume() -> f32 {
+    1.0
+}
+
+/// Handles the visual builder of a [`TemplateDef`] inserts.
+#[derive(Clone)]
+enum VisualHandles {
+    Mesh(Handle<Mesh>, Handle<StandardMaterial>),
+    Scene(Handle<Scene>),
+}
+
+impl TemplateDef {
+    /// The [`ItemCatalog`] entry of this template, if it describes an item.
+    pub fn item_kind(&self) -> Option<ItemKind> {
+        (self.item || self.draggable || self.max_stack.is_some()).then(|| ItemKind {
+            display_name: self
+                .display_name
+                .clone()
+                .unwrap_or_else(|| self.name.clone()),
+            icon: self.icon.clone(),
+            max_stack: self.max_stack.unwrap_or(1),
+            mass: self.mass,
+            volume: self.volume,
+            equip_slots: self.equip_slots.clone(),
+            hold_offset: self.hold_offset,
+            grip: self.grip,
+        })
+    }
+
+    /// Inserts the functional components described by this template.
+    fn apply(&self, entity: Entity, commands: &mut Commands) {
+        let mut entity = commands.entity(entity);
+        entity.insert((RigidBody::from(self.body), GravityScale(self.gravity_scale)));
+        if let Some(shape) = self.collider {
+            entity.insert(shape.collider());
+            if self.body == BodyDef::Dynamic {
+                entity.insert(GameLayer::item());
+            }
+        }
+        if let Some(mass) = self.mass {
+            entity.insert(Mass(mass));
+        }
+        if self.item {
+            entity.insert(Item);
+        }
+        if self.draggable {
+            entity.insert(Draggable);
+        }
+        if self.max_stack.is_some() {
+            entity.insert(Stack::new(1));
+        }
+        if let Some(capacity) = self.container {
+            entity.insert(Container::with_capacity(capacity));
+        }
+        if let Some(health) = self.health {
+            entity.insert(Health::new(health));
+        }
+        if let Some(required) = self.access {
+            entity.insert(AccessRestricted { required });
+        }
+        if let Some(melee) = self.melee {
+            entity.insert(melee);
+        }
+        if let Some(consumable) = self.consumable {
+            entity.insert(consumable);
+        }
+        if self.labeler {
+            entity.insert(Labeler);
+        }
+        if let Some(fluid) = self.fluid {
+            entity.insert(fluid);
+        }
+        if let Some(source) = self.fluid_source {
+            entity.insert(source);
+        }
+        if self.fluid_drain {
+            entity.insert(FluidDrain);
+        }
+        if let Some(conveyor) = self.conveyor {
+            entity.insert(conveyor);
+        }
+        if let Some(name) = &self.display_name {
+            entity.insert(Name::new(name.clone()));
+        }
+    }
+}
+
+/// Reads every `*.ron` template in `dir`, in file name order.  Files that
+/// cannot be read or parsed are logged and skipped.
+pub fn load_template_defs(dir: &Path) -> Vec<TemplateDef> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Templates: cannot read template directory {dir:?}: {e}");
+            return Vec::new();
+        }
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .filter_map(|path| {
+            let contents = std::fs::read_to_string(path)
+                .map_err(|e| error!("Templates: failed to read {path:?}: {e}"))
+                .ok()?;
+            ron::from_str(&contents)
+                .map_err(|e| error!("Templates: failed to parse {path:?}: {e}"))
+                .ok()
+        })
+        .collect()
+}
+
+/// Where the data-driven templates came from, and the kinds they were
+/// registered as.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct TemplateSource {
+    pub dir: PathBuf,
+    pub kinds: Vec<u16>,
+}
+
+/// Registers every template in `dir` and records them in [`TemplateSource`].
+pub fn load_templates(world: &mut World, dir: &Path) {
+    let mut kinds = Vec::new();
+    for def in load_template_defs(dir) {
+        let kind = def.kind;
+        if register_template_def(world, def) {
+            kinds.push(kind);
+        }
+    }
+    world.insert_resource(TemplateSource {
+        dir: dir.to_path_buf(),
+        kinds,
+    });
+}
+
+/// Replaces the data-driven templates with the current contents of their
+/// directory.  Things already spawned keep their components; only things
+/// spawned afterwards use the new definitions.
+pub fn reload_templates(world: &mut World) {
+    let Some(source) = world.remove_resource::<TemplateSource>() else {
+        return;
+    };
+    {
+        let mut registry = world.resource_mut::<ThingRegistry>();
+        for &kind in &source.kinds {
+            registry.unregister(kind);
+        }
+    }
+    load_templates(world, &source.dir);
+    let count = world.resource::<TemplateSource>().kinds.len();
+    info!(
+        "Templates: reloaded {count} templates from {:?}",
+        source.dir
+    );
+}
+
+/// Registers `def` in the [`ThingRegistry`], and in the [`ItemCatalog`] if
+/// it is an item.  A template whose name or kind is already taken is refused.
+/// Returns whether `def` was registered.
+pub fn register_template_def(world: &mut World, def: TemplateDef) -> bool {
+    let registry = world.resource::<ThingRegistry>();
+    if registry.kind_by_name(&def.name).is_some() || registry.name_by_kind(def.kind).is_some() {
+        error!(
+            "Templates: \"{}\" (kind {}) clashes with an existing template, skipping",
+            def.name, def.kind
+        );
+        return false;
+    }
+
+    let visual = match &def.visual {
+        VisualDef::Shape {
+            shape,
+            color,
+            metallic,
+        } => {
+            let mesh = world.resource_mut::<Assets<Mesh>>().add(shape.mesh());
+            let material = world
+                .resource_mut::<Assets<StandardMaterial>>()
+                .add(StandardMaterial {
+                    base_color: Color::srgb(color.0, color.1, color.2),
+                    metallic: *metallic,
+                    ..default()
+                });
+            VisualHandles::Mesh(mesh, material)
+        }
+        VisualDef::Scene(path) => VisualHandles::Scene(world.resource::<AssetServer>().load(path)),
+    };
+
+    if let Some(item_kind) = def.item_kind() {
+        world
+            .resource_mut::<ItemCatalog>()
+            .register(def.kind, item_kind);
+    }
+
+    debug!(
+        "Templates: registering \"{}\" as kind {}",
+        def.name, def.kind
+    );
+    world.resource_mut::<ThingRegistry>().register_named(
+        def.name.clone(),
+        def.kind,
+        move |entity, commands| match &visual {
+            VisualHandles::Mesh(mesh, material) => {
+                commands
+                    .entity(entity)
+                    .insert((Mesh3d(mesh.clone()), MeshMaterial3d(material.clone())));
+            }
+            VisualHandles::Scene(scene) => {
+                commands.entity(entity).insert(SceneRoot(scene.clone()));
+            }
+        },
+        move |entity, commands| def.apply(entity, commands),
+    );
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_def_parses_with_defaults() {
+        let def: TemplateDef = ron::from_str(
+            r#"(
+                name: "wrench",
+                kind: 40,
+                visual: Scene("models/wrench.glb#Scene0"),
+                collider: Some(Cylinder(0.05, 0.3)),
+                mass: Some(0.8),
+                item: true,
+                max_stack: Some(5),
+            )"#,
+        )
+        .expect("template parses");
+
+        assert_eq!(def.name, "wrench");
+        assert_eq!(def.body, BodyDef::Dynamic);
+        assert_eq!(def.gravity_scale, 1.0);
+        assert_eq!(def.collider, Some(ShapeDef::Cylinder(0.05, 0.3)));
+        assert_eq!(
+            def.visual,
+            VisualDef::Scene("models/wrench.glb#Scene0".into())
+        );
+        assert!(def.item && !def.draggable);
+        assert_eq!((def.container, def.display_name.clone()), (None, None));
+        assert_eq!(def.access, None);
+        assert_eq!((def.melee, def.consumable), (None, None));
+        assert!(!def.labeler);
+        assert_eq!((def.fluid, def.fluid_source), (None, None));
+        assert!(!def.fluid_drain && def.conveyor.is_none());
+
+        let item_kind = def.item_kind().expect("items get a catalog entry");
+        assert_eq!(item_kind.display_name, "wrench");
+        assert_eq!((item_kind.max_stack, item_kind.volume), (5, 1.0));
+        assert_eq!(item_kind.hold_offset, HoldOffset::default());
+        assert_eq!(item_kind.grip, Grip::default());
+    }
+
+    #[test]
+    fn shipped_templates_parse_with_their_grips() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../assets/things");
+        let defs = load_template_defs(&dir);
+        let count = std::fs::read_dir(&dir).expect("template dir").count();
+        assert_eq!(defs.len(), count, "every shipped template parses");
+
+        let grip = |name: &str| {
+            let def = defs.iter().find(|def| def.name == name).expect(name);
+            def.item_kind().expect("an item").grip
+        };
+        assert_eq!(grip("toolbox"), Grip::OneHanded([0.0, 0.15, 0.0]));
+        assert!(matches!(grip("plasteel"), Grip::TwoHanded { .. }));
+    }
+}
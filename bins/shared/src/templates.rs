@@ -1,12 +1,68 @@
+use std::path::Path;
+
+use ai::{Behavior, Brain, Npc, WanderTimer};
+use animation::{HoldIk, IkChain};
+use atmospherics::Vent;
 use bevy::prelude::*;
-use creatures::{Creature, MovementSpeed};
-use items::{Container, Item};
-use physics::{Collider, GravityScale, LockedAxes, Restitution, RigidBody};
-use things::{HAND_OFFSET, HandSide, HandSlot, InputDirection, ThingRegistry};
+use creatures::{Creature, MovementSpeed, Observer, Stamina};
+use interactions::{AirAlarm, AirlockController};
+use items::{HoldArm, Item, ItemCatalog, ItemKind};
+use lighting::LightSource;
+use physics::{Collider, GameLayer, GravityScale, LockedAxes, Restitution, RigidBody};
+use player::MinimapMarker;
+use power::{Cable, DoorMotor, PowerConsumer, PowerGenerator};
+use things::{
+    Delegable, GHOST_KIND, HAND_OFFSET, HandSide, HandSlot, Health, InputDirection,
+    MovementModifiers, MovementState, Needs, OBSERVER_KIND, ThingRegistry,
+};
+use tiles::Deck;
+
+use crate::template_defs::load_templates;
 
 pub const BALL_RADIUS: f32 = 0.3;
 
-pub struct TemplatesPlugin;
+/// Reach of a lamp's light, in tiles.
+pub const LAMP_LIGHT_RADIUS: f32 = 6.0;
+
+/// Reach of a powered floor light, in tiles.
+pub const FLOOR_LIGHT_RADIUS: f32 = 5.0;
+
+/// Power drawn by a floor light.
+pub const FLOOR_LIGHT_DEMAND: f32 = 50.0;
+
+/// Moles per second a powered vent pumps into its cell.
+pub const VENT_RATE: f32 = 5.0;
+
+/// Power drawn by a vent.
+pub const VENT_DEMAND: f32 = 30.0;
+
+/// Power drawn by a door motor.
+pub const DOOR_MOTOR_DEMAND: f32 = 20.0;
+
+/// Power fed into its network by a generator.
+pub const GENERATOR_OUTPUT: f32 = 500.0;
+
+/// Minimap dot colours of generators and airlock controllers.
+const GENERATOR_MARKER_COLOR: Color = Color::srgb(1.0, 0.85, 0.1);
+const AIRLOCK_MARKER_COLOR: Color = Color::srgb(0.2, 0.8, 0.9);
+
+/// Creature-local position of the right shoulder; the left one mirrors it.
+const SHOULDER_OFFSET: Vec3 = Vec3::new(0.38, 0.55, 0.0);
+
+/// Length of the upper arm and of the forearm.
+const ARM_SEGMENT: f32 = 0.3;
+
+/// Hit points of creatures and NPCs.
+pub const CREATURE_HEALTH: f32 = 100.0;
+
+/// Stamina of creatures and NPCs: five seconds of sprinting.
+pub const CREATURE_STAMINA: f32 = 100.0;
+
+/// Registers the thing templates: those written below in code, then the
+/// data-driven ones found in `template_dir` (see [`crate::template_defs`]).
+pub struct TemplatesPlugin {
+    pub template_dir: String,
+}
 
 impl Plugin for TemplatesPlugin {
     fn build(&self, app: &mut App) {
@@ -19,9 +75,18 @@ impl Plugin for TemplatesPlugin {
 
         let mut meshes = app.world_mut().resource_mut::<Assets<Mesh>>();
         let creature_mesh = meshes.add(Capsule3d::new(0.3, 1.0));
+        let arm_mesh = meshes.add(Capsule3d::new(0.06, ARM_SEGMENT - 0.12));
+        let npc_mesh = creature_mesh.clone();
+        let ghost_mesh = creature_mesh.clone();
         let ball_mesh = meshes.add(Sphere::new(BALL_RADIUS));
-        let can_mesh = meshes.add(Cylinder::new(0.15, 0.1));
-        let toolbox_mesh = meshes.add(Cuboid::new(0.6, 0.3, 0.4));
+        let lamp_mesh = meshes.add(Cylinder::new(0.12, 0.4));
+        let cable_mesh = meshes.add(Cuboid::new(0.25, 0.02, 0.25));
+        let generator_mesh = meshes.add(Cuboid::new(0.8, 1.0, 0.8));
+        let floor_light_mesh = meshes.add(Cylinder::new(0.2, 0.05));
+        let airlock_controller_mesh = meshes.add(Cuboid::new(0.3, 0.4, 0.1));
+        let air_alarm_mesh = meshes.add(Cuboid::new(0.25, 0.25, 0.1));
+        let vent_mesh = meshes.add(Cuboid::new(0.6, 0.05, 0.6));
+        let door_motor_mesh = meshes.add(Cuboid::new(0.2, 0.2, 0.2));
 
         let mut materials = app.world_mut().resource_mut::<Assets<StandardMaterial>>();
         let creature_mat = materials.add(StandardMaterial {
@@ -32,15 +97,48 @@ impl Plugin for TemplatesPlugin {
             base_color: Color::srgb(1.0, 0.8, 0.0),
             ..default()
         });
-        let can_mat = materials.add(StandardMaterial {
-            base_color: Color::srgb(0.7, 0.7, 0.75),
-            metallic: 0.8,
+        let npc_mat = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.3, 0.6, 0.4),
+            ..default()
+        });
+        let ghost_mat = materials.add(StandardMaterial {
+            base_color: Color::srgba(0.8, 0.9, 1.0, 0.35),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+        let lamp_mat = materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.95, 0.7),
+            emissive: LinearRgba::rgb(4.0, 3.6, 2.4),
+            ..default()
+        });
+
+        let cable_mat = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.6, 0.1, 0.1),
+            ..default()
+        });
+        let generator_mat = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.3, 0.35, 0.3),
+            metallic: 0.7,
+            ..default()
+        });
+        let floor_light_mat = lamp_mat.clone();
+        let airlock_controller_mat = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.9, 0.15, 0.1),
+            emissive: LinearRgba::rgb(1.8, 0.3, 0.2),
+            ..default()
+        });
+        let air_alarm_mat = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.2, 0.9, 0.3),
+            emissive: LinearRgba::rgb(0.4, 1.8, 0.6),
             ..default()
         });
-        let toolbox_mat = materials.add(StandardMaterial {
-            base_color: Color::srgb(0.8, 0.2, 0.2),
+        let vent_mat = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.5, 0.55, 0.6),
+            metallic: 0.6,
             ..default()
         });
+        let door_motor_mat = generator_mat.clone();
 
         let mut registry = app.world_mut().resource_mut::<ThingRegistry>();
 
@@ -48,13 +146,16 @@ impl Plugin for TemplatesPlugin {
         registry.register_named(
             "creature",
             0,
-            // Visual: mesh + material only.
+            // Visual: mesh, material and arms that reach for held items.
             move |entity, commands| {
                 debug!("Template kind 0 (creature) visual: applying to {entity:?}");
                 commands.entity(entity).insert((
                     Mesh3d(creature_mesh.clone()),
                     MeshMaterial3d(creature_mat.clone()),
                 ));
+                for side in [HandSide::Left, HandSide::Right] {
+                    spawn_arm(commands, entity, side, &arm_mesh, &creature_mat);
+                }
             },
             // Functional: physics, movement, hand slot.
             |entity, commands| {
@@ -63,10 +164,17 @@ impl Plugin for TemplatesPlugin {
                     Creature,
                     MovementSpeed::default(),
                     InputDirection::default(),
+                    MovementModifiers::default(),
+                    MovementState::default(),
+                    Deck::default(),
                     RigidBody::Dynamic,
                     Collider::capsule(0.3, 1.0),
+                    GameLayer::creature(),
                     LockedAxes::ROTATION_LOCKED.lock_translation_y(),
                     GravityScale(0.0),
+                    Health::new(CREATURE_HEALTH),
+                    Stamina::new(CREATURE_STAMINA),
+                    Needs::default(),
                 ));
                 commands.entity(entity).with_children(|parent| {
                     parent.spawn((
@@ -93,57 +201,331 @@ impl Plugin for TemplatesPlugin {
                 debug!("Template kind 1 (ball) functional: applying to {entity:?}");
                 commands.entity(entity).insert((
                     Collider::sphere(BALL_RADIUS),
+                    GameLayer::item(),
                     RigidBody::Dynamic,
                     GravityScale(1.0),
                     Restitution::new(0.8),
+                    Delegable,
                 ));
             },
         );
 
-        // Kind 2: Can — pickable item.
+        // Kinds 2 (can), 3 (toolbox), 4 (plasteel) and 6 (crate) are plain
+        // items described in `assets/things`.
+
+        // Kind 5: NPC — creature driven by the server-side AI.
         registry.register_named(
-            "can",
-            2,
+            "npc",
+            5,
             move |entity, commands| {
-                debug!("Template kind 2 (can) visual: applying to {entity:?}");
+                debug!("Template kind 5 (npc) visual: applying to {entity:?}");
                 commands
                     .entity(entity)
-                    .insert((Mesh3d(can_mesh.clone()), MeshMaterial3d(can_mat.clone())));
+                    .insert((Mesh3d(npc_mesh.clone()), MeshMaterial3d(npc_mat.clone())));
             },
             |entity, commands| {
-                debug!("Template kind 2 (can) functional: applying to {entity:?}");
+                debug!("Template kind 5 (npc) functional: applying to {entity:?}");
                 commands.entity(entity).insert((
-                    Collider::cylinder(0.15, 0.1),
+                    Creature,
+                    MovementSpeed { speed: 2.0 },
+                    InputDirection::default(),
+                    MovementModifiers::default(),
+                    MovementState::default(),
+                    Deck::default(),
                     RigidBody::Dynamic,
-                    GravityScale(1.0),
-                    Item,
-                    Name::new("Can"),
+                    Collider::capsule(0.3, 1.0),
+                    GameLayer::creature(),
+                    LockedAxes::ROTATION_LOCKED.lock_translation_y(),
+                    GravityScale(0.0),
+                ));
+                // Split in two: a single tuple would exceed the 15-component
+                // bundle limit.
+                commands.entity(entity).insert((
+                    Health::new(CREATURE_HEALTH),
+                    Stamina::new(CREATURE_STAMINA),
+                    Npc,
+                    Brain::default(),
+                    Behavior::default(),
+                    WanderTimer::default(),
+                ));
+            },
+        );
+
+        // Kind 7: Observer — invisible free-flying body for spectators.  No
+        // collider, so it passes through walls and is never a raycast hit.
+        registry.register_named(
+            "observer",
+            OBSERVER_KIND,
+            |entity, _commands| {
+                debug!("Template kind 7 (observer) visual: nothing to apply to {entity:?}");
+            },
+            |entity, commands| {
+                debug!("Template kind 7 (observer) functional: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    Observer,
+                    MovementSpeed { speed: 6.0 },
+                    InputDirection::default(),
+                    MovementModifiers::default(),
+                    Deck::default(),
+                    RigidBody::Kinematic,
+                    GravityScale(0.0),
                 ));
             },
         );
 
-        // Kind 3: Toolbox — pickable container.
+        // Kind 16: Ghost — a dead player's free-flying, see-through body.
+        // No collider, like the observer; only other ghosts are sent it.
         registry.register_named(
-            "toolbox",
-            3,
+            "ghost",
+            GHOST_KIND,
             move |entity, commands| {
-                debug!("Template kind 3 (toolbox) visual: applying to {entity:?}");
+                debug!("Template kind 16 (ghost) visual: applying to {entity:?}");
                 commands.entity(entity).insert((
-                    Mesh3d(toolbox_mesh.clone()),
-                    MeshMaterial3d(toolbox_mat.clone()),
+                    Mesh3d(ghost_mesh.clone()),
+                    MeshMaterial3d(ghost_mat.clone()),
                 ));
             },
             |entity, commands| {
-                debug!("Template kind 3 (toolbox) functional: applying to {entity:?}");
+                debug!("Template kind 16 (ghost) functional: applying to {entity:?}");
                 commands.entity(entity).insert((
-                    Collider::cuboid(0.3, 0.15, 0.2),
+                    Observer,
+                    MovementSpeed { speed: 6.0 },
+                    InputDirection::default(),
+                    MovementModifiers::default(),
+                    Deck::default(),
+                    RigidBody::Kinematic,
+                    GravityScale(0.0),
+                ));
+            },
+        );
+
+        // Kind 8: Lamp — portable light source.
+        registry.register_named(
+            "lamp",
+            8,
+            move |entity, commands| {
+                debug!("Template kind 8 (lamp) visual: applying to {entity:?}");
+                commands
+                    .entity(entity)
+                    .insert((Mesh3d(lamp_mesh.clone()), MeshMaterial3d(lamp_mat.clone())));
+            },
+            |entity, commands| {
+                debug!("Template kind 8 (lamp) functional: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    Collider::cylinder(0.12, 0.4),
+                    GameLayer::item(),
                     RigidBody::Dynamic,
                     GravityScale(1.0),
                     Item,
-                    Name::new("Toolbox"),
-                    Container::with_capacity(6),
+                    Name::new("Lamp"),
+                    LightSource::new(LAMP_LIGHT_RADIUS, 1.0),
                 ));
             },
         );
+
+        // Kind 9: Cable — one segment of a power network.
+        registry.register_named(
+            "cable",
+            9,
+            move |entity, commands| {
+                debug!("Template kind 9 (cable) visual: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    Mesh3d(cable_mesh.clone()),
+                    MeshMaterial3d(cable_mat.clone()),
+                ));
+            },
+            |entity, commands| {
+                debug!("Template kind 9 (cable) functional: applying to {entity:?}");
+                commands
+                    .entity(entity)
+                    .insert((RigidBody::Static, Cable, Name::new("Cable")));
+            },
+        );
+
+        // Kind 10: Generator — powers the cable network it stands on.
+        registry.register_named(
+            "generator",
+            10,
+            move |entity, commands| {
+                debug!("Template kind 10 (generator) visual: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    Mesh3d(generator_mesh.clone()),
+                    MeshMaterial3d(generator_mat.clone()),
+                    MinimapMarker(GENERATOR_MARKER_COLOR),
+                ));
+            },
+            |entity, commands| {
+                debug!("Template kind 10 (generator) functional: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    Collider::cuboid(0.4, 0.5, 0.4),
+                    RigidBody::Static,
+                    PowerGenerator {
+                        output: GENERATOR_OUTPUT,
+                    },
+                    Name::new("Generator"),
+                ));
+            },
+        );
+
+        // Kind 11: Floor light — fixed light that only shines while powered.
+        registry.register_named(
+            "floor_light",
+            11,
+            move |entity, commands| {
+                debug!("Template kind 11 (floor_light) visual: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    Mesh3d(floor_light_mesh.clone()),
+                    MeshMaterial3d(floor_light_mat.clone()),
+                ));
+            },
+            |entity, commands| {
+                debug!("Template kind 11 (floor_light) functional: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    RigidBody::Static,
+                    LightSource::new(FLOOR_LIGHT_RADIUS, 1.0),
+                    PowerConsumer::new(FLOOR_LIGHT_DEMAND),
+                    Name::new("Floor light"),
+                ));
+            },
+        );
+
+        // Kind 12: Airlock controller — cycles the airlock whose chamber it
+        // stands in.  Its colour follows the replicated `AirlockState`.
+        registry.register_named(
+            "airlock_controller",
+            12,
+            move |entity, commands| {
+                debug!("Template kind 12 (airlock_controller) visual: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    Mesh3d(airlock_controller_mesh.clone()),
+                    MeshMaterial3d(airlock_controller_mat.clone()),
+                    MinimapMarker(AIRLOCK_MARKER_COLOR),
+                ));
+            },
+            |entity, commands| {
+                debug!("Template kind 12 (airlock_controller) functional: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    RigidBody::Static,
+                    AirlockController,
+                    Name::new("Airlock controller"),
+                ));
+            },
+        );
+
+        // Kind 17: Air alarm — watches the pressure and temperature of the
+        // room it is in.  Its colour follows the replicated `AirAlarmState`.
+        registry.register_named(
+            "air_alarm",
+            17,
+            move |entity, commands| {
+                debug!("Template kind 17 (air_alarm) visual: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    Mesh3d(air_alarm_mesh.clone()),
+                    MeshMaterial3d(air_alarm_mat.clone()),
+                ));
+            },
+            |entity, commands| {
+                debug!("Template kind 17 (air_alarm) functional: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    RigidBody::Static,
+                    AirAlarm::default(),
+                    Name::new("Air alarm"),
+                ));
+            },
+        );
+
+        // Kind 23: Vent — pumps air into its cell while powered.
+        registry.register_named(
+            "vent",
+            23,
+            move |entity, commands| {
+                debug!("Template kind 23 (vent) visual: applying to {entity:?}");
+                commands
+                    .entity(entity)
+                    .insert((Mesh3d(vent_mesh.clone()), MeshMaterial3d(vent_mat.clone())));
+            },
+            |entity, commands| {
+                debug!("Template kind 23 (vent) functional: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    RigidBody::Static,
+                    Vent::new(VENT_RATE),
+                    PowerConsumer::new(VENT_DEMAND),
+                    Name::new("Vent"),
+                ));
+            },
+        );
+
+        // Kind 24: Door motor — placed in a doorway, the door only moves
+        // while it is powered.
+        registry.register_named(
+            "door_motor",
+            24,
+            move |entity, commands| {
+                debug!("Template kind 24 (door_motor) visual: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    Mesh3d(door_motor_mesh.clone()),
+                    MeshMaterial3d(door_motor_mat.clone()),
+                ));
+            },
+            |entity, commands| {
+                debug!("Template kind 24 (door_motor) functional: applying to {entity:?}");
+                commands.entity(entity).insert((
+                    RigidBody::Static,
+                    DoorMotor,
+                    PowerConsumer::new(DOOR_MOTOR_DEMAND),
+                    Name::new("Door motor"),
+                ));
+            },
+        );
+
+        app.init_resource::<ItemCatalog>();
+        app.world_mut()
+            .resource_mut::<ItemCatalog>()
+            .register(8, ItemKind::named("Lamp"));
+        load_templates(app.world_mut(), Path::new(&self.template_dir));
     }
 }
+
+/// Spawns one arm of `creature`: a [`HoldArm`] chain at the creature's
+/// origin, with an upper arm and a forearm hanging from the shoulder.
+fn spawn_arm(
+    commands: &mut Commands,
+    creature: Entity,
+    side: HandSide,
+    mesh: &Handle<Mesh>,
+    material: &Handle<StandardMaterial>,
+) {
+    let shoulder = match side {
+        HandSide::Left => SHOULDER_OFFSET * Vec3::new(-1.0, 1.0, 1.0),
+        HandSide::Right => SHOULDER_OFFSET,
+    };
+    let arm = commands
+        .spawn((
+            HoldArm { side },
+            HoldIk::default(),
+            Transform::default(),
+            Visibility::default(),
+            ChildOf(creature),
+        ))
+        .id();
+    let mut segment = |parent, translation| {
+        commands
+            .spawn((
+                Transform::from_translation(translation),
+                Visibility::default(),
+                ChildOf(parent),
+            ))
+            .with_child((
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::from_xyz(0.0, -ARM_SEGMENT / 2.0, 0.0),
+            ))
+            .id()
+    };
+    let upper = segment(arm, shoulder);
+    let fore = segment(upper, Vec3::NEG_Y * ARM_SEGMENT);
+    let hand = commands
+        .spawn((Transform::from_xyz(0.0, -ARM_SEGMENT, 0.0), ChildOf(fore)))
+        .id();
+    commands.entity(arm).insert(IkChain::new(upper, fore, hand));
+}
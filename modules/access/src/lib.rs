@@ -0,0 +1,194 @@
+//! Who may open which door and which container.
+//!
+//! Things carrying [`AccessRestricted`] may only be used by creatures that
+//! hold an [`IdCard`] granting the required [`AccessLevel`]: in a hand, or
+//! in a container they hold in a hand (a wallet, a backpack).  Doors are
+//! tiles rather than things, so a door is restricted by a thing carrying
+//! [`AccessRestricted`] that stands in its cell (a door lock); containers
+//! standing in a doorway do not lock it.  Systems that honour requests on
+//! the server ask [`AccessCheck`] before opening, taking or storing.
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use items::Container;
+use serde::Deserialize;
+use things::{HandSlot, cell_of};
+use wincode::{SchemaRead, SchemaWrite};
+
+/// A clearance doors, lockers and machines may require.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Reflect, SchemaRead, SchemaWrite,
+)]
+pub enum AccessLevel {
+    Engineering,
+    Medical,
+    Command,
+}
+
+/// An ID item and the access levels it grants to whoever holds it.
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct IdCard(pub Vec<AccessLevel>);
+
+impl IdCard {
+    pub fn grants(&self, level: AccessLevel) -> bool {
+        self.0.contains(&level)
+    }
+}
+
+/// Only creatures holding an [`IdCard`] granting `required` may use this
+/// container, or the door in this thing's cell.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct AccessRestricted {
+    pub required: AccessLevel,
+}
+
+/// Checks [`AccessRestricted`] things against the ID cards an actor holds.
+#[derive(SystemParam)]
+pub struct AccessCheck<'w, 's> {
+    restricted: Query<
+        'w,
+        's,
+        (
+            &'static AccessRestricted,
+            Option<&'static Transform>,
+            Has<Container>,
+        ),
+    >,
+    children: Query<'w, 's, &'static Children>,
+    hands: Query<'w, 's, &'static Container, With<HandSlot>>,
+    containers: Query<'w, 's, &'static Container>,
+    cards: Query<'w, 's, &'static IdCard>,
+}
+
+impl AccessCheck<'_, '_> {
+    /// Whether `actor` holds an [`IdCard`] granting `level`, in a hand or in
+    /// a container held in a hand.
+    pub fn holds(&self, actor: Entity, level: AccessLevel) -> bool {
+        let grants = |item: Entity| self.cards.get(item).is_ok_and(|card| card.grants(level));
+        let Ok(children) = self.children.get(actor) else {
+            return false;
+        };
+        children
+            .iter()
+            .filter_map(|child| self.hands.get(child).ok())
+            .flat_map(|hand| hand.slots.iter().flatten().copied())
+            .any(|held| {
+                grants(held)
+                    || self
+                        .containers
+                        .get(held)
+                        .is_ok_and(|inner| inner.slots.iter().flatten().any(|&item| grants(item)))
+            })
+    }
+
+    /// Whether `actor` may use `target`.  Refusals carry the level that was
+    /// missing.
+    pub fn entity(&self, actor: Entity, target: Entity) -> Result<(), AccessLevel> {
+        match self.restricted.get(target) {
+            Ok((restriction, ..)) if !self.holds(actor, restriction.required) => {
+                Err(restriction.required)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether `actor` may open, close or change the door at `cell`.
+    /// Refusals carry the first level that was missing.
+    pub fn cell(&self, actor: Entity, cell: IVec2) -> Result<(), AccessLevel> {
+        self.restricted
+            .iter()
+            .filter(|(_, transform, is_container)| {
+                !is_container && transform.is_some_and(|t| cell_of(t.translation) == cell)
+            })
+            .map(|(restriction, ..)| restriction.required)
+            .find(|&required| !self.holds(actor, required))
+            .map_or(Ok(()), Err)
+    }
+}
+
+/// Registers the access components for reflection.  The checks themselves
+/// run in the systems that honour requests, through [`AccessCheck`].
+pub struct AccessPlugin;
+
+impl Plugin for AccessPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<IdCard>();
+        app.register_type::<AccessRestricted>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use things::HandSide;
+
+    #[test]
+    fn id_cards_count_in_hands_and_held_containers() {
+        let mut world = World::new();
+        let actor = world.spawn_empty().id();
+        let hand = world
+            .spawn((
+                HandSlot {
+                    side: HandSide::Left,
+                },
+                Container::with_capacity(1),
+                ChildOf(actor),
+            ))
+            .id();
+        let locker = world
+            .spawn((
+                AccessRestricted {
+                    required: AccessLevel::Medical,
+                },
+                Container::with_capacity(4),
+            ))
+            .id();
+        let door_lock = world
+            .spawn((
+                AccessRestricted {
+                    required: AccessLevel::Engineering,
+                },
+                Transform::from_xyz(3.0, 0.0, 2.0),
+            ))
+            .id();
+        let check = |world: &mut World| {
+            world
+                .run_system_once(move |access: AccessCheck| {
+                    (
+                        access.entity(actor, locker),
+                        access.cell(actor, IVec2::new(3, 2)),
+                        access.cell(actor, IVec2::new(0, 0)),
+                    )
+                })
+                .unwrap()
+        };
+        assert_eq!(
+            check(&mut world),
+            (
+                Err(AccessLevel::Medical),
+                Err(AccessLevel::Engineering),
+                Ok(())
+            )
+        );
+
+        // A card lying in the locker grants nothing; one in a wallet held in
+        // a hand does.
+        let card = world.spawn(IdCard(vec![AccessLevel::Medical])).id();
+        world.get_mut::<Container>(locker).unwrap().slots[0] = Some(card);
+        assert_eq!(check(&mut world).0, Err(AccessLevel::Medical));
+        let wallet = world.spawn(Container::with_capacity(2)).id();
+        world.get_mut::<Container>(locker).unwrap().slots[0] = None;
+        world.get_mut::<Container>(wallet).unwrap().slots[1] = Some(card);
+        world.get_mut::<Container>(hand).unwrap().slots[0] = Some(wallet);
+        assert_eq!(
+            check(&mut world),
+            (Ok(()), Err(AccessLevel::Engineering), Ok(()))
+        );
+
+        world.entity_mut(door_lock).despawn();
+        assert_eq!(check(&mut world).1, Ok(()));
+    }
+}
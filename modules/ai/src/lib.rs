@@ -0,0 +1,301 @@
+use atmospherics::GasGrid;
+use bevy::prelude::*;
+use network::{ControlledByClient, Server, Spectator};
+use things::{InputDirection, SpatialIndex, cell_of};
+
+/// Marker component for creatures driven by the server-side AI instead of a
+/// client.  NPCs move through the regular creature movement path: the AI only
+/// writes their [`InputDirection`], and their position replicates like any
+/// other thing.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct Npc;
+
+/// Per-NPC tuning for the behavior state machine.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Brain {
+    /// Player creatures closer than this (in world units) are followed.
+    pub follow_radius: f32,
+    /// A following NPC stops once it is this close to its target.
+    pub stop_distance: f32,
+    /// Gas pressure below which the NPC flees towards higher pressure.
+    pub flee_below: f32,
+    /// Seconds between switching between idling and wandering.
+    pub wander_interval: f32,
+}
+
+impl Default for Brain {
+    fn default() -> Self {
+        Self {
+            follow_radius: 5.0,
+            stop_distance: 1.5,
+            flee_below: 50.0,
+            wander_interval: 3.0,
+        }
+    }
+}
+
+/// Current state of an NPC's behavior state machine, re-evaluated every frame
+/// by [`update_behaviors`] in priority order: flee, follow, wander/idle.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default, Reflect)]
+#[reflect(Component)]
+pub enum Behavior {
+    /// Standing still until the wander timer runs out.
+    #[default]
+    Idle,
+    /// Walking in a fixed horizontal direction until the wander timer runs out.
+    Wander { direction: Vec3 },
+    /// Walking towards the target creature.
+    Follow { target: Entity },
+    /// Walking up the pressure gradient, away from a breach.
+    Flee,
+}
+
+/// Seconds left before an idling or wandering NPC switches to the other state.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct WanderTimer(pub f32);
+
+/// Small deterministic random source for wander directions.
+///
+/// Xorshift is plenty for picking headings and keeps the module free of an
+/// external RNG dependency.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct AiRng(pub u64);
+
+impl Default for AiRng {
+    fn default() -> Self {
+        Self(0x9E37_79B9_7F4A_7C15)
+    }
+}
+
+impl AiRng {
+    /// Next value in `0.0..1.0`.
+    pub fn next_f32(&mut self) -> f32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        (x >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Random unit vector in the horizontal (XZ) plane.
+    pub fn next_heading(&mut self) -> Vec3 {
+        let angle = self.next_f32() * std::f32::consts::TAU;
+        Vec3::new(angle.cos(), 0.0, angle.sin())
+    }
+}
+
+/// Server system that picks each NPC's [`Behavior`]:
+///
+/// 1. **Flee** if the pressure on the NPC's cell is below [`Brain::flee_below`].
+/// 2. **Follow** the nearest player creature within [`Brain::follow_radius`],
+///    looked up through the [`SpatialIndex`].
+/// 3. Otherwise alternate between **Idle** and **Wander** every
+///    [`Brain::wander_interval`] seconds, picking a fresh heading each time.
+#[allow(clippy::type_complexity)]
+fn update_behaviors(
+    time: Res<Time>,
+    mut rng: ResMut<AiRng>,
+    gas_grid: Option<Res<GasGrid>>,
+    mut npcs: Query<(&Transform, &Brain, &mut Behavior, &mut WanderTimer), With<Npc>>,
+    spatial: Res<SpatialIndex>,
+    players: Query<(), (With<ControlledByClient>, Without<Npc>, Without<Spectator>)>,
+) {
+    for (transform, brain, mut behavior, mut timer) in npcs.iter_mut() {
+        let position = transform.translation;
+
+        let low_pressure = gas_grid
+            .as_ref()
+            .and_then(|grid| grid.pressure_at(cell_of(position)))
+            .is_some_and(|pressure| pressure < brain.flee_below);
+        if low_pressure {
+            behavior.set_if_neq(Behavior::Flee);
+            continue;
+        }
+
+        let nearest_player = spatial
+            .query_within(position, brain.follow_radius)
+            .filter(|&(entity, _)| players.contains(entity))
+            .map(|(entity, p)| (entity, p.distance(position)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((target, _)) = nearest_player {
+            behavior.set_if_neq(Behavior::Follow { target });
+            continue;
+        }
+
+        timer.0 -= time.delta_secs();
+        let roaming = matches!(*behavior, Behavior::Idle | Behavior::Wander { .. });
+        if roaming && timer.0 > 0.0 {
+            continue;
+        }
+        timer.0 = brain.wander_interval;
+        *behavior = match *behavior {
+            Behavior::Wander { .. } => Behavior::Idle,
+            _ => Behavior::Wander {
+                direction: rng.next_heading(),
+            },
+        };
+    }
+}
+
+/// Server system that turns each NPC's [`Behavior`] into an [`InputDirection`]
+/// for the creature movement system to apply.
+fn steer_npcs(
+    gas_grid: Option<Res<GasGrid>>,
+    mut npcs: Query<(&Transform, &Brain, &Behavior, &mut InputDirection), With<Npc>>,
+    targets: Query<&Transform>,
+) {
+    for (transform, brain, behavior, mut input) in npcs.iter_mut() {
+        let position = transform.translation;
+        let direction = match *behavior {
+            Behavior::Idle => Vec3::ZERO,
+            Behavior::Wander { direction } => direction,
+            Behavior::Follow { target } => targets
+                .get(target)
+                .ok()
+                .map(|t| (t.translation - position).with_y(0.0))
+                .filter(|offset| offset.length() > brain.stop_distance)
+                .map_or(Vec3::ZERO, Vec3::normalize_or_zero),
+            // The gradient points towards increasing pressure, i.e. away
+            // from the breach that is draining this cell.
+            Behavior::Flee => gas_grid
+                .as_ref()
+                .map(|grid| grid.pressure_gradient_at(cell_of(position)))
+                .map_or(Vec3::ZERO, |gradient| {
+                    Vec3::new(gradient.x, 0.0, gradient.y).normalize_or_zero()
+                }),
+        };
+        if input.0 != direction {
+            input.0 = direction;
+        }
+    }
+}
+
+/// Plugin that runs the NPC behavior state machine on the server.
+///
+/// NPCs are ordinary things spawned from a template that adds [`Npc`],
+/// [`Brain`], [`Behavior`] and [`WanderTimer`] next to the creature
+/// components.  Clients register the types but never run the AI; they only
+/// see the replicated movement.
+pub struct AiPlugin<S: States + Copy> {
+    state: S,
+}
+
+impl<S: States + Copy> AiPlugin<S> {
+    /// Creates the plugin gated on `state`.
+    pub fn in_state(state: S) -> Self {
+        Self { state }
+    }
+}
+
+impl<S: States + Copy> Plugin for AiPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Npc>();
+        app.register_type::<Brain>();
+        app.register_type::<Behavior>();
+        app.register_type::<WanderTimer>();
+        app.init_resource::<AiRng>();
+        app.add_systems(
+            Update,
+            (update_behaviors, steer_npcs)
+                .chain()
+                .run_if(in_state(self.state))
+                .run_if(resource_exists::<Server>),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<AiRng>();
+        app.init_resource::<SpatialIndex>();
+        app.add_systems(PreUpdate, things::update_spatial_index);
+        app.add_systems(Update, (update_behaviors, steer_npcs).chain());
+        app
+    }
+
+    fn spawn_npc(app: &mut App, position: Vec3) -> Entity {
+        app.world_mut()
+            .spawn((
+                Npc,
+                Brain::default(),
+                Behavior::default(),
+                WanderTimer::default(),
+                InputDirection::default(),
+                Transform::from_translation(position),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn npc_follows_nearby_player_and_stops_when_close() {
+        let mut app = test_app();
+        let npc = spawn_npc(&mut app, Vec3::new(0.0, 0.81, 0.0));
+        let player = app
+            .world_mut()
+            .spawn((
+                things::Thing { kind: 0 },
+                ControlledByClient(network::ClientId(1)),
+                Transform::from_xyz(3.0, 0.81, 0.0),
+            ))
+            .id();
+
+        app.update();
+        assert_eq!(
+            app.world().get::<Behavior>(npc),
+            Some(&Behavior::Follow { target: player })
+        );
+        let input = app.world().get::<InputDirection>(npc).unwrap().0;
+        assert!((input - Vec3::X).length() < 1e-5, "got {input:?}");
+
+        app.world_mut()
+            .entity_mut(player)
+            .insert(Transform::from_xyz(1.0, 0.81, 0.0));
+        app.update();
+        assert_eq!(
+            app.world().get::<InputDirection>(npc).unwrap().0,
+            Vec3::ZERO
+        );
+    }
+
+    #[test]
+    fn npc_wanders_without_players() {
+        let mut app = test_app();
+        let npc = spawn_npc(&mut app, Vec3::ZERO);
+
+        app.update();
+        let Some(&Behavior::Wander { direction }) = app.world().get::<Behavior>(npc) else {
+            panic!(
+                "expected Wander, got {:?}",
+                app.world().get::<Behavior>(npc)
+            );
+        };
+        assert!((direction.length() - 1.0).abs() < 1e-5);
+        assert_eq!(direction.y, 0.0);
+        assert_eq!(app.world().get::<InputDirection>(npc).unwrap().0, direction);
+    }
+
+    #[test]
+    fn npc_flees_up_the_pressure_gradient() {
+        let mut app = test_app();
+        let mut grid = GasGrid::new(4, 1);
+        for x in 0..4 {
+            grid.set_moles(IVec2::new(x, 0), x as f32 * 20.0);
+        }
+        app.insert_resource(grid);
+        let npc = spawn_npc(&mut app, Vec3::new(1.0, 0.81, 0.0));
+
+        app.update();
+        assert_eq!(app.world().get::<Behavior>(npc), Some(&Behavior::Flee));
+        let input = app.world().get::<InputDirection>(npc).unwrap().0;
+        assert!((input - Vec3::X).length() < 1e-5, "got {input:?}");
+    }
+}
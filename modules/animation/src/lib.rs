@@ -0,0 +1,288 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystems;
+
+/// Seconds a [`HoldIk`] takes to blend fully in or out.
+pub const HOLD_BLEND_SECONDS: f32 = 0.2;
+
+/// Slack kept between a chain's reach and its full length, so a straightened
+/// chain still has a bend direction.
+const REACH_EPSILON: f32 = 1e-3;
+
+/// A two-bone chain (e.g. upper arm, forearm and hand) solved by [`solve_ik`].
+///
+/// `root` is a child of the entity carrying the chain, `mid` a child of
+/// `root` and `tip` a child of `mid`.  The bones' translations give the
+/// segment lengths; only the root and mid rotations are written.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct IkChain {
+    pub root: Entity,
+    pub mid: Entity,
+    pub tip: Entity,
+    /// Direction the mid joint bends toward, in the chain entity's frame.
+    pub pole: Vec3,
+    /// Local rotations of the root and mid bones while the chain is not
+    /// reaching for anything.
+    pub rest: [Quat; 2],
+}
+
+impl IkChain {
+    /// A chain at rest along its bones' translations, bending backward and
+    /// down like an elbow.
+    pub fn new(root: Entity, mid: Entity, tip: Entity) -> Self {
+        Self {
+            root,
+            mid,
+            tip,
+            pole: Vec3::new(0.0, -1.0, 1.0).normalize(),
+            rest: [Quat::IDENTITY; 2],
+        }
+    }
+}
+
+/// Makes an [`IkChain`] reach for a held item.
+///
+/// Whoever knows what is held sets `active` and `target`; [`blend_hold_ik`]
+/// eases `weight` toward 1 while active and back to 0 otherwise, so the
+/// chain blends between its rest pose and the reach instead of snapping.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct HoldIk {
+    pub active: bool,
+    /// Where the tip reaches, in the chain entity's frame.
+    pub target: Vec3,
+    /// How far the pose is blended from rest (0) toward the target (1).
+    pub weight: f32,
+}
+
+/// Eases every [`HoldIk`] weight toward its `active` flag over
+/// [`HOLD_BLEND_SECONDS`].
+pub fn blend_hold_ik(time: Res<Time>, mut holds: Query<&mut HoldIk>) {
+    let step = time.delta_secs() / HOLD_BLEND_SECONDS;
+    for mut hold in holds.iter_mut() {
+        let goal = if hold.active { 1.0 } else { 0.0 };
+        if hold.weight != goal {
+            hold.weight = if goal > hold.weight {
+                (hold.weight + step).min(goal)
+            } else {
+                (hold.weight - step).max(goal)
+            };
+        }
+    }
+}
+
+/// Poses each [`IkChain`] with a [`HoldIk`]: its rest pose blended by the
+/// hold's weight toward the pose that puts the tip on the target.
+///
+/// Registered in `PostUpdate` before transform propagation, so the solved
+/// pose is drawn in the same frame.
+pub fn solve_ik(chains: Query<(&IkChain, &HoldIk)>, mut bones: Query<&mut Transform>) {
+    for (chain, hold) in chains.iter() {
+        let Ok(tip) = bones.get(chain.tip).map(|t| t.translation) else {
+            continue;
+        };
+        let Ok([mut root, mut mid]) = bones.get_many_mut([chain.root, chain.mid]) else {
+            continue;
+        };
+        let [root_rest, mid_rest] = chain.rest;
+        let (root_pose, mid_pose) = if hold.weight > 0.0 {
+            let a = root.translation;
+            let b = a + root_rest * mid.translation;
+            let c = b + root_rest * mid_rest * tip;
+            let (root_turn, mid_turn) = solve_two_bone(a, b, c, hold.target, chain.pole);
+            let root_solved = root_turn * root_rest;
+            let mid_solved = root_solved.inverse() * mid_turn * root_turn * root_rest * mid_rest;
+            (
+                root_rest.slerp(root_solved, hold.weight),
+                mid_rest.slerp(mid_solved, hold.weight),
+            )
+        } else {
+            (root_rest, mid_rest)
+        };
+        if root.rotation != root_pose {
+            root.rotation = root_pose;
+        }
+        if mid.rotation != mid_pose {
+            mid.rotation = mid_pose;
+        }
+    }
+}
+
+/// Bends the chain with root at `a`, joint at `b` and tip at `c` so the tip
+/// lands on `target` (or as close as the chain reaches), the joint bending
+/// toward `pole`.
+///
+/// Returns the rotation to apply to the root bone, then the one to apply to
+/// the joint once the root has turned, both in the frame of the points.
+pub fn solve_two_bone(a: Vec3, b: Vec3, c: Vec3, target: Vec3, pole: Vec3) -> (Quat, Quat) {
+    let upper = a.distance(b);
+    let lower = b.distance(c);
+    if upper < REACH_EPSILON || lower < REACH_EPSILON {
+        return (Quat::IDENTITY, Quat::IDENTITY);
+    }
+    let to_target = target - a;
+    let Some(dir) = to_target.try_normalize() else {
+        return (Quat::IDENTITY, Quat::IDENTITY);
+    };
+    let shortest = (upper - lower).abs() + REACH_EPSILON;
+    let longest = (upper + lower - REACH_EPSILON).max(shortest);
+    let reach = to_target.length().clamp(shortest, longest);
+    let bend = (pole - dir * pole.dot(dir))
+        .try_normalize()
+        .unwrap_or_else(|| dir.any_orthonormal_vector());
+
+    // Law of cosines for the angle at the root between the reach and the
+    // upper bone.
+    let cos =
+        ((upper * upper + reach * reach - lower * lower) / (2.0 * upper * reach)).clamp(-1.0, 1.0);
+    let sin = (1.0 - cos * cos).sqrt();
+    let joint = a + upper * (dir * cos + bend * sin);
+    let tip = a + dir * reach;
+
+    let root_turn = Quat::from_rotation_arc((b - a) / upper, (joint - a) / upper);
+    let turned_tip = a + root_turn * (c - a);
+    let mid_turn = Quat::from_rotation_arc((turned_tip - joint) / lower, (tip - joint) / lower);
+    (root_turn, mid_turn)
+}
+
+/// Blends and solves [`HoldIk`] chains.  Add it only where the poses are
+/// drawn; headless servers leave it out.
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<IkChain>();
+        app.register_type::<HoldIk>();
+        app.add_systems(
+            PostUpdate,
+            (blend_hold_ik, solve_ik)
+                .chain()
+                .before(TransformSystems::Propagate),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, TransformPlugin, AnimationPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                FRAME,
+            )));
+        app
+    }
+
+    /// Spawns an arm hanging down from a shoulder, in two 0.3 m segments.
+    fn spawn_arm(app: &mut App, hold: HoldIk) -> (Entity, Entity) {
+        let world = app.world_mut();
+        let holder = world.spawn(Transform::default()).id();
+        let root = world
+            .spawn((Transform::from_xyz(0.3, 0.5, 0.0), ChildOf(holder)))
+            .id();
+        let mid = world
+            .spawn((Transform::from_xyz(0.0, -0.3, 0.0), ChildOf(root)))
+            .id();
+        let tip = world
+            .spawn((Transform::from_xyz(0.0, -0.3, 0.0), ChildOf(mid)))
+            .id();
+        world
+            .entity_mut(holder)
+            .insert((IkChain::new(root, mid, tip), hold));
+        (holder, tip)
+    }
+
+    fn tip_position(app: &App, tip: Entity) -> Vec3 {
+        app.world()
+            .get::<GlobalTransform>(tip)
+            .expect("tip has a GlobalTransform")
+            .translation()
+    }
+
+    fn run_frames(app: &mut App, frames: u32) {
+        for _ in 0..frames {
+            app.update();
+        }
+    }
+
+    #[test]
+    fn two_bone_solve_reaches_the_target_bending_toward_the_pole() {
+        let (a, b, c) = (
+            Vec3::ZERO,
+            Vec3::new(0.0, -0.3, 0.0),
+            Vec3::new(0.0, -0.6, 0.0),
+        );
+        let target = Vec3::new(0.2, -0.1, -0.3);
+        let pole = Vec3::new(0.0, -1.0, 1.0);
+        let (root_turn, mid_turn) = solve_two_bone(a, b, c, target, pole);
+
+        let joint = a + root_turn * (b - a);
+        let tip = joint + mid_turn * root_turn * (c - b);
+        assert!(tip.distance(target) < 1e-3, "tip at {tip}, target {target}");
+        assert!(((joint - a).length() - 0.3).abs() < 1e-4);
+        let off_line =
+            (joint - a) - (target - a).normalize() * (joint - a).dot((target - a).normalize());
+        assert!(off_line.dot(pole) > 0.0, "joint must bend toward the pole");
+    }
+
+    #[test]
+    fn out_of_reach_targets_straighten_the_chain_toward_them() {
+        let (a, b, c) = (
+            Vec3::ZERO,
+            Vec3::new(0.0, -0.3, 0.0),
+            Vec3::new(0.0, -0.6, 0.0),
+        );
+        let target = Vec3::new(0.0, 0.0, -2.0);
+        let (root_turn, mid_turn) = solve_two_bone(a, b, c, target, Vec3::NEG_Y);
+
+        let tip = a + root_turn * (b - a) + mid_turn * root_turn * (c - b);
+        assert!(
+            tip.distance(Vec3::new(0.0, 0.0, -0.6)) < 1e-2,
+            "tip at {tip}"
+        );
+    }
+
+    #[test]
+    fn hold_blends_in_to_the_target_and_back_out_to_rest() {
+        let mut app = test_app();
+        let target = Vec3::new(0.4, 0.3, -0.3);
+        let (holder, tip) = spawn_arm(
+            &mut app,
+            HoldIk {
+                active: true,
+                target,
+                weight: 0.0,
+            },
+        );
+        app.update();
+        let rest = Vec3::new(0.3, -0.1, 0.0);
+
+        run_frames(&mut app, 3);
+        let blending = app.world().get::<HoldIk>(holder).unwrap().weight;
+        assert!(blending > 0.0 && blending < 1.0, "weight {blending}");
+        assert!(tip_position(&app, tip).distance(target) > 1e-2);
+
+        run_frames(&mut app, (HOLD_BLEND_SECONDS / FRAME) as u32 + 2);
+        assert_eq!(app.world().get::<HoldIk>(holder).unwrap().weight, 1.0);
+        assert!(
+            tip_position(&app, tip).distance(target) < 1e-3,
+            "tip at {}, target {target}",
+            tip_position(&app, tip)
+        );
+
+        app.world_mut().get_mut::<HoldIk>(holder).unwrap().active = false;
+        run_frames(&mut app, (HOLD_BLEND_SECONDS / FRAME) as u32 + 2);
+        assert_eq!(app.world().get::<HoldIk>(holder).unwrap().weight, 0.0);
+        assert!(
+            tip_position(&app, tip).distance(rest) < 1e-4,
+            "tip at {}, rest {rest}",
+            tip_position(&app, tip)
+        );
+    }
+}
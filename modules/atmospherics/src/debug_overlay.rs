@@ -1,7 +1,8 @@
 use bevy::prelude::*;
+use input::{Action, ActionInput};
 use tiles::{TileGrid, TileKind, TileMutated};
 
-use crate::GasGrid;
+use crate::{GasDisplay, GasGrid};
 
 /// Normal pressure threshold used by the overlay color scale.
 ///
@@ -10,12 +11,49 @@ use crate::GasGrid;
 const OVERLAY_NORMAL_PRESSURE: f32 = 101.325;
 /// High-pressure threshold for the overlay color scale (`1.5x` normal).
 const OVERLAY_HIGH_PRESSURE: f32 = OVERLAY_NORMAL_PRESSURE * 1.5;
+/// Rate of change (moles per second) at which the flow-mode colors saturate.
+const OVERLAY_RATE_SCALE: f32 = 20.0;
+/// World units of arrow per mole-per-tile of pressure gradient.
+const ARROW_LENGTH_PER_GRADIENT: f32 = 0.05;
+/// Arrows are capped just short of a tile so neighbouring cells stay readable.
+const ARROW_MAX_LENGTH: f32 = 0.9;
+/// Gradients shorter than this draw no arrow.
+const ARROW_MIN_GRADIENT: f32 = 0.01;
 
 /// Resource that controls the atmospheric pressure debug overlay.
 /// When true, the overlay is visible. When false, it is hidden.
 #[derive(Resource, Default)]
 pub struct AtmosDebugOverlay(pub bool);
 
+/// What the debug overlay shows while it is visible.  Cycled with
+/// [`Action::CycleAtmosOverlay`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AtmosOverlayMode {
+    /// Cells colored by absolute pressure.
+    #[default]
+    Pressure,
+    /// Cells colored by how fast their pressure changes, with an arrow per
+    /// cell along the flow (down the pressure gradient).
+    Flow,
+}
+
+/// Per-cell rate of change of moles, sampled whenever the [`GasGrid`] changes
+/// while the flow overlay is visible.
+#[derive(Resource, Debug, Default)]
+pub struct GasRateTracker {
+    last_moles: Vec<f32>,
+    last_time: f32,
+    /// Moles per second for each cell, row-major like the grid.
+    rates: Vec<f32>,
+}
+
+impl GasRateTracker {
+    /// Last sampled rate of change at `index`, or zero before two samples exist.
+    pub fn rate(&self, index: usize) -> f32 {
+        self.rates.get(index).copied().unwrap_or(0.0)
+    }
+}
+
 /// Marker component for overlay quad entities.
 /// Each entity represents one cell in the gas grid, positioned at the corresponding tile location.
 #[derive(Component)]
@@ -29,9 +67,9 @@ pub struct OverlayQuad {
     material: Handle<StandardMaterial>,
 }
 
-/// System that toggles the debug overlay on F3 keypress.
-pub fn toggle_overlay(keyboard: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<AtmosDebugOverlay>) {
-    if keyboard.just_pressed(KeyCode::F3) {
+/// System that toggles the debug overlay on [`Action::ToggleAtmosOverlay`].
+pub fn toggle_overlay(input: ActionInput, mut overlay: ResMut<AtmosDebugOverlay>) {
+    if input.just_pressed(Action::ToggleAtmosOverlay) {
         overlay.0 = !overlay.0;
         info!(
             "Atmospheric debug overlay: {}",
@@ -40,6 +78,113 @@ pub fn toggle_overlay(keyboard: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<A
     }
 }
 
+/// System that switches the overlay between pressure and flow mode on
+/// [`Action::CycleAtmosOverlay`].
+pub fn toggle_overlay_mode(input: ActionInput, mut mode: ResMut<AtmosOverlayMode>) {
+    if input.just_pressed(Action::CycleAtmosOverlay) {
+        *mode = match *mode {
+            AtmosOverlayMode::Pressure => AtmosOverlayMode::Flow,
+            AtmosOverlayMode::Flow => AtmosOverlayMode::Pressure,
+        };
+        info!("Atmospheric debug overlay mode: {:?}", *mode);
+    }
+}
+
+/// System that samples per-cell rates of change for the flow overlay.
+///
+/// Samples are only taken when the grid actually changed, so on clients the
+/// rate spans the interval between two received deltas rather than dropping
+/// to zero on the frames in between.
+pub fn track_gas_rates(
+    time: Res<Time>,
+    overlay: Res<AtmosDebugOverlay>,
+    mode: Res<AtmosOverlayMode>,
+    gas_grid: Option<Res<GasGrid>>,
+    mut tracker: ResMut<GasRateTracker>,
+) {
+    if !overlay.0 || *mode != AtmosOverlayMode::Flow {
+        return;
+    }
+    let Some(gas_grid) = gas_grid else {
+        return;
+    };
+    if !gas_grid.is_changed() {
+        return;
+    }
+
+    let now = time.elapsed_secs();
+    let moles = gas_grid.moles_vec();
+    let elapsed = now - tracker.last_time;
+    if tracker.last_moles.len() == moles.len() && elapsed > 0.0 {
+        tracker.rates = moles
+            .iter()
+            .zip(&tracker.last_moles)
+            .map(|(now, last)| (now - last) / elapsed)
+            .collect();
+    } else {
+        tracker.rates = vec![0.0; moles.len()];
+    }
+    tracker.last_moles = moles;
+    tracker.last_time = now;
+}
+
+/// System that draws one arrow per cell along the direction gas flows, i.e.
+/// down the pressure gradient, while the flow overlay is visible.
+///
+/// Arrow length is proportional to the gradient magnitude (the quantity
+/// `PressureForceScale` multiplies), so tuning the force scale against
+/// visible flow is a matter of comparing arrow lengths with body movement.
+pub fn draw_flow_arrows(
+    mut gizmos: Gizmos,
+    overlay: Res<AtmosDebugOverlay>,
+    mode: Res<AtmosOverlayMode>,
+    gas_grid: Option<Res<GasGrid>>,
+    quads: Query<&OverlayQuad>,
+) {
+    if !overlay.0 || *mode != AtmosOverlayMode::Flow {
+        return;
+    }
+    let Some(gas_grid) = gas_grid else {
+        return;
+    };
+
+    for quad in quads.iter() {
+        let gradient = gas_grid.pressure_gradient_at(quad.position);
+        let Some((offset, color)) = flow_arrow(gradient) else {
+            continue;
+        };
+        let start = Vec3::new(quad.position.x as f32, 0.05, quad.position.y as f32);
+        gizmos.arrow(start - offset * 0.5, start + offset * 0.5, color);
+    }
+}
+
+/// World-space arrow (centered on the cell) and color for a pressure
+/// gradient, or `None` when the gradient is negligible.
+///
+/// The arrow points from high to low pressure; its color fades from yellow
+/// for gentle flow to red for flow strong enough to hit the length cap.
+fn flow_arrow(gradient: Vec2) -> Option<(Vec3, Color)> {
+    let magnitude = gradient.length();
+    if magnitude < ARROW_MIN_GRADIENT {
+        return None;
+    }
+    let length = (magnitude * ARROW_LENGTH_PER_GRADIENT).min(ARROW_MAX_LENGTH);
+    let direction = -Vec3::new(gradient.x, 0.0, gradient.y) / magnitude;
+    let t = length / ARROW_MAX_LENGTH;
+    Some((direction * length, Color::srgb(1.0, 1.0 - t, 0.0)))
+}
+
+/// Overlay color for a cell's rate of change: blue while it drains, red while
+/// it fills, grey when steady.
+fn rate_color(rate: f32) -> Color {
+    let t = (rate / OVERLAY_RATE_SCALE).clamp(-1.0, 1.0);
+    if t < 0.0 {
+        Color::srgba(0.3 * (1.0 + t), 0.3 * (1.0 + t), 0.3 - 0.7 * t, 0.5)
+    } else {
+        Color::srgba(0.3 + 0.7 * t, 0.3 * (1.0 - t), 0.3 * (1.0 - t), 0.5)
+    }
+}
+
 /// System that spawns overlay quads when the overlay is enabled and none exist.
 /// Spawns one quad per walkable floor tile and creates the required mesh and materials.
 pub fn spawn_overlay_quads(
@@ -181,8 +326,15 @@ pub fn despawn_overlay_quads(
 /// System that updates the color of overlay quads based on the current pressure.
 /// Only runs when the overlay is active.
 /// Color mapping: blue (vacuum, p = 0) -> green (normal, p ≈ 101.325) -> red (high, p > 151.9875)
+///
+/// In [`AtmosOverlayMode::Flow`] quads are colored by rate of change instead
+/// (see [`GasRateTracker`]).  On clients pressure comes from the smoothed
+/// [`GasDisplay`] rather than the stepwise-replicated grid.
 pub fn update_overlay_colors(
     overlay: Res<AtmosDebugOverlay>,
+    mode: Res<AtmosOverlayMode>,
+    tracker: Res<GasRateTracker>,
+    display: Res<GasDisplay>,
     gas_grid: Option<Res<GasGrid>>,
     quads: Query<(&OverlayQuad, &MeshMaterial3d<StandardMaterial>)>,
     mut materials: ResMut<Assets<StandardMaterial>>,
@@ -200,7 +352,18 @@ pub fn update_overlay_colors(
             continue;
         };
 
-        let pressure = gas_grid.pressure_at(quad.position).unwrap_or(0.0);
+        if *mode == AtmosOverlayMode::Flow {
+            let index = (quad.position.y * gas_grid.width() as i32 + quad.position.x) as usize;
+            material.base_color = rate_color(tracker.rate(index));
+            continue;
+        }
+
+        let pressure = if display.is_empty() {
+            gas_grid.pressure_at(quad.position)
+        } else {
+            display.pressure_at(quad.position)
+        }
+        .unwrap_or(0.0);
 
         // Color mapping based on pressure:
         // p = 0.0: blue (vacuum)
@@ -489,6 +652,30 @@ mod tests {
         assert!(!overlay.0, "Should be off after second toggle");
     }
 
+    #[test]
+    fn test_flow_arrow_points_down_the_gradient() {
+        assert!(flow_arrow(Vec2::ZERO).is_none());
+
+        // Pressure rises towards +x, so gas flows towards -x.
+        let (offset, _) = flow_arrow(Vec2::new(4.0, 0.0)).unwrap();
+        assert!(offset.x < 0.0 && offset.z == 0.0, "got {offset:?}");
+        assert!((offset.length() - 4.0 * ARROW_LENGTH_PER_GRADIENT).abs() < 1e-6);
+
+        let (offset, _) = flow_arrow(Vec2::new(0.0, -1000.0)).unwrap();
+        assert!((offset.length() - ARROW_MAX_LENGTH).abs() < 1e-6);
+        assert!(offset.z > 0.0);
+    }
+
+    #[test]
+    fn test_rate_color_distinguishes_draining_and_filling() {
+        let draining = rate_color(-OVERLAY_RATE_SCALE).to_srgba();
+        let filling = rate_color(OVERLAY_RATE_SCALE).to_srgba();
+        let steady = rate_color(0.0).to_srgba();
+        assert!(draining.blue > draining.red);
+        assert!(filling.red > filling.blue);
+        assert_eq!(steady.red, steady.blue);
+    }
+
     // ── ECS-style mutation system tests ──────────────────────────────────
 
     #[test]
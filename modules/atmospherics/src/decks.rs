@@ -0,0 +1,177 @@
+//! Atmosphere on the decks above the main deck.
+//!
+//! Every [`UpperDecks`] deck gets its own [`DeckAtmos`]: a [`GasGrid`]
+//! filled to standard pressure on its walkable cells when the map loads,
+//! together with the fire and breach state the main deck keeps in
+//! resources.  The server steps each deck next to the main grid with the
+//! same rules (diffusion, fire, breaches and pressure forces on the bodies
+//! standing on it) and replicates it on stream 2 tagged with its deck.
+//! Decks do not exchange gas; ladders are not open to it.  Upper decks are
+//! static, so their walls are only synced once.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use tiles::{Deck, UpperDecks};
+
+use crate::decompression::{Breaches, DecompressionEvent, scan_breaches};
+use crate::fire::{BurningCells, FireGrid, HeatSource, advance_fire};
+use crate::{AtmosLayer, AtmosSimPaused, GasGrid};
+
+/// Atmosphere of one deck above the main deck: its gas and the fire and
+/// breach state that [`FireGrid`], [`BurningCells`] and [`Breaches`] hold
+/// for the main deck.  Clients only fill in the gas and the burning cells.
+#[derive(Debug, Clone)]
+pub struct DeckAtmos {
+    pub gas: GasGrid,
+    pub fire: FireGrid,
+    pub burning: BurningCells,
+    pub breaches: Breaches,
+}
+
+impl DeckAtmos {
+    /// A deck holding `gas`, at ambient temperature with nothing burning.
+    pub fn new(gas: GasGrid) -> Self {
+        Self {
+            fire: FireGrid::new(gas.width(), gas.height()),
+            gas,
+            burning: BurningCells::default(),
+            breaches: Breaches::default(),
+        }
+    }
+}
+
+/// Atmosphere of the upper decks, bottom first: `UpperDeckAtmos.0[0]`
+/// belongs to deck 1.  Empty on single-deck maps.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct UpperDeckAtmos(pub Vec<DeckAtmos>);
+
+impl UpperDeckAtmos {
+    /// Atmosphere of `deck`, or `None` for the main deck (see [`GasGrid`])
+    /// and decks that do not exist.
+    pub fn get(&self, deck: Deck) -> Option<&DeckAtmos> {
+        let index = (deck.0 as usize).checked_sub(1)?;
+        self.0.get(index)
+    }
+
+    /// Mutable access to the atmosphere of `deck`; see [`Self::get`].
+    pub fn get_mut(&mut self, deck: Deck) -> Option<&mut DeckAtmos> {
+        let index = (deck.0 as usize).checked_sub(1)?;
+        self.0.get_mut(index)
+    }
+
+    /// Every upper deck with its atmosphere, bottom first.
+    pub fn iter(&self) -> impl Iterator<Item = (Deck, &DeckAtmos)> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(index, atmos)| (Deck(index as u8 + 1), atmos))
+    }
+
+    /// Every upper deck with its atmosphere, bottom first.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Deck, &mut DeckAtmos)> {
+        self.0
+            .iter_mut()
+            .enumerate()
+            .map(|(index, atmos)| (Deck(index as u8 + 1), atmos))
+    }
+
+    /// Atmosphere of `deck`, adding empty decks up to it as needed.  `None`
+    /// for the main deck.  Clients use this to store what the server sends.
+    pub(crate) fn get_or_grow(&mut self, deck: Deck) -> Option<&mut DeckAtmos> {
+        let index = (deck.0 as usize).checked_sub(1)?;
+        if self.0.len() <= index {
+            self.0
+                .resize_with(index + 1, || DeckAtmos::new(GasGrid::new(0, 0)));
+        }
+        self.0.get_mut(index)
+    }
+}
+
+impl AtmosLayer {
+    /// Builds the atmosphere of every deck in `upper` with the default
+    /// atmosphere.
+    pub(crate) fn init_upper_deck_atmos(&self, upper: &UpperDecks) -> UpperDeckAtmos {
+        UpperDeckAtmos(
+            upper
+                .0
+                .iter()
+                .map(|deck| DeckAtmos::new(self.init_gas_grid(&deck.structures, &HashMap::new())))
+                .collect(),
+        )
+    }
+}
+
+/// Server-side system that advances every upper deck alongside the main
+/// deck's diffusion, fire and breach systems: its gas is stepped, the
+/// [`HeatSource`]s standing on it heat its fire, and breaches that open on
+/// it fire a [`DecompressionEvent`] for that deck.  Skips while the
+/// simulation is paused.
+pub(crate) fn step_upper_decks(
+    time: Res<Time<Fixed>>,
+    paused: Res<AtmosSimPaused>,
+    decks: Option<ResMut<UpperDeckAtmos>>,
+    sources: Query<(&HeatSource, &GlobalTransform)>,
+    mut events: MessageWriter<DecompressionEvent>,
+) {
+    if paused.0 {
+        return;
+    }
+    let Some(mut decks) = decks else {
+        return;
+    };
+    let dt = time.delta_secs();
+    for (deck, atmos) in decks.iter_mut() {
+        atmos.gas.step(dt);
+
+        let heat = sources
+            .iter()
+            .filter(|(_, transform)| Deck::of_height(transform.translation().y) == deck)
+            .map(|(source, transform)| (transform.translation(), source.temperature));
+        let cells = advance_fire(&mut atmos.fire, &atmos.gas, heat, dt);
+        if atmos.burning.0 != cells {
+            atmos.burning.0 = cells;
+        }
+
+        let (open, opened) = scan_breaches(&atmos.gas, &atmos.breaches);
+        for (position, differential) in opened {
+            info!(
+                "Decompression on deck {} at {position} ({differential:.1} mol)",
+                deck.0
+            );
+            events.write(DecompressionEvent {
+                deck,
+                position,
+                differential,
+            });
+        }
+        atmos.breaches.0 = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AtmosInitConfig;
+    use tiles::{DeckLayers, TileKind};
+
+    #[test]
+    fn upper_decks_start_pressurised_behind_their_own_walls() {
+        let layer = AtmosLayer {
+            config: AtmosInitConfig {
+                standard_pressure: 100.0,
+                pressure_force_scale: 1.0,
+                diffusion_rate: 1.0,
+            },
+        };
+        let mut deck = DeckLayers::new(3, 1);
+        deck.structures.set(IVec2::new(1, 0), TileKind::Wall);
+        let atmos = layer.init_upper_deck_atmos(&UpperDecks(vec![deck]));
+
+        let grid = &atmos.get(Deck(1)).unwrap().gas;
+        assert_eq!(grid.pressure_at(IVec2::new(0, 0)), Some(100.0));
+        assert!(!grid.is_passable(IVec2::new(1, 0)));
+        assert!(atmos.get(Deck::MAIN).is_none());
+        assert!(atmos.get(Deck(2)).is_none());
+    }
+}
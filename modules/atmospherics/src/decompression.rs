@@ -0,0 +1,174 @@
+//! Breaches: sudden openings between pressurised gas and vacuum.
+//!
+//! After every diffusion step the server looks for cells with a neighbour at
+//! least [`DECOMPRESSION_THRESHOLD`] moles fuller than themselves.  Those
+//! cells are the current [`Breaches`]; bodies near one are pushed out harder
+//! by the pressure forces.  Each breach that was not already open fires a
+//! [`DecompressionEvent`], which is also replicated to clients for sound and
+//! screen shake.  [`Breaches`] covers the main deck; each upper deck keeps
+//! its own in its [`DeckAtmos`](crate::DeckAtmos).
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use tiles::Deck;
+
+use crate::GasGrid;
+
+/// Pressure difference across one cell edge, in moles, that counts as a
+/// breach.
+pub const DECOMPRESSION_THRESHOLD: f32 = 30.0;
+
+/// Distance in tiles from a breach within which pressure forces are boosted.
+pub const BREACH_RADIUS: f32 = 3.0;
+
+/// Factor applied to pressure forces on bodies within [`BREACH_RADIUS`] of a
+/// breach.
+pub const BREACH_FORCE_MULTIPLIER: f32 = 3.0;
+
+/// A breach opened at `position` on `deck`, the low-pressure side of an edge
+/// with a pressure difference of `differential` moles.
+///
+/// Written on the server when the breach opens and on clients when the
+/// server's notice arrives.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct DecompressionEvent {
+    pub deck: Deck,
+    pub position: IVec2,
+    pub differential: f32,
+}
+
+/// Cells currently on the low side of a breach.  Server only.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct Breaches(pub HashSet<IVec2>);
+
+impl Breaches {
+    /// Whether `cell` is within [`BREACH_RADIUS`] of any breach.
+    pub fn is_near(&self, cell: IVec2) -> bool {
+        self.0
+            .iter()
+            .any(|breach| breach.distance_squared(cell) as f32 <= BREACH_RADIUS * BREACH_RADIUS)
+    }
+}
+
+/// Breach cells of `grid` and the largest pressure difference at each.
+///
+/// Only dirty cells can differ from their neighbours, so only they are
+/// checked.
+fn find_breaches(grid: &GasGrid) -> Vec<(IVec2, f32)> {
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+    for cell in grid.dirty_cells() {
+        if !seen.insert(cell) || !grid.is_passable(cell) {
+            continue;
+        }
+        let here = grid.pressure_at(cell).unwrap_or(0.0);
+        let differential = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+            .into_iter()
+            .map(|offset| cell + offset)
+            .filter(|&other| grid.is_passable(other))
+            .filter_map(|other| grid.pressure_at(other))
+            .map(|there| there - here)
+            .fold(0.0, f32::max);
+        if differential >= DECOMPRESSION_THRESHOLD {
+            found.push((cell, differential));
+        }
+    }
+    found
+}
+
+/// The breach cells of `grid` given the `open` breaches of the previous
+/// tick, and the breaches among them that are new, with their pressure
+/// difference.
+///
+/// A breach cell next to one that was already open belongs to the same
+/// breach as its front moves, so it is not new.
+pub(crate) fn scan_breaches(
+    grid: &GasGrid,
+    open: &Breaches,
+) -> (HashSet<IVec2>, Vec<(IVec2, f32)>) {
+    let found = find_breaches(grid);
+    let opened = found
+        .iter()
+        .copied()
+        .filter(|&(position, _)| {
+            ![IVec2::ZERO, IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+                .into_iter()
+                .any(|offset| open.0.contains(&(position + offset)))
+        })
+        .collect();
+    (found.into_iter().map(|(cell, _)| cell).collect(), opened)
+}
+
+/// Server-side system: updates [`Breaches`] from the main deck's gas grid and
+/// writes a [`DecompressionEvent`] for each breach that is new this tick.
+/// Runs in `FixedUpdate` after diffusion.
+pub(crate) fn detect_decompression(
+    gas_grid: Option<Res<GasGrid>>,
+    mut breaches: ResMut<Breaches>,
+    mut events: MessageWriter<DecompressionEvent>,
+) {
+    let Some(grid) = gas_grid else {
+        return;
+    };
+    let (current, opened) = scan_breaches(&grid, &breaches);
+    for (position, differential) in opened {
+        info!("Decompression at {position} ({differential:.1} mol)");
+        events.write(DecompressionEvent {
+            deck: Deck::MAIN,
+            position,
+            differential,
+        });
+    }
+    if breaches.0 != current {
+        breaches.0 = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Captured(Vec<DecompressionEvent>);
+
+    fn capture(mut reader: MessageReader<DecompressionEvent>, mut captured: ResMut<Captured>) {
+        captured.0.extend(reader.read().copied());
+    }
+
+    #[test]
+    fn opening_to_space_fires_one_event() {
+        let mut grid = GasGrid::new(6, 1);
+        for x in 0..6 {
+            grid.set_moles(IVec2::new(x, 0), 100.0);
+        }
+        grid.step(0.05);
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_message::<DecompressionEvent>();
+        app.init_resource::<Breaches>();
+        app.init_resource::<Captured>();
+        app.insert_resource(grid);
+        app.add_systems(Update, (detect_decompression, capture).chain());
+
+        app.update();
+        assert!(app.world().resource::<Captured>().0.is_empty());
+
+        app.world_mut()
+            .resource_mut::<GasGrid>()
+            .set_vacuum(IVec2::new(0, 0), true);
+        for _ in 0..10 {
+            app.world_mut().resource_mut::<GasGrid>().step(0.05);
+            app.update();
+        }
+
+        let captured = &app.world().resource::<Captured>().0;
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0].position, IVec2::new(0, 0));
+        assert!(captured[0].differential >= DECOMPRESSION_THRESHOLD);
+        let breaches = app.world().resource::<Breaches>();
+        assert!(breaches.is_near(IVec2::new(2, 0)));
+        assert!(!breaches.is_near(IVec2::new(5, 0)));
+    }
+}
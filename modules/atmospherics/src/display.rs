@@ -0,0 +1,106 @@
+use bevy::prelude::*;
+
+use crate::AtmosBroadcastTimers;
+#[cfg(test)]
+use crate::DELTA_INTERVAL;
+
+/// Client-side display copy of the gas grid.
+///
+/// The server replicates the grid at ~10 Hz, so drawing the authoritative
+/// [`GasGrid`](crate::GasGrid) directly shows visible steps.  `GasDisplay`
+/// keeps its own per-cell values that glide from the previously displayed
+/// state to the latest received one over the delta interval.  Only
+/// presentation code (the debug overlay, future vision effects) reads it; the
+/// simulation keeps using the authoritative grid.
+///
+/// Empty on the server and before the first snapshot arrives; callers should
+/// fall back to the grid when [`GasDisplay::is_empty`] is true.
+#[derive(Resource, Debug, Default)]
+pub struct GasDisplay {
+    width: u32,
+    from: Vec<f32>,
+    to: Vec<f32>,
+    current: Vec<f32>,
+    elapsed: f32,
+}
+
+impl GasDisplay {
+    /// Returns `true` until the first received state has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.current.is_empty()
+    }
+
+    /// Displayed pressure at `pos`, or `None` when out of bounds.
+    pub fn pressure_at(&self, pos: IVec2) -> Option<f32> {
+        if pos.x < 0 || pos.y < 0 || pos.x >= self.width as i32 {
+            return None;
+        }
+        let idx = pos.y as usize * self.width as usize + pos.x as usize;
+        self.current.get(idx).copied()
+    }
+
+    /// Starts interpolating from the currently displayed values towards
+    /// `target`.  Snaps straight to `target` when the grid size changed.
+    pub fn retarget(&mut self, width: u32, target: Vec<f32>) {
+        if width != self.width || target.len() != self.current.len() {
+            self.width = width;
+            self.from = target.clone();
+            self.current = target.clone();
+        } else {
+            self.from.clone_from(&self.current);
+        }
+        self.to = target;
+        self.elapsed = 0.0;
+    }
+
+    /// Advances the interpolation by `dt` seconds, reaching the target
+    /// `interval` seconds after it was set.
+    pub fn advance(&mut self, dt: f32, interval: f32) {
+        if self.elapsed >= interval {
+            return;
+        }
+        self.elapsed = (self.elapsed + dt).min(interval);
+        let t = self.elapsed / interval;
+        for ((current, from), to) in self.current.iter_mut().zip(&self.from).zip(&self.to) {
+            *current = from + (to - from) * t;
+        }
+    }
+}
+
+/// Client system: moves the displayed values towards the last received
+/// state, over the server's delta interval.
+pub(crate) fn advance_gas_display(
+    time: Res<Time>,
+    timers: Res<AtmosBroadcastTimers>,
+    mut display: ResMut<GasDisplay>,
+) {
+    display.advance(time.delta_secs(), timers.delta.duration().as_secs_f32());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_glides_between_received_states() {
+        let mut display = GasDisplay::default();
+        assert!(display.is_empty());
+
+        display.retarget(2, vec![0.0, 100.0]);
+        assert_eq!(display.pressure_at(IVec2::new(1, 0)), Some(100.0));
+
+        display.retarget(2, vec![50.0, 100.0]);
+        assert_eq!(display.pressure_at(IVec2::new(0, 0)), Some(0.0));
+        display.advance(DELTA_INTERVAL / 2.0, DELTA_INTERVAL);
+        let halfway = display.pressure_at(IVec2::new(0, 0)).unwrap();
+        assert!((halfway - 25.0).abs() < 1e-3, "got {halfway}");
+
+        // A new state mid-way continues from what is on screen, not from the
+        // previous target.
+        display.retarget(2, vec![0.0, 100.0]);
+        assert!((display.pressure_at(IVec2::new(0, 0)).unwrap() - 25.0).abs() < 1e-3);
+        display.advance(DELTA_INTERVAL * 3.0, DELTA_INTERVAL);
+        assert_eq!(display.pressure_at(IVec2::new(0, 0)), Some(0.0));
+        assert_eq!(display.pressure_at(IVec2::new(2, 0)), None);
+    }
+}
@@ -0,0 +1,515 @@
+//! Fire: per-cell temperature and combustion on top of the gas grid.
+//!
+//! The gas grid tracks a single gas by moles only.  Fire adds the two
+//! quantities combustion needs, kept alongside it in [`FireGrid`]:
+//!
+//! - a **temperature** per cell, conducted between gas-passable neighbours
+//!   and cooling towards [`AMBIENT_TEMPERATURE`];
+//! - an **exhaust** fraction per cell, the share of the cell's oxygen that
+//!   has been burnt to CO2.  O2 → CO2 is one mole for one, so burning does
+//!   not change the cell's moles and the gas grid is left untouched.
+//!
+//! A cell ignites once it is at least [`IGNITION_TEMPERATURE`] with at least
+//! [`MIN_OXYGEN`] moles of oxygen.  While burning it consumes
+//! [`BURN_RATE`] moles of oxygen per second and is held at
+//! [`FLAME_TEMPERATURE`], which heats its neighbours past ignition so the
+//! fire spreads.  It goes out when its oxygen runs low.  Temperature does
+//! not feed back into pressure.
+//!
+//! The server simulates fire; clients only receive the set of burning cells
+//! ([`BurningCells`]) for rendering.  These resources cover the main deck;
+//! each upper deck keeps its own in its [`DeckAtmos`](crate::DeckAtmos).
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use things::{
+    ApplyStatus, Damage, DamageKind, Health, SpatialIndex, StatusEffects, StatusKind, cell_of,
+};
+use tiles::Deck;
+
+use crate::{AtmosSimPaused, GasGrid, UpperDeckAtmos};
+
+/// Temperature cells cool towards, in kelvin.
+pub const AMBIENT_TEMPERATURE: f32 = 293.15;
+
+/// Temperature at which a cell with enough oxygen catches fire, in kelvin.
+pub const IGNITION_TEMPERATURE: f32 = 450.0;
+
+/// Temperature a burning cell is held at, in kelvin.
+pub const FLAME_TEMPERATURE: f32 = 1200.0;
+
+/// Share of a cell's moles that is oxygen before anything has burnt.
+pub const OXYGEN_FRACTION: f32 = 0.21;
+
+/// Oxygen a cell needs to catch fire or keep burning, in moles.
+pub const MIN_OXYGEN: f32 = 2.0;
+
+/// Oxygen a burning cell consumes, in moles per second.
+pub const BURN_RATE: f32 = 1.0;
+
+/// Health per second taken from things standing in a burning cell.
+pub const BURN_DAMAGE_PER_SECOND: f32 = 5.0;
+
+/// Seconds things that stood in a burning cell keep burning after leaving
+/// it (see [`StatusKind::Burning`]).
+pub const AFTERBURN_SECS: f32 = 3.0;
+
+/// Rate of heat and exhaust exchange between neighbouring cells, per second.
+const CONDUCTIVITY: f32 = 2.0;
+
+/// Rate at which cells lose heat to their surroundings, per second.
+const COOLING_RATE: f32 = 0.1;
+
+/// Largest exchange factor per step, keeping the explicit update stable for
+/// long steps.
+const MAX_EXCHANGE: f32 = 0.2;
+
+/// Raises the temperature of the cell it stands in to at least
+/// `temperature` kelvin every tick.  Welders, burning debris and the like.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct HeatSource {
+    pub temperature: f32,
+}
+
+/// Cells currently on fire.
+///
+/// Written by the fire simulation on the server and replaced by each
+/// [`AtmosStreamMessage::BurningCells`](crate::AtmosStreamMessage::BurningCells)
+/// on clients.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct BurningCells(pub HashSet<IVec2>);
+
+/// Temperature, exhaust and combustion state for every gas grid cell.
+/// Row-major like the gas grid; server only.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct FireGrid {
+    width: u32,
+    height: u32,
+    temperature: Vec<f32>,
+    exhaust: Vec<f32>,
+    burning: Vec<bool>,
+}
+
+impl FireGrid {
+    /// A grid at ambient temperature with nothing burnt and nothing burning.
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = (width as usize) * (height as usize);
+        Self {
+            width,
+            height,
+            temperature: vec![AMBIENT_TEMPERATURE; size],
+            exhaust: vec![0.0; size],
+            burning: vec![false; size],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn index(&self, pos: IVec2) -> Option<usize> {
+        if pos.x >= 0 && pos.x < self.width as i32 && pos.y >= 0 && pos.y < self.height as i32 {
+            Some((pos.y * self.width as i32 + pos.x) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn position(&self, idx: usize) -> IVec2 {
+        IVec2::new(
+            (idx % self.width as usize) as i32,
+            (idx / self.width as usize) as i32,
+        )
+    }
+
+    /// Temperature at `pos` in kelvin, or `None` when out of bounds.
+    pub fn temperature_at(&self, pos: IVec2) -> Option<f32> {
+        self.index(pos).map(|idx| self.temperature[idx])
+    }
+
+    /// Raises the temperature at `pos` to at least `temperature`.
+    pub fn heat(&mut self, pos: IVec2, temperature: f32) {
+        if let Some(idx) = self.index(pos) {
+            self.temperature[idx] = self.temperature[idx].max(temperature);
+        }
+    }
+
+    /// Whether the cell at `pos` is on fire.
+    pub fn is_burning(&self, pos: IVec2) -> bool {
+        self.index(pos).is_some_and(|idx| self.burning[idx])
+    }
+
+    /// Positions of every burning cell, row-major.
+    pub fn burning_cells(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.burning
+            .iter()
+            .enumerate()
+            .filter(|(_, burning)| **burning)
+            .map(|(idx, _)| self.position(idx))
+    }
+
+    /// Moles of oxygen left in the cell at `pos` of `gas`.
+    pub fn oxygen_at(&self, gas: &GasGrid, pos: IVec2) -> f32 {
+        let (Some(idx), Some(moles)) = (self.index(pos), gas.pressure_at(pos)) else {
+            return 0.0;
+        };
+        moles * OXYGEN_FRACTION * (1.0 - self.exhaust[idx])
+    }
+
+    /// Moles of CO2 produced so far in the cell at `pos` of `gas`.
+    pub fn carbon_dioxide_at(&self, gas: &GasGrid, pos: IVec2) -> f32 {
+        let (Some(idx), Some(moles)) = (self.index(pos), gas.pressure_at(pos)) else {
+            return 0.0;
+        };
+        moles * OXYGEN_FRACTION * self.exhaust[idx]
+    }
+
+    /// Advances the fire by `dt` seconds against the current state of `gas`,
+    /// which must have the same dimensions.
+    ///
+    /// Heat and exhaust are exchanged between gas-passable neighbours first,
+    /// then cells cool, then burning cells consume oxygen and may go out,
+    /// and finally cells that were hot at the start of the step and have
+    /// enough oxygen ignite.
+    pub fn step(&mut self, gas: &GasGrid, dt: f32) {
+        let passable = gas.passable_vec();
+        let exchange = (CONDUCTIVITY * dt).min(MAX_EXCHANGE);
+        let cooling = (COOLING_RATE * dt).min(1.0);
+
+        let temperature = self.temperature.clone();
+        let exhaust = self.exhaust.clone();
+        for idx in 0..self.burning.len() {
+            if !passable[idx] {
+                self.temperature[idx] = AMBIENT_TEMPERATURE;
+                self.exhaust[idx] = 0.0;
+                self.burning[idx] = false;
+                continue;
+            }
+            let pos = self.position(idx);
+            let mut heat_flow = 0.0;
+            let mut exhaust_flow = 0.0;
+            for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                let Some(other) = self.index(pos + offset).filter(|&other| passable[other]) else {
+                    continue;
+                };
+                heat_flow += temperature[other] - temperature[idx];
+                exhaust_flow += exhaust[other] - exhaust[idx];
+            }
+            let heated = temperature[idx] + heat_flow * exchange;
+            self.temperature[idx] = heated + (AMBIENT_TEMPERATURE - heated) * cooling;
+            self.exhaust[idx] = (exhaust[idx] + exhaust_flow * exchange).clamp(0.0, 1.0);
+        }
+
+        for (idx, &open) in passable.iter().enumerate() {
+            if !open {
+                continue;
+            }
+            let pos = self.position(idx);
+            let oxygen = self.oxygen_at(gas, pos);
+            if self.burning[idx] {
+                let total_oxygen = gas.pressure_at(pos).unwrap_or(0.0) * OXYGEN_FRACTION;
+                let burnt = (BURN_RATE * dt).min(oxygen);
+                if total_oxygen > 0.0 {
+                    self.exhaust[idx] = (self.exhaust[idx] + burnt / total_oxygen).min(1.0);
+                }
+                if oxygen - burnt < MIN_OXYGEN {
+                    self.burning[idx] = false;
+                } else {
+                    self.temperature[idx] = self.temperature[idx].max(FLAME_TEMPERATURE);
+                }
+            } else if temperature[idx] >= IGNITION_TEMPERATURE && oxygen >= MIN_OXYGEN {
+                self.burning[idx] = true;
+                self.temperature[idx] = self.temperature[idx].max(FLAME_TEMPERATURE);
+            }
+        }
+    }
+}
+
+/// Heats `fire` at each `(position, temperature)` of `sources`, advances it
+/// by `dt` against `gas` and returns its burning cells.  The fire grid
+/// follows the size of `gas` and is reset whenever that changes.
+pub(crate) fn advance_fire(
+    fire: &mut FireGrid,
+    gas: &GasGrid,
+    sources: impl IntoIterator<Item = (Vec3, f32)>,
+    dt: f32,
+) -> HashSet<IVec2> {
+    if fire.width() != gas.width() || fire.height() != gas.height() {
+        *fire = FireGrid::new(gas.width(), gas.height());
+    }
+    for (position, temperature) in sources {
+        fire.heat(cell_of(position), temperature);
+    }
+    fire.step(gas, dt);
+    fire.burning_cells().collect()
+}
+
+/// Server-side system: heats the cells of the main deck's [`HeatSource`]s,
+/// advances the [`FireGrid`] one fixed tick and mirrors its burning cells
+/// into [`BurningCells`].
+///
+/// Runs in `FixedUpdate` after diffusion.  Skipped while the simulation is
+/// paused.
+pub(crate) fn fire_step_system(
+    time: Res<Time<Fixed>>,
+    paused: Res<AtmosSimPaused>,
+    gas_grid: Option<Res<GasGrid>>,
+    mut fire: ResMut<FireGrid>,
+    mut burning: ResMut<BurningCells>,
+    sources: Query<(&HeatSource, &GlobalTransform)>,
+) {
+    if paused.0 {
+        return;
+    }
+    let Some(gas) = gas_grid else {
+        return;
+    };
+    let heat = sources
+        .iter()
+        .filter(|(_, transform)| Deck::of_height(transform.translation().y) == Deck::MAIN)
+        .map(|(source, transform)| (transform.translation(), source.temperature));
+    let cells = advance_fire(&mut fire, &gas, heat, time.delta_secs());
+    if burning.0 != cells {
+        burning.0 = cells;
+    }
+}
+
+/// Server-side system: writes [`Damage`] of kind [`DamageKind::Burn`] for
+/// every thing standing in a burning cell of its deck, and sets those that
+/// can be hurt and are not burning yet on fire for [`AFTERBURN_SECS`].
+/// Skipped while the simulation is paused.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn burn_things(
+    time: Res<Time<Fixed>>,
+    paused: Res<AtmosSimPaused>,
+    burning: Res<BurningCells>,
+    upper: Option<Res<UpperDeckAtmos>>,
+    spatial: Option<Res<SpatialIndex>>,
+    flammable: Query<Option<&StatusEffects>, With<Health>>,
+    mut damage: MessageWriter<Damage>,
+    mut statuses: MessageWriter<ApplyStatus>,
+) {
+    if paused.0 {
+        return;
+    }
+    let Some(spatial) = spatial else {
+        return;
+    };
+    let amount = BURN_DAMAGE_PER_SECOND * time.delta_secs();
+    let decks = std::iter::once((Deck::MAIN, &burning.0)).chain(
+        upper
+            .iter()
+            .flat_map(|upper| upper.iter().map(|(deck, atmos)| (deck, &atmos.burning.0))),
+    );
+    for (deck, cells) in decks {
+        for &cell in cells {
+            for target in spatial.in_cell(cell) {
+                if spatial
+                    .position(target)
+                    .is_some_and(|position| Deck::of_height(position.y) != deck)
+                {
+                    continue;
+                }
+                damage.write(Damage {
+                    target,
+                    amount,
+                    kind: DamageKind::Burn,
+                });
+                let Ok(effects) = flammable.get(target) else {
+                    continue;
+                };
+                if effects.is_none_or(|effects| effects.stacks(StatusKind::Burning) == 0) {
+                    statuses.write(ApplyStatus {
+                        target,
+                        kind: StatusKind::Burning,
+                        stacks: 1,
+                        duration: AFTERBURN_SECS,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Marker for the flame drawn over a burning cell on clients.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct FlameMarker {
+    pub deck: Deck,
+    pub position: IVec2,
+}
+
+/// Client-side system: keeps one [`FlameMarker`] per burning cell of every
+/// deck, spawning and despawning them as [`BurningCells`] and the upper
+/// decks' burning cells change.
+pub(crate) fn update_flame_markers(
+    mut commands: Commands,
+    burning: Res<BurningCells>,
+    upper: Res<UpperDeckAtmos>,
+    flames: Query<(Entity, &FlameMarker)>,
+    mut assets: Local<Option<(Handle<Mesh>, Handle<StandardMaterial>)>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !burning.is_changed() && !upper.is_changed() {
+        return;
+    }
+    let (mesh, material) = assets
+        .get_or_insert_with(|| {
+            (
+                meshes.add(Cone::new(0.35, 0.8)),
+                materials.add(StandardMaterial {
+                    base_color: Color::srgba(1.0, 0.45, 0.1, 0.8),
+                    emissive: LinearRgba::rgb(6.0, 2.0, 0.4),
+                    alpha_mode: AlphaMode::Blend,
+                    ..default()
+                }),
+            )
+        })
+        .clone();
+
+    let burning_on = |deck: Deck| {
+        if deck == Deck::MAIN {
+            Some(&burning.0)
+        } else {
+            upper.get(deck).map(|atmos| &atmos.burning.0)
+        }
+    };
+    let mut shown = HashSet::new();
+    for (entity, flame) in flames.iter() {
+        if burning_on(flame.deck).is_some_and(|cells| cells.contains(&flame.position)) {
+            shown.insert((flame.deck, flame.position));
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+    let decks = std::iter::once((Deck::MAIN, &burning.0))
+        .chain(upper.iter().map(|(deck, atmos)| (deck, &atmos.burning.0)));
+    for (deck, cells) in decks {
+        for &position in cells {
+            if shown.contains(&(deck, position)) {
+                continue;
+            }
+            commands.spawn((
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::from_xyz(
+                    position.x as f32,
+                    deck.floor_height() + 0.4,
+                    position.y as f32,
+                ),
+                FlameMarker { deck, position },
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use things::{Thing, apply_damage, update_spatial_index};
+
+    use super::*;
+
+    /// A `width`×1 corridor of gas at `moles` per cell.
+    fn corridor(width: u32, moles: f32) -> GasGrid {
+        let mut gas = GasGrid::new(width, 1);
+        for x in 0..width as i32 {
+            gas.set_moles(IVec2::new(x, 0), moles);
+        }
+        gas
+    }
+
+    #[test]
+    fn heat_ignites_and_fire_spreads() {
+        let gas = corridor(5, 100.0);
+        let mut fire = FireGrid::new(5, 1);
+        fire.heat(IVec2::new(0, 0), IGNITION_TEMPERATURE);
+        fire.step(&gas, 0.05);
+        assert!(fire.is_burning(IVec2::new(0, 0)));
+        assert!(!fire.is_burning(IVec2::new(4, 0)));
+
+        for _ in 0..200 {
+            fire.step(&gas, 0.05);
+        }
+        assert!(fire.is_burning(IVec2::new(4, 0)));
+    }
+
+    #[test]
+    fn fire_burns_oxygen_into_co2_and_goes_out() {
+        let gas = corridor(1, 20.0);
+        let mut fire = FireGrid::new(1, 1);
+        let cell = IVec2::new(0, 0);
+        let oxygen = fire.oxygen_at(&gas, cell);
+        fire.heat(cell, FLAME_TEMPERATURE);
+        fire.step(&gas, 0.05);
+        assert!(fire.is_burning(cell));
+
+        for _ in 0..20 {
+            fire.step(&gas, 0.05);
+        }
+        let burnt = fire.carbon_dioxide_at(&gas, cell);
+        assert!(burnt > 0.5);
+        assert!((fire.oxygen_at(&gas, cell) + burnt - oxygen).abs() < 1e-3);
+
+        for _ in 0..400 {
+            fire.step(&gas, 0.05);
+        }
+        assert!(!fire.is_burning(cell));
+        assert!(fire.oxygen_at(&gas, cell) < MIN_OXYGEN);
+    }
+
+    #[test]
+    fn vacuum_does_not_burn() {
+        let gas = corridor(1, 0.0);
+        let mut fire = FireGrid::new(1, 1);
+        fire.heat(IVec2::new(0, 0), FLAME_TEMPERATURE);
+        fire.step(&gas, 0.05);
+        assert!(!fire.is_burning(IVec2::new(0, 0)));
+    }
+
+    #[test]
+    fn burning_cells_damage_things_in_them() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<AtmosSimPaused>();
+        app.init_resource::<SpatialIndex>();
+        app.add_message::<Damage>();
+        app.add_message::<ApplyStatus>();
+        app.insert_resource(BurningCells(HashSet::from([IVec2::new(1, 0)])));
+        app.add_systems(
+            FixedUpdate,
+            (update_spatial_index, burn_things, apply_damage).chain(),
+        );
+        let spawn = |app: &mut App, x: f32| {
+            app.world_mut()
+                .spawn((
+                    Thing { kind: 0 },
+                    Health::new(100.0),
+                    Transform::from_xyz(x, 0.5, 0.0),
+                ))
+                .id()
+        };
+        let burning = spawn(&mut app, 1.0);
+        let safe = spawn(&mut app, 3.0);
+
+        app.world_mut()
+            .resource_mut::<Time<Fixed>>()
+            .advance_by(std::time::Duration::from_millis(100));
+        app.world_mut().run_schedule(FixedUpdate);
+        let health = |entity| app.world().get::<Health>(entity).unwrap().current;
+        assert!(health(burning) < 100.0);
+        assert_eq!(health(safe), 100.0);
+        let set_on_fire: Vec<Entity> = app
+            .world_mut()
+            .resource_mut::<Messages<ApplyStatus>>()
+            .drain()
+            .map(|status| status.target)
+            .collect();
+        assert_eq!(set_on_fire, vec![burning]);
+    }
+}
@@ -26,12 +26,96 @@ impl Default for GasCell {
     }
 }
 
-/// Represents a proposed gas flow between two cells during a diffusion substep.
-#[derive(Debug, Clone, Copy)]
-struct ProposedFlow {
-    from: usize,
-    to: usize,
-    amount: f32,
+/// Minimum number of cells in a substep's work set before rows are processed
+/// in parallel; smaller sets are cheaper to walk on one thread.
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_WORK: usize = 4096;
+
+/// Cardinal neighbours of `idx` in the order up, left, right, down.
+///
+/// This is the order in which the row-major flow scan of the original solver
+/// touched each cell's edges; accumulating in the same order keeps the
+/// results bit-identical.
+fn neighbours(idx: usize, width: usize, height: usize) -> [Option<usize>; 4] {
+    let (x, y) = (idx % width, idx / width);
+    [
+        (y > 0).then(|| idx - width),
+        (x > 0).then(|| idx - 1),
+        (x + 1 < width).then(|| idx + 1),
+        (y + 1 < height).then(|| idx + width),
+    ]
+}
+
+/// Read-only view of the grid used to evaluate one diffusion substep per cell.
+///
+/// Every cell is computed from the previous substep's values only, so cells
+/// can be evaluated in any order or in parallel.
+struct Stencil<'a> {
+    cells: &'a [GasCell],
+    passable: &'a [bool],
+    width: usize,
+    height: usize,
+    diffusion_rate: f32,
+    dt: f32,
+}
+
+impl Stencil<'_> {
+    /// Passable neighbours of `idx` with the moles difference `here - there`.
+    fn diffs(&self, idx: usize) -> impl Iterator<Item = (usize, f32)> + '_ {
+        let here = self.cells[idx].moles;
+        neighbours(idx, self.width, self.height)
+            .into_iter()
+            .flatten()
+            .filter(|&n| self.passable[n])
+            .map(move |n| (n, here - self.cells[n].moles))
+    }
+
+    /// Fraction of its proposed outflow that `idx` can actually supply, and
+    /// whether any of its edges carries a non-zero difference.
+    fn source_scale(&self, idx: usize) -> (f32, bool) {
+        if !self.passable[idx] {
+            return (1.0, false);
+        }
+        let mut outgoing = 0.0;
+        let mut unsettled = false;
+        for (_, diff) in self.diffs(idx) {
+            unsettled |= diff != 0.0;
+            if diff > 0.0 {
+                outgoing += diff * self.diffusion_rate * self.dt;
+            }
+        }
+        let scale = if outgoing > 0.0 {
+            let available = self.cells[idx].moles.max(0.0);
+            (available / outgoing).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        (scale, unsettled)
+    }
+
+    /// Moles of `idx` after this substep, given every source's scale.
+    fn next_moles(&self, idx: usize, scale: &[f32]) -> f32 {
+        let here = self.cells[idx].moles;
+        if !self.passable[idx] {
+            return here;
+        }
+        let mut delta = 0.0;
+        for (n, diff) in self.diffs(idx) {
+            if diff > 0.0 {
+                let actual = diff * self.diffusion_rate * self.dt * scale[idx];
+                if actual > 0.0 && actual.is_finite() {
+                    delta -= actual;
+                }
+            } else if diff < 0.0 {
+                let actual = (-diff) * self.diffusion_rate * self.dt * scale[n];
+                if actual > 0.0 && actual.is_finite() {
+                    delta += actual;
+                }
+            }
+        }
+        let next = here + delta;
+        if next.is_finite() { next.max(0.0) } else { 0.0 }
+    }
 }
 
 /// A grid-based gas simulation that tracks moles per cell and derives pressure.
@@ -44,21 +128,32 @@ pub struct GasGrid {
     height: u32,
     cells: Vec<GasCell>,
     passable: Vec<bool>,
+    /// Cells open to space.  They take in gas like any other cell but are
+    /// emptied after every substep, so they drain their neighbours forever.
+    vacuum: Vec<bool>,
     /// Fraction of the pressure difference equalized per step.
     diffusion_rate: f32,
     /// Tracks the moles values from the last broadcast snapshot or delta.
     /// Used by the server to compute incremental deltas for replication.
     #[reflect(ignore)]
     pub last_broadcast_moles: Vec<f32>,
+    /// Cells that may differ from a neighbour: changed by the last substep or
+    /// edited since.  Only these and their neighbours are re-evaluated; every
+    /// other edge is at exact equilibrium and carries no flow.  May contain
+    /// duplicates.
+    #[reflect(ignore)]
+    dirty: Vec<usize>,
     // Scratch buffers reused across substeps to avoid per-frame heap allocations
     #[reflect(ignore)]
-    scratch_flows: Vec<ProposedFlow>,
+    scratch_work: Vec<usize>,
+    #[reflect(ignore)]
+    scratch_in_work: Vec<bool>,
     #[reflect(ignore)]
-    scratch_outgoing: Vec<f32>,
+    scratch_unsettled: Vec<bool>,
     #[reflect(ignore)]
     scratch_source_scale: Vec<f32>,
     #[reflect(ignore)]
-    scratch_delta: Vec<f32>,
+    scratch_next: Vec<f32>,
 }
 
 impl GasGrid {
@@ -77,12 +172,15 @@ impl GasGrid {
             height,
             cells: vec![GasCell::default(); size],
             passable: vec![true; size],
+            vacuum: vec![false; size],
             diffusion_rate,
             last_broadcast_moles: vec![0.0; size],
-            scratch_flows: Vec::new(),
-            scratch_outgoing: vec![0.0; size],
+            dirty: (0..size).collect(),
+            scratch_work: Vec::new(),
+            scratch_in_work: vec![false; size],
+            scratch_unsettled: vec![false; size],
             scratch_source_scale: vec![1.0; size],
-            scratch_delta: vec![0.0; size],
+            scratch_next: vec![0.0; size],
         }
     }
 
@@ -106,24 +204,50 @@ impl GasGrid {
         }
     }
 
-    /// Updates the passability mask from [`TileFlags`].
-    /// Gas-passable tiles allow gas flow, others do not.
-    /// When a cell transitions to impassable, its moles are zeroed.
+    /// Updates the passability and vacuum masks from [`TileFlags`].
+    /// Gas-passable tiles allow gas flow, others do not; vacuum tiles are
+    /// open to space.
+    /// When a cell transitions to impassable or vacuum, its moles are zeroed.
     pub fn sync_walls_from_flags(&mut self, flags: &TileFlags) {
         for y in 0..self.height {
             for x in 0..self.width {
                 let pos = IVec2::new(x as i32, y as i32);
                 if let Some(idx) = self.coord_to_index(pos) {
                     let new_passable = flags.is_gas_passable(pos);
-                    if !new_passable && self.passable[idx] {
+                    let new_vacuum = flags.is_vacuum(pos);
+                    if new_passable == self.passable[idx] && new_vacuum == self.vacuum[idx] {
+                        continue;
+                    }
+                    if !new_passable || new_vacuum {
                         self.cells[idx].moles = 0.0;
                     }
                     self.passable[idx] = new_passable;
+                    self.vacuum[idx] = new_vacuum;
+                    self.mark_dirty(idx);
                 }
             }
         }
     }
 
+    /// Opens (or closes) the cell at `pos` to space.  Returns false if the
+    /// position is out of bounds.
+    pub fn set_vacuum(&mut self, pos: IVec2, vacuum: bool) -> bool {
+        let Some(idx) = self.coord_to_index(pos) else {
+            return false;
+        };
+        if vacuum {
+            self.cells[idx].moles = 0.0;
+        }
+        self.vacuum[idx] = vacuum;
+        self.mark_dirty(idx);
+        true
+    }
+
+    /// Whether the cell at `pos` is open to space.
+    pub fn is_vacuum(&self, pos: IVec2) -> bool {
+        self.coord_to_index(pos).is_some_and(|idx| self.vacuum[idx])
+    }
+
     /// Returns the pressure at the given position.
     /// Pressure equals moles (unit cell volume, fixed temperature).
     /// Returns None if the position is out of bounds.
@@ -158,6 +282,12 @@ impl GasGrid {
         Vec2::new((p_x_pos - p_x_neg) / 2.0, (p_y_pos - p_y_neg) / 2.0)
     }
 
+    /// Whether gas can flow through the cell at `pos`.  Out of bounds is not.
+    pub fn is_passable(&self, pos: IVec2) -> bool {
+        self.coord_to_index(pos)
+            .is_some_and(|idx| self.passable[idx])
+    }
+
     /// Returns the pressure at `pos` only if the cell is in-bounds and passable; otherwise `None`.
     fn passable_pressure_at(&self, pos: IVec2) -> Option<f32> {
         let idx = self.coord_to_index(pos)?;
@@ -174,6 +304,7 @@ impl GasGrid {
     pub fn set_moles(&mut self, pos: IVec2, moles: f32) -> bool {
         if let Some(idx) = self.coord_to_index(pos) {
             self.cells[idx].moles = moles.max(0.0);
+            self.mark_dirty(idx);
             true
         } else {
             false
@@ -240,6 +371,7 @@ impl GasGrid {
         for &(idx, moles) in changes {
             if let Some(cell) = self.cells.get_mut(idx as usize) {
                 cell.moles = moles.max(0.0);
+                self.mark_dirty(idx as usize);
             }
         }
     }
@@ -284,12 +416,15 @@ impl GasGrid {
             height,
             cells,
             passable,
+            vacuum: vec![false; size],
             diffusion_rate: DEFAULT_DIFFUSION_RATE,
             last_broadcast_moles,
-            scratch_flows: Vec::new(),
-            scratch_outgoing: vec![0.0; size],
+            dirty: (0..size).collect(),
+            scratch_work: Vec::new(),
+            scratch_in_work: vec![false; size],
+            scratch_unsettled: vec![false; size],
             scratch_source_scale: vec![1.0; size],
-            scratch_delta: vec![0.0; size],
+            scratch_next: vec![0.0; size],
         })
     }
 
@@ -326,102 +461,127 @@ impl GasGrid {
         }
     }
 
-    fn step_substep(&mut self, dt: f32) {
-        let width = self.width as usize;
-        let height = self.height as usize;
-        let diffusion_rate = self.diffusion_rate;
-
-        // Reuse scratch buffers to avoid per-substep heap allocations
-        self.scratch_flows.clear();
-        for val in self.scratch_outgoing.iter_mut() {
-            *val = 0.0;
+    /// Queues the cell at `idx` for re-evaluation by the next substep.
+    ///
+    /// Clients apply deltas without ever stepping, so the list is compacted
+    /// whenever it outgrows the grid.
+    fn mark_dirty(&mut self, idx: usize) {
+        self.dirty.push(idx);
+        if self.dirty.len() > self.cells.len() {
+            self.dirty.sort_unstable();
+            self.dirty.dedup();
         }
+    }
 
-        for y in 0..height {
-            for x in 0..width {
-                let idx = y * width + x;
-                if !self.passable[idx] {
-                    continue;
-                }
+    /// Returns `true` if the cell at `pos` may still exchange gas with a
+    /// neighbour, i.e. it changed in the last substep or was edited since.
+    pub fn is_dirty(&self, pos: IVec2) -> bool {
+        self.coord_to_index(pos)
+            .is_some_and(|idx| self.dirty.contains(&idx))
+    }
 
-                let moles_here = self.cells[idx].moles;
+    /// Positions of the dirty cells: the only cells that can have a non-zero
+    /// [`pressure_gradient_at`](Self::pressure_gradient_at) after a step.  May
+    /// repeat a cell.
+    pub fn dirty_cells(&self) -> impl Iterator<Item = IVec2> + '_ {
+        let width = self.width as usize;
+        self.dirty
+            .iter()
+            .map(move |&idx| IVec2::new((idx % width) as i32, (idx / width) as i32))
+    }
 
-                if x + 1 < width {
-                    let n_idx = y * width + (x + 1);
-                    if self.passable[n_idx] {
-                        let diff = moles_here - self.cells[n_idx].moles;
-                        if diff > 0.0 {
-                            let amount = diff * diffusion_rate * dt;
-                            self.scratch_flows.push(ProposedFlow {
-                                from: idx,
-                                to: n_idx,
-                                amount,
-                            });
-                            self.scratch_outgoing[idx] += amount;
-                        } else if diff < 0.0 {
-                            let amount = (-diff) * diffusion_rate * dt;
-                            self.scratch_flows.push(ProposedFlow {
-                                from: n_idx,
-                                to: idx,
-                                amount,
-                            });
-                            self.scratch_outgoing[n_idx] += amount;
-                        }
-                    }
-                }
+    /// Returns `true` once every passable cell matches all of its neighbours
+    /// exactly, so further steps are no-ops.
+    pub fn is_settled(&self) -> bool {
+        self.dirty.is_empty()
+    }
 
-                if y + 1 < height {
-                    let n_idx = (y + 1) * width + x;
-                    if self.passable[n_idx] {
-                        let diff = moles_here - self.cells[n_idx].moles;
-                        if diff > 0.0 {
-                            let amount = diff * diffusion_rate * dt;
-                            self.scratch_flows.push(ProposedFlow {
-                                from: idx,
-                                to: n_idx,
-                                amount,
-                            });
-                            self.scratch_outgoing[idx] += amount;
-                        } else if diff < 0.0 {
-                            let amount = (-diff) * diffusion_rate * dt;
-                            self.scratch_flows.push(ProposedFlow {
-                                from: n_idx,
-                                to: idx,
-                                amount,
-                            });
-                            self.scratch_outgoing[n_idx] += amount;
-                        }
-                    }
-                }
-            }
+    fn step_substep(&mut self, dt: f32) {
+        if self.dirty.is_empty() {
+            return;
         }
+        let width = self.width as usize;
+        let height = self.height as usize;
 
-        for (idx, &outgoing) in self.scratch_outgoing.iter().enumerate() {
-            if outgoing > 0.0 {
-                let available = self.cells[idx].moles.max(0.0);
-                self.scratch_source_scale[idx] = (available / outgoing).clamp(0.0, 1.0);
-            } else {
-                self.scratch_source_scale[idx] = 1.0;
+        // Work set: every dirty cell plus its neighbours.
+        self.scratch_work.clear();
+        for &idx in &self.dirty {
+            for cell in
+                std::iter::once(idx).chain(neighbours(idx, width, height).into_iter().flatten())
+            {
+                if !self.scratch_in_work[cell] {
+                    self.scratch_in_work[cell] = true;
+                    self.scratch_work.push(cell);
+                }
             }
         }
+        self.dirty.clear();
 
-        for val in self.scratch_delta.iter_mut() {
-            *val = 0.0;
-        }
+        let stencil = Stencil {
+            cells: &self.cells,
+            passable: &self.passable,
+            width,
+            height,
+            diffusion_rate: self.diffusion_rate,
+            dt,
+        };
 
-        for flow in &self.scratch_flows {
-            let actual = flow.amount * self.scratch_source_scale[flow.from];
-            if actual <= 0.0 || !actual.is_finite() {
-                continue;
+        #[cfg(feature = "parallel")]
+        let parallel = self.scratch_work.len() >= PARALLEL_MIN_WORK;
+        #[cfg(not(feature = "parallel"))]
+        let parallel = false;
+
+        if parallel {
+            #[cfg(feature = "parallel")]
+            {
+                use rayon::prelude::*;
+                let in_work = &self.scratch_in_work;
+                self.scratch_source_scale
+                    .par_chunks_mut(width)
+                    .zip(self.scratch_unsettled.par_chunks_mut(width))
+                    .enumerate()
+                    .for_each(|(y, (scales, unsettled))| {
+                        for (x, (scale, unsettled)) in scales.iter_mut().zip(unsettled).enumerate()
+                        {
+                            let idx = y * width + x;
+                            if in_work[idx] {
+                                (*scale, *unsettled) = stencil.source_scale(idx);
+                            }
+                        }
+                    });
+                let scale = &self.scratch_source_scale;
+                self.scratch_next
+                    .par_chunks_mut(width)
+                    .enumerate()
+                    .for_each(|(y, row)| {
+                        for (x, next) in row.iter_mut().enumerate() {
+                            let idx = y * width + x;
+                            if in_work[idx] {
+                                *next = stencil.next_moles(idx, scale);
+                            }
+                        }
+                    });
+            }
+        } else {
+            for &idx in &self.scratch_work {
+                (self.scratch_source_scale[idx], self.scratch_unsettled[idx]) =
+                    stencil.source_scale(idx);
+            }
+            for &idx in &self.scratch_work {
+                self.scratch_next[idx] = stencil.next_moles(idx, &self.scratch_source_scale);
             }
-
-            self.scratch_delta[flow.from] -= actual;
-            self.scratch_delta[flow.to] += actual;
         }
 
-        for (idx, cell) in self.cells.iter_mut().enumerate() {
-            let next = cell.moles + self.scratch_delta[idx];
-            cell.moles = if next.is_finite() { next.max(0.0) } else { 0.0 };
+        for &idx in &self.scratch_work {
+            self.cells[idx].moles = if self.vacuum[idx] {
+                0.0
+            } else {
+                self.scratch_next[idx]
+            };
+            self.scratch_in_work[idx] = false;
+            if self.scratch_unsettled[idx] {
+                self.dirty.push(idx);
+            }
         }
     }
 }
@@ -429,18 +589,14 @@ impl GasGrid {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tiles::{TileFlag, TileGrid, TileKind};
+    use tiles::{TileGrid, TileKind};
 
     /// Build a [`TileFlags`] from a [`TileGrid<TileKind>`], mirroring
     /// `rebuild_tile_flags` logic for unit tests.
     fn flags_from_grid(grid: &TileGrid<TileKind>) -> TileFlags {
         let mut flags = TileFlags::new(grid.width(), grid.height());
-        for (pos, &kind) in grid.iter() {
-            let flag = match kind {
-                TileKind::Floor => TileFlag::WALKABLE | TileFlag::GAS_PASS,
-                TileKind::Wall => TileFlag::empty(),
-            };
-            flags.set(pos, flag);
+        for (pos, kind) in grid.iter() {
+            flags.set(pos, kind.flags());
         }
         flags
     }
@@ -592,6 +748,32 @@ mod tests {
         assert_eq!(grid.pressure_at(IVec2::new(2, 2)), Some(0.0)); // vacuum (was already 0)
     }
 
+    #[test]
+    fn test_sync_walls_closed_door_blocks_gas() {
+        let mut grid = GasGrid::new(3, 1);
+        let mut tile_grid = TileGrid::<TileKind>::new_fill(3, 1, TileKind::Floor);
+        tile_grid.set(IVec2::new(1, 0), TileKind::Door { open: false });
+        grid.sync_walls_from_flags(&flags_from_grid(&tile_grid));
+
+        grid.set_moles(IVec2::new(0, 0), 10.0);
+        for _ in 0..50 {
+            grid.step(1.0);
+        }
+
+        // Closed door seals the right-hand cell off completely.
+        assert!(!grid.passable[1]);
+        assert_eq!(grid.pressure_at(IVec2::new(2, 0)), Some(0.0));
+
+        // Opening the door lets gas through.
+        tile_grid.set(IVec2::new(1, 0), TileKind::Door { open: true });
+        grid.sync_walls_from_flags(&flags_from_grid(&tile_grid));
+        for _ in 0..50 {
+            grid.step(1.0);
+        }
+        assert!(grid.passable[1]);
+        assert!(grid.pressure_at(IVec2::new(2, 0)).unwrap() > 1.0);
+    }
+
     #[test]
     fn test_pressure_at_formula() {
         let mut grid = GasGrid::new(3, 3);
@@ -937,4 +1119,175 @@ mod tests {
         assert_eq!(grid.pressure_at(IVec2::new(0, 0)), Some(10.0));
         assert_eq!(grid.pressure_at(IVec2::new(2, 0)), Some(10.0));
     }
+
+    /// The original full-grid solver: collects a flow for every unequal pair
+    /// of passable neighbours in row-major order, then applies them all.
+    fn naive_step(grid: &mut GasGrid, dt: f32) {
+        let rate = grid.diffusion_rate;
+        let max_substep_dt = rate * MAX_DIFFUSION_FACTOR_RATIO / rate;
+        let substeps = (dt / max_substep_dt).ceil().max(1.0) as u32;
+        let dt = dt / substeps as f32;
+        let (width, height) = (grid.width as usize, grid.height as usize);
+        for _ in 0..substeps {
+            let mut flows = Vec::new();
+            let mut outgoing = vec![0.0f32; grid.cells.len()];
+            for y in 0..height {
+                for x in 0..width {
+                    let idx = y * width + x;
+                    if !grid.passable[idx] {
+                        continue;
+                    }
+                    let right = (x + 1 < width).then_some(idx + 1);
+                    let down = (y + 1 < height).then_some(idx + width);
+                    for n in [right, down].into_iter().flatten() {
+                        if !grid.passable[n] {
+                            continue;
+                        }
+                        let diff = grid.cells[idx].moles - grid.cells[n].moles;
+                        if diff > 0.0 {
+                            let amount = diff * rate * dt;
+                            flows.push((idx, n, amount));
+                            outgoing[idx] += amount;
+                        } else if diff < 0.0 {
+                            let amount = (-diff) * rate * dt;
+                            flows.push((n, idx, amount));
+                            outgoing[n] += amount;
+                        }
+                    }
+                }
+            }
+            let scale: Vec<f32> = outgoing
+                .iter()
+                .zip(&grid.cells)
+                .map(|(&out, cell)| {
+                    if out > 0.0 {
+                        (cell.moles.max(0.0) / out).clamp(0.0, 1.0)
+                    } else {
+                        1.0
+                    }
+                })
+                .collect();
+            let mut delta = vec![0.0f32; grid.cells.len()];
+            for (from, to, amount) in flows {
+                let actual = amount * scale[from];
+                if actual <= 0.0 || !actual.is_finite() {
+                    continue;
+                }
+                delta[from] -= actual;
+                delta[to] += actual;
+            }
+            for (cell, d) in grid.cells.iter_mut().zip(delta) {
+                let next = cell.moles + d;
+                cell.moles = if next.is_finite() { next.max(0.0) } else { 0.0 };
+            }
+        }
+    }
+
+    /// Station-like test grid: a walled room split by an interior wall with a
+    /// doorway, a pressurised corner and a breach to vacuum.
+    fn station_grid(width: u32, height: u32) -> (GasGrid, TileGrid<TileKind>) {
+        let mut tiles = TileGrid::<TileKind>::new_fill(width, height, TileKind::Floor);
+        let mid = width as i32 / 2;
+        for y in 0..height as i32 {
+            if y != height as i32 / 2 {
+                tiles.set(IVec2::new(mid, y), TileKind::Wall);
+            }
+        }
+        let mut grid = GasGrid::new(width, height);
+        grid.sync_walls_from_flags(&flags_from_grid(&tiles));
+        for y in 0..height as i32 {
+            for x in 0..mid {
+                grid.set_moles(IVec2::new(x, y), 100.0 + ((x * 7 + y * 13) % 11) as f32);
+            }
+        }
+        grid.set_moles(IVec2::new(width as i32 - 1, height as i32 - 1), 0.0);
+        (grid, tiles)
+    }
+
+    fn assert_bitwise_eq(a: &GasGrid, b: &GasGrid) {
+        for (idx, (x, y)) in a.cells.iter().zip(&b.cells).enumerate() {
+            assert_eq!(
+                x.moles.to_bits(),
+                y.moles.to_bits(),
+                "cell {idx}: {} vs {}",
+                x.moles,
+                y.moles
+            );
+        }
+    }
+
+    #[test]
+    fn test_dirty_step_matches_naive_solver() {
+        let (mut grid, mut tiles) = station_grid(24, 16);
+        let mut reference = grid.clone();
+
+        for step in 0..400 {
+            let dt = if step % 3 == 0 { 1.0 / 64.0 } else { 0.05 };
+            grid.step(dt);
+            naive_step(&mut reference, dt);
+            assert_bitwise_eq(&grid, &reference);
+
+            // Mid-run edits: a gas canister and a wall being deconstructed.
+            if step == 100 {
+                for g in [&mut grid, &mut reference] {
+                    g.set_moles(IVec2::new(20, 3), 500.0);
+                }
+            }
+            if step == 200 {
+                tiles.set(IVec2::new(12, 2), TileKind::Floor);
+                let flags = flags_from_grid(&tiles);
+                grid.sync_walls_from_flags(&flags);
+                reference.sync_walls_from_flags(&flags);
+            }
+        }
+    }
+
+    #[test]
+    fn test_equilibrium_grid_settles_and_stops_working() {
+        let mut grid = GasGrid::new(6, 6);
+        for y in 0..6 {
+            for x in 0..6 {
+                grid.set_moles(IVec2::new(x, y), 42.0);
+            }
+        }
+        assert!(!grid.is_settled());
+        grid.step(0.05);
+        assert!(grid.is_settled());
+        assert!(!grid.is_dirty(IVec2::new(3, 3)));
+
+        grid.set_moles(IVec2::new(3, 3), 50.0);
+        assert!(grid.is_dirty(IVec2::new(3, 3)));
+        grid.step(0.05);
+        assert!(grid.is_dirty(IVec2::new(3, 4)));
+        assert!(!grid.is_dirty(IVec2::new(0, 0)));
+    }
+
+    #[test]
+    fn test_vacuum_cells_drain_their_neighbours_forever() {
+        let mut grid = GasGrid::new(4, 1);
+        for x in 0..4 {
+            grid.set_moles(IVec2::new(x, 0), 100.0);
+        }
+        grid.set_vacuum(IVec2::new(0, 0), true);
+        assert_eq!(grid.pressure_at(IVec2::new(0, 0)), Some(0.0));
+
+        for _ in 0..6000 {
+            grid.step(0.05);
+        }
+        assert_eq!(grid.pressure_at(IVec2::new(0, 0)), Some(0.0));
+        assert!(grid.total_moles() < 1.0, "left {}", grid.total_moles());
+    }
+
+    /// Large enough for the work set to cross `PARALLEL_MIN_WORK`, so the
+    /// rayon path is exercised when the `parallel` feature is enabled.
+    #[test]
+    fn test_large_grid_matches_naive_solver() {
+        let (mut grid, _) = station_grid(96, 64);
+        let mut reference = grid.clone();
+        for _ in 0..30 {
+            grid.step(0.05);
+            naive_step(&mut reference, 0.05);
+        }
+        assert_bitwise_eq(&grid, &reference);
+    }
 }
@@ -1,22 +1,48 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
 
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use input::{Action, ActionInput, InputMap};
 use network::{
-    ClientId, Headless, ModuleReadySent, NetworkReceive, NetworkSend, PlayerEvent, Server,
-    StreamDef, StreamDirection, StreamReader, StreamRegistry, StreamSender,
+    Client, ClientId, Headless, ModuleReadySent, NetworkReceive, NetworkSend, PlayerEvent, Server,
+    StreamBudget, StreamDef, StreamDirection, StreamPriority, StreamReader, StreamRegistry,
+    StreamSender, TunablesAppExt,
 };
 use physics::{ConstantForce, RigidBody};
 use ron::value::RawValue;
 use serde::{Deserialize, Serialize};
-use tiles::{TileFlags, TileGrid, TileKind, TileMutated};
+use things::{SpatialIndex, cell_of};
+use tiles::{Deck, TileFlags, TileGrid, TileKind, TileMutated, UpperDecks};
 use wincode::{SchemaRead, SchemaWrite};
 use world::{MapLayer, MapLayerRegistryExt, from_layer_value, to_layer_value};
 
 mod gas_grid;
 pub use gas_grid::{DEFAULT_DIFFUSION_RATE, GasCell, GasGrid};
 
+mod display;
+pub use display::GasDisplay;
+
+mod decks;
+pub use decks::{DeckAtmos, UpperDeckAtmos};
+
 mod debug_overlay;
-pub use debug_overlay::{AtmosDebugOverlay, OverlayQuad};
+pub use debug_overlay::{AtmosDebugOverlay, AtmosOverlayMode, GasRateTracker, OverlayQuad};
+
+mod decompression;
+pub use decompression::{
+    BREACH_FORCE_MULTIPLIER, BREACH_RADIUS, Breaches, DECOMPRESSION_THRESHOLD, DecompressionEvent,
+};
+
+mod fire;
+pub use fire::{
+    AMBIENT_TEMPERATURE, BurningCells, FLAME_TEMPERATURE, FireGrid, FlameMarker, HeatSource,
+    IGNITION_TEMPERATURE,
+};
+
+mod vents;
+pub use vents::Vent;
 
 /// System set for the atmospherics module's server-side lifecycle systems.
 /// Other modules can use this for explicit ordering relative to atmospherics systems.
@@ -33,18 +59,28 @@ pub enum AtmosSet {
 
 /// Resource that controls whether the atmospherics simulation is paused.
 /// When true, `diffusion_step_system` skips advancing the gas grid.
-/// Toggle with F5.
+/// Toggled with [`Action::ToggleAtmosPause`] (F5 by default).
 #[derive(Resource, Default)]
 pub struct AtmosSimPaused(pub bool);
 
 /// Stream tag for the server→client atmospherics stream (stream 2).
 pub const ATMOS_STREAM_TAG: u8 = 2;
 
+/// Per-client allowance on stream 2 for periodic snapshots and deltas, in
+/// bytes per network tick.  Atmos yields to entity state when a client's
+/// link is congested.
+const ATMOS_BYTES_PER_TICK: usize = 8 * 1024;
+
 /// Wire format for stream 2 (server→client atmospherics stream).
+///
+/// Every message names the [`Deck`] it is about by index: deck 0 is the
+/// main deck's [`GasGrid`] and [`BurningCells`], the others their
+/// [`UpperDeckAtmos`] entries.
 #[derive(Debug, Clone, SchemaRead, SchemaWrite)]
 pub enum AtmosStreamMessage {
     /// Full gas grid snapshot sent on connect and every ~2 seconds.
     GasGridData {
+        deck: u8,
         width: u32,
         height: u32,
         gas_moles: Vec<f32>,
@@ -52,7 +88,16 @@ pub enum AtmosStreamMessage {
     },
     /// Incremental update broadcast at ~10 Hz; contains only cells that changed
     /// beyond the delta epsilon since the last snapshot or delta.
-    GasGridDelta { changes: Vec<(u16, f32)> },
+    GasGridDelta { deck: u8, changes: Vec<(u16, f32)> },
+    /// Every cell currently on fire as `(x, y)`, replacing the previous set.
+    /// Sent on connect, whenever the set changes and with each full snapshot.
+    BurningCells { deck: u8, cells: Vec<(u16, u16)> },
+    /// A breach opened at `(x, y)`; see [`DecompressionEvent`].
+    Decompression {
+        deck: u8,
+        position: (u16, u16),
+        differential: f32,
+    },
 }
 
 // ---------------------------------------------------------------------------
@@ -75,7 +120,7 @@ pub enum Atmo {
 /// On-disk representation of the `"atmosphere"` map layer.
 ///
 /// Each [`Atmo`] state maps to a list of regions.  Tiles not covered by any
-/// region use the default rule: walkable → `Pressurised`, wall → nothing.
+/// region use the default rule: walkable → `Pressurised`, wall or space → nothing.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AtmosLayerData {
     #[serde(default)]
@@ -114,6 +159,14 @@ pub struct AtmosLayer {
 }
 
 impl AtmosLayer {
+    /// The pressure force scale of the [`AtmosInitConfig`] resource, which
+    /// reflects config reloads, or the one this layer was built with.
+    fn pressure_force_scale(&self, world: &World) -> f32 {
+        world
+            .get_resource::<AtmosInitConfig>()
+            .map_or(self.config.pressure_force_scale, |c| c.pressure_force_scale)
+    }
+
     /// Core initialization shared by `load` and `load_default`.
     ///
     /// `overrides` maps tile positions to non-default atmosphere states
@@ -130,11 +183,7 @@ impl AtmosLayer {
         // Build initial flags from the tile grid for wall sync.
         let mut flags = TileFlags::new(grid.width(), grid.height());
         for (pos, kind) in grid.iter() {
-            let flag = match kind {
-                TileKind::Floor => tiles::TileFlag::WALKABLE | tiles::TileFlag::GAS_PASS,
-                TileKind::Wall => tiles::TileFlag::empty(),
-            };
-            flags.set(pos, flag);
+            flags.set(pos, kind.flags());
         }
         gas_grid.sync_walls_from_flags(&flags);
 
@@ -144,7 +193,7 @@ impl AtmosLayer {
                 let kind = grid.get_copy(pos).unwrap_or(TileKind::Floor);
                 let effective = if let Some(&atmo) = overrides.get(&pos) {
                     Some(atmo)
-                } else if kind.is_walkable() {
+                } else if kind.is_walkable() && !kind.is_vacuum() {
                     Some(Atmo::Pressurised)
                 } else {
                     None
@@ -191,7 +240,7 @@ impl AtmosLayer {
                         let bytes = base64::engine::general_purpose::STANDARD
                             .decode(b64)
                             .map_err(|e| format!("atmosphere layer: bitmap decode failed: {e}"))?;
-                        let expected = ((*size as usize) * (*size as usize) + 7) / 8;
+                        let expected = ((*size as usize) * (*size as usize)).div_ceil(8);
                         if bytes.len() != expected {
                             return Err(format!(
                                 "atmosphere layer: bitmap has {} bytes, expected {expected}",
@@ -221,6 +270,14 @@ impl AtmosLayer {
     }
 }
 
+/// Puts out fires and forgets breaches, so a map loaded again over a running
+/// world (see `world::reload_map`) starts without the old round's hazards.
+fn reset_hazards(world: &mut World) {
+    world.insert_resource(FireGrid::default());
+    world.insert_resource(BurningCells::default());
+    world.insert_resource(Breaches::default());
+}
+
 impl MapLayer for AtmosLayer {
     fn key(&self) -> &'static str {
         "atmosphere"
@@ -238,9 +295,15 @@ impl MapLayer for AtmosLayer {
             "atmosphere layer: TileGrid<TileKind> not found (tiles layer must load first)",
         )?;
         let gas_grid = self.init_gas_grid(grid, &overrides);
+        let upper = world
+            .get_resource::<UpperDecks>()
+            .map(|upper| self.init_upper_deck_atmos(upper))
+            .unwrap_or_default();
 
         world.insert_resource(gas_grid);
-        world.insert_resource(PressureForceScale(self.config.pressure_force_scale));
+        world.insert_resource(upper);
+        world.insert_resource(PressureForceScale(self.pressure_force_scale(world)));
+        reset_hazards(world);
         info!("AtmosphericsPlugin: atmosphere initialized from map layer");
         Ok(())
     }
@@ -253,9 +316,15 @@ impl MapLayer for AtmosLayer {
             "atmosphere layer: TileGrid<TileKind> not found (tiles layer must load first)",
         )?;
         let gas_grid = self.init_gas_grid(grid, &HashMap::new());
+        let upper = world
+            .get_resource::<UpperDecks>()
+            .map(|upper| self.init_upper_deck_atmos(upper))
+            .unwrap_or_default();
 
         world.insert_resource(gas_grid);
-        world.insert_resource(PressureForceScale(self.config.pressure_force_scale));
+        world.insert_resource(upper);
+        world.insert_resource(PressureForceScale(self.pressure_force_scale(world)));
+        reset_hazards(world);
         info!("AtmosphericsPlugin: atmosphere initialized with defaults");
         Ok(())
     }
@@ -280,7 +349,8 @@ impl MapLayer for AtmosLayer {
                     let kind = tile_grid.get_copy(pos).unwrap_or(TileKind::Floor);
                     let moles = gas_grid.pressure_at(pos).unwrap_or(0.0);
                     // Walkable tile with ~0 moles is vacuum (non-default).
-                    if kind.is_walkable() && moles < 0.01 {
+                    // Space tiles are vacuum by definition.
+                    if kind.is_walkable() && !kind.is_vacuum() && moles < 0.01 {
                         vacuum_cells.push((x as i32, y as i32));
                     }
                 }
@@ -297,12 +367,13 @@ impl MapLayer for AtmosLayer {
 
     fn unload(&self, world: &mut World) {
         world.remove_resource::<GasGrid>();
+        world.insert_resource(UpperDeckAtmos::default());
         world.remove_resource::<PressureForceScale>();
     }
 }
 
 /// Simulation time step (in seconds) applied when advancing the atmospherics simulation manually
-/// (e.g., via the F4 key). A value of 2.0 seconds makes gas movement visibly noticeable per step,
+/// (e.g., via [`Action::StepAtmos`]). A value of 2.0 seconds makes gas movement visibly noticeable per step,
 /// while still keeping the number of manual steps reasonable during debugging.
 const MANUAL_STEP_DT: f32 = 2.0;
 
@@ -323,9 +394,9 @@ fn wall_sync_system(flags: Option<Res<TileFlags>>, gas_grid: Option<ResMut<GasGr
 }
 
 /// System that advances the atmospherics simulation by one manual tick.
-/// Press F4 to advance diffusion by a fixed dt (MANUAL_STEP_DT), which may be internally sub-stepped, for debugging/inspection.
-fn manual_step_input(keyboard: Res<ButtonInput<KeyCode>>, gas_grid: Option<ResMut<GasGrid>>) {
-    if !keyboard.just_pressed(KeyCode::F4) {
+/// [`Action::StepAtmos`] (F4 by default) advances diffusion by a fixed dt (MANUAL_STEP_DT), which may be internally sub-stepped, for debugging/inspection.
+fn manual_step_input(input: ActionInput, gas_grid: Option<ResMut<GasGrid>>) {
+    if !input.just_pressed(Action::StepAtmos) {
         return;
     }
 
@@ -334,13 +405,13 @@ fn manual_step_input(keyboard: Res<ButtonInput<KeyCode>>, gas_grid: Option<ResMu
     };
 
     gas_grid.step(MANUAL_STEP_DT);
-    info!("Atmospherics manual step (F4): dt={}", MANUAL_STEP_DT);
+    info!("Atmospherics manual step: dt={}", MANUAL_STEP_DT);
 }
 
-/// System that toggles the atmospherics simulation pause state on F5 keypress.
+/// System that toggles the atmospherics simulation pause state on [`Action::ToggleAtmosPause`].
 /// When paused, `diffusion_step_system` does not advance the gas grid.
-fn pause_toggle_input(keyboard: Res<ButtonInput<KeyCode>>, mut paused: ResMut<AtmosSimPaused>) {
-    if keyboard.just_pressed(KeyCode::F5) {
+fn pause_toggle_input(input: ActionInput, mut paused: ResMut<AtmosSimPaused>) {
+    if input.just_pressed(Action::ToggleAtmosPause) {
         paused.0 = !paused.0;
         info!(
             "Atmospherics simulation {}",
@@ -349,13 +420,18 @@ fn pause_toggle_input(keyboard: Res<ButtonInput<KeyCode>>, mut paused: ResMut<At
     }
 }
 
+/// Diagnostic: wall-clock time of one [`GasGrid::step`], in milliseconds.
+pub const GAS_STEP_TIME: DiagnosticPath = DiagnosticPath::const_new("atmospherics/gas_step_time");
+
 /// System that advances the atmospherics simulation by one fixed-timestep tick.
 /// Runs in `FixedUpdate` so gas diffusion happens at a consistent simulation rate.
-/// Skips if the simulation is paused via `AtmosSimPaused`.
+/// Skips if the simulation is paused via `AtmosSimPaused`.  Records how long
+/// the step took as [`GAS_STEP_TIME`].
 fn diffusion_step_system(
     time: Res<Time<Fixed>>,
     paused: Res<AtmosSimPaused>,
     gas_grid: Option<ResMut<GasGrid>>,
+    mut diagnostics: Diagnostics,
 ) {
     if paused.0 {
         return;
@@ -365,7 +441,9 @@ fn diffusion_step_system(
         return;
     };
 
+    let started = std::time::Instant::now();
     gas_grid.step(time.delta_secs());
+    diagnostics.add_measurement(&GAS_STEP_TIME, || started.elapsed().as_secs_f64() * 1000.0);
 }
 
 /// Resource that holds the configurable scale factor applied to the pressure gradient
@@ -379,47 +457,120 @@ pub struct PressureForceScale(pub f32);
 /// Adjust during integration testing to produce convincing entity movement.
 const PRESSURE_FORCE_SCALE: f32 = 50.0;
 
-/// Server-side system: applies pressure-gradient forces to nearby `RigidBody::Dynamic` entities.
+/// Gradients shorter than this (moles per tile) exert no force; it keeps the
+/// long tail of a settling room from nudging every body in it.
+const MIN_PRESSURE_GRADIENT: f32 = 0.01;
+
+/// Marker for bodies whose `ConstantForce` is currently set by
+/// [`apply_pressure_forces`].  Removed together with the force once the body
+/// stands somewhere without a noticeable gradient.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct PressureDriven;
+
+/// The resources [`apply_pressure_forces`] reads the pressure field from.
+#[derive(SystemParam)]
+struct PressureField<'w> {
+    gas_grid: Option<Res<'w, GasGrid>>,
+    upper: Option<Res<'w, UpperDeckAtmos>>,
+    spatial: Option<Res<'w, SpatialIndex>>,
+    force_scale: Option<Res<'w, PressureForceScale>>,
+    breaches: Option<Res<'w, Breaches>>,
+}
+
+/// Server-side system: applies pressure-gradient forces to `RigidBody::Dynamic` things
+/// standing in cells where gas still flows, each pushed by the grid of the deck it stands on.
 ///
-/// For each dynamic body with a `Transform`, reads the gas pressure at the entity's grid cell and
-/// its four cardinal neighbours, computes the net force vector from the central-difference pressure
-/// gradient, scales it by `PRESSURE_FORCE_SCALE`, and writes it to the entity's `ConstantForce`
-/// component (inserting the component if the entity doesn't yet have one).
+/// Only a grid's [dirty cells](GasGrid::dirty_cells) and their neighbours can have a non-zero
+/// gradient, so the system looks up the things on those cells through the [`SpatialIndex`]
+/// instead of visiting every body.  For each one whose central-difference gradient is at least
+/// `MIN_PRESSURE_GRADIENT`, the force `−∇P · scale` is written to its `ConstantForce` and the
+/// body is marked [`PressureDriven`].  Within [`BREACH_RADIUS`] of an open [breach](Breaches)
+/// on its deck the force is multiplied by [`BREACH_FORCE_MULTIPLIER`], flinging loose things
+/// outward.  Bodies that were driven last tick but are no longer found lose both components, so
+/// a settled station carries no forces at all.
 ///
 /// Runs in `FixedUpdate` after `diffusion_step_system`.  Forces are overwritten every tick, so
 /// there is no accumulation even if `ConstantForce` persists across frames.
 fn apply_pressure_forces(
     mut commands: Commands,
-    gas_grid: Option<Res<GasGrid>>,
-    force_scale: Option<Res<PressureForceScale>>,
-    mut query: Query<(Entity, &RigidBody, &Transform, Option<&mut ConstantForce>)>,
+    field: PressureField,
+    mut bodies: Query<(&RigidBody, &Transform, Option<&mut ConstantForce>)>,
+    driven: Query<Entity, With<PressureDriven>>,
+    mut pushed: Local<HashSet<Entity>>,
 ) {
-    let Some(grid) = gas_grid else {
+    let PressureField {
+        gas_grid,
+        upper,
+        spatial,
+        force_scale,
+        breaches,
+    } = field;
+    let (Some(grid), Some(spatial)) = (gas_grid, spatial) else {
         return;
     };
 
     let scale = force_scale.map(|r| r.0).unwrap_or(PRESSURE_FORCE_SCALE);
 
-    for (entity, rigid_body, transform, maybe_force) in &mut query {
-        if *rigid_body != RigidBody::Dynamic {
-            continue;
-        }
+    pushed.clear();
+    let decks = std::iter::once((Deck::MAIN, &*grid, breaches.as_deref())).chain(
+        upper.iter().flat_map(|upper| {
+            upper
+                .iter()
+                .map(|(deck, atmos)| (deck, &atmos.gas, Some(&atmos.breaches)))
+        }),
+    );
+    for (deck, grid, breaches) in decks {
+        // A cell next to a freshly edited one has a gradient before the next
+        // step marks it dirty, so neighbours of dirty cells are checked too.
+        let cells = grid.dirty_cells().flat_map(|cell| {
+            [IVec2::ZERO, IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+                .map(|offset| cell + offset)
+        });
+        for cell in cells {
+            for entity in spatial.in_cell(cell) {
+                if pushed.contains(&entity) {
+                    continue;
+                }
+                let Ok((rigid_body, transform, maybe_force)) = bodies.get_mut(entity) else {
+                    continue;
+                };
+                // Each grid only pushes bodies on its own deck.
+                if *rigid_body != RigidBody::Dynamic
+                    || Deck::of_height(transform.translation.y) != deck
+                {
+                    continue;
+                }
 
-        // Map world-space translation to tile-grid coordinates.
-        let tile_pos = IVec2::new(
-            transform.translation.x.round() as i32,
-            transform.translation.z.round() as i32,
-        );
+                // gradient points toward increasing pressure; physical force is −∇P
+                // (objects are pushed from high pressure toward low pressure / the breach).
+                let body_cell = cell_of(transform.translation);
+                let gradient = grid.pressure_gradient_at(body_cell);
+                if gradient.length() < MIN_PRESSURE_GRADIENT {
+                    continue;
+                }
+                let boost = if breaches.is_some_and(|b| b.is_near(body_cell)) {
+                    BREACH_FORCE_MULTIPLIER
+                } else {
+                    1.0
+                };
+                let force_vec = Vec3::new(-gradient.x, 0.0, -gradient.y) * scale * boost;
 
-        // gradient points toward increasing pressure; physical force is −∇P
-        // (objects are pushed from high pressure toward low pressure / the breach).
-        let gradient = grid.pressure_gradient_at(tile_pos);
-        let force_vec = Vec3::new(-gradient.x, 0.0, -gradient.y) * scale;
+                if let Some(mut cf) = maybe_force {
+                    cf.0 = force_vec;
+                } else {
+                    commands.entity(entity).insert(ConstantForce(force_vec));
+                }
+                commands.entity(entity).insert(PressureDriven);
+                pushed.insert(entity);
+            }
+        }
+    }
 
-        if let Some(mut cf) = maybe_force {
-            cf.0 = force_vec;
-        } else {
-            commands.entity(entity).insert(ConstantForce(force_vec));
+    for entity in driven.iter() {
+        if !pushed.contains(&entity) {
+            commands
+                .entity(entity)
+                .remove::<(ConstantForce, PressureDriven)>();
         }
     }
 }
@@ -435,7 +586,12 @@ pub struct AtmosInitConfig {
 
 fn cleanup_atmos(mut commands: Commands) {
     commands.remove_resource::<GasGrid>();
+    commands.insert_resource(UpperDeckAtmos::default());
     commands.remove_resource::<PressureForceScale>();
+    commands.insert_resource(GasDisplay::default());
+    commands.insert_resource(FireGrid::default());
+    commands.insert_resource(BurningCells::default());
+    commands.insert_resource(Breaches::default());
 }
 
 /// Plugin that manages atmospheric simulation in the game.
@@ -473,9 +629,21 @@ impl<S: States + Copy> Plugin for AtmosphericsPlugin<S> {
         let state = self.state;
         app.insert_resource(self.config);
         app.register_type::<GasGrid>();
+        app.register_type::<HeatSource>();
+        app.register_type::<Vent>();
+        app.init_resource::<FireGrid>();
+        app.init_resource::<BurningCells>();
+        app.init_resource::<Breaches>();
+        app.init_resource::<UpperDeckAtmos>();
+        app.add_message::<DecompressionEvent>();
         app.init_resource::<AtmosDebugOverlay>();
+        app.init_resource::<AtmosOverlayMode>();
+        app.init_resource::<GasRateTracker>();
+        app.init_resource::<GasDisplay>();
         app.init_resource::<AtmosSimPaused>();
+        app.init_resource::<InputMap>();
         app.add_message::<TileMutated>();
+        app.register_diagnostic(Diagnostic::new(GAS_STEP_TIME).with_suffix("ms"));
 
         // Register the atmosphere map layer (must come after TilesLayer).
         app.register_map_layer(AtmosLayer {
@@ -486,7 +654,12 @@ impl<S: States + Copy> Plugin for AtmosphericsPlugin<S> {
             FixedUpdate,
             (
                 wall_sync_system,
+                vents::run_vents,
                 diffusion_step_system,
+                fire::fire_step_system,
+                decompression::detect_decompression,
+                decks::step_upper_decks,
+                fire::burn_things,
                 apply_pressure_forces,
             )
                 .chain()
@@ -496,7 +669,7 @@ impl<S: States + Copy> Plugin for AtmosphericsPlugin<S> {
             FixedUpdate,
             (wall_sync_system, diffusion_step_system)
                 .chain()
-                .run_if(not(resource_exists::<Server>)),
+                .run_if(resource_exists::<Client>),
         );
         app.add_systems(
             Update,
@@ -504,9 +677,13 @@ impl<S: States + Copy> Plugin for AtmosphericsPlugin<S> {
                 manual_step_input,
                 pause_toggle_input,
                 debug_overlay::toggle_overlay,
+                debug_overlay::toggle_overlay_mode,
                 debug_overlay::spawn_overlay_quads,
                 debug_overlay::despawn_overlay_quads,
+                debug_overlay::track_gas_rates,
                 debug_overlay::update_overlay_colors,
+                debug_overlay::draw_flow_arrows,
+                fire::update_flame_markers,
             )
                 .chain()
                 .run_if(not(resource_exists::<Headless>)),
@@ -521,10 +698,18 @@ impl<S: States + Copy> Plugin for AtmosphericsPlugin<S> {
         );
         app.add_systems(
             NetworkReceive,
-            handle_atmos_updates.run_if(not(resource_exists::<Server>)),
+            handle_atmos_updates.run_if(resource_exists::<Client>),
+        );
+        app.add_systems(
+            Update,
+            display::advance_gas_display
+                .before(debug_overlay::update_overlay_colors)
+                .run_if(resource_exists::<Client>),
         );
         app.init_resource::<PendingAtmosSyncs>();
         app.init_resource::<AtmosBroadcastTimers>();
+        app.init_resource::<GasDeltaEpsilon>();
+        register_tunables(app);
         // send_gas_grid_on_connect runs in NetworkReceive (after Drain) so
         // PlayerEvent::Joined is readable.
         app.configure_sets(NetworkReceive, AtmosSet::SendOnConnect);
@@ -536,7 +721,13 @@ impl<S: States + Copy> Plugin for AtmosphericsPlugin<S> {
         );
         app.add_systems(
             NetworkSend,
-            broadcast_gas_grid.run_if(resource_exists::<Server>),
+            (
+                broadcast_gas_grid,
+                broadcast_burning_cells,
+                broadcast_decompression,
+            )
+                .chain()
+                .run_if(resource_exists::<Server>),
         );
 
         // Register stream 2 (server→client atmospherics stream). Requires NetworkPlugin to be added first.
@@ -549,11 +740,14 @@ impl<S: States + Copy> Plugin for AtmosphericsPlugin<S> {
         let (sender, reader): (
             StreamSender<AtmosStreamMessage>,
             StreamReader<AtmosStreamMessage>,
-        ) = registry.register(StreamDef {
-            tag: ATMOS_STREAM_TAG,
-            name: "atmospherics",
-            direction: StreamDirection::ServerToClient,
-        });
+        ) = registry.register_with_budget(
+            StreamDef {
+                tag: ATMOS_STREAM_TAG,
+                name: "atmospherics",
+                direction: StreamDirection::ServerToClient,
+            },
+            StreamBudget::new(StreamPriority::Low, ATMOS_BYTES_PER_TICK),
+        );
         app.insert_resource(sender);
         app.insert_resource(reader);
 
@@ -567,39 +761,89 @@ impl<S: States + Copy> Plugin for AtmosphericsPlugin<S> {
 ///   [`GasGrid::from_moles_vec`] and inserts/replaces the resource.
 /// - [`AtmosStreamMessage::GasGridDelta`]: applies incremental cell updates to the
 ///   existing [`GasGrid`] resource; silently ignored when no grid is present yet.
+/// - [`AtmosStreamMessage::BurningCells`]: replaces [`BurningCells`].
+/// - [`AtmosStreamMessage::Decompression`]: writes a [`DecompressionEvent`].
+///
+/// Messages about an upper deck update its [`UpperDeckAtmos`] entry instead.
+/// Whenever a message about the main deck arrives, the resulting state
+/// becomes the new target of the smoothed [`GasDisplay`].
 fn handle_atmos_updates(
     mut commands: Commands,
     mut reader: ResMut<StreamReader<AtmosStreamMessage>>,
     gas_grid: Option<ResMut<GasGrid>>,
+    mut upper: ResMut<UpperDeckAtmos>,
+    mut display: ResMut<GasDisplay>,
+    mut burning: ResMut<BurningCells>,
+    mut decompression: MessageWriter<DecompressionEvent>,
 ) {
     // `pending` holds a newly-received full snapshot that hasn't been committed yet.
     // Deltas that arrive in the same batch are applied to it directly so that no
     // updates are dropped within a single drain cycle.
     let mut pending: Option<GasGrid> = None;
     let mut gas_grid = gas_grid;
+    let mut received = false;
     for msg in reader.drain() {
         match msg {
             AtmosStreamMessage::GasGridData {
+                deck,
                 width,
                 height,
                 gas_moles,
                 passable,
             } => match GasGrid::from_moles_vec(width, height, gas_moles, passable) {
                 Ok(new_grid) => {
-                    debug!("Received gas grid {}×{} from server", width, height);
-                    pending = Some(new_grid);
+                    debug!(
+                        "Received gas grid {}×{} of deck {deck} from server",
+                        width, height
+                    );
+                    if let Some(atmos) = upper.get_or_grow(Deck(deck)) {
+                        atmos.gas = new_grid;
+                    } else {
+                        received = true;
+                        pending = Some(new_grid);
+                    }
                 }
                 Err(e) => error!("Invalid gas grid data on stream {ATMOS_STREAM_TAG}: {e}"),
             },
-            AtmosStreamMessage::GasGridDelta { changes } => {
-                if let Some(ref mut grid) = pending {
+            AtmosStreamMessage::GasGridDelta { deck, changes } => {
+                if let Some(atmos) = upper.get_or_grow(Deck(deck)) {
+                    atmos.gas.apply_delta_changes(&changes);
+                } else if let Some(ref mut grid) = pending {
+                    received = true;
                     grid.apply_delta_changes(&changes);
                 } else if let Some(ref mut grid) = gas_grid {
+                    received = true;
                     grid.apply_delta_changes(&changes);
                 }
             }
+            AtmosStreamMessage::BurningCells { deck, cells } => {
+                let cells = cells
+                    .into_iter()
+                    .map(|(x, y)| IVec2::new(x as i32, y as i32))
+                    .collect();
+                if let Some(atmos) = upper.get_or_grow(Deck(deck)) {
+                    atmos.burning.0 = cells;
+                } else {
+                    received = true;
+                    burning.0 = cells;
+                }
+            }
+            AtmosStreamMessage::Decompression {
+                deck,
+                position: (x, y),
+                differential,
+            } => {
+                decompression.write(DecompressionEvent {
+                    deck: Deck(deck),
+                    position: IVec2::new(x as i32, y as i32),
+                    differential,
+                });
+            }
         }
     }
+    if received && let Some(latest) = pending.as_ref().or(gas_grid.as_deref()) {
+        display.retarget(latest.width(), latest.moles_vec());
+    }
     if let Some(new_grid) = pending {
         commands.insert_resource(new_grid);
     }
@@ -611,7 +855,8 @@ fn handle_atmos_updates(
 #[derive(Resource, Default)]
 struct PendingAtmosSyncs(Vec<ClientId>);
 
-/// Server-side system: sends a full gas grid snapshot + [`StreamReady`] to each joining client.
+/// Server-side system: sends a full gas grid snapshot of every deck + [`StreamReady`] to each
+/// joining client.
 /// Listens to the [`PlayerEvent::Joined`] lifecycle event so `AtmosphericsPlugin` is decoupled from
 /// internal network events.
 ///
@@ -621,6 +866,8 @@ fn send_gas_grid_on_connect(
     mut events: MessageReader<PlayerEvent>,
     atmos_sender: Option<Res<StreamSender<AtmosStreamMessage>>>,
     gas_grid: Option<Res<GasGrid>>,
+    upper: Res<UpperDeckAtmos>,
+    burning: Res<BurningCells>,
     mut module_ready: MessageWriter<ModuleReadySent>,
     mut pending: ResMut<PendingAtmosSyncs>,
 ) {
@@ -651,18 +898,26 @@ fn send_gas_grid_on_connect(
         return;
     };
 
+    let decks: Vec<(Deck, &GasGrid, &BurningCells)> =
+        std::iter::once((Deck::MAIN, grid, &*burning))
+            .chain(
+                upper
+                    .iter()
+                    .map(|(deck, atmos)| (deck, &atmos.gas, &atmos.burning)),
+            )
+            .collect();
     let clients = std::mem::take(&mut pending.0);
-    for from in clients {
-        let msg = AtmosStreamMessage::GasGridData {
-            width: grid.width(),
-            height: grid.height(),
-            gas_moles: grid.moles_vec(),
-            passable: grid.passable_vec().to_vec(),
-        };
+    'clients: for from in clients {
+        for &(deck, grid, burning) in &decks {
+            if let Err(e) = sender.send_to(from, &gas_grid_message(deck, grid)) {
+                error!("Failed to send GasGridData to ClientId({}): {}", from.0, e);
+                continue 'clients;
+            }
 
-        if let Err(e) = sender.send_to(from, &msg) {
-            error!("Failed to send GasGridData to ClientId({}): {}", from.0, e);
-            continue;
+            if let Err(e) = sender.send_to(from, &burning_cells_message(deck, burning)) {
+                error!("Failed to send BurningCells to ClientId({}): {}", from.0, e);
+                continue 'clients;
+            }
         }
 
         if let Err(e) = sender.send_stream_ready_to(from) {
@@ -671,26 +926,41 @@ fn send_gas_grid_on_connect(
         }
 
         info!(
-            "Sent gas grid snapshot {}×{} + StreamReady to ClientId({})",
+            "Sent gas grid snapshot {}×{} ({} deck(s)) + StreamReady to ClientId({})",
             grid.width(),
             grid.height(),
+            decks.len(),
             from.0
         );
         module_ready.write(ModuleReadySent { client: from });
     }
 }
 
-/// Moles-change threshold for including a cell in a [`GasGridDelta`].
+/// Default moles-change threshold for including a cell in a [`GasGridDelta`].
 /// Cells whose moles have changed by less than this amount since the last broadcast
 /// are omitted to reduce network traffic.
 const DELTA_EPSILON: f32 = 0.01;
 
+/// Moles-change threshold for including a cell in a [`GasGridDelta`]; the
+/// `atmospherics.delta_epsilon` tunable.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct GasDeltaEpsilon(pub f32);
+
+impl Default for GasDeltaEpsilon {
+    fn default() -> Self {
+        Self(DELTA_EPSILON)
+    }
+}
+
 /// Interval between full [`GasGridData`] snapshot broadcasts (seconds).
 const FULL_SNAPSHOT_INTERVAL: f32 = 2.0;
 
 /// Interval between incremental [`GasGridDelta`] broadcasts (seconds).
 /// 0.1 s → ~10 Hz update rate.
-const DELTA_INTERVAL: f32 = 0.1;
+pub(crate) const DELTA_INTERVAL: f32 = 0.1;
+
+/// Shortest delta interval the `atmospherics.delta_interval` tunable takes.
+const MIN_DELTA_INTERVAL: f32 = 0.02;
 
 /// Timers that drive the periodic gas grid replication broadcasts.
 #[derive(Resource)]
@@ -710,19 +980,69 @@ impl Default for AtmosBroadcastTimers {
     }
 }
 
-/// Server-side system: broadcasts gas grid replication messages to all connected clients.
+/// Registers the atmosphere's tunables.  The delta interval is replicated
+/// so that clients glide the displayed gas over the server's interval.
+fn register_tunables(app: &mut App) {
+    app.register_tunable(
+        "atmospherics.pressure_force_scale",
+        false,
+        |world| {
+            world
+                .get_resource::<AtmosInitConfig>()
+                .map(|config| config.pressure_force_scale)
+        },
+        |world, value| {
+            if let Some(mut live) = world.get_resource_mut::<PressureForceScale>() {
+                live.0 = value;
+            }
+            if let Some(mut init) = world.get_resource_mut::<AtmosInitConfig>() {
+                init.pressure_force_scale = value;
+            }
+        },
+    );
+    app.register_tunable(
+        "atmospherics.delta_interval",
+        true,
+        |world| {
+            world
+                .get_resource::<AtmosBroadcastTimers>()
+                .map(|timers| timers.delta.duration().as_secs_f32())
+        },
+        |world, value| {
+            if let Some(mut timers) = world.get_resource_mut::<AtmosBroadcastTimers>() {
+                let interval = Duration::from_secs_f32(value.max(MIN_DELTA_INTERVAL));
+                timers.delta.set_duration(interval);
+            }
+        },
+    );
+    app.register_tunable(
+        "atmospherics.delta_epsilon",
+        false,
+        |world| world.get_resource::<GasDeltaEpsilon>().map(|e| e.0),
+        |world, value| world.insert_resource(GasDeltaEpsilon(value.max(0.0))),
+    );
+}
+
+/// Server-side system: broadcasts gas grid replication messages of every deck to all
+/// connected clients.
 ///
 /// - Every [`DELTA_INTERVAL`] seconds (~10 Hz): computes a [`GasGridDelta`] of cells
-///   that have changed beyond [`DELTA_EPSILON`] since the last broadcast and sends it
+///   that have changed beyond [`GasDeltaEpsilon`] since the last broadcast and sends it
 ///   to all clients.  [`GasGrid::last_broadcast_moles`] is updated after each delta.
-/// - Every [`FULL_SNAPSHOT_INTERVAL`] seconds (~0.5 Hz): broadcasts a full
-///   [`GasGridData`] snapshot to resync clients.  [`GasGrid::last_broadcast_moles`] is
+/// - Every [`FULL_SNAPSHOT_INTERVAL`] seconds (~0.5 Hz), and when the map was
+///   reloaded: broadcasts a full [`GasGridData`] snapshot to resync clients.  [`GasGrid::last_broadcast_moles`] is
 ///   updated after the snapshot so the following deltas are relative to it.
+///
+/// Both go out as deferrable state frames.  A delta is skipped while stream 2
+/// is saturated; its changes stay pending against the old baseline and are
+/// folded into the next delta that is sent.
 fn broadcast_gas_grid(
     time: Res<Time>,
     mut timers: ResMut<AtmosBroadcastTimers>,
+    epsilon: Res<GasDeltaEpsilon>,
     atmos_sender: Option<Res<StreamSender<AtmosStreamMessage>>>,
     gas_grid: Option<ResMut<GasGrid>>,
+    mut upper: Option<ResMut<UpperDeckAtmos>>,
 ) {
     timers.full_snapshot.tick(time.delta());
     timers.delta.tick(time.delta());
@@ -733,31 +1053,240 @@ fn broadcast_gas_grid(
     let Some(mut grid) = gas_grid else {
         return;
     };
+    let decks =
+        (0..=upper.as_ref().map_or(0, |upper| upper.0.len())).map(|index| Deck(index as u8));
 
     // Full snapshot broadcast takes priority; also resets the delta baseline.
-    if timers.full_snapshot.just_finished() {
-        let msg = AtmosStreamMessage::GasGridData {
-            width: grid.width(),
-            height: grid.height(),
-            gas_moles: grid.moles_vec(),
-            passable: grid.passable_vec().to_vec(),
-        };
-        match sender.broadcast(&msg) {
-            Ok(()) => grid.update_last_broadcast_moles(),
-            Err(e) => error!("Failed to broadcast GasGridData: {e}"),
+    // A grid loaded again over the running world (see `world::reload_map`)
+    // goes out at once.
+    if timers.full_snapshot.just_finished() || grid.is_added() {
+        for deck in decks {
+            let Some(grid) = deck_gas_mut(&mut grid, upper.as_deref_mut(), deck) else {
+                continue;
+            };
+            match sender.broadcast_state(&gas_grid_message(deck, grid)) {
+                Ok(()) => grid.update_last_broadcast_moles(),
+                Err(e) => error!("Failed to broadcast GasGridData: {e}"),
+            }
         }
         return;
     }
 
     // Incremental delta broadcast.
-    if timers.delta.just_finished() {
-        let changes = grid.compute_delta_changes(DELTA_EPSILON);
-        if !changes.is_empty() {
-            let msg = AtmosStreamMessage::GasGridDelta { changes };
-            match sender.broadcast(&msg) {
-                Ok(()) => grid.update_last_broadcast_moles(),
+    if timers.delta.just_finished() && !sender.saturation().is_saturated() {
+        for deck in decks {
+            let current = if deck == Deck::MAIN {
+                Some(&*grid)
+            } else {
+                upper
+                    .as_deref()
+                    .and_then(|upper| upper.get(deck))
+                    .map(|atmos| &atmos.gas)
+            };
+            let Some(changes) = current
+                .map(|current| current.compute_delta_changes(epsilon.0))
+                .filter(|changes| !changes.is_empty())
+            else {
+                continue;
+            };
+            let msg = AtmosStreamMessage::GasGridDelta {
+                deck: deck.0,
+                changes,
+            };
+            match sender.broadcast_state(&msg) {
+                Ok(()) => {
+                    if let Some(grid) = deck_gas_mut(&mut grid, upper.as_deref_mut(), deck) {
+                        grid.update_last_broadcast_moles();
+                    }
+                }
                 Err(e) => error!("Failed to broadcast GasGridDelta: {e}"),
             }
         }
     }
 }
+
+/// The gas grid of `deck`: `main` for the main deck, its [`UpperDeckAtmos`]
+/// entry otherwise.
+fn deck_gas_mut<'a>(
+    main: &'a mut GasGrid,
+    upper: Option<&'a mut UpperDeckAtmos>,
+    deck: Deck,
+) -> Option<&'a mut GasGrid> {
+    if deck == Deck::MAIN {
+        Some(main)
+    } else {
+        upper?.get_mut(deck).map(|atmos| &mut atmos.gas)
+    }
+}
+
+/// The full [`AtmosStreamMessage::GasGridData`] snapshot of `deck`'s `grid`.
+fn gas_grid_message(deck: Deck, grid: &GasGrid) -> AtmosStreamMessage {
+    AtmosStreamMessage::GasGridData {
+        deck: deck.0,
+        width: grid.width(),
+        height: grid.height(),
+        gas_moles: grid.moles_vec(),
+        passable: grid.passable_vec().to_vec(),
+    }
+}
+
+/// The [`AtmosStreamMessage::BurningCells`] message for `deck`'s `burning`.
+fn burning_cells_message(deck: Deck, burning: &BurningCells) -> AtmosStreamMessage {
+    AtmosStreamMessage::BurningCells {
+        deck: deck.0,
+        cells: burning
+            .0
+            .iter()
+            .map(|cell| (cell.x as u16, cell.y as u16))
+            .collect(),
+    }
+}
+
+/// Server-side system: broadcasts the [`BurningCells`] of each deck whenever
+/// its set changes and alongside every full gas grid snapshot, so a client
+/// that missed a deferred update is resynced.  Upper decks' sets are compared
+/// against the ones last sent, kept in `sent`.
+///
+/// Runs after [`broadcast_gas_grid`], which ticks the snapshot timer.
+fn broadcast_burning_cells(
+    timers: Res<AtmosBroadcastTimers>,
+    atmos_sender: Option<Res<StreamSender<AtmosStreamMessage>>>,
+    burning: Res<BurningCells>,
+    upper: Res<UpperDeckAtmos>,
+    mut sent: Local<Vec<HashSet<IVec2>>>,
+) {
+    let Some(sender) = atmos_sender.as_deref() else {
+        return;
+    };
+    let snapshot = timers.full_snapshot.just_finished();
+    if (burning.is_changed() || snapshot)
+        && let Err(e) = sender.broadcast_state(&burning_cells_message(Deck::MAIN, &burning))
+    {
+        error!("Failed to broadcast BurningCells: {e}");
+    }
+    sent.resize_with(upper.0.len(), HashSet::new);
+    for ((deck, atmos), sent) in upper.iter().zip(sent.iter_mut()) {
+        if !snapshot && atmos.burning.0 == *sent {
+            continue;
+        }
+        match sender.broadcast_state(&burning_cells_message(deck, &atmos.burning)) {
+            Ok(()) => sent.clone_from(&atmos.burning.0),
+            Err(e) => error!("Failed to broadcast BurningCells of deck {}: {e}", deck.0),
+        }
+    }
+}
+
+/// Server-side system: tells every client about each [`DecompressionEvent`]
+/// written this tick.
+fn broadcast_decompression(
+    mut events: MessageReader<DecompressionEvent>,
+    atmos_sender: Option<Res<StreamSender<AtmosStreamMessage>>>,
+) {
+    let Some(sender) = atmos_sender.as_deref() else {
+        events.clear();
+        return;
+    };
+    for event in events.read() {
+        let msg = AtmosStreamMessage::Decompression {
+            deck: event.deck.0,
+            position: (event.position.x as u16, event.position.y as u16),
+            differential: event.differential,
+        };
+        if let Err(e) = sender.broadcast(&msg) {
+            error!("Failed to broadcast Decompression: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pressure_app(grid: GasGrid) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.insert_resource(grid);
+        app.init_resource::<SpatialIndex>();
+        app.add_systems(
+            Update,
+            (things::update_spatial_index, apply_pressure_forces).chain(),
+        );
+        app
+    }
+
+    fn spawn_body(app: &mut App, x: f32, z: f32) -> Entity {
+        app.world_mut()
+            .spawn((
+                things::Thing { kind: 1 },
+                RigidBody::Dynamic,
+                Transform::from_xyz(x, 0.5, z),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn pressure_forces_only_touch_bodies_in_a_gradient() {
+        let mut grid = GasGrid::new(8, 1);
+        for x in 0..8 {
+            grid.set_moles(IVec2::new(x, 0), 100.0);
+        }
+        grid.step(0.05);
+        grid.set_moles(IVec2::new(0, 0), 0.0);
+        let mut app = pressure_app(grid);
+        let near_breach = spawn_body(&mut app, 1.0, 0.0);
+        let far_away = spawn_body(&mut app, 6.0, 0.0);
+        let deck_above = spawn_body(&mut app, 1.0, 0.0);
+        app.world_mut()
+            .get_mut::<Transform>(deck_above)
+            .unwrap()
+            .translation
+            .y += tiles::DECK_HEIGHT;
+
+        app.update();
+        let force = app.world().get::<ConstantForce>(near_breach).unwrap().0;
+        assert!(
+            force.x < 0.0,
+            "expected a push towards the breach, got {force:?}"
+        );
+        assert!(app.world().get::<PressureDriven>(near_breach).is_some());
+        assert!(app.world().get::<ConstantForce>(far_away).is_none());
+        assert!(app.world().get::<ConstantForce>(deck_above).is_none());
+
+        // Once the room is sealed and even again, the force is taken away.
+        app.world_mut()
+            .resource_mut::<GasGrid>()
+            .set_moles(IVec2::new(0, 0), 100.0);
+        app.world_mut().resource_mut::<GasGrid>().step(0.05);
+        app.update();
+        assert!(app.world().get::<ConstantForce>(near_breach).is_none());
+        assert!(app.world().get::<PressureDriven>(near_breach).is_none());
+    }
+
+    #[test]
+    fn upper_deck_gradients_push_bodies_on_that_deck() {
+        let mut even = GasGrid::new(8, 1);
+        for x in 0..8 {
+            even.set_moles(IVec2::new(x, 0), 100.0);
+        }
+        even.step(0.05);
+        let mut breached = even.clone();
+        breached.set_moles(IVec2::new(0, 0), 0.0);
+        let mut app = pressure_app(even);
+        app.insert_resource(UpperDeckAtmos(vec![DeckAtmos::new(breached)]));
+        let main_deck = spawn_body(&mut app, 1.0, 0.0);
+        let deck_above = spawn_body(&mut app, 1.0, 0.0);
+        app.world_mut()
+            .get_mut::<Transform>(deck_above)
+            .unwrap()
+            .translation
+            .y += tiles::DECK_HEIGHT;
+
+        app.update();
+        let force = app.world().get::<ConstantForce>(deck_above).unwrap().0;
+        assert!(
+            force.x < 0.0,
+            "expected a push towards the breach, got {force:?}"
+        );
+        assert!(app.world().get::<ConstantForce>(main_deck).is_none());
+    }
+}
@@ -0,0 +1,119 @@
+//! Air vents: things that pump air into the cell they stand in.
+//!
+//! A [`Vent`] refills its cell towards the map's standard pressure
+//! ([`AtmosInitConfig::standard_pressure`]) at a fixed rate and never
+//! pumps past it, so rooms with a vent recover from small leaks while a
+//! hull breach still wins.  Vents on upper decks fill their deck's grid.
+//! Vents that draw power are switched by L3 `power` through
+//! [`Vent::powered`].
+
+use bevy::prelude::*;
+use things::cell_of;
+use tiles::Deck;
+
+use crate::{AtmosInitConfig, AtmosSimPaused, GasGrid, UpperDeckAtmos, deck_gas_mut};
+
+/// Pumps air into the cell it stands in, up to standard pressure.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Vent {
+    /// Moles pumped per second while below standard pressure.
+    pub rate: f32,
+    /// Whether the vent has power.  Kept up to date by L3 `power` for vents
+    /// that draw it; an unpowered vent pumps nothing.
+    pub powered: bool,
+}
+
+impl Vent {
+    /// A powered vent pumping `rate` moles per second.
+    pub fn new(rate: f32) -> Self {
+        Self {
+            rate,
+            powered: true,
+        }
+    }
+}
+
+/// Server-side system: every powered [`Vent`] tops up its cell on its
+/// deck's grid for one fixed tick.  Skipped while the simulation is paused.
+pub(crate) fn run_vents(
+    time: Res<Time<Fixed>>,
+    paused: Res<AtmosSimPaused>,
+    config: Res<AtmosInitConfig>,
+    mut gas_grid: Option<ResMut<GasGrid>>,
+    mut upper: Option<ResMut<UpperDeckAtmos>>,
+    vents: Query<(&Vent, &GlobalTransform)>,
+) {
+    if paused.0 {
+        return;
+    }
+    let Some(grid) = gas_grid.as_deref_mut() else {
+        return;
+    };
+    let dt = time.delta_secs();
+    for (vent, transform) in vents.iter() {
+        if !vent.powered {
+            continue;
+        }
+        let deck = Deck::of_height(transform.translation().y);
+        let Some(grid) = deck_gas_mut(grid, upper.as_deref_mut(), deck) else {
+            continue;
+        };
+        let cell = cell_of(transform.translation());
+        let Some(moles) = grid.pressure_at(cell) else {
+            continue;
+        };
+        if grid.is_passable(cell) && moles < config.standard_pressure {
+            grid.set_moles(cell, (moles + vent.rate * dt).min(config.standard_pressure));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn powered_vents_refill_their_cell_up_to_standard_pressure() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<AtmosSimPaused>();
+        app.insert_resource(AtmosInitConfig {
+            standard_pressure: 100.0,
+            pressure_force_scale: 1.0,
+            diffusion_rate: 0.25,
+        });
+        let mut grid = GasGrid::new(2, 1);
+        grid.set_moles(IVec2::new(0, 0), 90.0);
+        app.insert_resource(grid);
+        app.add_systems(FixedUpdate, run_vents);
+        let vent = app
+            .world_mut()
+            .spawn((Vent::new(30.0), GlobalTransform::from_xyz(0.0, 0.5, 0.0)))
+            .id();
+        app.world_mut()
+            .spawn((Vent::new(30.0), GlobalTransform::from_xyz(1.0, 0.5, 0.0)))
+            .get_mut::<Vent>()
+            .unwrap()
+            .powered = false;
+        let step = |app: &mut App| {
+            app.world_mut()
+                .resource_mut::<Time<Fixed>>()
+                .advance_by(std::time::Duration::from_millis(500));
+            app.world_mut().run_schedule(FixedUpdate);
+        };
+
+        step(&mut app);
+        let grid = app.world().resource::<GasGrid>();
+        assert_eq!(grid.pressure_at(IVec2::new(0, 0)), Some(100.0));
+        assert_eq!(grid.pressure_at(IVec2::new(1, 0)), Some(0.0));
+
+        app.world_mut().get_mut::<Vent>(vent).unwrap().powered = false;
+        app.world_mut()
+            .resource_mut::<GasGrid>()
+            .set_moles(IVec2::new(0, 0), 50.0);
+        step(&mut app);
+        let grid = app.world().resource::<GasGrid>();
+        assert_eq!(grid.pressure_at(IVec2::new(0, 0)), Some(50.0));
+    }
+}
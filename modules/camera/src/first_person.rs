@@ -0,0 +1,264 @@
+//! First-person view.
+//!
+//! [`Action::ToggleFirstPerson`] puts the camera at the local creature's
+//! eyes, looking along the [`CameraRig`] yaw.  While it is there the
+//! creature's own mesh and arms are moved to a render layer the camera does
+//! not see, and its [`HandSlot`] is moved in front of the camera so the held item
+//! (a child of the hand) shows in the lower right of the view.  The near
+//! clip plane is pulled in so the item is not cut off.  Everything is
+//! undone when the view is left; none of it is replicated.
+
+use animation::IkChain;
+use bevy::camera::visibility::RenderLayers;
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use player::PlayerControlled;
+use things::HandSlot;
+
+use crate::{CameraMode, CameraRig, FollowCamera};
+
+/// Height of the eyes above the creature's origin.
+pub const EYE_HEIGHT: f32 = 0.6;
+
+/// Downward tilt of the view in radians, so the floor ahead stays in sight.
+const LOOK_PITCH: f32 = -0.3;
+
+/// Factor applied to the follow speed in first person, so the view does
+/// not trail behind the creature.
+pub(crate) const CATCH_UP: f32 = 6.0;
+
+/// Camera-local position of the hand: low, to the right and just past the
+/// near plane.
+const VIEW_HAND_OFFSET: Vec3 = Vec3::new(0.25, -0.2, -0.45);
+
+/// Near clip plane while in first person.
+const FIRST_PERSON_NEAR: f32 = 0.02;
+
+/// Render layer the local creature's mesh is moved to; the camera only
+/// renders the default layer.
+const HIDDEN_LAYER: usize = 1;
+
+/// Marks the local player's [`HandSlot`] while it is held in front of the
+/// first-person camera, with the transform to restore afterwards.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ViewModelHand {
+    pub rest: Transform,
+}
+
+/// Near plane the camera had before first person.
+#[derive(Resource)]
+pub(crate) struct SavedNearPlane(f32);
+
+/// Eye position and a point to look at for a first-person `rig`.
+pub(crate) fn eye_and_look(rig: &CameraRig) -> (Vec3, Vec3) {
+    let eye = rig.focus + Vec3::Y * EYE_HEIGHT;
+    let direction = Quat::from_euler(EulerRot::YXZ, rig.yaw, LOOK_PITCH, 0.0) * Vec3::NEG_Z;
+    (eye, eye + direction)
+}
+
+/// System that switches between first person and the follow camera on
+/// [`Action::ToggleFirstPerson`].
+pub(crate) fn toggle_first_person(input: ActionInput, mut rig: ResMut<CameraRig>) {
+    if !input.just_pressed(Action::ToggleFirstPerson) {
+        return;
+    }
+    rig.mode = if rig.mode == CameraMode::FirstPerson {
+        CameraMode::Follow
+    } else {
+        CameraMode::FirstPerson
+    };
+    info!("Camera mode: {:?}", rig.mode);
+}
+
+/// System that applies or undoes the first-person adjustments: the near
+/// plane, the hidden creature mesh and the hand in front of the camera.
+///
+/// Runs after the camera has been moved for the frame, so the hand keeps
+/// up with it.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub(crate) fn update_first_person_view(
+    mut commands: Commands,
+    rig: Res<CameraRig>,
+    saved_near: Option<Res<SavedNearPlane>>,
+    mut camera_q: Query<(&Transform, &mut Projection), With<FollowCamera>>,
+    player_q: Query<
+        (Entity, &Transform, Option<&Children>, Has<RenderLayers>),
+        (With<PlayerControlled>, Without<FollowCamera>),
+    >,
+    mut hand_q: Query<
+        (Entity, &mut Transform, Option<&ViewModelHand>),
+        (
+            With<HandSlot>,
+            Without<PlayerControlled>,
+            Without<FollowCamera>,
+        ),
+    >,
+    arms: Query<&IkChain>,
+    descendants: Query<&Children>,
+) {
+    let first_person = rig.mode == CameraMode::FirstPerson;
+    let Ok((camera_tf, mut projection)) = camera_q.single_mut() else {
+        return;
+    };
+
+    if first_person != saved_near.is_some()
+        && let Projection::Perspective(perspective) = projection.as_mut()
+    {
+        if let Some(saved) = saved_near {
+            perspective.near = saved.0;
+            commands.remove_resource::<SavedNearPlane>();
+        } else {
+            commands.insert_resource(SavedNearPlane(perspective.near));
+            perspective.near = FIRST_PERSON_NEAR;
+        }
+    }
+
+    for (creature, creature_tf, children, hidden) in player_q.iter() {
+        if first_person && !hidden {
+            commands
+                .entity(creature)
+                .insert(RenderLayers::layer(HIDDEN_LAYER));
+        } else if !first_person && hidden {
+            commands.entity(creature).remove::<RenderLayers>();
+        }
+        if first_person != hidden {
+            let arm_parts = children
+                .iter()
+                .flat_map(|c| c.iter())
+                .filter_map(|child| arms.get(child).ok())
+                .flat_map(|arm| descendants.iter_descendants(arm.root));
+            for part in arm_parts {
+                if first_person {
+                    commands
+                        .entity(part)
+                        .insert(RenderLayers::layer(HIDDEN_LAYER));
+                } else {
+                    commands.entity(part).remove::<RenderLayers>();
+                }
+            }
+        }
+
+        for child in children.iter().flat_map(|c| c.iter()) {
+            let Ok((hand, mut hand_tf, view_model)) = hand_q.get_mut(child) else {
+                continue;
+            };
+            if first_person {
+                if view_model.is_none() {
+                    commands
+                        .entity(hand)
+                        .insert(ViewModelHand { rest: *hand_tf });
+                }
+                let world = camera_tf.transform_point(VIEW_HAND_OFFSET);
+                hand_tf.translation = creature_tf
+                    .compute_affine()
+                    .inverse()
+                    .transform_point3(world);
+                hand_tf.rotation = creature_tf.rotation.inverse() * camera_tf.rotation;
+            } else if let Some(view_model) = view_model {
+                *hand_tf = view_model.rest;
+                commands.entity(hand).remove::<ViewModelHand>();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CameraConfig, camera_follow_system};
+    use input::InputMap;
+    use things::{HAND_OFFSET, HandSide};
+
+    fn near(app: &mut App) -> f32 {
+        let mut q = app
+            .world_mut()
+            .query_filtered::<&Projection, With<FollowCamera>>();
+        match q.single(app.world()).unwrap() {
+            Projection::Perspective(p) => p.near,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn first_person_hides_the_creature_and_holds_the_hand_in_view() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<InputMap>();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.insert_resource(CameraConfig {
+            follow_speed: 1.0e9,
+            ..default()
+        });
+        app.init_resource::<CameraRig>();
+        app.add_systems(
+            Update,
+            (
+                toggle_first_person,
+                camera_follow_system,
+                update_first_person_view,
+            )
+                .chain(),
+        );
+        let creature_tf = Transform::from_xyz(2.0, 0.8, 3.0);
+        let creature = app.world_mut().spawn((creature_tf, PlayerControlled)).id();
+        let hand = app
+            .world_mut()
+            .spawn((
+                HandSlot {
+                    side: HandSide::Right,
+                },
+                Transform::from_translation(HAND_OFFSET),
+                ChildOf(creature),
+            ))
+            .id();
+        let arm = app.world_mut().spawn(ChildOf(creature)).id();
+        let upper_arm = app.world_mut().spawn(ChildOf(arm)).id();
+        let arm_mesh = app.world_mut().spawn(ChildOf(upper_arm)).id();
+        app.world_mut()
+            .entity_mut(arm)
+            .insert(IkChain::new(upper_arm, arm_mesh, arm_mesh));
+        app.world_mut().spawn((
+            FollowCamera,
+            Transform::default(),
+            Projection::Perspective(PerspectiveProjection::default()),
+        ));
+        let default_near = near(&mut app);
+        app.update();
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyV);
+        app.update();
+
+        assert!(app.world().get::<RenderLayers>(creature).is_some());
+        assert!(app.world().get::<RenderLayers>(arm_mesh).is_some());
+        assert!(app.world().get::<ViewModelHand>(hand).is_some());
+        assert_eq!(near(&mut app), FIRST_PERSON_NEAR);
+        let mut cameras = app
+            .world_mut()
+            .query_filtered::<&Transform, With<FollowCamera>>();
+        let camera_tf = *cameras.single(app.world()).unwrap();
+        assert!(
+            (camera_tf.translation - (creature_tf.translation + Vec3::Y * EYE_HEIGHT)).length()
+                < 1e-3
+        );
+        let hand_world =
+            creature_tf.transform_point(app.world().get::<Transform>(hand).unwrap().translation);
+        assert!((hand_world - camera_tf.transform_point(VIEW_HAND_OFFSET)).length() < 1e-3);
+
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.release(KeyCode::KeyV);
+        keys.clear();
+        keys.press(KeyCode::KeyV);
+        app.update();
+
+        assert!(app.world().get::<RenderLayers>(creature).is_none());
+        assert!(app.world().get::<RenderLayers>(arm_mesh).is_none());
+        assert!(app.world().get::<ViewModelHand>(hand).is_none());
+        assert_eq!(
+            app.world().get::<Transform>(hand).unwrap().translation,
+            HAND_OFFSET
+        );
+        assert_eq!(near(&mut app), default_near);
+    }
+}
@@ -1,18 +1,68 @@
+//! The in-game camera.
+//!
+//! The camera looks at a focus point from [`CameraConfig::offset`], turned
+//! around the vertical axis by the [`CameraRig`]'s yaw and scaled by its
+//! zoom.  In [`CameraMode::Follow`] the focus is the [`PlayerControlled`]
+//! creature; in [`CameraMode::Free`] it stays put and is panned with the
+//! pan actions or by pushing the cursor against the window edge.
+//! Spectators may also follow other players, see [`spectate`].  The
+//! camera eases towards where the rig says it should be, so mode switches,
+//! zooming and orbiting are smooth.  [`CameraMode::FirstPerson`] looks out
+//! of the creature's eyes instead; see [`first_person`].
+
+use atmospherics::DecompressionEvent;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::prelude::*;
 use bevy::state::state_scoped::DespawnOnExit;
+use bevy::window::PrimaryWindow;
+use input::{Action, ActionInput, InputMap};
 use player::PlayerControlled;
+use tiles::Deck;
+
+pub mod first_person;
+pub mod spectate;
+
+pub use first_person::ViewModelHand;
+
+/// Largest angle, in radians, the camera is knocked off target at full
+/// trauma.
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+
+/// Trauma shed per second.
+const SHAKE_DECAY: f32 = 1.2;
+
+/// Distance in tiles beyond which a decompression is not felt.
+const DECOMPRESSION_SHAKE_RANGE: f32 = 12.0;
+
+/// Pressure difference, in moles, of a decompression that shakes the camera
+/// at full strength when the player stands on it.
+const FULL_SHAKE_DIFFERENTIAL: f32 = 100.0;
+
+/// Scroll distance, in pixels, that counts as one wheel notch.
+const PIXELS_PER_LINE: f32 = 20.0;
 
-/// Marker component for the follow camera.
+/// Marker component for the in-game camera.
 #[derive(Component)]
 pub struct FollowCamera;
 
 /// Camera configuration for smooth following behavior.
-#[derive(Resource)]
+#[derive(Resource, Debug, Clone)]
 pub struct CameraConfig {
     /// How quickly the camera follows the target (higher values = faster following)
     pub follow_speed: f32,
-    /// Fixed offset from the target position
+    /// Offset from the focus point at zoom 1.0 and yaw 0.0
     pub offset: Vec3,
+    /// Zoom change per mouse wheel notch
+    pub zoom_step: f32,
+    /// Closest and farthest zoom, as factors of `offset`
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    /// Orbit speed in radians per second
+    pub orbit_speed: f32,
+    /// Free camera pan speed in world units per second at zoom 1.0
+    pub pan_speed: f32,
+    /// Distance in logical pixels from the window edge that pans the free camera
+    pub edge_pan_margin: f32,
 }
 
 impl Default for CameraConfig {
@@ -22,10 +72,91 @@ impl Default for CameraConfig {
             // Position camera at roughly 50 degree angle looking down
             // Offset: 8 units back, 10 units up from player position
             offset: Vec3::new(0.0, 10.0, 8.0),
+            zoom_step: 0.1,
+            min_zoom: 0.4,
+            max_zoom: 2.5,
+            orbit_speed: 2.0,
+            pan_speed: 12.0,
+            edge_pan_margin: 8.0,
         }
     }
 }
 
+/// What the camera's focus point follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum CameraMode {
+    /// Centred on the local player's creature.
+    #[default]
+    Follow,
+    /// Left where it is and panned by hand.
+    Free,
+    /// At the player's eyes, looking along the yaw, with the held item in
+    /// view.  Client-only: nothing about it is replicated.
+    FirstPerson,
+    /// Centred on another player's creature, for spectators.
+    Spectate(Entity),
+}
+
+/// Where the camera wants to be.  [`camera_follow_system`] eases the
+/// [`FollowCamera`] towards it every frame.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct CameraRig {
+    pub mode: CameraMode,
+    /// Point the camera looks at and orbits around.
+    pub focus: Vec3,
+    /// Rotation of the offset around the vertical axis, in radians.
+    pub yaw: f32,
+    /// Factor applied to the offset; smaller is closer.
+    pub zoom: f32,
+    /// Point the camera currently looks at, easing towards `focus`.
+    look_at: Vec3,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self::looking_at(Vec3::ZERO)
+    }
+}
+
+impl CameraRig {
+    /// A rig in follow mode focused on `focus`.
+    pub fn looking_at(focus: Vec3) -> Self {
+        Self {
+            mode: CameraMode::Follow,
+            focus,
+            yaw: 0.0,
+            zoom: 1.0,
+            look_at: focus,
+        }
+    }
+
+    /// Camera position the rig aims for with `offset` from the config.
+    pub fn eye(&self, offset: Vec3) -> Vec3 {
+        self.focus + Quat::from_rotation_y(self.yaw) * offset * self.zoom
+    }
+
+    /// Horizontal direction of `screen` (x right, y down) under the current
+    /// yaw, for panning.
+    fn ground_direction(&self, screen: Vec2) -> Vec3 {
+        Quat::from_rotation_y(self.yaw) * Vec3::new(screen.x, 0.0, screen.y)
+    }
+}
+
+/// Camera shake, driven by a `trauma` level in `0.0..=1.0` that decays over
+/// time.  The shake grows with the square of the trauma, so small knocks
+/// stay subtle.
+#[derive(Resource, Debug, Default)]
+pub struct CameraShake {
+    pub trauma: f32,
+}
+
+impl CameraShake {
+    /// Adds `amount` of trauma, capped at `1.0`.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount.max(0.0)).min(1.0);
+    }
+}
+
 /// Resource storing the active state value for `DespawnOnExit`.
 #[derive(Resource, Clone, Copy)]
 struct CameraActiveState<S: States>(S);
@@ -43,18 +174,47 @@ impl<S: States + Copy> CameraPlugin<S> {
 impl<S: States + Copy> Plugin for CameraPlugin<S> {
     fn build(&self, app: &mut App) {
         let state = self.state;
-        app.insert_resource(CameraConfig::default());
+        app.register_type::<CameraRig>();
+        app.init_resource::<CameraConfig>();
+        app.init_resource::<CameraRig>();
+        app.init_resource::<InputMap>();
         app.insert_resource(CameraActiveState(state));
+        app.init_resource::<CameraShake>();
+        app.add_message::<DecompressionEvent>();
+        app.add_message::<MouseWheel>();
         app.add_systems(OnEnter(state), spawn_camera::<S>);
-        app.add_systems(Update, camera_follow_system.run_if(in_state(state)));
+        app.add_systems(
+            Update,
+            (
+                toggle_camera_mode,
+                first_person::toggle_first_person,
+                spectate::cycle_spectate_target,
+                spectate::drop_lost_spectate_target,
+                zoom_camera,
+                orbit_camera,
+                pan_camera,
+                shake_on_decompression,
+                camera_follow_system,
+                apply_camera_shake,
+                first_person::update_first_person_view,
+            )
+                .chain()
+                .run_if(in_state(state)),
+        );
     }
 }
 
-/// Spawns the 3D follow camera and directional lighting when entering the active state.
-fn spawn_camera<S: States + Copy>(mut commands: Commands, active_state: Res<CameraActiveState<S>>) {
+/// Spawns the 3D follow camera and directional lighting when entering the
+/// active state, and resets the [`CameraRig`] to follow mode.
+fn spawn_camera<S: States + Copy>(
+    mut commands: Commands,
+    active_state: Res<CameraActiveState<S>>,
+    mut rig: ResMut<CameraRig>,
+) {
     // Start camera at default position (will move to player in first frame)
     let camera_pos = Vec3::new(6.0, 10.0, 10.0);
     let look_target = Vec3::new(6.0, 0.0, 5.0);
+    *rig = CameraRig::looking_at(look_target);
 
     commands.spawn((
         Camera3d::default(),
@@ -79,34 +239,192 @@ fn spawn_camera<S: States + Copy>(mut commands: Commands, active_state: Res<Came
     ));
 }
 
-/// System that smoothly follows the PlayerControlled entity.
+/// System that switches the [`CameraRig`] between follow and free mode on
+/// [`Action::ToggleCameraMode`].  The free camera starts where the follow
+/// camera was looking.
+fn toggle_camera_mode(input: ActionInput, mut rig: ResMut<CameraRig>) {
+    if !input.just_pressed(Action::ToggleCameraMode) {
+        return;
+    }
+    rig.mode = match rig.mode {
+        CameraMode::Free => CameraMode::Follow,
+        CameraMode::Follow | CameraMode::FirstPerson | CameraMode::Spectate(_) => CameraMode::Free,
+    };
+    info!("Camera mode: {:?}", rig.mode);
+}
+
+/// System that zooms the [`CameraRig`] with the mouse wheel.
+fn zoom_camera(
+    mut wheel: MessageReader<MouseWheel>,
+    config: Res<CameraConfig>,
+    mut rig: ResMut<CameraRig>,
+) {
+    let notches: f32 = wheel
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    if notches != 0.0 {
+        rig.zoom = (rig.zoom - notches * config.zoom_step).clamp(config.min_zoom, config.max_zoom);
+    }
+}
+
+/// System that turns the [`CameraRig`] around its focus with the orbit
+/// actions.
+fn orbit_camera(
+    time: Res<Time>,
+    input: ActionInput,
+    config: Res<CameraConfig>,
+    mut rig: ResMut<CameraRig>,
+) {
+    let mut turn = 0.0;
+    if input.pressed(Action::OrbitCameraLeft) {
+        turn -= 1.0;
+    }
+    if input.pressed(Action::OrbitCameraRight) {
+        turn += 1.0;
+    }
+    if turn != 0.0 {
+        rig.yaw += turn * config.orbit_speed * time.delta_secs();
+    }
+}
+
+/// System that pans the focus of a free [`CameraRig`] with the pan actions
+/// or while the cursor touches the window edge.
+fn pan_camera(
+    time: Res<Time>,
+    input: ActionInput,
+    config: Res<CameraConfig>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut rig: ResMut<CameraRig>,
+) {
+    if rig.mode != CameraMode::Free {
+        return;
+    }
+    let mut screen = Vec2::ZERO;
+    for (action, direction) in [
+        (Action::PanCameraUp, Vec2::NEG_Y),
+        (Action::PanCameraDown, Vec2::Y),
+        (Action::PanCameraLeft, Vec2::NEG_X),
+        (Action::PanCameraRight, Vec2::X),
+    ] {
+        if input.pressed(action) {
+            screen += direction;
+        }
+    }
+    if let Ok(window) = window_q.single()
+        && window.focused
+        && let Some(cursor) = window.cursor_position()
+    {
+        let margin = config.edge_pan_margin;
+        let size = window.size();
+        if cursor.x <= margin {
+            screen.x -= 1.0;
+        } else if cursor.x >= size.x - margin {
+            screen.x += 1.0;
+        }
+        if cursor.y <= margin {
+            screen.y -= 1.0;
+        } else if cursor.y >= size.y - margin {
+            screen.y += 1.0;
+        }
+    }
+    if screen != Vec2::ZERO {
+        let step = config.pan_speed * rig.zoom * time.delta_secs();
+        let direction = rig.ground_direction(screen.normalize());
+        rig.focus += direction * step;
+    }
+}
+
+/// System that moves the focus of a following or first-person
+/// [`CameraRig`] onto the PlayerControlled entity, or of a spectating one
+/// onto its target, and eases the camera towards the rig.
 fn camera_follow_system(
     time: Res<Time>,
     config: Res<CameraConfig>,
+    mut rig: ResMut<CameraRig>,
     player_query: Query<&Transform, (With<PlayerControlled>, Without<FollowCamera>)>,
+    target_query: Query<&Transform, Without<FollowCamera>>,
     mut camera_query: Query<&mut Transform, With<FollowCamera>>,
 ) {
-    // Get player position
-    let Ok(player_transform) = player_query.single() else {
-        return;
+    let focus = match rig.mode {
+        CameraMode::Free => None,
+        CameraMode::Spectate(target) => target_query.get(target).ok(),
+        CameraMode::Follow | CameraMode::FirstPerson => player_query.single().ok(),
     };
+    if let Some(focus) = focus {
+        rig.focus = focus.translation;
+    }
 
-    // Get camera transform
     let Ok(mut camera_transform) = camera_query.single_mut() else {
         return;
     };
 
-    // Calculate target camera position (player position + offset)
-    let target_position = player_transform.translation + config.offset;
-
-    // Smoothly interpolate camera position using lerp
-    let lerp_factor = (config.follow_speed * time.delta_secs()).min(1.0);
+    // Smoothly interpolate camera position and look point using lerp
+    let (target_position, target_look, speed) = if rig.mode == CameraMode::FirstPerson {
+        let (eye, look) = first_person::eye_and_look(&rig);
+        (eye, look, config.follow_speed * first_person::CATCH_UP)
+    } else {
+        (rig.eye(config.offset), rig.focus, config.follow_speed)
+    };
+    let lerp_factor = (speed * time.delta_secs()).min(1.0);
     camera_transform.translation = camera_transform
         .translation
         .lerp(target_position, lerp_factor);
+    rig.look_at = rig.look_at.lerp(target_look, lerp_factor);
+    camera_transform.look_at(rig.look_at, Vec3::Y);
+}
 
-    // Always look at the player
-    camera_transform.look_at(player_transform.translation, Vec3::Y);
+/// System that shakes the camera when a decompression happens near the
+/// player on their deck, harder the bigger the pressure difference and the
+/// closer it is.
+fn shake_on_decompression(
+    mut events: MessageReader<DecompressionEvent>,
+    player_query: Query<&Transform, With<PlayerControlled>>,
+    mut shake: ResMut<CameraShake>,
+) {
+    let player = player_query.single().ok().map(|t| t.translation);
+    for event in events.read() {
+        let Some(player) = player else {
+            continue;
+        };
+        if event.deck != Deck::of_height(player.y) {
+            continue;
+        }
+        let at = Vec2::new(event.position.x as f32, event.position.y as f32);
+        let distance = at.distance(Vec2::new(player.x, player.z));
+        let falloff = (1.0 - distance / DECOMPRESSION_SHAKE_RANGE).max(0.0);
+        shake.add_trauma(falloff * event.differential / FULL_SHAKE_DIFFERENTIAL);
+    }
+}
+
+/// System that tilts the follow camera off target by the current
+/// [`CameraShake`] and lets the trauma decay.
+///
+/// Runs after [`camera_follow_system`], whose `look_at` resets the rotation
+/// every frame, so the shake never accumulates.
+fn apply_camera_shake(
+    time: Res<Time>,
+    mut shake: ResMut<CameraShake>,
+    mut camera_query: Query<&mut Transform, With<FollowCamera>>,
+) {
+    if shake.trauma <= 0.0 {
+        return;
+    }
+    let Ok(mut camera_transform) = camera_query.single_mut() else {
+        return;
+    };
+    let strength = shake.trauma * shake.trauma * MAX_SHAKE_ANGLE;
+    let t = time.elapsed_secs();
+    camera_transform.rotate_local(Quat::from_euler(
+        EulerRot::XYZ,
+        (t * 41.0).sin() * strength,
+        (t * 53.0 + 1.3).sin() * strength,
+        (t * 29.0 + 2.1).sin() * strength,
+    ));
+    shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_secs()).max(0.0);
 }
 
 #[cfg(test)]
@@ -124,7 +442,9 @@ mod tests {
         app.insert_resource(CameraConfig {
             follow_speed: 10_000.0,
             offset,
+            ..default()
         });
+        app.init_resource::<CameraRig>();
         app.add_systems(Update, camera_follow_system);
 
         // Player at a known position
@@ -158,4 +478,99 @@ mod tests {
              Distance: {dist}, camera: {camera_pos}, target: {target}"
         );
     }
+
+    /// Verifies that the free camera stops following the player and pans
+    /// with the pan actions, and that follow mode picks the player up again.
+    #[test]
+    fn test_free_mode_pans_instead_of_following() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<InputMap>();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.insert_resource(CameraConfig {
+            follow_speed: 10_000.0,
+            ..default()
+        });
+        app.init_resource::<CameraRig>();
+        app.add_systems(
+            Update,
+            (toggle_camera_mode, pan_camera, camera_follow_system).chain(),
+        );
+        let player = app
+            .world_mut()
+            .spawn((Transform::from_xyz(5.0, 0.0, 5.0), PlayerControlled))
+            .id();
+        app.update();
+        assert_eq!(
+            app.world().resource::<CameraRig>().focus,
+            Vec3::new(5.0, 0.0, 5.0)
+        );
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyC);
+        app.update();
+        assert_eq!(app.world().resource::<CameraRig>().mode, CameraMode::Free);
+
+        app.world_mut()
+            .get_mut::<Transform>(player)
+            .unwrap()
+            .translation = Vec3::new(9.0, 0.0, 9.0);
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.release(KeyCode::KeyC);
+        keys.clear();
+        keys.press(KeyCode::ArrowRight);
+        app.update();
+        let focus = app.world().resource::<CameraRig>().focus;
+        assert!(focus.x > 5.0 && focus.x < 9.0, "focus {focus}");
+        assert_eq!(focus.z, 5.0);
+
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.release(KeyCode::ArrowRight);
+        keys.clear();
+        keys.press(KeyCode::KeyC);
+        app.update();
+        assert_eq!(
+            app.world().resource::<CameraRig>().focus,
+            Vec3::new(9.0, 0.0, 9.0)
+        );
+    }
+
+    /// Verifies that decompressions shake the camera only when they are
+    /// near the player and on the player's deck.
+    #[test]
+    fn test_nearby_decompression_shakes_camera() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<CameraShake>();
+        app.add_message::<DecompressionEvent>();
+        app.add_systems(Update, shake_on_decompression);
+        app.world_mut()
+            .spawn((Transform::from_xyz(5.0, 0.0, 5.0), PlayerControlled));
+
+        app.world_mut().write_message(DecompressionEvent {
+            deck: Deck::MAIN,
+            position: IVec2::new(40, 40),
+            differential: 100.0,
+        });
+        app.update();
+        assert_eq!(app.world().resource::<CameraShake>().trauma, 0.0);
+
+        app.world_mut().write_message(DecompressionEvent {
+            deck: Deck(1),
+            position: IVec2::new(5, 6),
+            differential: 100.0,
+        });
+        app.update();
+        assert_eq!(app.world().resource::<CameraShake>().trauma, 0.0);
+
+        app.world_mut().write_message(DecompressionEvent {
+            deck: Deck::MAIN,
+            position: IVec2::new(5, 6),
+            differential: 100.0,
+        });
+        app.update();
+        let trauma = app.world().resource::<CameraShake>().trauma;
+        assert!(trauma > 0.8 && trauma <= 1.0, "trauma {trauma}");
+    }
 }
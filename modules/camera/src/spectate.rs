@@ -0,0 +1,88 @@
+//! Spectator camera.
+//!
+//! While the local player controls an observer,
+//! [`Action::CycleSpectateTarget`] puts the [`CameraRig`] in
+//! [`CameraMode::Spectate`] on the next player creature, in name order, and
+//! back to following the observer after the last one.  The focus tracks the
+//! target's replicated position and the camera eases after it like it does
+//! in follow mode.  Players far from the observer are only streamed to
+//! spectators that asked for the whole map (see the `souls` module).
+
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use player::PlayerControlled;
+use things::{DisplayName, OBSERVER_KIND, Thing};
+
+use crate::{CameraMode, CameraRig};
+
+/// The player after `current` among `players`, or `None` after the last
+/// one and when there is none.  `players` is in cycling order.
+fn next_target(current: Option<Entity>, players: &[Entity]) -> Option<Entity> {
+    match current.and_then(|current| players.iter().position(|&p| p == current)) {
+        Some(index) => players.get(index + 1).copied(),
+        None => players.first().copied(),
+    }
+}
+
+/// System that cycles the spectated player on
+/// [`Action::CycleSpectateTarget`] while the local player is an observer.
+pub(crate) fn cycle_spectate_target(
+    input: ActionInput,
+    mut rig: ResMut<CameraRig>,
+    local: Query<&Thing, With<PlayerControlled>>,
+    players: Query<(Entity, &Thing, &DisplayName), Without<PlayerControlled>>,
+) {
+    if !input.just_pressed(Action::CycleSpectateTarget) {
+        return;
+    }
+    if !local.iter().any(|thing| thing.kind == OBSERVER_KIND) {
+        return;
+    }
+    // Kind 0 is the creature; named ones are players.
+    let mut named: Vec<(&str, Entity)> = players
+        .iter()
+        .filter(|(_, thing, _)| thing.kind == 0)
+        .map(|(entity, _, name)| (name.0.as_str(), entity))
+        .collect();
+    named.sort();
+    let order: Vec<Entity> = named.into_iter().map(|(_, entity)| entity).collect();
+    let current = match rig.mode {
+        CameraMode::Spectate(target) => Some(target),
+        _ => None,
+    };
+    rig.mode = match next_target(current, &order) {
+        Some(target) => CameraMode::Spectate(target),
+        None => CameraMode::Follow,
+    };
+    info!("Camera mode: {:?}", rig.mode);
+}
+
+/// System that gives up spectating a player whose creature is gone, e.g.
+/// after they left or moved out of relevancy.
+pub(crate) fn drop_lost_spectate_target(
+    mut rig: ResMut<CameraRig>,
+    things: Query<(), With<Thing>>,
+) {
+    if let CameraMode::Spectate(target) = rig.mode
+        && !things.contains(target)
+    {
+        rig.mode = CameraMode::Follow;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycling_visits_every_player_then_returns_to_free_fly() {
+        let mut world = World::new();
+        let players = [world.spawn_empty().id(), world.spawn_empty().id()];
+        let gone = world.spawn_empty().id();
+        assert_eq!(next_target(None, &players), Some(players[0]));
+        assert_eq!(next_target(Some(players[0]), &players), Some(players[1]));
+        assert_eq!(next_target(Some(players[1]), &players), None);
+        assert_eq!(next_target(Some(gone), &players), Some(players[0]));
+        assert_eq!(next_target(None, &[]), None);
+    }
+}
@@ -0,0 +1,568 @@
+//! Crafting: turning items into other items.
+//!
+//! A [`Recipe`] names the item kinds and counts it consumes, the item it
+//! makes, the [`Thing`] kind of the station it must be made at (if any) and
+//! how long the work takes.  Recipes live in the [`RecipeBook`], which client
+//! and server fill the same way at startup, so a [`RecipeId`] means the same
+//! recipe on both.
+//!
+//! Clients ask for a craft with a [`CraftRequest`] on the interactions
+//! stream.  The interactions module checks it with [`Workshop::check`] and
+//! runs it as a timed action; once that completes it writes
+//! [`CraftCompleted`], and [`complete_crafts`] checks the recipe again,
+//! consumes its inputs from the actor's hands and the containers within
+//! reach, and spawns the output.  On the next tick the output is put into a
+//! free hand of the actor, or else into the station's container, or else
+//! left on the floor.
+
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use items::{
+    Container, InteractionRange, Item, ItemCatalog, ItemInsertRequest, ItemPickupRequest, OwnedBy,
+    Stack,
+};
+use network::{ControlledByClient, NetId, Server, SimulationTick, StreamSender};
+use things::{DespawnReason, HandSlot, NetIdIndex, Thing, ThingsStreamMessage, spawn_thing};
+use wincode::{SchemaRead, SchemaWrite};
+
+/// Index of a [`Recipe`] in the [`RecipeBook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SchemaRead, SchemaWrite)]
+pub struct RecipeId(pub u16);
+
+/// A number of units of one item kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemCount {
+    /// [`Thing`] kind of the item.
+    pub kind: u16,
+    pub count: u32,
+}
+
+impl ItemCount {
+    pub fn new(kind: u16, count: u32) -> Self {
+        Self { kind, count }
+    }
+}
+
+/// How to make an item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipe {
+    /// Shown in menus, e.g. `"Toolbox"`.
+    pub name: String,
+    /// Consumed when the craft completes.
+    pub inputs: Vec<ItemCount>,
+    pub output: ItemCount,
+    /// [`Thing`] kind of the station the recipe is made at; made in the
+    /// hands when `None`.
+    pub station: Option<u16>,
+    /// Seconds of work.
+    pub craft_time: f32,
+}
+
+/// Every known [`Recipe`], in registration order.
+#[derive(Resource, Debug, Default)]
+pub struct RecipeBook(Vec<Recipe>);
+
+impl RecipeBook {
+    /// Adds `recipe` and returns its id.
+    pub fn register(&mut self, recipe: Recipe) -> RecipeId {
+        let id = RecipeId(self.0.len() as u16);
+        self.0.push(recipe);
+        id
+    }
+
+    pub fn get(&self, id: RecipeId) -> Option<&Recipe> {
+        self.0.get(id.0 as usize)
+    }
+
+    /// Every recipe with its id.
+    pub fn iter(&self) -> impl Iterator<Item = (RecipeId, &Recipe)> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(i, recipe)| (RecipeId(i as u16), recipe))
+    }
+
+    /// Recipes made at a station of kind `station`, or in the hands for
+    /// `None`.
+    pub fn made_at(&self, station: Option<u16>) -> impl Iterator<Item = (RecipeId, &Recipe)> {
+        self.iter()
+            .filter(move |(_, recipe)| recipe.station == station)
+    }
+}
+
+/// Client request to craft `recipe`, at `station` if the recipe needs one.
+/// Travels on the interactions stream inside `InteractionRequest::Craft`.
+#[derive(Debug, Clone, Copy, PartialEq, SchemaRead, SchemaWrite)]
+pub struct CraftRequest {
+    pub recipe: RecipeId,
+    pub station: Option<NetId>,
+}
+
+/// Server-side: `actor` finished working on `recipe`.  Consumes the inputs
+/// and spawns the output.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct CraftCompleted {
+    pub actor: Entity,
+    pub recipe: RecipeId,
+    pub station: Option<Entity>,
+}
+
+/// Why a craft cannot be made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CraftError {
+    /// No recipe has that id.
+    UnknownRecipe,
+    /// The recipe needs a station and none of its kind was named.
+    WrongStation,
+    /// The station is beyond the actor's [`InteractionRange`].
+    OutOfReach,
+    /// The actor cannot reach enough of the recipe's inputs.
+    MissingInputs,
+}
+
+/// Marks a freshly crafted thing until [`deliver_crafted_items`] hands it
+/// over.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct CraftedFor {
+    pub actor: Entity,
+    pub station: Option<Entity>,
+}
+
+/// Server-side access to recipes and to the items an actor can craft with:
+/// those in its hands and in every container within its
+/// [`InteractionRange`], including held ones such as a toolbox.
+#[derive(SystemParam)]
+pub struct Workshop<'w, 's> {
+    recipes: Res<'w, RecipeBook>,
+    range: Res<'w, InteractionRange>,
+    transforms: Query<'w, 's, &'static GlobalTransform>,
+    children: Query<'w, 's, &'static Children>,
+    containers: Query<'w, 's, (Entity, &'static Container, Has<HandSlot>)>,
+    things: Query<'w, 's, &'static Thing>,
+    stacks: Query<'w, 's, &'static Stack>,
+}
+
+impl Workshop<'_, '_> {
+    /// Checks that `actor` can make recipe `id` at `station` right now.
+    pub fn check(
+        &self,
+        actor: Entity,
+        id: RecipeId,
+        station: Option<Entity>,
+    ) -> Result<&Recipe, CraftError> {
+        let recipe = self.recipes.get(id).ok_or(CraftError::UnknownRecipe)?;
+        if let Some(kind) = recipe.station {
+            let station = station
+                .filter(|&station| self.things.get(station).is_ok_and(|t| t.kind == kind))
+                .ok_or(CraftError::WrongStation)?;
+            let distance = match (self.transforms.get(actor), self.transforms.get(station)) {
+                (Ok(actor), Ok(station)) => actor.translation().distance(station.translation()),
+                _ => f32::INFINITY,
+            };
+            if distance > self.range.0 {
+                return Err(CraftError::OutOfReach);
+            }
+        }
+
+        if !self.has_inputs(actor, &recipe.inputs) {
+            return Err(CraftError::MissingInputs);
+        }
+        Ok(recipe)
+    }
+
+    /// Whether `actor` can reach every one of `inputs`.
+    pub fn has_inputs(&self, actor: Entity, inputs: &[ItemCount]) -> bool {
+        let items = self.reachable_items(actor);
+        inputs.iter().all(|input| {
+            let reachable: u32 = items.iter().map(|&item| self.units(item, input.kind)).sum();
+            reachable >= input.count
+        })
+    }
+
+    /// Items `actor` can take inputs from: the ones in its hands, then the
+    /// ones in containers within reach, nearest container first.
+    fn reachable_items(&self, actor: Entity) -> Vec<Entity> {
+        let mut items = Vec::new();
+        if let Ok(children) = self.children.get(actor) {
+            for &child in children {
+                if let Ok((_, hand, true)) = self.containers.get(child) {
+                    items.extend(hand.slots.iter().flatten());
+                }
+            }
+        }
+
+        let Ok(actor_pos) = self.transforms.get(actor).map(|t| t.translation()) else {
+            return items;
+        };
+        let mut nearby: Vec<(f32, &Container)> = self
+            .containers
+            .iter()
+            .filter(|&(_, _, is_hand)| !is_hand)
+            .filter_map(|(entity, container, _)| {
+                let distance = self
+                    .transforms
+                    .get(entity)
+                    .ok()?
+                    .translation()
+                    .distance(actor_pos);
+                (distance <= self.range.0).then_some((distance, container))
+            })
+            .collect();
+        nearby.sort_by(|a, b| a.0.total_cmp(&b.0));
+        items.extend(
+            nearby
+                .into_iter()
+                .flat_map(|(_, container)| container.slots.iter().flatten()),
+        );
+        items
+    }
+
+    /// Units of `kind` in `item`: its [`Stack`] count, or one for an item
+    /// that does not stack.
+    fn units(&self, item: Entity, kind: u16) -> u32 {
+        match self.things.get(item) {
+            Ok(thing) if thing.kind == kind => self.stacks.get(item).map_or(1, |s| s.count),
+            _ => 0,
+        }
+    }
+
+    /// Works out how to take `inputs` from the items `actor` can reach, in
+    /// the order of [`Workshop::reachable_items`]: each item drawn on, with
+    /// the units its [`Stack`] keeps, or `None` for an unstacked item used up
+    /// whole.  Only meaningful after [`Workshop::has_inputs`] succeeded.
+    pub fn take_inputs(&self, actor: Entity, inputs: &[ItemCount]) -> Vec<(Entity, Option<u32>)> {
+        let items = self.reachable_items(actor);
+        let mut taken = Vec::new();
+        for input in inputs {
+            let mut remaining = input.count;
+            for &item in &items {
+                if remaining == 0 {
+                    break;
+                }
+                if !self.things.get(item).is_ok_and(|t| t.kind == input.kind) {
+                    continue;
+                }
+                match self.stacks.get(item) {
+                    Ok(stack) => {
+                        let units = stack.count.min(remaining);
+                        remaining -= units;
+                        taken.push((item, Some(stack.count - units)));
+                    }
+                    Err(_) => {
+                        remaining -= 1;
+                        taken.push((item, None));
+                    }
+                }
+            }
+        }
+        taken
+    }
+}
+
+/// Removes the inputs planned by [`Workshop::take_inputs`] from the world:
+/// drained stacks are left for the items module to despawn, items used up
+/// whole are despawned here and the despawn is broadcast on the things
+/// stream.
+#[derive(SystemParam)]
+pub struct InputConsumer<'w, 's> {
+    commands: Commands<'w, 's>,
+    net_ids: Query<'w, 's, &'static NetId>,
+    net_id_index: ResMut<'w, NetIdIndex>,
+    things_sender: Option<Res<'w, StreamSender<ThingsStreamMessage>>>,
+}
+
+impl InputConsumer<'_, '_> {
+    /// Consumes `taken`, as returned by [`Workshop::take_inputs`].
+    pub fn consume(&mut self, taken: Vec<(Entity, Option<u32>)>) {
+        for (item, left) in taken {
+            if let Some(left) = left {
+                self.commands.entity(item).insert(Stack::new(left));
+                continue;
+            }
+            self.commands.entity(item).despawn();
+            let Ok(&net_id) = self.net_ids.get(item) else {
+                continue;
+            };
+            self.net_id_index.0.remove(&net_id);
+            if let Some(ref sender) = self.things_sender
+                && let Err(e) = sender.broadcast(&ThingsStreamMessage::EntityDespawned {
+                    net_id,
+                    reason: DespawnReason::Consumed,
+                })
+            {
+                error!(
+                    "Failed to broadcast EntityDespawned for NetId({}): {e}",
+                    net_id.0
+                );
+            }
+        }
+    }
+}
+
+/// Server system that makes every [`CraftCompleted`] craft: checks the
+/// recipe again (inputs may have moved during the work), consumes the
+/// inputs with [`InputConsumer`] and spawns the output at the actor, split
+/// into stacks when the [`ItemCatalog`] says it stacks.  What a player
+/// crafts is [`OwnedBy`] their client.  Spawns are broadcast on the things
+/// stream.
+#[allow(clippy::too_many_arguments)]
+pub fn complete_crafts(
+    mut commands: Commands,
+    mut completed: MessageReader<CraftCompleted>,
+    workshop: Workshop,
+    mut consumer: InputConsumer,
+    mut server: ResMut<Server>,
+    catalog: Res<ItemCatalog>,
+    controllers: Query<&ControlledByClient>,
+    things_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+) {
+    for craft in completed.read() {
+        let recipe = match workshop.check(craft.actor, craft.recipe, craft.station) {
+            Ok(recipe) => recipe.clone(),
+            Err(e) => {
+                warn!(
+                    "Craft of {:?} by {:?} failed on completion: {e:?}",
+                    craft.recipe, craft.actor
+                );
+                continue;
+            }
+        };
+        let Ok(position) = workshop
+            .transforms
+            .get(craft.actor)
+            .map(|t| t.translation())
+        else {
+            continue;
+        };
+
+        consumer.consume(workshop.take_inputs(craft.actor, &recipe.inputs));
+        let crafter = controllers
+            .get(craft.actor)
+            .ok()
+            .map(|controller| controller.0);
+
+        let ItemCount { kind, count } = recipe.output;
+        let max_stack = catalog.max_stack(kind);
+        let position = position + Vec3::Y * 0.5;
+        let mut remaining = count;
+        while remaining > 0 {
+            let units = remaining.min(max_stack);
+            remaining -= units;
+            let (entity, net_id) = spawn_thing(&mut commands, &mut server, kind, position);
+            commands.entity(entity).insert(CraftedFor {
+                actor: craft.actor,
+                station: craft.station,
+            });
+            if let Some(crafter) = crafter {
+                commands.entity(entity).insert(OwnedBy(crafter));
+            }
+            if max_stack > 1 {
+                commands.entity(entity).insert(Stack::new(units));
+            }
+            let Some(ref sender) = things_sender else {
+                continue;
+            };
+            if let Err(e) = sender.broadcast(&ThingsStreamMessage::EntitySpawned {
+                net_id,
+                kind,
+                position: position.into(),
+                velocity: [0.0, 0.0, 0.0],
+                owner: None,
+                name: None,
+            }) {
+                error!(
+                    "Failed to broadcast EntitySpawned for NetId({}): {e}",
+                    net_id.0
+                );
+            }
+        }
+        info!("{:?} crafted {} x{}", craft.actor, recipe.name, count);
+    }
+}
+
+/// Server system that hands crafted things over once their templates have
+/// been applied: items go into a free hand of the actor, else into the
+/// station's container; whatever does not fit stays on the floor.
+pub fn deliver_crafted_items(
+    mut commands: Commands,
+    crafted: Query<(Entity, &CraftedFor, Has<Item>)>,
+    children: Query<&Children>,
+    containers: Query<(&Container, Has<HandSlot>)>,
+    mut pickup_req: MessageWriter<ItemPickupRequest>,
+    mut insert_req: MessageWriter<ItemInsertRequest>,
+) {
+    let free_slots = |container: &Container| container.slots.iter().filter(|s| s.is_none()).count();
+    // Free slots left in each actor's hands and each station, counting the
+    // requests already written this tick.
+    let mut free_hands: HashMap<Entity, usize> = HashMap::new();
+    let mut free_stations: HashMap<Entity, usize> = HashMap::new();
+
+    for (item, crafted_for, is_item) in crafted.iter() {
+        commands.entity(item).remove::<CraftedFor>();
+        if !is_item {
+            continue;
+        }
+        let actor = crafted_for.actor;
+        let hands = free_hands.entry(actor).or_insert_with(|| {
+            children.get(actor).map_or(0, |children| {
+                children
+                    .iter()
+                    .filter_map(|child| containers.get(child).ok())
+                    .filter(|&(_, is_hand)| is_hand)
+                    .map(|(hand, _)| free_slots(hand))
+                    .sum()
+            })
+        });
+        if *hands > 0 {
+            *hands -= 1;
+            pickup_req.write(ItemPickupRequest {
+                actor,
+                item,
+                correlation: None,
+            });
+            continue;
+        }
+        let Some(station) = crafted_for.station else {
+            continue;
+        };
+        let slots = free_stations.entry(station).or_insert_with(|| {
+            containers
+                .get(station)
+                .map_or(0, |(container, _)| free_slots(container))
+        });
+        if *slots > 0 {
+            *slots -= 1;
+            insert_req.write(ItemInsertRequest {
+                item,
+                container: station,
+            });
+        }
+    }
+}
+
+/// Registers the [`RecipeBook`] and, on servers, the systems that make and
+/// hand over crafts.  Add after `ItemsPlugin`.
+pub struct CraftingPlugin;
+
+impl Plugin for CraftingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<CraftCompleted>();
+        app.init_resource::<RecipeBook>();
+        app.add_systems(
+            SimulationTick,
+            (deliver_crafted_items, complete_crafts)
+                .chain()
+                .run_if(resource_exists::<Server>),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use things::HandSide;
+
+    const PLASTEEL: u16 = 4;
+    const CAN: u16 = 2;
+    const BENCH: u16 = 13;
+
+    /// An actor at the origin holding 3 plasteel, a crate with a can next to
+    /// it, another can out of reach and a bench.  Returns
+    /// `(actor, plasteel, near_can, bench)`.
+    fn workshop_world(app: &mut App) -> (Entity, Entity, Entity, Entity) {
+        let world = app.world_mut();
+        let at = |x: f32| GlobalTransform::from_translation(Vec3::new(x, 0.0, 0.0));
+        let actor = world.spawn(at(0.0)).id();
+        let plasteel = world
+            .spawn((Item, Thing { kind: PLASTEEL }, Stack::new(3)))
+            .id();
+        world.spawn((
+            HandSlot {
+                side: HandSide::Right,
+            },
+            Container {
+                slots: vec![Some(plasteel)],
+            },
+            ChildOf(actor),
+        ));
+        let near_can = world.spawn((Item, Thing { kind: CAN })).id();
+        world.spawn((
+            Container {
+                slots: vec![Some(near_can), None],
+            },
+            at(1.0),
+        ));
+        let far_can = world.spawn((Item, Thing { kind: CAN })).id();
+        world.spawn((
+            Container {
+                slots: vec![Some(far_can)],
+            },
+            at(10.0),
+        ));
+        let bench = world.spawn((Thing { kind: BENCH }, at(1.5))).id();
+        (actor, plasteel, near_can, bench)
+    }
+
+    #[test]
+    fn workshop_checks_station_and_reachable_inputs_then_takes_them() {
+        let mut app = App::new();
+        app.insert_resource(InteractionRange(2.0));
+        let mut book = RecipeBook::default();
+        let lamp = book.register(Recipe {
+            name: "Lamp".into(),
+            inputs: vec![ItemCount::new(PLASTEEL, 2), ItemCount::new(CAN, 1)],
+            output: ItemCount::new(8, 1),
+            station: Some(BENCH),
+            craft_time: 2.0,
+        });
+        let two_cans = book.register(Recipe {
+            name: "Can pair".into(),
+            inputs: vec![ItemCount::new(CAN, 2)],
+            output: ItemCount::new(CAN, 1),
+            station: None,
+            craft_time: 1.0,
+        });
+        app.insert_resource(book);
+        let (actor, plasteel, near_can, bench) = workshop_world(&mut app);
+
+        let checks = app
+            .world_mut()
+            .run_system_once(move |workshop: Workshop| {
+                [
+                    workshop.check(actor, lamp, None).err(),
+                    workshop.check(actor, lamp, Some(actor)).err(),
+                    workshop.check(actor, two_cans, None).err(),
+                    workshop.check(actor, RecipeId(9), None).err(),
+                    workshop.check(actor, lamp, Some(bench)).err(),
+                ]
+            })
+            .unwrap();
+        assert_eq!(
+            checks,
+            [
+                Some(CraftError::WrongStation),
+                Some(CraftError::WrongStation),
+                // The second can is out of reach.
+                Some(CraftError::MissingInputs),
+                Some(CraftError::UnknownRecipe),
+                None,
+            ]
+        );
+
+        let taken = app
+            .world_mut()
+            .run_system_once(move |workshop: Workshop| {
+                let inputs = workshop
+                    .check(actor, lamp, Some(bench))
+                    .unwrap()
+                    .inputs
+                    .clone();
+                workshop.take_inputs(actor, &inputs)
+            })
+            .unwrap();
+        assert_eq!(taken, vec![(plasteel, Some(1)), (near_can, None)]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Falling unconscious and coming to again.
+//!
+//! A creature that is badly hurt, starving or parched collapses: the server
+//! puts it in [`MovementState::Unconscious`], which replicates to every
+//! client like any other movement state.  An unconscious creature ignores
+//! its input, lies down (its collider is swapped for a [`lying_collider`])
+//! and can be dragged by others.  It comes to once its health is back above
+//! [`WAKE_HEALTH_FRACTION`] and it is neither starving nor parched.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use physics::Collider;
+use things::{Health, MovementState, Needs};
+
+use crate::Creature;
+
+/// Fraction of its maximum health below which a creature collapses.
+pub const KNOCKOUT_HEALTH_FRACTION: f32 = 0.2;
+
+/// Fraction of its maximum health an unconscious creature needs to come to,
+/// higher than [`KNOCKOUT_HEALTH_FRACTION`] so it does not flicker in and
+/// out of consciousness at the threshold.
+pub const WAKE_HEALTH_FRACTION: f32 = 0.3;
+
+/// How far the centre of a lying collider sits below the creature's origin,
+/// which stands about 0.8 above the floor.
+const LYING_DROP: f32 = 0.5;
+
+/// Present on a creature while its collider is tilted onto its side, holding
+/// the standing collider to restore when it comes to.
+#[derive(Component, Debug, Clone)]
+pub struct Lying {
+    pub standing: Collider,
+}
+
+/// Whether a creature with `health` and `needs` is out cold.  `unconscious`
+/// is its current state, which decides the health threshold.
+pub fn is_knocked_out(health: Option<&Health>, needs: Option<&Needs>, unconscious: bool) -> bool {
+    let threshold = if unconscious {
+        WAKE_HEALTH_FRACTION
+    } else {
+        KNOCKOUT_HEALTH_FRACTION
+    };
+    let hurt = health.is_some_and(|health| health.fraction() < threshold);
+    let deprived = needs.is_some_and(|needs| needs.nutrition <= 0.0 || needs.hydration <= 0.0);
+    hurt || deprived
+}
+
+/// `standing` turned onto its side and lowered to the floor.
+pub fn lying_collider(standing: &Collider) -> Collider {
+    Collider::compound(vec![(
+        Vec3::NEG_Y * LYING_DROP,
+        Quat::from_rotation_x(FRAC_PI_2),
+        standing.clone(),
+    )])
+}
+
+/// Server system: knocks out creatures with too little health, nutrition or
+/// hydration and wakes those that recovered.  Runs before the movement
+/// state is updated, which leaves unconscious creatures alone.
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_consciousness(
+    mut creatures: Query<(Option<&Health>, Option<&Needs>, &mut MovementState), With<Creature>>,
+) {
+    for (health, needs, mut state) in creatures.iter_mut() {
+        let unconscious = *state == MovementState::Unconscious;
+        let knocked_out = is_knocked_out(health, needs, unconscious);
+        if knocked_out != unconscious {
+            *state = if knocked_out {
+                MovementState::Unconscious
+            } else {
+                MovementState::Walking
+            };
+        }
+    }
+}
+
+/// Lays creatures that became unconscious down and stands those that came
+/// to back up.  Runs on the server and on clients, from the replicated
+/// [`MovementState`].
+#[allow(clippy::type_complexity)]
+pub(crate) fn lie_down_unconscious(
+    mut commands: Commands,
+    mut creatures: Query<
+        (Entity, &MovementState, &mut Collider, Option<&Lying>),
+        (With<Creature>, Changed<MovementState>),
+    >,
+) {
+    for (entity, state, mut collider, lying) in creatures.iter_mut() {
+        match (*state == MovementState::Unconscious, lying) {
+            (true, None) => {
+                let standing = collider.clone();
+                *collider = lying_collider(&standing);
+                commands.entity(entity).insert(Lying { standing });
+            }
+            (false, Some(lying)) => {
+                *collider = lying.standing.clone();
+                commands.entity(entity).remove::<Lying>();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hurt_creatures_collapse_and_come_to_once_recovered() {
+        let mut app = App::new();
+        app.add_systems(Update, (update_consciousness, lie_down_unconscious).chain());
+        let creature = app
+            .world_mut()
+            .spawn((
+                Creature,
+                Health {
+                    current: 10.0,
+                    max: 100.0,
+                },
+                MovementState::Sprinting,
+                Collider::capsule(0.3, 1.0),
+            ))
+            .id();
+        app.update();
+        assert_eq!(
+            *app.world().get::<MovementState>(creature).unwrap(),
+            MovementState::Unconscious
+        );
+        assert!(app.world().get::<Lying>(creature).is_some());
+
+        // Between the thresholds: still out.
+        app.world_mut().get_mut::<Health>(creature).unwrap().current = 25.0;
+        app.update();
+        assert_eq!(
+            *app.world().get::<MovementState>(creature).unwrap(),
+            MovementState::Unconscious
+        );
+
+        app.world_mut().get_mut::<Health>(creature).unwrap().current = 50.0;
+        app.update();
+        assert_eq!(
+            *app.world().get::<MovementState>(creature).unwrap(),
+            MovementState::Walking
+        );
+        assert!(app.world().get::<Lying>(creature).is_none());
+
+        let starving = Needs {
+            nutrition: 0.0,
+            hydration: 80.0,
+        };
+        assert!(is_knocked_out(None, Some(&starving), false));
+    }
+}
@@ -1,12 +1,50 @@
 use bevy::prelude::*;
-use physics::LinearVelocity;
-use things::InputDirection;
+use network::Server;
+use physics::{Collider, GameLayer, LinearVelocity, ShapeCastConfig, SpatialQuery};
+use things::{InputDirection, MovementModifiers, MovementState, StatusEffects};
+use tiles::TileChunk;
+
+mod consciousness;
+pub use consciousness::{
+    KNOCKOUT_HEALTH_FRACTION, Lying, WAKE_HEALTH_FRACTION, is_knocked_out, lying_collider,
+};
+
+/// How far below a creature's origin the ground may be for it to count as
+/// [`Grounded`].  Creatures stand about 0.8 above the floor surface.
+const GROUND_PROBE_DISTANCE: f32 = 1.0;
+
+/// Height above the creature's origin the wall sweep starts from, so the
+/// swept shape clears the floor slab it stands on.
+const SLIDE_LIFT: f32 = 0.1;
+
+/// Gap kept between a creature and the wall it slides along.
+const SLIDE_SKIN: f32 = 0.02;
+
+/// Walls a single step can slide along, e.g. both sides of a corner.
+const SLIDE_ITERATIONS: usize = 3;
+
+/// Stamina spent per second of sprinting.
+const SPRINT_STAMINA_COST: f32 = 20.0;
+
+/// Stamina recovered per second while not sprinting.
+const STAMINA_REGEN: f32 = 10.0;
+
+/// Fraction of its maximum stamina an exhausted creature must recover
+/// before it can start sprinting again, so it does not flicker in and out
+/// of a sprint at empty.
+const SPRINT_RESTART_FRACTION: f32 = 0.2;
 
 /// Marker component for creatures - entities that can move and act in the world.
 #[derive(Component, Debug, Clone, Copy, Default, Reflect)]
 #[reflect(Component)]
 pub struct Creature;
 
+/// Marker component for observers - free-flying bodies that spectating
+/// clients steer around the station.  They move like creatures but cannot act.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct Observer;
+
 /// Component that defines how fast a creature can move (units per second).
 #[derive(Component, Debug, Clone, Copy, Reflect)]
 #[reflect(Component)]
@@ -20,29 +58,512 @@ impl Default for MovementSpeed {
     }
 }
 
+/// Scales a creature's [`MovementSpeed`] while present, e.g. to slow an actor
+/// down while it drags something heavy.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct SpeedMultiplier(pub f32);
+
+/// Breath a creature spends to sprint.  Drains while [`MovementState::Sprinting`]
+/// and recovers otherwise; other modules (e.g. health) may lower or cap
+/// `current` and the sprint stops when it runs out.  Server-side only.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Stamina {
+    /// Full stamina of `max`.
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    /// Remaining stamina as a fraction of the maximum, `0.0..=1.0`.
+    pub fn fraction(&self) -> f32 {
+        if self.max <= 0.0 {
+            0.0
+        } else {
+            (self.current / self.max).clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// Present on creatures standing on something; without it they have no
+/// traction and keep drifting (see [`MovementSettings::air_control`]).
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct Grounded;
+
+/// Movement tuning shared by every creature and observer.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct MovementSettings {
+    /// Upper bound on any body's speed, whatever its [`MovementSpeed`] and
+    /// [`SpeedMultiplier`].
+    pub max_speed: f32,
+    /// Speed gained per second while moving.
+    pub acceleration: f32,
+    /// Speed lost per second after letting go.
+    pub friction: f32,
+    /// Fraction of acceleration and friction kept while not [`Grounded`].
+    pub air_control: f32,
+    /// Speed factor while [`MovementState::Sprinting`].
+    pub sprint_multiplier: f32,
+    /// Speed factor while [`MovementState::Crouching`].
+    pub crouch_multiplier: f32,
+}
+
+impl MovementSettings {
+    /// Speed factor for a creature moving in `state`.
+    pub fn state_multiplier(&self, state: MovementState) -> f32 {
+        match state {
+            MovementState::Walking => 1.0,
+            MovementState::Sprinting => self.sprint_multiplier,
+            MovementState::Crouching => self.crouch_multiplier,
+            MovementState::Unconscious => 0.0,
+        }
+    }
+}
+
+impl Default for MovementSettings {
+    fn default() -> Self {
+        Self {
+            max_speed: 6.0,
+            acceleration: 30.0,
+            friction: 25.0,
+            air_control: 0.0,
+            sprint_multiplier: 1.6,
+            crouch_multiplier: 0.5,
+        }
+    }
+}
+
 /// Plugin that registers creature components and movement systems.
 pub struct CreaturesPlugin;
 
 impl Plugin for CreaturesPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Creature>();
+        app.register_type::<Observer>();
         app.register_type::<MovementSpeed>();
-        app.add_systems(Update, apply_input_velocity);
+        app.register_type::<Stamina>();
+        app.register_type::<Grounded>();
+        app.register_type::<MovementSettings>();
+        app.init_resource::<MovementSettings>();
+        app.add_systems(
+            FixedUpdate,
+            (
+                consciousness::update_consciousness.run_if(resource_exists::<Server>),
+                update_movement_state.run_if(resource_exists::<Server>),
+                consciousness::lie_down_unconscious,
+                detect_grounded,
+                apply_input_velocity,
+            )
+                .chain(),
+        );
+        app.add_systems(Update, face_movement_direction);
     }
 }
 
-/// Applies InputDirection to LinearVelocity using MovementSpeed.
-/// Runs on both client (for local prediction) and server (authoritative).
-fn apply_input_velocity(
-    mut query: Query<(&InputDirection, &MovementSpeed, &mut LinearVelocity), With<Creature>>,
+/// Horizontal velocity after one movement step of `dt` seconds.
+///
+/// Accelerates towards `input` at `max_speed`, or brakes to a stop without
+/// input, scaled down by [`MovementSettings::air_control`] when not
+/// `grounded`.  Vertical velocity is left to physics.  The server and the
+/// client predicting its own creature both step with this function, so
+/// they agree on where the creature ends up.
+pub fn movement_step(
+    velocity: Vec3,
+    input: Vec3,
+    max_speed: f32,
+    grounded: bool,
+    settings: &MovementSettings,
+    dt: f32,
+) -> Vec3 {
+    let current = velocity.with_y(0.0);
+    let direction = input.with_y(0.0).normalize_or_zero();
+    let (target, rate) = if direction != Vec3::ZERO {
+        (direction * max_speed, settings.acceleration)
+    } else {
+        (Vec3::ZERO, settings.friction)
+    };
+    let control = if grounded { 1.0 } else { settings.air_control };
+    let max_change = rate * control * dt;
+    let change = target - current;
+    if change.length() <= max_change {
+        target
+    } else {
+        current + change.normalize() * max_change
+    }
+}
+
+/// Removes the part of `velocity` heading into a wall with surface
+/// `normal`.  Only the horizontal part of the normal counts, so floors and
+/// ceilings never slow a creature down.
+pub fn clip_velocity(velocity: Vec3, normal: Vec3) -> Vec3 {
+    let normal = normal.with_y(0.0).normalize_or_zero();
+    let into = velocity.dot(normal);
+    if into < 0.0 {
+        velocity - normal * into
+    } else {
+        velocity
+    }
+}
+
+/// Collide-and-slide: sweeps `collider` from `origin` along `velocity` for
+/// `dt` seconds against the tile colliders and clips the velocity against
+/// each wall it would hit, so creatures glide along walls instead of
+/// sticking to them.
+#[allow(clippy::too_many_arguments)]
+pub fn slide_velocity(
+    spatial_query: &SpatialQuery,
+    is_tile: &dyn Fn(Entity) -> bool,
+    entity: Entity,
+    collider: &Collider,
+    origin: Vec3,
+    rotation: Quat,
+    velocity: Vec3,
+    dt: f32,
+) -> Vec3 {
+    let filter = GameLayer::solid_filter().with_excluded_entities([entity]);
+    let origin = origin + Vec3::Y * SLIDE_LIFT;
+    let mut velocity = velocity;
+    for _ in 0..SLIDE_ITERATIONS {
+        let Ok(direction) = Dir3::new(velocity.with_y(0.0)) else {
+            break;
+        };
+        let config = ShapeCastConfig::from_max_distance(velocity.length() * dt + SLIDE_SKIN);
+        let Some(hit) = spatial_query.cast_shape_predicate(
+            collider, origin, rotation, direction, &config, &filter, is_tile,
+        ) else {
+            break;
+        };
+        let clipped = clip_velocity(velocity, hit.normal1);
+        if clipped == velocity {
+            break;
+        }
+        velocity = clipped;
+    }
+    velocity
+}
+
+/// The [`MovementState`] a creature in `current` state moves into with
+/// `modifiers` held.  Crouching wins over sprinting, and a sprint needs the
+/// creature to be moving and to have the stamina for it.
+pub fn next_movement_state(
+    current: MovementState,
+    modifiers: MovementModifiers,
+    moving: bool,
+    stamina: Option<&Stamina>,
+) -> MovementState {
+    if modifiers.crouch {
+        return MovementState::Crouching;
+    }
+    let rested = stamina.is_none_or(|stamina| {
+        if current == MovementState::Sprinting {
+            stamina.current > 0.0
+        } else {
+            stamina.fraction() >= SPRINT_RESTART_FRACTION
+        }
+    });
+    if modifiers.sprint && moving && rested {
+        MovementState::Sprinting
+    } else {
+        MovementState::Walking
+    }
+}
+
+/// Server-side system: moves creatures into the [`MovementState`] their
+/// [`MovementModifiers`] ask for and spends or recovers their [`Stamina`].
+/// Stunned creatures count as standing still, so they cannot sprint, and
+/// unconscious ones stay unconscious until they come to.  The state
+/// replicates to clients, which use it to predict their speed.
+#[allow(clippy::type_complexity)]
+fn update_movement_state(
+    time: Res<Time>,
+    mut creatures: Query<
+        (
+            &MovementModifiers,
+            &InputDirection,
+            Option<&StatusEffects>,
+            Option<&mut Stamina>,
+            &mut MovementState,
+        ),
+        With<Creature>,
+    >,
 ) {
-    for (input, movement_speed, mut velocity) in query.iter_mut() {
-        let desired = if input.0.length_squared() > 0.0 {
-            input.0.normalize() * movement_speed.speed
+    let dt = time.delta_secs();
+    for (modifiers, input, effects, stamina, mut state) in creatures.iter_mut() {
+        let stunned = effects.is_some_and(StatusEffects::is_stunned);
+        let moving = !stunned && input.0.with_y(0.0).length_squared() > 0.0;
+        let next = if *state == MovementState::Unconscious {
+            MovementState::Unconscious
         } else {
+            next_movement_state(*state, *modifiers, moving, stamina.as_deref())
+        };
+        state.set_if_neq(next);
+
+        let Some(mut stamina) = stamina else {
+            continue;
+        };
+        let current = if next == MovementState::Sprinting {
+            (stamina.current - SPRINT_STAMINA_COST * dt).max(0.0)
+        } else {
+            (stamina.current + STAMINA_REGEN * dt).min(stamina.max)
+        };
+        if current != stamina.current {
+            stamina.current = current;
+        }
+    }
+}
+
+/// Adds or removes [`Grounded`] on creatures depending on whether there is
+/// anything solid just below them.  Observers fly and are never grounded.
+fn detect_grounded(
+    mut commands: Commands,
+    spatial_query: SpatialQuery,
+    creatures: Query<(Entity, &Transform, Has<Grounded>), With<Creature>>,
+) {
+    for (entity, transform, was_grounded) in creatures.iter() {
+        let filter = GameLayer::solid_filter().with_excluded_entities([entity]);
+        let grounded = spatial_query
+            .cast_ray(
+                transform.translation,
+                Dir3::NEG_Y,
+                GROUND_PROBE_DISTANCE,
+                true,
+                &filter,
+            )
+            .is_some();
+        if grounded && !was_grounded {
+            commands.entity(entity).insert(Grounded);
+        } else if !grounded && was_grounded {
+            commands.entity(entity).remove::<Grounded>();
+        }
+    }
+}
+
+/// Steps the velocity of creatures and observers towards their
+/// InputDirection with [`movement_step`], capped by MovementSpeed scaled by
+/// [`SpeedMultiplier`] and the [`MovementState`] when present, then slides creatures along walls with
+/// [`slide_velocity`].  Observers have full control and no collider.
+/// [`StatusEffects`] slow creatures down, and stunned or unconscious ones
+/// ignore their input and brake to a stop.
+///
+/// Runs in `FixedUpdate` on both client (for local prediction) and server
+/// (authoritative), so both step with the same `dt`.
+#[allow(clippy::type_complexity)]
+fn apply_input_velocity(
+    time: Res<Time>,
+    settings: Res<MovementSettings>,
+    spatial_query: SpatialQuery,
+    tile_colliders: Query<(), With<TileChunk>>,
+    mut query: Query<
+        (
+            Entity,
+            &InputDirection,
+            &MovementSpeed,
+            Option<&SpeedMultiplier>,
+            Option<&MovementState>,
+            Option<&StatusEffects>,
+            &Transform,
+            Option<&Collider>,
+            Has<Grounded>,
+            Has<Observer>,
+            &mut LinearVelocity,
+        ),
+        Or<(With<Creature>, With<Observer>)>,
+    >,
+) {
+    let dt = time.delta_secs();
+    let is_tile = |entity: Entity| tile_colliders.contains(entity);
+    for (
+        entity,
+        input,
+        movement_speed,
+        multiplier,
+        state,
+        effects,
+        transform,
+        collider,
+        grounded,
+        observer,
+        mut velocity,
+    ) in query.iter_mut()
+    {
+        let max_speed = (movement_speed.speed
+            * multiplier.map_or(1.0, |m| m.0)
+            * state.map_or(1.0, |s| settings.state_multiplier(*s))
+            * effects.map_or(1.0, StatusEffects::speed_multiplier))
+        .min(settings.max_speed);
+        let unconscious = state == Some(&MovementState::Unconscious);
+        let input = if unconscious || effects.is_some_and(StatusEffects::is_stunned) {
             Vec3::ZERO
+        } else {
+            input.0
         };
-        velocity.x = desired.x;
-        velocity.z = desired.z;
+        let mut desired = movement_step(
+            velocity.0,
+            input,
+            max_speed,
+            grounded || observer,
+            &settings,
+            dt,
+        );
+        if let Some(collider) = collider {
+            desired = slide_velocity(
+                &spatial_query,
+                &is_tile,
+                entity,
+                collider,
+                transform.translation,
+                transform.rotation,
+                desired,
+                dt,
+            );
+        }
+        if velocity.x != desired.x || velocity.z != desired.z {
+            velocity.x = desired.x;
+            velocity.z = desired.z;
+        }
+    }
+}
+
+/// Turns creatures to face their `InputDirection`.  Creatures keep their last
+/// heading while standing still, stunned or unconscious.  The server
+/// replicates the resulting yaw; on the client this only affects the
+/// locally predicted creature.
+#[allow(clippy::type_complexity)]
+fn face_movement_direction(
+    mut query: Query<
+        (
+            &InputDirection,
+            Option<&StatusEffects>,
+            Option<&MovementState>,
+            &mut Transform,
+        ),
+        With<Creature>,
+    >,
+) {
+    for (input, effects, state, mut transform) in query.iter_mut() {
+        if effects.is_some_and(StatusEffects::is_stunned)
+            || state == Some(&MovementState::Unconscious)
+        {
+            continue;
+        }
+        let direction = input.0.with_y(0.0);
+        if direction.length_squared() <= f32::EPSILON {
+            continue;
+        }
+        // Bevy's forward is -Z.
+        let rotation = Quat::from_rotation_y(f32::atan2(-direction.x, -direction.z));
+        if transform.rotation.angle_between(rotation) > 1e-3 {
+            transform.rotation = rotation;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 64.0;
+
+    #[test]
+    fn movement_accelerates_to_max_speed_and_brakes_to_a_stop() {
+        let settings = MovementSettings::default();
+        let mut velocity = Vec3::ZERO;
+        velocity = movement_step(velocity, Vec3::X, 3.0, true, &settings, DT);
+        assert!((velocity.x - settings.acceleration * DT).abs() < 1e-5);
+
+        for _ in 0..64 {
+            velocity = movement_step(velocity, Vec3::X * 5.0, 3.0, true, &settings, DT);
+        }
+        assert_eq!(velocity, Vec3::new(3.0, 0.0, 0.0));
+
+        for _ in 0..64 {
+            velocity = movement_step(velocity, Vec3::ZERO, 3.0, true, &settings, DT);
+        }
+        assert_eq!(velocity, Vec3::ZERO);
+    }
+
+    #[test]
+    fn ungrounded_creatures_drift() {
+        let settings = MovementSettings::default();
+        let drifting = Vec3::new(0.0, 0.0, 2.0);
+        assert_eq!(
+            movement_step(drifting, Vec3::X, 3.0, false, &settings, DT),
+            drifting
+        );
+    }
+
+    #[test]
+    fn sprinting_spends_stamina_until_exhausted() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        // Virtual time clamps a step to 0.25 s, so no step may be longer.
+        app.insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            std::time::Duration::from_secs_f32(0.25),
+        ));
+        app.add_systems(Update, update_movement_state);
+        let creature = app
+            .world_mut()
+            .spawn((
+                Creature,
+                MovementModifiers {
+                    sprint: true,
+                    crouch: false,
+                },
+                InputDirection(Vec3::X),
+                Stamina::new(5.0),
+                MovementState::default(),
+            ))
+            .id();
+        // The first update has no elapsed time.
+        app.update();
+        assert_eq!(
+            *app.world().get::<MovementState>(creature).unwrap(),
+            MovementState::Sprinting
+        );
+
+        app.update();
+        assert_eq!(app.world().get::<Stamina>(creature).unwrap().current, 0.0);
+        app.update();
+        assert_eq!(
+            *app.world().get::<MovementState>(creature).unwrap(),
+            MovementState::Walking
+        );
+        assert_eq!(app.world().get::<Stamina>(creature).unwrap().current, 2.5);
+        // A sprint does not restart on the first breath recovered.
+        assert_eq!(
+            next_movement_state(
+                MovementState::Walking,
+                MovementModifiers {
+                    sprint: true,
+                    crouch: false,
+                },
+                true,
+                Some(&Stamina {
+                    current: 1.0,
+                    max: 10.0
+                }),
+            ),
+            MovementState::Walking
+        );
+    }
+
+    #[test]
+    fn clipping_keeps_the_motion_along_the_wall() {
+        let velocity = Vec3::new(2.0, 0.0, -1.0);
+        // Wall facing -x, in the way of +x motion.
+        assert_eq!(
+            clip_velocity(velocity, Vec3::NEG_X),
+            Vec3::new(0.0, 0.0, -1.0)
+        );
+        // Moving away from the wall, or over a floor, is left alone.
+        assert_eq!(clip_velocity(velocity, Vec3::X), velocity);
+        assert_eq!(clip_velocity(velocity, Vec3::Y), velocity);
     }
 }
@@ -0,0 +1,257 @@
+//! Timed world events run by the server.
+//!
+//! A plugin registers an event with [`WorldEventsExt::add_world_event`]: a
+//! name, how often it happens and the system that carries it out.  The
+//! server runs every due event's system on the simulation tick, first one
+//! interval after startup.  Events tell players what happened with an
+//! [`Announce`] message, which is broadcast on the events stream and turned
+//! into an [`Announced`] message on every client.
+//!
+//! The first event is the [`supply`] drop.
+
+use std::time::Duration;
+
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+use network::{
+    Client, ModuleReadySent, NetworkReceive, NetworkSend, PlayerEvent, Server, SimulationTick,
+    StreamBudget, StreamDef, StreamDirection, StreamPriority, StreamReader, StreamRegistry,
+    StreamSender, stream_tag,
+};
+use wincode::{SchemaRead, SchemaWrite};
+
+pub mod supply;
+pub use supply::{LootEntry, SupplyDrop};
+
+/// Stream tag for the server→client events stream, derived from its name.
+pub const EVENTS_STREAM_TAG: u8 = stream_tag("events");
+
+/// Per-client allowance on the events stream, in bytes per network tick.
+const EVENTS_BYTES_PER_TICK: usize = 1024;
+
+/// Wire format for the server→client events stream.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub enum EventsStreamMessage {
+    /// Text shown to every player.
+    Announcement { text: String },
+}
+
+/// Server-side request to announce `text` to every player.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct Announce {
+    pub text: String,
+}
+
+/// Client-side message: the server announced `text`.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct Announced {
+    pub text: String,
+}
+
+/// One event in the [`WorldEvents`] schedule.
+#[derive(Debug, Clone)]
+struct ScheduledEvent {
+    name: String,
+    every: Duration,
+    /// Server time at which the event next runs.
+    next: Duration,
+    system: SystemId,
+}
+
+/// Every registered world event and when it runs next.
+#[derive(Resource, Debug, Default)]
+pub struct WorldEvents {
+    events: Vec<ScheduledEvent>,
+}
+
+impl WorldEvents {
+    /// Server time at which the event `name` runs next.
+    pub fn next_run(&self, name: &str) -> Option<Duration> {
+        self.events
+            .iter()
+            .find(|event| event.name == name)
+            .map(|event| event.next)
+    }
+}
+
+/// Extension trait for [`App`] that provides `add_world_event`.
+pub trait WorldEventsExt {
+    /// Runs `system` on the server every `every`, starting one interval
+    /// after startup.
+    fn add_world_event<M>(
+        &mut self,
+        name: impl Into<String>,
+        every: Duration,
+        system: impl IntoSystem<(), (), M> + 'static,
+    ) -> &mut Self;
+}
+
+impl WorldEventsExt for App {
+    fn add_world_event<M>(
+        &mut self,
+        name: impl Into<String>,
+        every: Duration,
+        system: impl IntoSystem<(), (), M> + 'static,
+    ) -> &mut Self {
+        let system = self.register_system(system);
+        self.world_mut()
+            .get_resource_or_insert_with::<WorldEvents>(WorldEvents::default)
+            .events
+            .push(ScheduledEvent {
+                name: name.into(),
+                every,
+                next: every,
+                system,
+            });
+        self
+    }
+}
+
+/// Server system: runs the system of every event that is due and schedules
+/// its next run.
+pub fn run_world_events(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut schedule: ResMut<WorldEvents>,
+) {
+    let now = time.elapsed();
+    for event in schedule.events.iter_mut().filter(|event| now >= event.next) {
+        info!("World event: {}", event.name);
+        commands.run_system(event.system);
+        // An interval shorter than a tick runs once per tick.
+        event.next = now + event.every;
+    }
+}
+
+/// Server system: broadcasts every [`Announce`] on the events stream.
+fn broadcast_announcements(
+    mut requests: MessageReader<Announce>,
+    sender: Res<StreamSender<EventsStreamMessage>>,
+) {
+    for request in requests.read() {
+        let msg = EventsStreamMessage::Announcement {
+            text: request.text.clone(),
+        };
+        if let Err(e) = sender.broadcast(&msg) {
+            error!("Failed to broadcast announcement: {e}");
+        }
+    }
+}
+
+/// Sends the [`StreamReady`](network::StreamReady) sentinel for the events
+/// stream to joining clients; past announcements are not repeated.
+fn send_events_stream_ready_on_join(
+    mut player_events: MessageReader<PlayerEvent>,
+    sender: Res<StreamSender<EventsStreamMessage>>,
+    mut module_ready: MessageWriter<ModuleReadySent>,
+) {
+    for event in player_events.read() {
+        let PlayerEvent::Joined { id, .. } = event else {
+            continue;
+        };
+        if let Err(e) = sender.send_stream_ready_to(*id) {
+            error!(
+                "Failed to send StreamReady for events stream to ClientId({}): {e}",
+                id.0
+            );
+        } else {
+            module_ready.write(ModuleReadySent { client: *id });
+        }
+    }
+}
+
+/// Client system: drains the events stream into [`Announced`] messages.
+fn receive_announcements(
+    mut reader: ResMut<StreamReader<EventsStreamMessage>>,
+    mut announced: MessageWriter<Announced>,
+) {
+    for msg in reader.drain() {
+        let EventsStreamMessage::Announcement { text } = msg;
+        info!("Announcement: {text}");
+        announced.write(Announced { text });
+    }
+}
+
+pub struct EventsPlugin;
+
+impl Plugin for EventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<Announce>();
+        app.add_message::<Announced>();
+        app.init_resource::<WorldEvents>();
+        app.init_resource::<supply::SupplyRng>();
+        app.add_systems(
+            SimulationTick,
+            (run_world_events, supply::pack_supply_crates).run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            NetworkReceive,
+            send_events_stream_ready_on_join.run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            NetworkReceive,
+            receive_announcements.run_if(resource_exists::<Client>),
+        );
+        app.add_systems(
+            NetworkSend,
+            broadcast_announcements.run_if(resource_exists::<Server>),
+        );
+
+        let mut registry = app.world_mut().get_resource_mut::<StreamRegistry>().expect(
+            "EventsPlugin requires NetworkPlugin to be added before it (StreamRegistry not found)",
+        );
+        let (sender, reader): (
+            StreamSender<EventsStreamMessage>,
+            StreamReader<EventsStreamMessage>,
+        ) = registry.register_with_budget(
+            StreamDef {
+                tag: EVENTS_STREAM_TAG,
+                name: "events",
+                direction: StreamDirection::ServerToClient,
+            },
+            StreamBudget::new(StreamPriority::Low, EVENTS_BYTES_PER_TICK),
+        );
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Runs(u32);
+
+    #[test]
+    fn events_run_once_per_interval() {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.init_resource::<Runs>();
+        app.add_world_event(
+            "tally",
+            Duration::from_secs(60),
+            |mut runs: ResMut<Runs>| {
+                runs.0 += 1;
+            },
+        );
+        let tick = |app: &mut App, secs| {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs(secs));
+            app.world_mut().run_system_once(run_world_events).unwrap();
+            app.world().resource::<Runs>().0
+        };
+
+        assert_eq!(tick(&mut app, 30), 0);
+        assert_eq!(tick(&mut app, 30), 1);
+        assert_eq!(tick(&mut app, 30), 1);
+        assert_eq!(
+            app.world().resource::<WorldEvents>().next_run("tally"),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(tick(&mut app, 30), 2);
+    }
+}
@@ -0,0 +1,240 @@
+//! Supply drops: a crate of random goods delivered to the cargo tile.
+//!
+//! Configured by the [`SupplyDrop`] resource, whose presence enables the
+//! event.  Each drop spawns the crate on [`SupplyDrop::cargo_tile`] with
+//! [`SupplyDrop::rolls`] items drawn from the loot table next to it, and
+//! announces the delivery.  The items are tagged [`PackedInto`] the crate and
+//! put into it on the next tick, once both have their template components;
+//! whatever does not fit stays on the floor.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use items::{Item, ItemInsertRequest};
+use network::{Server, StreamSender};
+use serde::Deserialize;
+use things::{ThingRegistry, ThingsStreamMessage, spawn_thing};
+
+use crate::Announce;
+
+/// Name the supply drop is registered under in [`WorldEvents`](crate::WorldEvents).
+pub const SUPPLY_DROP_EVENT: &str = "supply_drop";
+
+/// Height above the floor at which the crate and its goods appear.
+const DROP_HEIGHT: f32 = 1.0;
+
+fn default_crate_template() -> String {
+    "crate".to_string()
+}
+
+fn default_announcement() -> String {
+    "A supply crate has arrived at cargo.".to_string()
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// One entry of the loot table: a thing template and how likely it is to
+/// be drawn relative to the others.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LootEntry {
+    pub template: String,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+/// Server-side configuration of the supply drop.
+#[derive(Resource, Debug, Clone, PartialEq, Deserialize)]
+pub struct SupplyDrop {
+    /// Minutes between two drops.
+    pub every_minutes: f32,
+    /// Cell the crate is delivered to.
+    pub cargo_tile: [i32; 2],
+    /// Template of the container the goods come in.
+    #[serde(default = "default_crate_template")]
+    pub crate_template: String,
+    /// Number of items drawn from `loot` for each crate.
+    pub rolls: u32,
+    pub loot: Vec<LootEntry>,
+    /// Text announced to every player on delivery.
+    #[serde(default = "default_announcement")]
+    pub announcement: String,
+}
+
+impl SupplyDrop {
+    /// Time between two drops.
+    pub fn interval(&self) -> Duration {
+        Duration::try_from_secs_f32(self.every_minutes * 60.0).unwrap_or_default()
+    }
+
+    /// World position the crate is delivered to.
+    pub fn drop_position(&self) -> Vec3 {
+        let [x, z] = self.cargo_tile;
+        Vec3::new(x as f32, DROP_HEIGHT, z as f32)
+    }
+
+    /// Draws one template from the loot table, or `None` when it is empty.
+    pub fn roll(&self, rng: &mut SupplyRng) -> Option<&str> {
+        let total: u32 = self.loot.iter().map(|entry| entry.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = rng.next_below(total);
+        for entry in &self.loot {
+            if pick < entry.weight {
+                return Some(&entry.template);
+            }
+            pick -= entry.weight;
+        }
+        None
+    }
+}
+
+/// Small deterministic random source for loot rolls.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct SupplyRng(pub u64);
+
+impl Default for SupplyRng {
+    fn default() -> Self {
+        Self(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+impl SupplyRng {
+    /// Next value in `0..bound`.
+    pub fn next_below(&mut self, bound: u32) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        (((x >> 32) * u64::from(bound)) >> 32) as u32
+    }
+}
+
+/// Goods waiting to be put into the supply crate they were delivered with.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackedInto(pub Entity);
+
+/// Event system: delivers a supply crate with its goods to the cargo tile,
+/// broadcasts the spawns on the things stream and announces the delivery.
+pub fn drop_supplies(
+    mut commands: Commands,
+    supply: Option<Res<SupplyDrop>>,
+    registry: Res<ThingRegistry>,
+    mut server: ResMut<Server>,
+    mut rng: ResMut<SupplyRng>,
+    things_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+    mut announce: MessageWriter<Announce>,
+) {
+    let Some(supply) = supply else {
+        return;
+    };
+    let Some(crate_kind) = registry.kind_by_name(&supply.crate_template) else {
+        warn!(
+            "Supply drop: unknown crate template {:?}",
+            supply.crate_template
+        );
+        return;
+    };
+    let position = supply.drop_position();
+    let goods: Vec<u16> = (0..supply.rolls)
+        .filter_map(|_| supply.roll(&mut rng))
+        .filter_map(|name| {
+            let kind = registry.kind_by_name(name);
+            if kind.is_none() {
+                warn!("Supply drop: unknown loot template {name:?}");
+            }
+            kind
+        })
+        .collect();
+
+    let (supply_crate, net_id) = spawn_thing(&mut commands, &mut server, crate_kind, position);
+    let mut spawned = vec![(net_id, crate_kind)];
+    for &kind in &goods {
+        let (item, net_id) = spawn_thing(&mut commands, &mut server, kind, position);
+        commands.entity(item).insert(PackedInto(supply_crate));
+        spawned.push((net_id, kind));
+    }
+    info!(
+        "Supply drop at {:?}: crate with {} items",
+        supply.cargo_tile,
+        goods.len()
+    );
+    announce.write(Announce {
+        text: supply.announcement.clone(),
+    });
+
+    let Some(sender) = things_sender else {
+        return;
+    };
+    for (net_id, kind) in spawned {
+        if let Err(e) = sender.broadcast(&ThingsStreamMessage::EntitySpawned {
+            net_id,
+            kind,
+            position: position.into(),
+            velocity: [0.0, 0.0, 0.0],
+            owner: None,
+            name: None,
+        }) {
+            error!(
+                "Failed to broadcast EntitySpawned for NetId({}): {e}",
+                net_id.0
+            );
+        }
+    }
+}
+
+/// Server system: asks the items module to put [`PackedInto`] goods into
+/// their crate.
+pub(crate) fn pack_supply_crates(
+    mut commands: Commands,
+    packed: Query<(Entity, &PackedInto), With<Item>>,
+    mut insert_req: MessageWriter<ItemInsertRequest>,
+) {
+    for (item, &PackedInto(container)) in packed.iter() {
+        commands.entity(item).remove::<PackedInto>();
+        insert_req.write(ItemInsertRequest { item, container });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loot_rolls_follow_the_weights() {
+        let drop = SupplyDrop {
+            every_minutes: 10.0,
+            cargo_tile: [4, 9],
+            crate_template: default_crate_template(),
+            rolls: 3,
+            loot: vec![
+                LootEntry {
+                    template: "plasteel".into(),
+                    weight: 3,
+                },
+                LootEntry {
+                    template: "can".into(),
+                    weight: 1,
+                },
+            ],
+            announcement: default_announcement(),
+        };
+        assert_eq!(drop.interval(), Duration::from_secs(600));
+        assert_eq!(drop.drop_position(), Vec3::new(4.0, DROP_HEIGHT, 9.0));
+
+        let mut rng = SupplyRng::default();
+        let plasteel = (0..4000)
+            .filter(|_| drop.roll(&mut rng) == Some("plasteel"))
+            .count();
+        assert!((2800..3200).contains(&plasteel), "{plasteel} of 4000");
+
+        let empty = SupplyDrop {
+            loot: Vec::new(),
+            ..drop
+        };
+        assert_eq!(empty.roll(&mut rng), None);
+    }
+}
@@ -0,0 +1,315 @@
+//! Game modes: what a round is about and how it ends.
+//!
+//! The server runs exactly one [`GameMode`], chosen by name in its config
+//! (see [`game_mode_by_name`]).  Its [`GameMode::respawn_rules`] replace
+//! the souls module's [`RespawnRules`] when the plugin is built.
+//!
+//! Rounds cycle through the phases of the replicated [`RoundState`]:
+//!
+//! - **Lobby:** players that join wait without a creature until the lobby
+//!   time is up.
+//! - **In round:** the mode is set up, everyone waiting is spawned, and the
+//!   mode is told about every player that joins and ticked until it
+//!   declares a [`RoundOutcome`], which every player is told with an
+//!   [`Announce`].
+//! - **Round over:** interactions are refused and clients show the outcome
+//!   until the restart delay is up; then the station is reset (see
+//!   [`reset_world`]) and a new lobby opens.
+//!
+//! Admins and player votes (see [`VotePlugin`]) end a round early with a
+//! [`RoundRequest`], which goes straight to the round over phase.
+//!
+//! Two modes ship with the game: the open-ended [`Sandbox`] and
+//! [`Survival`], where the crew must stay alive until the shuttle arrives.
+
+use std::time::Duration;
+
+use bevy::ecs::message::MessageCursor;
+use bevy::prelude::*;
+use events::Announce;
+use network::{ClientId, PlayerEvent, RoundState, Server, ServerGameState, SimulationTick};
+use souls::RespawnRules;
+use world::MapPath;
+
+mod round;
+pub use round::reset_world;
+mod sandbox;
+pub use sandbox::Sandbox;
+mod survival;
+pub use survival::Survival;
+mod vote;
+pub use vote::{Verdict, Vote, VotePlugin, VoteSettings, Votes};
+
+/// How a round ended, with the reason told to the players.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoundOutcome {
+    Won(String),
+    Lost(String),
+}
+
+impl RoundOutcome {
+    /// Text announced to every player when the round ends.
+    pub fn announcement(&self) -> String {
+        match self {
+            RoundOutcome::Won(reason) => format!("The crew wins: {reason}"),
+            RoundOutcome::Lost(reason) => format!("The crew loses: {reason}"),
+        }
+    }
+}
+
+/// The rules of a round.  Every hook runs on the server with exclusive
+/// access to the world.
+pub trait GameMode: Send + Sync + 'static {
+    /// Name selecting the mode in the server config.
+    fn name(&self) -> &'static str;
+
+    /// Whether and when dead players may rejoin while this mode runs.
+    fn respawn_rules(&self) -> RespawnRules {
+        RespawnRules::default()
+    }
+
+    /// Called whenever a round starts.
+    fn setup(&mut self, _world: &mut World) {}
+
+    /// Called when a player, not a spectator, joins the server.
+    fn on_player_join(&mut self, _world: &mut World, _client: ClientId, _name: &str) {}
+
+    /// Called every simulation tick until the round is decided.
+    fn tick(&mut self, _world: &mut World) {}
+
+    /// The outcome of the round once it is over; checked after every tick.
+    fn outcome(&mut self, world: &mut World) -> Option<RoundOutcome>;
+}
+
+/// The built-in mode called `name`, if any.  `round_length` is how long the
+/// crew must hold out in timed modes.
+pub fn game_mode_by_name(name: &str, round_length: Duration) -> Option<Box<dyn GameMode>> {
+    match name {
+        "sandbox" => Some(Box::new(Sandbox)),
+        "survival" => Some(Box::new(Survival::new(round_length))),
+        _ => None,
+    }
+}
+
+/// Server-side request to end the current round early.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub enum RoundRequest {
+    /// End the round, telling the players `reason`, and reset the station.
+    Restart { reason: String },
+    /// Like `Restart`, loading every layer of the map at `path` on reset:
+    /// its tiles, atmosphere and things replace those of the current map.
+    ChangeMap { path: String },
+}
+
+/// How long the lobby and the end of a round last.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RoundTimings {
+    /// Wait in the lobby before a round starts.
+    pub lobby: Duration,
+    /// Time the outcome is shown before the station is reset.
+    pub restart: Duration,
+}
+
+/// Server resource: the running [`GameMode`] and, once decided, how the
+/// current round ended.
+#[derive(Resource)]
+pub struct ActiveGameMode {
+    mode: Box<dyn GameMode>,
+    timings: RoundTimings,
+    outcome: Option<RoundOutcome>,
+}
+
+impl ActiveGameMode {
+    pub fn new(mode: Box<dyn GameMode>, timings: RoundTimings) -> Self {
+        Self {
+            mode,
+            timings,
+            outcome: None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.mode.name()
+    }
+
+    /// How the round ended, or `None` while it is still running.
+    pub fn outcome(&self) -> Option<&RoundOutcome> {
+        self.outcome.as_ref()
+    }
+}
+
+/// Server system: moves the round through its phases, running the mode
+/// while it is in round and ending it on a [`RoundRequest`].
+fn run_game_mode(
+    world: &mut World,
+    mut joins: Local<MessageCursor<PlayerEvent>>,
+    mut requests: Local<MessageCursor<RoundRequest>>,
+) {
+    let joined: Vec<(ClientId, String)> = joins
+        .read(world.resource::<Messages<PlayerEvent>>())
+        .filter_map(|event| match event {
+            PlayerEvent::Joined {
+                id,
+                name,
+                spectator: false,
+            } => Some((*id, name.clone())),
+            _ => None,
+        })
+        .collect();
+    let requests: Vec<RoundRequest> = requests
+        .read(world.resource::<Messages<RoundRequest>>())
+        .cloned()
+        .collect();
+    let now = world.resource::<Time>().elapsed();
+    let round = world.resource::<RoundState>().clone();
+    for request in &requests {
+        if let RoundRequest::ChangeMap { path } = request {
+            // Picked up by the reset of a round that is already over, too.
+            world.insert_resource(MapPath::new(path));
+        }
+    }
+    if let Some(request) = requests.last()
+        && !round.is_frozen()
+    {
+        let summary = match request {
+            RoundRequest::Restart { reason } => reason.clone(),
+            RoundRequest::ChangeMap { path } => format!("Changing the map to {path}"),
+        };
+        info!("Round ended early: {summary}");
+        world.write_message(Announce {
+            text: summary.clone(),
+        });
+        let restart = world.resource::<ActiveGameMode>().timings.restart;
+        *world.resource_mut::<RoundState>() = RoundState {
+            state: ServerGameState::RoundOver { summary },
+            ends_at: Some(now + restart),
+        };
+        return;
+    }
+    let phase_over = round.remaining(now).is_some_and(|left| left.is_zero());
+
+    world.resource_scope(|world, mut active: Mut<ActiveGameMode>| match round.state {
+        ServerGameState::Lobby => {
+            if !phase_over {
+                return;
+            }
+            *world.resource_mut::<RoundState>() = RoundState {
+                state: ServerGameState::InRound,
+                ends_at: None,
+            };
+            info!("Starting a round of {}", active.mode.name());
+            world.write_message(Announce {
+                text: "The round has started.".to_string(),
+            });
+            active.mode.setup(world);
+        }
+        ServerGameState::InRound => {
+            for (client, name) in &joined {
+                active.mode.on_player_join(world, *client, name);
+            }
+            active.mode.tick(world);
+            let Some(outcome) = active.mode.outcome(world) else {
+                return;
+            };
+            let summary = outcome.announcement();
+            info!("Round of {} over: {summary}", active.mode.name());
+            world.write_message(Announce {
+                text: summary.clone(),
+            });
+            *world.resource_mut::<RoundState>() = RoundState {
+                state: ServerGameState::RoundOver { summary },
+                ends_at: Some(now + active.timings.restart),
+            };
+            active.outcome = Some(outcome);
+        }
+        ServerGameState::RoundOver { .. } => {
+            if !phase_over {
+                return;
+            }
+            reset_world(world);
+            active.outcome = None;
+            *world.resource_mut::<RoundState>() = RoundState {
+                state: ServerGameState::Lobby,
+                ends_at: Some(now + active.timings.lobby),
+            };
+        }
+    });
+}
+
+/// Runs the configured [`GameMode`] on servers, starting with a lobby.
+/// Unknown names fall back to the [`Sandbox`].  Add after `NetworkPlugin`,
+/// `SoulsPlugin` and `EventsPlugin`.
+pub struct GameModePlugin {
+    /// Name of the mode, see [`game_mode_by_name`].
+    pub mode: String,
+    /// How long the crew must hold out in timed modes.
+    pub round_length: Duration,
+    /// How long the lobby and the end of a round last.
+    pub timings: RoundTimings,
+}
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        let mode = game_mode_by_name(&self.mode, self.round_length).unwrap_or_else(|| {
+            warn!(
+                "Unknown game mode \"{}\", playing sandbox instead",
+                self.mode
+            );
+            Box::new(Sandbox)
+        });
+        app.add_message::<RoundRequest>();
+        app.insert_resource(mode.respawn_rules());
+        app.insert_resource(ActiveGameMode::new(mode, self.timings));
+        app.insert_resource(RoundState {
+            state: ServerGameState::Lobby,
+            ends_at: Some(self.timings.lobby),
+        });
+        app.add_systems(
+            SimulationTick,
+            run_game_mode.run_if(resource_exists::<Server>),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tiles::{TileGrid, TileKind};
+
+    use super::*;
+    use crate::round::tests::{TempMap, tiles_world};
+
+    #[test]
+    fn change_map_loads_the_new_map_tiles() {
+        let old = TempMap::new("change_old", TileGrid::new_fill(4, 4, TileKind::Floor));
+        let new = TempMap::new("change_new", TileGrid::new_fill(6, 3, TileKind::Wall));
+        let mut world = tiles_world();
+        world.insert_resource(MapPath::new(old.path().to_string_lossy()));
+        world::reload_map(&mut world).expect("load map");
+        world.init_resource::<Time>();
+        world.init_resource::<Messages<PlayerEvent>>();
+        world.init_resource::<Messages<RoundRequest>>();
+        world.init_resource::<Messages<Announce>>();
+        world.insert_resource(RoundState {
+            state: ServerGameState::InRound,
+            ends_at: None,
+        });
+        world.insert_resource(ActiveGameMode::new(
+            Box::new(Sandbox),
+            RoundTimings::default(),
+        ));
+
+        world.write_message(RoundRequest::ChangeMap {
+            path: new.path().to_string_lossy().into_owned(),
+        });
+        let mut system = IntoSystem::into_system(run_game_mode);
+        system.initialize(&mut world);
+        // Ends the round, then resets the station with no restart delay.
+        system.run((), &mut world).unwrap();
+        system.run((), &mut world).unwrap();
+
+        assert!(world.resource::<RoundState>().is_lobby());
+        let grid = world.resource::<TileGrid<TileKind>>();
+        assert_eq!(grid.get_copy(IVec2::new(1, 1)), Some(TileKind::Wall));
+        assert_eq!(grid.get_copy(IVec2::new(5, 2)), Some(TileKind::Wall));
+    }
+}
@@ -0,0 +1,218 @@
+//! Resetting the station between rounds.
+//!
+//! [`reset_world`] clears every thing from the station, creatures and
+//! ghosts included, then loads every layer of the map file at
+//! [`MapPath`](world::MapPath) again through [`world::reload_map`]: tiles,
+//! floors and upper decks, the atmosphere, spawn points and the map's
+//! things.  The rebuilt tilemap and gas grid go out to connected clients
+//! from the `tiles` and `atmospherics` modules; the despawned and respawned
+//! things are announced here.  Players keep their souls, unbound, and get a
+//! new creature when the next round starts.  Spectators keep their
+//! observers.
+
+use bevy::prelude::*;
+use network::{NetId, Spectator, StreamSender};
+use souls::{Deceased, Ghost, Soul};
+use things::{DespawnReason, NetIdIndex, ReplicatedJoint, Thing, ThingsStreamMessage};
+
+/// Despawns every thing but spectators' observers, telling clients, and
+/// unbinds and revives the souls that were bound to them.
+fn clear_things(world: &mut World) {
+    let mut things =
+        world.query_filtered::<(Entity, Option<&NetId>, Has<Spectator>, Has<Ghost>), With<Thing>>();
+    let doomed: Vec<(Entity, Option<NetId>)> = things
+        .iter(world)
+        .filter(|&(_, _, spectator, ghost)| !spectator || ghost)
+        .map(|(entity, net_id, ..)| (entity, net_id.copied()))
+        .collect();
+    let mut joints = world.query_filtered::<Entity, With<ReplicatedJoint>>();
+    let joints: Vec<Entity> = joints.iter(world).collect();
+
+    for &(_, net_id) in &doomed {
+        let Some(net_id) = net_id else {
+            continue;
+        };
+        world.resource_mut::<NetIdIndex>().0.remove(&net_id);
+        if let Some(sender) = world.get_resource::<StreamSender<ThingsStreamMessage>>()
+            && let Err(e) = sender.broadcast(&ThingsStreamMessage::EntityDespawned {
+                net_id,
+                reason: DespawnReason::Destroyed,
+            })
+        {
+            error!(
+                "Failed to broadcast EntityDespawned for NetId({}): {e}",
+                net_id.0
+            );
+        }
+    }
+    // Held and stored things go with their parents.
+    for &entity in joints.iter().chain(doomed.iter().map(|(entity, _)| entity)) {
+        if let Ok(entity) = world.get_entity_mut(entity) {
+            entity.despawn();
+        }
+    }
+
+    let mut souls = world.query::<(Entity, &mut Soul)>();
+    let mut revived = Vec::new();
+    for (soul_entity, mut soul) in souls.iter_mut(world) {
+        if soul
+            .bound_to
+            .is_some_and(|body| doomed.iter().any(|&(entity, _)| entity == body))
+        {
+            soul.bound_to = None;
+            revived.push(soul_entity);
+        }
+    }
+    for soul in revived {
+        world.entity_mut(soul).remove::<Deceased>();
+    }
+}
+
+/// Loads the map at [`MapPath`](world::MapPath) again over the station and
+/// announces the things it spawned to connected clients.
+fn reload_map(world: &mut World) -> Result<(), String> {
+    let mut existing = world.query_filtered::<Entity, With<Thing>>();
+    let existing: Vec<Entity> = existing.iter(world).collect();
+    world::reload_map(world)?;
+    world.flush();
+
+    let mut roots = world.query_filtered::<Entity, (With<Thing>, Without<ChildOf>)>();
+    let spawned: Vec<Entity> = roots
+        .iter(world)
+        .filter(|entity| !existing.contains(entity))
+        .collect();
+    for root in spawned {
+        items::announce_spawned(world, root);
+    }
+    Ok(())
+}
+
+/// Clears the station and loads its map again; see the module docs.  A map
+/// that cannot be loaded is logged.
+pub fn reset_world(world: &mut World) {
+    clear_things(world);
+    match reload_map(world) {
+        Ok(()) => info!("Station reset for the next round"),
+        Err(e) => error!("Failed to reload the map: {e}"),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::path::{Path, PathBuf};
+
+    use network::ClientId;
+    use tiles::{TileGrid, TileKind, TilesLayer};
+    use world::{MapLayerRegistry, MapPath};
+
+    use super::*;
+
+    /// A map file in the temp directory, deleted on drop.
+    pub(crate) struct TempMap(pub PathBuf);
+
+    impl TempMap {
+        /// Saves the tiles of `grid` as map `name`.
+        pub(crate) fn new(name: &str, grid: TileGrid<TileKind>) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "gamemode_{name}_{}.station.ron",
+                std::process::id()
+            ));
+            let mut world = tiles_world();
+            world.insert_resource(grid);
+            world::save_world_to(&mut world, &path, 0).expect("save map");
+            TempMap(path)
+        }
+
+        pub(crate) fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempMap {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// A world that loads and saves the tiles layer.
+    pub(crate) fn tiles_world() -> World {
+        let mut world = World::new();
+        let mut registry = MapLayerRegistry::new();
+        registry.register(TilesLayer);
+        world.insert_resource(registry);
+        world.init_resource::<NetIdIndex>();
+        world
+    }
+
+    #[test]
+    fn reset_restores_the_map_tiles() {
+        let mut grid = TileGrid::<TileKind>::new_fill(4, 4, TileKind::Floor);
+        grid.set(IVec2::new(1, 1), TileKind::Wall);
+        let map = TempMap::new("reset", grid);
+        let mut world = tiles_world();
+        world.insert_resource(MapPath::new(map.path().to_string_lossy()));
+        world::reload_map(&mut world).expect("load map");
+
+        // The wall is broken during the round.
+        world
+            .resource_mut::<TileGrid<TileKind>>()
+            .set(IVec2::new(1, 1), TileKind::Floor);
+
+        reset_world(&mut world);
+
+        assert_eq!(
+            world
+                .resource::<TileGrid<TileKind>>()
+                .get_copy(IVec2::new(1, 1)),
+            Some(TileKind::Wall)
+        );
+    }
+
+    #[test]
+    fn clearing_unbinds_players_and_keeps_observers() {
+        let mut world = World::new();
+        world.init_resource::<NetIdIndex>();
+        let body = world.spawn((Thing { kind: 0 }, NetId(1))).id();
+        let ghost = world.spawn((Thing { kind: 1 }, Spectator, Ghost)).id();
+        let observer = world.spawn((Thing { kind: 2 }, Spectator)).id();
+        let player = world
+            .spawn(Soul {
+                name: "Ann".into(),
+                client_id: ClientId(1),
+                bound_to: Some(body),
+            })
+            .id();
+        let dead = world
+            .spawn((
+                Soul {
+                    name: "Bob".into(),
+                    client_id: ClientId(2),
+                    bound_to: Some(ghost),
+                },
+                Deceased {
+                    respawn_at: Default::default(),
+                },
+            ))
+            .id();
+        let spectator = world
+            .spawn(Soul {
+                name: "Cy".into(),
+                client_id: ClientId(3),
+                bound_to: Some(observer),
+            })
+            .id();
+
+        clear_things(&mut world);
+
+        assert!(world.get_entity(body).is_err());
+        assert!(world.get_entity(ghost).is_err());
+        assert!(world.get_entity(observer).is_ok());
+        assert_eq!(world.get::<Soul>(player).unwrap().bound_to, None);
+        assert_eq!(world.get::<Soul>(dead).unwrap().bound_to, None);
+        assert!(world.get::<Deceased>(dead).is_none());
+        assert_eq!(
+            world.get::<Soul>(spectator).unwrap().bound_to,
+            Some(observer)
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! Sandbox: build, break and explore with no goal and no end.
+
+use bevy::prelude::*;
+
+use crate::{GameMode, RoundOutcome};
+
+/// The open-ended mode: the round never ends, and the dead rejoin after
+/// the usual [`RESPAWN_DELAY`](souls::RESPAWN_DELAY).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sandbox;
+
+impl GameMode for Sandbox {
+    fn name(&self) -> &'static str {
+        "sandbox"
+    }
+
+    fn outcome(&mut self, _world: &mut World) -> Option<RoundOutcome> {
+        None
+    }
+}
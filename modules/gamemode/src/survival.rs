@@ -0,0 +1,153 @@
+//! Survival: stay alive until the shuttle arrives.
+//!
+//! The round lasts a fixed time from setup, and nobody comes back from the
+//! dead.  The crew wins if anyone is still alive when the time is up, and
+//! loses as soon as every player has died.  Spectators do not count either
+//! way.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use events::Announce;
+use network::{ClientId, Spectator};
+use souls::{Deceased, RespawnRules, Soul};
+
+use crate::{GameMode, RoundOutcome};
+
+/// Whole minutes in `duration`, rounded up, for announcements.
+fn minutes(duration: Duration) -> u64 {
+    duration.as_secs().div_ceil(60)
+}
+
+/// The timed mode: survive `round_length` without respawns.
+#[derive(Debug, Clone)]
+pub struct Survival {
+    round_length: Duration,
+    /// `Time::elapsed` at which the shuttle arrives.
+    ends_at: Duration,
+}
+
+impl Survival {
+    pub fn new(round_length: Duration) -> Self {
+        Self {
+            round_length,
+            ends_at: round_length,
+        }
+    }
+}
+
+impl GameMode for Survival {
+    fn name(&self) -> &'static str {
+        "survival"
+    }
+
+    fn respawn_rules(&self) -> RespawnRules {
+        RespawnRules {
+            allowed: false,
+            ..default()
+        }
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        self.ends_at = world.resource::<Time>().elapsed() + self.round_length;
+    }
+
+    fn on_player_join(&mut self, world: &mut World, _client: ClientId, name: &str) {
+        let left = self
+            .ends_at
+            .saturating_sub(world.resource::<Time>().elapsed());
+        world.write_message(Announce {
+            text: format!(
+                "{name} has joined the crew. Stay alive: the shuttle arrives in {} minutes, \
+                 and the dead stay dead.",
+                minutes(left)
+            ),
+        });
+    }
+
+    fn outcome(&mut self, world: &mut World) -> Option<RoundOutcome> {
+        let (mut living, mut dead) = (0, 0);
+        let mut souls = world.query::<(&Soul, Has<Deceased>)>();
+        for (soul, deceased) in souls.iter(world) {
+            if deceased {
+                dead += 1;
+            } else if soul
+                .bound_to
+                .is_some_and(|body| !world.entity(body).contains::<Spectator>())
+            {
+                living += 1;
+            }
+        }
+
+        if dead > 0 && living == 0 {
+            return Some(RoundOutcome::Lost("everyone died.".to_string()));
+        }
+        if world.resource::<Time>().elapsed() < self.ends_at {
+            return None;
+        }
+        Some(if living > 0 {
+            RoundOutcome::Won(format!("{living} survived until the shuttle arrived."))
+        } else {
+            RoundOutcome::Lost("nobody was left to board the shuttle.".to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advance(world: &mut World, secs: u64) {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(secs));
+    }
+
+    fn spawn_player(world: &mut World, client: u64) {
+        let body = world.spawn_empty().id();
+        world.spawn(Soul {
+            name: format!("Player {client}"),
+            client_id: ClientId(client),
+            bound_to: Some(body),
+        });
+    }
+
+    #[test]
+    fn survivors_win_when_the_time_is_up() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        spawn_player(&mut world, 1);
+        let mut survival = Survival::new(Duration::from_secs(600));
+        assert!(!survival.respawn_rules().allowed);
+        survival.setup(&mut world);
+
+        advance(&mut world, 599);
+        assert_eq!(survival.outcome(&mut world), None);
+        advance(&mut world, 1);
+        assert!(matches!(
+            survival.outcome(&mut world),
+            Some(RoundOutcome::Won(_))
+        ));
+    }
+
+    #[test]
+    fn the_round_is_lost_once_every_player_died() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        spawn_player(&mut world, 1);
+        let mut survival = Survival::new(Duration::from_secs(600));
+        survival.setup(&mut world);
+
+        let soul = world
+            .query_filtered::<Entity, With<Soul>>()
+            .single(&world)
+            .unwrap();
+        world.entity_mut(soul).insert(Deceased {
+            respawn_at: Duration::ZERO,
+        });
+        assert!(matches!(
+            survival.outcome(&mut world),
+            Some(RoundOutcome::Lost(_))
+        ));
+    }
+}
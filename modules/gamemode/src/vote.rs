@@ -0,0 +1,321 @@
+//! Player votes on restarting the round, changing the map and kicking a
+//! player.
+//!
+//! Clients send [`VoteRequest`]s on the client→server votes stream: any
+//! player may start a vote when none is running, and every player may cast
+//! one ballot on it, the starter voting yes.  The server tells everyone
+//! about each vote and ballot with an [`Announce`], and decides the vote as
+//! [`VoteSettings`] say:
+//!
+//! - it passes as soon as more than half of the connected players voted
+//!   yes, and fails as soon as that can no longer happen;
+//! - when time is up it passes if at least `quorum` of the players voted
+//!   and yes outnumbers no.
+//!
+//! A passed vote is carried out through the same means as the admin
+//! console: a [`RoundRequest`] for restarts and map changes, and
+//! [`NetServerSender::kick`] for kicks.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use events::Announce;
+use network::{
+    ClientId, NetServerSender, Server, StreamDef, StreamDirection, StreamReader, StreamRegistry,
+    StreamSender, VOTES_STREAM_TAG, VoteKind, VoteRequest,
+};
+use souls::Soul;
+
+use crate::RoundRequest;
+
+/// Server resource: how votes are decided.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct VoteSettings {
+    /// How long a vote stays open.
+    pub duration: Duration,
+    /// Fraction of the connected players that must have voted for a vote
+    /// to pass when time is up.
+    pub quorum: f32,
+    /// Map files players may vote to change to.  Map votes are refused
+    /// when empty.
+    pub maps: Vec<String>,
+}
+
+impl Default for VoteSettings {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(30),
+            quorum: 0.5,
+            maps: Vec::new(),
+        }
+    }
+}
+
+/// A vote being held.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vote {
+    pub kind: VoteKind,
+    /// What is voted on, as told to the players.
+    pub subject: String,
+    pub yes: HashSet<ClientId>,
+    pub no: HashSet<ClientId>,
+    /// `Time::elapsed` at which the vote closes.
+    pub ends_at: Duration,
+}
+
+/// How a vote stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Open,
+    Passed,
+    Failed,
+}
+
+impl Vote {
+    /// The verdict at `now` with `players` connected.
+    pub fn verdict(&self, players: usize, quorum: f32, now: Duration) -> Verdict {
+        let (yes, no) = (self.yes.len(), self.no.len());
+        let majority = players / 2 + 1;
+        if yes >= majority {
+            return Verdict::Passed;
+        }
+        if players.saturating_sub(no) < majority {
+            return Verdict::Failed;
+        }
+        if now < self.ends_at {
+            return Verdict::Open;
+        }
+        let turnout = (yes + no) as f32 / players.max(1) as f32;
+        if turnout >= quorum && yes > no {
+            Verdict::Passed
+        } else {
+            Verdict::Failed
+        }
+    }
+
+    fn tally(&self) -> String {
+        format!(
+            "Vote to {}: {} yes, {} no",
+            self.subject,
+            self.yes.len(),
+            self.no.len()
+        )
+    }
+}
+
+/// Server resource: the vote being held, if any.
+#[derive(Resource, Debug, Default)]
+pub struct Votes {
+    pub current: Option<Vote>,
+}
+
+/// What `kind` asks for, as told to the players, or why it may not be
+/// voted on.
+fn describe(kind: &VoteKind, settings: &VoteSettings, souls: &[&Soul]) -> Result<String, String> {
+    match kind {
+        VoteKind::RestartRound => Ok("restart the round".to_string()),
+        VoteKind::ChangeMap { map } if settings.maps.contains(map) => {
+            Ok(format!("change the map to {map}"))
+        }
+        VoteKind::ChangeMap { map } => Err(format!("\"{map}\" is not a map that can be voted on")),
+        VoteKind::Kick { player } => souls
+            .iter()
+            .find(|soul| soul.client_id == *player)
+            .map(|soul| format!("kick {}", soul.name))
+            .ok_or_else(|| format!("no player with ClientId({})", player.0)),
+    }
+}
+
+/// Server system: starts votes and records ballots from the votes stream.
+fn handle_vote_requests(
+    time: Res<Time>,
+    settings: Res<VoteSettings>,
+    mut votes: ResMut<Votes>,
+    mut reader: ResMut<StreamReader<VoteRequest>>,
+    souls: Query<&Soul>,
+    mut announce: MessageWriter<Announce>,
+) {
+    let souls: Vec<&Soul> = souls.iter().collect();
+    for (from, request) in reader.drain_from_client() {
+        let Some(voter) = souls.iter().find(|soul| soul.client_id == from) else {
+            continue;
+        };
+        match request {
+            VoteRequest::Start(kind) => {
+                if votes.current.is_some() {
+                    debug!("Vote from ClientId({}) ignored: a vote is running", from.0);
+                    continue;
+                }
+                let subject = match describe(&kind, &settings, &souls) {
+                    Ok(subject) => subject,
+                    Err(e) => {
+                        debug!("Vote from ClientId({}) ignored: {e}", from.0);
+                        continue;
+                    }
+                };
+                info!("{} started a vote to {subject}", voter.name);
+                announce.write(Announce {
+                    text: format!(
+                        "{} started a vote to {subject} ({}s left)",
+                        voter.name,
+                        settings.duration.as_secs()
+                    ),
+                });
+                votes.current = Some(Vote {
+                    kind,
+                    subject,
+                    yes: HashSet::from([from]),
+                    no: HashSet::new(),
+                    ends_at: time.elapsed() + settings.duration,
+                });
+            }
+            VoteRequest::Cast { yes } => {
+                let Some(vote) = votes.current.as_mut() else {
+                    debug!("Ballot from ClientId({}) ignored: no vote", from.0);
+                    continue;
+                };
+                let changed = if yes {
+                    vote.no.remove(&from);
+                    vote.yes.insert(from)
+                } else {
+                    vote.yes.remove(&from);
+                    vote.no.insert(from)
+                };
+                if changed {
+                    announce.write(Announce { text: vote.tally() });
+                }
+            }
+        }
+    }
+}
+
+/// Server system: closes the running vote once it is decided and carries
+/// it out if it passed.
+fn conclude_votes(
+    time: Res<Time>,
+    settings: Res<VoteSettings>,
+    mut votes: ResMut<Votes>,
+    souls: Query<&Soul>,
+    sender: Option<Res<NetServerSender>>,
+    mut rounds: MessageWriter<RoundRequest>,
+    mut announce: MessageWriter<Announce>,
+) {
+    let Some(vote) = votes.current.as_mut() else {
+        return;
+    };
+    // Players who left take their ballots with them.
+    let players: HashSet<ClientId> = souls.iter().map(|soul| soul.client_id).collect();
+    vote.yes.retain(|client| players.contains(client));
+    vote.no.retain(|client| players.contains(client));
+
+    let verdict = vote.verdict(players.len(), settings.quorum, time.elapsed());
+    if verdict == Verdict::Open {
+        return;
+    }
+    let Some(vote) = votes.current.take() else {
+        return;
+    };
+    let passed = verdict == Verdict::Passed;
+    info!(
+        "{} ({})",
+        vote.tally(),
+        if passed { "passed" } else { "failed" }
+    );
+    announce.write(Announce {
+        text: format!(
+            "{}: the vote {}",
+            vote.tally(),
+            if passed { "passes" } else { "fails" }
+        ),
+    });
+    if !passed {
+        return;
+    }
+    match vote.kind {
+        VoteKind::RestartRound => {
+            rounds.write(RoundRequest::Restart {
+                reason: "The players voted to restart the round".to_string(),
+            });
+        }
+        VoteKind::ChangeMap { map } => {
+            rounds.write(RoundRequest::ChangeMap { path: map });
+        }
+        VoteKind::Kick { player } => {
+            if let Some(sender) = sender {
+                sender.kick(player, "Kicked by a vote of the players");
+            }
+        }
+    }
+}
+
+/// Registers the votes stream, on clients and servers alike, and holds
+/// votes on servers.  Add after `NetworkPlugin` and `EventsPlugin`; servers
+/// also need the [`GameModePlugin`](crate::GameModePlugin).
+#[derive(Default)]
+pub struct VotePlugin {
+    pub settings: VoteSettings,
+}
+
+impl Plugin for VotePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<RoundRequest>();
+        app.insert_resource(self.settings.clone());
+        app.init_resource::<Votes>();
+        app.add_systems(
+            Update,
+            (handle_vote_requests, conclude_votes)
+                .chain()
+                .run_if(resource_exists::<Server>),
+        );
+
+        let (sender, reader): (StreamSender<VoteRequest>, StreamReader<VoteRequest>) = app
+            .world_mut()
+            .get_resource_mut::<StreamRegistry>()
+            .expect(
+                "VotePlugin requires NetworkPlugin to be added before it (StreamRegistry not found)",
+            )
+            .register(StreamDef {
+                tag: VOTES_STREAM_TAG,
+                name: "votes",
+                direction: StreamDirection::ClientToServer,
+            });
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vote(yes: &[u64], no: &[u64]) -> Vote {
+        Vote {
+            kind: VoteKind::RestartRound,
+            subject: "restart the round".into(),
+            yes: yes.iter().map(|&id| ClientId(id)).collect(),
+            no: no.iter().map(|&id| ClientId(id)).collect(),
+            ends_at: Duration::from_secs(30),
+        }
+    }
+
+    #[test]
+    fn votes_are_decided_by_majority_or_quorum() {
+        let early = Duration::from_secs(10);
+        let late = Duration::from_secs(30);
+
+        assert_eq!(
+            vote(&[1, 2, 3], &[]).verdict(5, 0.5, early),
+            Verdict::Passed
+        );
+        assert_eq!(
+            vote(&[1], &[2, 3, 4]).verdict(5, 0.5, early),
+            Verdict::Failed
+        );
+        assert_eq!(vote(&[1, 2], &[3]).verdict(5, 0.5, early), Verdict::Open);
+
+        assert_eq!(vote(&[1, 2], &[3]).verdict(5, 0.5, late), Verdict::Passed);
+        assert_eq!(vote(&[1, 2], &[]).verdict(5, 0.5, late), Verdict::Failed);
+        assert_eq!(vote(&[1], &[2]).verdict(5, 0.3, late), Verdict::Failed);
+    }
+}
@@ -0,0 +1,541 @@
+//! Named actions and the keys or mouse buttons bound to them.
+//!
+//! Systems ask [`ActionInput`] whether an [`Action`] is pressed instead of
+//! checking raw key codes, so every binding can be changed in the config
+//! file (see [`InputMap::with_overrides`]) or at runtime, either directly
+//! through [`InputMap::rebind`] or by capturing the next input the player
+//! presses with [`PendingRebind`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// Something the player can do with a key or mouse button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    /// Held to run, while stamina lasts.
+    Sprint,
+    /// Held to sneak at reduced speed.
+    Crouch,
+    /// Climb the ladder under or below the player to another deck.
+    Climb,
+    /// Undo the player's most recent tile edit.
+    UndoTileEdit,
+    /// Switch the camera between following the player and free panning.
+    ToggleCameraMode,
+    /// Switch between the overhead and first-person camera.
+    ToggleFirstPerson,
+    OrbitCameraLeft,
+    OrbitCameraRight,
+    /// Pan the free camera.
+    PanCameraUp,
+    PanCameraDown,
+    PanCameraLeft,
+    PanCameraRight,
+    /// Default action on what is under the cursor (left click).
+    Interact,
+    /// Open the context menu for what is under the cursor (right click).
+    ContextMenu,
+    /// Close the open menu or window.
+    Cancel,
+    ToggleInventory,
+    /// Held to show the player list.
+    ShowRoster,
+    ToggleMinimap,
+    MinimapZoomIn,
+    MinimapZoomOut,
+    ToggleAtmosOverlay,
+    CycleAtmosOverlay,
+    StepAtmos,
+    ToggleAtmosPause,
+    ToggleLightingOverlay,
+    /// Show the entity inspector debug panel for the hovered thing.
+    ToggleInspector,
+    /// Draw hand anchors and held items, for tuning hold offsets.
+    ToggleHoldGizmos,
+    /// Compare the replicated world with the server's and log differences.
+    CheckDesync,
+    /// Rejoin as a new character after dying.
+    Respawn,
+    /// Open the prompt to start a vote or vote on the running one.
+    Vote,
+    /// While spectating, follow the next player, or go back to flying
+    /// freely after the last one.
+    CycleSpectateTarget,
+    /// While spectating, ask the server to stream the whole map.
+    ToggleFullRelevancy,
+    /// Cycle the diagnostics panel between hidden, collapsed and expanded.
+    ToggleDiagnostics,
+}
+
+impl Action {
+    /// Every action, in the order a settings screen lists them.
+    pub const ALL: [Action; 37] = [
+        Action::MoveForward,
+        Action::MoveBack,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Sprint,
+        Action::Crouch,
+        Action::Climb,
+        Action::UndoTileEdit,
+        Action::ToggleCameraMode,
+        Action::ToggleFirstPerson,
+        Action::OrbitCameraLeft,
+        Action::OrbitCameraRight,
+        Action::PanCameraUp,
+        Action::PanCameraDown,
+        Action::PanCameraLeft,
+        Action::PanCameraRight,
+        Action::Interact,
+        Action::ContextMenu,
+        Action::Cancel,
+        Action::ToggleInventory,
+        Action::ShowRoster,
+        Action::ToggleMinimap,
+        Action::MinimapZoomIn,
+        Action::MinimapZoomOut,
+        Action::ToggleAtmosOverlay,
+        Action::CycleAtmosOverlay,
+        Action::StepAtmos,
+        Action::ToggleAtmosPause,
+        Action::ToggleLightingOverlay,
+        Action::ToggleInspector,
+        Action::ToggleHoldGizmos,
+        Action::CheckDesync,
+        Action::Respawn,
+        Action::Vote,
+        Action::CycleSpectateTarget,
+        Action::ToggleFullRelevancy,
+        Action::ToggleDiagnostics,
+    ];
+
+    /// Binding used when the config does not override it.
+    pub fn default_binding(self) -> Binding {
+        match self {
+            Action::MoveForward => Binding::Key(KeyCode::KeyW),
+            Action::MoveBack => Binding::Key(KeyCode::KeyS),
+            Action::MoveLeft => Binding::Key(KeyCode::KeyA),
+            Action::MoveRight => Binding::Key(KeyCode::KeyD),
+            Action::Sprint => Binding::Key(KeyCode::ShiftLeft),
+            Action::Crouch => Binding::Key(KeyCode::ControlLeft),
+            Action::Climb => Binding::Key(KeyCode::KeyF),
+            Action::UndoTileEdit => Binding::Key(KeyCode::KeyZ),
+            Action::ToggleCameraMode => Binding::Key(KeyCode::KeyC),
+            Action::ToggleFirstPerson => Binding::Key(KeyCode::KeyV),
+            Action::OrbitCameraLeft => Binding::Key(KeyCode::KeyQ),
+            Action::OrbitCameraRight => Binding::Key(KeyCode::KeyE),
+            Action::PanCameraUp => Binding::Key(KeyCode::ArrowUp),
+            Action::PanCameraDown => Binding::Key(KeyCode::ArrowDown),
+            Action::PanCameraLeft => Binding::Key(KeyCode::ArrowLeft),
+            Action::PanCameraRight => Binding::Key(KeyCode::ArrowRight),
+            Action::Interact => Binding::Mouse(MouseButton::Left),
+            Action::ContextMenu => Binding::Mouse(MouseButton::Right),
+            Action::Cancel => Binding::Key(KeyCode::Escape),
+            Action::ToggleInventory => Binding::Key(KeyCode::KeyI),
+            Action::ShowRoster => Binding::Key(KeyCode::Tab),
+            Action::ToggleMinimap => Binding::Key(KeyCode::KeyM),
+            Action::MinimapZoomIn => Binding::Key(KeyCode::Equal),
+            Action::MinimapZoomOut => Binding::Key(KeyCode::Minus),
+            Action::ToggleAtmosOverlay => Binding::Key(KeyCode::F3),
+            Action::StepAtmos => Binding::Key(KeyCode::F4),
+            Action::ToggleAtmosPause => Binding::Key(KeyCode::F5),
+            Action::CycleAtmosOverlay => Binding::Key(KeyCode::F6),
+            Action::ToggleLightingOverlay => Binding::Key(KeyCode::F7),
+            Action::ToggleInspector => Binding::Key(KeyCode::F8),
+            Action::ToggleHoldGizmos => Binding::Key(KeyCode::F11),
+            Action::CheckDesync => Binding::Key(KeyCode::F9),
+            Action::Respawn => Binding::Key(KeyCode::KeyR),
+            Action::Vote => Binding::Key(KeyCode::KeyT),
+            Action::CycleSpectateTarget => Binding::Key(KeyCode::KeyN),
+            Action::ToggleFullRelevancy => Binding::Key(KeyCode::KeyG),
+            Action::ToggleDiagnostics => Binding::Key(KeyCode::F10),
+        }
+    }
+}
+
+/// Error for an action or binding name the config could not parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownName(pub String);
+
+impl fmt::Display for UnknownName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown name `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownName {}
+
+impl FromStr for Action {
+    type Err = UnknownName;
+
+    /// Parses the variant name, e.g. `ToggleAtmosPause`, ignoring case since
+    /// config keys may arrive lowercased.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .into_iter()
+            .find(|action| format!("{action:?}").eq_ignore_ascii_case(s))
+            .ok_or_else(|| UnknownName(s.to_string()))
+    }
+}
+
+/// A physical input an [`Action`] can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+/// Keys that can be named in the config file, by their [`KeyCode`] name.
+const NAMED_KEYS: &[KeyCode] = &[
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+];
+
+impl fmt::Display for Binding {
+    /// Short label for hints and the settings screen, e.g. `W`, `F5` or
+    /// `Left click`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                let short = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name);
+                f.write_str(short)
+            }
+            Binding::Mouse(button) => write!(f, "{button:?} click"),
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = UnknownName;
+
+    /// Parses a [`KeyCode`] name such as `KeyW` or `F5`, or `MouseLeft`,
+    /// `MouseRight` or `MouseMiddle`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mouse = match s.to_ascii_lowercase().as_str() {
+            "mouseleft" => Some(MouseButton::Left),
+            "mouseright" => Some(MouseButton::Right),
+            "mousemiddle" => Some(MouseButton::Middle),
+            _ => None,
+        };
+        if let Some(button) = mouse {
+            return Ok(Binding::Mouse(button));
+        }
+        NAMED_KEYS
+            .iter()
+            .find(|key| format!("{key:?}").eq_ignore_ascii_case(s))
+            .map(|&key| Binding::Key(key))
+            .ok_or_else(|| UnknownName(s.to_string()))
+    }
+}
+
+/// The bindings of every [`Action`].  An action may have several.
+///
+/// Inserted by [`InputPlugin`](crate::InputPlugin) with the defaults; the
+/// client replaces it with one built from the config.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct InputMap {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|action| (action, vec![action.default_binding()]))
+                .collect(),
+        }
+    }
+}
+
+impl InputMap {
+    /// The defaults with the actions named in `overrides` bound to the
+    /// listed inputs instead.  Unknown names are logged and skipped.
+    pub fn with_overrides(overrides: &HashMap<String, Vec<String>>) -> Self {
+        let mut map = Self::default();
+        for (action_name, binding_names) in overrides {
+            let action = match action_name.parse::<Action>() {
+                Ok(action) => action,
+                Err(e) => {
+                    warn!("Ignoring key binding: {e}");
+                    continue;
+                }
+            };
+            let bindings = binding_names
+                .iter()
+                .filter_map(|name| {
+                    name.parse::<Binding>()
+                        .inspect_err(|e| warn!("Ignoring binding for {action:?}: {e}"))
+                        .ok()
+                })
+                .collect();
+            map.bindings.insert(action, bindings);
+        }
+        map
+    }
+
+    /// Inputs bound to `action`.
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Replaces every binding of `action` with `binding`.
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        self.bindings.insert(action, vec![binding]);
+    }
+
+    /// Binds `binding` to `action` in addition to its current bindings.
+    pub fn add_binding(&mut self, action: Action, binding: Binding) {
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes every binding of `action`, leaving it unbound.
+    pub fn unbind(&mut self, action: Action) {
+        self.bindings.remove(&action);
+    }
+
+    /// Actions `binding` triggers.
+    pub fn actions_for(&self, binding: Binding) -> impl Iterator<Item = Action> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, bindings)| bindings.contains(&binding))
+            .map(|(&action, _)| action)
+    }
+}
+
+/// Reads [`Action`]s through the current [`InputMap`].
+///
+/// Keyboard or mouse input that does not exist (e.g. in tests) counts as
+/// never pressed.
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    map: Res<'w, InputMap>,
+    keys: Option<Res<'w, ButtonInput<KeyCode>>>,
+    mouse: Option<Res<'w, ButtonInput<MouseButton>>>,
+}
+
+impl ActionInput<'_> {
+    fn any(
+        &self,
+        action: Action,
+        key: impl Fn(&ButtonInput<KeyCode>, KeyCode) -> bool,
+        mouse: impl Fn(&ButtonInput<MouseButton>, MouseButton) -> bool,
+    ) -> bool {
+        self.map
+            .bindings(action)
+            .iter()
+            .any(|binding| match *binding {
+                Binding::Key(code) => self.keys.as_deref().is_some_and(|k| key(k, code)),
+                Binding::Mouse(button) => self.mouse.as_deref().is_some_and(|m| mouse(m, button)),
+            })
+    }
+
+    /// Whether any input bound to `action` is held.
+    pub fn pressed(&self, action: Action) -> bool {
+        self.any(action, |k, c| k.pressed(c), |m, b| m.pressed(b))
+    }
+
+    /// Whether any input bound to `action` was pressed this frame.
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.any(action, |k, c| k.just_pressed(c), |m, b| m.just_pressed(b))
+    }
+
+    /// Whether any input bound to `action` was released this frame.
+    pub fn just_released(&self, action: Action) -> bool {
+        self.any(action, |k, c| k.just_released(c), |m, b| m.just_released(b))
+    }
+}
+
+/// Action whose binding is replaced by the next key or mouse button
+/// pressed.  Set it from a settings screen; [`capture_rebind`] clears it
+/// again once the input arrives, or on Escape, which cancels.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PendingRebind(pub Option<Action>);
+
+/// An action was bound to a new input by [`capture_rebind`].
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActionRebound {
+    pub action: Action,
+    pub binding: Binding,
+}
+
+/// Completes a [`PendingRebind`] with the first key or mouse button pressed.
+///
+/// Runs in `PreUpdate` before actions are read, and clears the input it
+/// consumed so the new binding does not also fire this frame.
+pub fn capture_rebind(
+    mut pending: ResMut<PendingRebind>,
+    mut map: ResMut<InputMap>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+    mut rebound: MessageWriter<ActionRebound>,
+) {
+    let Some(action) = pending.0 else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::Escape) {
+        keys.clear_just_pressed(KeyCode::Escape);
+        pending.0 = None;
+        return;
+    }
+    let key = keys.get_just_pressed().next().copied();
+    let button = mouse.get_just_pressed().next().copied();
+    let binding = if let Some(key) = key {
+        keys.clear_just_pressed(key);
+        Binding::Key(key)
+    } else if let Some(button) = button {
+        mouse.clear_just_pressed(button);
+        Binding::Mouse(button)
+    } else {
+        return;
+    };
+    info!("Bound {action:?} to {binding:?}");
+    map.rebind(action, binding);
+    pending.0 = None;
+    rebound.write(ActionRebound { action, binding });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_overrides_replace_only_named_actions() {
+        let overrides = HashMap::from([
+            (
+                "toggleatmospause".to_string(),
+                vec!["KeyP".to_string(), "MouseMiddle".to_string()],
+            ),
+            ("NoSuchAction".to_string(), vec!["KeyQ".to_string()]),
+            ("Interact".to_string(), vec!["NoSuchKey".to_string()]),
+        ]);
+        let map = InputMap::with_overrides(&overrides);
+
+        assert_eq!(
+            map.bindings(Action::ToggleAtmosPause),
+            &[
+                Binding::Key(KeyCode::KeyP),
+                Binding::Mouse(MouseButton::Middle)
+            ]
+        );
+        assert!(map.bindings(Action::Interact).is_empty());
+        assert_eq!(
+            map.bindings(Action::MoveForward),
+            &[Binding::Key(KeyCode::KeyW)]
+        );
+        assert_eq!(map.bindings(Action::MoveForward)[0].to_string(), "W");
+        assert_eq!(Binding::Mouse(MouseButton::Left).to_string(), "Left click");
+    }
+
+    #[test]
+    fn next_press_completes_a_pending_rebind() {
+        let mut app = App::new();
+        app.add_message::<ActionRebound>();
+        app.init_resource::<InputMap>();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.init_resource::<ButtonInput<MouseButton>>();
+        app.insert_resource(PendingRebind(Some(Action::ToggleAtmosPause)));
+        app.add_systems(Update, capture_rebind);
+
+        app.update();
+        assert_eq!(
+            app.world().resource::<PendingRebind>().0,
+            Some(Action::ToggleAtmosPause)
+        );
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyP);
+        app.update();
+
+        let map = app.world().resource::<InputMap>();
+        assert_eq!(
+            map.bindings(Action::ToggleAtmosPause),
+            &[Binding::Key(KeyCode::KeyP)]
+        );
+        assert_eq!(
+            map.actions_for(Binding::Key(KeyCode::KeyP))
+                .collect::<Vec<_>>(),
+            vec![Action::ToggleAtmosPause]
+        );
+        assert_eq!(app.world().resource::<PendingRebind>().0, None);
+    }
+}
@@ -1,10 +1,20 @@
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use network::Headless;
 
+mod bindings;
+
+pub use bindings::{
+    Action, ActionInput, ActionRebound, Binding, InputMap, PendingRebind, UnknownName,
+    capture_rebind,
+};
+
 /// Normalised pointer event fired on mouse button press.
 #[derive(Message, Debug, Clone, Copy)]
 pub struct PointerAction {
+    /// `Left` for [`Action::Interact`], `Right` for [`Action::ContextMenu`],
+    /// whatever input those are bound to.
     pub button: MouseButton,
     pub screen_pos: Vec2,
 }
@@ -26,11 +36,44 @@ pub struct WorldHit {
     pub world_pos: Vec3,
 }
 
-/// System that emits [`PointerAction`] events for each mouse button just pressed.
+/// The camera the player sees the world through, for systems that turn
+/// screen positions into world rays.
+///
+/// This is the active 3D camera with the lowest order, so it does not matter
+/// which module spawned it or whether overlay cameras render on top of it.
+#[derive(SystemParam)]
+pub struct ViewCamera<'w, 's> {
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<Camera3d>>,
+}
+
+impl ViewCamera<'_, '_> {
+    /// The camera and its transform, or `None` when no 3D camera is active.
+    pub fn get(&self) -> Option<(&Camera, &GlobalTransform)> {
+        self.cameras
+            .iter()
+            .filter(|(camera, _)| camera.is_active)
+            .min_by_key(|(camera, _)| camera.order)
+    }
+
+    /// World-space position of the camera.
+    pub fn translation(&self) -> Option<Vec3> {
+        self.get().map(|(_, transform)| transform.translation())
+    }
+
+    /// Ray from the camera through `screen_pos`, in logical window pixels.
+    pub fn ray(&self, screen_pos: Vec2) -> Option<Ray3d> {
+        let (camera, transform) = self.get()?;
+        camera.viewport_to_world(transform, screen_pos).ok()
+    }
+}
+
+/// System that emits a [`PointerAction`] for each pointer action just
+/// pressed: [`Action::Interact`] as `Left` and [`Action::ContextMenu`] as
+/// `Right`.
 ///
 /// Runs in `PreUpdate`, gated on the provided game state and absence of [`Headless`].
 fn emit_pointer_actions(
-    mouse: Res<ButtonInput<MouseButton>>,
+    input: ActionInput,
     window: Query<&Window, With<PrimaryWindow>>,
     mut writer: MessageWriter<PointerAction>,
 ) {
@@ -41,11 +84,13 @@ fn emit_pointer_actions(
     let Some(screen_pos) = window.cursor_position() else {
         return;
     };
-    for button in mouse.get_just_pressed() {
-        writer.write(PointerAction {
-            button: *button,
-            screen_pos,
-        });
+    for (action, button) in [
+        (Action::Interact, MouseButton::Left),
+        (Action::ContextMenu, MouseButton::Right),
+    ] {
+        if input.just_pressed(action) {
+            writer.write(PointerAction { button, screen_pos });
+        }
     }
 }
 
@@ -63,11 +108,15 @@ impl<S: States + Copy> Plugin for InputPlugin<S> {
     fn build(&self, app: &mut App) {
         app.add_message::<PointerAction>();
         app.add_message::<WorldHit>();
+        app.add_message::<ActionRebound>();
+        app.init_resource::<InputMap>();
+        app.init_resource::<PendingRebind>();
         let state = self.state;
         app.add_systems(
             PreUpdate,
-            emit_pointer_actions
-                .run_if(in_state(state))
+            (capture_rebind, emit_pointer_actions.run_if(in_state(state)))
+                .chain()
+                .after(bevy::input::InputSystems)
                 .run_if(not(resource_exists::<Headless>)),
         );
     }
@@ -76,6 +125,7 @@ impl<S: States + Copy> Plugin for InputPlugin<S> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bevy::ecs::system::RunSystemOnce;
     use bevy::window::WindowResolution;
 
     #[derive(Resource, Default)]
@@ -90,6 +140,7 @@ mod tests {
         app.add_plugins(MinimalPlugins);
         app.add_message::<PointerAction>();
         app.insert_resource(ButtonInput::<MouseButton>::default());
+        app.init_resource::<InputMap>();
         app.init_resource::<CapturedActions>();
         app.add_systems(Update, emit_pointer_actions);
         app.add_systems(Update, capture.after(emit_pointer_actions));
@@ -130,6 +181,70 @@ mod tests {
         assert_eq!(actions.0[0].screen_pos, Vec2::new(200.0, 150.0));
     }
 
+    #[test]
+    fn test_rebound_interact_emits_left_pointer_action() {
+        let mut app = make_test_app();
+        app.insert_resource(ButtonInput::<KeyCode>::default());
+        app.world_mut()
+            .resource_mut::<InputMap>()
+            .rebind(Action::Interact, Binding::Key(KeyCode::KeyE));
+
+        let window_entity = spawn_primary_window(&mut app);
+        app.world_mut()
+            .get_mut::<Window>(window_entity)
+            .unwrap()
+            .set_cursor_position(Some(Vec2::new(10.0, 20.0)));
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .press(MouseButton::Left);
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyE);
+
+        app.update();
+
+        let actions = app.world().resource::<CapturedActions>();
+        assert_eq!(actions.0.len(), 1);
+        assert_eq!(actions.0[0].button, MouseButton::Left);
+    }
+
+    #[test]
+    fn test_view_camera_prefers_lowest_active_order() {
+        let mut app = App::new();
+        app.world_mut().spawn((
+            Camera3d::default(),
+            Camera {
+                order: 2,
+                ..default()
+            },
+            GlobalTransform::from_xyz(2.0, 0.0, 0.0),
+        ));
+        app.world_mut().spawn((
+            Camera3d::default(),
+            Camera {
+                order: 0,
+                is_active: false,
+                ..default()
+            },
+            GlobalTransform::from_xyz(0.0, 0.0, 0.0),
+        ));
+        app.world_mut().spawn((
+            Camera3d::default(),
+            Camera {
+                order: 1,
+                ..default()
+            },
+            GlobalTransform::from_xyz(1.0, 0.0, 0.0),
+        ));
+
+        let translation = app
+            .world_mut()
+            .run_system_once(|view: ViewCamera| view.translation())
+            .unwrap();
+        assert_eq!(translation, Some(Vec3::X));
+    }
+
     #[test]
     fn test_no_event_when_cursor_outside_window() {
         let mut app = make_test_app();
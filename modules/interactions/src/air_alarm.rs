@@ -0,0 +1,413 @@
+//! Air alarms: wall-mounted monitors that raise the alarm when the room they
+//! are in loses pressure, is overpressurized or catches fire.
+//!
+//! An [`AirAlarm`] watches every cell of its [`Zone`](tiles::Zone) (or just
+//! its own cell outside any room) on the server.  When the lowest pressure,
+//! the highest pressure or the hottest cell crosses its thresholds it goes
+//! into [`AirAlarmState::Alarm`], writes an [`AirAlarmTriggered`] message and
+//! announces the cause to every player.  Choosing "Acknowledge alarm" in its
+//! context menu silences it; "Reset alarm" re-arms it once the crew is done.
+//! Every [`AirAlarmState`] change is replicated on the air alarms stream so
+//! clients can light the alarm's indicator accordingly.
+
+use atmospherics::{FireGrid, GasGrid};
+use bevy::prelude::*;
+use events::Announce;
+use items::InteractionRange;
+use network::{ModuleReadySent, NetId, PlayerEvent, StreamReader, StreamSender, stream_tag};
+use things::{NetIdIndex, cell_of};
+use tiles::ZoneIndex;
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{ContextEntry, ContextTarget, InteractionRequest};
+
+/// Stream tag for the server→client air alarm state stream.
+pub const AIR_ALARMS_STREAM_TAG: u8 = stream_tag("air_alarms");
+
+/// Marks a thing as an air alarm and holds the thresholds it checks.
+/// Pressures are in moles per cell, like [`GasGrid::pressure_at`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct AirAlarm {
+    /// Alarm when any cell of the room drops below this pressure.
+    pub low_pressure: f32,
+    /// Alarm when any cell of the room rises above this pressure.
+    pub high_pressure: f32,
+    /// Alarm when any cell of the room is hotter than this, in kelvin.
+    pub high_temperature: f32,
+}
+
+impl Default for AirAlarm {
+    fn default() -> Self {
+        Self {
+            low_pressure: 60.0,
+            high_pressure: 150.0,
+            high_temperature: 330.0,
+        }
+    }
+}
+
+impl AirAlarm {
+    /// The first threshold crossed by a room whose cells range from
+    /// `min_pressure` to `max_pressure` and are at most `max_temperature`
+    /// hot, or `None` while the air is fine.
+    pub fn check(
+        &self,
+        min_pressure: f32,
+        max_pressure: f32,
+        max_temperature: f32,
+    ) -> Option<AlarmCause> {
+        if max_temperature > self.high_temperature {
+            Some(AlarmCause::HighTemperature)
+        } else if min_pressure < self.low_pressure {
+            Some(AlarmCause::LowPressure)
+        } else if max_pressure > self.high_pressure {
+            Some(AlarmCause::HighPressure)
+        } else {
+            None
+        }
+    }
+}
+
+/// Why an air alarm went off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, SchemaRead, SchemaWrite)]
+pub enum AlarmCause {
+    LowPressure,
+    HighPressure,
+    HighTemperature,
+}
+
+impl AlarmCause {
+    /// How the cause is named in announcements.
+    pub fn label(self) -> &'static str {
+        match self {
+            AlarmCause::LowPressure => "decompression",
+            AlarmCause::HighPressure => "overpressure",
+            AlarmCause::HighTemperature => "fire",
+        }
+    }
+}
+
+/// Whether an air alarm is going off.  Decided by the server and replicated
+/// to every client.
+#[derive(
+    Component, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, SchemaRead, SchemaWrite,
+)]
+#[reflect(Component)]
+pub enum AirAlarmState {
+    /// Watching the room, nothing wrong.
+    #[default]
+    Clear,
+    /// Going off because of `cause`.
+    Alarm { cause: AlarmCause },
+    /// Silenced by the crew; stays so until reset.
+    Acknowledged { cause: AlarmCause },
+}
+
+/// Server-side message written when `alarm` goes off.  `zone` is the name of
+/// the room it watches, if it is in one.
+#[derive(Message, Clone, Debug)]
+pub struct AirAlarmTriggered {
+    pub alarm: Entity,
+    pub cause: AlarmCause,
+    pub zone: Option<String>,
+}
+
+/// Server-side request: `actor` acknowledges or resets `alarm`.
+#[derive(Message, Clone, Debug)]
+pub struct AirAlarmResetRequest {
+    pub actor: Entity,
+    pub alarm: Entity,
+}
+
+/// Air alarms stream wire format: server→client air alarm states.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub enum AirAlarmsStreamMessage {
+    /// The air alarm `alarm` is now in `state`.
+    State { alarm: NetId, state: AirAlarmState },
+}
+
+// ── Server ────────────────────────────────────────────────────────────────────
+
+/// Gives every new [`AirAlarm`] its initial [`AirAlarmState`].
+pub(crate) fn arm_air_alarms(
+    mut commands: Commands,
+    alarms: Query<Entity, (With<AirAlarm>, Without<AirAlarmState>)>,
+) {
+    for entity in alarms.iter() {
+        commands.entity(entity).insert(AirAlarmState::default());
+    }
+}
+
+/// Checks the room of every clear air alarm against its thresholds and sets
+/// it off, announcing the cause, when one is crossed.
+pub(crate) fn monitor_air_alarms(
+    gas: Option<Res<GasGrid>>,
+    fire: Option<Res<FireGrid>>,
+    zones: Option<Res<ZoneIndex>>,
+    mut alarms: Query<(Entity, &Transform, &AirAlarm, &mut AirAlarmState)>,
+    mut triggered: MessageWriter<AirAlarmTriggered>,
+    mut announce: MessageWriter<Announce>,
+) {
+    let Some(gas) = gas else {
+        return;
+    };
+    for (entity, transform, alarm, mut state) in alarms.iter_mut() {
+        if *state != AirAlarmState::Clear {
+            continue;
+        }
+        let cell = cell_of(transform.translation);
+        let zone = zones.as_deref().and_then(|zones| zones.zone_at(cell));
+        let cells: Vec<IVec2> = match (zones.as_deref(), zone) {
+            (Some(zones), Some(zone)) => zones.cells_of(zone.id).collect(),
+            _ => vec![cell],
+        };
+
+        let mut min_pressure = f32::INFINITY;
+        let mut max_pressure = f32::NEG_INFINITY;
+        let mut max_temperature = f32::NEG_INFINITY;
+        for &cell in &cells {
+            if let Some(pressure) = gas.pressure_at(cell) {
+                min_pressure = min_pressure.min(pressure);
+                max_pressure = max_pressure.max(pressure);
+            }
+            if let Some(temperature) = fire.as_deref().and_then(|fire| fire.temperature_at(cell)) {
+                max_temperature = max_temperature.max(temperature);
+            }
+        }
+        if min_pressure > max_pressure {
+            // No gas cell under the alarm, nothing to watch.
+            continue;
+        }
+
+        let Some(cause) = alarm.check(min_pressure, max_pressure, max_temperature) else {
+            continue;
+        };
+        let zone = zone.map(|zone| zone.name.clone());
+        info!(
+            "Air alarm {:?} went off: {} in {zone:?}",
+            entity,
+            cause.label()
+        );
+        *state = AirAlarmState::Alarm { cause };
+        announce.write(Announce {
+            text: match &zone {
+                Some(zone) => format!("Atmospherics alarm: {} in {zone}", cause.label()),
+                None => format!("Atmospherics alarm: {}", cause.label()),
+            },
+        });
+        triggered.write(AirAlarmTriggered {
+            alarm: entity,
+            cause,
+            zone,
+        });
+    }
+}
+
+/// Handles [`AirAlarmResetRequest`]s: a ringing alarm is acknowledged and an
+/// acknowledged one is re-armed.  Requests out of [`InteractionRange`] are
+/// dropped.
+pub(crate) fn reset_air_alarms(
+    mut requests: MessageReader<AirAlarmResetRequest>,
+    interaction_range: Res<InteractionRange>,
+    transforms: Query<&Transform>,
+    mut alarms: Query<&mut AirAlarmState>,
+) {
+    for req in requests.read() {
+        let Ok(mut state) = alarms.get_mut(req.alarm) else {
+            warn!("AirAlarmResetRequest: {:?} is not an air alarm", req.alarm);
+            continue;
+        };
+        let (Ok(actor_tf), Ok(alarm_tf)) = (transforms.get(req.actor), transforms.get(req.alarm))
+        else {
+            continue;
+        };
+        let distance = actor_tf.translation.distance(alarm_tf.translation);
+        if distance > interaction_range.0 {
+            warn!(
+                "AirAlarmResetRequest: {:?} is {distance:.2} from {:?}, out of range",
+                req.alarm, req.actor
+            );
+            continue;
+        }
+        *state = match *state {
+            AirAlarmState::Alarm { cause } => AirAlarmState::Acknowledged { cause },
+            AirAlarmState::Acknowledged { .. } => AirAlarmState::Clear,
+            AirAlarmState::Clear => continue,
+        };
+    }
+}
+
+/// Sends [`AirAlarmsStreamMessage::State`] for every air alarm whose state
+/// changed this frame.
+pub(crate) fn broadcast_air_alarm_states(
+    sender: Res<StreamSender<AirAlarmsStreamMessage>>,
+    changed: Query<(&NetId, &AirAlarmState), Changed<AirAlarmState>>,
+) {
+    for (&alarm, &state) in changed.iter() {
+        if let Err(e) = sender.broadcast(&AirAlarmsStreamMessage::State { alarm, state }) {
+            error!(
+                "Failed to broadcast air alarm state for NetId({}): {e}",
+                alarm.0
+            );
+        }
+    }
+}
+
+/// Sends every air alarm's state to joining clients, followed by the
+/// [`StreamReady`](network::StreamReady) sentinel for the air alarms stream.
+pub(crate) fn send_air_alarms_on_join(
+    mut player_events: MessageReader<PlayerEvent>,
+    alarms: Query<(&NetId, &AirAlarmState)>,
+    sender: Res<StreamSender<AirAlarmsStreamMessage>>,
+    mut module_ready: MessageWriter<ModuleReadySent>,
+) {
+    for event in player_events.read() {
+        let PlayerEvent::Joined { id, .. } = event else {
+            continue;
+        };
+        for (&alarm, &state) in alarms.iter() {
+            if let Err(e) = sender.send_to(*id, &AirAlarmsStreamMessage::State { alarm, state }) {
+                error!(
+                    "Failed to send air alarm catch-up to ClientId({}): {e}",
+                    id.0
+                );
+            }
+        }
+        if let Err(e) = sender.send_stream_ready_to(*id) {
+            error!(
+                "Failed to send StreamReady for air alarms stream to ClientId({}): {e}",
+                id.0
+            );
+        } else {
+            module_ready.write(ModuleReadySent { client: *id });
+        }
+    }
+}
+
+// ── Client ────────────────────────────────────────────────────────────────────
+
+/// Drains the air alarms stream and mirrors each state onto the replicated
+/// alarm.
+pub(crate) fn receive_air_alarm_messages(
+    mut commands: Commands,
+    mut reader: ResMut<StreamReader<AirAlarmsStreamMessage>>,
+    net_id_index: Res<NetIdIndex>,
+) {
+    for msg in reader.drain() {
+        let AirAlarmsStreamMessage::State { alarm, state } = msg;
+        let Some(&entity) = net_id_index.0.get(&alarm) else {
+            debug!("Air alarm state for unknown NetId({})", alarm.0);
+            continue;
+        };
+        commands.entity(entity).insert(state);
+    }
+}
+
+/// Colours an air alarm after its state: green while clear, red while going
+/// off and amber once acknowledged.
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_air_alarm_indicators(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    changed: Query<(Entity, &AirAlarmState), (Changed<AirAlarmState>, With<Mesh3d>)>,
+) {
+    for (entity, state) in changed.iter() {
+        let color = match state {
+            AirAlarmState::Clear => Color::srgb(0.2, 0.9, 0.3),
+            AirAlarmState::Alarm { .. } => Color::srgb(0.9, 0.15, 0.1),
+            AirAlarmState::Acknowledged { .. } => Color::srgb(1.0, 0.65, 0.1),
+        };
+        let material = materials.add(StandardMaterial {
+            base_color: color,
+            emissive: color.to_linear() * 2.0,
+            ..default()
+        });
+        commands.entity(entity).insert(MeshMaterial3d(material));
+    }
+}
+
+/// Context-menu provider offering to acknowledge a ringing air alarm or to
+/// reset an acknowledged one.
+pub(crate) fn air_alarm_context_actions(
+    world: &World,
+    target: &ContextTarget,
+    entries: &mut Vec<ContextEntry>,
+) {
+    let (Some(state), Some(&alarm)) = (
+        world.get::<AirAlarmState>(target.entity),
+        world.get::<NetId>(target.entity),
+    ) else {
+        return;
+    };
+    let label = match state {
+        AirAlarmState::Clear => return,
+        AirAlarmState::Alarm { .. } => "Acknowledge alarm",
+        AirAlarmState::Acknowledged { .. } => "Reset alarm",
+    };
+    if target.in_range {
+        entries.push(ContextEntry {
+            label: label.to_string(),
+            request: InteractionRequest::ResetAirAlarm { alarm },
+            available: true,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_reports_the_crossed_threshold() {
+        let alarm = AirAlarm::default();
+        assert_eq!(alarm.check(100.0, 100.0, 293.15), None);
+        assert_eq!(
+            alarm.check(10.0, 100.0, 293.15),
+            Some(AlarmCause::LowPressure)
+        );
+        assert_eq!(
+            alarm.check(100.0, 200.0, 293.15),
+            Some(AlarmCause::HighPressure)
+        );
+        // Fire wins over the pressure it also disturbs.
+        assert_eq!(
+            alarm.check(10.0, 100.0, 900.0),
+            Some(AlarmCause::HighTemperature)
+        );
+    }
+
+    #[test]
+    fn context_menu_acknowledges_then_resets() {
+        let mut world = World::new();
+        let entity = world
+            .spawn((
+                NetId(4),
+                AirAlarmState::Alarm {
+                    cause: AlarmCause::LowPressure,
+                },
+            ))
+            .id();
+        let target = ContextTarget {
+            entity,
+            world_pos: Vec3::ZERO,
+            player: None,
+            holding: None,
+            in_range: true,
+        };
+
+        let mut entries = Vec::new();
+        air_alarm_context_actions(&world, &target, &mut entries);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].label, "Acknowledge alarm");
+        assert!(matches!(
+            entries[0].request,
+            InteractionRequest::ResetAirAlarm { alarm: NetId(4) }
+        ));
+
+        world.entity_mut(entity).insert(AirAlarmState::Clear);
+        entries.clear();
+        air_alarm_context_actions(&world, &target, &mut entries);
+        assert!(entries.is_empty());
+    }
+}
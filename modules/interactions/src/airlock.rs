@@ -0,0 +1,530 @@
+//! Airlocks: two doors around a small chamber that is pumped to the pressure
+//! of whichever side it opens to.
+//!
+//! An [`AirlockController`] thing placed inside the chamber is linked on the
+//! server to the [`Airlock`] around it.  Choosing "Cycle to inner" or "Cycle
+//! to outer" in its context menu sends [`InteractionRequest::CycleAirlock`];
+//! the server closes both doors, equalizes the chamber with the pressure
+//! beyond the target door and then opens it.  Doors driven by a
+//! [`DoorMotor`](power::DoorMotor) only move while it is powered: a cycle
+//! does not start while either motor is down and waits for power before it
+//! opens the target door.  Every [`AirlockState`] change
+//! is replicated on the airlocks stream so clients can light the
+//! controller's indicator accordingly.
+
+use std::collections::{HashSet, VecDeque};
+
+use atmospherics::GasGrid;
+use bevy::prelude::*;
+use items::InteractionRange;
+use network::{ModuleReadySent, NetId, PlayerEvent, StreamReader, StreamSender, stream_tag};
+use power::DoorPower;
+use things::NetIdIndex;
+use tiles::{TileGrid, TileKind};
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{ContextEntry, ContextTarget, InteractionRequest, TileEdits};
+
+/// Stream tag for the server→client airlock state stream.
+pub const AIRLOCKS_STREAM_TAG: u8 = stream_tag("airlocks");
+
+/// Most cells a chamber may have; larger rooms are not airlocks.
+pub const AIRLOCK_MAX_CHAMBER: usize = 16;
+
+/// Fraction of the remaining pressure difference the pumps remove per
+/// second.
+pub const AIRLOCK_PUMP_RATE: f32 = 1.5;
+
+/// The chamber counts as equalized once every cell is within this many
+/// moles of the target pressure.
+pub const AIRLOCK_TOLERANCE: f32 = 1.0;
+
+/// Marks a thing as an airlock controller.  It must sit on a chamber cell;
+/// the server links it to its doors as soon as it exists.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct AirlockController;
+
+/// One of the two doors of an airlock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, SchemaRead, SchemaWrite)]
+pub enum AirlockSide {
+    /// The door to the higher pressure when the airlock was linked, usually
+    /// the station.
+    Inner,
+    /// The other door, usually space.
+    Outer,
+}
+
+/// A door of an airlock and the cell right behind it, seen from the chamber.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AirlockDoor {
+    pub door: IVec2,
+    pub beyond: IVec2,
+}
+
+/// Server-side layout of a linked airlock, on its controller.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Airlock {
+    pub inner: AirlockDoor,
+    pub outer: AirlockDoor,
+    pub chamber: Vec<IVec2>,
+}
+
+impl Airlock {
+    /// Finds the airlock around `start`: the walkable cells reachable from it
+    /// without passing a door form the chamber, which must border exactly
+    /// two doors and have at most [`AIRLOCK_MAX_CHAMBER`] cells.  The door
+    /// with the higher pressure in `gas` behind it becomes the inner one.
+    pub fn link(
+        grid: &TileGrid<TileKind>,
+        gas: Option<&GasGrid>,
+        start: IVec2,
+    ) -> Result<Self, String> {
+        let is_chamber = |pos: IVec2| {
+            grid.get_copy(pos)
+                .is_some_and(|kind| kind.is_walkable() && !kind.is_door())
+        };
+        if !is_chamber(start) {
+            return Err(format!("{start:?} is not an open cell"));
+        }
+
+        let mut chamber = vec![start];
+        let mut seen = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        let mut doors = Vec::new();
+        while let Some(cell) = queue.pop_front() {
+            for dir in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                let next = cell + dir;
+                if grid.get_copy(next).is_some_and(|kind| kind.is_door()) {
+                    if doors.iter().any(|door: &AirlockDoor| door.door == next) {
+                        continue;
+                    }
+                    doors.push(AirlockDoor {
+                        door: next,
+                        beyond: next + dir,
+                    });
+                } else if is_chamber(next) && seen.insert(next) {
+                    if chamber.len() == AIRLOCK_MAX_CHAMBER {
+                        return Err(format!(
+                            "chamber at {start:?} has more than {AIRLOCK_MAX_CHAMBER} cells"
+                        ));
+                    }
+                    chamber.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let [a, b] = doors[..] else {
+            return Err(format!(
+                "chamber at {start:?} borders {} doors, expected 2",
+                doors.len()
+            ));
+        };
+        let pressure = |door: AirlockDoor| {
+            gas.and_then(|gas| gas.pressure_at(door.beyond))
+                .unwrap_or(0.0)
+        };
+        let (inner, outer) = if pressure(b) > pressure(a) {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        Ok(Self {
+            inner,
+            outer,
+            chamber,
+        })
+    }
+
+    /// The door on `side`.
+    pub fn door(&self, side: AirlockSide) -> AirlockDoor {
+        match side {
+            AirlockSide::Inner => self.inner,
+            AirlockSide::Outer => self.outer,
+        }
+    }
+}
+
+/// Where an airlock is in its cycle.  Decided by the server and replicated
+/// to every client.
+#[derive(
+    Component, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, SchemaRead, SchemaWrite,
+)]
+#[reflect(Component)]
+pub enum AirlockState {
+    /// Both doors closed, nothing happening.
+    #[default]
+    Sealed,
+    /// Both doors closed while the chamber is pumped towards `target`.
+    Cycling { target: AirlockSide },
+    /// The door on `side` is open.
+    Open { side: AirlockSide },
+}
+
+/// Server-side request: `actor` cycles `airlock` to open towards `target`.
+#[derive(Message, Clone, Debug)]
+pub struct AirlockCycleRequest {
+    pub actor: Entity,
+    pub airlock: Entity,
+    pub target: AirlockSide,
+}
+
+/// Airlocks stream wire format: server→client airlock states.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub enum AirlocksStreamMessage {
+    /// The airlock controlled by `airlock` is now in `state`.
+    State { airlock: NetId, state: AirlockState },
+}
+
+// ── Server ────────────────────────────────────────────────────────────────────
+
+/// Links every new [`AirlockController`] to the airlock around it.
+/// Controllers outside a valid chamber are logged and stop being
+/// controllers.
+#[allow(clippy::type_complexity)]
+pub(crate) fn link_airlocks(
+    mut commands: Commands,
+    controllers: Query<(Entity, &Transform), (With<AirlockController>, Without<Airlock>)>,
+    grid: Option<Res<TileGrid<TileKind>>>,
+    gas: Option<Res<GasGrid>>,
+) {
+    let Some(grid) = grid else {
+        return;
+    };
+    for (entity, transform) in controllers.iter() {
+        let pos = transform.translation;
+        let cell = IVec2::new(pos.x.round() as i32, pos.z.round() as i32);
+        match Airlock::link(&grid, gas.as_deref(), cell) {
+            Ok(airlock) => {
+                info!(
+                    "Airlock {:?} linked: inner door {:?}, outer door {:?}, {} chamber cells",
+                    entity,
+                    airlock.inner.door,
+                    airlock.outer.door,
+                    airlock.chamber.len()
+                );
+                commands
+                    .entity(entity)
+                    .insert((airlock, AirlockState::default()));
+            }
+            Err(e) => {
+                warn!("Airlock controller {:?} not linked: {e}", entity);
+                commands.entity(entity).remove::<AirlockController>();
+            }
+        }
+    }
+}
+
+/// Starts the cycles asked for by [`AirlockCycleRequest`]s by closing both
+/// doors.  Requests out of [`InteractionRange`], for airlocks already
+/// cycling, towards the side that is already open or with a door that has
+/// no power to move are dropped.
+pub(crate) fn start_airlock_cycles(
+    mut requests: MessageReader<AirlockCycleRequest>,
+    interaction_range: Res<InteractionRange>,
+    transforms: Query<&Transform>,
+    mut airlocks: Query<(&Airlock, &mut AirlockState)>,
+    mut edits: TileEdits,
+    power: DoorPower,
+) {
+    for req in requests.read() {
+        let Ok((airlock, mut state)) = airlocks.get_mut(req.airlock) else {
+            warn!(
+                "AirlockCycleRequest: {:?} is not a linked airlock",
+                req.airlock
+            );
+            continue;
+        };
+        let (Ok(actor_tf), Ok(airlock_tf)) =
+            (transforms.get(req.actor), transforms.get(req.airlock))
+        else {
+            continue;
+        };
+        let distance = actor_tf.translation.distance(airlock_tf.translation);
+        if distance > interaction_range.0 {
+            warn!(
+                "AirlockCycleRequest: {:?} is {distance:.2} from {:?}, out of range",
+                req.airlock, req.actor
+            );
+            continue;
+        }
+        match *state {
+            AirlockState::Cycling { .. } => {
+                debug!("Airlock {:?} is already cycling", req.airlock);
+                continue;
+            }
+            AirlockState::Open { side } if side == req.target => continue,
+            _ => {}
+        }
+        if ![airlock.inner.door, airlock.outer.door]
+            .into_iter()
+            .all(|door| power.door(door))
+        {
+            debug!("Airlock {:?} has no power to move its doors", req.airlock);
+            continue;
+        }
+
+        for side in [AirlockSide::Inner, AirlockSide::Outer] {
+            let door = airlock.door(side).door;
+            if edits.structure(door) == Some(TileKind::Door { open: true })
+                && let Err(e) =
+                    edits.set_structure(Some(req.actor), door, TileKind::Door { open: false })
+            {
+                error!(
+                    "Airlock {:?}: failed to close door at {door:?}: {e}",
+                    req.airlock
+                );
+            }
+        }
+        *state = AirlockState::Cycling { target: req.target };
+    }
+}
+
+/// Pumps every cycling chamber towards the pressure behind its target door
+/// and opens that door once the chamber is within [`AIRLOCK_TOLERANCE`] of
+/// it, as soon as the door has power to move.  Without a gas grid the
+/// chamber counts as equalized at once.
+pub(crate) fn run_airlock_cycles(
+    time: Res<Time>,
+    mut gas: Option<ResMut<GasGrid>>,
+    mut airlocks: Query<(Entity, &Airlock, &mut AirlockState)>,
+    mut edits: TileEdits,
+    power: DoorPower,
+) {
+    let pumped = 1.0 - (-AIRLOCK_PUMP_RATE * time.delta_secs()).exp();
+    for (entity, airlock, mut state) in airlocks.iter_mut() {
+        let AirlockState::Cycling { target } = *state else {
+            continue;
+        };
+        let door = airlock.door(target);
+
+        if let Some(gas) = gas.as_deref_mut()
+            && let Some(goal) = gas.pressure_at(door.beyond)
+        {
+            let mut equalized = true;
+            for &cell in &airlock.chamber {
+                let Some(moles) = gas.pressure_at(cell) else {
+                    continue;
+                };
+                let moles = moles + (goal - moles) * pumped;
+                gas.set_moles(cell, moles);
+                equalized &= (goal - moles).abs() <= AIRLOCK_TOLERANCE;
+            }
+            if !equalized {
+                continue;
+            }
+        }
+        if !power.door(door.door) {
+            continue;
+        }
+
+        if let Err(e) = edits.set_structure(None, door.door, TileKind::Door { open: true }) {
+            warn!(
+                "Airlock {:?}: failed to open door at {:?}: {e}",
+                entity, door.door
+            );
+        }
+        *state = AirlockState::Open { side: target };
+    }
+}
+
+/// Sends [`AirlocksStreamMessage::State`] for every airlock whose state
+/// changed this frame.
+pub(crate) fn broadcast_airlock_states(
+    sender: Res<StreamSender<AirlocksStreamMessage>>,
+    changed: Query<(&NetId, &AirlockState), Changed<AirlockState>>,
+) {
+    for (&airlock, &state) in changed.iter() {
+        if let Err(e) = sender.broadcast(&AirlocksStreamMessage::State { airlock, state }) {
+            error!(
+                "Failed to broadcast airlock state for NetId({}): {e}",
+                airlock.0
+            );
+        }
+    }
+}
+
+/// Sends every airlock's state to joining clients, followed by the
+/// [`StreamReady`](network::StreamReady) sentinel for the airlocks stream.
+pub(crate) fn send_airlocks_on_join(
+    mut player_events: MessageReader<PlayerEvent>,
+    airlocks: Query<(&NetId, &AirlockState)>,
+    sender: Res<StreamSender<AirlocksStreamMessage>>,
+    mut module_ready: MessageWriter<ModuleReadySent>,
+) {
+    for event in player_events.read() {
+        let PlayerEvent::Joined { id, .. } = event else {
+            continue;
+        };
+        for (&airlock, &state) in airlocks.iter() {
+            if let Err(e) = sender.send_to(*id, &AirlocksStreamMessage::State { airlock, state }) {
+                error!("Failed to send airlock catch-up to ClientId({}): {e}", id.0);
+            }
+        }
+        if let Err(e) = sender.send_stream_ready_to(*id) {
+            error!(
+                "Failed to send StreamReady for airlocks stream to ClientId({}): {e}",
+                id.0
+            );
+        } else {
+            module_ready.write(ModuleReadySent { client: *id });
+        }
+    }
+}
+
+// ── Client ────────────────────────────────────────────────────────────────────
+
+/// Drains the airlocks stream and mirrors each state onto the replicated
+/// controller.
+pub(crate) fn receive_airlock_messages(
+    mut commands: Commands,
+    mut reader: ResMut<StreamReader<AirlocksStreamMessage>>,
+    net_id_index: Res<NetIdIndex>,
+) {
+    for msg in reader.drain() {
+        let AirlocksStreamMessage::State { airlock, state } = msg;
+        let Some(&entity) = net_id_index.0.get(&airlock) else {
+            debug!("Airlock state for unknown NetId({})", airlock.0);
+            continue;
+        };
+        commands.entity(entity).insert(state);
+    }
+}
+
+/// Colours an airlock controller after its state: red while sealed, amber
+/// while cycling and green while a door is open.
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_airlock_indicators(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    changed: Query<(Entity, &AirlockState), (Changed<AirlockState>, With<Mesh3d>)>,
+) {
+    for (entity, state) in changed.iter() {
+        let color = match state {
+            AirlockState::Sealed => Color::srgb(0.9, 0.15, 0.1),
+            AirlockState::Cycling { .. } => Color::srgb(1.0, 0.65, 0.1),
+            AirlockState::Open { .. } => Color::srgb(0.2, 0.9, 0.3),
+        };
+        let material = materials.add(StandardMaterial {
+            base_color: color,
+            emissive: color.to_linear() * 2.0,
+            ..default()
+        });
+        commands.entity(entity).insert(MeshMaterial3d(material));
+    }
+}
+
+/// Context-menu provider offering to cycle an in-range airlock towards the
+/// side that is not already open.
+pub(crate) fn airlock_context_actions(
+    world: &World,
+    target: &ContextTarget,
+    entries: &mut Vec<ContextEntry>,
+) {
+    let (Some(state), Some(&airlock)) = (
+        world.get::<AirlockState>(target.entity),
+        world.get::<NetId>(target.entity),
+    ) else {
+        return;
+    };
+    if !target.in_range || matches!(state, AirlockState::Cycling { .. }) {
+        return;
+    }
+    for (side, label) in [
+        (AirlockSide::Inner, "Cycle to inner"),
+        (AirlockSide::Outer, "Cycle to outer"),
+    ] {
+        if *state != (AirlockState::Open { side }) {
+            entries.push(ContextEntry {
+                label: label.to_string(),
+                request: InteractionRequest::CycleAirlock {
+                    airlock,
+                    target: side,
+                },
+                available: true,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A corridor `inside | door | chamber | chamber | door | space`.
+    fn airlock_grid() -> TileGrid<TileKind> {
+        let mut grid = TileGrid::<TileKind>::new_fill(6, 3, TileKind::Wall);
+        for x in 0..6 {
+            grid.set(IVec2::new(x, 1), TileKind::Floor);
+        }
+        grid.set(IVec2::new(1, 1), TileKind::Door { open: false });
+        grid.set(IVec2::new(4, 1), TileKind::Door { open: false });
+        grid
+    }
+
+    #[test]
+    fn link_finds_both_doors_and_orders_them_by_pressure() {
+        let grid = airlock_grid();
+        let mut gas = GasGrid::new(6, 3);
+        gas.set_moles(IVec2::new(0, 1), 100.0);
+
+        let airlock = Airlock::link(&grid, Some(&gas), IVec2::new(2, 1)).unwrap();
+        assert_eq!(
+            airlock.inner,
+            AirlockDoor {
+                door: IVec2::new(1, 1),
+                beyond: IVec2::new(0, 1),
+            }
+        );
+        assert_eq!(airlock.outer.beyond, IVec2::new(5, 1));
+        assert_eq!(airlock.chamber.len(), 2);
+
+        // A door opened into the room is not part of the chamber either.
+        let mut open = grid.clone();
+        open.set(IVec2::new(4, 1), TileKind::Door { open: true });
+        assert!(Airlock::link(&open, Some(&gas), IVec2::new(2, 1)).is_ok());
+
+        // A room with a single door is no airlock.
+        let mut one_door = grid.clone();
+        one_door.set(IVec2::new(4, 1), TileKind::Wall);
+        assert!(Airlock::link(&one_door, Some(&gas), IVec2::new(2, 1)).is_err());
+        assert!(Airlock::link(&grid, Some(&gas), IVec2::new(2, 0)).is_err());
+    }
+
+    #[test]
+    fn context_menu_offers_the_closed_side() {
+        let mut world = World::new();
+        let entity = world
+            .spawn((
+                NetId(3),
+                AirlockState::Open {
+                    side: AirlockSide::Inner,
+                },
+            ))
+            .id();
+        let mut target = ContextTarget {
+            entity,
+            world_pos: Vec3::ZERO,
+            player: None,
+            holding: None,
+            in_range: true,
+        };
+
+        let mut entries = Vec::new();
+        airlock_context_actions(&world, &target, &mut entries);
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            entries[0].request,
+            InteractionRequest::CycleAirlock {
+                airlock: NetId(3),
+                target: AirlockSide::Outer,
+            }
+        ));
+
+        target.in_range = false;
+        entries.clear();
+        airlock_context_actions(&world, &target, &mut entries);
+        assert!(entries.is_empty());
+    }
+}
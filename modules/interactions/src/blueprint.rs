@@ -0,0 +1,291 @@
+//! Construction blueprints: previewing a structure before building it.
+//!
+//! "Build Wall" and "Build Door" in a floor tile's context menu enter
+//! blueprint mode instead of building straight away.  A translucent ghost
+//! of the structure then follows the cursor across the floor, green where
+//! the server would accept the build and red where it would not (see
+//! [`check_blueprint`]).  Clicking a valid cell sends the
+//! [`TILE_TOGGLE_RPC`](crate::TILE_TOGGLE_RPC) call and leaves the mode;
+//! [`Action::Cancel`] leaves it without building.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use input::{Action, ActionInput, ViewCamera};
+use items::HeldStackCounts;
+use network::RpcClient;
+use things::{PlayerControlled, ThingRegistry};
+use tiles::{TileGrid, TileKind};
+
+use crate::hover::floor_point;
+use crate::{
+    CONSTRUCTION_MATERIAL, ContextMenuAction, PendingTileToggles, TileToggleRequest,
+    TileToggleResponse, is_adjacent, material_cost,
+};
+
+/// Ghost colour over a cell the structure can be built on.
+const VALID_COLOR: Color = Color::srgba(0.3, 0.9, 0.4, 0.4);
+
+/// Ghost colour over a cell the server would refuse.
+const BLOCKED_COLOR: Color = Color::srgba(0.95, 0.25, 0.2, 0.4);
+
+/// Why a blueprint cannot be built where it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildBlocker {
+    /// The cell lies outside the grid.
+    OutOfBounds,
+    /// Another structure already stands on the cell.
+    Occupied,
+    /// The local player does not stand next to the cell.
+    OutOfRange,
+    /// The local player does not hold enough [`CONSTRUCTION_MATERIAL`].
+    MissingMaterial,
+}
+
+/// The structure being placed in blueprint mode and where.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct Blueprint {
+    /// Structure to build, or `None` outside blueprint mode.
+    pub kind: Option<TileKind>,
+    /// Cell under the cursor, if the cursor is over the floor.
+    pub cell: Option<IVec2>,
+    /// Why `kind` cannot be built on `cell`, if it cannot.
+    pub blocker: Option<BuildBlocker>,
+}
+
+/// Marker for the ghost entity previewing the [`Blueprint`].
+#[derive(Component)]
+pub(crate) struct BlueprintGhost;
+
+/// Checks the build of `kind` at `cell` the way the server will: the cell
+/// must be in the grid and free, the player at `player_pos` must stand next
+/// to it and hold `held` units of [`CONSTRUCTION_MATERIAL`], at least the
+/// [`material_cost`] of `kind`.
+pub fn check_blueprint(
+    grid: &TileGrid<TileKind>,
+    kind: TileKind,
+    cell: IVec2,
+    player_pos: Vec3,
+    held: u32,
+) -> Result<(), BuildBlocker> {
+    let current = grid.get_copy(cell).ok_or(BuildBlocker::OutOfBounds)?;
+    if !current.is_free() {
+        return Err(BuildBlocker::Occupied);
+    }
+    if !is_adjacent(player_pos, cell) {
+        return Err(BuildBlocker::OutOfRange);
+    }
+    if held < material_cost(kind) {
+        return Err(BuildBlocker::MissingMaterial);
+    }
+    Ok(())
+}
+
+/// Run condition: whether blueprint mode is active.
+pub(crate) fn blueprint_active(blueprint: Res<Blueprint>) -> bool {
+    blueprint.kind.is_some()
+}
+
+/// Enters blueprint mode for the structure picked with
+/// [`ContextMenuAction::Blueprint`].
+pub(crate) fn enter_blueprint_mode(
+    mut actions: MessageReader<ContextMenuAction>,
+    mut blueprint: ResMut<Blueprint>,
+) {
+    for action in actions.read() {
+        if let ContextMenuAction::Blueprint { kind } = *action {
+            *blueprint = Blueprint {
+                kind: Some(kind),
+                ..default()
+            };
+        }
+    }
+}
+
+/// Moves the [`Blueprint`] to the floor cell under the cursor and checks it
+/// against the replicated tilemap and the local player's hands.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_blueprint(
+    mut blueprint: ResMut<Blueprint>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    view_camera: ViewCamera,
+    ui_q: Query<&Interaction>,
+    grid: Option<Res<TileGrid<TileKind>>>,
+    player_q: Query<(Entity, &GlobalTransform), With<PlayerControlled>>,
+    held: HeldStackCounts,
+    registry: Option<Res<ThingRegistry>>,
+) {
+    let Some(kind) = blueprint.kind else {
+        return;
+    };
+    let over_ui = ui_q.iter().any(|i| *i != Interaction::None);
+    let cursor = window_q.single().ok().and_then(|w| w.cursor_position());
+    let cell = match (cursor, view_camera.get(), over_ui) {
+        (Some(cursor), Some((camera, cam_tf)), false) => floor_point(camera, cam_tf, cursor)
+            .map(|point| IVec2::new(point.x.round() as i32, point.z.round() as i32)),
+        _ => None,
+    };
+
+    let blocker = match (cell, grid.as_deref(), player_q.single()) {
+        (Some(cell), Some(grid), Ok((player, player_tf))) => {
+            let held = registry
+                .as_ref()
+                .and_then(|registry| registry.kind_by_name(CONSTRUCTION_MATERIAL))
+                .map_or(0, |material| held.count(player, material));
+            check_blueprint(grid, kind, cell, player_tf.translation(), held).err()
+        }
+        _ => None,
+    };
+    blueprint.set_if_neq(Blueprint {
+        kind: Some(kind),
+        cell,
+        blocker,
+    });
+}
+
+/// Leaves blueprint mode on [`Action::Cancel`], or builds on
+/// [`Action::Interact`] if the [`Blueprint`] can be built where it is.
+///
+/// Runs before [`enter_blueprint_mode`] so the click on the menu button
+/// that started the mode does not also confirm it.
+pub(crate) fn confirm_blueprint(
+    input: ActionInput,
+    mut blueprint: ResMut<Blueprint>,
+    mut tile_toggle: Option<ResMut<RpcClient<TileToggleRequest, TileToggleResponse>>>,
+    mut pending: ResMut<PendingTileToggles>,
+) {
+    let Some(kind) = blueprint.kind else {
+        return;
+    };
+    if input.just_pressed(Action::Cancel) {
+        *blueprint = Blueprint::default();
+        return;
+    }
+    if !input.just_pressed(Action::Interact) {
+        return;
+    }
+    let Some(cell) = blueprint.cell else {
+        return;
+    };
+    if let Some(blocker) = blueprint.blocker {
+        info!("Cannot build {:?} at {:?}: {:?}", kind, cell, blocker);
+        return;
+    }
+    if let Some(ref mut rpc) = tile_toggle {
+        match rpc.call(&TileToggleRequest {
+            position: [cell.x, cell.y],
+            kind,
+        }) {
+            Ok(call) => pending.0.push(call),
+            Err(e) => error!("Failed to send TileToggleRequest to server: {}", e),
+        }
+    }
+    *blueprint = Blueprint::default();
+}
+
+/// Keeps the [`BlueprintGhost`] on the [`Blueprint`]'s cell, coloured by
+/// whether it can be built there, and removes it outside blueprint mode.
+pub(crate) fn draw_blueprint_ghost(
+    mut commands: Commands,
+    blueprint: Res<Blueprint>,
+    mut ghost_q: Query<
+        (Entity, &mut Transform, &MeshMaterial3d<StandardMaterial>),
+        With<BlueprintGhost>,
+    >,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let Some(cell) = blueprint.cell.filter(|_| blueprint.kind.is_some()) else {
+        for (entity, ..) in ghost_q.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+    if !blueprint.is_changed() && !ghost_q.is_empty() {
+        return;
+    }
+    let color = if blueprint.blocker.is_none() {
+        VALID_COLOR
+    } else {
+        BLOCKED_COLOR
+    };
+    let translation = Vec3::new(cell.x as f32, 0.5, cell.y as f32);
+
+    if let Ok((_, mut transform, material)) = ghost_q.single_mut() {
+        transform.translation = translation;
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = color;
+        }
+        return;
+    }
+    commands.spawn((
+        BlueprintGhost,
+        Mesh3d(meshes.add(Cuboid::new(1.0, 1.0, 1.0))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: color,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })),
+        Transform::from_translation(translation),
+    ));
+}
+
+/// Leaves blueprint mode and removes the ghost, e.g. when leaving the game
+/// state.
+pub(crate) fn clear_blueprint(
+    mut commands: Commands,
+    mut blueprint: ResMut<Blueprint>,
+    ghost_q: Query<Entity, With<BlueprintGhost>>,
+) {
+    *blueprint = Blueprint::default();
+    for entity in ghost_q.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_blueprint_mirrors_the_server_checks() {
+        let mut grid = TileGrid::<TileKind>::new_fill(4, 4, TileKind::Floor);
+        grid.set(IVec2::new(2, 1), TileKind::Wall);
+        grid.set(IVec2::new(1, 2), TileKind::Space);
+        let player = Vec3::new(1.0, 0.81, 1.0);
+        let wall = TileKind::Wall;
+        let cost = material_cost(wall);
+
+        assert_eq!(
+            check_blueprint(&grid, wall, IVec2::new(1, 0), player, cost),
+            Ok(())
+        );
+        // Building on a hull breach patches it.
+        assert_eq!(
+            check_blueprint(&grid, wall, IVec2::new(1, 2), player, cost),
+            Ok(())
+        );
+        assert_eq!(
+            check_blueprint(&grid, wall, IVec2::new(-1, 0), player, cost),
+            Err(BuildBlocker::OutOfBounds)
+        );
+        assert_eq!(
+            check_blueprint(&grid, wall, IVec2::new(2, 1), player, cost),
+            Err(BuildBlocker::Occupied)
+        );
+        assert_eq!(
+            check_blueprint(&grid, wall, IVec2::new(3, 3), player, cost),
+            Err(BuildBlocker::OutOfRange)
+        );
+        assert_eq!(
+            check_blueprint(&grid, wall, IVec2::new(1, 0), player, cost - 1),
+            Err(BuildBlocker::MissingMaterial)
+        );
+        // Doors cost nothing.
+        let door = TileKind::Door { open: false };
+        assert_eq!(
+            check_blueprint(&grid, door, IVec2::new(1, 0), player, 0),
+            Ok(())
+        );
+    }
+}
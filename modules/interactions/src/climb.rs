@@ -0,0 +1,104 @@
+//! Climbing ladders between decks.
+//!
+//! [`Action::Climb`] (or "Climb" in a ladder's context menu) sends
+//! [`InteractionRequest::Climb`].  The server moves the actor one deck up if
+//! it stands on a [`TileKind::Ladder`], or one deck down if it stands on
+//! the top of one (see [`climb_target`]); everyone else sees the move
+//! through ordinary transform replication.
+
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use tiles::{DECK_HEIGHT, Deck, TileGrid, TileKind, UpperDecks, climb_target};
+
+use crate::InteractionRequest;
+
+/// Server-side request: `actor` climbs whatever ladder it stands on or
+/// above.
+#[derive(Message, Clone, Debug)]
+pub struct ClimbRequest {
+    pub actor: Entity,
+}
+
+/// Client-side system that sends [`InteractionRequest::Climb`] on
+/// [`Action::Climb`].
+pub(crate) fn request_climb(
+    input: ActionInput,
+    mut interaction_requests: MessageWriter<InteractionRequest>,
+) {
+    if input.just_pressed(Action::Climb) {
+        interaction_requests.write(InteractionRequest::Climb);
+    }
+}
+
+/// Applies [`ClimbRequest`]s by moving the actor by the height between its
+/// deck and the one the ladder leads to.  Requests with nothing to climb
+/// are dropped.
+pub(crate) fn handle_climb_requests(
+    mut requests: MessageReader<ClimbRequest>,
+    grid: Option<Res<TileGrid<TileKind>>>,
+    upper: Option<Res<UpperDecks>>,
+    mut actors: Query<&mut Transform>,
+) {
+    let Some(grid) = grid else {
+        requests.clear();
+        return;
+    };
+    for request in requests.read() {
+        let Ok(mut transform) = actors.get_mut(request.actor) else {
+            continue;
+        };
+        let pos = transform.translation;
+        let deck = Deck::of_height(pos.y);
+        let cell = IVec2::new(pos.x.round() as i32, pos.z.round() as i32);
+        let Some(target) = climb_target(&grid, upper.as_deref(), deck, cell) else {
+            debug!("Climb by {:?}: no ladder at {:?}", request.actor, cell);
+            continue;
+        };
+        transform.translation.y += (target.0 as f32 - deck.0 as f32) * DECK_HEIGHT;
+        info!(
+            "{:?} climbed from {:?} to {:?}",
+            request.actor, deck, target
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiles::DeckLayers;
+
+    #[test]
+    fn climbing_moves_the_actor_between_decks() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_message::<ClimbRequest>();
+        let mut grid = TileGrid::<TileKind>::new(4, 4);
+        grid.set(IVec2::new(1, 2), TileKind::Ladder);
+        app.insert_resource(grid);
+        app.insert_resource(UpperDecks(vec![DeckLayers::new(4, 4)]));
+        app.add_systems(Update, handle_climb_requests);
+        let actor = app
+            .world_mut()
+            .spawn(Transform::from_xyz(1.1, 0.8, 2.0))
+            .id();
+        let y = |app: &App| app.world().get::<Transform>(actor).unwrap().translation.y;
+
+        app.world_mut().write_message(ClimbRequest { actor });
+        app.update();
+        assert!((y(&app) - (0.8 + DECK_HEIGHT)).abs() < 1e-5);
+
+        app.world_mut().write_message(ClimbRequest { actor });
+        app.update();
+        assert!((y(&app) - 0.8).abs() < 1e-5);
+
+        // Away from the ladder there is nothing to climb.
+        app.world_mut()
+            .get_mut::<Transform>(actor)
+            .unwrap()
+            .translation
+            .x = 3.0;
+        app.world_mut().write_message(ClimbRequest { actor });
+        app.update();
+        assert!((y(&app) - 0.8).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,90 @@
+//! Context-menu entries contributed from outside `build_context_menu`.
+//!
+//! Any plugin, before or after [`InteractionsPlugin`](crate::InteractionsPlugin),
+//! can call [`ContextActionRegistryExt::add_context_action`] with a
+//! [`ContextActionProvider`].  Each time the menu opens, every provider sees
+//! the right-clicked [`ContextTarget`] and may append [`ContextEntry`]s;
+//! choosing one sends its [`InteractionRequest`] to the server like a
+//! built-in action.
+
+use bevy::prelude::*;
+use network::NetId;
+
+use crate::InteractionRequest;
+
+/// What the menu was opened on, as seen by a [`ContextActionProvider`].
+#[derive(Debug, Clone, Copy)]
+pub struct ContextTarget {
+    /// The hit entity: a tile, item, container or other thing.
+    pub entity: Entity,
+    /// World position of the hit.
+    pub world_pos: Vec3,
+    /// The local player's creature, if it exists.
+    pub player: Option<Entity>,
+    /// The item in the local player's hands, if any.
+    pub holding: Option<NetId>,
+    /// Whether the hit is within the player's `InteractionRange`.
+    pub in_range: bool,
+}
+
+/// A menu entry: the button label and the request sent when it is chosen.
+#[derive(Debug, Clone)]
+pub struct ContextEntry {
+    pub label: String,
+    pub request: InteractionRequest,
+    /// Whether the entry can be chosen.  Unavailable entries, such as ones
+    /// not researched yet, are shown greyed out and send nothing.
+    pub available: bool,
+}
+
+/// Callback that appends the entries it offers for a [`ContextTarget`].
+///
+/// Gets read access to the whole world to inspect the target's components.
+pub type ContextActionProvider =
+    Box<dyn Fn(&World, &ContextTarget, &mut Vec<ContextEntry>) + Send + Sync>;
+
+/// Every registered [`ContextActionProvider`], asked in registration order
+/// after the built-in entries.
+#[derive(Resource, Default)]
+pub struct ContextActionRegistry {
+    providers: Vec<ContextActionProvider>,
+}
+
+impl ContextActionRegistry {
+    /// Adds `provider` after the ones already registered.
+    pub fn register(
+        &mut self,
+        provider: impl Fn(&World, &ContextTarget, &mut Vec<ContextEntry>) + Send + Sync + 'static,
+    ) {
+        self.providers.push(Box::new(provider));
+    }
+
+    /// Entries every provider offers for `target`.
+    pub fn entries(&self, world: &World, target: &ContextTarget) -> Vec<ContextEntry> {
+        let mut entries = Vec::new();
+        for provider in &self.providers {
+            provider(world, target, &mut entries);
+        }
+        entries
+    }
+}
+
+/// Extension trait for [`App`] that provides `add_context_action`.
+pub trait ContextActionRegistryExt {
+    fn add_context_action(
+        &mut self,
+        provider: impl Fn(&World, &ContextTarget, &mut Vec<ContextEntry>) + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl ContextActionRegistryExt for App {
+    fn add_context_action(
+        &mut self,
+        provider: impl Fn(&World, &ContextTarget, &mut Vec<ContextEntry>) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_insert_with::<ContextActionRegistry>(ContextActionRegistry::default)
+            .register(provider);
+        self
+    }
+}
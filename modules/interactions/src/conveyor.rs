@@ -0,0 +1,40 @@
+//! Context-menu entry for turning conveyors.
+//!
+//! An in-range [`Conveyor`] offers "Turn conveyor (runs east)", which sends
+//! [`InteractionRequest::TurnConveyor`]; the server turns the belt a
+//! quarter clockwise and its new heading is replicated to every client.
+
+use bevy::prelude::*;
+use items::{Conveyor, ConveyorDirection};
+use network::NetId;
+
+use crate::{ContextEntry, ContextTarget, InteractionRequest};
+
+/// [`ContextActionProvider`](crate::ContextActionProvider) offering to turn
+/// an in-range conveyor.
+pub(crate) fn conveyor_context_actions(
+    world: &World,
+    target: &ContextTarget,
+    entries: &mut Vec<ContextEntry>,
+) {
+    let (Some(conveyor), Some(&id)) = (
+        world.get::<Conveyor>(target.entity),
+        world.get::<NetId>(target.entity),
+    ) else {
+        return;
+    };
+    if !target.in_range {
+        return;
+    }
+    let runs = match conveyor.direction {
+        ConveyorDirection::North => "north",
+        ConveyorDirection::East => "east",
+        ConveyorDirection::South => "south",
+        ConveyorDirection::West => "west",
+    };
+    entries.push(ContextEntry {
+        label: format!("Turn conveyor (runs {runs})"),
+        request: InteractionRequest::TurnConveyor { conveyor: id },
+        available: true,
+    });
+}
@@ -0,0 +1,66 @@
+//! Context-menu entries for crafting.
+//!
+//! Right-clicking a crafting station offers every recipe made at its kind;
+//! right-clicking the floor while holding an item offers the recipes made in
+//! the hands that use it; recipes whose output has not been researched are
+//! shown greyed out.  Choosing one sends [`InteractionRequest::Craft`], which
+//! the server checks against what the player can reach before the work
+//! starts.
+
+use bevy::prelude::*;
+use crafting::{CraftRequest, RecipeBook};
+use network::NetId;
+use research::{TechTree, Unlock};
+use things::{NetIdIndex, Thing};
+use tiles::{Tile, TileLayer};
+
+use crate::{ContextEntry, ContextTarget, InteractionRequest};
+
+/// [`ContextActionProvider`](crate::ContextActionProvider) offering "Craft
+/// {recipe}" entries.
+pub(crate) fn craft_context_actions(
+    world: &World,
+    target: &ContextTarget,
+    entries: &mut Vec<ContextEntry>,
+) {
+    let Some(book) = world.get_resource::<RecipeBook>() else {
+        return;
+    };
+    if !target.in_range {
+        return;
+    }
+
+    let (recipes, station): (Vec<_>, _) = if let Some(thing) = world.get::<Thing>(target.entity) {
+        let Some(&station) = world.get::<NetId>(target.entity) else {
+            return;
+        };
+        (book.made_at(Some(thing.kind)).collect(), Some(station))
+    } else if world
+        .get::<Tile>(target.entity)
+        .is_some_and(|tile| tile.layer == TileLayer::Floor)
+    {
+        let Some(held) = target
+            .holding
+            .and_then(|item| world.get_resource::<NetIdIndex>()?.0.get(&item).copied())
+            .and_then(|item| world.get::<Thing>(item))
+        else {
+            return;
+        };
+        let recipes = book
+            .made_at(None)
+            .filter(|(_, recipe)| recipe.inputs.iter().any(|input| input.kind == held.kind))
+            .collect();
+        (recipes, None)
+    } else {
+        return;
+    };
+
+    let tech = world.get_resource::<TechTree>();
+    for (recipe, details) in recipes {
+        entries.push(ContextEntry {
+            label: format!("Craft {}", details.name),
+            request: InteractionRequest::Craft(CraftRequest { recipe, station }),
+            available: tech.is_none_or(|tech| tech.allows(Unlock::Item(details.output.kind))),
+        });
+    }
+}
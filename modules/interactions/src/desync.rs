@@ -0,0 +1,389 @@
+//! Desync checker: compares the client's replicated world with the server's.
+//!
+//! [`Action::CheckDesync`] asks the server for its [`WorldChecksums`]
+//! through [`DESYNC_RPC`].  When the answer arrives the client hashes its
+//! own world the same way and logs every section and entry that differs,
+//! which catches replication bugs that would otherwise only show up as
+//! something looking slightly off.
+//!
+//! The server answers each client for the part of the world it sends that
+//! client: the tile chunks streamed to it and the things visible to it
+//! through [`ReplicationScope`].  The client hashes the chunks it received
+//! and the things it knows, so interest management alone never shows up as
+//! a desync.
+//!
+//! Each section keeps one hash per entry (a tile chunk, a gas row, a
+//! container, an entity) so mismatches can be pinned down without shipping
+//! the world itself.  Floats are rounded before hashing: gas is replicated in deltas
+//! above an epsilon and clients interpolate positions, so values within
+//! [`GAS_QUANTUM`] and [`POSITION_QUANTUM`] count as equal.  A value right
+//! at a rounding boundary can still be reported; repeat the check to rule
+//! that out.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use atmospherics::GasGrid;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use items::Container;
+use network::{ClientId, NetId, RpcCall, RpcClient, RpcDef, RpcServer};
+use things::ReplicationScope;
+use tiles::{ChunkSubscriptions, ClientTileChunks, FloorKind, TILE_CHUNK_SIZE, TileGrid, TileKind};
+use wincode::{SchemaRead, SchemaWrite};
+
+/// RPC through which the client asks the server for its
+/// [`WorldChecksums`].  Its stream tags are derived from the name.
+pub const DESYNC_RPC: RpcDef = RpcDef::named("desync");
+
+/// Moles per cell below which gas differences are ignored.
+pub const GAS_QUANTUM: f32 = 0.1;
+
+/// Metres below which position differences are ignored.
+pub const POSITION_QUANTUM: f32 = 0.05;
+
+/// Most differing entries logged per section.
+const MAX_REPORTED_ENTRIES: usize = 16;
+
+/// Part of the world covered by one [`SectionChecksum`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SchemaRead, SchemaWrite)]
+pub enum DesyncSection {
+    /// Floor and structure layers, one entry per chunk.
+    Tiles,
+    /// Moles per cell, one entry per row.
+    Gas,
+    /// Slot contents by [`NetId`], one entry per container.
+    Containers,
+    /// Rounded positions, one entry per replicated entity.
+    Positions,
+}
+
+impl DesyncSection {
+    /// Label of an entry's key in reports.
+    fn entry_label(self, key: u64) -> String {
+        match self {
+            DesyncSection::Tiles => format!("chunk {}", chunk_of_key(key)),
+            DesyncSection::Gas => format!("row {key}"),
+            DesyncSection::Containers | DesyncSection::Positions => format!("{:?}", NetId(key)),
+        }
+    }
+}
+
+/// Hashes of one section of the world.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub struct SectionChecksum {
+    pub section: DesyncSection,
+    /// Hash over every entry; equal hashes mean the section is in sync.
+    pub hash: u64,
+    /// `(key, hash)` per entry, sorted by key.
+    pub entries: Vec<(u64, u64)>,
+}
+
+impl SectionChecksum {
+    /// Checksum of `section` from its entries in any order.
+    pub fn new(section: DesyncSection, entries: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        let entries: Vec<(u64, u64)> = entries
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .collect();
+        Self {
+            section,
+            hash: hash_of(&entries),
+            entries,
+        }
+    }
+}
+
+/// Request of [`DESYNC_RPC`].  It carries nothing; the server answers for
+/// the part of its world it sends the caller.
+#[derive(Debug, Clone, Copy, PartialEq, SchemaRead, SchemaWrite)]
+pub struct DesyncRequest;
+
+/// Response of [`DESYNC_RPC`]: the server's checksums.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub struct WorldChecksums {
+    pub sections: Vec<SectionChecksum>,
+}
+
+/// The desync check awaiting the server's answer, if any.
+#[derive(Resource, Default)]
+pub struct DesyncCheck {
+    pending: Option<RpcCall<WorldChecksums>>,
+}
+
+/// Read access to everything [`WorldChecksums`] cover.
+#[derive(SystemParam)]
+pub struct DesyncQuery<'w, 's> {
+    floors: Option<Res<'w, TileGrid<FloorKind>>>,
+    structures: Option<Res<'w, TileGrid<TileKind>>>,
+    gas: Option<Res<'w, GasGrid>>,
+    client_chunks: Option<Res<'w, ClientTileChunks>>,
+    subscriptions: Option<Res<'w, ChunkSubscriptions>>,
+    scope: ReplicationScope<'w, 's>,
+    containers: Query<'w, 's, (Entity, &'static NetId, &'static Container)>,
+    positions: Query<'w, 's, (Entity, &'static NetId, &'static GlobalTransform)>,
+    net_ids: Query<'w, 's, &'static NetId>,
+}
+
+impl DesyncQuery<'_, '_> {
+    /// Checksums of the world as this client has it: the tile chunks it
+    /// received and every thing it knows.  A listen server's host has no
+    /// stream of its own and hashes what the server sends
+    /// [`ClientId::LOCAL`].
+    pub fn local_checksums(&self) -> WorldChecksums {
+        match &self.client_chunks {
+            Some(chunks) => self.checksums(chunks.received(), chunks.chunk_size(), |_| true),
+            None => self.checksums_for(ClientId::LOCAL),
+        }
+    }
+
+    /// Checksums of the part of the world the server sends `client`.
+    pub fn checksums_for(&self, client: ClientId) -> WorldChecksums {
+        let none = HashSet::new();
+        let chunks = self
+            .subscriptions
+            .as_ref()
+            .and_then(|subscriptions| subscriptions.sent_to(client))
+            .unwrap_or(&none);
+        self.checksums(chunks, TILE_CHUNK_SIZE, |entity| {
+            self.scope.is_visible_to(entity, client)
+        })
+    }
+
+    /// Checksums of the tile `chunks` of `chunk_size` and the things for
+    /// which `visible` holds.  Sections whose resources are missing hash as
+    /// empty.
+    fn checksums(
+        &self,
+        chunks: &HashSet<IVec2>,
+        chunk_size: u32,
+        visible: impl Fn(Entity) -> bool,
+    ) -> WorldChecksums {
+        let tiles = chunks.iter().map(|&chunk| {
+            let mut hasher = DefaultHasher::new();
+            if let Some(floors) = &self.floors {
+                floors.chunk_cells(chunk, chunk_size).hash(&mut hasher);
+            }
+            if let Some(structures) = &self.structures {
+                structures.chunk_cells(chunk, chunk_size).hash(&mut hasher);
+            }
+            (chunk_key(chunk), hasher.finish())
+        });
+
+        let gas_rows: Vec<(u64, u64)> = self.gas.as_ref().map_or_else(Vec::new, |gas| {
+            let width = gas.width().max(1) as usize;
+            gas.moles_vec()
+                .chunks(width)
+                .enumerate()
+                .map(|(y, row)| {
+                    let row: Vec<i64> = row.iter().map(|&m| quantize(m, GAS_QUANTUM)).collect();
+                    (y as u64, hash_of(&row))
+                })
+                .collect()
+        });
+
+        let containers = self
+            .containers
+            .iter()
+            .filter(|&(entity, ..)| visible(entity))
+            .map(|(_, net_id, container)| {
+                let slots: Vec<Option<u64>> = container
+                    .slots
+                    .iter()
+                    .map(|slot| slot.and_then(|e| self.net_ids.get(e).ok()).map(|id| id.0))
+                    .collect();
+                (net_id.0, hash_of(&slots))
+            });
+
+        let positions = self
+            .positions
+            .iter()
+            .filter(|&(entity, ..)| visible(entity))
+            .map(|(_, net_id, transform)| {
+                let position = transform
+                    .translation()
+                    .to_array()
+                    .map(|v| quantize(v, POSITION_QUANTUM));
+                (net_id.0, hash_of(&position))
+            });
+
+        WorldChecksums {
+            sections: vec![
+                SectionChecksum::new(DesyncSection::Tiles, tiles),
+                SectionChecksum::new(DesyncSection::Gas, gas_rows),
+                SectionChecksum::new(DesyncSection::Containers, containers),
+                SectionChecksum::new(DesyncSection::Positions, positions),
+            ],
+        }
+    }
+}
+
+/// Entry key of a tile chunk.
+fn chunk_key(chunk: IVec2) -> u64 {
+    ((chunk.x as u32 as u64) << 32) | chunk.y as u32 as u64
+}
+
+/// Inverse of [`chunk_key`].
+fn chunk_of_key(key: u64) -> IVec2 {
+    IVec2::new((key >> 32) as u32 as i32, key as u32 as i32)
+}
+
+fn hash_of(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn quantize(value: f32, quantum: f32) -> i64 {
+    (value / quantum).round() as i64
+}
+
+/// Report lines for every section where `local` and `server` differ, each
+/// followed by its differing entries.  Empty when the worlds agree.
+pub fn desync_report(local: &WorldChecksums, server: &WorldChecksums) -> Vec<String> {
+    let mut lines = Vec::new();
+    for theirs in &server.sections {
+        let Some(ours) = local.sections.iter().find(|s| s.section == theirs.section) else {
+            lines.push(format!("{:?}: missing on client", theirs.section));
+            continue;
+        };
+        if ours.hash == theirs.hash {
+            continue;
+        }
+        let ours_by_key: BTreeMap<u64, u64> = ours.entries.iter().copied().collect();
+        let theirs_by_key: BTreeMap<u64, u64> = theirs.entries.iter().copied().collect();
+        let keys: BTreeSet<u64> = ours_by_key
+            .keys()
+            .chain(theirs_by_key.keys())
+            .copied()
+            .collect();
+        let total = keys.len();
+        let differing: Vec<String> = keys
+            .into_iter()
+            .filter_map(|key| {
+                let label = theirs.section.entry_label(key);
+                match (ours_by_key.get(&key), theirs_by_key.get(&key)) {
+                    (Some(a), Some(b)) if a != b => Some(format!("  {label} differs")),
+                    (Some(_), None) => Some(format!("  {label} only on client")),
+                    (None, Some(_)) => Some(format!("  {label} only on server")),
+                    _ => None,
+                }
+            })
+            .collect();
+        lines.push(format!(
+            "{:?}: {} of {} entries differ",
+            theirs.section,
+            differing.len(),
+            total
+        ));
+        let omitted = differing.len().saturating_sub(MAX_REPORTED_ENTRIES);
+        lines.extend(differing.into_iter().take(MAX_REPORTED_ENTRIES));
+        if omitted > 0 {
+            lines.push(format!("  ... and {omitted} more"));
+        }
+    }
+    lines
+}
+
+/// Sends a [`DesyncRequest`] on [`Action::CheckDesync`] and logs the
+/// comparison once the server answers.
+pub(crate) fn check_desync(
+    input: ActionInput,
+    mut check: ResMut<DesyncCheck>,
+    desync: DesyncQuery,
+    mut rpc: Option<ResMut<RpcClient<DesyncRequest, WorldChecksums>>>,
+) {
+    if let Some(call) = check.pending.take() {
+        match call.try_take() {
+            None => check.pending = Some(call),
+            Some(Ok(server)) => {
+                // Hash the local world only now, so it is compared with the
+                // server's as close to the same tick as replication allows.
+                let report = desync_report(&desync.local_checksums(), &server);
+                if report.is_empty() {
+                    info!("Desync check: client and server agree");
+                } else {
+                    warn!("Desync check found differences:\n{}", report.join("\n"));
+                }
+            }
+            Some(Err(e)) => warn!("Desync check {:?} abandoned: {}", call.id(), e),
+        }
+    }
+
+    if !input.just_pressed(Action::CheckDesync) || check.pending.is_some() {
+        return;
+    }
+    let Some(rpc) = rpc.as_mut() else {
+        info!("Desync check needs a connection to a server");
+        return;
+    };
+    match rpc.call(&DesyncRequest) {
+        Ok(call) => check.pending = Some(call),
+        Err(e) => error!("Failed to send DesyncRequest to server: {}", e),
+    }
+}
+
+/// Server-side system that answers [`DESYNC_RPC`] calls with the
+/// authoritative [`WorldChecksums`] of what each caller is sent.
+pub(crate) fn serve_desync_checks(
+    mut rpc: ResMut<RpcServer<DesyncRequest, WorldChecksums>>,
+    desync: DesyncQuery,
+) {
+    let calls: Vec<_> = rpc.drain().collect();
+    for call in calls {
+        let checksums = desync.checksums_for(call.from);
+        if let Err(e) = rpc.respond(call.from, call.id, &checksums) {
+            error!("Failed to answer DesyncRequest from {:?}: {}", call.from, e);
+        }
+    }
+}
+
+/// Forgets a pending check on leaving the game state.
+pub(crate) fn clear_desync_check(mut check: ResMut<DesyncCheck>) {
+    *check = DesyncCheck::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world(positions: Vec<(u64, u64)>) -> WorldChecksums {
+        WorldChecksums {
+            sections: vec![
+                SectionChecksum::new(DesyncSection::Tiles, [(0, 1), (1, 2)]),
+                SectionChecksum::new(DesyncSection::Positions, positions),
+            ],
+        }
+    }
+
+    #[test]
+    fn chunk_keys_round_trip_negative_chunks() {
+        for chunk in [IVec2::ZERO, IVec2::new(-1, 3), IVec2::new(7, -20)] {
+            assert_eq!(chunk_of_key(chunk_key(chunk)), chunk);
+        }
+        assert_eq!(
+            DesyncSection::Tiles.entry_label(chunk_key(IVec2::new(-1, 3))),
+            "chunk [-1, 3]"
+        );
+    }
+
+    #[test]
+    fn desync_report_lists_differing_entries_only() {
+        let local = world(vec![(7, 1), (8, 2), (9, 3)]);
+        assert!(desync_report(&local, &local).is_empty());
+
+        let server = world(vec![(9, 3), (7, 5), (10, 4)]);
+        let report = desync_report(&local, &server);
+        assert_eq!(
+            report,
+            vec![
+                "Positions: 3 of 4 entries differ",
+                "  NetId(7) differs",
+                "  NetId(8) only on client",
+                "  NetId(10) only on server",
+            ]
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Dragging: pulling a heavy thing along instead of carrying it.
+//!
+//! The server links the actor and a [`Draggable`] thing with a
+//! [`ReplicatedJoint`] of kind [`DRAG_JOINT`], so physics keeps the thing
+//! trailing behind the actor on the server and on every client.  Wherever
+//! such a joint exists, its actor is marked [`Dragging`] and slowed down
+//! with a [`SpeedMultiplier`], so prediction moves at the same pace as the
+//! server.  The link breaks when either end disappears, the thing is picked
+//! up or stops being draggable, the actor falls unconscious, or the two
+//! drift too far apart.
+//!
+//! Unconscious creatures are [`Draggable`] for as long as they are out, on
+//! the server and on every client, so bodies can be pulled to safety like
+//! any heavy thing.
+
+use bevy::prelude::*;
+use creatures::SpeedMultiplier;
+use items::{Draggable, InteractionRange};
+use network::{NetId, Server};
+use things::{JointKind, MovementState, NetIdIndex, ReplicatedJoint, SpatialIndex};
+
+/// Longest the joint lets the dragged thing trail behind the actor.
+pub const DRAG_DISTANCE: f32 = 1.5;
+
+/// The link breaks once actor and dragged thing are further apart than this,
+/// e.g. when the thing snags on a wall.
+pub const DRAG_BREAK_DISTANCE: f32 = 3.0;
+
+/// [`SpeedMultiplier`] applied to an actor while it drags something.
+pub const DRAG_SPEED_FACTOR: f32 = 0.6;
+
+/// Kind of the [`ReplicatedJoint`] behind every drag, with the actor as `a`
+/// and the dragged thing as `b`.  No other joint uses this shape, which is
+/// how clients tell drags apart.
+pub const DRAG_JOINT: JointKind = JointKind::Distance {
+    min: 0.0,
+    max: DRAG_DISTANCE,
+};
+
+/// Present on a creature while it drags `0`.
+///
+/// Inserted together with the actor's [`SpeedMultiplier`] when a
+/// [`DRAG_JOINT`] is added, on the server and on every client.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dragging(pub Entity);
+
+/// Server-side link between an actor and the thing it drags.  Lives on the
+/// [`ReplicatedJoint`] entity that does the pulling.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct DragLink {
+    pub actor: Entity,
+    pub target: Entity,
+}
+
+/// Server-side request: `actor` starts dragging `target`.
+#[derive(Message, Clone, Debug)]
+pub struct DragRequest {
+    pub actor: Entity,
+    pub target: Entity,
+}
+
+/// Server-side request: `actor` lets go of whatever it drags.
+#[derive(Message, Clone, Debug)]
+pub struct ReleaseDragRequest {
+    pub actor: Entity,
+}
+
+// ── Server ────────────────────────────────────────────────────────────────────
+
+/// Applies [`DragRequest`]s and [`ReleaseDragRequest`]s.
+///
+/// A drag starts only if the target is [`Draggable`], lies in the world (not
+/// in a hand or container) and is among the things within
+/// [`InteractionRange`] of the actor in the [`SpatialIndex`].
+/// Starting a new drag releases the actor's previous one.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_drag_requests(
+    mut commands: Commands,
+    mut drag_req: MessageReader<DragRequest>,
+    mut release_req: MessageReader<ReleaseDragRequest>,
+    interaction_range: Res<InteractionRange>,
+    spatial: Res<SpatialIndex>,
+    draggable_q: Query<(), (With<Draggable>, Without<ChildOf>)>,
+    transforms: Query<&Transform>,
+    links: Query<(Entity, &DragLink)>,
+    net_ids: Query<&NetId>,
+    mut server: ResMut<Server>,
+) {
+    for req in release_req.read() {
+        release_actor(&mut commands, &links, req.actor);
+    }
+
+    for req in drag_req.read() {
+        if req.actor == req.target || draggable_q.get(req.target).is_err() {
+            warn!(
+                "DragRequest: {:?} cannot drag {:?} (not a free draggable thing)",
+                req.actor, req.target
+            );
+            continue;
+        }
+        let Ok(actor_tf) = transforms.get(req.actor) else {
+            warn!("DragRequest: {:?} has no Transform", req.actor);
+            continue;
+        };
+        let (Ok(&a), Ok(&b)) = (net_ids.get(req.actor), net_ids.get(req.target)) else {
+            warn!(
+                "DragRequest: {:?} or {:?} has no NetId",
+                req.actor, req.target
+            );
+            continue;
+        };
+        if !spatial
+            .query_within(actor_tf.translation, interaction_range.0)
+            .any(|(entity, _)| entity == req.target)
+        {
+            warn!(
+                "DragRequest: {:?} is out of range of {:?}",
+                req.target, req.actor
+            );
+            continue;
+        }
+        if links.iter().any(|(_, link)| link.target == req.target) {
+            debug!("DragRequest: {:?} is already being dragged", req.target);
+            continue;
+        }
+
+        release_actor(&mut commands, &links, req.actor);
+        let (joint, _) = things::spawn_joint(
+            &mut commands,
+            &mut server,
+            ReplicatedJoint {
+                a,
+                b,
+                kind: DRAG_JOINT,
+            },
+        );
+        commands.entity(joint).insert(DragLink {
+            actor: req.actor,
+            target: req.target,
+        });
+    }
+}
+
+/// Despawns the drag link of `actor`, if any.
+fn release_actor(commands: &mut Commands, links: &Query<(Entity, &DragLink)>, actor: Entity) {
+    for (entity, link) in links.iter() {
+        if link.actor == actor {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Breaks links whose actor or target is gone, whose target was picked up,
+/// stored or is no longer [`Draggable`], whose actor fell unconscious, or
+/// whose ends drifted further apart than [`DRAG_BREAK_DISTANCE`].
+pub(crate) fn break_stretched_drags(
+    mut commands: Commands,
+    links: Query<(Entity, &DragLink)>,
+    free_targets: Query<&Transform, (With<Draggable>, Without<ChildOf>)>,
+    actors: Query<(&Transform, Option<&MovementState>)>,
+) {
+    for (entity, link) in links.iter() {
+        let intact = match (actors.get(link.actor), free_targets.get(link.target)) {
+            (Ok((actor, state)), Ok(target)) => {
+                state != Some(&MovementState::Unconscious)
+                    && actor.translation.distance(target.translation) <= DRAG_BREAK_DISTANCE
+            }
+            _ => false,
+        };
+        if !intact {
+            debug!("Drag link {:?} -> {:?} broke", link.actor, link.target);
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Makes creatures that fell unconscious [`Draggable`] and takes it away
+/// again once they come to.  Runs on the server and on clients, from the
+/// replicated [`MovementState`].
+pub(crate) fn mark_unconscious_draggable(
+    mut commands: Commands,
+    creatures: Query<(Entity, &MovementState, Has<Draggable>), Changed<MovementState>>,
+) {
+    for (entity, state, draggable) in creatures.iter() {
+        let unconscious = *state == MovementState::Unconscious;
+        if unconscious && !draggable {
+            commands.entity(entity).insert(Draggable);
+        } else if !unconscious && draggable {
+            commands.entity(entity).remove::<Draggable>();
+        }
+    }
+}
+
+/// Despawns all drag links, e.g. when leaving the game state.
+pub(crate) fn clear_drag_links(mut commands: Commands, links: Query<Entity, With<DragLink>>) {
+    for entity in links.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// ── Shared ────────────────────────────────────────────────────────────────────
+
+/// Marks the actor of a newly added [`DRAG_JOINT`] as [`Dragging`] its `b`
+/// end and slows it down.
+pub(crate) fn on_drag_joint_added(
+    trigger: On<Add, ReplicatedJoint>,
+    mut commands: Commands,
+    joints: Query<&ReplicatedJoint>,
+    net_id_index: Res<NetIdIndex>,
+) {
+    let Ok(joint) = joints.get(trigger.event_target()) else {
+        return;
+    };
+    if joint.kind != DRAG_JOINT {
+        return;
+    }
+    let (Some(&actor), Some(&target)) =
+        (net_id_index.0.get(&joint.a), net_id_index.0.get(&joint.b))
+    else {
+        warn!("Drag joint links unknown {:?} or {:?}", joint.a, joint.b);
+        return;
+    };
+    commands
+        .entity(actor)
+        .insert((Dragging(target), SpeedMultiplier(DRAG_SPEED_FACTOR)));
+}
+
+/// Restores the speed of the actor of a [`DRAG_JOINT`] that is going away,
+/// unless it has already started dragging something else.
+pub(crate) fn on_drag_joint_removed(
+    trigger: On<Remove, ReplicatedJoint>,
+    mut commands: Commands,
+    joints: Query<&ReplicatedJoint>,
+    dragging: Query<&Dragging>,
+    net_id_index: Res<NetIdIndex>,
+) {
+    let Ok(joint) = joints.get(trigger.event_target()) else {
+        return;
+    };
+    if joint.kind != DRAG_JOINT {
+        return;
+    }
+    let Some(&actor) = net_id_index.0.get(&joint.a) else {
+        return;
+    };
+    if let (Ok(&Dragging(current)), Some(&target)) =
+        (dragging.get(actor), net_id_index.0.get(&joint.b))
+        && current != target
+    {
+        return;
+    }
+    if let Ok(mut actor) = commands.get_entity(actor) {
+        actor.remove::<(Dragging, SpeedMultiplier)>();
+    }
+}
@@ -0,0 +1,97 @@
+//! Context-menu entries for pouring fluids.
+//!
+//! While the player holds a [`FluidContainer`], the context menu of any
+//! other fluid end in reach offers "Pour into …" when it can take fluid
+//! and "Fill from …" when it can give some.  Entries that would move
+//! nothing, such as pouring into a full tank, are shown greyed out.
+//! Choosing one sends [`InteractionRequest::PourFluid`].
+
+use bevy::prelude::*;
+use items::{
+    CustomLabel, FluidContainer, FluidDrain, FluidEnd, FluidSource, ItemCatalog, plan_pour,
+};
+use network::NetId;
+use things::{DisplayName, NetIdIndex, Thing};
+
+use crate::{ContextEntry, ContextTarget, InteractionRequest};
+
+/// The [`FluidEnd`] `entity` acts as, if any.
+fn fluid_end(entity: EntityRef) -> Option<FluidEnd> {
+    if let Some(container) = entity.get::<FluidContainer>() {
+        return Some(FluidEnd::Container(*container));
+    }
+    if let Some(source) = entity.get::<FluidSource>() {
+        return Some(FluidEnd::Source(source.kind));
+    }
+    entity.contains::<FluidDrain>().then_some(FluidEnd::Drain)
+}
+
+/// [`ContextActionProvider`](crate::ContextActionProvider) offering pours
+/// between the held fluid container and the target.
+pub(crate) fn fluid_context_actions(
+    world: &World,
+    target: &ContextTarget,
+    entries: &mut Vec<ContextEntry>,
+) {
+    let (Some(net_id_index), Some(held)) = (world.get_resource::<NetIdIndex>(), target.holding)
+    else {
+        return;
+    };
+    if !target.in_range {
+        return;
+    }
+    let Some(held_entity) = net_id_index
+        .0
+        .get(&held)
+        .and_then(|&item| world.get_entity(item).ok())
+    else {
+        return;
+    };
+    let Some(held_end @ FluidEnd::Container(_)) = fluid_end(held_entity) else {
+        return;
+    };
+    if held_entity.id() == target.entity {
+        return;
+    }
+    let Ok(other) = world.get_entity(target.entity) else {
+        return;
+    };
+    let (Some(other_end), Some(&other_id)) = (fluid_end(other), other.get::<NetId>()) else {
+        return;
+    };
+    let name = other
+        .get::<CustomLabel>()
+        .map(|label| label.0.clone())
+        .or_else(|| other.get::<DisplayName>().map(|name| name.0.clone()))
+        .or_else(|| {
+            let kind = other.get::<Thing>()?.kind;
+            Some(
+                world
+                    .get_resource::<ItemCatalog>()?
+                    .display_name(kind)?
+                    .to_string(),
+            )
+        })
+        .unwrap_or_else(|| "it".to_string());
+
+    if !matches!(other_end, FluidEnd::Source(_)) {
+        entries.push(ContextEntry {
+            label: format!("Pour into {name}"),
+            request: InteractionRequest::PourFluid {
+                from: held,
+                to: other_id,
+            },
+            available: plan_pour(held_end, other_end).is_ok(),
+        });
+    }
+    if !matches!(other_end, FluidEnd::Drain) {
+        entries.push(ContextEntry {
+            label: format!("Fill from {name}"),
+            request: InteractionRequest::PourFluid {
+                from: other_id,
+                to: held,
+            },
+            available: plan_pour(other_end, held_end).is_ok(),
+        });
+    }
+}
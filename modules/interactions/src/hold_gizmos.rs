@@ -0,0 +1,89 @@
+//! Debug gizmos for tuning [`HoldOffset`](items::HoldOffset)s.
+//!
+//! [`Action::ToggleHoldGizmos`] draws the axes of every hand anchor and of
+//! each item held in it, joined by a line, and outlines the held items, so
+//! a template's `hold_offset` can be adjusted until the item clears its
+//! holder.  Turning the gizmos on also logs the offsets in use.
+
+use bevy::camera::primitives::Aabb;
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use items::{Container, ItemCatalog};
+use things::{HandSlot, Thing};
+
+/// Length of the axes drawn at a hand anchor, in metres.
+const ANCHOR_AXES: f32 = 0.15;
+/// Length of the axes drawn at a held item, in metres.
+const ITEM_AXES: f32 = 0.3;
+/// Colour of the anchor-to-item line and the item outline.
+const OFFSET_COLOR: Color = Color::srgb(0.3, 0.9, 1.0);
+
+/// Whether the hold offset gizmos are drawn.
+#[derive(Resource, Default)]
+pub struct HoldGizmos(pub bool);
+
+/// Toggles the gizmos on [`Action::ToggleHoldGizmos`], logging the hold
+/// offset of every held item's kind when they come on.
+pub(crate) fn toggle_hold_gizmos(
+    input: ActionInput,
+    mut enabled: ResMut<HoldGizmos>,
+    catalog: Res<ItemCatalog>,
+    hands: Query<&Container, With<HandSlot>>,
+    things: Query<&Thing>,
+) {
+    if !input.just_pressed(Action::ToggleHoldGizmos) {
+        return;
+    }
+    enabled.0 = !enabled.0;
+    info!(
+        "Hold offset gizmos: {}",
+        if enabled.0 { "ON" } else { "OFF" }
+    );
+    if !enabled.0 {
+        return;
+    }
+    for item in hands.iter().flat_map(|hand| hand.slots.iter().flatten()) {
+        if let Ok(thing) = things.get(*item) {
+            info!(
+                "  {} (kind {}): {:?}",
+                catalog.display_name(thing.kind).unwrap_or("unnamed"),
+                thing.kind,
+                catalog.hold_offset(thing.kind)
+            );
+        }
+    }
+}
+
+/// Draws each hand anchor's axes and, for the item it holds, the item's
+/// axes, a line to it and its bounds.
+pub(crate) fn draw_hold_gizmos(
+    mut gizmos: Gizmos,
+    enabled: Res<HoldGizmos>,
+    hands: Query<(&GlobalTransform, &Container), With<HandSlot>>,
+    items: Query<(&GlobalTransform, Option<&Aabb>)>,
+) {
+    if !enabled.0 {
+        return;
+    }
+    for (anchor, container) in hands.iter() {
+        gizmos.axes(*anchor, ANCHOR_AXES);
+        for &item in container.slots.iter().flatten() {
+            let Ok((transform, aabb)) = items.get(item) else {
+                continue;
+            };
+            gizmos.axes(*transform, ITEM_AXES);
+            gizmos.line(anchor.translation(), transform.translation(), OFFSET_COLOR);
+            if let Some(aabb) = aabb {
+                let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+                gizmos.cube(
+                    Transform {
+                        translation: translation + rotation * (Vec3::from(aabb.center) * scale),
+                        rotation,
+                        scale: scale * Vec3::from(aabb.half_extents) * 2.0,
+                    },
+                    OFFSET_COLOR,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! What the cursor is pointing at, every frame.
+//!
+//! [`update_hover_target`] casts a ray from the cursor against thing
+//! colliders and the floor plane, keeps the hit closest to the camera (the
+//! same tie-break `resolve_world_hits` applies to clicks) and stores it in
+//! [`HoverTarget`] together with what a left or right click on it would do.
+//! The hovered thing or tile is outlined with gizmos and the click hints
+//! follow the cursor.
+
+use bevy::camera::primitives::Aabb;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use input::{Action, InputMap, ViewCamera};
+use items::{Container, CustomLabel, Draggable, InteractionRange, Item, ItemCatalog};
+use physics::{GameLayer, SpatialQuery};
+use things::{DisplayName, HandSlot, PlayerControlled, Thing};
+use tiles::{LayerTile, Tile, TileKind, TileLayer, Tilemap};
+use ui::UiTheme;
+
+/// Outline colour of the hovered thing or tile.
+const HIGHLIGHT_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+
+/// Offset of the click hints from the cursor, in logical pixels.
+const HINT_OFFSET: Vec2 = Vec2::new(16.0, 16.0);
+
+/// The thing or tile under the cursor and what clicking it would do.
+///
+/// Updated every frame on clients by [`update_hover_target`]; empty while
+/// the cursor is over UI or off the window.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct HoverTarget {
+    /// Entity under the cursor and the world point that was hit.
+    pub hit: Option<(Entity, Vec3)>,
+    /// What a left click would do, e.g. "Pick up".
+    pub left: Option<String>,
+    /// The first action of the context menu a right click would open.
+    pub right: Option<String>,
+}
+
+/// Marker for the text node showing [`HoverTarget`]'s click hints.
+#[derive(Component)]
+pub(crate) struct HoverHint;
+
+/// What kind of target the hints are chosen for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum HoverKind<'a> {
+    Item,
+    Container { name: &'a str, has_items: bool },
+    Tile(LayerTile),
+    Other,
+}
+
+/// Left- and right-click hints for a target, mirroring
+/// `default_interaction` and the first entry `build_context_menu` would
+/// offer.
+pub(crate) fn click_hints(
+    kind: HoverKind,
+    holding: bool,
+    in_range: bool,
+    draggable: bool,
+) -> (Option<String>, Option<String>) {
+    let left = (kind == HoverKind::Item && in_range).then(|| "Pick up".to_string());
+    let right = match kind {
+        HoverKind::Item => in_range.then(|| "Pick up".to_string()),
+        HoverKind::Container { name, has_items } => in_range.then(|| {
+            if holding {
+                format!("Store in {name}")
+            } else if has_items {
+                format!("Take from {name}")
+            } else {
+                format!("Open {name}")
+            }
+        }),
+        HoverKind::Tile(LayerTile::Structure(TileKind::Door { open })) => Some(
+            match (in_range, open) {
+                (true, true) => "Close Door",
+                (true, false) => "Open Door",
+                (false, _) => "Remove Door",
+            }
+            .to_string(),
+        ),
+        HoverKind::Tile(LayerTile::Structure(TileKind::Wall)) => Some("Remove Wall".to_string()),
+        HoverKind::Tile(LayerTile::Structure(TileKind::Space)) => Some("Patch Hull".to_string()),
+        HoverKind::Tile(LayerTile::Structure(TileKind::Ladder)) => {
+            Some(if in_range { "Climb" } else { "Remove Ladder" }.to_string())
+        }
+        HoverKind::Tile(LayerTile::Structure(TileKind::Floor)) => None,
+        HoverKind::Tile(LayerTile::Floor(_)) if holding && in_range => Some("Drop".to_string()),
+        HoverKind::Tile(LayerTile::Floor(_)) => Some("Build Wall".to_string()),
+        HoverKind::Other => None,
+    };
+    let right = right.or_else(|| (draggable && in_range).then(|| "Drag".to_string()));
+    (left, right)
+}
+
+/// Where the ray through `cursor` meets the `y = 0` floor plane.
+pub(crate) fn floor_point(camera: &Camera, cam_tf: &GlobalTransform, cursor: Vec2) -> Option<Vec3> {
+    let ray = camera.viewport_to_world(cam_tf, cursor).ok()?;
+    let dir = Vec3::from(ray.direction);
+    if dir.y.abs() < 1e-4 {
+        return None;
+    }
+    let t = -ray.origin.y / dir.y;
+    (t >= 0.0).then(|| ray.origin + t * dir)
+}
+
+/// Client system: raycasts from the cursor and updates [`HoverTarget`].
+///
+/// Things are hit through their colliders, tiles through the floor plane
+/// (structures win over floors, as for clicks).  Nothing is hovered while
+/// the cursor is over an interactive UI node.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn update_hover_target(
+    mut hover: ResMut<HoverTarget>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    view_camera: ViewCamera,
+    ui_q: Query<&Interaction>,
+    spatial_query: SpatialQuery,
+    things_q: Query<(), With<Thing>>,
+    tiles_q: Query<(Entity, &Tile)>,
+    tilemap: Tilemap,
+    item_q: Query<(), With<Item>>,
+    container_q: Query<
+        (
+            &Container,
+            Option<&CustomLabel>,
+            Option<&DisplayName>,
+            Option<&Thing>,
+        ),
+        Without<HandSlot>,
+    >,
+    draggable_q: Query<(), With<Draggable>>,
+    player_q: Query<(&GlobalTransform, Option<&Children>), With<PlayerControlled>>,
+    hands_q: Query<&Container, With<HandSlot>>,
+    range: Res<InteractionRange>,
+    catalog: Option<Res<ItemCatalog>>,
+) {
+    let cursor = window_q.single().ok().and_then(|w| w.cursor_position());
+    let over_ui = ui_q.iter().any(|i| *i != Interaction::None);
+    let (Some(cursor), Some((camera, cam_tf)), false) = (cursor, view_camera.get(), over_ui) else {
+        hover.set_if_neq(HoverTarget::default());
+        return;
+    };
+    let Ok(ray) = camera.viewport_to_world(cam_tf, cursor) else {
+        hover.set_if_neq(HoverTarget::default());
+        return;
+    };
+
+    let thing_hit = spatial_query
+        .cast_ray(
+            ray.origin,
+            ray.direction,
+            f32::MAX,
+            true,
+            &GameLayer::solid_filter(),
+        )
+        .filter(|hit| things_q.get(hit.entity).is_ok())
+        .map(|hit| (hit.entity, ray.origin + *ray.direction * hit.distance));
+    let tile_hit = floor_point(camera, cam_tf, cursor).and_then(|point| {
+        let cell = IVec2::new(point.x.round() as i32, point.z.round() as i32);
+        tiles_q
+            .iter()
+            .filter(|(_, t)| t.position == cell)
+            .max_by_key(|(_, t)| t.layer == TileLayer::Structure)
+            .map(|(entity, _)| (entity, point))
+    });
+    let cam_pos = cam_tf.translation();
+    let hit = [thing_hit, tile_hit].into_iter().flatten().min_by(|a, b| {
+        let da = cam_pos.distance_squared(a.1);
+        let db = cam_pos.distance_squared(b.1);
+        da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let Some((entity, world_pos)) = hit else {
+        hover.set_if_neq(HoverTarget::default());
+        return;
+    };
+
+    let player = player_q.single().ok();
+    let in_range = player.is_some_and(|(tf, _)| tf.translation().distance(world_pos) <= range.0);
+    let holding = player
+        .and_then(|(_, children)| children)
+        .is_some_and(|children| {
+            children
+                .iter()
+                .filter_map(|child| hands_q.get(child).ok())
+                .any(|hand| hand.slots.iter().any(Option::is_some))
+        });
+    let kind = if item_q.get(entity).is_ok() {
+        HoverKind::Item
+    } else if let Ok((container, label, name, thing)) = container_q.get(entity) {
+        HoverKind::Container {
+            name: label
+                .map(|l| l.0.as_str())
+                .or(name.map(|n| n.0.as_str()))
+                .or_else(|| catalog.as_deref()?.display_name(thing?.kind))
+                .unwrap_or("container"),
+            has_items: container.slots.iter().any(Option::is_some),
+        }
+    } else if let Ok((_, tile)) = tiles_q.get(entity)
+        && let Some(layer_tile) = tilemap.get_layer(tile.position, tile.layer)
+    {
+        HoverKind::Tile(layer_tile)
+    } else {
+        HoverKind::Other
+    };
+    let (left, right) = click_hints(kind, holding, in_range, draggable_q.get(entity).is_ok());
+    hover.set_if_neq(HoverTarget {
+        hit: Some((entity, world_pos)),
+        left,
+        right,
+    });
+}
+
+/// Client system: outlines the hovered thing's bounds, or the hovered tile.
+pub(crate) fn draw_hover_highlight(
+    mut gizmos: Gizmos,
+    hover: Res<HoverTarget>,
+    tiles_q: Query<&Tile>,
+    bounds_q: Query<(&GlobalTransform, Option<&Aabb>)>,
+) {
+    let Some((entity, _)) = hover.hit else {
+        return;
+    };
+    if let Ok(tile) = tiles_q.get(entity) {
+        let center = Vec3::new(tile.position.x as f32, 0.02, tile.position.y as f32);
+        let flat = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+        gizmos.rect(Isometry3d::new(center, flat), Vec2::ONE, HIGHLIGHT_COLOR);
+    } else if let Ok((transform, aabb)) = bounds_q.get(entity) {
+        let (center, size) = aabb
+            .map(|aabb| (Vec3::from(aabb.center), Vec3::from(aabb.half_extents) * 2.0))
+            .unwrap_or((Vec3::ZERO, Vec3::ONE));
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+        let outline = Transform {
+            translation: translation + rotation * (center * scale),
+            rotation,
+            scale: scale * size * 1.05,
+        };
+        gizmos.cube(outline, HIGHLIGHT_COLOR);
+    }
+}
+
+/// Client system: shows [`HoverTarget`]'s click hints next to the cursor.
+///
+/// Spawns the hint label on first use; [`despawn_hover_hint`] removes it
+/// when the game state is left.
+pub(crate) fn update_hover_hint(
+    mut commands: Commands,
+    hover: Res<HoverTarget>,
+    input_map: Res<InputMap>,
+    theme: Res<UiTheme>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut hint_q: Query<(&mut Text, &mut Node, &mut Visibility), With<HoverHint>>,
+) {
+    let Ok((mut text, mut node, mut visibility)) = hint_q.single_mut() else {
+        commands.spawn((
+            Text::default(),
+            TextFont::from_font_size(theme.font_size_small),
+            TextColor(theme.text),
+            Node {
+                position_type: PositionType::Absolute,
+                padding: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(theme.background.with_alpha(0.8)),
+            Visibility::Hidden,
+            HoverHint,
+        ));
+        return;
+    };
+    let cursor = window_q.single().ok().and_then(|w| w.cursor_position());
+    let lines: Vec<String> = [
+        (Action::Interact, &hover.left),
+        (Action::ContextMenu, &hover.right),
+    ]
+    .into_iter()
+    .filter_map(|(action, hint)| {
+        let hint = hint.as_ref()?;
+        let binding = input_map.bindings(action).first()?;
+        Some(format!("{binding}: {hint}"))
+    })
+    .collect();
+    let Some(cursor) = cursor.filter(|_| !lines.is_empty()) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    visibility.set_if_neq(Visibility::Inherited);
+    let position = cursor + HINT_OFFSET;
+    node.left = Val::Px(position.x);
+    node.top = Val::Px(position.y);
+    if hover.is_changed() || input_map.is_changed() {
+        text.0 = lines.join("\n");
+    }
+}
+
+/// Removes the hint label and forgets the hover target on leaving the game
+/// state.
+pub(crate) fn despawn_hover_hint(
+    mut commands: Commands,
+    mut hover: ResMut<HoverTarget>,
+    hint_q: Query<Entity, With<HoverHint>>,
+) {
+    *hover = HoverTarget::default();
+    for hint in hint_q.iter() {
+        commands.entity(hint).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiles::FloorKind;
+
+    #[test]
+    fn click_hints_follow_target_and_hands() {
+        let hints = |kind, holding, in_range| click_hints(kind, holding, in_range, false);
+        assert_eq!(
+            hints(HoverKind::Item, false, true),
+            (Some("Pick up".into()), Some("Pick up".into()))
+        );
+        assert_eq!(hints(HoverKind::Item, false, false), (None, None));
+
+        let locker = HoverKind::Container {
+            name: "Locker",
+            has_items: true,
+        };
+        assert_eq!(
+            hints(locker, true, true).1.as_deref(),
+            Some("Store in Locker")
+        );
+        assert_eq!(
+            hints(locker, false, true).1.as_deref(),
+            Some("Take from Locker")
+        );
+
+        let floor = HoverKind::Tile(LayerTile::Floor(FloorKind::Plating));
+        assert_eq!(hints(floor, true, true).1.as_deref(), Some("Drop"));
+        assert_eq!(hints(floor, true, false).1.as_deref(), Some("Build Wall"));
+        let door = HoverKind::Tile(LayerTile::Structure(TileKind::Door { open: true }));
+        assert_eq!(hints(door, false, true).1.as_deref(), Some("Close Door"));
+
+        assert_eq!(
+            click_hints(HoverKind::Other, false, true, true),
+            (None, Some("Drag".into()))
+        );
+    }
+}
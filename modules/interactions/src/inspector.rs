@@ -0,0 +1,337 @@
+//! Entity inspector: a debug panel describing the hovered thing.
+//!
+//! [`Action::ToggleInspector`] opens a panel that follows [`HoverTarget`]
+//! and lists the thing's [`NetId`], template kind, container contents,
+//! [`StashedPhysics`] and controlling client as the local world sees them.
+//! While open it also asks the server for its own [`EntitySnapshot`]
+//! through [`INSPECT_RPC`], so values that diverge between client and
+//! server are flagged side by side.
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use items::{Container, StashedPhysics};
+use network::{ClientId, ControlledByClient, NetId, RpcCall, RpcClient, RpcDef, RpcServer};
+use things::{NetIdIndex, ReplicationScope, Thing};
+use ui::UiTheme;
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::hover::HoverTarget;
+
+/// RPC through which the inspector asks the server for an
+/// [`EntitySnapshot`].  Its stream tags are derived from the name.
+pub const INSPECT_RPC: RpcDef = RpcDef::named("inspect");
+
+/// Seconds between server queries for the inspected thing.
+pub const INSPECT_REFRESH_SECS: f32 = 1.0;
+
+/// What one world knows about a replicated thing.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub struct EntitySnapshot {
+    pub net_id: NetId,
+    /// [`Thing`] template kind, if the entity is a thing.
+    pub kind: Option<u16>,
+    pub position: [f32; 3],
+    /// Slots of its [`Container`], if it has one.
+    pub contents: Option<Vec<Option<NetId>>>,
+    /// Gravity scale kept in [`StashedPhysics`] while the item is held or
+    /// stored.
+    pub stashed_gravity: Option<f32>,
+    /// Client whose input controls it.  Only servers know this.
+    pub controlled_by: Option<ClientId>,
+}
+
+/// Request of [`INSPECT_RPC`].
+#[derive(Debug, Clone, Copy, PartialEq, SchemaRead, SchemaWrite)]
+pub struct InspectRequest {
+    pub target: NetId,
+}
+
+/// Response of [`INSPECT_RPC`].
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub enum InspectResponse {
+    Found(EntitySnapshot),
+    /// The server has no entity with that [`NetId`], or it is not
+    /// replicated to the asking client.
+    Unknown,
+}
+
+/// State of the inspector panel.
+#[derive(Resource, Default)]
+pub struct EntityInspector {
+    pub open: bool,
+    /// The inspected entity: the last hovered one with a [`NetId`].
+    pub selected: Option<Entity>,
+    /// The server's latest answer for `selected`.
+    pub server: Option<InspectResponse>,
+    pending: Option<RpcCall<InspectResponse>>,
+    since_query: f32,
+}
+
+/// Marker for the inspector panel's text node.
+#[derive(Component)]
+pub(crate) struct InspectorPanel;
+
+/// Read access to everything an [`EntitySnapshot`] reports.
+#[allow(clippy::type_complexity)]
+#[derive(SystemParam)]
+pub struct InspectQuery<'w, 's> {
+    entities: Query<
+        'w,
+        's,
+        (
+            &'static NetId,
+            Option<&'static Thing>,
+            Option<&'static GlobalTransform>,
+            Option<&'static Container>,
+            Option<&'static StashedPhysics>,
+            Option<&'static ControlledByClient>,
+        ),
+    >,
+    net_ids: Query<'w, 's, &'static NetId>,
+}
+
+impl InspectQuery<'_, '_> {
+    /// Snapshot of `entity`, or `None` if it is not replicated.
+    pub fn snapshot(&self, entity: Entity) -> Option<EntitySnapshot> {
+        let (&net_id, thing, transform, container, stashed, controller) =
+            self.entities.get(entity).ok()?;
+        Some(EntitySnapshot {
+            net_id,
+            kind: thing.map(|t| t.kind),
+            position: transform.map_or([0.0; 3], |t| t.translation().to_array()),
+            contents: container.map(|c| {
+                c.slots
+                    .iter()
+                    .map(|slot| slot.and_then(|e| self.net_ids.get(e).ok().copied()))
+                    .collect()
+            }),
+            stashed_gravity: stashed.map(|s| s.gravity.0),
+            controlled_by: controller.map(|c| c.0),
+        })
+    }
+}
+
+/// Panel lines for a `local` snapshot next to the `server`'s answer.  Lines
+/// whose values differ are marked with `!`; positions count as equal within
+/// a centimetre, since the client interpolates.
+pub fn inspector_lines(local: &EntitySnapshot, server: Option<&InspectResponse>) -> Vec<String> {
+    let server = match server {
+        Some(InspectResponse::Found(snapshot)) => Some(snapshot),
+        Some(InspectResponse::Unknown) => {
+            return vec![
+                format!("{:?}", local.net_id),
+                "server: no such entity".into(),
+            ];
+        }
+        None => None,
+    };
+    let row = |label: &str, local: String, server: Option<String>, same: bool| match server {
+        Some(server) if !same => format!("! {label}: {local} | server: {server}"),
+        _ => format!("  {label}: {local}"),
+    };
+    let position_same = server.is_none_or(|s| {
+        Vec3::from_array(s.position).distance(Vec3::from_array(local.position)) < 0.01
+    });
+    vec![
+        format!("{:?}", local.net_id),
+        row(
+            "kind",
+            format!("{:?}", local.kind),
+            server.map(|s| format!("{:?}", s.kind)),
+            server.is_none_or(|s| s.kind == local.kind),
+        ),
+        row(
+            "position",
+            format!("{:.2?}", local.position),
+            server.map(|s| format!("{:.2?}", s.position)),
+            position_same,
+        ),
+        row(
+            "contents",
+            format!("{:?}", local.contents),
+            server.map(|s| format!("{:?}", s.contents)),
+            server.is_none_or(|s| s.contents == local.contents),
+        ),
+        row(
+            "stashed gravity",
+            format!("{:?}", local.stashed_gravity),
+            server.map(|s| format!("{:?}", s.stashed_gravity)),
+            server.is_none_or(|s| s.stashed_gravity == local.stashed_gravity),
+        ),
+        // Clients never know the controller, so only the server's is shown.
+        format!(
+            "  controlled by: {:?}",
+            server.map_or(local.controlled_by, |s| s.controlled_by)
+        ),
+    ]
+}
+
+/// Opens and closes the inspector on [`Action::ToggleInspector`].
+pub(crate) fn toggle_inspector(input: ActionInput, mut inspector: ResMut<EntityInspector>) {
+    if input.just_pressed(Action::ToggleInspector) {
+        inspector.open = !inspector.open;
+        if !inspector.open {
+            *inspector = EntityInspector::default();
+        }
+    }
+}
+
+/// Selects the hovered thing and keeps the server's snapshot of it fresh.
+pub(crate) fn query_inspected_entity(
+    time: Res<Time>,
+    hover: Res<HoverTarget>,
+    mut inspector: ResMut<EntityInspector>,
+    net_ids: Query<&NetId>,
+    mut rpc: Option<ResMut<RpcClient<InspectRequest, InspectResponse>>>,
+) {
+    if !inspector.open {
+        return;
+    }
+    if let Some((entity, _)) = hover.hit
+        && net_ids.contains(entity)
+        && inspector.selected != Some(entity)
+    {
+        inspector.selected = Some(entity);
+        inspector.server = None;
+        inspector.pending = None;
+        inspector.since_query = INSPECT_REFRESH_SECS;
+    }
+
+    if let Some(call) = inspector.pending.take() {
+        match call.try_take() {
+            None => {
+                inspector.pending = Some(call);
+                return;
+            }
+            Some(Ok(response)) => inspector.server = Some(response),
+            Some(Err(e)) => debug!("Inspect call {:?} abandoned: {}", call.id(), e),
+        }
+    }
+
+    inspector.since_query += time.delta_secs();
+    let Some(target) = inspector.selected.and_then(|e| net_ids.get(e).ok()) else {
+        return;
+    };
+    let Some(rpc) = rpc.as_mut() else {
+        return;
+    };
+    if inspector.since_query < INSPECT_REFRESH_SECS {
+        return;
+    }
+    inspector.since_query = 0.0;
+    match rpc.call(&InspectRequest { target: *target }) {
+        Ok(call) => inspector.pending = Some(call),
+        Err(e) => error!("Failed to send InspectRequest to server: {}", e),
+    }
+}
+
+/// Shows the inspector panel in the top-right corner while it is open.
+pub(crate) fn update_inspector_panel(
+    mut commands: Commands,
+    inspector: Res<EntityInspector>,
+    inspect: InspectQuery,
+    theme: Res<UiTheme>,
+    mut panel_q: Query<(&mut Text, &mut Visibility), With<InspectorPanel>>,
+) {
+    let Ok((mut text, mut visibility)) = panel_q.single_mut() else {
+        commands.spawn((
+            Text::default(),
+            TextFont::from_font_size(theme.font_size_small),
+            TextColor(theme.text),
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                top: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(theme.background.with_alpha(0.8)),
+            Visibility::Hidden,
+            InspectorPanel,
+        ));
+        return;
+    };
+    if !inspector.open {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+    let lines = match inspector.selected.and_then(|e| inspect.snapshot(e)) {
+        Some(local) => inspector_lines(&local, inspector.server.as_ref()),
+        None => vec!["Hover a thing to inspect it".into()],
+    };
+    let lines = lines.join("\n");
+    if text.0 != lines {
+        text.0 = lines;
+    }
+}
+
+/// Server-side system that answers [`INSPECT_RPC`] calls with the
+/// authoritative [`EntitySnapshot`].  Things hidden from the caller by
+/// [`ReplicationScope`] are answered as unknown.
+pub(crate) fn serve_inspect_queries(
+    mut rpc: ResMut<RpcServer<InspectRequest, InspectResponse>>,
+    net_id_index: Res<NetIdIndex>,
+    scope: ReplicationScope,
+    inspect: InspectQuery,
+) {
+    for call in rpc.drain() {
+        let response = net_id_index
+            .0
+            .get(&call.request.target)
+            .filter(|&&entity| scope.is_visible_to(entity, call.from))
+            .and_then(|&entity| inspect.snapshot(entity))
+            .map_or(InspectResponse::Unknown, InspectResponse::Found);
+        if let Err(e) = rpc.respond(call.from, call.id, &response) {
+            error!("Failed to answer Inspect from {:?}: {}", call.from, e);
+        }
+    }
+}
+
+/// Closes the inspector and removes its panel on leaving the game state.
+pub(crate) fn clear_inspector(
+    mut commands: Commands,
+    mut inspector: ResMut<EntityInspector>,
+    panel_q: Query<Entity, With<InspectorPanel>>,
+) {
+    *inspector = EntityInspector::default();
+    for panel in panel_q.iter() {
+        commands.entity(panel).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> EntitySnapshot {
+        EntitySnapshot {
+            net_id: NetId(7),
+            kind: Some(3),
+            position: [1.0, 0.5, 2.0],
+            contents: Some(vec![None, Some(NetId(9))]),
+            stashed_gravity: None,
+            controlled_by: None,
+        }
+    }
+
+    #[test]
+    fn inspector_lines_flag_divergent_values_only() {
+        let local = snapshot();
+        let server = InspectResponse::Found(EntitySnapshot {
+            position: [1.001, 0.5, 2.0],
+            contents: Some(vec![Some(NetId(9)), None]),
+            controlled_by: Some(ClientId(4)),
+            ..snapshot()
+        });
+        let lines = inspector_lines(&local, Some(&server));
+        let flagged: Vec<&String> = lines.iter().filter(|l| l.starts_with('!')).collect();
+        assert_eq!(flagged.len(), 1);
+        assert!(flagged[0].starts_with("! contents"));
+        assert_eq!(lines.last().unwrap(), "  controlled by: Some(ClientId(4))");
+
+        let unknown = inspector_lines(&local, Some(&InspectResponse::Unknown));
+        assert_eq!(unknown[1], "server: no such entity");
+    }
+}
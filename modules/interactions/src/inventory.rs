@@ -0,0 +1,820 @@
+//! Inventory window: the slots of an open container next to the local
+//! player's hands.
+//!
+//! Choosing "Open {name}" on a container opens the window on it; pressing
+//! [`Action::ToggleInventory`] shows the hands alone.  The window is rebuilt from the
+//! replicated [`Container`] state whenever it changes, so it follows the
+//! server's [`items::ItemEvent`]s live.
+//!
+//! Items are moved with the mouse.  Clicking a container item takes it;
+//! clicking a held item stores it in the open container.  An item can also
+//! be dragged: onto the hands to take it, onto the container to store it,
+//! or out of the window to drop it where the cursor meets the floor.  A
+//! moved item is shown ghosted until the server confirms the move with an
+//! item event; if none arrives within [`ROLLBACK_AFTER`] seconds the server
+//! refused it and the slot is restored.
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use bevy::window::PrimaryWindow;
+use input::{Action, ActionInput, ViewCamera};
+use items::{Container, CustomLabel, InteractionRange, ItemCatalog, Stack};
+use network::NetId;
+use things::{DisplayName, HandSlot, NetIdIndex, PlayerControlled, Thing, ThingRegistry};
+use ui::{UiTheme, build_button};
+
+use crate::hover::floor_point;
+use crate::{ContextMenuAction, InteractionRequest};
+
+/// Seconds to wait for the server to confirm a move before restoring the
+/// item to its slot.
+pub const ROLLBACK_AFTER: f32 = 2.0;
+
+/// Columns of the container slot grid.
+const SLOT_COLUMNS: u16 = 4;
+
+/// Distance in logical pixels the cursor must travel before a press on a
+/// slot becomes a drag.
+const DRAG_THRESHOLD: f32 = 6.0;
+
+/// Side of an item icon in a slot button, in logical pixels.
+const ICON_SIZE: f32 = 20.0;
+
+/// What the inventory window shows.  Closed by default.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct InventoryView {
+    pub open: bool,
+    /// Container shown above the hands, or `None` for the hands alone.
+    pub container: Option<Entity>,
+}
+
+/// Root node of the inventory window while it is on screen.
+#[derive(Resource)]
+pub(crate) struct InventoryWindow(Entity);
+
+/// Toggles the hands view on [`Action::ToggleInventory`] and closes the
+/// window on [`Action::Cancel`].
+pub(crate) fn toggle_inventory(input: ActionInput, mut view: ResMut<InventoryView>) {
+    if input.just_pressed(Action::Cancel) && view.open {
+        *view = InventoryView::default();
+    } else if input.just_pressed(Action::ToggleInventory) {
+        *view = if view.open && view.container.is_none() {
+            InventoryView::default()
+        } else {
+            InventoryView {
+                open: true,
+                container: None,
+            }
+        };
+    }
+}
+
+/// Opens the window on the container picked with
+/// [`ContextMenuAction::OpenContainer`].
+pub(crate) fn open_container_from_menu(
+    mut actions: MessageReader<ContextMenuAction>,
+    net_id_index: Res<NetIdIndex>,
+    mut view: ResMut<InventoryView>,
+) {
+    for action in actions.read() {
+        if let ContextMenuAction::OpenContainer { container } = *action
+            && let Some(&entity) = net_id_index.0.get(&container)
+        {
+            *view = InventoryView {
+                open: true,
+                container: Some(entity),
+            };
+        }
+    }
+}
+
+/// Closes the window when its container is gone or the player has walked
+/// out of [`InteractionRange`] of it.
+pub(crate) fn close_distant_container(
+    mut view: ResMut<InventoryView>,
+    range: Res<InteractionRange>,
+    player_q: Query<&GlobalTransform, With<PlayerControlled>>,
+    container_q: Query<&GlobalTransform, With<Container>>,
+) {
+    let Some(container) = view.container else {
+        return;
+    };
+    let in_range = match (player_q.single(), container_q.get(container)) {
+        (Ok(player), Ok(target)) => player.translation().distance(target.translation()) <= range.0,
+        _ => false,
+    };
+    if !in_range {
+        *view = InventoryView::default();
+    }
+}
+
+/// Which part of the window a slot belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SlotSection {
+    Container,
+    Hands,
+}
+
+/// A slot of the inventory window, as a click or drag source and as a drop
+/// target.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct InventorySlot {
+    /// Item in the slot, if any.
+    pub(crate) item: Option<Entity>,
+    /// Container holding the slot: the open container or a hand.
+    pub(crate) source: Entity,
+    pub(crate) section: SlotSection,
+}
+
+/// An item picked up with the mouse, from press until release.
+#[derive(Resource)]
+pub(crate) struct ItemDrag {
+    slot: InventorySlot,
+    press_pos: Vec2,
+    /// Label following the cursor, spawned once the press becomes a drag.
+    ghost: Option<Entity>,
+}
+
+/// A move sent to the server and not yet confirmed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PendingMove {
+    pub(crate) item: Entity,
+    /// Container the item is leaving.
+    pub(crate) source: Entity,
+    /// Elapsed time when the move was sent.
+    pub(crate) sent_at: f32,
+}
+
+/// Moves awaiting confirmation; their items are drawn ghosted.
+#[derive(Resource, Debug, Default)]
+pub(crate) struct PendingItemMoves(pub(crate) Vec<PendingMove>);
+
+/// Where a press on a slot was released.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum DropTarget {
+    /// On a slot of the window (possibly the one pressed).
+    Slot(InventorySlot),
+    /// Over the window but not on a slot.
+    Window,
+    /// Outside the window, on the floor at the given point.
+    World(Vec3),
+    /// Outside the window where the floor cannot be hit.
+    Nowhere,
+}
+
+/// The request for releasing a press on `from` over `target`, if any.
+///
+/// `container` is the open container's [`NetId`] and `net_id` resolves
+/// entities to theirs.
+pub(crate) fn release_request(
+    from: InventorySlot,
+    target: DropTarget,
+    container: Option<NetId>,
+    net_id: impl Fn(Entity) -> Option<NetId>,
+) -> Option<InteractionRequest> {
+    let item = net_id(from.item?)?;
+    let on_section = |section| matches!(target, DropTarget::Slot(slot) if slot.section == section);
+    match from.section {
+        SlotSection::Container => {
+            let clicked = target == DropTarget::Slot(from);
+            (clicked || on_section(SlotSection::Hands)).then_some(
+                InteractionRequest::TakeFromContainer {
+                    item,
+                    container: container?,
+                },
+            )
+        }
+        SlotSection::Hands => match target {
+            DropTarget::World(position) => Some(InteractionRequest::ItemDrop {
+                item,
+                drop_position: position.to_array(),
+            }),
+            DropTarget::Slot(slot) if slot == from || slot.section == SlotSection::Container => {
+                Some(InteractionRequest::StoreInContainer {
+                    item,
+                    container: container?,
+                })
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Names and icons of items.
+#[allow(clippy::type_complexity)]
+#[derive(SystemParam)]
+pub(crate) struct ItemNames<'w, 's> {
+    names: Query<
+        'w,
+        's,
+        (
+            Option<&'static CustomLabel>,
+            Option<&'static DisplayName>,
+            Option<&'static Thing>,
+            Option<&'static Stack>,
+        ),
+    >,
+    registry: Res<'w, ThingRegistry>,
+    catalog: Res<'w, ItemCatalog>,
+    asset_server: Option<Res<'w, AssetServer>>,
+}
+
+impl ItemNames<'_, '_> {
+    /// Label for an item slot: its custom label, display name, catalog name
+    /// or template name, with the stack count when there is more than one.
+    fn label(&self, item: Entity) -> String {
+        let Ok((custom, display_name, thing, stack)) = self.names.get(item) else {
+            return "?".to_string();
+        };
+        let name = custom
+            .map(|l| l.0.as_str())
+            .or(display_name.map(|n| n.0.as_str()))
+            .or_else(|| thing.and_then(|t| self.catalog.display_name(t.kind)))
+            .or_else(|| thing.and_then(|t| self.registry.name_by_kind(t.kind)))
+            .unwrap_or("item");
+        match stack {
+            Some(stack) if stack.count > 1 => format!("{name} x{}", stack.count),
+            _ => name.to_string(),
+        }
+    }
+
+    /// The item's icon from the [`ItemCatalog`], if its kind has one.
+    fn icon(&self, item: Entity) -> Option<Handle<Image>> {
+        let (_, _, thing, _) = self.names.get(item).ok()?;
+        let path = self.catalog.get(thing?.kind)?.icon.clone()?;
+        Some(self.asset_server.as_ref()?.load(path))
+    }
+}
+
+/// Cell for a slot that cannot be picked up: an empty slot or an item
+/// whose move is pending.
+fn slot_cell(
+    commands: &mut Commands,
+    theme: &UiTheme,
+    label: &str,
+    slot: Option<InventorySlot>,
+) -> Entity {
+    let mut cell = commands.spawn((
+        Node {
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            padding: theme.button_padding,
+            ..default()
+        },
+        BackgroundColor(theme.surface),
+    ));
+    cell.with_child((
+        Text::new(label),
+        TextFont::from_font_size(theme.font_size_body),
+        TextColor(theme.text_muted),
+    ));
+    if let Some(slot) = slot {
+        cell.insert((slot, RelativeCursorPosition::default()));
+    }
+    cell.id()
+}
+
+/// Button for a slot holding an item that can be clicked or dragged, with
+/// the item's icon next to its label.
+fn item_button(
+    commands: &mut Commands,
+    theme: &UiTheme,
+    label: &str,
+    icon: Option<Handle<Image>>,
+    slot: InventorySlot,
+) -> Entity {
+    let button = build_button(theme).with_text(label).build(commands);
+    let mut button_commands = commands.entity(button);
+    button_commands.insert((slot, RelativeCursorPosition::default()));
+    if let Some(icon) = icon {
+        button_commands.with_child((
+            ImageNode::new(icon),
+            Node {
+                width: Val::Px(ICON_SIZE),
+                height: Val::Px(ICON_SIZE),
+                ..default()
+            },
+        ));
+    }
+    button
+}
+
+fn section_title(commands: &mut Commands, theme: &UiTheme, title: &str) -> Entity {
+    commands
+        .spawn((
+            Text::new(title),
+            TextFont::from_font_size(theme.font_size_small),
+            TextColor(theme.text_muted),
+        ))
+        .id()
+}
+
+fn slot_grid(commands: &mut Commands, slots: &[Entity]) -> Entity {
+    commands
+        .spawn(Node {
+            display: Display::Grid,
+            grid_template_columns: RepeatedGridTrack::flex(SLOT_COLUMNS, 1.0),
+            column_gap: Val::Px(4.0),
+            row_gap: Val::Px(4.0),
+            ..default()
+        })
+        .add_children(slots)
+        .id()
+}
+
+/// Rebuilds the inventory window when [`InventoryView`], any container or
+/// stack, or the pending moves change, and removes it when the view is
+/// closed.
+///
+/// Every item gets a button that can be clicked or dragged, except items
+/// with a pending move, which are drawn as muted cells.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn rebuild_inventory_window(
+    mut commands: Commands,
+    view: Res<InventoryView>,
+    pending: Res<PendingItemMoves>,
+    window: Option<Res<InventoryWindow>>,
+    theme: Res<UiTheme>,
+    item_names: ItemNames,
+    changed_containers: Query<(), Changed<Container>>,
+    changed_stacks: Query<(), Changed<Stack>>,
+    changed_labels: Query<(), Changed<CustomLabel>>,
+    mut removed_labels: RemovedComponents<CustomLabel>,
+    containers: Query<(&Container, Option<&CustomLabel>, Option<&DisplayName>), Without<HandSlot>>,
+    player_q: Query<&Children, With<PlayerControlled>>,
+    hands: Query<&Container, With<HandSlot>>,
+) {
+    let labels_removed = removed_labels.read().count() > 0;
+    if !view.is_changed()
+        && !pending.is_changed()
+        && changed_containers.is_empty()
+        && changed_stacks.is_empty()
+        && changed_labels.is_empty()
+        && !labels_removed
+    {
+        return;
+    }
+    if let Some(window) = window {
+        commands.entity(window.0).despawn();
+        commands.remove_resource::<InventoryWindow>();
+    }
+    if !view.open {
+        return;
+    }
+
+    let is_pending = |item: Entity| pending.0.iter().any(|m| m.item == item);
+    let slot_for = |commands: &mut Commands, label: String, slot: InventorySlot| {
+        if let Some(item) = slot.item.filter(|&item| !is_pending(item)) {
+            item_button(commands, &theme, &label, item_names.icon(item), slot)
+        } else {
+            slot_cell(commands, &theme, &label, Some(slot))
+        }
+    };
+
+    let mut sections = Vec::new();
+    if let Some((source, (container, custom, display_name))) = view
+        .container
+        .and_then(|entity| Some((entity, containers.get(entity).ok()?)))
+    {
+        let title = custom
+            .map(|l| l.0.as_str())
+            .or(display_name.map(|n| n.0.as_str()))
+            .unwrap_or("Container");
+        sections.push(section_title(&mut commands, &theme, title));
+        let slots: Vec<Entity> = container
+            .slots
+            .iter()
+            .map(|&item| {
+                let label = item
+                    .map(|item| item_names.label(item))
+                    .unwrap_or_else(|| "-".to_string());
+                let slot = InventorySlot {
+                    item,
+                    source,
+                    section: SlotSection::Container,
+                };
+                slot_for(&mut commands, label, slot)
+            })
+            .collect();
+        sections.push(slot_grid(&mut commands, &slots));
+    }
+
+    sections.push(section_title(&mut commands, &theme, "Hands"));
+    let mut slots = Vec::new();
+    if let Ok(children) = player_q.single() {
+        for hand in children.iter() {
+            let Ok(container) = hands.get(hand) else {
+                continue;
+            };
+            for &item in container.slots.iter().flatten() {
+                let slot = InventorySlot {
+                    item: Some(item),
+                    source: hand,
+                    section: SlotSection::Hands,
+                };
+                let label = item_names.label(item);
+                slots.push(slot_for(&mut commands, label, slot));
+            }
+            // A hand is also where container items are dragged to.
+            if container.has_space() {
+                let slot = InventorySlot {
+                    item: None,
+                    source: hand,
+                    section: SlotSection::Hands,
+                };
+                slots.push(slot_for(&mut commands, "Empty".to_string(), slot));
+            }
+        }
+    }
+    if slots.is_empty() {
+        slots.push(slot_cell(&mut commands, &theme, "Empty", None));
+    }
+    sections.push(slot_grid(&mut commands, &slots));
+
+    let root = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(16.0),
+                top: Val::Px(16.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(theme.background),
+            RelativeCursorPosition::default(),
+        ))
+        .add_children(&sections)
+        .id();
+    commands.insert_resource(InventoryWindow(root));
+}
+
+/// Starts an [`ItemDrag`] when [`Action::Interact`] is pressed on a slot with
+/// an item that is not already moving.
+pub(crate) fn start_item_drag(
+    mut commands: Commands,
+    input: ActionInput,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    slots: Query<(&InventorySlot, &RelativeCursorPosition)>,
+    pending: Res<PendingItemMoves>,
+    drag: Option<Res<ItemDrag>>,
+) {
+    if drag.is_some() || !input.just_pressed(Action::Interact) {
+        return;
+    }
+    let Some(cursor) = window_q.single().ok().and_then(|w| w.cursor_position()) else {
+        return;
+    };
+    let Some(slot) = slots
+        .iter()
+        .find(|(_, pos)| pos.cursor_over())
+        .map(|(slot, _)| *slot)
+    else {
+        return;
+    };
+    if slot
+        .item
+        .is_some_and(|item| !pending.0.iter().any(|m| m.item == item))
+    {
+        commands.insert_resource(ItemDrag {
+            slot,
+            press_pos: cursor,
+            ghost: None,
+        });
+    }
+}
+
+/// Spawns the ghost label once the cursor has moved far enough from the
+/// press, then keeps it under the cursor.
+pub(crate) fn update_item_drag(
+    mut commands: Commands,
+    drag: Option<ResMut<ItemDrag>>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut nodes: Query<&mut Node>,
+    theme: Res<UiTheme>,
+    item_names: ItemNames,
+) {
+    let Some(mut drag) = drag else {
+        return;
+    };
+    let Some(cursor) = window_q.single().ok().and_then(|w| w.cursor_position()) else {
+        return;
+    };
+    match drag.ghost {
+        Some(ghost) => {
+            if let Ok(mut node) = nodes.get_mut(ghost) {
+                node.left = Val::Px(cursor.x);
+                node.top = Val::Px(cursor.y);
+            }
+        }
+        None if cursor.distance(drag.press_pos) >= DRAG_THRESHOLD => {
+            let label = drag
+                .slot
+                .item
+                .map(|item| item_names.label(item))
+                .unwrap_or_default();
+            let ghost = commands
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(cursor.x),
+                        top: Val::Px(cursor.y),
+                        padding: UiRect::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    BackgroundColor(theme.primary.with_alpha(0.6)),
+                    GlobalZIndex(i32::MAX),
+                ))
+                .with_child((
+                    Text::new(label),
+                    TextFont::from_font_size(theme.font_size_small),
+                    TextColor(theme.text),
+                ))
+                .id();
+            drag.ghost = Some(ghost);
+        }
+        None => {}
+    }
+}
+
+/// Ends the [`ItemDrag`] on release: writes the [`InteractionRequest`]
+/// chosen by [`release_request`] and marks the item as pending.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn finish_item_drag(
+    mut commands: Commands,
+    input: ActionInput,
+    drag: Option<Res<ItemDrag>>,
+    view: Res<InventoryView>,
+    time: Res<Time>,
+    window: Option<Res<InventoryWindow>>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    view_camera: ViewCamera,
+    hover_q: Query<&RelativeCursorPosition>,
+    slots: Query<(&InventorySlot, &RelativeCursorPosition)>,
+    net_ids: Query<&NetId>,
+    mut pending: ResMut<PendingItemMoves>,
+    mut requests: MessageWriter<InteractionRequest>,
+) {
+    let Some(drag) = drag else {
+        return;
+    };
+    if view.open && !input.just_released(Action::Interact) {
+        return;
+    }
+    commands.remove_resource::<ItemDrag>();
+    if let Some(ghost) = drag.ghost {
+        commands.entity(ghost).despawn();
+    }
+    if !view.open {
+        return;
+    }
+
+    let over_window = window
+        .and_then(|w| hover_q.get(w.0).ok())
+        .is_some_and(|pos| pos.cursor_over());
+    let target = if let Some((slot, _)) = slots.iter().find(|(_, pos)| pos.cursor_over()) {
+        DropTarget::Slot(*slot)
+    } else if over_window {
+        DropTarget::Window
+    } else {
+        let cursor = window_q.single().ok().and_then(|w| w.cursor_position());
+        cursor
+            .zip(view_camera.get())
+            .and_then(|(cursor, (camera, cam_tf))| floor_point(camera, cam_tf, cursor))
+            .map_or(DropTarget::Nowhere, DropTarget::World)
+    };
+
+    let container = view.container.and_then(|e| net_ids.get(e).ok()).copied();
+    let request = release_request(drag.slot, target, container, |e| {
+        net_ids.get(e).ok().copied()
+    });
+    if let (Some(request), Some(item)) = (request, drag.slot.item) {
+        requests.write(request);
+        pending.0.push(PendingMove {
+            item,
+            source: drag.slot.source,
+            sent_at: time.elapsed_secs(),
+        });
+    }
+}
+
+/// Settles [`PendingItemMoves`]: a move is confirmed once its item has left
+/// the source container, and rolled back after [`ROLLBACK_AFTER`] seconds
+/// without that.
+pub(crate) fn settle_pending_moves(
+    time: Res<Time>,
+    mut pending: ResMut<PendingItemMoves>,
+    containers: Query<&Container>,
+) {
+    let now = time.elapsed_secs();
+    let before = pending.0.len();
+    pending.bypass_change_detection().0.retain(|m| {
+        let moved = containers.get(m.source).is_ok_and(|c| !c.contains(m.item));
+        let expired = now - m.sent_at >= ROLLBACK_AFTER;
+        if !moved && expired {
+            info!("Move of item {:?} was not confirmed; restoring it", m.item);
+        }
+        !moved && !expired
+    });
+    if pending.0.len() != before {
+        pending.set_changed();
+    }
+}
+
+/// Closes the window, drops any drag, and forgets its container on leaving
+/// the game state.
+pub(crate) fn clear_inventory(
+    mut commands: Commands,
+    mut view: ResMut<InventoryView>,
+    mut pending: ResMut<PendingItemMoves>,
+    window: Option<Res<InventoryWindow>>,
+    drag: Option<Res<ItemDrag>>,
+) {
+    *view = InventoryView::default();
+    pending.0.clear();
+    if let Some(window) = window {
+        commands.entity(window.0).despawn();
+        commands.remove_resource::<InventoryWindow>();
+    }
+    if let Some(drag) = drag {
+        if let Some(ghost) = drag.ghost {
+            commands.entity(ghost).despawn();
+        }
+        commands.remove_resource::<ItemDrag>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use input::InputMap;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_message::<ContextMenuAction>();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.init_resource::<InputMap>();
+        app.init_resource::<UiTheme>();
+        app.init_resource::<ThingRegistry>();
+        app.init_resource::<ItemCatalog>();
+        app.init_resource::<NetIdIndex>();
+        app.init_resource::<InventoryView>();
+        app.init_resource::<PendingItemMoves>();
+        app.add_systems(
+            Update,
+            (
+                toggle_inventory,
+                open_container_from_menu,
+                rebuild_inventory_window,
+            )
+                .chain(),
+        );
+        app
+    }
+
+    fn button_count(app: &mut App) -> usize {
+        let mut q = app.world_mut().query_filtered::<(), With<Button>>();
+        q.iter(app.world()).count()
+    }
+
+    #[test]
+    fn inventory_key_toggles_the_hands_window() {
+        let mut app = test_app();
+        app.update();
+        assert!(!app.world().contains_resource::<InventoryWindow>());
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyI);
+        app.update();
+        assert!(app.world().contains_resource::<InventoryWindow>());
+
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.release(KeyCode::KeyI);
+        keys.clear();
+        keys.press(KeyCode::KeyI);
+        app.update();
+        assert!(!app.world().contains_resource::<InventoryWindow>());
+    }
+
+    #[test]
+    fn opened_container_shows_a_button_per_item_and_updates_live() {
+        let mut app = test_app();
+        let first = app.world_mut().spawn(NetId(11)).id();
+        let second = app.world_mut().spawn(NetId(12)).id();
+        let mut container = Container::with_capacity(4);
+        container.insert(first);
+        let crate_entity = app.world_mut().spawn((NetId(10), container)).id();
+        app.world_mut()
+            .resource_mut::<NetIdIndex>()
+            .0
+            .insert(NetId(10), crate_entity);
+
+        app.world_mut()
+            .write_message(ContextMenuAction::OpenContainer {
+                container: NetId(10),
+            });
+        app.update();
+        assert_eq!(button_count(&mut app), 1);
+
+        app.world_mut()
+            .get_mut::<Container>(crate_entity)
+            .unwrap()
+            .insert(second);
+        app.update();
+        assert_eq!(button_count(&mut app), 2);
+    }
+
+    #[test]
+    fn releasing_a_slot_picks_the_matching_request() {
+        let mut world = World::new();
+        let item = world.spawn(NetId(1)).id();
+        let crate_entity = world.spawn(NetId(2)).id();
+        let hand = world.spawn_empty().id();
+        let net_id = |e: Entity| world.get::<NetId>(e).copied();
+        let stored = InventorySlot {
+            item: Some(item),
+            source: crate_entity,
+            section: SlotSection::Container,
+        };
+        let held = InventorySlot {
+            item: Some(item),
+            source: hand,
+            section: SlotSection::Hands,
+        };
+        let empty_hand = InventorySlot {
+            item: None,
+            source: hand,
+            section: SlotSection::Hands,
+        };
+        let container = Some(NetId(2));
+
+        // Clicking or dragging a stored item onto the hands takes it.
+        for target in [DropTarget::Slot(stored), DropTarget::Slot(empty_hand)] {
+            assert!(matches!(
+                release_request(stored, target, container, net_id),
+                Some(InteractionRequest::TakeFromContainer {
+                    item: NetId(1),
+                    container: NetId(2)
+                })
+            ));
+        }
+        // Stored items cannot be dropped straight into the world.
+        assert!(
+            release_request(stored, DropTarget::World(Vec3::ZERO), container, net_id).is_none()
+        );
+
+        // Clicking or dragging a held item onto the container stores it.
+        for target in [DropTarget::Slot(held), DropTarget::Slot(stored)] {
+            assert!(matches!(
+                release_request(held, target, container, net_id),
+                Some(InteractionRequest::StoreInContainer { .. })
+            ));
+        }
+        assert!(release_request(held, DropTarget::Slot(held), None, net_id).is_none());
+        // Dragging it out of the window drops it where the floor was hit.
+        assert!(matches!(
+            release_request(held, DropTarget::World(Vec3::X), None, net_id),
+            Some(InteractionRequest::ItemDrop { drop_position, .. }) if drop_position == [1.0, 0.0, 0.0]
+        ));
+        assert!(release_request(held, DropTarget::Window, container, net_id).is_none());
+    }
+
+    #[test]
+    fn unconfirmed_moves_roll_back_and_confirmed_ones_settle() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<PendingItemMoves>();
+        app.add_systems(Update, settle_pending_moves);
+
+        let kept = app.world_mut().spawn_empty().id();
+        let moved = app.world_mut().spawn_empty().id();
+        let mut container = Container::with_capacity(2);
+        container.insert(kept);
+        let source = app.world_mut().spawn(container).id();
+        app.world_mut().resource_mut::<PendingItemMoves>().0 = vec![
+            PendingMove {
+                item: kept,
+                source,
+                sent_at: 0.0,
+            },
+            PendingMove {
+                item: moved,
+                source,
+                sent_at: 0.0,
+            },
+        ];
+
+        app.update();
+        let pending = &app.world().resource::<PendingItemMoves>().0;
+        assert_eq!(pending.len(), 1, "the move that left its container settles");
+        assert_eq!(pending[0].item, kept);
+
+        app.world_mut().resource_mut::<PendingItemMoves>().0[0].sent_at = -ROLLBACK_AFTER;
+        app.update();
+        assert!(app.world().resource::<PendingItemMoves>().0.is_empty());
+    }
+}
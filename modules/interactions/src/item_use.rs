@@ -0,0 +1,51 @@
+//! Context-menu entry for using the held item.
+//!
+//! While the player holds an item with a registered use (see
+//! [`ItemUseRegistry`]), every context menu offers it, e.g. "Drink Can".
+//! Choosing it sends [`InteractionRequest::UseItem`]; the server runs the
+//! use if the player still holds the item.
+
+use bevy::prelude::*;
+use items::{ItemCatalog, ItemUseRegistry};
+use things::{NetIdIndex, Thing};
+
+use crate::{ContextEntry, ContextTarget, InteractionRequest};
+
+/// [`ContextActionProvider`](crate::ContextActionProvider) offering the use
+/// of the held item.
+pub(crate) fn item_use_context_actions(
+    world: &World,
+    target: &ContextTarget,
+    entries: &mut Vec<ContextEntry>,
+) {
+    let (Some(registry), Some(net_id_index), Some(held)) = (
+        world.get_resource::<ItemUseRegistry>(),
+        world.get_resource::<NetIdIndex>(),
+        target.holding,
+    ) else {
+        return;
+    };
+    let Some(item) = net_id_index
+        .0
+        .get(&held)
+        .and_then(|&item| world.get_entity(item).ok())
+    else {
+        return;
+    };
+    let Some(verb) = registry.label(item) else {
+        return;
+    };
+    let name = item.get::<Thing>().and_then(|thing| {
+        world
+            .get_resource::<ItemCatalog>()?
+            .display_name(thing.kind)
+    });
+    entries.push(ContextEntry {
+        label: match name {
+            Some(name) => format!("{verb} {name}"),
+            None => verb.to_string(),
+        },
+        request: InteractionRequest::UseItem { item: held },
+        available: true,
+    });
+}
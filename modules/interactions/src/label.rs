@@ -0,0 +1,291 @@
+//! Writing [`CustomLabel`]s on things with a held [`Labeler`].
+//!
+//! "Label" in the context menu of an item or container, offered while the
+//! local player holds a labeler, opens a prompt at the top of the screen.
+//! Typing there goes to the label instead of the key bindings; Enter sends
+//! the text through [`LABEL_RPC`] and Escape cancels.  The server checks
+//! the actor, tool, range and text (see [`validate_label`]) before writing
+//! the label, which the items stream then replicates to every client.
+
+use bevy::ecs::system::SystemParam;
+use bevy::input::ButtonState;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use items::{
+    Container, CustomLabel, InteractionRange, Item, Labeler, MAX_LABEL_LEN, validate_label,
+};
+use network::{ClientId, NetId, RpcCall, RpcClient, RpcDef, RpcServer};
+use things::{HandSlot, NetIdIndex};
+use ui::UiTheme;
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{ActionRejection, ActorQuery, ContextMenuAction, RequestLog, resolve_actor};
+
+/// RPC through which clients write labels.  Its stream tags are derived
+/// from the name.
+pub const LABEL_RPC: RpcDef = RpcDef::named("label");
+
+/// Request of [`LABEL_RPC`]: label `target` with `text`, or clear its label
+/// when `text` is blank.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub struct LabelRequest {
+    pub target: NetId,
+    pub text: String,
+}
+
+/// Response of [`LABEL_RPC`].
+#[derive(Debug, Clone, Copy, PartialEq, SchemaRead, SchemaWrite)]
+pub enum LabelResponse {
+    Applied,
+    Rejected { reason: ActionRejection },
+}
+
+/// The label being typed on the client, and the calls that sent earlier
+/// ones.
+#[derive(Resource, Default)]
+pub struct LabelPrompt {
+    /// Thing being labelled, or `None` while the prompt is closed.
+    pub target: Option<NetId>,
+    pub text: String,
+    pending: Vec<RpcCall<LabelResponse>>,
+}
+
+/// Marker for the prompt's text node.
+#[derive(Component)]
+pub(crate) struct LabelPromptPanel;
+
+/// Opens the prompt on "Label", starting from the target's current label.
+pub(crate) fn open_label_prompt(
+    mut actions: MessageReader<ContextMenuAction>,
+    mut prompt: ResMut<LabelPrompt>,
+    net_id_index: Res<NetIdIndex>,
+    labels: Query<&CustomLabel>,
+) {
+    for action in actions.read() {
+        if let ContextMenuAction::Label { target } = *action {
+            prompt.target = Some(target);
+            prompt.text = net_id_index
+                .0
+                .get(&target)
+                .and_then(|&entity| labels.get(entity).ok())
+                .map(|label| label.0.clone())
+                .unwrap_or_default();
+        }
+    }
+}
+
+/// Types into the open prompt and sends it on Enter.
+///
+/// Runs in `PreUpdate` after input is collected, and releases every key
+/// while the prompt is open so that typing does not also move the player
+/// or trigger other actions.
+pub(crate) fn type_label(
+    mut prompt: ResMut<LabelPrompt>,
+    mut typed: MessageReader<KeyboardInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut rpc: Option<ResMut<RpcClient<LabelRequest, LabelResponse>>>,
+) {
+    let Some(target) = prompt.target else {
+        typed.clear();
+        return;
+    };
+    keys.reset_all();
+    for input in typed.read() {
+        if input.state != ButtonState::Pressed {
+            continue;
+        }
+        match &input.logical_key {
+            Key::Escape => {
+                prompt.target = None;
+                return;
+            }
+            Key::Enter => {
+                prompt.target = None;
+                let text = std::mem::take(&mut prompt.text);
+                let Some(rpc) = rpc.as_mut() else {
+                    return;
+                };
+                match rpc.call(&LabelRequest { target, text }) {
+                    Ok(call) => prompt.pending.push(call),
+                    Err(e) => error!("Failed to send LabelRequest to server: {}", e),
+                }
+                return;
+            }
+            Key::Backspace => {
+                prompt.text.pop();
+            }
+            _ => {
+                let Some(text) = &input.text else {
+                    continue;
+                };
+                for c in text.chars().filter(|c| !c.is_control()) {
+                    if prompt.text.chars().count() < MAX_LABEL_LEN {
+                        prompt.text.push(c);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Logs the outcome of finished label calls.
+pub(crate) fn report_label_results(mut prompt: ResMut<LabelPrompt>) {
+    prompt.pending.retain(|call| match call.try_take() {
+        None => true,
+        Some(Ok(LabelResponse::Rejected { reason })) => {
+            info!("Label refused by the server: {:?}", reason);
+            false
+        }
+        Some(Ok(LabelResponse::Applied)) => false,
+        Some(Err(e)) => {
+            debug!("Label call {:?} abandoned: {}", call.id(), e);
+            false
+        }
+    });
+}
+
+/// Shows the prompt at the top of the screen while it is open.
+pub(crate) fn update_label_prompt_panel(
+    mut commands: Commands,
+    prompt: Res<LabelPrompt>,
+    theme: Res<UiTheme>,
+    mut panel_q: Query<(&mut Text, &mut Visibility), With<LabelPromptPanel>>,
+) {
+    let Ok((mut text, mut visibility)) = panel_q.single_mut() else {
+        commands.spawn((
+            Text::default(),
+            TextFont::from_font_size(theme.font_size_body),
+            TextColor(theme.text),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(35.0),
+                top: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(theme.background.with_alpha(0.9)),
+            Visibility::Hidden,
+            LabelPromptPanel,
+        ));
+        return;
+    };
+    if prompt.target.is_none() {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+    let line = format!(
+        "Label: {}_\nEnter to write, Esc to cancel, blank to remove",
+        prompt.text
+    );
+    if text.0 != line {
+        text.0 = line;
+    }
+}
+
+/// Everything the server checks a [`LabelRequest`] against.
+#[allow(clippy::type_complexity)]
+#[derive(SystemParam)]
+pub(crate) struct LabelCheck<'w, 's> {
+    net_id_index: Res<'w, NetIdIndex>,
+    actor_query: ActorQuery<'w, 's>,
+    children: Query<'w, 's, &'static Children>,
+    hands: Query<'w, 's, &'static Container, With<HandSlot>>,
+    labelers: Query<'w, 's, (), With<Labeler>>,
+    targets: Query<'w, 's, Option<&'static CustomLabel>, Or<(With<Item>, With<Container>)>>,
+    transforms: Query<'w, 's, &'static GlobalTransform>,
+    range: Res<'w, InteractionRange>,
+}
+
+impl LabelCheck<'_, '_> {
+    /// The thing `client` may label as `request` asks, and the label to
+    /// give it (`None` to clear it).
+    fn check(
+        &self,
+        client: ClientId,
+        request: &LabelRequest,
+    ) -> Result<(Entity, Option<CustomLabel>), ActionRejection> {
+        let actor = resolve_actor(&self.actor_query, client).ok_or(ActionRejection::NoActor)?;
+        let target = self
+            .net_id_index
+            .0
+            .get(&request.target)
+            .copied()
+            .ok_or(ActionRejection::UnknownTarget)?;
+        let current = self
+            .targets
+            .get(target)
+            .map_err(|_| ActionRejection::UnknownTarget)?;
+        let holds_labeler = self.children.get(actor).is_ok_and(|children| {
+            children
+                .iter()
+                .filter_map(|child| self.hands.get(child).ok())
+                .flat_map(|hand| hand.slots.iter().flatten())
+                .any(|&item| self.labelers.contains(item))
+        });
+        if !holds_labeler {
+            return Err(ActionRejection::MissingTool);
+        }
+        let in_range = self
+            .transforms
+            .get(actor)
+            .ok()
+            .zip(self.transforms.get(target).ok())
+            .is_some_and(|(a, t)| a.translation().distance(t.translation()) <= self.range.0);
+        if !in_range {
+            return Err(ActionRejection::NotAdjacent);
+        }
+        let label = validate_label(&request.text)
+            .map_err(|error| ActionRejection::InvalidLabel { error })?;
+        if label.as_ref() == current {
+            return Err(ActionRejection::Unchanged);
+        }
+        Ok((target, label))
+    }
+}
+
+/// Server-side system that answers [`LABEL_RPC`] calls, writing or clearing
+/// the [`CustomLabel`] of the target when the actor holds a [`Labeler`]
+/// within [`InteractionRange`] of it.
+pub(crate) fn serve_label_requests(
+    mut commands: Commands,
+    mut rpc: ResMut<RpcServer<LabelRequest, LabelResponse>>,
+    check: LabelCheck,
+    mut log: RequestLog,
+) {
+    for call in rpc.drain() {
+        let from = call.from;
+        let scope = log.begin(from, "label");
+        let _entered = scope.span.enter();
+
+        let outcome = check.check(from, &call.request);
+        log.finish(&scope, &outcome);
+
+        let response = match outcome {
+            Ok((target, Some(label))) => {
+                commands.entity(target).insert(label);
+                LabelResponse::Applied
+            }
+            Ok((target, None)) => {
+                commands.entity(target).remove::<CustomLabel>();
+                LabelResponse::Applied
+            }
+            Err(reason) => LabelResponse::Rejected { reason },
+        };
+        if let Err(e) = rpc.respond(from, call.id, &response) {
+            error!(error = %e, "failed to answer the label request");
+        }
+    }
+}
+
+/// Closes the prompt and removes its panel on leaving the game state.
+pub(crate) fn clear_label_prompt(
+    mut commands: Commands,
+    mut prompt: ResMut<LabelPrompt>,
+    panel_q: Query<Entity, With<LabelPromptPanel>>,
+) {
+    *prompt = LabelPrompt::default();
+    for panel in panel_q.iter() {
+        commands.entity(panel).despawn();
+    }
+}
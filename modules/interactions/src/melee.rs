@@ -0,0 +1,338 @@
+//! Melee attacks with held items.
+//!
+//! Left-clicking while holding an item with [`MeleeStats`] sends
+//! [`InteractionRequest::Attack`](crate::InteractionRequest::Attack) with
+//! the thing clicked, or just the aim direction when a tile was clicked.
+//! The server checks the weapon's
+//! cooldown, then casts a ray of the weapon's reach from the attacker's
+//! hand: the first solid body it meets is hit, and must be the named target
+//! if there is one.  A hit writes [`Damage`] and pushes dynamic bodies back
+//! by [`MELEE_KNOCKBACK`].  Every swing, hit or miss, is broadcast on the
+//! melee stream, and clients turn it into a [`MeleeSwung`] message for
+//! animation and sound.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use items::{Container, MeleeStats};
+use network::{ModuleReadySent, NetId, PlayerEvent, StreamReader, StreamSender, stream_tag};
+use physics::{GameLayer, LinearVelocity, RigidBody, SpatialQuery};
+use things::{Damage, DamageKind, HAND_OFFSET, HandSlot, NetIdIndex};
+use wincode::{SchemaRead, SchemaWrite};
+
+/// Stream tag for the server→client melee swing stream.
+pub const MELEE_STREAM_TAG: u8 = stream_tag("melee");
+
+/// Speed in m/s a melee hit adds to a dynamic body, away from the attacker.
+pub const MELEE_KNOCKBACK: f32 = 1.5;
+
+/// Melee stream wire format.
+#[derive(Debug, Clone, Copy, PartialEq, SchemaRead, SchemaWrite)]
+pub enum MeleeStreamMessage {
+    /// `attacker` swung its weapon, hitting `target` if set.
+    Swing {
+        attacker: NetId,
+        target: Option<NetId>,
+    },
+}
+
+/// Client-side message: a creature swung its weapon, for animation and
+/// sound.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct MeleeSwung {
+    pub attacker: Entity,
+    pub target: Option<Entity>,
+}
+
+/// Server-side request: `actor` swings its weapon at `target`, or along
+/// `direction` when no target is named.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct MeleeAttackRequest {
+    pub actor: Entity,
+    pub target: Option<Entity>,
+    pub direction: Vec3,
+}
+
+/// Why a [`MeleeAttackRequest`] was dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MeleeRefusal {
+    NoWeapon,
+    CoolingDown,
+    NoAttacker,
+    NoAim,
+    OutOfReach,
+}
+
+/// When each attacker may swing again, in server time.
+#[derive(Resource, Debug, Default)]
+pub(crate) struct MeleeCooldowns(HashMap<Entity, Duration>);
+
+/// Everything a melee attack needs on the server.
+#[derive(SystemParam)]
+pub(crate) struct Melee<'w, 's> {
+    time: Res<'w, Time>,
+    cooldowns: ResMut<'w, MeleeCooldowns>,
+    spatial: SpatialQuery<'w, 's>,
+    children: Query<'w, 's, &'static Children>,
+    hands: Query<'w, 's, &'static Container, With<HandSlot>>,
+    weapons: Query<'w, 's, &'static MeleeStats>,
+    net_ids: Query<'w, 's, &'static NetId>,
+    bodies: Query<
+        'w,
+        's,
+        (
+            &'static GlobalTransform,
+            Option<&'static RigidBody>,
+            Option<&'static mut LinearVelocity>,
+        ),
+    >,
+    damage: MessageWriter<'w, Damage>,
+    sender: Option<Res<'w, StreamSender<MeleeStreamMessage>>>,
+}
+
+impl Melee<'_, '_> {
+    /// The [`MeleeStats`] of the first weapon `actor` holds in a hand.
+    fn weapon(&self, actor: Entity) -> Option<MeleeStats> {
+        let children = self.children.get(actor).ok()?;
+        children
+            .iter()
+            .filter_map(|child| self.hands.get(child).ok())
+            .flat_map(|hand| hand.slots.iter().flatten())
+            .find_map(|&item| self.weapons.get(item).ok().copied())
+    }
+
+    /// Swings the weapon of the requesting actor.  Returns what was hit.
+    fn attack(&mut self, request: &MeleeAttackRequest) -> Result<Option<Entity>, MeleeRefusal> {
+        let actor = request.actor;
+        let weapon = self.weapon(actor).ok_or(MeleeRefusal::NoWeapon)?;
+        let now = self.time.elapsed();
+        if self
+            .cooldowns
+            .0
+            .get(&actor)
+            .is_some_and(|&ready_at| now < ready_at)
+        {
+            return Err(MeleeRefusal::CoolingDown);
+        }
+        let position = |entity| self.bodies.get(entity).map(|(t, ..)| t.translation());
+        let origin =
+            position(actor).map_err(|_| MeleeRefusal::NoAttacker)? + Vec3::Y * HAND_OFFSET.y;
+        let aim = match request.target {
+            Some(target) => position(target).map_err(|_| MeleeRefusal::NoAim)? - origin,
+            None => request.direction,
+        };
+        let aim = Dir3::new(aim).map_err(|_| MeleeRefusal::NoAim)?;
+
+        let filter = GameLayer::solid_filter().with_excluded_entities([actor]);
+        let hit = self
+            .spatial
+            .cast_ray(origin, aim, weapon.reach, true, &filter)
+            .map(|hit| hit.entity);
+        if request.target.is_some() && hit != request.target {
+            return Err(MeleeRefusal::OutOfReach);
+        }
+        self.cooldowns
+            .0
+            .insert(actor, now + Duration::from_secs_f32(weapon.cooldown));
+
+        if let Some(hit) = hit {
+            self.damage.write(Damage {
+                target: hit,
+                amount: weapon.damage,
+                kind: DamageKind::Brute,
+            });
+            if let Ok((_, Some(&RigidBody::Dynamic), Some(mut velocity))) = self.bodies.get_mut(hit)
+            {
+                velocity.0 += aim * MELEE_KNOCKBACK;
+            }
+        }
+        Ok(hit)
+    }
+
+    /// Broadcasts a [`MeleeStreamMessage::Swing`] of `actor`.
+    fn broadcast_swing(&self, actor: Entity, hit: Option<Entity>) {
+        let (Some(sender), Ok(&attacker)) = (&self.sender, self.net_ids.get(actor)) else {
+            return;
+        };
+        let swing = MeleeStreamMessage::Swing {
+            attacker,
+            target: hit.and_then(|hit| self.net_ids.get(hit).ok().copied()),
+        };
+        if let Err(e) = sender.broadcast(&swing) {
+            error!("Failed to broadcast swing of NetId({}): {e}", attacker.0);
+        }
+    }
+}
+
+/// Applies [`MeleeAttackRequest`]s: checks the held weapon's cooldown, casts
+/// a ray of its reach from the actor's hand, damages and knocks back the
+/// first body hit, and broadcasts the swing.  Requests without a weapon,
+/// during the cooldown or whose target is out of reach are dropped.
+pub(crate) fn handle_melee_attacks(
+    mut requests: MessageReader<MeleeAttackRequest>,
+    mut melee: Melee,
+) {
+    for request in requests.read() {
+        match melee.attack(request) {
+            Ok(hit) => melee.broadcast_swing(request.actor, hit),
+            Err(refusal) => debug!(?request, ?refusal, "melee attack dropped"),
+        }
+    }
+}
+
+/// Sends the [`StreamReady`](network::StreamReady) sentinel for the melee
+/// stream to joining clients; swings need no catch-up.
+pub(crate) fn send_melee_stream_ready_on_join(
+    mut player_events: MessageReader<PlayerEvent>,
+    sender: Res<StreamSender<MeleeStreamMessage>>,
+    mut module_ready: MessageWriter<ModuleReadySent>,
+) {
+    for event in player_events.read() {
+        let PlayerEvent::Joined { id, .. } = event else {
+            continue;
+        };
+        if let Err(e) = sender.send_stream_ready_to(*id) {
+            error!(
+                "Failed to send StreamReady for melee stream to ClientId({}): {e}",
+                id.0
+            );
+        } else {
+            module_ready.write(ModuleReadySent { client: *id });
+        }
+    }
+}
+
+/// Forgets cooldowns that have run out, including those of attackers that
+/// no longer exist.
+pub(crate) fn forget_stale_cooldowns(mut cooldowns: ResMut<MeleeCooldowns>, time: Res<Time>) {
+    let now = time.elapsed();
+    cooldowns.0.retain(|_, &mut ready_at| ready_at > now);
+}
+
+// ── Client ────────────────────────────────────────────────────────────────────
+
+/// Drains the melee stream into [`MeleeSwung`] messages.
+pub(crate) fn receive_melee_messages(
+    mut reader: ResMut<StreamReader<MeleeStreamMessage>>,
+    net_id_index: Res<NetIdIndex>,
+    mut swings: MessageWriter<MeleeSwung>,
+) {
+    for msg in reader.drain() {
+        let MeleeStreamMessage::Swing { attacker, target } = msg;
+        let Some(&attacker) = net_id_index.0.get(&attacker) else {
+            continue;
+        };
+        swings.write(MeleeSwung {
+            attacker,
+            target: target.and_then(|target| net_id_index.0.get(&target).copied()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::time::TimeUpdateStrategy;
+    use physics::{Collider, GravityScale, PhysicsPlugin};
+    use things::HandSide;
+
+    use super::*;
+
+    const CROWBAR: MeleeStats = MeleeStats {
+        reach: 1.5,
+        damage: 12.0,
+        cooldown: 1.0,
+    };
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            bevy::asset::AssetPlugin::default(),
+            bevy::mesh::MeshPlugin,
+            bevy::scene::ScenePlugin,
+            PhysicsPlugin,
+        ));
+        // Fixed steps only run on updates that advance time, and the
+        // spatial query only sees colliders after a physics step.
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            1.0 / 60.0,
+        )));
+        app.init_resource::<MeleeCooldowns>();
+        app.add_message::<MeleeAttackRequest>();
+        app.add_message::<Damage>();
+        app.finish();
+        app
+    }
+
+    fn swing(app: &mut App, actor: Entity, target: Entity) -> Vec<Damage> {
+        app.world_mut().write_message(MeleeAttackRequest {
+            actor,
+            target: Some(target),
+            direction: Vec3::ZERO,
+        });
+        app.world_mut()
+            .run_system_once(handle_melee_attacks)
+            .unwrap();
+        app.world_mut()
+            .resource_mut::<Messages<Damage>>()
+            .drain()
+            .collect()
+    }
+
+    #[test]
+    fn swings_hit_within_reach_and_wait_for_the_cooldown() {
+        let mut app = test_app();
+        let crowbar = app.world_mut().spawn(CROWBAR).id();
+        let actor = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                RigidBody::Static,
+                Collider::sphere(0.3),
+            ))
+            .with_child((
+                HandSlot {
+                    side: HandSide::Right,
+                },
+                Container {
+                    slots: vec![Some(crowbar)],
+                },
+            ))
+            .id();
+        let body = |x| {
+            (
+                Transform::from_xyz(x, HAND_OFFSET.y, 0.0),
+                RigidBody::Dynamic,
+                GravityScale(0.0),
+                Collider::sphere(0.3),
+            )
+        };
+        let near = app.world_mut().spawn(body(1.2)).id();
+        let far = app.world_mut().spawn(body(-3.0)).id();
+        for _ in 0..3 {
+            app.update();
+        }
+
+        assert!(swing(&mut app, actor, far).is_empty());
+        let damage = swing(&mut app, actor, near);
+        assert_eq!(damage.len(), 1);
+        assert_eq!(damage[0].target, near);
+        assert_eq!(damage[0].amount, CROWBAR.damage);
+        let knockback = app.world().get::<LinearVelocity>(near).unwrap().0;
+        assert!((knockback - Vec3::X * MELEE_KNOCKBACK).length() < 1e-4);
+
+        // The second swing comes too soon; once the cooldown has run out it
+        // lands again.
+        assert!(swing(&mut app, actor, near).is_empty());
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(CROWBAR.cooldown));
+        assert_eq!(swing(&mut app, actor, near).len(), 1);
+    }
+}
@@ -0,0 +1,166 @@
+//! Structured, per-client logging of inbound requests.
+//!
+//! Every request a client sends (interactions on stream 4, tile toggle RPC
+//! calls) is handled inside a tracing span opened by [`RequestLog::begin`].
+//! The span carries a [`CorrelationId`], the sending client and the kind of
+//! request, so every log line emitted while handling it, including those of
+//! the systems it is forwarded to in the same call, can be grouped by
+//! request or by client.
+//!
+//! [`RequestLog::finish`] then logs the outcome once.  A refusal is also
+//! written as a typed [`RequestRejected`] message, for consumers such as a
+//! feedback channel back to the client.
+//!
+//! Item requests are forwarded to the items module with their
+//! [`CorrelationId`] and validated there.  Their scope stays open in
+//! [`ForwardedRequests`] until the items module reports the outcome as an
+//! [`ItemRequestHandled`].
+
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::log::tracing::Span;
+use bevy::prelude::*;
+use items::ItemRequestHandled;
+use network::ClientId;
+pub use network::CorrelationId;
+
+use crate::ActionRejection;
+
+/// An inbound request being handled.  Enter `span` while handling it.
+#[derive(Debug)]
+pub struct RequestScope {
+    pub correlation: CorrelationId,
+    pub client: ClientId,
+    /// Kind of request, e.g. `"item_pickup"`.
+    pub request: &'static str,
+    pub span: Span,
+}
+
+/// Emitted on the server when a client's request is refused.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct RequestRejected {
+    pub correlation: CorrelationId,
+    pub client: ClientId,
+    pub request: &'static str,
+    pub reason: ActionRejection,
+}
+
+/// Next [`CorrelationId`] to hand out.
+#[derive(Resource, Debug, Default)]
+pub struct RequestCounter(u64);
+
+/// Opens and closes [`RequestScope`]s.
+#[derive(SystemParam)]
+pub struct RequestLog<'w> {
+    counter: ResMut<'w, RequestCounter>,
+    rejected: MessageWriter<'w, RequestRejected>,
+}
+
+impl RequestLog<'_> {
+    /// Assigns the next [`CorrelationId`] to a `request` from `client`.
+    pub fn begin(&mut self, client: ClientId, request: &'static str) -> RequestScope {
+        self.counter.0 += 1;
+        let correlation = CorrelationId(self.counter.0);
+        RequestScope {
+            correlation,
+            client,
+            request,
+            span: info_span!(
+                "request",
+                correlation = %correlation,
+                client = client.0,
+                request,
+            ),
+        }
+    }
+
+    /// Logs how the request ended and emits [`RequestRejected`] if it was
+    /// refused.
+    pub fn finish<T>(&mut self, scope: &RequestScope, outcome: &Result<T, ActionRejection>) {
+        let _entered = scope.span.enter();
+        match outcome {
+            Ok(_) => debug!("request accepted"),
+            Err(reason) => {
+                warn!(reason = ?reason, "request rejected");
+                self.rejected.write(RequestRejected {
+                    correlation: scope.correlation,
+                    client: scope.client,
+                    request: scope.request,
+                    reason: *reason,
+                });
+            }
+        }
+    }
+}
+
+/// Server resource: scopes of the requests forwarded to the items module
+/// whose outcome has not been reported yet.
+#[derive(Resource, Debug, Default)]
+pub struct ForwardedRequests(HashMap<CorrelationId, RequestScope>);
+
+impl ForwardedRequests {
+    /// Keeps `scope` open until its [`ItemRequestHandled`] arrives.
+    pub fn insert(&mut self, scope: RequestScope) {
+        self.0.insert(scope.correlation, scope);
+    }
+}
+
+/// Server-side system: finishes each forwarded request with the outcome the
+/// items module reported for it.
+pub(crate) fn finish_forwarded_requests(
+    mut handled: MessageReader<ItemRequestHandled>,
+    mut forwarded: ResMut<ForwardedRequests>,
+    mut log: RequestLog,
+) {
+    for handled in handled.read() {
+        let Some(scope) = forwarded.0.remove(&handled.correlation) else {
+            continue;
+        };
+        let outcome = handled
+            .outcome
+            .map_err(|reason| ActionRejection::Item { reason });
+        log.finish(&scope, &outcome);
+    }
+}
+
+/// Forgets the forwarded requests on leaving the game state.
+pub(crate) fn clear_forwarded_requests(mut forwarded: ResMut<ForwardedRequests>) {
+    forwarded.0.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_request_gets_its_own_id_and_rejections_are_emitted() {
+        let mut app = App::new();
+        app.add_message::<RequestRejected>();
+        app.init_resource::<RequestCounter>();
+        app.add_systems(Update, |mut log: RequestLog| {
+            let first = log.begin(ClientId(1), "climb");
+            log.finish(&first, &Ok(()));
+            let second = log.begin(ClientId(2), "drag");
+            assert_ne!(first.correlation, second.correlation);
+            log.finish::<()>(&second, &Err(ActionRejection::NoActor));
+        });
+        app.update();
+
+        let rejected: Vec<RequestRejected> = app
+            .world_mut()
+            .resource_mut::<Messages<RequestRejected>>()
+            .drain()
+            .collect();
+        assert_eq!(
+            rejected,
+            vec![RequestRejected {
+                correlation: CorrelationId(2),
+                client: ClientId(2),
+                request: "drag",
+                reason: ActionRejection::NoActor,
+            }]
+        );
+        assert_eq!(CorrelationId(2).to_string(), "req-2");
+    }
+}
@@ -0,0 +1,39 @@
+//! Context-menu entries for research.
+//!
+//! Right-clicking the floor within reach offers every [`TechTree`] node
+//! whose prerequisites have been researched, with its cost in research
+//! points.  Nodes costing more points than are banked are shown greyed out.
+//! Choosing one sends [`InteractionRequest::Research`]; the server also
+//! checks that the player can reach the node's items.
+
+use bevy::prelude::*;
+use research::TechTree;
+use tiles::{Tile, TileLayer};
+
+use crate::{ContextEntry, ContextTarget, InteractionRequest};
+
+/// [`ContextActionProvider`](crate::ContextActionProvider) offering
+/// "Research {node}" entries.
+pub(crate) fn research_context_actions(
+    world: &World,
+    target: &ContextTarget,
+    entries: &mut Vec<ContextEntry>,
+) {
+    let Some(tree) = world.get_resource::<TechTree>() else {
+        return;
+    };
+    let on_floor = world
+        .get::<Tile>(target.entity)
+        .is_some_and(|tile| tile.layer == TileLayer::Floor);
+    if !target.in_range || !on_floor {
+        return;
+    }
+
+    for (id, node) in tree.iter().filter(|&(id, _)| tree.is_available(id)) {
+        entries.push(ContextEntry {
+            label: format!("Research {} ({} pts)", node.name, node.points),
+            request: InteractionRequest::Research { node: id },
+            available: tree.points() >= node.points,
+        });
+    }
+}
@@ -0,0 +1,44 @@
+//! Context-menu entry for throwing the held item.
+//!
+//! While the player holds an item, every context menu offers to throw it at
+//! the spot it was opened on, e.g. "Throw Wrench".  Choosing it sends
+//! [`InteractionRequest::ThrowItem`]; the server throws the item if the
+//! player still holds it.
+
+use bevy::prelude::*;
+use items::ItemCatalog;
+use things::{NetIdIndex, Thing};
+
+use crate::{ContextEntry, ContextTarget, InteractionRequest};
+
+/// [`ContextActionProvider`](crate::ContextActionProvider) offering to throw
+/// the held item at the target.
+pub(crate) fn throw_context_actions(
+    world: &World,
+    target: &ContextTarget,
+    entries: &mut Vec<ContextEntry>,
+) {
+    let Some(held) = target.holding else {
+        return;
+    };
+    let name = world
+        .get_resource::<NetIdIndex>()
+        .and_then(|index| index.0.get(&held))
+        .and_then(|&item| world.get::<Thing>(item))
+        .and_then(|thing| {
+            world
+                .get_resource::<ItemCatalog>()?
+                .display_name(thing.kind)
+        });
+    entries.push(ContextEntry {
+        label: match name {
+            Some(name) => format!("Throw {name}"),
+            None => "Throw".to_string(),
+        },
+        request: InteractionRequest::ThrowItem {
+            item: held,
+            target: target.world_pos.to_array(),
+        },
+        available: true,
+    });
+}
@@ -0,0 +1,346 @@
+//! Timed actions: interactions that take a while to complete.
+//!
+//! The server validates a request, spawns a [`TimedAction`] entity, and only
+//! applies the action's effect once the actor has stayed next to the target
+//! for the full duration.  Walking away cancels it.  The acting client is told
+//! when an action starts and ends on stream 6 and animates a progress bar
+//! from the announced duration.
+
+use bevy::prelude::*;
+use crafting::RecipeId;
+use network::{ClientId, ModuleReadySent, PlayerEvent, StreamReader, StreamSender};
+use tiles::{FloorKind, TileKind};
+use ui::{UiTheme, WorldSpaceOverlay};
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{TileEdits, is_adjacent};
+
+/// Stream tag for the server→client timed-action stream (stream 6).
+pub const ACTIONS_STREAM_TAG: u8 = 6;
+
+/// Seconds needed to change the floor covering of a tile.
+pub const FLOOR_ACTION_DURATION: f32 = 1.0;
+
+/// Seconds an actor must stay next to a tile to change its structure from
+/// `from` to `to`.  Zero means the change is applied immediately.
+pub fn structure_action_duration(from: TileKind, to: TileKind) -> f32 {
+    match (from, to) {
+        // Opening and closing doors is instant.
+        (from, to) if from.is_door() && to.is_door() => 0.0,
+        (TileKind::Wall, _) => 3.0,
+        (_, TileKind::Wall) => 2.0,
+        _ => 1.5,
+    }
+}
+
+/// The change a [`TimedAction`] applies when it completes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActionEffect {
+    /// Set the structure layer at `position` to `kind`.
+    TileToggle { position: IVec2, kind: TileKind },
+    /// Set the floor layer at `position` to `floor`.
+    FloorToggle { position: IVec2, floor: FloorKind },
+    /// Make `recipe`, worked on at `station` (or in the hands) on the tile
+    /// at `position`.
+    Craft {
+        recipe: RecipeId,
+        station: Option<Entity>,
+        position: IVec2,
+    },
+}
+
+impl ActionEffect {
+    /// Grid position of the tile the actor works on.
+    pub fn position(&self) -> IVec2 {
+        match *self {
+            ActionEffect::TileToggle { position, .. }
+            | ActionEffect::FloorToggle { position, .. }
+            | ActionEffect::Craft { position, .. } => position,
+        }
+    }
+
+    /// Applies the effect on behalf of `actor`.
+    pub(crate) fn apply(self, edits: &mut TileEdits, actor: Option<Entity>) -> Result<(), String> {
+        match self {
+            ActionEffect::TileToggle { position, kind } => {
+                if edits.structure(position) == Some(kind) {
+                    return Err(format!("tile at {position:?} is already {kind:?}"));
+                }
+                edits.set_structure(actor, position, kind)
+            }
+            ActionEffect::FloorToggle { position, floor } => {
+                if edits.floor(position) == Some(floor) {
+                    return Err(format!("floor at {position:?} is already {floor:?}"));
+                }
+                edits.set_floor(actor, position, floor)
+            }
+            ActionEffect::Craft {
+                recipe, station, ..
+            } => edits.complete_craft(actor, recipe, station),
+        }
+    }
+}
+
+/// Server-side action in progress.  Lives on its own entity; despawned when
+/// it completes or is cancelled.
+#[derive(Component, Debug, Clone)]
+pub struct TimedAction {
+    /// The creature performing the action.
+    pub actor: Entity,
+    /// The client controlling `actor`, which receives progress messages.
+    pub client: ClientId,
+    /// Total time in seconds the action takes.
+    pub duration: f32,
+    /// Time in seconds spent on the action so far.
+    pub elapsed: f32,
+    /// Applied when `elapsed` reaches `duration`.
+    pub on_complete: ActionEffect,
+}
+
+impl TimedAction {
+    pub fn new(actor: Entity, client: ClientId, duration: f32, on_complete: ActionEffect) -> Self {
+        Self {
+            actor,
+            client,
+            duration,
+            elapsed: 0.0,
+            on_complete,
+        }
+    }
+
+    /// Fraction of the action completed, in `0.0..=1.0`.
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        (self.elapsed / self.duration).clamp(0.0, 1.0)
+    }
+}
+
+/// Stream 6 wire format: server→client messages about the receiving
+/// client's own timed action.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub enum ActionsStreamMessage {
+    /// An action on the tile at `position` started and takes `duration` seconds.
+    Started { position: [i32; 2], duration: f32 },
+    /// The current action ended; `completed` is `false` if it was cancelled.
+    Ended { completed: bool },
+}
+
+// ── Server ────────────────────────────────────────────────────────────────────
+
+/// Tells the acting client about every newly started [`TimedAction`].
+pub(crate) fn announce_timed_actions(
+    actions: Query<&TimedAction, Added<TimedAction>>,
+    sender: Res<StreamSender<ActionsStreamMessage>>,
+) {
+    for action in actions.iter() {
+        let position = action.on_complete.position();
+        if let Err(e) = sender.send_to(
+            action.client,
+            &ActionsStreamMessage::Started {
+                position: [position.x, position.y],
+                duration: action.duration,
+            },
+        ) {
+            error!(
+                "Failed to send action start to ClientId({}): {e}",
+                action.client.0
+            );
+        }
+    }
+}
+
+/// Advances every [`TimedAction`], cancelling those whose actor is gone or
+/// no longer adjacent to the target tile and applying the effect of those
+/// that completed.
+pub(crate) fn tick_timed_actions(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut actions: Query<(Entity, &mut TimedAction)>,
+    transforms: Query<&Transform>,
+    mut edits: TileEdits,
+    sender: Res<StreamSender<ActionsStreamMessage>>,
+) {
+    for (entity, mut action) in actions.iter_mut() {
+        let in_range = transforms
+            .get(action.actor)
+            .is_ok_and(|t| is_adjacent(t.translation, action.on_complete.position()));
+        let completed = if !in_range {
+            debug!(
+                "Timed action {:?} of {:?} cancelled: actor out of range",
+                action.on_complete, action.client
+            );
+            false
+        } else {
+            action.elapsed += time.delta_secs();
+            if action.elapsed < action.duration {
+                continue;
+            }
+            match action.on_complete.apply(&mut edits, Some(action.actor)) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!(
+                        "Timed action {:?} of {:?} failed on completion: {}",
+                        action.on_complete, action.client, e
+                    );
+                    false
+                }
+            }
+        };
+
+        commands.entity(entity).despawn();
+        if let Err(e) = sender.send_to(action.client, &ActionsStreamMessage::Ended { completed }) {
+            error!(
+                "Failed to send action end to ClientId({}): {e}",
+                action.client.0
+            );
+        }
+    }
+}
+
+/// Drops the timed actions of clients that disconnected.
+pub(crate) fn cancel_actions_on_leave(
+    mut commands: Commands,
+    mut player_events: MessageReader<PlayerEvent>,
+    actions: Query<(Entity, &TimedAction)>,
+) {
+    for event in player_events.read() {
+        let PlayerEvent::Left { id } = event else {
+            continue;
+        };
+        for (entity, action) in actions.iter() {
+            if action.client == *id {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+/// Sends the [`StreamReady`](network::StreamReady) sentinel for stream 6 to
+/// every client that joined this frame.  Stream 6 carries no initial state.
+pub(crate) fn send_actions_stream_ready_on_join(
+    mut player_events: MessageReader<PlayerEvent>,
+    sender: Res<StreamSender<ActionsStreamMessage>>,
+    mut module_ready: MessageWriter<ModuleReadySent>,
+) {
+    for event in player_events.read() {
+        let PlayerEvent::Joined { id, .. } = event else {
+            continue;
+        };
+        if let Err(e) = sender.send_stream_ready_to(*id) {
+            error!(
+                "Failed to send StreamReady for actions stream to ClientId({}): {e}",
+                id.0
+            );
+        } else {
+            module_ready.write(ModuleReadySent { client: *id });
+        }
+    }
+}
+
+/// Despawns all timed actions, e.g. when leaving the game state.
+pub(crate) fn clear_timed_actions(
+    mut commands: Commands,
+    actions: Query<Entity, With<TimedAction>>,
+) {
+    for entity in actions.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// ── Client ────────────────────────────────────────────────────────────────────
+
+/// Progress bar of the local player's current timed action.
+#[derive(Resource, Debug)]
+pub(crate) struct ActionProgressBar {
+    root: Entity,
+    fill: Entity,
+    started_at: f32,
+    duration: f32,
+}
+
+/// Drains stream 6 and spawns or despawns the [`ActionProgressBar`] overlay
+/// above the tile being worked on.
+pub(crate) fn receive_action_messages(
+    mut commands: Commands,
+    mut reader: ResMut<StreamReader<ActionsStreamMessage>>,
+    bar: Option<Res<ActionProgressBar>>,
+    time: Res<Time>,
+    theme: Res<UiTheme>,
+) {
+    let mut current = bar.as_deref().map(|bar| bar.root);
+    for msg in reader.drain() {
+        if let Some(root) = current.take() {
+            commands.entity(root).despawn();
+            commands.remove_resource::<ActionProgressBar>();
+        }
+        let ActionsStreamMessage::Started { position, duration } = msg else {
+            continue;
+        };
+        let fill = commands
+            .spawn((
+                Node {
+                    width: Val::Percent(0.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(theme.primary_hover),
+            ))
+            .id();
+        let root = commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Px(60.0),
+                    height: Val::Px(8.0),
+                    ..default()
+                },
+                BackgroundColor(theme.background),
+                WorldSpaceOverlay {
+                    world_pos: Vec3::new(position[0] as f32, 1.5, position[1] as f32),
+                },
+            ))
+            .add_child(fill)
+            .id();
+        commands.insert_resource(ActionProgressBar {
+            root,
+            fill,
+            started_at: time.elapsed_secs(),
+            duration,
+        });
+        current = Some(root);
+    }
+}
+
+/// Grows the fill of the [`ActionProgressBar`] with the time since the action
+/// started.
+pub(crate) fn update_action_progress_bar(
+    bar: Option<Res<ActionProgressBar>>,
+    time: Res<Time>,
+    mut nodes: Query<&mut Node>,
+) {
+    let Some(bar) = bar else {
+        return;
+    };
+    let Ok(mut node) = nodes.get_mut(bar.fill) else {
+        return;
+    };
+    let progress = if bar.duration > 0.0 {
+        ((time.elapsed_secs() - bar.started_at) / bar.duration).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    node.width = Val::Percent(progress * 100.0);
+}
+
+/// Despawns the progress bar, e.g. when leaving the game state.
+pub(crate) fn clear_action_progress_bar(
+    mut commands: Commands,
+    bar: Option<Res<ActionProgressBar>>,
+) {
+    if let Some(bar) = bar {
+        commands.entity(bar.root).despawn();
+        commands.remove_resource::<ActionProgressBar>();
+    }
+}
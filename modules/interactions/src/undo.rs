@@ -0,0 +1,43 @@
+//! Undoing recent tile edits.
+//!
+//! [`Action::UndoTileEdit`] sends [`InteractionRequest::UndoTileEdit`],
+//! which the server turns into a [`TileRollbackRequest`] for the sender's
+//! most recent edit.  Other server code (e.g. admin tools) can write
+//! [`TileRollbackRequest`]s directly to roll back everyone's edits.  Either
+//! way the restored tiles reach clients as ordinary mutations.
+
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use tiles::TileRollbackRequest;
+
+use crate::{ActorQuery, InteractionRequest, TileEdits, resolve_actor};
+
+/// Client-side system that sends [`InteractionRequest::UndoTileEdit`] on
+/// [`Action::UndoTileEdit`].
+pub(crate) fn request_undo(
+    input: ActionInput,
+    mut interaction_requests: MessageWriter<InteractionRequest>,
+) {
+    if input.just_pressed(Action::UndoTileEdit) {
+        interaction_requests.write(InteractionRequest::UndoTileEdit);
+    }
+}
+
+/// Applies [`TileRollbackRequest`]s through [`TileEdits::rollback`].  A
+/// client's undo is paid for by the creature it controls.
+pub(crate) fn handle_tile_rollbacks(
+    mut requests: MessageReader<TileRollbackRequest>,
+    mut edits: TileEdits,
+    actor_query: ActorQuery,
+) {
+    for req in requests.read() {
+        let actor = req
+            .by
+            .and_then(|client| resolve_actor(&actor_query, client));
+        let reverted = edits.rollback(req.count, req.by, actor);
+        match req.by {
+            Some(client) => info!("Undid {reverted} tile edit(s) of {:?}", client),
+            None => info!("Rolled back {reverted} of {} tile edit(s)", req.count),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Food and drink.
+//!
+//! Items with [`Consumable`] are eaten or drunk through the item use
+//! registry: the use restores the eater's [`Needs`] and takes one unit of
+//! the item, despawning it when it was the last.
+
+use bevy::prelude::*;
+use network::{NetId, StreamSender};
+use serde::{Deserialize, Serialize};
+use things::{DespawnReason, Needs, NetIdIndex, ThingsStreamMessage};
+
+use crate::{ItemUseRequest, Stack};
+
+/// Makes an item edible or drinkable: using it restores this much of the
+/// user's [`Needs`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Consumable {
+    pub nutrition: f32,
+    pub hydration: f32,
+}
+
+/// "Drink" for items that quench thirst more than hunger, "Eat" for other
+/// [`Consumable`]s.
+pub(crate) fn consume_label(item: EntityRef) -> Option<&'static str> {
+    let consumable = item.get::<Consumable>()?;
+    Some(if consumable.hydration > consumable.nutrition {
+        "Drink"
+    } else {
+        "Eat"
+    })
+}
+
+/// Item use: feeds the actor and takes one unit of the item.  Actors
+/// without [`Needs`] cannot eat.
+pub(crate) fn consume(
+    In(request): In<ItemUseRequest>,
+    mut commands: Commands,
+    mut consumables: Query<(&Consumable, Option<&mut Stack>, Option<&NetId>)>,
+    mut needs: Query<&mut Needs>,
+    mut net_id_index: ResMut<NetIdIndex>,
+    things_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+) {
+    let Ok((consumable, stack, net_id)) = consumables.get_mut(request.item) else {
+        return;
+    };
+    let Ok(mut needs) = needs.get_mut(request.actor) else {
+        return;
+    };
+    needs.restore(consumable.nutrition, consumable.hydration);
+
+    // Stacks are despawned by `despawn_empty_stacks` once they run out.
+    if let Some(mut stack) = stack {
+        stack.count = stack.count.saturating_sub(1);
+        return;
+    }
+    commands.entity(request.item).despawn();
+    let Some(&net_id) = net_id else {
+        return;
+    };
+    net_id_index.0.remove(&net_id);
+    if let Some(ref sender) = things_sender
+        && let Err(e) = sender.broadcast(&ThingsStreamMessage::EntityDespawned {
+            net_id,
+            reason: DespawnReason::Consumed,
+        })
+    {
+        error!(
+            "Failed to broadcast EntityDespawned for NetId({}): {e}",
+            net_id.0
+        );
+    }
+}
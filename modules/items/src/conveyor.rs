@@ -0,0 +1,209 @@
+//! Conveyors: belts that carry loose items along one grid direction.
+//!
+//! Every simulation tick the server sets the horizontal velocity of each
+//! dropped [`Item`] resting on a [`Conveyor`]'s cell to [`CONVEYOR_SPEED`]
+//! along the belt; clients only see the resulting positions, which they
+//! interpolate like any other moving thing.  A [`ConveyorTurnRequest`]
+//! turns the belt a quarter clockwise.  The server faces the conveyor's
+//! transform along its direction, so the replicated heading shows which
+//! way it runs, and the component itself is replicated and saved under
+//! `"conveyor"`.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use physics::{LinearVelocity, RigidBody};
+use serde::{Deserialize, Serialize};
+use things::{AuthorityHolder, cell_of};
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{InteractionRange, Item};
+
+/// Speed items are carried at, in metres per second.
+pub const CONVEYOR_SPEED: f32 = 1.5;
+
+/// Items whose centre is up to this far above a conveyor ride on it.
+pub const CONVEYOR_REACH: f32 = 1.0;
+
+/// Grid direction a conveyor runs in.  North is towards -Z.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Reflect,
+    Serialize,
+    Deserialize,
+    SchemaRead,
+    SchemaWrite,
+)]
+pub enum ConveyorDirection {
+    North,
+    #[default]
+    East,
+    South,
+    West,
+}
+
+impl ConveyorDirection {
+    /// Unit vector along the direction.
+    pub fn vector(self) -> Vec3 {
+        match self {
+            ConveyorDirection::North => Vec3::NEG_Z,
+            ConveyorDirection::East => Vec3::X,
+            ConveyorDirection::South => Vec3::Z,
+            ConveyorDirection::West => Vec3::NEG_X,
+        }
+    }
+
+    /// The next direction clockwise, seen from above.
+    pub fn clockwise(self) -> Self {
+        match self {
+            ConveyorDirection::North => ConveyorDirection::East,
+            ConveyorDirection::East => ConveyorDirection::South,
+            ConveyorDirection::South => ConveyorDirection::West,
+            ConveyorDirection::West => ConveyorDirection::North,
+        }
+    }
+
+    /// Rotation that turns a thing's +X axis along the direction.
+    pub fn rotation(self) -> Quat {
+        let v = self.vector();
+        Quat::from_rotation_y((-v.z).atan2(v.x))
+    }
+}
+
+/// A belt carrying the items on its cell along `direction`.
+#[derive(
+    Component,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Reflect,
+    Serialize,
+    Deserialize,
+    SchemaRead,
+    SchemaWrite,
+)]
+#[reflect(Component)]
+pub struct Conveyor {
+    pub direction: ConveyorDirection,
+}
+
+/// Server-side request: `actor` turns `conveyor` a quarter clockwise.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConveyorTurnRequest {
+    pub actor: Entity,
+    pub conveyor: Entity,
+}
+
+/// Server system: turns the conveyors named by [`ConveyorTurnRequest`]s
+/// whose actor stands within [`InteractionRange`].
+pub(crate) fn turn_conveyors(
+    mut requests: MessageReader<ConveyorTurnRequest>,
+    range: Res<InteractionRange>,
+    transforms: Query<&GlobalTransform>,
+    mut conveyors: Query<&mut Conveyor>,
+) {
+    for request in requests.read() {
+        let in_range = transforms
+            .get(request.actor)
+            .ok()
+            .zip(transforms.get(request.conveyor).ok())
+            .is_some_and(|(a, c)| a.translation().distance(c.translation()) <= range.0);
+        if !in_range {
+            debug!(?request, "conveyor turn dropped: out of reach");
+            continue;
+        }
+        let Ok(mut conveyor) = conveyors.get_mut(request.conveyor) else {
+            debug!(?request, "conveyor turn dropped: not a conveyor");
+            continue;
+        };
+        conveyor.direction = conveyor.direction.clockwise();
+    }
+}
+
+/// Server system: faces every new or turned conveyor along its direction.
+pub(crate) fn orient_conveyors(
+    mut conveyors: Query<(&Conveyor, &mut Transform), Changed<Conveyor>>,
+) {
+    for (conveyor, mut transform) in conveyors.iter_mut() {
+        transform.rotation = conveyor.direction.rotation();
+    }
+}
+
+/// Server system: carries the loose items resting on conveyors.
+///
+/// Only the horizontal velocity is set, so items still fall and settle on
+/// the belt.  Props simulated by a client (see [`AuthorityHolder`]) are
+/// left alone; their state comes from that client.
+#[allow(clippy::type_complexity)]
+pub(crate) fn move_items_on_conveyors(
+    conveyors: Query<(&Conveyor, &GlobalTransform)>,
+    mut items: Query<
+        (&GlobalTransform, &mut LinearVelocity),
+        (
+            With<Item>,
+            With<RigidBody>,
+            Without<ChildOf>,
+            Without<AuthorityHolder>,
+        ),
+    >,
+) {
+    let belts: HashMap<IVec2, (Vec3, f32)> = conveyors
+        .iter()
+        .map(|(conveyor, transform)| {
+            let pos = transform.translation();
+            (cell_of(pos), (conveyor.direction.vector(), pos.y))
+        })
+        .collect();
+    if belts.is_empty() {
+        return;
+    }
+    for (transform, mut velocity) in items.iter_mut() {
+        let pos = transform.translation();
+        let Some(&(direction, height)) = belts.get(&cell_of(pos)) else {
+            continue;
+        };
+        if !(0.0..=CONVEYOR_REACH).contains(&(pos.y - height)) {
+            continue;
+        }
+        let carried = direction * CONVEYOR_SPEED;
+        velocity.0 = Vec3::new(carried.x, velocity.0.y, carried.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directions_turn_clockwise_and_face_their_vector() {
+        let mut direction = ConveyorDirection::North;
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            let forward = direction.rotation() * Vec3::X;
+            assert!(
+                forward.abs_diff_eq(direction.vector(), 1e-5),
+                "{direction:?}"
+            );
+            seen.push(direction);
+            direction = direction.clockwise();
+        }
+        assert_eq!(direction, ConveyorDirection::North);
+        assert_eq!(
+            seen,
+            [
+                ConveyorDirection::North,
+                ConveyorDirection::East,
+                ConveyorDirection::South,
+                ConveyorDirection::West,
+            ]
+        );
+    }
+}
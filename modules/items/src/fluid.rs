@@ -0,0 +1,274 @@
+//! Fluids: buckets, water tanks and the taps and drains they are filled
+//! from and emptied into.
+//!
+//! A [`FluidContainer`] holds litres of a single [`FluidKind`].  A
+//! [`FluidPourRequest`] moves as much as fits from one end to the other,
+//! where either end may also be an endless [`FluidSource`] or a
+//! [`FluidDrain`] that swallows anything.  One of the two must be held by
+//! the actor and the other within [`InteractionRange`].  Containers are
+//! replicated as a synced component and saved under `"fluid"`.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use things::HandSlot;
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{Container, InteractionRange};
+
+/// A fluid a [`FluidContainer`] can hold.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Reflect,
+    Serialize,
+    Deserialize,
+    SchemaRead,
+    SchemaWrite,
+)]
+pub enum FluidKind {
+    #[default]
+    Water,
+    Coolant,
+    Fuel,
+}
+
+/// Litres of one fluid held by a thing.
+///
+/// `contents` is only meaningful while `amount` is above zero; an empty
+/// container takes any fluid.
+#[derive(
+    Component,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Reflect,
+    Serialize,
+    Deserialize,
+    SchemaRead,
+    SchemaWrite,
+)]
+#[reflect(Component)]
+pub struct FluidContainer {
+    pub contents: FluidKind,
+    pub amount: f32,
+    pub capacity: f32,
+}
+
+impl FluidContainer {
+    pub fn empty(capacity: f32) -> Self {
+        Self {
+            contents: FluidKind::default(),
+            amount: 0.0,
+            capacity,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amount <= 0.0
+    }
+
+    /// Litres of `kind` that still fit: none while it holds another fluid.
+    pub fn room_for(&self, kind: FluidKind) -> f32 {
+        if !self.is_empty() && self.contents != kind {
+            return 0.0;
+        }
+        (self.capacity - self.amount).max(0.0)
+    }
+}
+
+/// Endless supply of a fluid, such as a tap.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct FluidSource {
+    pub kind: FluidKind,
+}
+
+/// Takes any amount of any fluid poured into it, such as a sink.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct FluidDrain;
+
+/// One end of a pour, as far as the amount moved is concerned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FluidEnd {
+    Container(FluidContainer),
+    Source(FluidKind),
+    Drain,
+}
+
+/// Why a pour would move nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PourError {
+    /// The first end cannot give fluid (a drain), or the second cannot take
+    /// it (a source), or both are endless.
+    WrongEnds,
+    /// There is nothing to pour.
+    Empty,
+    /// The receiving container is full, or holds another fluid.
+    NoRoom,
+}
+
+/// The fluid and litres a pour from `from` into `to` moves.
+pub fn plan_pour(from: FluidEnd, to: FluidEnd) -> Result<(FluidKind, f32), PourError> {
+    let (kind, available) = match from {
+        FluidEnd::Container(container) if container.is_empty() => return Err(PourError::Empty),
+        FluidEnd::Container(container) => (container.contents, container.amount),
+        FluidEnd::Source(kind) => (kind, f32::INFINITY),
+        FluidEnd::Drain => return Err(PourError::WrongEnds),
+    };
+    let amount = match to {
+        FluidEnd::Container(container) => available.min(container.room_for(kind)),
+        FluidEnd::Drain if available.is_finite() => available,
+        FluidEnd::Drain | FluidEnd::Source(_) => return Err(PourError::WrongEnds),
+    };
+    if amount <= 0.0 {
+        return Err(PourError::NoRoom);
+    }
+    Ok((kind, amount))
+}
+
+/// Server-side request: `actor` pours from `from` into `to`.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FluidPourRequest {
+    pub actor: Entity,
+    pub from: Entity,
+    pub to: Entity,
+}
+
+/// The [`FluidEnd`] `entity` acts as, if any.
+fn fluid_end(
+    entity: Entity,
+    containers: &Query<&mut FluidContainer>,
+    ends: &Query<(Option<&FluidSource>, Has<FluidDrain>)>,
+) -> Option<FluidEnd> {
+    if let Ok(container) = containers.get(entity) {
+        return Some(FluidEnd::Container(*container));
+    }
+    match ends.get(entity).ok()? {
+        (Some(source), _) => Some(FluidEnd::Source(source.kind)),
+        (None, true) => Some(FluidEnd::Drain),
+        (None, false) => None,
+    }
+}
+
+/// Server system: carries out every [`FluidPourRequest`] where the actor
+/// holds one end and stands within range of the other.  Requests that
+/// would move nothing are dropped.
+pub(crate) fn handle_fluid_pours(
+    mut requests: MessageReader<FluidPourRequest>,
+    range: Res<InteractionRange>,
+    children: Query<&Children>,
+    hands: Query<&Container, With<HandSlot>>,
+    transforms: Query<&GlobalTransform>,
+    ends: Query<(Option<&FluidSource>, Has<FluidDrain>)>,
+    mut containers: Query<&mut FluidContainer>,
+) {
+    for request in requests.read() {
+        let holds = |item: Entity| {
+            children.get(request.actor).is_ok_and(|children| {
+                children
+                    .iter()
+                    .any(|child| hands.get(child).is_ok_and(|hand| hand.contains(item)))
+            })
+        };
+        let other = match (holds(request.from), holds(request.to)) {
+            (true, _) => request.to,
+            (false, true) => request.from,
+            (false, false) => {
+                debug!(?request, "fluid pour dropped: neither end held");
+                continue;
+            }
+        };
+        let in_range = transforms
+            .get(request.actor)
+            .ok()
+            .zip(transforms.get(other).ok())
+            .is_some_and(|(a, o)| a.translation().distance(o.translation()) <= range.0);
+        if request.from == request.to || !in_range {
+            debug!(?request, "fluid pour dropped: other end out of reach");
+            continue;
+        }
+        let (Some(from), Some(to)) = (
+            fluid_end(request.from, &containers, &ends),
+            fluid_end(request.to, &containers, &ends),
+        ) else {
+            debug!(?request, "fluid pour dropped: an end holds no fluid");
+            continue;
+        };
+        let (kind, amount) = match plan_pour(from, to) {
+            Ok(pour) => pour,
+            Err(error) => {
+                debug!(?request, ?error, "fluid pour dropped");
+                continue;
+            }
+        };
+        if let Ok(mut container) = containers.get_mut(request.from) {
+            container.amount = (container.amount - amount).max(0.0);
+        }
+        if let Ok(mut container) = containers.get_mut(request.to) {
+            container.contents = kind;
+            container.amount = (container.amount + amount).min(container.capacity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pours_move_what_fits_of_a_single_fluid() {
+        let bucket = FluidContainer {
+            contents: FluidKind::Water,
+            amount: 8.0,
+            capacity: 10.0,
+        };
+        let tank = FluidContainer {
+            amount: 95.0,
+            capacity: 100.0,
+            ..bucket
+        };
+        let fuel_can = FluidContainer {
+            contents: FluidKind::Fuel,
+            amount: 1.0,
+            capacity: 5.0,
+        };
+
+        assert_eq!(
+            plan_pour(FluidEnd::Container(bucket), FluidEnd::Container(tank)),
+            Ok((FluidKind::Water, 5.0))
+        );
+        assert_eq!(
+            plan_pour(
+                FluidEnd::Source(FluidKind::Water),
+                FluidEnd::Container(bucket)
+            ),
+            Ok((FluidKind::Water, 2.0))
+        );
+        assert_eq!(
+            plan_pour(FluidEnd::Container(bucket), FluidEnd::Drain),
+            Ok((FluidKind::Water, 8.0))
+        );
+        assert_eq!(
+            plan_pour(FluidEnd::Container(bucket), FluidEnd::Container(fuel_can)),
+            Err(PourError::NoRoom)
+        );
+        assert_eq!(
+            plan_pour(
+                FluidEnd::Container(FluidContainer::empty(10.0)),
+                FluidEnd::Drain
+            ),
+            Err(PourError::Empty)
+        );
+        assert_eq!(
+            plan_pour(FluidEnd::Source(FluidKind::Water), FluidEnd::Drain),
+            Err(PourError::WrongEnds)
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Arms reaching for what their creature holds.
+//!
+//! A creature's visual may give it arms: one [`IkChain`](animation::IkChain)
+//! entity per arm, marked [`HoldArm`] and sitting at the creature's origin,
+//! so the chain's frame is the creature's.  [`aim_hold_arms`] points each
+//! arm's [`HoldIk`] at the [`Grip`] of the held item: the holding hand's arm
+//! at its primary point, the other arm at the secondary point of a
+//! two-handed item.  Arms with nothing to reach for blend back to rest.
+//! Headless servers have no poses to draw, so the system is left out there.
+
+use animation::HoldIk;
+use bevy::prelude::*;
+use things::{HandSide, HandSlot, Thing};
+
+use crate::{Container, Grip, ItemCatalog};
+
+/// Marks an arm's [`IkChain`](animation::IkChain) entity, a child of its
+/// creature, with the hand it moves.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct HoldArm {
+    pub side: HandSide,
+}
+
+/// Client system: aims each [`HoldArm`] at the grip of the item in its
+/// creature's hands, or lets it rest when there is none to reach.
+pub(crate) fn aim_hold_arms(
+    catalog: Res<ItemCatalog>,
+    mut arms: Query<(&HoldArm, &ChildOf, &mut HoldIk)>,
+    children: Query<&Children>,
+    hands: Query<(&HandSlot, &Transform, &Container)>,
+    things: Query<&Thing>,
+) {
+    for (arm, child_of, mut hold) in arms.iter_mut() {
+        let held = children
+            .get(child_of.parent())
+            .into_iter()
+            .flat_map(|children| children.iter())
+            .filter_map(|child| {
+                let (slot, anchor, container) = hands.get(child).ok()?;
+                let thing = container
+                    .slots
+                    .iter()
+                    .flatten()
+                    .find_map(|&item| things.get(item).ok())?;
+                Some((slot.side, *anchor, thing.kind))
+            });
+
+        let mut reach = None;
+        for (side, anchor, kind) in held {
+            let item = anchor * catalog.hold_offset(kind).transform();
+            match (side == arm.side, catalog.grip(kind)) {
+                (true, Grip::OneHanded(point) | Grip::TwoHanded { primary: point, .. }) => {
+                    reach = Some(item.transform_point(Vec3::from_array(point)));
+                    break;
+                }
+                (false, Grip::TwoHanded { secondary, .. }) => {
+                    reach.get_or_insert(item.transform_point(Vec3::from_array(secondary)));
+                }
+                (false, Grip::OneHanded(_)) => {}
+            }
+        }
+
+        let (active, target) = reach.map_or((false, hold.target), |target| (true, target));
+        if hold.active != active || hold.target != target {
+            hold.active = active;
+            hold.target = target;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ItemKind;
+    use things::HAND_OFFSET;
+
+    const WRENCH: u16 = 3;
+    const SHEET: u16 = 4;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        let mut catalog = ItemCatalog::default();
+        catalog.register(
+            WRENCH,
+            ItemKind {
+                grip: Grip::OneHanded([0.0, 0.1, 0.0]),
+                ..ItemKind::named("Wrench")
+            },
+        );
+        catalog.register(
+            SHEET,
+            ItemKind {
+                grip: Grip::TwoHanded {
+                    primary: [0.0, 0.0, 0.25],
+                    secondary: [0.0, 0.0, -0.25],
+                },
+                ..ItemKind::named("Plasteel")
+            },
+        );
+        app.insert_resource(catalog);
+        app.add_systems(Update, aim_hold_arms);
+        app
+    }
+
+    /// Spawns a creature with a right hand holding nothing and an arm on
+    /// each side.  Returns (hand, left arm, right arm).
+    fn spawn_creature(app: &mut App) -> (Entity, Entity, Entity) {
+        let world = app.world_mut();
+        let creature = world.spawn(Transform::default()).id();
+        let hand = world
+            .spawn((
+                HandSlot {
+                    side: HandSide::Right,
+                },
+                Transform::from_translation(HAND_OFFSET),
+                Container::with_capacity(1),
+                ChildOf(creature),
+            ))
+            .id();
+        let mut arm = |side| {
+            world
+                .spawn((HoldArm { side }, HoldIk::default(), ChildOf(creature)))
+                .id()
+        };
+        let (left, right) = (arm(HandSide::Left), arm(HandSide::Right));
+        (hand, left, right)
+    }
+
+    fn hold(app: &mut App, hand: Entity, kind: Option<u16>) {
+        let item = kind.map(|kind| app.world_mut().spawn(Thing { kind }).id());
+        let mut container = Container::with_capacity(1);
+        if let Some(item) = item {
+            container.insert(item);
+        }
+        app.world_mut().entity_mut(hand).insert(container);
+        app.update();
+    }
+
+    fn hold_ik(app: &App, arm: Entity) -> HoldIk {
+        *app.world().get::<HoldIk>(arm).unwrap()
+    }
+
+    #[test]
+    fn arms_reach_for_the_grips_of_the_held_item() {
+        let mut app = test_app();
+        let (hand, left, right) = spawn_creature(&mut app);
+
+        hold(&mut app, hand, Some(SHEET));
+        let (right_ik, left_ik) = (hold_ik(&app, right), hold_ik(&app, left));
+        assert!(right_ik.active && left_ik.active);
+        assert_eq!(right_ik.target, HAND_OFFSET + Vec3::new(0.0, 0.0, 0.25));
+        assert_eq!(left_ik.target, HAND_OFFSET + Vec3::new(0.0, 0.0, -0.25));
+
+        hold(&mut app, hand, Some(WRENCH));
+        assert!(hold_ik(&app, right).active);
+        assert_eq!(
+            hold_ik(&app, right).target,
+            HAND_OFFSET + Vec3::new(0.0, 0.1, 0.0)
+        );
+        assert!(
+            !hold_ik(&app, left).active,
+            "One-handed items leave the other arm at rest"
+        );
+
+        hold(&mut app, hand, None);
+        assert!(!hold_ik(&app, right).active && !hold_ik(&app, left).active);
+    }
+}
@@ -0,0 +1,181 @@
+//! Item state transitions shared by the server and the client.
+//!
+//! The server applies a transition once it has validated a request in
+//! `handle_item_interaction`; clients apply the same transition when the
+//! matching [`ItemEvent`](crate::ItemEvent) arrives in `handle_item_event`.
+//! Both go through these functions so an item ends up with the same
+//! components on either side:
+//!
+//! | State     | Physics   | Parent | Visibility | [`StoredInContainer`] |
+//! |-----------|-----------|--------|------------|-----------------------|
+//! | loose     | in world  | none   | as it was  | none                  |
+//! | held      | stashed   | hand   | inherited  | none                  |
+//! | stored    | stashed   | none   | hidden     | the container         |
+//!
+//! Held items sit at their kind's [`HoldOffset`](crate::HoldOffset) from the
+//! hand anchor, looked up through [`HoldTransforms`] on both sides.
+//!
+//! The functions queue component changes on `commands` and update the
+//! [`Container`] slots right away, so later requests in the same system see
+//! them.  Validation is left to the callers.
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use physics::{Collider, GravityScale, LinearVelocity, RigidBody};
+use things::Thing;
+
+use crate::{Container, ItemCatalog, StashedPhysics, StoredInContainer};
+
+/// System parameter giving the local [`Transform`] an item takes in a hand.
+#[derive(SystemParam)]
+pub(crate) struct HoldTransforms<'w, 's> {
+    catalog: Res<'w, ItemCatalog>,
+    things: Query<'w, 's, &'static Thing>,
+}
+
+impl HoldTransforms<'_, '_> {
+    /// The hold offset of `item`'s kind; the bare anchor for items that are
+    /// not things.
+    pub(crate) fn of(&self, item: Entity) -> Transform {
+        self.things.get(item).map_or(Transform::IDENTITY, |thing| {
+            self.catalog.hold_offset(thing.kind).transform()
+        })
+    }
+}
+
+/// The physics to stash for an item with `collider` and `gravity`, or `None`
+/// when it lacks either and so has nothing to restore on drop.
+pub(crate) fn stash(
+    collider: Option<&Collider>,
+    gravity: Option<&GravityScale>,
+) -> Option<StashedPhysics> {
+    let (collider, gravity) = (collider?, gravity?);
+    Some(StashedPhysics {
+        collider: collider.clone(),
+        gravity: *gravity,
+    })
+}
+
+/// Takes `item`'s physics out of the world, keeping it in `stash`.
+fn stash_physics(commands: &mut Commands, item: Entity, stash: Option<StashedPhysics>) {
+    if let Some(stash) = stash {
+        commands
+            .entity(item)
+            .insert(stash)
+            .remove::<(RigidBody, Collider, LinearVelocity, GravityScale)>();
+    }
+}
+
+/// Loose → held: `item` goes into `hand`, placed at `held` from its anchor.
+pub(crate) fn apply_pickup(
+    commands: &mut Commands,
+    containers: &mut Query<&mut Container>,
+    item: Entity,
+    stash: Option<StashedPhysics>,
+    hand: Entity,
+    held: Transform,
+) {
+    stash_physics(commands, item, stash);
+    commands
+        .entity(item)
+        .insert((Visibility::Inherited, held, ChildOf(hand)));
+    if let Ok(mut container) = containers.get_mut(hand) {
+        container.insert(item);
+    }
+}
+
+/// Held → loose: `item` leaves `hand` and lands at `position` with the
+/// physics from `stash`.
+pub(crate) fn apply_drop(
+    commands: &mut Commands,
+    containers: &mut Query<&mut Container>,
+    item: Entity,
+    stash: StashedPhysics,
+    hand: Option<Entity>,
+    position: Vec3,
+) {
+    if let Some(mut container) = hand.and_then(|hand| containers.get_mut(hand).ok()) {
+        container.remove(item);
+    }
+    commands
+        .entity(item)
+        .remove::<(ChildOf, StashedPhysics)>()
+        .insert((
+            Transform::from_translation(position),
+            RigidBody::Dynamic,
+            stash.collider,
+            stash.gravity,
+            LinearVelocity::default(),
+        ));
+}
+
+/// Held or loose → stored: `item` leaves `from` (a hand or another
+/// container, if any) and goes into `container`, hidden.
+///
+/// Returns whether `container` holds the item afterwards; `false` if it is
+/// full or not a container.
+pub(crate) fn apply_store(
+    commands: &mut Commands,
+    containers: &mut Query<&mut Container>,
+    item: Entity,
+    stash: Option<StashedPhysics>,
+    from: Option<Entity>,
+    container: Entity,
+) -> bool {
+    if let Some(mut source) = from.and_then(|from| containers.get_mut(from).ok()) {
+        source.remove(item);
+    }
+    stash_physics(commands, item, stash);
+    commands
+        .entity(item)
+        .remove::<ChildOf>()
+        .insert((Visibility::Hidden, StoredInContainer(container)));
+    containers
+        .get_mut(container)
+        .is_ok_and(|mut target| target.insert(item).is_some() || target.contains(item))
+}
+
+/// Stored → held: `item` leaves `from` (its container, if known) and goes
+/// into `hand` at `held` from its anchor, shown again.
+///
+/// Returns whether `from` held the item.
+pub(crate) fn apply_take(
+    commands: &mut Commands,
+    containers: &mut Query<&mut Container>,
+    item: Entity,
+    stash: Option<StashedPhysics>,
+    from: Option<Entity>,
+    hand: Entity,
+    held: Transform,
+) -> bool {
+    let was_in_source = from
+        .and_then(|from| containers.get_mut(from).ok())
+        .is_some_and(|mut source| source.remove(item));
+    stash_physics(commands, item, stash);
+    commands.entity(item).remove::<StoredInContainer>().insert((
+        Visibility::Inherited,
+        held,
+        ChildOf(hand),
+    ));
+    if let Ok(mut hand_container) = containers.get_mut(hand) {
+        hand_container.insert(item);
+    }
+    was_in_source
+}
+
+/// Stored → unknown: `item` leaves `container` and is shown again, for a
+/// client that learns the item is gone but not yet where to.
+pub(crate) fn apply_release(
+    commands: &mut Commands,
+    containers: &mut Query<&mut Container>,
+    item: Entity,
+    container: Entity,
+) {
+    if let Ok(mut container) = containers.get_mut(container) {
+        container.remove(item);
+    }
+    commands
+        .entity(item)
+        .remove::<StoredInContainer>()
+        .insert(Visibility::Inherited);
+}
@@ -0,0 +1,86 @@
+//! Custom labels written on items and containers with a labeler.
+//!
+//! A thing carrying a [`CustomLabel`] shows it in place of its template
+//! name in menus and the inventory.  The server checks every new label
+//! with [`validate_label`], replicates it as a synced component and saves it
+//! with the thing's other properties under `"label"`.
+
+use std::fmt;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use wincode::{SchemaRead, SchemaWrite};
+
+/// Longest label accepted, in characters.
+pub const MAX_LABEL_LEN: usize = 32;
+
+/// Text written on a thing by a player.
+#[derive(
+    Component, Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize, SchemaRead, SchemaWrite,
+)]
+#[reflect(Component)]
+pub struct CustomLabel(pub String);
+
+/// Marks an item that writes [`CustomLabel`]s while held.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Labeler;
+
+/// Why [`validate_label`] refused a label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SchemaRead, SchemaWrite)]
+pub enum LabelError {
+    /// Longer than [`MAX_LABEL_LEN`] characters.
+    TooLong,
+    /// Contains a control character such as a newline.
+    InvalidCharacter,
+}
+
+impl fmt::Display for LabelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LabelError::TooLong => write!(f, "longer than {MAX_LABEL_LEN} characters"),
+            LabelError::InvalidCharacter => write!(f, "contains a control character"),
+        }
+    }
+}
+
+impl std::error::Error for LabelError {}
+
+/// The label to store for `text` with surrounding whitespace trimmed, or
+/// `None` when nothing is left, which clears the label.
+pub fn validate_label(text: &str) -> Result<Option<CustomLabel>, LabelError> {
+    let text = text.trim();
+    if text.chars().any(char::is_control) {
+        return Err(LabelError::InvalidCharacter);
+    }
+    if text.chars().count() > MAX_LABEL_LEN {
+        return Err(LabelError::TooLong);
+    }
+    Ok((!text.is_empty()).then(|| CustomLabel(text.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_label_trims_and_rejects_bad_text() {
+        assert_eq!(
+            validate_label("  Medical  "),
+            Ok(Some(CustomLabel("Medical".into())))
+        );
+        assert_eq!(validate_label("   "), Ok(None));
+        assert_eq!(
+            validate_label("two\nlines"),
+            Err(LabelError::InvalidCharacter)
+        );
+        assert_eq!(
+            validate_label(&"ü".repeat(MAX_LABEL_LEN)),
+            Ok(Some(CustomLabel("ü".repeat(MAX_LABEL_LEN))))
+        );
+        assert_eq!(
+            validate_label(&"a".repeat(MAX_LABEL_LEN + 1)),
+            Err(LabelError::TooLong)
+        );
+    }
+}
@@ -1,20 +1,64 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use bevy::ecs::system::SystemParam;
+use bevy::log::tracing::Span;
 use bevy::prelude::*;
 use network::{
-    Client, ModuleReadySent, NetId, NetworkReceive, NetworkSend, PlayerEvent, Server, StreamDef,
-    StreamDirection, StreamReader, StreamRegistry, StreamSender,
+    Client, CorrelationId, Headless, ModuleReadySent, NetId, NetworkReceive, NetworkSend,
+    PlayerEvent, Server, SimulationTick, StreamBudget, StreamDef, StreamDirection, StreamReader,
+    StreamRegistry, StreamSender, TunablesAppExt,
 };
 use physics::{Collider, GravityScale, LinearVelocity, RigidBody};
 use ron::value::RawValue;
 use serde::{Deserialize, Serialize};
 use things::{
-    HandSlot, NetIdIndex, PropertyEntry, SpawnMarker, SpawnPoint, Thing, ThingPropertyRegistry,
-    ThingRegistry, ThingsSet, apply_properties, serialize_entity_properties, spawn_thing_world,
+    ComponentSync, DespawnReason, EntitySpawnedData, HandSlot, NetIdIndex, PropertyEntry,
+    ReplicateComponentExt, ReplicationScope, SPAWN_BATCH_SIZE, SpatialIndex, SpawnMarker,
+    SpawnPoint, Thing, ThingPropertyRegistry, ThingRegistry, ThingsSet, ThingsStreamMessage,
+    apply_properties, serialize_entity_properties, spawn_thing, spawn_thing_world,
 };
 use wincode::{SchemaRead, SchemaWrite};
 
+mod consumable;
+pub use consumable::Consumable;
+
+mod conveyor;
+pub use conveyor::{
+    CONVEYOR_REACH, CONVEYOR_SPEED, Conveyor, ConveyorDirection, ConveyorTurnRequest,
+};
+
+mod fluid;
+pub use fluid::{
+    FluidContainer, FluidDrain, FluidEnd, FluidKind, FluidPourRequest, FluidSource, PourError,
+    plan_pour,
+};
+
+mod hold;
+pub use hold::HoldArm;
+
+mod item_state;
+
+mod label;
+pub use label::{CustomLabel, LabelError, Labeler, MAX_LABEL_LEN, validate_label};
+
+mod manifest;
+pub use manifest::{CargoManifest, ManifestRequest, announce_spawned, spawn_manifest};
+
+mod ownership;
+pub use ownership::{ItemTheft, OwnedBy, Stolen};
+
+mod snapshot;
+pub use snapshot::{
+    CONTAINER_RESYNC_STREAM_TAG, ContainerResyncRequest, ContainerResyncs, ContainerState,
+    PendingContainerStates,
+};
+
+mod throw;
+pub use throw::{ItemThrowRequest, THROW_HIT, THROW_SPEED};
+mod uses;
+pub use uses::{ItemUseRegistry, ItemUseRegistryExt, ItemUseRequest, UseLabel};
+
 // ── Components ────────────────────────────────────────────────────────────────
 
 /// Marker component for item entities — world objects that can be picked up,
@@ -23,6 +67,12 @@ use wincode::{SchemaRead, SchemaWrite};
 #[reflect(Component)]
 pub struct Item;
 
+/// Marker component for heavy things that are too big to carry in a hand but
+/// can be dragged along behind a creature (e.g. crates).
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct Draggable;
+
 /// Inventory container component.  Holds up to `capacity()` item entities in its
 /// slot list.  Added automatically to every [`HandSlot`] entity by
 /// [`init_hand_containers`].
@@ -86,6 +136,33 @@ impl Container {
     }
 }
 
+/// Number of units in a stackable item such as construction material.
+///
+/// Items without this component count as a single unit.  A stack whose count
+/// drops to zero is despawned by [`despawn_empty_stacks`] on the server.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Stack {
+    pub count: u32,
+}
+
+impl Stack {
+    pub fn new(count: u32) -> Self {
+        Self { count }
+    }
+}
+
+/// Makes an item a melee weapon: left-clicking with it in a hand swings it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct MeleeStats {
+    /// Furthest a hit lands from the wielder's hand, in metres.
+    pub reach: f32,
+    pub damage: f32,
+    /// Seconds between two swings.
+    pub cooldown: f32,
+}
+
 /// Physics snapshot stored on an item while it is held or stashed inside a
 /// container.  Restored when the item is dropped back into the world.
 ///
@@ -97,10 +174,11 @@ pub struct StashedPhysics {
     pub gravity: GravityScale,
 }
 
-/// Tracks which container entity currently holds this item on the client.
+/// Tracks which container entity currently holds this item.
 ///
-/// Inserted by [`handle_item_event`] when an [`ItemEvent::Stored`] message is
-/// processed, and removed when [`ItemEvent::Taken`] moves the item to a hand.
+/// Inserted whenever an item is stored (on the server by
+/// [`handle_item_interaction`], on clients by [`handle_item_event`]), and
+/// removed when it is taken out to a hand.
 /// Allows `Taken` to locate the source container in O(1) rather than scanning
 /// all [`Container`] entities.
 #[derive(Component, Debug, Clone, Copy)]
@@ -120,6 +198,177 @@ impl Default for InteractionRange {
     }
 }
 
+/// Where an item can be worn besides the hands.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, SchemaRead, SchemaWrite,
+)]
+pub enum EquipSlot {
+    Head,
+    Body,
+    Back,
+    Belt,
+}
+
+/// Where an item sits relative to the hand anchor while held, so large items
+/// do not clip through the creature holding them.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, SchemaRead, SchemaWrite,
+)]
+#[serde(default)]
+pub struct HoldOffset {
+    /// Offset from the anchor in metres, in the hand's frame.
+    pub translation: [f32; 3],
+    /// Yaw, pitch and roll in degrees, applied in that order.
+    pub rotation: [f32; 3],
+}
+
+impl HoldOffset {
+    /// The held item's local [`Transform`] under its [`HandSlot`].
+    pub fn transform(&self) -> Transform {
+        let [yaw, pitch, roll] = self.rotation.map(f32::to_radians);
+        Transform {
+            translation: Vec3::from_array(self.translation),
+            rotation: Quat::from_euler(EulerRot::YXZ, yaw, pitch, roll),
+            scale: Vec3::ONE,
+        }
+    }
+}
+
+/// Where hands take hold of an item, in the item's local frame.  Arms on
+/// the client reach for these points (see [`HoldArm`]).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, SchemaRead, SchemaWrite)]
+pub enum Grip {
+    /// Held at one point by the hand holding it.
+    OneHanded([f32; 3]),
+    /// Held at `primary` by the hand holding it and at `secondary` by the
+    /// other hand.
+    TwoHanded {
+        primary: [f32; 3],
+        secondary: [f32; 3],
+    },
+}
+
+impl Default for Grip {
+    fn default() -> Self {
+        Self::OneHanded([0.0; 3])
+    }
+}
+
+/// Metadata for one item kind, registered in [`ItemCatalog`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SchemaRead, SchemaWrite)]
+pub struct ItemKind {
+    /// Name shown for the item in the inventory and menus.
+    pub display_name: String,
+    /// Asset path of the item's inventory icon.
+    pub icon: Option<String>,
+    /// Largest [`Stack`] count a single entity of this kind may hold.
+    pub max_stack: u32,
+    /// Mass in kilograms, if fixed by the template.
+    pub mass: Option<f32>,
+    /// Room the item takes up in a container, in litres.
+    pub volume: f32,
+    /// Slots the item can be equipped in besides the hands.
+    pub equip_slots: Vec<EquipSlot>,
+    /// Where the item sits in a hand.
+    #[serde(default)]
+    pub hold_offset: HoldOffset,
+    /// Where the hands take hold of it.
+    #[serde(default)]
+    pub grip: Grip,
+}
+
+impl ItemKind {
+    /// A single-unit, one-litre item without icon or equip slots.
+    pub fn named(display_name: impl Into<String>) -> Self {
+        Self {
+            display_name: display_name.into(),
+            icon: None,
+            max_stack: 1,
+            mass: None,
+            volume: 1.0,
+            equip_slots: Vec::new(),
+            hold_offset: HoldOffset::default(),
+            grip: Grip::default(),
+        }
+    }
+}
+
+/// One [`ItemCatalog`] entry on the wire.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SchemaRead, SchemaWrite)]
+pub struct CatalogEntry {
+    pub kind: u16,
+    pub item: ItemKind,
+}
+
+/// Item metadata keyed by [`Thing`] kind.
+///
+/// Templates register their item kinds here next to the [`ThingRegistry`]
+/// entry.  The server sends its catalog to every client on join (and again
+/// whenever it changes), so clients show the server's names and icons
+/// without knowing kind numbers.  Kinds without an entry do not stack.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct ItemCatalog(HashMap<u16, ItemKind>);
+
+impl ItemCatalog {
+    /// Register (or replace) the metadata for `kind`.
+    pub fn register(&mut self, kind: u16, item_kind: ItemKind) {
+        self.0.insert(kind, item_kind);
+    }
+
+    /// Metadata for `kind`, or `None` if it was never registered.
+    pub fn get(&self, kind: u16) -> Option<&ItemKind> {
+        self.0.get(&kind)
+    }
+
+    /// Largest stack allowed for `kind`; 1 for unregistered kinds.
+    pub fn max_stack(&self, kind: u16) -> u32 {
+        self.get(kind).map_or(1, |k| k.max_stack.max(1))
+    }
+
+    /// Where items of `kind` sit in a hand; at the anchor for unregistered
+    /// kinds.
+    pub fn hold_offset(&self, kind: u16) -> HoldOffset {
+        self.get(kind)
+            .map_or_else(HoldOffset::default, |k| k.hold_offset)
+    }
+
+    /// Where hands take hold of items of `kind`; one-handed at the item's
+    /// origin for unregistered kinds.
+    pub fn grip(&self, kind: u16) -> Grip {
+        self.get(kind).map_or_else(Grip::default, |k| k.grip)
+    }
+
+    /// Display name of `kind`, or `None` if it was never registered.
+    pub fn display_name(&self, kind: u16) -> Option<&str> {
+        self.get(kind).map(|k| k.display_name.as_str())
+    }
+
+    /// Every entry, sorted by kind.
+    pub fn entries(&self) -> Vec<CatalogEntry> {
+        let mut entries: Vec<CatalogEntry> = self
+            .0
+            .iter()
+            .map(|(&kind, item)| CatalogEntry {
+                kind,
+                item: item.clone(),
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.kind);
+        entries
+    }
+}
+
+impl FromIterator<CatalogEntry> for ItemCatalog {
+    fn from_iter<I: IntoIterator<Item = CatalogEntry>>(entries: I) -> Self {
+        Self(
+            entries
+                .into_iter()
+                .map(|entry| (entry.kind, entry.item))
+                .collect(),
+        )
+    }
+}
+
 // ── Request events ────────────────────────────────────────────────────────────
 
 /// Server-side request: actor picks up an item from the world.
@@ -129,6 +378,8 @@ pub struct ItemPickupRequest {
     pub actor: Entity,
     /// The item entity to pick up.
     pub item: Entity,
+    /// The client request this was forwarded for, if any.
+    pub correlation: Option<CorrelationId>,
 }
 
 /// Server-side request: actor drops a held item at a world position.
@@ -140,6 +391,8 @@ pub struct ItemDropRequest {
     pub item: Entity,
     /// World position where the item should land.
     pub drop_position: Vec3,
+    /// The client request this was forwarded for, if any.
+    pub correlation: Option<CorrelationId>,
 }
 
 /// Server-side request: actor stores a held item into a container.
@@ -151,6 +404,8 @@ pub struct ItemStoreRequest {
     pub item: Entity,
     /// The target container entity.
     pub container: Entity,
+    /// The client request this was forwarded for, if any.
+    pub correlation: Option<CorrelationId>,
 }
 
 /// Server-side request: actor takes an item from a container into their hand.
@@ -162,6 +417,67 @@ pub struct ItemTakeRequest {
     pub item: Entity,
     /// The container that currently holds the item.
     pub container: Entity,
+    /// The client request this was forwarded for, if any.
+    pub correlation: Option<CorrelationId>,
+}
+
+/// Server-side request: put a loose item lying in the world straight into a
+/// container, without anyone carrying it there.  Used for things made at a
+/// crafting station.
+#[derive(Message, Clone, Debug)]
+pub struct ItemInsertRequest {
+    /// The item entity to put away (must not be held or stored).
+    pub item: Entity,
+    /// The target container entity.
+    pub container: Entity,
+}
+
+/// Why the server refused an item request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SchemaRead, SchemaWrite)]
+pub enum ItemRejection {
+    /// The entity named as the item is not an [`Item`].
+    NotAnItem,
+    /// The item to pick up is already held or stored.
+    AlreadyHeld,
+    /// The item has no physics to stash while it is held.
+    NoPhysics,
+    /// The actor or the container is not placed in the world.
+    NotPlaced,
+    /// The item, drop position or container is beyond the
+    /// [`InteractionRange`].
+    OutOfRange,
+    /// The actor has no hand with a free slot.
+    HandsFull,
+    /// The item is not in the actor's hand.
+    NotHeld,
+    /// The entity named as the container has no [`Container`].
+    NotAContainer,
+    /// The item is not in the container it is taken from.
+    NotInContainer,
+    /// The target container has no free slot.
+    ContainerFull,
+    /// The throw was aimed at the hand it leaves from.
+    NoAim,
+}
+
+/// Server-side: how an item request carrying a [`CorrelationId`] ended.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct ItemRequestHandled {
+    pub correlation: CorrelationId,
+    pub outcome: Result<(), ItemRejection>,
+}
+
+/// Server-side request: spawn `count` units of a stackable item kind at a
+/// world position, split into as many stacks as [`ItemCatalog::max_stack`]
+/// requires.  Used to hand materials back, e.g. when a wall is deconstructed.
+#[derive(Message, Clone, Debug)]
+pub struct StackSpawnRequest {
+    /// [`Thing`] kind of the item to spawn.
+    pub kind: u16,
+    /// Total number of units to spawn.
+    pub count: u32,
+    /// World position where the stacks appear.
+    pub position: Vec3,
 }
 
 // ── Item action / wire events ─────────────────────────────────────────────────
@@ -200,6 +516,8 @@ pub enum ItemEvent {
     /// Item was taken from a non-hand container into a hand slot.
     /// `holder` is the [`NetId`] of the creature that took it.
     Taken { item: NetId, holder: NetId },
+    /// The [`Stack`] count of an item changed (or was first set).
+    StackChanged { item: NetId, count: u32 },
 }
 
 /// Intermediate buffer that `handle_items_lifecycle` fills with decoded
@@ -214,6 +532,11 @@ pub const ITEMS_STREAM_TAG: u8 = 5;
 pub enum ItemsStreamMessage {
     /// An item operation occurred; clients apply the corresponding state change.
     ItemEvent(ItemEvent),
+    /// The server's whole [`ItemCatalog`], replacing the client's.
+    Catalog(Vec<CatalogEntry>),
+    /// What a world container holds; clients reconcile their [`Container`]
+    /// slots against it.
+    ContainerState(ContainerState),
 }
 
 // ── Systems ───────────────────────────────────────────────────────────────────
@@ -227,27 +550,35 @@ fn init_hand_containers(mut commands: Commands, query: Query<Entity, Added<HandS
     }
 }
 
-/// Server system that processes all four item interaction request events.
+/// Server system that processes all five item interaction request events.
 ///
 /// For each request it:
 /// 1. Validates that all referenced entities exist and constraints are met
 ///    (range, space, item marker, physics presence, stashed physics for drop).
-/// 2. Executes the operation via `Commands`.
+///    Items to pick up lie in the world, so they are looked up among the
+///    things around the actor in the [`SpatialIndex`].
+/// 2. Executes the operation with the transitions in [`item_state`], which
+///    clients replay in [`handle_item_event`].
 /// 3. Fires an [`ItemActionEvent`] so other systems (e.g. replication) can react.
 ///
-/// Validation failures are logged as warnings and the request is silently
-/// dropped — no error is sent back to the client in this iteration.
+/// Validation failures are logged as warnings.  Requests forwarded for a
+/// client carry its request's [`CorrelationId`]: they are handled inside a
+/// span with that id, and their outcome, with the [`ItemRejection`] of a
+/// refusal, is written as an [`ItemRequestHandled`].
 ///
+/// Runs in [`SimulationTick`] so requests are applied at the fixed tick rate.
 /// The system is gated on [`Server`] so it only runs in server builds; on
 /// clients no request messages will be written and the resource is absent.
 #[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn handle_item_interaction(
     mut commands: Commands,
     interaction_range: Res<InteractionRange>,
+    spatial: Res<SpatialIndex>,
     mut pickup_req: MessageReader<ItemPickupRequest>,
     mut drop_req: MessageReader<ItemDropRequest>,
     mut store_req: MessageReader<ItemStoreRequest>,
     mut take_req: MessageReader<ItemTakeRequest>,
+    mut insert_req: MessageReader<ItemInsertRequest>,
     transforms: Query<&GlobalTransform>,
     children: Query<&Children>,
     hand_slot_q: Query<Entity, With<HandSlot>>,
@@ -261,339 +592,399 @@ fn handle_item_interaction(
         ),
         With<Item>,
     >,
+    hold_transforms: item_state::HoldTransforms,
     mut action_events: MessageWriter<ItemActionEvent>,
+    mut handled: MessageWriter<ItemRequestHandled>,
 ) {
     let range = interaction_range.0;
+    let mut report = |correlation: Option<CorrelationId>, outcome| {
+        if let Some(correlation) = correlation {
+            handled.write(ItemRequestHandled {
+                correlation,
+                outcome,
+            });
+        }
+    };
 
     // Collect events first to avoid simultaneous mutable borrows on readers.
     let pickups: Vec<_> = pickup_req.read().cloned().collect();
     let drops: Vec<_> = drop_req.read().cloned().collect();
     let stores: Vec<_> = store_req.read().cloned().collect();
     let takes: Vec<_> = take_req.read().cloned().collect();
+    let inserts: Vec<_> = insert_req.read().cloned().collect();
 
     // ── Pickup ────────────────────────────────────────────────────────────────
     for req in pickups {
-        // Validate: item must have Item component.
-        let Ok((maybe_collider, maybe_gravity, maybe_stash, maybe_parent)) = items_q.get(req.item)
-        else {
-            warn!("ItemPickupRequest: entity {:?} is not an Item", req.item);
-            continue;
-        };
-
-        // Validate: item must not already be held / stashed.
-        if maybe_stash.is_some() || maybe_parent.is_some() {
-            warn!(
-                "ItemPickupRequest: item {:?} is already held or parented — ignoring",
-                req.item
-            );
-            continue;
-        }
+        let span = request_span(req.correlation);
+        let _entered = span.enter();
+        let outcome = 'request: {
+            // Validate: item must have Item component.
+            let Ok((maybe_collider, maybe_gravity, maybe_stash, maybe_parent)) =
+                items_q.get(req.item)
+            else {
+                warn!("ItemPickupRequest: entity {:?} is not an Item", req.item);
+                break 'request Err(ItemRejection::NotAnItem);
+            };
 
-        // Validate: item must have its own Collider and GravityScale so that
-        // physics can be faithfully stashed and restored.  Fabricating defaults
-        // here would make an originally non-physical item become a dynamic
-        // rigid body after a pickup/drop cycle.
-        let (Some(collider), Some(gravity)) = (maybe_collider, maybe_gravity) else {
-            warn!(
-                "ItemPickupRequest: item {:?} is missing Collider or GravityScale — cannot stash physics",
-                req.item
-            );
-            continue;
-        };
+            // Validate: item must not already be held / stashed.
+            if maybe_stash.is_some() || maybe_parent.is_some() {
+                warn!(
+                    "ItemPickupRequest: item {:?} is already held or parented — ignoring",
+                    req.item
+                );
+                break 'request Err(ItemRejection::AlreadyHeld);
+            }
 
-        // Validate: actor and item must have transforms for range check.
-        let (Ok(actor_gt), Ok(item_gt)) = (transforms.get(req.actor), transforms.get(req.item))
-        else {
-            warn!("ItemPickupRequest: actor or item has no GlobalTransform");
-            continue;
-        };
-        let distance = actor_gt.translation().distance(item_gt.translation());
-        if distance > range {
-            warn!(
-                "ItemPickupRequest: item {:?} is out of range ({:.2} > {:.2})",
-                req.item, distance, range
-            );
-            continue;
-        }
+            // Validate: item must have its own Collider and GravityScale so that
+            // physics can be faithfully stashed and restored.  Fabricating defaults
+            // here would make an originally non-physical item become a dynamic
+            // rigid body after a pickup/drop cycle.
+            let (Some(collider), Some(gravity)) = (maybe_collider, maybe_gravity) else {
+                warn!(
+                    "ItemPickupRequest: item {:?} is missing Collider or GravityScale — cannot stash physics",
+                    req.item
+                );
+                break 'request Err(ItemRejection::NoPhysics);
+            };
 
-        // Find an actor hand slot that has a Container with free space.
-        let Some(hand_entity) =
-            find_hand_slot_with_space(req.actor, &children, &hand_slot_q, &containers)
-        else {
-            warn!(
-                "ItemPickupRequest: actor {:?} has no hand with free space",
-                req.actor
-            );
-            continue;
-        };
+            // Validate: item must be among the things within range of the actor.
+            let Ok(actor_gt) = transforms.get(req.actor) else {
+                warn!("ItemPickupRequest: actor has no GlobalTransform");
+                break 'request Err(ItemRejection::NotPlaced);
+            };
+            if !spatial
+                .query_within(actor_gt.translation(), range)
+                .any(|(entity, _)| entity == req.item)
+            {
+                warn!(
+                    "ItemPickupRequest: item {:?} is out of range ({:.2})",
+                    req.item, range
+                );
+                break 'request Err(ItemRejection::OutOfRange);
+            }
 
-        // Stash physics and reparent.
-        commands
-            .entity(req.item)
-            .insert(StashedPhysics {
-                collider: collider.clone(),
-                gravity: *gravity,
-            })
-            .remove::<(RigidBody, Collider, LinearVelocity, GravityScale)>()
-            // Reset local transform so the item aligns with the hand anchor.
-            .insert((Transform::IDENTITY, ChildOf(hand_entity)));
+            // Find an actor hand slot that has a Container with free space.
+            let Some(hand_entity) =
+                find_hand_slot_with_space(req.actor, &children, &hand_slot_q, &containers)
+            else {
+                warn!(
+                    "ItemPickupRequest: actor {:?} has no hand with free space",
+                    req.actor
+                );
+                break 'request Err(ItemRejection::HandsFull);
+            };
 
-        // Update hand container immediately (before commands are applied).
-        if let Ok(mut container) = containers.get_mut(hand_entity) {
-            container.insert(req.item);
-        }
+            item_state::apply_pickup(
+                &mut commands,
+                &mut containers,
+                req.item,
+                item_state::stash(Some(collider), Some(gravity)),
+                hand_entity,
+                hold_transforms.of(req.item),
+            );
 
-        action_events.write(ItemActionEvent::PickedUp {
-            item: req.item,
-            hand: hand_entity,
-        });
+            action_events.write(ItemActionEvent::PickedUp {
+                item: req.item,
+                hand: hand_entity,
+            });
+            Ok(())
+        };
+        report(req.correlation, outcome);
     }
 
     // ── Drop ──────────────────────────────────────────────────────────────────
     for req in drops {
-        // Validate: item must have Item component with StashedPhysics.
-        let Ok((_, _, maybe_stash, _)) = items_q.get(req.item) else {
-            warn!("ItemDropRequest: entity {:?} is not an Item", req.item);
-            continue;
-        };
-        let Some(stash) = maybe_stash else {
-            warn!(
-                "ItemDropRequest: item {:?} has no StashedPhysics (not held)",
-                req.item
-            );
-            continue;
-        };
-
-        // Validate: drop_position must be within interaction range of the actor.
-        let Ok(actor_gt) = transforms.get(req.actor) else {
-            warn!(
-                "ItemDropRequest: actor {:?} has no GlobalTransform",
-                req.actor
-            );
-            continue;
-        };
-        let drop_distance = actor_gt.translation().distance(req.drop_position);
-        if drop_distance > range {
-            warn!(
-                "ItemDropRequest: drop_position {:?} is out of range ({:.2} > {:.2})",
-                req.drop_position, drop_distance, range
-            );
-            continue;
-        }
+        let span = request_span(req.correlation);
+        let _entered = span.enter();
+        let outcome = 'request: {
+            // Validate: item must have Item component with StashedPhysics.
+            let Ok((_, _, maybe_stash, _)) = items_q.get(req.item) else {
+                warn!("ItemDropRequest: entity {:?} is not an Item", req.item);
+                break 'request Err(ItemRejection::NotAnItem);
+            };
+            let Some(stash) = maybe_stash else {
+                warn!(
+                    "ItemDropRequest: item {:?} has no StashedPhysics (not held)",
+                    req.item
+                );
+                break 'request Err(ItemRejection::NotHeld);
+            };
 
-        // Find the hand slot container that holds this item.
-        let Some(hand_entity) =
-            find_hand_slot_containing(req.actor, req.item, &children, &hand_slot_q, &containers)
-        else {
-            warn!(
-                "ItemDropRequest: item {:?} is not in actor {:?}'s hand container",
-                req.item, req.actor
-            );
-            continue;
-        };
+            // Validate: drop_position must be within interaction range of the actor.
+            let Ok(actor_gt) = transforms.get(req.actor) else {
+                warn!(
+                    "ItemDropRequest: actor {:?} has no GlobalTransform",
+                    req.actor
+                );
+                break 'request Err(ItemRejection::NotPlaced);
+            };
+            let drop_distance = actor_gt.translation().distance(req.drop_position);
+            if drop_distance > range {
+                warn!(
+                    "ItemDropRequest: drop_position {:?} is out of range ({:.2} > {:.2})",
+                    req.drop_position, drop_distance, range
+                );
+                break 'request Err(ItemRejection::OutOfRange);
+            }
 
-        let stash = stash.clone();
-
-        // Restore physics, deparent, place slightly above the drop position
-        // so the item doesn't clip into the ground and get ejected by physics.
-        let spawn_pos = req.drop_position + Vec3::Y * 0.5;
-        commands
-            .entity(req.item)
-            .remove::<ChildOf>()
-            .remove::<StashedPhysics>()
-            .insert(Transform::from_translation(spawn_pos))
-            .insert((
-                RigidBody::Dynamic,
-                stash.collider,
-                stash.gravity,
-                LinearVelocity::default(),
-            ));
+            // Find the hand slot container that holds this item.
+            let Some(hand_entity) = find_hand_slot_containing(
+                req.actor,
+                req.item,
+                &children,
+                &hand_slot_q,
+                &containers,
+            ) else {
+                warn!(
+                    "ItemDropRequest: item {:?} is not in actor {:?}'s hand container",
+                    req.item, req.actor
+                );
+                break 'request Err(ItemRejection::NotHeld);
+            };
 
-        // Update hand container immediately.
-        if let Ok(mut container) = containers.get_mut(hand_entity) {
-            container.remove(req.item);
-        }
+            // Place slightly above the drop position so the item doesn't clip
+            // into the ground and get ejected by physics.
+            let spawn_pos = req.drop_position + Vec3::Y * 0.5;
+            item_state::apply_drop(
+                &mut commands,
+                &mut containers,
+                req.item,
+                stash.clone(),
+                Some(hand_entity),
+                spawn_pos,
+            );
 
-        action_events.write(ItemActionEvent::Dropped {
-            item: req.item,
-            position: spawn_pos,
-        });
+            action_events.write(ItemActionEvent::Dropped {
+                item: req.item,
+                position: spawn_pos,
+            });
+            Ok(())
+        };
+        report(req.correlation, outcome);
     }
 
     // ── Store ─────────────────────────────────────────────────────────────────
     for req in stores {
-        // Validate: item must be an Item.
-        if items_q.get(req.item).is_err() {
-            warn!("ItemStoreRequest: entity {:?} is not an Item", req.item);
-            continue;
-        }
+        let span = request_span(req.correlation);
+        let _entered = span.enter();
+        let outcome = 'request: {
+            // Validate: item must be an Item.
+            let Ok((maybe_collider, maybe_gravity, _, _)) = items_q.get(req.item) else {
+                warn!("ItemStoreRequest: entity {:?} is not an Item", req.item);
+                break 'request Err(ItemRejection::NotAnItem);
+            };
 
-        // Validate: item must be in actor's hand container.
-        let Some(hand_entity) =
-            find_hand_slot_containing(req.actor, req.item, &children, &hand_slot_q, &containers)
-        else {
-            warn!(
-                "ItemStoreRequest: item {:?} is not in actor {:?}'s hand container",
-                req.item, req.actor
-            );
-            continue;
-        };
+            // Validate: item must be in actor's hand container.
+            let Some(hand_entity) = find_hand_slot_containing(
+                req.actor,
+                req.item,
+                &children,
+                &hand_slot_q,
+                &containers,
+            ) else {
+                warn!(
+                    "ItemStoreRequest: item {:?} is not in actor {:?}'s hand container",
+                    req.item, req.actor
+                );
+                break 'request Err(ItemRejection::NotHeld);
+            };
 
-        // Validate: distance to target container — both transforms are required.
-        match (transforms.get(req.actor), transforms.get(req.container)) {
-            (Ok(actor_gt), Ok(container_gt)) => {
-                let distance = actor_gt.translation().distance(container_gt.translation());
-                if distance > range {
+            // Validate: distance to target container — both transforms are required.
+            match (transforms.get(req.actor), transforms.get(req.container)) {
+                (Ok(actor_gt), Ok(container_gt)) => {
+                    let distance = actor_gt.translation().distance(container_gt.translation());
+                    if distance > range {
+                        warn!(
+                            "ItemStoreRequest: container {:?} is out of range ({:.2} > {:.2})",
+                            req.container, distance, range
+                        );
+                        break 'request Err(ItemRejection::OutOfRange);
+                    }
+                }
+                (actor_res, container_res) => {
                     warn!(
-                        "ItemStoreRequest: container {:?} is out of range ({:.2} > {:.2})",
-                        req.container, distance, range
+                        "ItemStoreRequest: missing GlobalTransform (actor missing: {}, container missing: {}) — rejecting",
+                        actor_res.is_err(),
+                        container_res.is_err()
                     );
-                    continue;
+                    break 'request Err(ItemRejection::NotPlaced);
                 }
             }
-            (actor_res, container_res) => {
-                warn!(
-                    "ItemStoreRequest: missing GlobalTransform (actor missing: {}, container missing: {}) — rejecting",
-                    actor_res.is_err(),
-                    container_res.is_err()
-                );
-                continue;
-            }
-        }
-
-        // Validate: target container has space.
-        let has_space = containers
-            .get(req.container)
-            .map(|c| c.has_space())
-            .unwrap_or(false);
-        if !has_space {
-            warn!("ItemStoreRequest: container {:?} is full", req.container);
-            continue;
-        }
 
-        // Deparent, hide, update containers.
-        commands
-            .entity(req.item)
-            .remove::<ChildOf>()
-            .insert(Visibility::Hidden);
+            // Validate: target container has space.
+            let has_space = containers
+                .get(req.container)
+                .map(|c| c.has_space())
+                .unwrap_or(false);
+            if !has_space {
+                warn!("ItemStoreRequest: container {:?} is full", req.container);
+                break 'request Err(ItemRejection::ContainerFull);
+            }
 
-        if let Ok(mut hand_container) = containers.get_mut(hand_entity) {
-            hand_container.remove(req.item);
-        }
-        if let Ok(mut target_container) = containers.get_mut(req.container) {
-            target_container.insert(req.item);
-        }
+            item_state::apply_store(
+                &mut commands,
+                &mut containers,
+                req.item,
+                item_state::stash(maybe_collider, maybe_gravity),
+                Some(hand_entity),
+                req.container,
+            );
 
-        action_events.write(ItemActionEvent::Stored {
-            item: req.item,
-            container: req.container,
-        });
+            action_events.write(ItemActionEvent::Stored {
+                item: req.item,
+                container: req.container,
+            });
+            Ok(())
+        };
+        report(req.correlation, outcome);
     }
 
     // ── Take ──────────────────────────────────────────────────────────────────
     for req in takes {
-        // Validate: item must be an Item.
-        let Ok((maybe_collider, maybe_gravity, _, _)) = items_q.get(req.item) else {
-            warn!("ItemTakeRequest: entity {:?} is not an Item", req.item);
-            continue;
-        };
+        let span = request_span(req.correlation);
+        let _entered = span.enter();
+        let outcome = 'request: {
+            // Validate: item must be an Item.
+            let Ok((maybe_collider, maybe_gravity, maybe_stashed, _)) = items_q.get(req.item)
+            else {
+                warn!("ItemTakeRequest: entity {:?} is not an Item", req.item);
+                break 'request Err(ItemRejection::NotAnItem);
+            };
 
-        // Validate: item must be in the specified container.
-        match containers.get(req.container) {
-            Ok(container) => {
-                if !container.contains(req.item) {
+            // Validate: item must be in the specified container.
+            match containers.get(req.container) {
+                Ok(container) => {
+                    if !container.contains(req.item) {
+                        warn!(
+                            "ItemTakeRequest: item {:?} is not in container {:?}",
+                            req.item, req.container
+                        );
+                        break 'request Err(ItemRejection::NotInContainer);
+                    }
+                }
+                Err(_) => {
                     warn!(
-                        "ItemTakeRequest: item {:?} is not in container {:?}",
-                        req.item, req.container
+                        "ItemTakeRequest: entity {:?} has no Container component (requested as container for item {:?})",
+                        req.container, req.item
                     );
-                    continue;
+                    break 'request Err(ItemRejection::NotAContainer);
                 }
             }
-            Err(_) => {
+
+            // Validate: actor must have a hand with space.
+            let Some(hand_entity) =
+                find_hand_slot_with_space(req.actor, &children, &hand_slot_q, &containers)
+            else {
                 warn!(
-                    "ItemTakeRequest: entity {:?} has no Container component (requested as container for item {:?})",
-                    req.container, req.item
+                    "ItemTakeRequest: actor {:?} has no hand with free space",
+                    req.actor
                 );
-                continue;
-            }
-        }
-
-        // Validate: actor must have a hand with space.
-        let Some(hand_entity) =
-            find_hand_slot_with_space(req.actor, &children, &hand_slot_q, &containers)
-        else {
-            warn!(
-                "ItemTakeRequest: actor {:?} has no hand with free space",
-                req.actor
-            );
-            continue;
-        };
+                break 'request Err(ItemRejection::HandsFull);
+            };
 
-        // Validate: distance to container — both transforms are required.
-        match (transforms.get(req.actor), transforms.get(req.container)) {
-            (Ok(actor_gt), Ok(container_gt)) => {
-                let distance = actor_gt.translation().distance(container_gt.translation());
-                if distance > range {
+            // Validate: distance to container — both transforms are required.
+            match (transforms.get(req.actor), transforms.get(req.container)) {
+                (Ok(actor_gt), Ok(container_gt)) => {
+                    let distance = actor_gt.translation().distance(container_gt.translation());
+                    if distance > range {
+                        warn!(
+                            "ItemTakeRequest: container {:?} is out of range ({:.2} > {:.2})",
+                            req.container, distance, range
+                        );
+                        break 'request Err(ItemRejection::OutOfRange);
+                    }
+                }
+                (actor_res, container_res) => {
                     warn!(
-                        "ItemTakeRequest: container {:?} is out of range ({:.2} > {:.2})",
-                        req.container, distance, range
+                        "ItemTakeRequest: missing GlobalTransform (actor missing: {}, container missing: {}) — rejecting",
+                        actor_res.is_err(),
+                        container_res.is_err()
                     );
-                    continue;
+                    break 'request Err(ItemRejection::NotPlaced);
                 }
             }
-            (actor_res, container_res) => {
+
+            // Validate: item must have physics to restore on a later drop.  A
+            // stored item normally has them stashed already; one that still has
+            // them live (inserted directly into the container) has them stashed
+            // now, so a dynamic rigid body is never parented under a hand slot
+            // (which would cause jitter/collisions).
+            let stash = item_state::stash(maybe_collider, maybe_gravity);
+            if stash.is_none() && maybe_stashed.is_none() {
                 warn!(
-                    "ItemTakeRequest: missing GlobalTransform (actor missing: {}, container missing: {}) — rejecting",
-                    actor_res.is_err(),
-                    container_res.is_err()
+                    "ItemTakeRequest: item {:?} has neither physics nor StashedPhysics — rejecting",
+                    req.item
                 );
-                continue;
+                break 'request Err(ItemRejection::NoPhysics);
             }
-        }
 
-        // Validate: item must have the required physics components to be taken.
-        let (Some(collider), Some(gravity)) = (maybe_collider, maybe_gravity) else {
+            item_state::apply_take(
+                &mut commands,
+                &mut containers,
+                req.item,
+                stash,
+                Some(req.container),
+                hand_entity,
+                hold_transforms.of(req.item),
+            );
+
+            action_events.write(ItemActionEvent::Taken {
+                item: req.item,
+                hand: hand_entity,
+            });
+            Ok(())
+        };
+        report(req.correlation, outcome);
+    }
+
+    // ── Insert ────────────────────────────────────────────────────────────────
+    for req in inserts {
+        // Validate: item must be a loose Item with physics to stash.
+        let Ok((Some(collider), Some(gravity), None, None)) = items_q.get(req.item) else {
             warn!(
-                "ItemTakeRequest: item {:?} is missing Collider and/or GravityScale — rejecting",
+                "ItemInsertRequest: entity {:?} is not a loose Item with physics",
                 req.item
             );
             continue;
         };
 
-        // Remove from source container now that we know the item can be held.
-        if let Ok(mut src_container) = containers.get_mut(req.container) {
-            src_container.remove(req.item);
+        let Ok(container) = containers.get(req.container) else {
+            warn!(
+                "ItemInsertRequest: entity {:?} has no Container component",
+                req.container
+            );
+            continue;
+        };
+        if !container.has_space() {
+            warn!("ItemInsertRequest: container {:?} is full", req.container);
+            continue;
         }
 
-        // Ensure the item is in a non-physical "held" state: remove any
-        // physics components so a dynamic rigid body is never parented under a
-        // hand slot (which would cause jitter/collisions).  Stash the physics
-        // components so they can be restored on drop (same rule as pickup).
-        commands
-            .entity(req.item)
-            .insert(StashedPhysics {
-                collider: collider.clone(),
-                gravity: *gravity,
-            })
-            .remove::<(RigidBody, Collider, LinearVelocity, GravityScale)>();
-
-        // Show and reparent to hand, resetting local transform to the hand anchor.
-        commands.entity(req.item).insert((
-            Visibility::Inherited,
-            Transform::IDENTITY,
-            ChildOf(hand_entity),
-        ));
-        if let Ok(mut hand_container) = containers.get_mut(hand_entity) {
-            hand_container.insert(req.item);
-        }
+        item_state::apply_store(
+            &mut commands,
+            &mut containers,
+            req.item,
+            item_state::stash(Some(collider), Some(gravity)),
+            None,
+            req.container,
+        );
 
-        action_events.write(ItemActionEvent::Taken {
+        action_events.write(ItemActionEvent::Stored {
             item: req.item,
-            hand: hand_entity,
+            container: req.container,
         });
     }
 }
 
+/// Span grouping the log lines of a request forwarded with `correlation`
+/// with those of the module it came from.
+fn request_span(correlation: Option<CorrelationId>) -> Span {
+    correlation.map_or_else(
+        Span::none,
+        |correlation| info_span!("request", correlation = %correlation),
+    )
+}
+
 /// Find the first hand-slot entity that is a child of `actor`, has a
 /// [`Container`], and has at least one free slot.
 fn find_hand_slot_with_space(
@@ -639,6 +1030,193 @@ fn find_hand_slot_containing(
     None
 }
 
+// ── Stacks ────────────────────────────────────────────────────────────────────
+
+/// System parameter for reading and consuming the stacks an actor holds in
+/// its [`HandSlot`] containers.
+///
+/// Used by server-side systems that charge materials for an action, e.g.
+/// wall construction in the interactions module.
+#[derive(SystemParam)]
+pub struct HeldStacks<'w, 's> {
+    children: Query<'w, 's, &'static Children>,
+    hands: Query<'w, 's, &'static Container, With<HandSlot>>,
+    stacks: Query<'w, 's, (&'static Thing, &'static mut Stack)>,
+}
+
+impl HeldStacks<'_, '_> {
+    /// Item entities currently held in any of `actor`'s hands.
+    fn held(&self, actor: Entity) -> Vec<Entity> {
+        held_items(&self.children, &self.hands, actor)
+    }
+
+    /// Total units of `kind` held by `actor`.
+    pub fn count(&self, actor: Entity, kind: u16) -> u32 {
+        self.held(actor)
+            .into_iter()
+            .filter_map(|item| self.stacks.get(item).ok())
+            .filter(|(thing, _)| thing.kind == kind)
+            .map(|(_, stack)| stack.count)
+            .sum()
+    }
+
+    /// Removes `amount` units of `kind` from the stacks `actor` holds.
+    ///
+    /// Returns `false` and leaves every stack untouched if the actor holds
+    /// fewer than `amount` units.  Stacks drained to zero are left for
+    /// [`despawn_empty_stacks`] to clean up.
+    pub fn consume(&mut self, actor: Entity, kind: u16, amount: u32) -> bool {
+        if self.count(actor, kind) < amount {
+            return false;
+        }
+        let mut remaining = amount;
+        for item in self.held(actor) {
+            if remaining == 0 {
+                break;
+            }
+            let Ok((thing, mut stack)) = self.stacks.get_mut(item) else {
+                continue;
+            };
+            if thing.kind != kind {
+                continue;
+            }
+            let taken = stack.count.min(remaining);
+            stack.count -= taken;
+            remaining -= taken;
+        }
+        true
+    }
+}
+
+/// Read-only counterpart of [`HeldStacks`] for client-side checks, e.g.
+/// whether the local player can afford a construction blueprint.  Stack
+/// counts and hand contents are replicated, so clients see what the server
+/// will charge.
+#[derive(SystemParam)]
+pub struct HeldStackCounts<'w, 's> {
+    children: Query<'w, 's, &'static Children>,
+    hands: Query<'w, 's, &'static Container, With<HandSlot>>,
+    stacks: Query<'w, 's, (&'static Thing, &'static Stack)>,
+}
+
+impl HeldStackCounts<'_, '_> {
+    /// Total units of `kind` held by `actor`.
+    pub fn count(&self, actor: Entity, kind: u16) -> u32 {
+        held_items(&self.children, &self.hands, actor)
+            .into_iter()
+            .filter_map(|item| self.stacks.get(item).ok())
+            .filter(|(thing, _)| thing.kind == kind)
+            .map(|(_, stack)| stack.count)
+            .sum()
+    }
+}
+
+/// Item entities held in any of `actor`'s hands.
+fn held_items(
+    children: &Query<&Children>,
+    hands: &Query<&Container, With<HandSlot>>,
+    actor: Entity,
+) -> Vec<Entity> {
+    let Ok(actor_children) = children.get(actor) else {
+        return Vec::new();
+    };
+    actor_children
+        .iter()
+        .filter_map(|child| hands.get(child).ok())
+        .flat_map(|container| container.slots.iter().filter_map(|slot| *slot))
+        .collect()
+}
+
+/// Server system that spawns the stacks requested via [`StackSpawnRequest`]
+/// and broadcasts them in `EntityBatch`es so clients create replicas.
+///
+/// The stack counts themselves reach clients through
+/// [`broadcast_stack_changes`].
+fn handle_stack_spawn_requests(
+    mut commands: Commands,
+    mut requests: MessageReader<StackSpawnRequest>,
+    mut server: ResMut<Server>,
+    catalog: Res<ItemCatalog>,
+    things_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+) {
+    for req in requests.read() {
+        let max_stack = catalog.max_stack(req.kind);
+        let mut remaining = req.count;
+        let mut spawned = Vec::new();
+        while remaining > 0 {
+            let count = remaining.min(max_stack);
+            remaining -= count;
+            let (entity, net_id) = spawn_thing(&mut commands, &mut server, req.kind, req.position);
+            commands.entity(entity).insert(Stack::new(count));
+            spawned.push(EntitySpawnedData {
+                net_id,
+                kind: req.kind,
+                position: req.position.into(),
+                velocity: [0.0, 0.0, 0.0],
+                owner: None,
+                name: None,
+            });
+        }
+        let Some(ref sender) = things_sender else {
+            continue;
+        };
+        for batch in spawned.chunks(SPAWN_BATCH_SIZE) {
+            if let Err(e) = sender.broadcast(&ThingsStreamMessage::EntityBatch(batch.to_vec())) {
+                error!(
+                    "Failed to broadcast EntityBatch of {} stacks: {e}",
+                    batch.len()
+                );
+            }
+        }
+    }
+}
+
+/// Server system that despawns stacks whose count reached zero and tells
+/// clients to drop their replicas.
+fn despawn_empty_stacks(
+    mut commands: Commands,
+    stacks: Query<(Entity, &Stack, Option<&NetId>), Changed<Stack>>,
+    mut net_id_index: ResMut<NetIdIndex>,
+    things_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+) {
+    for (entity, stack, net_id) in stacks.iter() {
+        if stack.count > 0 {
+            continue;
+        }
+        commands.entity(entity).despawn();
+        let Some(&net_id) = net_id else {
+            continue;
+        };
+        net_id_index.0.remove(&net_id);
+        if let Some(ref sender) = things_sender
+            && let Err(e) = sender.broadcast(&ThingsStreamMessage::EntityDespawned {
+                net_id,
+                reason: DespawnReason::Consumed,
+            })
+        {
+            error!(
+                "Failed to broadcast EntityDespawned for NetId({}): {e}",
+                net_id.0
+            );
+        }
+    }
+}
+
+/// Clears despawned items out of every [`Container`] slot so hands and
+/// containers do not keep pointing at dead entities.
+fn release_despawned_items(
+    mut removed: RemovedComponents<Item>,
+    mut containers: Query<&mut Container>,
+) {
+    for item in removed.read() {
+        for mut container in containers.iter_mut() {
+            if container.contains(item) {
+                container.remove(item);
+            }
+        }
+    }
+}
+
 // ── Client-side item event handler ───────────────────────────────────────────
 
 /// Applies [`ItemEvent`] messages that arrived on stream 5 to the local ECS state.
@@ -647,19 +1225,31 @@ fn find_hand_slot_containing(
 /// `handle_items_lifecycle` in `PreUpdate`).  Runs on clients only.
 ///
 /// - **PickedUp**: strip physics, insert [`StashedPhysics`], reparent item to
-///   the holder creature's [`HandSlot`], update the hand's [`Container`] slots.
+///   the holder creature's [`HandSlot`] at its [`HoldOffset`], update the
+///   hand's [`Container`] slots.
 /// - **Dropped**: restore physics from [`StashedPhysics`], deparent, set world
 ///   position, clear the former hand's [`Container`] slot.
 /// - **Stored**: strip physics if present, insert [`StashedPhysics`], deparent,
 ///   set [`Visibility::Hidden`], insert item into the target container's slots,
 ///   and tag the item with [`StoredInContainer`] for O(1) source-lookup on `Taken`.
-/// - **Taken**: show item, reparent to creature's hand, remove from the source
-///   container (via [`StoredInContainer`]), update the hand's [`Container`] slot.
-#[allow(clippy::type_complexity)]
+/// - **Taken**: show item, reparent to creature's hand at its [`HoldOffset`],
+///   remove from the source container (via [`StoredInContainer`]), update the
+///   hand's [`Container`] slot.
+/// - **StackChanged**: overwrite the item's [`Stack`] count.
+///
+/// The first four use the [`item_state`] transitions the server applied, so
+/// the item ends up with the same components on both sides.
+///
+/// A `Stored` into a full container, or a `Taken` from a container that did
+/// not hold the item, means the client has drifted; the container goes into
+/// [`ContainerResyncs`] so the server sends its [`ContainerState`].
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn handle_item_event(
     mut commands: Commands,
     mut pending: ResMut<PendingItemEvents>,
+    mut resyncs: ResMut<ContainerResyncs>,
     net_id_index: Res<NetIdIndex>,
+    net_ids: Query<&NetId>,
     mut containers: Query<&mut Container>,
     items_q: Query<
         (
@@ -673,6 +1263,7 @@ fn handle_item_event(
     >,
     children: Query<&Children>,
     hand_slot_q: Query<Entity, With<HandSlot>>,
+    hold_transforms: item_state::HoldTransforms,
 ) {
     for event in pending.0.drain(..) {
         match event {
@@ -704,21 +1295,14 @@ fn handle_item_event(
                     warn!("handle_item_event: PickedUp item entity has no Item component");
                     continue;
                 };
-                if let (Some(col), Some(grav)) = (maybe_collider, maybe_gravity) {
-                    commands
-                        .entity(item_entity)
-                        .insert(StashedPhysics {
-                            collider: col.clone(),
-                            gravity: *grav,
-                        })
-                        .remove::<(RigidBody, Collider, LinearVelocity, GravityScale)>();
-                }
-                commands
-                    .entity(item_entity)
-                    .insert((Transform::IDENTITY, ChildOf(hand_entity)));
-                if let Ok(mut container) = containers.get_mut(hand_entity) {
-                    container.insert(item_entity);
-                }
+                item_state::apply_pickup(
+                    &mut commands,
+                    &mut containers,
+                    item_entity,
+                    item_state::stash(maybe_collider, maybe_gravity),
+                    hand_entity,
+                    hold_transforms.of(item_entity),
+                );
             }
 
             ItemEvent::Dropped { item, position } => {
@@ -733,13 +1317,6 @@ fn handle_item_event(
                     warn!("handle_item_event: Dropped item entity has no Item component");
                     continue;
                 };
-                // Remove item from its hand container slot.
-                if let Some(child_of) = maybe_child_of
-                    && let Ok(mut container) = containers.get_mut(child_of.parent())
-                {
-                    container.remove(item_entity);
-                }
-                let drop_pos = Vec3::from_array(position);
                 let Some(stash) = maybe_stash.cloned() else {
                     warn!(
                         "handle_item_event: Dropped item {:?} has no StashedPhysics — \
@@ -748,17 +1325,14 @@ fn handle_item_event(
                     );
                     continue;
                 };
-                commands
-                    .entity(item_entity)
-                    .remove::<ChildOf>()
-                    .remove::<StashedPhysics>()
-                    .insert(Transform::from_translation(drop_pos))
-                    .insert((
-                        RigidBody::Dynamic,
-                        stash.collider,
-                        stash.gravity,
-                        LinearVelocity::default(),
-                    ));
+                item_state::apply_drop(
+                    &mut commands,
+                    &mut containers,
+                    item_entity,
+                    stash,
+                    maybe_child_of.map(ChildOf::parent),
+                    Vec3::from_array(position),
+                );
             }
 
             ItemEvent::Stored { item, container } => {
@@ -779,28 +1353,20 @@ fn handle_item_event(
                     warn!("handle_item_event: Stored item entity has no Item component");
                     continue;
                 };
-                // Remove item from its current hand container if held.
-                if let Some(child_of) = maybe_child_of
-                    && let Ok(mut hand_container) = containers.get_mut(child_of.parent())
-                {
-                    hand_container.remove(item_entity);
-                }
-                // Strip physics if still present (e.g. for items received during initial sync).
-                if let (Some(col), Some(grav)) = (maybe_collider, maybe_gravity) {
-                    commands
-                        .entity(item_entity)
-                        .insert(StashedPhysics {
-                            collider: col.clone(),
-                            gravity: *grav,
-                        })
-                        .remove::<(RigidBody, Collider, LinearVelocity, GravityScale)>();
-                }
-                commands
-                    .entity(item_entity)
-                    .remove::<ChildOf>()
-                    .insert((Visibility::Hidden, StoredInContainer(container_entity)));
-                if let Ok(mut target_container) = containers.get_mut(container_entity) {
-                    target_container.insert(item_entity);
+                // Physics is still present for items inserted while loose.
+                if !item_state::apply_store(
+                    &mut commands,
+                    &mut containers,
+                    item_entity,
+                    item_state::stash(maybe_collider, maybe_gravity),
+                    maybe_child_of.map(ChildOf::parent),
+                    container_entity,
+                ) {
+                    warn!(
+                        "handle_item_event: Stored into full container NetId({}) — resyncing",
+                        container.0
+                    );
+                    resyncs.request(container);
                 }
             }
 
@@ -831,35 +1397,37 @@ fn handle_item_event(
                     warn!("handle_item_event: Taken item entity has no Item component");
                     continue;
                 };
-                // Strip physics if somehow still present (shouldn't be after Stored,
-                // but guard against unexpected states).
-                if let (Some(col), Some(grav)) = (maybe_collider, maybe_gravity) {
-                    commands
-                        .entity(item_entity)
-                        .insert(StashedPhysics {
-                            collider: col.clone(),
-                            gravity: *grav,
-                        })
-                        .remove::<(RigidBody, Collider, LinearVelocity, GravityScale)>();
-                }
-                // Remove from the tracked source container (O(1) via StoredInContainer).
-                if let Some(&StoredInContainer(src_container)) = maybe_stored_in
-                    && let Ok(mut container) = containers.get_mut(src_container)
+                // The source container is tracked by StoredInContainer (O(1)).
+                let src_container = maybe_stored_in.map(|stored_in| stored_in.0);
+                if !item_state::apply_take(
+                    &mut commands,
+                    &mut containers,
+                    item_entity,
+                    item_state::stash(maybe_collider, maybe_gravity),
+                    src_container,
+                    hand_entity,
+                    hold_transforms.of(item_entity),
+                ) && let Some(&src_net_id) =
+                    src_container.and_then(|container| net_ids.get(container).ok())
                 {
-                    container.remove(item_entity);
-                }
-                commands
-                    .entity(item_entity)
-                    .remove::<StoredInContainer>()
-                    .insert((
-                        Visibility::Inherited,
-                        Transform::IDENTITY,
-                        ChildOf(hand_entity),
-                    ));
-                if let Ok(mut hand_container) = containers.get_mut(hand_entity) {
-                    hand_container.insert(item_entity);
+                    warn!(
+                        "handle_item_event: Taken item NetId({}) was not in container NetId({}) — resyncing",
+                        item.0, src_net_id.0
+                    );
+                    resyncs.request(src_net_id);
                 }
             }
+
+            ItemEvent::StackChanged { item, count } => {
+                let Some(&item_entity) = net_id_index.0.get(&item) else {
+                    warn!(
+                        "handle_item_event: StackChanged item NetId({}) not found",
+                        item.0
+                    );
+                    continue;
+                };
+                commands.entity(item_entity).insert(Stack::new(count));
+            }
         }
     }
 }
@@ -867,22 +1435,25 @@ fn handle_item_event(
 // ── Client-side stream receiver ───────────────────────────────────────────────
 
 /// Drains incoming [`ItemsStreamMessage`] frames from stream 5 and buffers them
-/// in [`PendingItemEvents`] for `handle_item_event` to process in `Update`.
+/// in [`PendingItemEvents`] and [`PendingContainerStates`] for
+/// `handle_item_event` and `apply_container_states` to process in `Update`.
 fn handle_items_lifecycle(
     mut reader: ResMut<StreamReader<ItemsStreamMessage>>,
     mut pending: ResMut<PendingItemEvents>,
-    server: Option<Res<Server>>,
+    mut pending_states: ResMut<PendingContainerStates>,
+    mut catalog: ResMut<ItemCatalog>,
 ) {
-    // On a listen-server the item state is already authoritative; applying
-    // replicated events again would double-apply operations.
-    if server.is_some() {
-        return;
-    }
     for msg in reader.drain() {
         match msg {
             ItemsStreamMessage::ItemEvent(ie) => {
                 pending.0.push(ie);
             }
+            ItemsStreamMessage::Catalog(entries) => {
+                *catalog = entries.into_iter().collect();
+            }
+            ItemsStreamMessage::ContainerState(state) => {
+                pending_states.0.push(state);
+            }
         }
     }
 }
@@ -890,16 +1461,18 @@ fn handle_items_lifecycle(
 // ── Server-side broadcast ─────────────────────────────────────────────────────
 
 /// Reads [`ItemActionEvent`] messages (fired by `handle_item_interaction`),
-/// resolves entity references to [`NetId`]s, and broadcasts the corresponding
-/// [`ItemEvent`] on stream 5 to all connected clients.
+/// resolves entity references to [`NetId`]s, and sends the corresponding
+/// [`ItemEvent`] on stream 5 to every client that can see all entities involved
+/// (see [`ReplicationScope`]).
 fn broadcast_item_event(
     mut action_events: MessageReader<ItemActionEvent>,
     stream_sender: Res<StreamSender<ItemsStreamMessage>>,
     net_ids: Query<&NetId>,
     child_of_q: Query<&ChildOf>,
+    scope: ReplicationScope,
 ) {
     for event in action_events.read() {
-        let msg = match event {
+        let (msg, involved) = match event {
             ItemActionEvent::PickedUp { item, hand } => {
                 let Ok(&item_net_id) = net_ids.get(*item) else {
                     warn!(
@@ -916,24 +1489,31 @@ fn broadcast_item_event(
                     );
                     continue;
                 };
-                let Ok(&holder_net_id) = net_ids.get(hand_child_of.parent()) else {
+                let holder = hand_child_of.parent();
+                let Ok(&holder_net_id) = net_ids.get(holder) else {
                     warn!("broadcast_item_event: PickedUp holder has no NetId");
                     continue;
                 };
-                ItemsStreamMessage::ItemEvent(ItemEvent::PickedUp {
-                    item: item_net_id,
-                    holder: holder_net_id,
-                })
+                (
+                    ItemsStreamMessage::ItemEvent(ItemEvent::PickedUp {
+                        item: item_net_id,
+                        holder: holder_net_id,
+                    }),
+                    vec![*item, holder],
+                )
             }
             ItemActionEvent::Dropped { item, position } => {
                 let Ok(&item_net_id) = net_ids.get(*item) else {
                     warn!("broadcast_item_event: Dropped item {:?} has no NetId", item);
                     continue;
                 };
-                ItemsStreamMessage::ItemEvent(ItemEvent::Dropped {
-                    item: item_net_id,
-                    position: (*position).into(),
-                })
+                (
+                    ItemsStreamMessage::ItemEvent(ItemEvent::Dropped {
+                        item: item_net_id,
+                        position: (*position).into(),
+                    }),
+                    vec![*item],
+                )
             }
             ItemActionEvent::Stored { item, container } => {
                 let Ok(&item_net_id) = net_ids.get(*item) else {
@@ -947,10 +1527,13 @@ fn broadcast_item_event(
                     );
                     continue;
                 };
-                ItemsStreamMessage::ItemEvent(ItemEvent::Stored {
-                    item: item_net_id,
-                    container: container_net_id,
-                })
+                (
+                    ItemsStreamMessage::ItemEvent(ItemEvent::Stored {
+                        item: item_net_id,
+                        container: container_net_id,
+                    }),
+                    vec![*item, *container],
+                )
             }
             ItemActionEvent::Taken { item, hand } => {
                 let Ok(&item_net_id) = net_ids.get(*item) else {
@@ -962,24 +1545,82 @@ fn broadcast_item_event(
                     warn!("broadcast_item_event: Taken hand {:?} has no parent", hand);
                     continue;
                 };
-                let Ok(&holder_net_id) = net_ids.get(hand_child_of.parent()) else {
+                let holder = hand_child_of.parent();
+                let Ok(&holder_net_id) = net_ids.get(holder) else {
                     warn!("broadcast_item_event: Taken holder has no NetId");
                     continue;
                 };
-                ItemsStreamMessage::ItemEvent(ItemEvent::Taken {
-                    item: item_net_id,
-                    holder: holder_net_id,
-                })
+                (
+                    ItemsStreamMessage::ItemEvent(ItemEvent::Taken {
+                        item: item_net_id,
+                        holder: holder_net_id,
+                    }),
+                    vec![*item, holder],
+                )
             }
         };
-        if let Err(e) = stream_sender.broadcast(&msg) {
+        if let Err(e) = scope.send(&stream_sender, &involved, &msg) {
             error!("broadcast_item_event: failed to broadcast: {e}");
         }
     }
 }
 
+/// Broadcasts [`ItemEvent::StackChanged`] for every stack whose count changed
+/// this frame, including newly spawned stacks, to the clients that can see it.
+fn broadcast_stack_changes(
+    stacks: Query<(Entity, &NetId, &Stack), Changed<Stack>>,
+    stream_sender: Res<StreamSender<ItemsStreamMessage>>,
+    scope: ReplicationScope,
+) {
+    for (entity, &item, stack) in stacks.iter() {
+        let msg = ItemsStreamMessage::ItemEvent(ItemEvent::StackChanged {
+            item,
+            count: stack.count,
+        });
+        if let Err(e) = scope.send(&stream_sender, &[entity], &msg) {
+            error!("broadcast_stack_changes: failed to broadcast: {e}");
+        }
+    }
+}
+
 // ── Server-side initial-sync ──────────────────────────────────────────────────
 
+/// Sends the [`ItemCatalog`] to a newly joined client, ahead of any item
+/// events.
+fn send_catalog_on_join(
+    mut player_events: MessageReader<PlayerEvent>,
+    catalog: Res<ItemCatalog>,
+    stream_sender: Res<StreamSender<ItemsStreamMessage>>,
+) {
+    for event in player_events.read() {
+        let PlayerEvent::Joined { id: from, .. } = event else {
+            continue;
+        };
+        if let Err(e) =
+            stream_sender.send_to(*from, &ItemsStreamMessage::Catalog(catalog.entries()))
+        {
+            error!(
+                "send_catalog_on_join: failed to send to ClientId({}): {e}",
+                from.0
+            );
+        }
+    }
+}
+
+/// Sends the [`ItemCatalog`] to every client again after it changed, e.g.
+/// when templates were reloaded.
+fn broadcast_catalog_changes(
+    catalog: Res<ItemCatalog>,
+    stream_sender: Res<StreamSender<ItemsStreamMessage>>,
+) {
+    if !catalog.is_changed() || catalog.is_added() {
+        return;
+    }
+    if let Err(e) = stream_sender.broadcast(&ItemsStreamMessage::Catalog(catalog.entries())) {
+        error!("broadcast_catalog_changes: failed to broadcast: {e}");
+    }
+}
+
 /// Sends [`ItemEvent::PickedUp`] for every item currently held in a hand slot
 /// to a newly joined client.
 ///
@@ -987,17 +1628,18 @@ fn broadcast_item_event(
 /// has already received `EntitySpawned` for every entity before these events.
 fn broadcast_held_on_join(
     mut player_events: MessageReader<PlayerEvent>,
-    held_items_q: Query<(&NetId, &ChildOf)>,
+    held_items_q: Query<(Entity, &NetId, &ChildOf)>,
     hand_slot_q: Query<(), With<HandSlot>>,
     hand_parent_q: Query<&ChildOf, With<HandSlot>>,
     creature_net_id_q: Query<&NetId, Without<HandSlot>>,
     stream_sender: Res<StreamSender<ItemsStreamMessage>>,
+    scope: ReplicationScope,
 ) {
     for event in player_events.read() {
         let PlayerEvent::Joined { id: from, .. } = event else {
             continue;
         };
-        for (&item_net_id, child_of) in held_items_q.iter() {
+        for (item_entity, &item_net_id, child_of) in held_items_q.iter() {
             let hand_entity = child_of.parent();
             // Confirm parent is a HandSlot.
             if hand_slot_q.get(hand_entity).is_err() {
@@ -1011,6 +1653,11 @@ fn broadcast_held_on_join(
             let Ok(&holder_net_id) = creature_net_id_q.get(creature_entity) else {
                 continue;
             };
+            if !scope.is_visible_to(item_entity, *from)
+                || !scope.is_visible_to(creature_entity, *from)
+            {
+                continue;
+            }
             if let Err(e) = stream_sender.send_to(
                 *from,
                 &ItemsStreamMessage::ItemEvent(ItemEvent::PickedUp {
@@ -1027,47 +1674,32 @@ fn broadcast_held_on_join(
     }
 }
 
-/// Sends [`ItemEvent::Stored`] for every item currently held in a non-hand
-/// container to a newly joined client.
-///
-/// Runs in `PreUpdate` after [`ThingsSet::HandleClientJoined`] so that
-/// the client has already received `EntitySpawned` for every entity before
-/// these events arrive.
-///
-/// Hand-held items are covered by [`broadcast_held_on_join`]; this system
-/// covers items inside world containers (entities with [`Container`] that are
-/// NOT [`HandSlot`] entities, and therefore have a [`NetId`] of their own).
-fn broadcast_stored_on_join(
+/// Sends [`ItemEvent::StackChanged`] for every stack to a newly joined client.
+fn broadcast_stacks_on_join(
     mut player_events: MessageReader<PlayerEvent>,
-    containers: Query<(Entity, &Container, &NetId), Without<HandSlot>>,
-    net_ids: Query<&NetId>,
+    stacks: Query<(Entity, &NetId, &Stack)>,
     stream_sender: Res<StreamSender<ItemsStreamMessage>>,
+    scope: ReplicationScope,
 ) {
     for event in player_events.read() {
         let PlayerEvent::Joined { id: from, .. } = event else {
             continue;
         };
-        for (container_entity, container, &container_net_id) in containers.iter() {
-            for item_entity in container.slots.iter().filter_map(|s| *s) {
-                let Ok(&item_net_id) = net_ids.get(item_entity) else {
-                    warn!(
-                        "broadcast_stored_on_join: item in container {:?} has no NetId",
-                        container_entity
-                    );
-                    continue;
-                };
-                if let Err(e) = stream_sender.send_to(
-                    *from,
-                    &ItemsStreamMessage::ItemEvent(ItemEvent::Stored {
-                        item: item_net_id,
-                        container: container_net_id,
-                    }),
-                ) {
-                    error!(
-                        "broadcast_stored_on_join: failed to send to ClientId({}): {e}",
-                        from.0
-                    );
-                }
+        for (entity, &item, stack) in stacks.iter() {
+            if !scope.is_visible_to(entity, *from) {
+                continue;
+            }
+            if let Err(e) = stream_sender.send_to(
+                *from,
+                &ItemsStreamMessage::ItemEvent(ItemEvent::StackChanged {
+                    item,
+                    count: stack.count,
+                }),
+            ) {
+                error!(
+                    "broadcast_stacks_on_join: failed to send to ClientId({}): {e}",
+                    from.0
+                );
             }
         }
     }
@@ -1215,19 +1847,70 @@ pub struct ItemsPlugin;
 impl Plugin for ItemsPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Item>();
+        app.register_type::<Draggable>();
         app.register_type::<Container>();
+        app.register_type::<Stack>();
+        app.register_type::<MeleeStats>();
+        app.register_type::<Consumable>();
+        app.register_type::<CustomLabel>();
+        app.register_type::<Labeler>();
+        app.register_type::<FluidContainer>();
+        app.register_type::<FluidSource>();
+        app.register_type::<FluidDrain>();
+        app.register_type::<Conveyor>();
+        app.register_type::<HoldArm>();
 
         app.add_message::<ItemPickupRequest>();
         app.add_message::<ItemDropRequest>();
         app.add_message::<ItemStoreRequest>();
         app.add_message::<ItemTakeRequest>();
+        app.add_message::<ItemThrowRequest>();
+        app.add_message::<projectiles::FireProjectile>();
+        app.add_message::<ItemInsertRequest>();
+        app.add_message::<ItemRequestHandled>();
+        app.add_message::<StackSpawnRequest>();
         app.add_message::<ItemActionEvent>();
+        app.add_message::<ItemUseRequest>();
+        app.add_message::<FluidPourRequest>();
+        app.add_message::<ConveyorTurnRequest>();
+        app.add_message::<ItemTheft>();
+        app.add_message::<ManifestRequest>();
 
         app.init_resource::<InteractionRange>();
+        // Replicated for the client's in-range checks and prompts.
+        app.register_tunable(
+            "items.interaction_range",
+            true,
+            |world| {
+                world
+                    .get_resource::<InteractionRange>()
+                    .map(|range| range.0)
+            },
+            |world, value| world.insert_resource(InteractionRange(value.max(0.0))),
+        );
         app.init_resource::<PendingItemEvents>();
+        app.init_resource::<PendingContainerStates>();
+        app.init_resource::<ContainerResyncs>();
+        app.init_resource::<ItemCatalog>();
 
         // Register the "contents" property for container pre-loading.
         register_contents_property(app);
+        app.add_item_use(consumable::consume_label, consumable::consume);
+        app.world_mut()
+            .resource_mut::<ThingPropertyRegistry>()
+            .register_property::<Stack>("stack");
+        app.world_mut()
+            .resource_mut::<ThingPropertyRegistry>()
+            .register_property::<CustomLabel>("label");
+        app.world_mut()
+            .resource_mut::<ThingPropertyRegistry>()
+            .register_property::<FluidContainer>("fluid");
+        app.world_mut()
+            .resource_mut::<ThingPropertyRegistry>()
+            .register_property::<Conveyor>("conveyor");
+        app.replicate_component::<CustomLabel, ()>(ComponentSync::named("label"));
+        app.replicate_component::<FluidContainer, ()>(ComponentSync::named("fluid"));
+        app.replicate_component::<Conveyor, ()>(ComponentSync::named("conveyor"));
 
         // Register stream 5 (server→client) with StreamRegistry.
         let (sender, reader) = app
@@ -1242,31 +1925,104 @@ impl Plugin for ItemsPlugin {
         app.insert_resource(sender);
         app.insert_resource(reader);
 
+        // Client→server requests for container snapshots.
+        let (sender, reader): (StreamSender<ContainerResyncRequest>, _) = app
+            .world_mut()
+            .resource_mut::<StreamRegistry>()
+            .register_with_budget(
+                StreamDef {
+                    tag: CONTAINER_RESYNC_STREAM_TAG,
+                    name: "container_resync",
+                    direction: StreamDirection::ClientToServer,
+                },
+                StreamBudget::UNLIMITED.with_max_frame(64),
+            );
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+
         app.add_systems(
             NetworkReceive,
             handle_items_lifecycle.run_if(resource_exists::<Client>),
         );
+        app.add_systems(
+            NetworkReceive,
+            snapshot::answer_container_resyncs.run_if(resource_exists::<Server>),
+        );
         app.add_systems(
             Update,
             (
                 init_hand_containers,
                 ApplyDeferred,
-                handle_item_event.run_if(resource_exists::<Client>),
+                (snapshot::apply_container_states, handle_item_event)
+                    .chain()
+                    .run_if(resource_exists::<Client>),
             )
                 .chain(),
         );
+        app.add_systems(
+            NetworkSend,
+            snapshot::request_container_resyncs.run_if(resource_exists::<Client>),
+        );
+        app.add_systems(
+            Update,
+            hold::aim_hold_arms.run_if(not(resource_exists::<Headless>)),
+        );
+        app.add_systems(
+            SimulationTick,
+            (
+                handle_item_interaction,
+                throw::handle_item_throws.before(projectiles::ProjectilesSet::Fire),
+                ownership::flag_thefts,
+                uses::handle_item_use_requests,
+                fluid::handle_fluid_pours,
+            )
+                .chain()
+                .run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            SimulationTick,
+            (
+                conveyor::turn_conveyors,
+                conveyor::orient_conveyors,
+                conveyor::move_items_on_conveyors,
+            )
+                .chain()
+                .run_if(resource_exists::<Server>),
+        );
         app.add_systems(
             Update,
-            handle_item_interaction.run_if(resource_exists::<Server>),
+            (
+                handle_stack_spawn_requests,
+                manifest::handle_manifest_requests,
+            )
+                .run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            PostUpdate,
+            (
+                despawn_empty_stacks.run_if(resource_exists::<Server>),
+                release_despawned_items,
+            )
+                .chain(),
         );
         app.add_systems(
             NetworkSend,
-            broadcast_item_event.run_if(resource_exists::<Server>),
+            (
+                broadcast_item_event,
+                broadcast_stack_changes,
+                broadcast_catalog_changes,
+            )
+                .run_if(resource_exists::<Server>),
         );
         app.add_systems(
             NetworkReceive,
             (
-                (broadcast_held_on_join, broadcast_stored_on_join),
+                send_catalog_on_join,
+                (
+                    broadcast_held_on_join,
+                    snapshot::send_container_states_on_join,
+                    broadcast_stacks_on_join,
+                ),
                 send_items_stream_ready_on_join,
             )
                 .chain()
@@ -1313,9 +2069,14 @@ mod tests {
         app.add_message::<ItemDropRequest>();
         app.add_message::<ItemStoreRequest>();
         app.add_message::<ItemTakeRequest>();
+        app.add_message::<ItemInsertRequest>();
+        app.add_message::<ItemRequestHandled>();
         app.add_message::<ItemActionEvent>();
         // Add the interaction systems.
         app.add_systems(Update, (init_hand_containers, handle_item_interaction));
+        app.init_resource::<SpatialIndex>();
+        app.add_systems(PreUpdate, things::update_spatial_index);
+        app.init_resource::<ItemCatalog>();
         app.insert_resource(InteractionRange(2.0));
         app.finish();
         app
@@ -1345,6 +2106,7 @@ mod tests {
         app.world_mut()
             .spawn((
                 Item,
+                Thing { kind: 0 },
                 Transform::from_translation(pos),
                 RigidBody::Dynamic,
                 Collider::sphere(0.3),
@@ -1385,8 +2147,11 @@ mod tests {
         let item = spawn_item(&mut app, Vec3::new(1.0, 0.0, 0.0));
         app.update(); // init_hand_containers gives the hand a Container
 
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item,
+            correlation: None,
+        });
         app.update();
 
         // Physics components must be removed.
@@ -1423,6 +2188,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn forwarded_requests_report_their_outcome() {
+        let mut app = test_app();
+        let (actor, _) = spawn_actor(&mut app, Vec3::ZERO);
+        let near = spawn_item(&mut app, Vec3::new(1.0, 0.0, 0.0));
+        let far = spawn_item(&mut app, Vec3::new(10.0, 0.0, 0.0));
+        app.update();
+
+        for (id, item) in [(1, far), (2, near)] {
+            app.world_mut().write_message(ItemPickupRequest {
+                actor,
+                item,
+                correlation: Some(CorrelationId(id)),
+            });
+        }
+        app.update();
+
+        let handled: Vec<ItemRequestHandled> = app
+            .world_mut()
+            .resource_mut::<Messages<ItemRequestHandled>>()
+            .drain()
+            .collect();
+        assert_eq!(
+            handled,
+            vec![
+                ItemRequestHandled {
+                    correlation: CorrelationId(1),
+                    outcome: Err(ItemRejection::OutOfRange),
+                },
+                ItemRequestHandled {
+                    correlation: CorrelationId(2),
+                    outcome: Ok(()),
+                },
+            ]
+        );
+    }
+
     #[test]
     fn pickup_out_of_range_fails() {
         let mut app = test_app();
@@ -1430,8 +2232,11 @@ mod tests {
         let item = spawn_item(&mut app, Vec3::new(10.0, 0.0, 0.0)); // outside range 2.0
         app.update();
 
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item,
+            correlation: None,
+        });
         app.update();
 
         // Item should still have physics (not picked up).
@@ -1460,6 +2265,7 @@ mod tests {
         app.world_mut().write_message(ItemPickupRequest {
             actor,
             item: non_item,
+            correlation: None,
         });
         app.update();
 
@@ -1479,14 +2285,20 @@ mod tests {
         app.update(); // init_hand_containers
 
         // Pick up first item.
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item: item1 });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item: item1,
+            correlation: None,
+        });
         app.update();
         assert!(app.world().get::<Container>(hand).unwrap().contains(item1));
 
         // Attempt to pick up second item with full hand.
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item: item2 });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item: item2,
+            correlation: None,
+        });
         app.update();
 
         // Second item should still be in the world (physics intact).
@@ -1514,13 +2326,17 @@ mod tests {
         app.world_mut().write_message(ItemPickupRequest {
             actor: actor2,
             item,
+            correlation: None,
         });
         app.update();
         assert!(app.world().get::<StashedPhysics>(item).is_some());
 
         // actor1 tries to pick up the same already-held item — should fail.
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item,
+            correlation: None,
+        });
         app.update();
 
         // item should not be in actor1's hand.
@@ -1541,8 +2357,11 @@ mod tests {
             .id();
         app.update();
 
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item,
+            correlation: None,
+        });
         app.update();
 
         // Non-physical item should be rejected — no StashedPhysics fabricated.
@@ -1567,8 +2386,11 @@ mod tests {
         app.update();
 
         // Pick up.
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item,
+            correlation: None,
+        });
         app.update();
         assert!(app.world().get::<StashedPhysics>(item).is_some());
 
@@ -1578,6 +2400,7 @@ mod tests {
             actor,
             item,
             drop_position: drop_pos,
+            correlation: None,
         });
         app.update();
 
@@ -1640,6 +2463,7 @@ mod tests {
             actor,
             item,
             drop_position: Vec3::ZERO,
+            correlation: None,
         });
         app.update();
 
@@ -1658,8 +2482,11 @@ mod tests {
         app.update();
 
         // Pick up the item first.
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item,
+            correlation: None,
+        });
         app.update();
         assert!(app.world().get::<StashedPhysics>(item).is_some());
 
@@ -1668,6 +2495,7 @@ mod tests {
             actor,
             item,
             drop_position: Vec3::new(50.0, 0.0, 0.0),
+            correlation: None,
         });
         app.update();
 
@@ -1678,6 +2506,90 @@ mod tests {
         );
     }
 
+    // ── Throw ─────────────────────────────────────────────────────────────────
+
+    /// Verifies the whole throw: the held item leaves the hand, is fired
+    /// through the projectiles module, flies and drops as a loose physics body
+    /// where it hits the floor, which it damages.
+    #[test]
+    fn thrown_items_fly_and_land_loose() {
+        let mut app = test_app();
+        app.init_schedule(SimulationTick);
+        app.world_mut()
+            .resource_mut::<bevy::app::FixedMainScheduleOrder>()
+            .insert_after(FixedUpdate, SimulationTick);
+        app.init_resource::<Server>();
+        app.init_resource::<NetIdIndex>();
+        app.add_message::<things::Damage>();
+        app.add_message::<ItemThrowRequest>();
+        // `test_app` is already finished, which rules out `add_plugins`.
+        projectiles::ProjectilesPlugin.build(&mut app);
+        app.add_systems(
+            SimulationTick,
+            throw::handle_item_throws.before(projectiles::ProjectilesSet::Fire),
+        );
+
+        let floor = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(0.0, -0.5, 0.0),
+                RigidBody::Static,
+                Collider::cuboid(40.0, 1.0, 40.0),
+            ))
+            .id();
+        let (actor, hand) = spawn_actor(&mut app, Vec3::new(0.0, 1.0, 0.0));
+        let item = spawn_item(&mut app, Vec3::new(1.0, 0.5, 0.0));
+        app.world_mut().entity_mut(item).insert(NetId(7));
+        app.update();
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item,
+            correlation: None,
+        });
+        app.update();
+        assert!(app.world().get::<StashedPhysics>(item).is_some());
+
+        app.world_mut().write_message(ItemThrowRequest {
+            actor,
+            item,
+            target: Vec3::new(4.0, 0.0, 0.0),
+            correlation: Some(CorrelationId(1)),
+        });
+        let mut flew = false;
+        let mut handled = Vec::new();
+        let mut damage = Vec::new();
+        for _ in 0..120 {
+            app.update();
+            let world = app.world_mut();
+            handled.extend(world.resource_mut::<Messages<ItemRequestHandled>>().drain());
+            damage.extend(world.resource_mut::<Messages<things::Damage>>().drain());
+            let flying = world.get::<projectiles::Projectile>(item).is_some();
+            if flew && !flying {
+                break;
+            }
+            flew |= flying;
+        }
+
+        assert!(flew, "the item should have flown as a projectile");
+        assert!(app.world().get::<projectiles::Projectile>(item).is_none());
+        assert_eq!(
+            app.world().get::<RigidBody>(item),
+            Some(&RigidBody::Dynamic)
+        );
+        assert!(app.world().get::<ChildOf>(item).is_none());
+        assert!(!app.world().get::<Container>(hand).unwrap().contains(item));
+        let landed = app.world().get::<Transform>(item).unwrap().translation;
+        assert!(landed.x > 2.0, "item landed at {landed}");
+        assert_eq!(
+            handled,
+            [ItemRequestHandled {
+                correlation: CorrelationId(1),
+                outcome: Ok(()),
+            }]
+        );
+        assert!(damage.iter().any(|d| d.target == floor));
+    }
+
     // ── Store ─────────────────────────────────────────────────────────────────
 
     #[test]
@@ -1696,8 +2608,11 @@ mod tests {
         app.update();
 
         // Pick up item.
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item,
+            correlation: None,
+        });
         app.update();
         assert!(app.world().get::<Container>(hand).unwrap().contains(item));
 
@@ -1706,6 +2621,7 @@ mod tests {
             actor,
             item,
             container: ext_container,
+            correlation: None,
         });
         app.update();
 
@@ -1754,8 +2670,11 @@ mod tests {
         app.update();
 
         // Pick up item1.
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item: item1 });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item: item1,
+            correlation: None,
+        });
         app.update();
 
         // Attempt to store into the full container.
@@ -1763,6 +2682,7 @@ mod tests {
             actor,
             item: item1,
             container: full_container,
+            correlation: None,
         });
         app.update();
 
@@ -1773,6 +2693,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn insert_stows_a_loose_item_without_an_actor() {
+        let mut app = test_app();
+        let item = spawn_item(&mut app, Vec3::new(1.0, 0.0, 0.0));
+        let container = app
+            .world_mut()
+            .spawn((
+                Container::with_capacity(2),
+                Transform::from_translation(Vec3::new(1.0, 0.0, 0.0)),
+            ))
+            .id();
+        app.update();
+
+        app.world_mut()
+            .write_message(ItemInsertRequest { item, container });
+        app.update();
+
+        let world = app.world();
+        assert!(world.get::<Container>(container).unwrap().contains(item));
+        assert_eq!(*world.get::<Visibility>(item).unwrap(), Visibility::Hidden);
+        assert!(world.get::<StashedPhysics>(item).is_some());
+        assert!(
+            world.get::<RigidBody>(item).is_none(),
+            "stowed item should leave the physics world"
+        );
+
+        // A stowed item is no longer loose, so inserting it again is refused.
+        app.world_mut()
+            .write_message(ItemInsertRequest { item, container });
+        app.update();
+        let slots = &app.world().get::<Container>(container).unwrap().slots;
+        assert_eq!(slots.iter().flatten().count(), 1);
+    }
+
     // ── Take ──────────────────────────────────────────────────────────────────
 
     #[test]
@@ -1798,6 +2752,7 @@ mod tests {
             actor,
             item,
             container: src_container,
+            correlation: None,
         });
         app.update();
 
@@ -1854,6 +2809,7 @@ mod tests {
             actor,
             item,
             container: empty_container,
+            correlation: None,
         });
         app.update();
 
@@ -1885,6 +2841,7 @@ mod tests {
             actor,
             item,
             container: far_container,
+            correlation: None,
         });
         app.update();
 
@@ -1912,8 +2869,11 @@ mod tests {
         app.update();
 
         // Fill hand with item1.
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item: item1 });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item: item1,
+            correlation: None,
+        });
         app.update();
         assert!(app.world().get::<Container>(hand).unwrap().contains(item1));
 
@@ -1922,6 +2882,7 @@ mod tests {
             actor,
             item: item2,
             container: src_container,
+            correlation: None,
         });
         app.update();
 
@@ -1952,8 +2913,11 @@ mod tests {
             "no StashedPhysics before pickup"
         );
 
-        app.world_mut()
-            .write_message(ItemPickupRequest { actor, item });
+        app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item,
+            correlation: None,
+        });
         app.update();
         assert!(
             app.world().get::<StashedPhysics>(item).is_some(),
@@ -1964,6 +2928,7 @@ mod tests {
             actor,
             item,
             drop_position: Vec3::ZERO,
+            correlation: None,
         });
         app.update();
         assert!(
@@ -1994,8 +2959,17 @@ mod tests {
         app.register_type::<Container>();
         app.register_type::<HandSlot>();
         app.init_resource::<PendingItemEvents>();
+        app.init_resource::<PendingContainerStates>();
+        app.init_resource::<ContainerResyncs>();
         app.init_resource::<NetIdIndex>();
-        app.add_systems(Update, (init_hand_containers, handle_item_event));
+        app.add_systems(
+            Update,
+            (
+                init_hand_containers,
+                (snapshot::apply_container_states, handle_item_event).chain(),
+            ),
+        );
+        app.init_resource::<ItemCatalog>();
         app.insert_resource(InteractionRange(2.0));
         app.finish();
         app
@@ -2214,6 +3188,116 @@ mod tests {
         );
     }
 
+    /// A `ContainerState` takes out the items the server no longer has in
+    /// the container and stores the ones the client missed.
+    #[test]
+    fn container_state_reconciles_drifted_slots() {
+        let mut app = test_app_item_event();
+        let container_net = NetId(20);
+        let container = app
+            .world_mut()
+            .spawn((
+                Container::with_capacity(2),
+                container_net,
+                Transform::default(),
+            ))
+            .id();
+        app.world_mut()
+            .resource_mut::<NetIdIndex>()
+            .0
+            .insert(container_net, container);
+        let stale = spawn_item_with_net_id(&mut app, NetId(10), Vec3::ZERO);
+        let missed = spawn_item_with_net_id(&mut app, NetId(11), Vec3::ZERO);
+        app.update();
+        app.world_mut()
+            .resource_mut::<PendingItemEvents>()
+            .0
+            .push(ItemEvent::Stored {
+                item: NetId(10),
+                container: container_net,
+            });
+        app.update();
+
+        app.world_mut()
+            .resource_mut::<PendingContainerStates>()
+            .0
+            .push(ContainerState {
+                container: container_net,
+                items: vec![NetId(11)],
+            });
+        app.update();
+
+        let slots = &app.world().get::<Container>(container).unwrap().slots;
+        assert!(!slots.contains(&Some(stale)) && slots.contains(&Some(missed)));
+        assert_eq!(
+            app.world().get::<Visibility>(stale),
+            Some(&Visibility::Inherited)
+        );
+        assert!(app.world().get::<StoredInContainer>(stale).is_none());
+        assert_eq!(
+            app.world().get::<Visibility>(missed),
+            Some(&Visibility::Hidden)
+        );
+        assert_eq!(
+            app.world().get::<StoredInContainer>(missed).map(|s| s.0),
+            Some(container)
+        );
+        assert!(app.world().get::<RigidBody>(missed).is_none());
+    }
+
+    /// A `Stored` event into a container the client has full asks for a
+    /// snapshot, which settles it.
+    #[test]
+    fn stored_into_full_container_requests_a_resync() {
+        let mut app = test_app_item_event();
+        let container_net = NetId(20);
+        let container = app
+            .world_mut()
+            .spawn((
+                Container::with_capacity(1),
+                container_net,
+                Transform::default(),
+            ))
+            .id();
+        app.world_mut()
+            .resource_mut::<NetIdIndex>()
+            .0
+            .insert(container_net, container);
+        spawn_item_with_net_id(&mut app, NetId(10), Vec3::ZERO);
+        spawn_item_with_net_id(&mut app, NetId(11), Vec3::ZERO);
+        app.update();
+
+        for item in [NetId(10), NetId(11)] {
+            app.world_mut()
+                .resource_mut::<PendingItemEvents>()
+                .0
+                .push(ItemEvent::Stored {
+                    item,
+                    container: container_net,
+                });
+        }
+        app.update();
+        assert!(
+            app.world()
+                .resource::<ContainerResyncs>()
+                .is_pending(container_net)
+        );
+
+        app.world_mut()
+            .resource_mut::<PendingContainerStates>()
+            .0
+            .push(ContainerState {
+                container: container_net,
+                items: vec![NetId(11)],
+            });
+        app.update();
+        assert!(
+            !app.world()
+                .resource::<ContainerResyncs>()
+                .is_pending(container_net)
+        );
+    }
+
     /// Receiving `ItemEvent::Taken` shows the item, reparents it to the
     /// holder's hand, removes it from its source container, and updates the
     /// hand container's slots.
@@ -2300,6 +3384,412 @@ mod tests {
         );
     }
 
+    // ── Server/client parity ──────────────────────────────────────────────────
+
+    /// An item's state with entities replaced by their role, so states from
+    /// the server and client apps compare equal.
+    #[derive(Debug, PartialEq)]
+    struct ItemStateView {
+        /// RigidBody, Collider, GravityScale, LinearVelocity.
+        world_physics: [bool; 4],
+        stashed: Option<f32>,
+        parented_to_hand: bool,
+        stored_in_container: bool,
+        in_hand_slot: bool,
+        in_container_slot: bool,
+        visibility: Option<Visibility>,
+        /// Local transform while parented to the hand.
+        held_at: Option<Transform>,
+    }
+
+    /// One side of a parity test: an actor's hand, an item and a world
+    /// container in `app`.
+    struct Side {
+        app: App,
+        item: Entity,
+        hand: Entity,
+        container: Entity,
+    }
+
+    impl Side {
+        fn view(&self) -> ItemStateView {
+            let world = self.app.world();
+            let item = world.entity(self.item);
+            ItemStateView {
+                world_physics: [
+                    item.contains::<RigidBody>(),
+                    item.contains::<Collider>(),
+                    item.contains::<GravityScale>(),
+                    item.contains::<LinearVelocity>(),
+                ],
+                stashed: item.get::<StashedPhysics>().map(|stash| stash.gravity.0),
+                parented_to_hand: item.get::<ChildOf>().map(ChildOf::parent) == Some(self.hand),
+                stored_in_container: item.get::<StoredInContainer>().map(|s| s.0)
+                    == Some(self.container),
+                in_hand_slot: world
+                    .get::<Container>(self.hand)
+                    .is_some_and(|c| c.contains(self.item)),
+                in_container_slot: world
+                    .get::<Container>(self.container)
+                    .is_some_and(|c| c.contains(self.item)),
+                visibility: item.get::<Visibility>().copied(),
+                held_at: item
+                    .get::<Transform>()
+                    .copied()
+                    .filter(|_| item.get::<ChildOf>().is_some()),
+            }
+        }
+    }
+
+    /// Applies `event` on the client after the server handled the request
+    /// already written to it, and checks both sides agree.
+    fn assert_parity(server: &mut Side, client: &mut Side, event: ItemEvent) {
+        let step = format!("{event:?}");
+        server.app.update();
+        client
+            .app
+            .world_mut()
+            .resource_mut::<PendingItemEvents>()
+            .0
+            .push(event);
+        client.app.update();
+        assert_eq!(server.view(), client.view(), "after {step}");
+    }
+
+    /// Pick-up, store, take, drop and insert leave the item with the same
+    /// components whether the server handles the request or a client
+    /// replays the resulting event, held items at their kind's offset.
+    #[test]
+    fn server_and_client_transitions_leave_identical_item_state() {
+        let container_pos = Vec3::new(1.5, 0.0, 0.0);
+        let offset = HoldOffset {
+            translation: [0.0, -0.3, 0.2],
+            rotation: [0.0, 90.0, 0.0],
+        };
+        let give_offset = |app: &mut App, item: Entity| {
+            app.world_mut().resource_mut::<ItemCatalog>().register(
+                7,
+                ItemKind {
+                    hold_offset: offset,
+                    ..ItemKind::named("Crowbar")
+                },
+            );
+            app.world_mut().entity_mut(item).insert(Thing { kind: 7 });
+        };
+
+        let mut app = test_app();
+        let (actor, hand) = spawn_actor(&mut app, Vec3::ZERO);
+        let item = spawn_item(&mut app, Vec3::new(1.0, 0.0, 0.0));
+        give_offset(&mut app, item);
+        let container = app
+            .world_mut()
+            .spawn((
+                Container::with_capacity(4),
+                Transform::from_translation(container_pos),
+            ))
+            .id();
+        app.update();
+        let mut server = Side {
+            app,
+            item,
+            hand,
+            container,
+        };
+
+        let (item_net, holder_net, container_net) = (NetId(10), NetId(1), NetId(20));
+        let mut app = test_app_item_event();
+        let (_, hand) = spawn_creature_with_net_id(&mut app, holder_net, Vec3::ZERO);
+        let item = spawn_item_with_net_id(&mut app, item_net, Vec3::new(1.0, 0.0, 0.0));
+        give_offset(&mut app, item);
+        let container = app
+            .world_mut()
+            .spawn((
+                Container::with_capacity(4),
+                Transform::from_translation(container_pos),
+            ))
+            .id();
+        app.world_mut()
+            .resource_mut::<NetIdIndex>()
+            .0
+            .insert(container_net, container);
+        app.update();
+        let mut client = Side {
+            app,
+            item,
+            hand,
+            container,
+        };
+        assert_eq!(server.view(), client.view(), "before any transition");
+
+        server.app.world_mut().write_message(ItemPickupRequest {
+            actor,
+            item: server.item,
+            correlation: None,
+        });
+        assert_parity(
+            &mut server,
+            &mut client,
+            ItemEvent::PickedUp {
+                item: item_net,
+                holder: holder_net,
+            },
+        );
+        assert_eq!(client.view().held_at, Some(offset.transform()));
+
+        server.app.world_mut().write_message(ItemStoreRequest {
+            actor,
+            item: server.item,
+            container: server.container,
+            correlation: None,
+        });
+        assert_parity(
+            &mut server,
+            &mut client,
+            ItemEvent::Stored {
+                item: item_net,
+                container: container_net,
+            },
+        );
+
+        server.app.world_mut().write_message(ItemTakeRequest {
+            actor,
+            item: server.item,
+            container: server.container,
+            correlation: None,
+        });
+        assert_parity(
+            &mut server,
+            &mut client,
+            ItemEvent::Taken {
+                item: item_net,
+                holder: holder_net,
+            },
+        );
+        assert_eq!(client.view().held_at, Some(offset.transform()));
+
+        let drop_position = Vec3::new(-1.0, 0.0, 0.0);
+        server.app.world_mut().write_message(ItemDropRequest {
+            actor,
+            item: server.item,
+            drop_position,
+            correlation: None,
+        });
+        assert_parity(
+            &mut server,
+            &mut client,
+            ItemEvent::Dropped {
+                item: item_net,
+                position: (drop_position + Vec3::Y * 0.5).to_array(),
+            },
+        );
+
+        server.app.world_mut().write_message(ItemInsertRequest {
+            item: server.item,
+            container: server.container,
+        });
+        assert_parity(
+            &mut server,
+            &mut client,
+            ItemEvent::Stored {
+                item: item_net,
+                container: container_net,
+            },
+        );
+        assert!(client.view().in_container_slot);
+    }
+
+    // ── Stacks ────────────────────────────────────────────────────────────────
+
+    /// Spawn an actor holding a stack of `count` units of `kind` in its hand.
+    /// Returns (actor_entity, hand_slot_entity, stack_entity).
+    fn spawn_actor_with_stack(app: &mut App, kind: u16, count: u32) -> (Entity, Entity, Entity) {
+        let (actor, hand) = spawn_actor(app, Vec3::ZERO);
+        let stack = app
+            .world_mut()
+            .spawn((Item, Thing { kind }, Stack::new(count), ChildOf(hand)))
+            .id();
+        let mut container = Container::with_capacity(1);
+        container.insert(stack);
+        app.world_mut().entity_mut(hand).insert(container);
+        (actor, hand, stack)
+    }
+
+    #[test]
+    fn held_stacks_consume_is_all_or_nothing() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut app = test_app();
+        let (actor, _, stack) = spawn_actor_with_stack(&mut app, 4, 3);
+
+        let consumed = app
+            .world_mut()
+            .run_system_once(move |mut held: HeldStacks| held.consume(actor, 4, 5))
+            .unwrap();
+        assert!(!consumed, "Consuming more than is held must fail");
+        assert_eq!(app.world().get::<Stack>(stack), Some(&Stack::new(3)));
+
+        let consumed = app
+            .world_mut()
+            .run_system_once(move |mut held: HeldStacks| held.consume(actor, 4, 2))
+            .unwrap();
+        assert!(consumed);
+        assert_eq!(app.world().get::<Stack>(stack), Some(&Stack::new(1)));
+
+        let other_kind = app
+            .world_mut()
+            .run_system_once(move |held: HeldStacks| held.count(actor, 7))
+            .unwrap();
+        assert_eq!(other_kind, 0, "Stacks of other kinds must not be counted");
+
+        let counted = app
+            .world_mut()
+            .run_system_once(move |held: HeldStackCounts| held.count(actor, 4))
+            .unwrap();
+        assert_eq!(counted, 1, "HeldStackCounts must agree with HeldStacks");
+    }
+
+    #[test]
+    fn empty_stack_is_despawned_and_released_from_hand() {
+        let mut app = test_app();
+        app.init_resource::<NetIdIndex>();
+        app.add_systems(
+            PostUpdate,
+            (despawn_empty_stacks, release_despawned_items).chain(),
+        );
+        let (_, hand, stack) = spawn_actor_with_stack(&mut app, 4, 0);
+        app.world_mut().entity_mut(stack).insert(NetId(9));
+        app.world_mut()
+            .resource_mut::<NetIdIndex>()
+            .0
+            .insert(NetId(9), stack);
+
+        app.update();
+        app.update();
+
+        assert!(app.world().get_entity(stack).is_err());
+        assert!(
+            !app.world()
+                .resource::<NetIdIndex>()
+                .0
+                .contains_key(&NetId(9)),
+            "Despawned stack must leave the NetIdIndex"
+        );
+        let container = app.world().get::<Container>(hand).unwrap();
+        assert!(
+            !container.contains(stack),
+            "Hand must not keep the dead stack"
+        );
+    }
+
+    #[test]
+    fn item_catalog_max_stack_defaults_to_one() {
+        let mut catalog = ItemCatalog::default();
+        catalog.register(
+            4,
+            ItemKind {
+                max_stack: 50,
+                ..ItemKind::named("Plasteel")
+            },
+        );
+        assert_eq!(catalog.max_stack(4), 50);
+        assert_eq!(catalog.max_stack(2), 1);
+    }
+
+    #[test]
+    fn item_catalog_survives_the_wire() {
+        let mut catalog = ItemCatalog::default();
+        catalog.register(
+            3,
+            ItemKind {
+                icon: Some("icons/toolbox.png".into()),
+                volume: 12.0,
+                equip_slots: vec![EquipSlot::Back],
+                hold_offset: HoldOffset {
+                    translation: [0.0, -0.2, 0.1],
+                    rotation: [90.0, 0.0, 0.0],
+                },
+                ..ItemKind::named("Toolbox")
+            },
+        );
+        catalog.register(2, ItemKind::named("Can"));
+
+        let bytes =
+            wincode::serialize(&ItemsStreamMessage::Catalog(catalog.entries())).expect("serialize");
+        let ItemsStreamMessage::Catalog(entries) =
+            wincode::deserialize(&bytes).expect("deserialize")
+        else {
+            panic!("expected a catalog");
+        };
+        assert_eq!(entries[0].kind, 2);
+        let received: ItemCatalog = entries.into_iter().collect();
+        assert_eq!(received, catalog);
+        assert_eq!(received.display_name(3), Some("Toolbox"));
+    }
+
+    // ── Item uses ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn drinking_restores_needs_and_uses_up_the_item() {
+        use things::Needs;
+
+        let mut app = test_app();
+        app.init_resource::<NetIdIndex>();
+        app.add_message::<ItemUseRequest>();
+        app.add_item_use(consumable::consume_label, consumable::consume);
+        app.add_systems(
+            Update,
+            (uses::handle_item_use_requests, release_despawned_items).chain(),
+        );
+        let soda = Consumable {
+            nutrition: 5.0,
+            hydration: 30.0,
+        };
+        let (actor, hand) = spawn_actor(&mut app, Vec3::ZERO);
+        app.world_mut().entity_mut(actor).insert(Needs {
+            nutrition: 50.0,
+            hydration: 50.0,
+        });
+        app.update(); // init_hand_containers gives the hand a Container
+        let cans = app
+            .world_mut()
+            .spawn((Item, soda, Stack::new(2), ChildOf(hand)))
+            .id();
+        app.world_mut()
+            .get_mut::<Container>(hand)
+            .unwrap()
+            .insert(cans);
+        let loose = app.world_mut().spawn((Item, soda)).id();
+        let use_item = |app: &mut App, item| {
+            app.world_mut()
+                .write_message(ItemUseRequest { actor, item });
+            app.update();
+            *app.world().get::<Needs>(actor).unwrap()
+        };
+        assert_eq!(
+            app.world()
+                .resource::<ItemUseRegistry>()
+                .label(app.world().entity(cans)),
+            Some("Drink")
+        );
+
+        // Only held items can be used.
+        assert_eq!(use_item(&mut app, loose).hydration, 50.0);
+        assert_eq!(
+            use_item(&mut app, cans),
+            Needs {
+                nutrition: 55.0,
+                hydration: 80.0,
+            }
+        );
+        assert_eq!(app.world().get::<Stack>(cans), Some(&Stack::new(1)));
+
+        // A lone item is despawned and leaves the hand.
+        app.world_mut().entity_mut(cans).remove::<Stack>();
+        assert_eq!(use_item(&mut app, cans).hydration, 100.0);
+        assert!(app.world().get_entity(cans).is_err());
+        assert!(!app.world().get::<Container>(hand).unwrap().contains(cans));
+    }
+
     // ── broadcast_item_event ─────────────────────────────────────────────────
 
     /// Verifies that `broadcast_item_event` processes a `PickedUp` action event
@@ -2323,6 +3813,7 @@ mod tests {
                 direction: StreamDirection::ServerToClient,
             });
         app.insert_resource(sender);
+        app.init_resource::<things::ReplicatedClients>();
 
         app.add_message::<ItemActionEvent>();
         app.add_systems(Update, broadcast_item_event);
@@ -2350,4 +3841,59 @@ mod tests {
         // which the system handles gracefully (logs an error and continues).
         app.update();
     }
+
+    // ── answer_container_resyncs ─────────────────────────────────────────────
+
+    /// Verifies that malformed frames on the container resync stream are
+    /// dropped by `answer_container_resyncs` without panicking or touching
+    /// the container they may name.
+    #[test]
+    fn container_resyncs_survive_malformed_frames() {
+        use network::fuzz::fuzz_client_streams;
+        use network::{StreamBudget, StreamDef, StreamDirection, StreamRegistry};
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<StreamRegistry>();
+        app.init_resource::<things::ReplicatedClients>();
+        let mut registry = app.world_mut().resource_mut::<StreamRegistry>();
+        let (sender, _reader) = registry.register::<ItemsStreamMessage>(StreamDef {
+            tag: ITEMS_STREAM_TAG,
+            name: "items",
+            direction: StreamDirection::ServerToClient,
+        });
+        let (_sender, reader) = registry.register_with_budget::<ContainerResyncRequest>(
+            StreamDef {
+                tag: CONTAINER_RESYNC_STREAM_TAG,
+                name: "container_resync",
+                direction: StreamDirection::ClientToServer,
+            },
+            StreamBudget::UNLIMITED.with_max_frame(64),
+        );
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+        let item = app.world_mut().spawn(Item).id();
+        let mut container = Container::with_capacity(2);
+        container.slots[0] = Some(item);
+        let container = app.world_mut().spawn((container, NetId(7))).id();
+        app.insert_resource(NetIdIndex(HashMap::from([(NetId(7), container)])));
+        app.add_systems(Update, snapshot::answer_container_resyncs);
+
+        let valid = wincode::serialize(&ContainerResyncRequest {
+            container: NetId(7),
+        })
+        .expect("serialize");
+        fuzz_client_streams(&mut app, &[("container_resync", valid)], 11, 2_000);
+
+        assert!(
+            app.world()
+                .resource::<StreamRegistry>()
+                .buffered_frames()
+                .is_empty()
+        );
+        assert_eq!(
+            app.world().get::<Container>(container).unwrap().slots,
+            vec![Some(item), None]
+        );
+    }
 }
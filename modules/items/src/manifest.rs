@@ -0,0 +1,273 @@
+//! Cargo manifests: a container and everything in it, as a RON file.
+//!
+//! [`CargoManifest::export`] walks a [`Container`] recursively and records
+//! each thing's template and registered properties (stack counts, labels,
+//! fluids, ...).  [`spawn_manifest`] builds the same cargo again through the
+//! `"contents"` property the map loader uses, then announces the new things
+//! to connected clients.  On servers, [`ManifestRequest`]s do both to and
+//! from files, e.g. from the admin console, to set up scenario starts and
+//! tests.
+//!
+//! ```ron
+//! (
+//!     template: "crate",
+//!     contents: [
+//!         (template: "plasteel", properties: {"stack": (count: 20)}),
+//!         (template: "toolbox", contents: [(template: "can")]),
+//!     ],
+//! )
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use network::{NetId, StreamSender};
+use ron::value::RawValue;
+use serde::{Deserialize, Serialize};
+use things::{
+    NetIdIndex, SpawnPoint, Thing, ThingRegistry, ThingsStreamMessage, apply_properties,
+    serialize_entity_properties, spawn_thing_world,
+};
+
+use crate::{Container, ItemActionEvent};
+
+/// Property key under which containers save what they hold.
+const CONTENTS_PROPERTY: &str = "contents";
+
+/// A thing, its properties and, for containers, what it holds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CargoManifest {
+    /// Name of the [`ThingRegistry`] template.
+    pub template: String,
+    /// Registered properties other than the contents, keyed like map spawn
+    /// points.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub properties: HashMap<String, Box<RawValue>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contents: Vec<CargoManifest>,
+}
+
+impl CargoManifest {
+    /// Manifest of `entity` and, recursively, everything in its container.
+    pub fn export(world: &World, entity: Entity) -> Result<Self, String> {
+        let thing = world
+            .get::<Thing>(entity)
+            .ok_or_else(|| format!("{entity:?} is not a thing"))?;
+        let template = world
+            .resource::<ThingRegistry>()
+            .name_by_kind(thing.kind)
+            .ok_or_else(|| format!("thing kind {} has no template name", thing.kind))?
+            .to_string();
+        let mut properties = serialize_entity_properties(entity, world);
+        properties.remove(CONTENTS_PROPERTY);
+        let contents = world
+            .get::<Container>(entity)
+            .map(|container| {
+                container
+                    .slots
+                    .iter()
+                    .flatten()
+                    .map(|&item| Self::export(world, item))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+        Ok(Self {
+            template,
+            properties,
+            contents,
+        })
+    }
+
+    /// Number of things inside, at any depth.
+    pub fn item_count(&self) -> usize {
+        self.contents.iter().map(|item| 1 + item.item_count()).sum()
+    }
+
+    /// The map spawn point that builds this cargo at `position`, with the
+    /// contents as nested `"contents"` spawn points.
+    pub fn to_spawn_point(&self, position: Vec3) -> Result<SpawnPoint, String> {
+        let mut properties = self.properties.clone();
+        if !self.contents.is_empty() {
+            let contents = self
+                .contents
+                .iter()
+                .map(|item| item.to_spawn_point(position))
+                .collect::<Result<Vec<_>, _>>()?;
+            let raw = world::to_layer_value(&contents).map_err(|e| e.to_string())?;
+            properties.insert(CONTENTS_PROPERTY.to_string(), raw);
+        }
+        Ok(SpawnPoint::with_properties(
+            position.to_array(),
+            self.template.clone(),
+            properties,
+        ))
+    }
+
+    /// Reads a manifest from a RON file.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        ron::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Writes the manifest to a RON file.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+
+/// Spawns the cargo of `manifest` at `position` and returns the outer
+/// thing.  Everything gets a [`NetId`] on servers, and connected clients are
+/// sent the spawns and what went into which container (see
+/// [`announce_spawned`]).
+pub fn spawn_manifest(
+    world: &mut World,
+    manifest: &CargoManifest,
+    position: Vec3,
+) -> Result<Entity, String> {
+    let point = manifest.to_spawn_point(position)?;
+    let kind = world
+        .resource::<ThingRegistry>()
+        .kind_by_name(&point.template)
+        .ok_or_else(|| format!("unknown template \"{}\"", point.template))?;
+    let entity = world.spawn_empty().id();
+    spawn_thing_world(world, entity, kind, position);
+    world.flush();
+    if let Err(e) = apply_properties(entity, &point.properties, world) {
+        world.entity_mut(entity).despawn();
+        return Err(e.to_string());
+    }
+
+    announce_spawned(world, entity);
+    Ok(entity)
+}
+
+/// Sends connected clients `EntitySpawned` for `root` and, at its position,
+/// everything in its containers, and fires [`ItemActionEvent::Stored`] for
+/// what is inside.  For things spawned on servers outside the usual
+/// broadcasting paths, e.g. from a manifest or a map file.
+pub fn announce_spawned(world: &mut World, root: Entity) {
+    let position = world
+        .get::<Transform>(root)
+        .map_or(Vec3::ZERO, |transform| transform.translation);
+    // Depth-first, so containers are announced before what they hold.
+    let mut stack = vec![(root, None)];
+    while let Some((thing, container)) = stack.pop() {
+        if let Some(held) = world.get::<Container>(thing) {
+            stack.extend(held.slots.iter().flatten().map(|&item| (item, Some(thing))));
+        }
+        let (Some(&net_id), Some(&Thing { kind })) =
+            (world.get::<NetId>(thing), world.get::<Thing>(thing))
+        else {
+            continue;
+        };
+        if let Some(sender) = world.get_resource::<StreamSender<ThingsStreamMessage>>()
+            && let Err(e) = sender.broadcast(&ThingsStreamMessage::EntitySpawned {
+                net_id,
+                kind,
+                position: position.into(),
+                velocity: [0.0, 0.0, 0.0],
+                owner: None,
+                name: None,
+            })
+        {
+            error!(
+                "Failed to broadcast EntitySpawned for NetId({}): {e}",
+                net_id.0
+            );
+        }
+        if let Some(container) = container {
+            world.write_message(ItemActionEvent::Stored {
+                item: thing,
+                container,
+            });
+        }
+    }
+}
+
+/// Server-side request to save or load a manifest file.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub enum ManifestRequest {
+    /// Write the manifest of `container` to `path`.
+    Export { container: NetId, path: PathBuf },
+    /// Spawn the cargo described in `path` at `position`.
+    Import { path: PathBuf, position: Vec3 },
+}
+
+/// Server system carrying out [`ManifestRequest`]s.  Failures are logged.
+pub(crate) fn handle_manifest_requests(world: &mut World) {
+    let requests: Vec<ManifestRequest> = world
+        .resource_mut::<Messages<ManifestRequest>>()
+        .drain()
+        .collect();
+    for request in requests {
+        match request {
+            ManifestRequest::Export { container, path } => {
+                let manifest = world
+                    .resource::<NetIdIndex>()
+                    .0
+                    .get(&container)
+                    .ok_or_else(|| format!("no thing has NetId({})", container.0))
+                    .and_then(|&entity| CargoManifest::export(world, entity));
+                match manifest.and_then(|manifest| {
+                    manifest.write(&path)?;
+                    Ok(manifest)
+                }) {
+                    Ok(manifest) => info!(
+                        "Exported the manifest of NetId({}) ({} items) to {}",
+                        container.0,
+                        manifest.item_count(),
+                        path.display()
+                    ),
+                    Err(e) => warn!("Manifest export of NetId({}) failed: {e}", container.0),
+                }
+            }
+            ManifestRequest::Import { path, position } => {
+                match CargoManifest::read(&path)
+                    .and_then(|manifest| spawn_manifest(world, &manifest, position))
+                {
+                    Ok(entity) => info!(
+                        "Spawned the cargo of {} as {entity:?} at {position}",
+                        path.display()
+                    ),
+                    Err(e) => warn!("Manifest import of {} failed: {e}", path.display()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifests_nest_contents_as_spawn_points() {
+        let manifest: CargoManifest = ron::from_str(
+            r#"(
+                template: "crate",
+                contents: [
+                    (template: "plasteel", properties: {"stack": (count: 20)}),
+                    (template: "toolbox", contents: [(template: "can")]),
+                ],
+            )"#,
+        )
+        .expect("manifest parses");
+        assert_eq!(manifest.item_count(), 3);
+
+        let point = manifest.to_spawn_point(Vec3::new(4.0, 1.0, 9.0)).unwrap();
+        assert_eq!(point.template, "crate");
+        let contents: Vec<SpawnPoint> =
+            world::from_layer_value(&point.properties[CONTENTS_PROPERTY]).unwrap();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(contents[0].position, [4.0, 1.0, 9.0]);
+        assert!(contents[0].properties.contains_key("stack"));
+        assert!(!contents[0].properties.contains_key(CONTENTS_PROPERTY));
+        let toolbox: Vec<SpawnPoint> =
+            world::from_layer_value(&contents[1].properties[CONTENTS_PROPERTY]).unwrap();
+        assert_eq!(toolbox[0].template, "can");
+    }
+}
@@ -0,0 +1,138 @@
+//! Who items belong to, and who took them anyway.
+//!
+//! Items issued by a role or crafted by a player get [`OwnedBy`] its
+//! client.  Anyone may still pick up or take an owned item; when another
+//! player does, the server marks it [`Stolen`], fires [`ItemTheft`] and
+//! logs it, so security gameplay and admins can tell who took what.  The
+//! mark is cleared once the owner has the item back.  Ownership is
+//! server-side only and lasts for the session.
+
+use bevy::prelude::*;
+use network::{ClientId, ControlledByClient};
+
+use crate::ItemActionEvent;
+
+/// The client an item was issued to or crafted by.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OwnedBy(pub ClientId);
+
+/// Marks an owned item last taken by someone other than its owner.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stolen {
+    pub by: ClientId,
+}
+
+/// Server-side event: the client `by` picked up or took `item`, which is
+/// owned by `owner`.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemTheft {
+    pub item: Entity,
+    pub owner: ClientId,
+    pub by: ClientId,
+}
+
+/// Server system: flags owned items that end up in the hands of another
+/// player, and clears the flag when they return to their owner.  Items
+/// moved by creatures no client controls are not flagged.
+pub(crate) fn flag_thefts(
+    mut commands: Commands,
+    mut actions: MessageReader<ItemActionEvent>,
+    owners: Query<(&OwnedBy, Has<Stolen>)>,
+    parents: Query<&ChildOf>,
+    controllers: Query<&ControlledByClient>,
+    mut thefts: MessageWriter<ItemTheft>,
+) {
+    for action in actions.read() {
+        let (ItemActionEvent::PickedUp { item, hand } | ItemActionEvent::Taken { item, hand }) =
+            *action
+        else {
+            continue;
+        };
+        let Ok((&OwnedBy(owner), stolen)) = owners.get(item) else {
+            continue;
+        };
+        let Some(by) = parents
+            .get(hand)
+            .ok()
+            .and_then(|parent| controllers.get(parent.parent()).ok())
+            .map(|controller| controller.0)
+        else {
+            continue;
+        };
+        if by == owner {
+            if stolen {
+                commands.entity(item).remove::<Stolen>();
+            }
+            continue;
+        }
+        info!(
+            "Item {:?} owned by ClientId({}) was taken by ClientId({})",
+            item, owner.0, by.0
+        );
+        commands.entity(item).insert(Stolen { by });
+        thefts.write(ItemTheft { item, owner, by });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Thefts(Vec<ItemTheft>);
+
+    #[test]
+    fn taking_another_players_item_flags_it_until_returned() {
+        let mut app = App::new();
+        app.add_message::<ItemActionEvent>();
+        app.add_message::<ItemTheft>();
+        app.init_resource::<Thefts>();
+        app.add_systems(
+            Update,
+            (
+                flag_thefts,
+                |mut reader: MessageReader<ItemTheft>, mut thefts: ResMut<Thefts>| {
+                    thefts.0.extend(reader.read().copied());
+                },
+            )
+                .chain(),
+        );
+
+        let owner = ClientId(1);
+        let thief = ClientId(2);
+        let owner_actor = app.world_mut().spawn(ControlledByClient(owner)).id();
+        let thief_actor = app.world_mut().spawn(ControlledByClient(thief)).id();
+        let owner_hand = app.world_mut().spawn(ChildOf(owner_actor)).id();
+        let thief_hand = app.world_mut().spawn(ChildOf(thief_actor)).id();
+        let item = app.world_mut().spawn(OwnedBy(owner)).id();
+
+        app.world_mut().write_message(ItemActionEvent::PickedUp {
+            item,
+            hand: owner_hand,
+        });
+        app.update();
+        assert!(app.world().get::<Stolen>(item).is_none());
+
+        app.world_mut().write_message(ItemActionEvent::Taken {
+            item,
+            hand: thief_hand,
+        });
+        app.update();
+        assert_eq!(app.world().get::<Stolen>(item), Some(&Stolen { by: thief }));
+        assert_eq!(
+            app.world().resource::<Thefts>().0,
+            vec![ItemTheft {
+                item,
+                owner,
+                by: thief
+            }]
+        );
+
+        app.world_mut().write_message(ItemActionEvent::PickedUp {
+            item,
+            hand: owner_hand,
+        });
+        app.update();
+        assert!(app.world().get::<Stolen>(item).is_none());
+    }
+}
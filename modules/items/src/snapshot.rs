@@ -0,0 +1,280 @@
+//! Authoritative snapshots of what world containers hold.
+//!
+//! Clients otherwise learn container contents only from replayed
+//! [`ItemEvent`](crate::ItemEvent)s, and drift for good if one is missed
+//! (for example while the container was out of their replication scope).
+//! The server sends a [`ContainerState`] for every visible container when a
+//! client joins, and again whenever the client reports a mismatch: an event
+//! that did not fit what it has locally.  The client then reconciles its
+//! [`Container`] slots against the snapshot in [`apply_container_states`].
+//!
+//! Hand slots have no [`NetId`] and are not covered; their contents follow
+//! the pick-up and take events.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use network::{ClientId, NetId, PlayerEvent, StreamReader, StreamSender, stream_tag};
+use physics::{Collider, GravityScale};
+use serde::{Deserialize, Serialize};
+use things::{HandSlot, NetIdIndex, ReplicationScope};
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{Container, Item, ItemsStreamMessage, StoredInContainer, item_state};
+
+/// Client→server stream on which clients ask for [`ContainerState`]s.
+pub const CONTAINER_RESYNC_STREAM_TAG: u8 = stream_tag("container_resync");
+
+/// The items in a world container, as the server has them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SchemaRead, SchemaWrite)]
+pub struct ContainerState {
+    pub container: NetId,
+    pub items: Vec<NetId>,
+}
+
+/// Client→server request for a fresh [`ContainerState`] of `container`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, SchemaRead, SchemaWrite)]
+pub struct ContainerResyncRequest {
+    pub container: NetId,
+}
+
+/// Client-side: snapshots received on stream 5 waiting to be applied.
+#[derive(Resource, Default)]
+pub struct PendingContainerStates(pub Vec<ContainerState>);
+
+/// Client-side: containers whose local contents disagreed with an event.
+#[derive(Resource, Default)]
+pub struct ContainerResyncs {
+    /// Not yet asked for.
+    wanted: HashSet<NetId>,
+    /// Asked for; not asked again until their snapshot arrives.
+    requested: HashSet<NetId>,
+}
+
+impl ContainerResyncs {
+    /// Asks the server for a snapshot of `container`, unless one is on its way.
+    pub fn request(&mut self, container: NetId) {
+        if !self.requested.contains(&container) {
+            self.wanted.insert(container);
+        }
+    }
+
+    /// Whether a snapshot of `container` is wanted and has not arrived yet.
+    pub fn is_pending(&self, container: NetId) -> bool {
+        self.wanted.contains(&container) || self.requested.contains(&container)
+    }
+
+    fn received(&mut self, container: NetId) {
+        self.wanted.remove(&container);
+        self.requested.remove(&container);
+    }
+}
+
+/// The [`ContainerState`] of `container` as `client` may see it: items out of
+/// its replication scope are left out.
+fn container_state(
+    container_net_id: NetId,
+    container: &Container,
+    net_ids: &Query<&NetId>,
+    scope: &ReplicationScope,
+    client: ClientId,
+) -> ContainerState {
+    let items = container
+        .slots
+        .iter()
+        .flatten()
+        .filter(|&&item| scope.is_visible_to(item, client))
+        .filter_map(|&item| net_ids.get(item).ok().copied())
+        .collect();
+    ContainerState {
+        container: container_net_id,
+        items,
+    }
+}
+
+/// Server system: sends a [`ContainerState`] for every world container a
+/// newly joined client can see, empty ones included.
+///
+/// Runs in `NetworkReceive` after `ThingsSet::HandleClientJoined`
+/// so the client has already received `EntitySpawned` for every entity.
+pub(crate) fn send_container_states_on_join(
+    mut player_events: MessageReader<PlayerEvent>,
+    containers: Query<(Entity, &Container, &NetId), Without<HandSlot>>,
+    net_ids: Query<&NetId>,
+    stream_sender: Res<StreamSender<ItemsStreamMessage>>,
+    scope: ReplicationScope,
+) {
+    for event in player_events.read() {
+        let PlayerEvent::Joined { id: from, .. } = event else {
+            continue;
+        };
+        for (entity, container, &container_net_id) in containers.iter() {
+            if !scope.is_visible_to(entity, *from) {
+                continue;
+            }
+            let state = container_state(container_net_id, container, &net_ids, &scope, *from);
+            if let Err(e) = stream_sender.send_to(*from, &ItemsStreamMessage::ContainerState(state))
+            {
+                error!(
+                    "send_container_states_on_join: failed to send to ClientId({}): {e}",
+                    from.0
+                );
+            }
+        }
+    }
+}
+
+/// Server system: answers [`ContainerResyncRequest`]s with the container's
+/// [`ContainerState`].  Requests for containers the client cannot see, or
+/// that are gone, are ignored; repeats within a frame are answered once.
+pub(crate) fn answer_container_resyncs(
+    mut reader: ResMut<StreamReader<ContainerResyncRequest>>,
+    net_id_index: Res<NetIdIndex>,
+    containers: Query<&Container, Without<HandSlot>>,
+    net_ids: Query<&NetId>,
+    stream_sender: Res<StreamSender<ItemsStreamMessage>>,
+    scope: ReplicationScope,
+) {
+    let mut answered = HashSet::new();
+    for (from, request) in reader.drain_from_client() {
+        if !answered.insert((from, request.container)) {
+            continue;
+        }
+        let Some((entity, container)) = net_id_index
+            .0
+            .get(&request.container)
+            .and_then(|&entity| containers.get(entity).ok().map(|c| (entity, c)))
+        else {
+            debug!(
+                "answer_container_resyncs: ClientId({}) asked for unknown container NetId({})",
+                from.0, request.container.0
+            );
+            continue;
+        };
+        if !scope.is_visible_to(entity, from) {
+            continue;
+        }
+        let state = container_state(request.container, container, &net_ids, &scope, from);
+        if let Err(e) = stream_sender.send_to(from, &ItemsStreamMessage::ContainerState(state)) {
+            error!(
+                "answer_container_resyncs: failed to send to ClientId({}): {e}",
+                from.0
+            );
+        }
+    }
+}
+
+/// Client system: asks the server for the containers in [`ContainerResyncs`].
+pub(crate) fn request_container_resyncs(
+    mut resyncs: ResMut<ContainerResyncs>,
+    stream_sender: Res<StreamSender<ContainerResyncRequest>>,
+) {
+    let ContainerResyncs { wanted, requested } = &mut *resyncs;
+    for container in wanted.drain() {
+        match stream_sender.send(&ContainerResyncRequest { container }) {
+            Ok(()) => {
+                requested.insert(container);
+            }
+            Err(e) => warn!(
+                "request_container_resyncs: failed to ask for container NetId({}): {e}",
+                container.0
+            ),
+        }
+    }
+}
+
+/// Client system: makes each container in [`PendingContainerStates`] hold
+/// exactly the snapshot's items.
+///
+/// Items the container no longer holds are taken out of its slots and shown
+/// again; where they went arrives with their own events.  Items it should
+/// hold are moved in from wherever the client had them, as a `Stored` event
+/// would.
+/// Items the client does not know yet are skipped.
+#[allow(clippy::type_complexity)]
+pub(crate) fn apply_container_states(
+    mut commands: Commands,
+    mut pending: ResMut<PendingContainerStates>,
+    mut resyncs: ResMut<ContainerResyncs>,
+    net_id_index: Res<NetIdIndex>,
+    mut containers: Query<&mut Container>,
+    items_q: Query<
+        (
+            Option<&Collider>,
+            Option<&GravityScale>,
+            Option<&ChildOf>,
+            Option<&StoredInContainer>,
+        ),
+        With<Item>,
+    >,
+) {
+    for state in pending.0.drain(..) {
+        resyncs.received(state.container);
+        let Some(&container_entity) = net_id_index.0.get(&state.container) else {
+            warn!(
+                "apply_container_states: container NetId({}) not found",
+                state.container.0
+            );
+            continue;
+        };
+        let Ok(container) = containers.get(container_entity) else {
+            warn!(
+                "apply_container_states: NetId({}) is not a container",
+                state.container.0
+            );
+            continue;
+        };
+        let capacity = container.capacity();
+        let held: Vec<Entity> = container.slots.iter().flatten().copied().collect();
+
+        let mut wanted = Vec::with_capacity(state.items.len());
+        for item in &state.items {
+            match net_id_index.0.get(item) {
+                Some(&entity) if items_q.contains(entity) => wanted.push(entity),
+                _ => warn!(
+                    "apply_container_states: item NetId({}) in container NetId({}) not found",
+                    item.0, state.container.0
+                ),
+            }
+        }
+        if wanted.len() > capacity {
+            warn!(
+                "apply_container_states: container NetId({}) holds {} items but has {} slots",
+                state.container.0,
+                wanted.len(),
+                capacity
+            );
+            wanted.truncate(capacity);
+        }
+
+        for &item in held.iter().filter(|item| !wanted.contains(item)) {
+            if let Ok((_, _, _, Some(&StoredInContainer(stored_in)))) = items_q.get(item)
+                && stored_in == container_entity
+            {
+                item_state::apply_release(&mut commands, &mut containers, item, container_entity);
+            } else if let Ok(mut container) = containers.get_mut(container_entity) {
+                container.remove(item);
+            }
+        }
+
+        for &item in wanted.iter().filter(|item| !held.contains(item)) {
+            let Ok((maybe_collider, maybe_gravity, maybe_child_of, maybe_stored_in)) =
+                items_q.get(item)
+            else {
+                continue;
+            };
+            // Out of the hand or other container the client had it in.
+            let from = maybe_child_of
+                .map(ChildOf::parent)
+                .or(maybe_stored_in.map(|stored_in| stored_in.0));
+            item_state::apply_store(
+                &mut commands,
+                &mut containers,
+                item,
+                item_state::stash(maybe_collider, maybe_gravity),
+                from,
+                container_entity,
+            );
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Throwing held items.
+//!
+//! An [`ItemThrowRequest`] takes the item out of the actor's hand, as a drop
+//! at the hand would, and fires it as a [`ProjectileMode::Thrown`]
+//! projectile towards the aimed point.  It hits the first solid body on its
+//! way for [`THROW_HIT`] and stays where it lands.  Clients see the drop on
+//! the items stream and the flight on the things stream.
+
+use bevy::prelude::*;
+use network::CorrelationId;
+use projectiles::{FireProjectile, ProjectileHit, ProjectileMode};
+use things::{DamageKind, HAND_OFFSET, HandSlot};
+
+use crate::{
+    Container, Item, ItemActionEvent, ItemRejection, ItemRequestHandled, StashedPhysics,
+    find_hand_slot_containing, item_state, request_span,
+};
+
+/// Speed in m/s a thrown item leaves the hand at.
+pub const THROW_SPEED: f32 = 8.0;
+
+/// Downward acceleration of a thrown item in m/s².
+const THROW_GRAVITY: f32 = 9.81;
+
+/// Seconds a thrown item flies before it drops, if it hits nothing.
+const THROW_LIFETIME: f32 = 3.0;
+
+/// What a thrown item does to whatever it hits.
+pub const THROW_HIT: ProjectileHit = ProjectileHit {
+    damage: 5.0,
+    kind: DamageKind::Brute,
+    knockback: 1.0,
+};
+
+/// Server-side request: actor throws a held item towards a world position.
+#[derive(Message, Clone, Debug)]
+pub struct ItemThrowRequest {
+    /// The creature (actor) performing the action.
+    pub actor: Entity,
+    /// The item entity to throw (must currently be in the actor's hand).
+    pub item: Entity,
+    /// World position the throw is aimed at.
+    pub target: Vec3,
+    /// The client request this was forwarded for, if any.
+    pub correlation: Option<CorrelationId>,
+}
+
+/// Server system: releases the items of [`ItemThrowRequest`]s from their
+/// hands and writes a [`FireProjectile`] for each.  Outcomes of forwarded
+/// requests are reported like those of the other item requests.
+///
+/// Runs before [`ProjectilesSet::Fire`](projectiles::ProjectilesSet::Fire),
+/// so the items are loose by the time they are launched.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_item_throws(
+    mut commands: Commands,
+    mut requests: MessageReader<ItemThrowRequest>,
+    transforms: Query<&GlobalTransform>,
+    children: Query<&Children>,
+    hand_slot_q: Query<Entity, With<HandSlot>>,
+    mut containers: Query<&mut Container>,
+    items_q: Query<Option<&StashedPhysics>, With<Item>>,
+    mut action_events: MessageWriter<ItemActionEvent>,
+    mut handled: MessageWriter<ItemRequestHandled>,
+    mut shots: MessageWriter<FireProjectile>,
+) {
+    for req in requests.read() {
+        let span = request_span(req.correlation);
+        let _entered = span.enter();
+        let outcome = 'request: {
+            let Ok(maybe_stash) = items_q.get(req.item) else {
+                warn!("ItemThrowRequest: entity {:?} is not an Item", req.item);
+                break 'request Err(ItemRejection::NotAnItem);
+            };
+            let Some(stash) = maybe_stash else {
+                warn!(
+                    "ItemThrowRequest: item {:?} has no StashedPhysics (not held)",
+                    req.item
+                );
+                break 'request Err(ItemRejection::NotHeld);
+            };
+            let Some(hand_entity) = find_hand_slot_containing(
+                req.actor,
+                req.item,
+                &children,
+                &hand_slot_q,
+                &containers,
+            ) else {
+                warn!(
+                    "ItemThrowRequest: item {:?} is not in actor {:?}'s hand container",
+                    req.item, req.actor
+                );
+                break 'request Err(ItemRejection::NotHeld);
+            };
+            let Ok(actor_gt) = transforms.get(req.actor) else {
+                warn!(
+                    "ItemThrowRequest: actor {:?} has no GlobalTransform",
+                    req.actor
+                );
+                break 'request Err(ItemRejection::NotPlaced);
+            };
+            let origin = actor_gt.translation() + Vec3::Y * HAND_OFFSET.y;
+            let Ok(direction) = Dir3::new(req.target - origin) else {
+                warn!("ItemThrowRequest: throw of {:?} has no direction", req.item);
+                break 'request Err(ItemRejection::NoAim);
+            };
+
+            item_state::apply_drop(
+                &mut commands,
+                &mut containers,
+                req.item,
+                stash.clone(),
+                Some(hand_entity),
+                origin,
+            );
+            action_events.write(ItemActionEvent::Dropped {
+                item: req.item,
+                position: origin,
+            });
+            shots.write(FireProjectile {
+                shooter: Some(req.actor),
+                origin,
+                direction,
+                mode: ProjectileMode::Thrown {
+                    thing: req.item,
+                    speed: THROW_SPEED,
+                    gravity: THROW_GRAVITY,
+                    lifetime: THROW_LIFETIME,
+                },
+                hit: THROW_HIT,
+            });
+            Ok(())
+        };
+        if let Some(correlation) = req.correlation {
+            handled.write(ItemRequestHandled {
+                correlation,
+                outcome,
+            });
+        }
+    }
+}
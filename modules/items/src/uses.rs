@@ -0,0 +1,110 @@
+//! Using a held item: eating, drinking, and whatever else plugins add.
+//!
+//! Any plugin can call [`ItemUseRegistryExt::add_item_use`] with a
+//! [`UseLabel`] naming the use of the items it applies to ("Eat", "Drink")
+//! and the system that carries it out.  An [`ItemUseRequest`] for an item
+//! the actor holds runs the system of the first registered use that applies
+//! to the item, with the request as input.  Clients look the label up to
+//! offer the use in menus.
+
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+use things::HandSlot;
+
+use crate::{Container, Item};
+
+/// Server-side request: `actor` uses the `item` it holds.  Also the input of
+/// the system that carries the use out.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemUseRequest {
+    pub actor: Entity,
+    pub item: Entity,
+}
+
+/// Name of the use offered for an item, or `None` when the use does not
+/// apply to it.
+pub type UseLabel = fn(EntityRef) -> Option<&'static str>;
+
+struct ItemUse {
+    label: UseLabel,
+    system: SystemId<In<ItemUseRequest>>,
+}
+
+/// Every registered use, tried in registration order.
+#[derive(Resource, Default)]
+pub struct ItemUseRegistry {
+    uses: Vec<ItemUse>,
+}
+
+impl ItemUseRegistry {
+    /// Name of the first use that applies to `item`.
+    pub fn label(&self, item: EntityRef) -> Option<&'static str> {
+        self.uses.iter().find_map(|item_use| (item_use.label)(item))
+    }
+
+    /// System of the first use that applies to `item`.
+    fn system(&self, item: EntityRef) -> Option<SystemId<In<ItemUseRequest>>> {
+        self.uses
+            .iter()
+            .find(|item_use| (item_use.label)(item).is_some())
+            .map(|item_use| item_use.system)
+    }
+}
+
+/// Extension trait for [`App`] that provides `add_item_use`.
+pub trait ItemUseRegistryExt {
+    fn add_item_use<M>(
+        &mut self,
+        label: UseLabel,
+        system: impl IntoSystem<In<ItemUseRequest>, (), M> + 'static,
+    ) -> &mut Self;
+}
+
+impl ItemUseRegistryExt for App {
+    fn add_item_use<M>(
+        &mut self,
+        label: UseLabel,
+        system: impl IntoSystem<In<ItemUseRequest>, (), M> + 'static,
+    ) -> &mut Self {
+        let system = self.register_system(system);
+        self.world_mut()
+            .get_resource_or_insert_with::<ItemUseRegistry>(ItemUseRegistry::default)
+            .uses
+            .push(ItemUse { label, system });
+        self
+    }
+}
+
+/// Server system: runs the registered use of every [`ItemUseRequest`] whose
+/// actor holds the item.  Requests for items without a use are dropped.
+pub(crate) fn handle_item_use_requests(
+    mut commands: Commands,
+    mut requests: MessageReader<ItemUseRequest>,
+    registry: Res<ItemUseRegistry>,
+    children: Query<&Children>,
+    hands: Query<&Container, With<HandSlot>>,
+    items: Query<EntityRef, With<Item>>,
+) {
+    for request in requests.read() {
+        let held = children.get(request.actor).is_ok_and(|children| {
+            children.iter().any(|child| {
+                hands
+                    .get(child)
+                    .is_ok_and(|hand| hand.contains(request.item))
+            })
+        });
+        if !held {
+            debug!(?request, "item use dropped: item not held");
+            continue;
+        }
+        let Some(system) = items
+            .get(request.item)
+            .ok()
+            .and_then(|item| registry.system(item))
+        else {
+            debug!(?request, "item use dropped: item has no use");
+            continue;
+        };
+        commands.run_system_with(system, *request);
+    }
+}
@@ -0,0 +1,457 @@
+use bevy::prelude::*;
+use input::InputMap;
+use network::{
+    Client, ClientId, Headless, ModuleReadySent, NetworkReceive, NetworkSend, PlayerEvent, Server,
+    StreamBudget, StreamDef, StreamDirection, StreamPriority, StreamReader, StreamRegistry,
+    StreamSender, stream_tag,
+};
+use things::cell_of;
+use tiles::TileFlags;
+use wincode::{SchemaRead, SchemaWrite};
+
+mod light_grid;
+pub use light_grid::{DARK_THRESHOLD, LightGrid, LightSample, MAX_LIGHT};
+
+mod shading;
+pub use shading::{LightShade, LightingDebugOverlay};
+
+/// Stream tag for the server→client lighting stream, derived from its name.
+pub const LIGHTING_STREAM_TAG: u8 = stream_tag("lighting");
+
+/// Per-client allowance on the lighting stream, in bytes per network tick.
+/// Light changes are cosmetic for now, so they yield to everything else.
+const LIGHTING_BYTES_PER_TICK: usize = 4 * 1024;
+
+/// Brightness (`0.0..=1.0`) that unlit cells are drawn with by default, so
+/// rooms without a lamp are dim rather than black.
+pub const DEFAULT_AMBIENT_LIGHT: f32 = 0.3;
+
+/// Wire format for the server→client lighting stream.
+#[derive(Debug, Clone, SchemaRead, SchemaWrite)]
+pub enum LightingStreamMessage {
+    /// Full light grid, sent on connect and every [`FULL_SNAPSHOT_INTERVAL`].
+    LightGridData {
+        width: u32,
+        height: u32,
+        levels: Vec<u8>,
+    },
+    /// Cells whose level changed since the last snapshot or delta.
+    LightGridDelta { changes: Vec<(u32, u8)> },
+}
+
+/// A thing that gives off light.
+///
+/// Added by thing templates (e.g. lamps).  The server turns every enabled,
+/// powered, visible source into light levels in [`LightGrid`]; sources
+/// stored inside containers are hidden and give off nothing.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct LightSource {
+    /// Distance in tiles at which the light has faded out completely.
+    pub radius: f32,
+    /// Brightness at the source, `0.0..=1.0`.
+    pub intensity: f32,
+    /// Switched-off sources give no light.
+    pub enabled: bool,
+    /// Whether the source has power.  Kept up to date by L3 `power` for
+    /// sources that draw it; an unpowered source gives no light whether it
+    /// is switched on or not.
+    pub powered: bool,
+}
+
+impl LightSource {
+    /// An enabled, powered source.
+    pub fn new(radius: f32, intensity: f32) -> Self {
+        Self {
+            radius,
+            intensity,
+            enabled: true,
+            powered: true,
+        }
+    }
+}
+
+/// Brightness that unlit cells are drawn with, `0.0..=1.0`.  Inserted by
+/// [`LightingPlugin`]; only presentation reads it.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct AmbientLightLevel(pub f32);
+
+/// Server-side system: keeps [`LightGrid`] in step with the tile map and
+/// the light sources.
+///
+/// The grid is rebuilt (at the size of [`TileFlags`]) when the map changes
+/// size, and recomputed whenever the flags change or any source is added,
+/// removed, moved, toggled, powered down or hidden.
+#[allow(clippy::type_complexity)]
+fn update_light_grid(
+    mut commands: Commands,
+    flags: Option<Res<TileFlags>>,
+    grid: Option<ResMut<LightGrid>>,
+    sources: Query<(&LightSource, &GlobalTransform, Option<&Visibility>)>,
+    changed: Query<
+        (),
+        (
+            With<LightSource>,
+            Or<(
+                Changed<LightSource>,
+                Changed<GlobalTransform>,
+                Changed<Visibility>,
+            )>,
+        ),
+    >,
+    mut removed: RemovedComponents<LightSource>,
+) {
+    let Some(flags) = flags else {
+        return;
+    };
+    let removed_any = removed.read().count() > 0;
+
+    let samples = sources
+        .iter()
+        .filter(|(source, _, visibility)| {
+            source.enabled && source.powered && visibility.is_none_or(|v| *v != Visibility::Hidden)
+        })
+        .map(|(source, transform, _)| LightSample {
+            cell: cell_of(transform.translation()),
+            radius: source.radius,
+            intensity: source.intensity,
+        });
+
+    match grid {
+        Some(mut grid) if grid.width() == flags.width() && grid.height() == flags.height() => {
+            if flags.is_changed() || removed_any || !changed.is_empty() {
+                grid.recompute(&flags, samples);
+            }
+        }
+        _ => {
+            let mut grid = LightGrid::new(flags.width(), flags.height());
+            grid.recompute(&flags, samples);
+            grid.update_last_broadcast();
+            commands.insert_resource(grid);
+        }
+    }
+}
+
+fn cleanup_lighting(mut commands: Commands) {
+    commands.remove_resource::<LightGrid>();
+}
+
+/// Plugin for per-tile lighting.
+///
+/// The server computes [`LightGrid`] from [`LightSource`] things and
+/// replicates it on the lighting stream; clients use it to darken unlit parts
+/// of the map (see [`LightShade`]).  Gameplay code can ask the grid whether a
+/// cell [is dark](LightGrid::is_dark).  [`input::Action::ToggleLightingOverlay`]
+/// (F7 by default) switches the shading to a false-colour
+/// debug overlay of the raw levels.
+pub struct LightingPlugin<S: States + Copy> {
+    state: S,
+    ambient: f32,
+}
+
+impl<S: States + Copy> LightingPlugin<S> {
+    /// Creates the plugin; the grid is dropped when leaving `state`.
+    /// `ambient` is the brightness unlit cells are drawn with.
+    pub fn new(state: S, ambient: f32) -> Self {
+        Self { state, ambient }
+    }
+}
+
+impl<S: States + Copy> Plugin for LightingPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.register_type::<LightSource>();
+        app.insert_resource(AmbientLightLevel(self.ambient.clamp(0.0, 1.0)));
+        app.init_resource::<LightingDebugOverlay>();
+        app.init_resource::<InputMap>();
+        app.init_resource::<PendingLightSyncs>();
+        app.init_resource::<LightBroadcastTimers>();
+
+        app.add_systems(Update, update_light_grid.run_if(resource_exists::<Server>));
+        app.add_systems(
+            Update,
+            (
+                shading::toggle_debug_overlay,
+                shading::spawn_light_shade,
+                shading::update_light_shade,
+            )
+                .chain()
+                .run_if(not(resource_exists::<Headless>)),
+        );
+        app.add_systems(
+            NetworkReceive,
+            handle_lighting_updates.run_if(resource_exists::<Client>),
+        );
+        // send_light_grid_on_connect runs in NetworkReceive (after Drain) so
+        // PlayerEvent::Joined is readable.
+        app.add_systems(
+            NetworkReceive,
+            send_light_grid_on_connect.run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            NetworkSend,
+            broadcast_light_grid.run_if(resource_exists::<Server>),
+        );
+
+        // Register the server→client lighting stream. Requires NetworkPlugin to be added first.
+        let mut registry = app
+            .world_mut()
+            .get_resource_mut::<StreamRegistry>()
+            .expect(
+                "LightingPlugin requires NetworkPlugin to be added before it (StreamRegistry not found)",
+            );
+        let (sender, reader): (
+            StreamSender<LightingStreamMessage>,
+            StreamReader<LightingStreamMessage>,
+        ) = registry.register_with_budget(
+            StreamDef {
+                tag: LIGHTING_STREAM_TAG,
+                name: "lighting",
+                direction: StreamDirection::ServerToClient,
+            },
+            StreamBudget::new(StreamPriority::Low, LIGHTING_BYTES_PER_TICK),
+        );
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+
+        app.add_systems(OnExit(self.state), cleanup_lighting);
+    }
+}
+
+/// Client-side system: applies light grid snapshots and deltas from the
+/// lighting stream.
+///
+/// Deltas that arrive before the first snapshot are dropped; the next
+/// snapshot brings the client up to date.
+fn handle_lighting_updates(
+    mut commands: Commands,
+    mut reader: ResMut<StreamReader<LightingStreamMessage>>,
+    grid: Option<ResMut<LightGrid>>,
+) {
+    let mut pending: Option<LightGrid> = None;
+    let mut grid = grid;
+    for msg in reader.drain() {
+        match msg {
+            LightingStreamMessage::LightGridData {
+                width,
+                height,
+                levels,
+            } => match LightGrid::from_levels(width, height, levels) {
+                Ok(new_grid) => pending = Some(new_grid),
+                Err(e) => error!("Invalid light grid on stream {LIGHTING_STREAM_TAG}: {e}"),
+            },
+            LightingStreamMessage::LightGridDelta { changes } => {
+                if let Some(ref mut new_grid) = pending {
+                    new_grid.apply_delta_changes(&changes);
+                } else if let Some(ref mut grid) = grid {
+                    grid.apply_delta_changes(&changes);
+                }
+            }
+        }
+    }
+    if let Some(new_grid) = pending {
+        commands.insert_resource(new_grid);
+    }
+}
+
+/// Clients that joined before the [`LightGrid`] existed.  Drained once it
+/// does.
+#[derive(Resource, Default)]
+struct PendingLightSyncs(Vec<ClientId>);
+
+/// Server-side system: sends the full light grid and [`StreamReady`] to each
+/// joining client, then reports [`ModuleReadySent`].
+///
+/// [`StreamReady`]: network::StreamReady
+fn send_light_grid_on_connect(
+    mut events: MessageReader<PlayerEvent>,
+    sender: Option<Res<StreamSender<LightingStreamMessage>>>,
+    grid: Option<Res<LightGrid>>,
+    mut module_ready: MessageWriter<ModuleReadySent>,
+    mut pending: ResMut<PendingLightSyncs>,
+) {
+    for event in events.read() {
+        if let PlayerEvent::Joined { id, .. } = event {
+            pending.0.push(*id);
+        }
+    }
+    if pending.0.is_empty() {
+        return;
+    }
+    let Some(sender) = sender.as_deref() else {
+        error!(
+            "No LightingStreamMessage sender available; {} client(s) waiting",
+            pending.0.len()
+        );
+        return;
+    };
+    let Some(grid) = grid.as_deref() else {
+        return;
+    };
+
+    for client in std::mem::take(&mut pending.0) {
+        let msg = LightingStreamMessage::LightGridData {
+            width: grid.width(),
+            height: grid.height(),
+            levels: grid.levels().to_vec(),
+        };
+        if let Err(e) = sender.send_to(client, &msg) {
+            error!(
+                "Failed to send LightGridData to ClientId({}): {}",
+                client.0, e
+            );
+            continue;
+        }
+        if let Err(e) = sender.send_stream_ready_to(client) {
+            error!(
+                "Failed to send StreamReady to ClientId({}): {}",
+                client.0, e
+            );
+            continue;
+        }
+        debug!(
+            "Sent light grid {}×{} + StreamReady to ClientId({})",
+            grid.width(),
+            grid.height(),
+            client.0
+        );
+        module_ready.write(ModuleReadySent { client });
+    }
+}
+
+/// Interval between full [`LightingStreamMessage::LightGridData`] resyncs
+/// (seconds).
+const FULL_SNAPSHOT_INTERVAL: f32 = 5.0;
+
+/// Interval between [`LightingStreamMessage::LightGridDelta`] broadcasts
+/// (seconds).
+const DELTA_INTERVAL: f32 = 0.1;
+
+/// Timers that drive the periodic light grid broadcasts.
+#[derive(Resource)]
+struct LightBroadcastTimers {
+    full_snapshot: Timer,
+    delta: Timer,
+}
+
+impl Default for LightBroadcastTimers {
+    fn default() -> Self {
+        Self {
+            full_snapshot: Timer::from_seconds(FULL_SNAPSHOT_INTERVAL, TimerMode::Repeating),
+            delta: Timer::from_seconds(DELTA_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Server-side system: broadcasts light grid changes at ~10 Hz and a full
+/// snapshot every [`FULL_SNAPSHOT_INTERVAL`].
+///
+/// Both go out as deferrable state frames.  A delta is skipped while the
+/// lighting stream is saturated; its changes are folded into the next one.
+fn broadcast_light_grid(
+    time: Res<Time>,
+    mut timers: ResMut<LightBroadcastTimers>,
+    sender: Option<Res<StreamSender<LightingStreamMessage>>>,
+    grid: Option<ResMut<LightGrid>>,
+) {
+    timers.full_snapshot.tick(time.delta());
+    timers.delta.tick(time.delta());
+
+    let (Some(sender), Some(mut grid)) = (sender, grid) else {
+        return;
+    };
+
+    if timers.full_snapshot.just_finished() {
+        let msg = LightingStreamMessage::LightGridData {
+            width: grid.width(),
+            height: grid.height(),
+            levels: grid.levels().to_vec(),
+        };
+        match sender.broadcast_state(&msg) {
+            Ok(()) => grid.update_last_broadcast(),
+            Err(e) => error!("Failed to broadcast LightGridData: {e}"),
+        }
+        return;
+    }
+
+    if timers.delta.just_finished() && !sender.saturation().is_saturated() {
+        let changes = grid.compute_delta_changes();
+        if !changes.is_empty() {
+            match sender.broadcast_state(&LightingStreamMessage::LightGridDelta { changes }) {
+                Ok(()) => grid.update_last_broadcast(),
+                Err(e) => error!("Failed to broadcast LightGridDelta: {e}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tiles::TileKind;
+
+    use super::*;
+
+    fn lighting_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        let mut flags = TileFlags::new(6, 6);
+        for y in 0..6 {
+            for x in 0..6 {
+                flags.set(IVec2::new(x, y), TileKind::Floor.flags());
+            }
+        }
+        app.insert_resource(flags);
+        app.add_systems(Update, update_light_grid);
+        app
+    }
+
+    fn is_dark(app: &App) -> bool {
+        app.world()
+            .resource::<LightGrid>()
+            .is_dark(IVec2::new(1, 1))
+    }
+
+    #[test]
+    fn grid_follows_sources() {
+        let mut app = lighting_app();
+        app.update();
+        assert!(is_dark(&app));
+
+        let lamp = app
+            .world_mut()
+            .spawn((
+                LightSource::new(3.0, 1.0),
+                GlobalTransform::from_xyz(1.0, 0.5, 1.0),
+            ))
+            .id();
+        app.update();
+        assert!(!is_dark(&app));
+
+        app.world_mut().entity_mut(lamp).insert(Visibility::Hidden);
+        app.update();
+        assert!(is_dark(&app), "hidden sources give no light");
+
+        app.world_mut()
+            .entity_mut(lamp)
+            .insert(Visibility::Inherited);
+        app.update();
+        assert!(!is_dark(&app));
+
+        app.world_mut()
+            .get_mut::<LightSource>(lamp)
+            .unwrap()
+            .powered = false;
+        app.update();
+        assert!(is_dark(&app), "unpowered sources give no light");
+
+        app.world_mut()
+            .get_mut::<LightSource>(lamp)
+            .unwrap()
+            .powered = true;
+        app.update();
+        assert!(!is_dark(&app));
+
+        app.world_mut().entity_mut(lamp).despawn();
+        app.update();
+        assert!(is_dark(&app));
+    }
+}
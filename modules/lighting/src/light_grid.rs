@@ -0,0 +1,236 @@
+use bevy::prelude::*;
+use tiles::TileFlags;
+
+/// Light level of a fully lit cell.  Levels from all sources add up and are
+/// clamped here.
+pub const MAX_LIGHT: u8 = u8::MAX;
+
+/// Cells at or below this level count as dark for gameplay purposes.
+pub const DARK_THRESHOLD: u8 = 32;
+
+/// A light source as seen by [`LightGrid::recompute`]: the cell it sits in,
+/// its reach in tiles and its brightness at the centre (`0.0..=1.0`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSample {
+    pub cell: IVec2,
+    pub radius: f32,
+    pub intensity: f32,
+}
+
+/// Per-tile light levels, `0` (pitch black) to [`MAX_LIGHT`].
+///
+/// Computed on the server from [`LightSource`](crate::LightSource) things and
+/// the light-blocking tiles in [`TileFlags`], then replicated to clients on
+/// the lighting stream.  Row-major like the gas grid.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct LightGrid {
+    width: u32,
+    height: u32,
+    levels: Vec<u8>,
+    /// Levels as of the last broadcast, the baseline for
+    /// [`compute_delta_changes`](Self::compute_delta_changes).
+    last_broadcast: Vec<u8>,
+}
+
+impl LightGrid {
+    /// A completely dark grid.
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = (width as usize) * (height as usize);
+        Self {
+            width,
+            height,
+            levels: vec![0; size],
+            last_broadcast: vec![0; size],
+        }
+    }
+
+    /// Rebuilds a grid received from the server.
+    pub fn from_levels(width: u32, height: u32, levels: Vec<u8>) -> Result<Self, String> {
+        let expected = (width as usize) * (height as usize);
+        if levels.len() != expected {
+            return Err(format!(
+                "light grid {width}×{height} needs {expected} levels, got {}",
+                levels.len()
+            ));
+        }
+        Ok(Self {
+            width,
+            height,
+            last_broadcast: levels.clone(),
+            levels,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// All levels, row-major.
+    pub fn levels(&self) -> &[u8] {
+        &self.levels
+    }
+
+    fn index(&self, pos: IVec2) -> Option<usize> {
+        if pos.x >= 0 && pos.x < self.width as i32 && pos.y >= 0 && pos.y < self.height as i32 {
+            Some((pos.y * self.width as i32 + pos.x) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Light level at `pos`, or `None` when out of bounds.
+    pub fn level_at(&self, pos: IVec2) -> Option<u8> {
+        self.index(pos).map(|idx| self.levels[idx])
+    }
+
+    /// Whether `pos` is too dark to see in.  Cells outside the grid are dark.
+    pub fn is_dark(&self, pos: IVec2) -> bool {
+        self.level_at(pos)
+            .is_none_or(|level| level <= DARK_THRESHOLD)
+    }
+
+    /// Recomputes every level from `sources`.
+    ///
+    /// Each source lights the cells within its radius that it has a clear
+    /// line to, fading linearly to nothing at the edge.  Cells that block
+    /// light (see [`TileFlags::is_light_passable`]) stop the line but are
+    /// lit themselves, so walls facing a lamp are bright and the room behind
+    /// them is not.
+    pub fn recompute(&mut self, flags: &TileFlags, sources: impl IntoIterator<Item = LightSample>) {
+        let mut light = vec![0.0_f32; self.levels.len()];
+        for source in sources {
+            if source.radius <= 0.0 || source.intensity <= 0.0 {
+                continue;
+            }
+            let reach = source.radius.ceil() as i32;
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let cell = source.cell + IVec2::new(dx, dy);
+                    let Some(idx) = self.index(cell) else {
+                        continue;
+                    };
+                    let distance = Vec2::new(dx as f32, dy as f32).length();
+                    if distance > source.radius || !flags.is_visible_from(source.cell, cell) {
+                        continue;
+                    }
+                    light[idx] += source.intensity * (1.0 - distance / source.radius);
+                }
+            }
+        }
+        for (level, light) in self.levels.iter_mut().zip(light) {
+            *level = (light.clamp(0.0, 1.0) * MAX_LIGHT as f32).round() as u8;
+        }
+    }
+
+    /// Cells whose level differs from the last broadcast, as
+    /// `(index, level)` pairs.
+    pub fn compute_delta_changes(&self) -> Vec<(u32, u8)> {
+        self.levels
+            .iter()
+            .zip(&self.last_broadcast)
+            .enumerate()
+            .filter(|(_, (now, last))| now != last)
+            .map(|(idx, (&now, _))| (idx as u32, now))
+            .collect()
+    }
+
+    /// Records the current levels as broadcast.
+    pub fn update_last_broadcast(&mut self) {
+        self.last_broadcast.clone_from(&self.levels);
+    }
+
+    /// Applies `(index, level)` pairs received from the server; indices out
+    /// of range are ignored.
+    pub fn apply_delta_changes(&mut self, changes: &[(u32, u8)]) {
+        for &(idx, level) in changes {
+            if let Some(slot) = self.levels.get_mut(idx as usize) {
+                *slot = level;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tiles::TileKind;
+
+    use super::*;
+
+    /// A `width`×1 corridor of floor with walls at `walls`.
+    fn corridor(width: u32, walls: &[i32]) -> TileFlags {
+        let mut flags = TileFlags::new(width, 1);
+        for x in 0..width as i32 {
+            let kind = if walls.contains(&x) {
+                TileKind::Wall
+            } else {
+                TileKind::Floor
+            };
+            flags.set(IVec2::new(x, 0), kind.flags());
+        }
+        flags
+    }
+
+    fn lamp(x: i32, radius: f32) -> LightSample {
+        LightSample {
+            cell: IVec2::new(x, 0),
+            radius,
+            intensity: 1.0,
+        }
+    }
+
+    #[test]
+    fn light_fades_with_distance() {
+        let flags = corridor(8, &[]);
+        let mut grid = LightGrid::new(8, 1);
+        grid.recompute(&flags, [lamp(0, 4.0)]);
+
+        assert_eq!(grid.levels()[..5], [255, 191, 128, 64, 0]);
+        assert!(!grid.is_dark(IVec2::new(2, 0)));
+        assert!(grid.is_dark(IVec2::new(4, 0)));
+        assert!(grid.is_dark(IVec2::new(20, 0)));
+    }
+
+    #[test]
+    fn walls_are_lit_but_cast_shadows() {
+        let flags = corridor(6, &[2]);
+        let mut grid = LightGrid::new(6, 1);
+        grid.recompute(&flags, [lamp(0, 5.0)]);
+
+        assert!(grid.level_at(IVec2::new(2, 0)).unwrap() > 0);
+        assert_eq!(grid.level_at(IVec2::new(3, 0)), Some(0));
+    }
+
+    #[test]
+    fn overlapping_sources_add_up_to_the_cap() {
+        let flags = corridor(3, &[]);
+        let mut grid = LightGrid::new(3, 1);
+        grid.recompute(&flags, [lamp(0, 2.0), lamp(2, 2.0)]);
+
+        assert_eq!(grid.levels(), [255, 255, 255]);
+    }
+
+    #[test]
+    fn deltas_carry_changed_cells_only() {
+        let flags = corridor(4, &[]);
+        let mut server = LightGrid::new(4, 1);
+        let mut client = LightGrid::from_levels(4, 1, server.levels().to_vec()).unwrap();
+
+        server.recompute(&flags, [lamp(3, 2.0)]);
+        let changes = server.compute_delta_changes();
+        assert_eq!(changes, [(2, 128), (3, 255)]);
+
+        client.apply_delta_changes(&changes);
+        server.update_last_broadcast();
+        assert_eq!(client.levels(), server.levels());
+        assert!(server.compute_delta_changes().is_empty());
+    }
+
+    #[test]
+    fn from_levels_rejects_wrong_length() {
+        assert!(LightGrid::from_levels(2, 2, vec![0; 3]).is_err());
+    }
+}
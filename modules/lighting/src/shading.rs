@@ -0,0 +1,214 @@
+//! Darkening of unlit cells, and the debug overlay of raw light levels.
+//!
+//! Every cell of the [`LightGrid`] gets a flat quad just above the floor.
+//! Quads share a small palette of materials, one per brightness step, and
+//! swap between them as levels change, so a grid update touches no assets.
+//! Cells outside the player's [`FieldOfView`] are shaded as if barely lit,
+//! whatever light falls on them.
+
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use tiles::FieldOfView;
+
+use crate::{AmbientLightLevel, LightGrid, MAX_LIGHT};
+
+/// Number of brightness steps in each palette.
+const SHADE_STEPS: usize = 16;
+
+/// Opacity of the shade over a cell with no light at all (and no ambient).
+const MAX_SHADE_ALPHA: f32 = 0.9;
+
+/// Brightness cap of cells the player cannot see.
+const UNSEEN_BRIGHTNESS: f32 = 0.1;
+
+/// Opacity of the debug overlay colours.
+const DEBUG_ALPHA: f32 = 0.6;
+
+/// Height of the quads, just above the floor and the atmospherics overlay.
+const SHADE_HEIGHT: f32 = 0.015;
+
+/// Whether the shade quads show the false-colour light level overlay
+/// instead of darkness.  Toggled with [`Action::ToggleLightingOverlay`].
+#[derive(Resource, Debug, Default)]
+pub struct LightingDebugOverlay(pub bool);
+
+/// Quad darkening the cell at `position` according to its light level.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct LightShade {
+    pub position: IVec2,
+}
+
+/// Shared quad mesh and the two material palettes, indexed by step.
+#[derive(Resource)]
+pub(crate) struct ShadePalette {
+    mesh: Handle<Mesh>,
+    shade: Vec<Handle<StandardMaterial>>,
+    debug: Vec<Handle<StandardMaterial>>,
+}
+
+impl ShadePalette {
+    fn new(meshes: &mut Assets<Mesh>, materials: &mut Assets<StandardMaterial>) -> Self {
+        let mut material = |color: Color| {
+            materials.add(StandardMaterial {
+                base_color: color,
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })
+        };
+        let steps = (0..SHADE_STEPS).map(|step| step as f32 / (SHADE_STEPS - 1) as f32);
+        Self {
+            mesh: meshes.add(Plane3d::new(Vec3::Y, Vec2::splat(0.5))),
+            shade: steps
+                .clone()
+                .map(|brightness| material(shade_color(brightness)))
+                .collect(),
+            debug: steps.map(|level| material(debug_color(level))).collect(),
+        }
+    }
+}
+
+/// Palette step for a brightness in `0.0..=1.0`.
+fn step_of(brightness: f32) -> usize {
+    (brightness.clamp(0.0, 1.0) * (SHADE_STEPS - 1) as f32).round() as usize
+}
+
+/// Shade step for `level`, never darker than `ambient`.
+fn shade_step(level: u8, ambient: f32) -> usize {
+    step_of((level as f32 / MAX_LIGHT as f32).max(ambient))
+}
+
+/// Shade step for a cell out of sight: as [`shade_step`], but never
+/// brighter than [`UNSEEN_BRIGHTNESS`].
+fn unseen_step(level: u8, ambient: f32) -> usize {
+    shade_step(level, ambient).min(step_of(UNSEEN_BRIGHTNESS))
+}
+
+/// Translucent black that hides less of the floor the brighter the cell.
+fn shade_color(brightness: f32) -> Color {
+    Color::srgba(0.0, 0.0, 0.0, (1.0 - brightness) * MAX_SHADE_ALPHA)
+}
+
+/// Debug colour for a light level: blue for darkness, through red and
+/// yellow, to white at full light.
+fn debug_color(level: f32) -> Color {
+    if level < 0.5 {
+        let t = level * 2.0;
+        Color::srgba(t, 0.0, 1.0 - t, DEBUG_ALPHA)
+    } else {
+        let t = (level - 0.5) * 2.0;
+        Color::srgba(1.0, t, t * t, DEBUG_ALPHA)
+    }
+}
+
+/// System that toggles the debug overlay on [`Action::ToggleLightingOverlay`].
+pub(crate) fn toggle_debug_overlay(input: ActionInput, mut overlay: ResMut<LightingDebugOverlay>) {
+    if input.just_pressed(Action::ToggleLightingOverlay) {
+        overlay.0 = !overlay.0;
+        info!(
+            "Lighting debug overlay: {}",
+            if overlay.0 { "ON" } else { "OFF" }
+        );
+    }
+}
+
+/// System that spawns one [`LightShade`] per cell when a [`LightGrid`]
+/// arrives, and despawns them when it is removed or changes size.
+pub(crate) fn spawn_light_shade(
+    mut commands: Commands,
+    grid: Option<Res<LightGrid>>,
+    palette: Option<Res<ShadePalette>>,
+    shades: Query<Entity, With<LightShade>>,
+    mut spawned_size: Local<Option<UVec2>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let size = grid.as_ref().map(|g| UVec2::new(g.width(), g.height()));
+    if size == *spawned_size {
+        return;
+    }
+    for entity in shades.iter() {
+        commands.entity(entity).despawn();
+    }
+    *spawned_size = size;
+    let Some(size) = size else {
+        return;
+    };
+
+    let mesh = match palette {
+        Some(palette) => palette.mesh.clone(),
+        None => {
+            let palette = ShadePalette::new(&mut meshes, &mut materials);
+            let mesh = palette.mesh.clone();
+            commands.insert_resource(palette);
+            mesh
+        }
+    };
+    for y in 0..size.y as i32 {
+        for x in 0..size.x as i32 {
+            commands.spawn((
+                Mesh3d(mesh.clone()),
+                Transform::from_xyz(x as f32, SHADE_HEIGHT, y as f32),
+                LightShade {
+                    position: IVec2::new(x, y),
+                },
+            ));
+        }
+    }
+    info!("Spawned {}×{} light shade quads", size.x, size.y);
+}
+
+/// System that points each [`LightShade`] at the palette material for its
+/// cell's level whenever the grid, the overlay mode, the [`FieldOfView`] or
+/// the set of quads changes.  The debug overlay ignores the field of view.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_light_shade(
+    mut commands: Commands,
+    grid: Option<Res<LightGrid>>,
+    palette: Option<Res<ShadePalette>>,
+    overlay: Res<LightingDebugOverlay>,
+    ambient: Res<AmbientLightLevel>,
+    fov: Option<Res<FieldOfView>>,
+    mut had_fov: Local<bool>,
+    shades: Query<(Entity, &LightShade)>,
+    added: Query<(), Added<LightShade>>,
+) {
+    let (Some(grid), Some(palette)) = (grid, palette) else {
+        return;
+    };
+    let fov_changed = fov.as_ref().is_some_and(|fov| fov.is_changed()) || fov.is_some() != *had_fov;
+    *had_fov = fov.is_some();
+    if !grid.is_changed() && !overlay.is_changed() && !fov_changed && added.is_empty() {
+        return;
+    }
+
+    for (entity, shade) in shades.iter() {
+        let level = grid.level_at(shade.position).unwrap_or(0);
+        let material = if overlay.0 {
+            palette.debug[step_of(level as f32 / MAX_LIGHT as f32)].clone()
+        } else if fov
+            .as_ref()
+            .is_some_and(|fov| !fov.is_visible(shade.position))
+        {
+            palette.shade[unseen_step(level, ambient.0)].clone()
+        } else {
+            palette.shade[shade_step(level, ambient.0)].clone()
+        };
+        commands.entity(entity).insert(MeshMaterial3d(material));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shade_never_goes_below_ambient() {
+        assert_eq!(shade_step(0, 0.0), 0);
+        assert_eq!(shade_step(MAX_LIGHT, 0.0), SHADE_STEPS - 1);
+        assert_eq!(shade_step(0, 1.0 / 3.0), 5);
+        assert_eq!(shade_step(200, 1.0 / 3.0), shade_step(200, 0.0));
+        assert_eq!(unseen_step(MAX_LIGHT, 1.0), step_of(UNSEEN_BRIGHTNESS));
+        assert_eq!(unseen_step(0, 0.0), 0);
+    }
+}
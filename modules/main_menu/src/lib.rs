@@ -1,19 +1,27 @@
+use std::net::SocketAddr;
+
 use bevy::{
     app::AppExit, prelude::*, state::state::FreelyMutableState, state::state_scoped::DespawnOnExit,
 };
-use network::{ClientEvent, NetCommand, NetworkReceive, ServerEvent};
+use network::{
+    CertFingerprint, ClientEvent, KnownServers, NetCommand, NetworkReceive, ServerEvent,
+};
 use ui::*;
 
 mod loading_screen;
 mod settings_screen;
 mod title_screen;
+mod trust_screen;
 
 /// Configuration resource injected by the binary crate.
 /// Decouples the main-menu module from `AppConfig`.
 #[derive(Resource, Clone)]
 pub struct MainMenuConfig {
-    pub port: u16,
+    /// Server "Join" connects to.
+    pub join_addr: SocketAddr,
     pub player_name: String,
+    /// Join servers as a spectator.
+    pub spectator: bool,
 }
 
 pub struct MainMenuPlugin<S: FreelyMutableState + Copy> {
@@ -46,8 +54,24 @@ struct MainMenuEditorState<S: States>(S);
 pub enum MenuEvent {
     Title,
     Settings,
+    /// Host a game: the binary starts a server for it and joins that.
     Play,
+    /// Like [`MenuEvent::Play`], offline: the server only takes the local
+    /// player.
+    PlaySolo,
     Join,
+    /// Ask whether to trust the certificate the server at `addr` presented
+    /// when joining; see [`ClientEvent::UntrustedCertificate`].
+    TrustPrompt {
+        addr: SocketAddr,
+        fingerprint: CertFingerprint,
+        pinned: Option<CertFingerprint>,
+    },
+    /// Pin `fingerprint` for the server at `addr` and join it again.
+    TrustServer {
+        addr: SocketAddr,
+        fingerprint: CertFingerprint,
+    },
     Editor,
     Quit,
 }
@@ -87,7 +111,8 @@ fn menu_init(mut writer: MessageWriter<MenuEvent>) {
 }
 
 /// Resets the menu to the title screen when network errors or disconnects
-/// occur while still in MainMenu state (e.g. during the loading screen).
+/// occur while still in MainMenu state (e.g. during the loading screen), or
+/// asks the player to trust a server whose certificate was refused.
 fn handle_network_errors(
     mut client_events: MessageReader<ClientEvent>,
     mut server_events: MessageReader<ServerEvent>,
@@ -100,6 +125,19 @@ fn handle_network_errors(
         ) {
             menu_events.write(MenuEvent::Title);
         }
+        // Arrives after the `Disconnected`, so the prompt replaces the title.
+        if let ClientEvent::UntrustedCertificate {
+            addr,
+            fingerprint,
+            pinned,
+        } = event
+        {
+            menu_events.write(MenuEvent::TrustPrompt {
+                addr: *addr,
+                fingerprint: *fingerprint,
+                pinned: *pinned,
+            });
+        }
     }
     for event in server_events.read() {
         if matches!(event, ServerEvent::Error(_)) {
@@ -114,6 +152,7 @@ fn menu_message_reader<S: FreelyMutableState + Copy>(
     query: Query<Entity, With<MenuRoot>>,
     theme: Res<UiTheme>,
     config: Res<MainMenuConfig>,
+    known: Res<KnownServers>,
     mut messages: MessageReader<MenuEvent>,
     mut exit: MessageWriter<AppExit>,
     mut net_commands: MessageWriter<NetCommand>,
@@ -133,17 +172,39 @@ fn menu_message_reader<S: FreelyMutableState + Copy>(
                 &mut commands,
                 theme.as_ref(),
             )),
-            MenuEvent::Play => {
-                net_commands.write(NetCommand::Host { port: config.port });
+            MenuEvent::Play | MenuEvent::PlaySolo => MenuEventResult::ReplaceChildren(
+                loading_screen::spawn(&mut commands, theme.as_ref()),
+            ),
+            MenuEvent::Join => {
+                net_commands.write(NetCommand::Connect {
+                    addr: config.join_addr,
+                    name: config.player_name.clone(),
+                    spectator: config.spectator,
+                });
                 MenuEventResult::ReplaceChildren(loading_screen::spawn(
                     &mut commands,
                     theme.as_ref(),
                 ))
             }
-            MenuEvent::Join => {
+            MenuEvent::TrustPrompt {
+                addr,
+                fingerprint,
+                pinned,
+            } => MenuEventResult::ReplaceChildren(trust_screen::spawn(
+                &mut commands,
+                theme.as_ref(),
+                *addr,
+                *fingerprint,
+                *pinned,
+            )),
+            MenuEvent::TrustServer { addr, fingerprint } => {
+                if let Err(e) = known.pin(*addr, *fingerprint) {
+                    warn!("Failed to save the certificate of {addr}: {e}");
+                }
                 net_commands.write(NetCommand::Connect {
-                    addr: ([127u8, 0u8, 0u8, 1u8], config.port).into(),
+                    addr: *addr,
                     name: config.player_name.clone(),
+                    spectator: config.spectator,
                 });
                 MenuEventResult::ReplaceChildren(loading_screen::spawn(
                     &mut commands,
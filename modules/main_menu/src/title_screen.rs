@@ -32,6 +32,11 @@ pub fn spawn(commands: &mut Commands, theme: &UiTheme) -> Vec<Entity> {
         .with_event(MenuEvent::Play)
         .build(commands);
 
+    let solo_button = build_button(theme)
+        .with_text("Play Solo")
+        .with_event(MenuEvent::PlaySolo)
+        .build(commands);
+
     let join_button = build_button(theme)
         .with_text("Join")
         .with_event(MenuEvent::Join)
@@ -57,6 +62,7 @@ pub fn spawn(commands: &mut Commands, theme: &UiTheme) -> Vec<Entity> {
         subtitle,
         spacer,
         play_button,
+        solo_button,
         join_button,
         editor_button,
         settings_button,
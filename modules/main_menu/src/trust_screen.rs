@@ -0,0 +1,87 @@
+use std::net::SocketAddr;
+
+use bevy::prelude::*;
+use network::CertFingerprint;
+use ui::{UiTheme, build_button};
+
+use crate::MenuEvent;
+
+/// Asks whether to trust the certificate `fingerprint` presented by the
+/// server at `addr`; `pinned` is the one trusted before, if it changed.
+pub fn spawn(
+    commands: &mut Commands,
+    theme: &UiTheme,
+    addr: SocketAddr,
+    fingerprint: CertFingerprint,
+    pinned: Option<CertFingerprint>,
+) -> Vec<Entity> {
+    let (heading, explanation) = match pinned {
+        Some(_) => (
+            "Server certificate changed",
+            format!(
+                "The server at {addr} presents a different certificate than last time. \
+                 It may have been reinstalled, or someone may be impersonating it."
+            ),
+        ),
+        None => (
+            "Unknown server",
+            format!("You have not joined the server at {addr} before."),
+        ),
+    };
+
+    let heading = commands
+        .spawn((
+            Text::new(heading),
+            TextFont::from_font_size(theme.font_size_heading),
+            TextColor(theme.text),
+        ))
+        .id();
+
+    let explanation = commands
+        .spawn((
+            Text::new(explanation),
+            TextFont::from_font_size(theme.font_size_body),
+            TextColor(theme.text),
+        ))
+        .id();
+
+    let mut entities = vec![heading, explanation];
+
+    if let Some(pinned) = pinned {
+        entities.push(
+            commands
+                .spawn((
+                    Text::new(format!("Trusted before: {pinned}")),
+                    TextFont::from_font_size(theme.font_size_small),
+                    TextColor(theme.text_muted),
+                ))
+                .id(),
+        );
+    }
+
+    entities.push(
+        commands
+            .spawn((
+                Text::new(format!("Certificate: {fingerprint}")),
+                TextFont::from_font_size(theme.font_size_small),
+                TextColor(theme.text_muted),
+            ))
+            .id(),
+    );
+
+    entities.push(
+        build_button(theme)
+            .with_text("Trust and Join")
+            .with_event(MenuEvent::TrustServer { addr, fingerprint })
+            .build(commands),
+    );
+
+    entities.push(
+        build_button(theme)
+            .with_text("Back")
+            .with_event(MenuEvent::Title)
+            .build(commands),
+    );
+
+    entities
+}
@@ -0,0 +1,582 @@
+//! Per-client replication budgets for server→client module streams.
+//!
+//! Every server→client stream has a [`StreamBudget`]: a priority and an
+//! optional byte allowance per client per network tick, given to
+//! [`StreamRegistry::register_with_budget`](crate::StreamRegistry::register_with_budget)
+//! or [`StreamBudget::UNLIMITED`] by default.  The server task runs
+//! each outgoing frame through a [`BudgetScheduler`]:
+//!
+//! - Frames sent with [`StreamSender::send_to`](crate::StreamSender::send_to),
+//!   [`StreamSender::broadcast`](crate::StreamSender::broadcast) and the
+//!   `StreamReady` sentinel are never deferred.  Lifecycle messages (spawns,
+//!   despawns, initial bursts) use these.  Their bytes still count against the
+//!   budget.
+//! - Frames sent with [`StreamSender::send_state_to`](crate::StreamSender::send_state_to)
+//!   or [`StreamSender::broadcast_state`](crate::StreamSender::broadcast_state)
+//!   are deferrable.  Once a stream's allowance or the client's total
+//!   allowance for the tick is used up, they queue per client and stream.
+//!   Queued frames are released on later ticks, highest priority first, in
+//!   the order they were sent.
+//!
+//! The control stream (tag 0) does not go through the scheduler at all.
+//!
+//! Each client is also paced by how well its connection keeps up.  A client
+//! whose write channels back up, or whose round trip exceeds
+//! [`CONGESTED_RTT`], gets deferrable frames only every few ticks: the
+//! interval doubles while it stays congested, up to [`MAX_SEND_INTERVAL`],
+//! and halves again after [`RECOVERY_TICKS`] ticks without congestion.  Its
+//! frames wait in its own queues in the meantime, so one bad connection
+//! never fills the channels shared with everyone else.  A paced client's
+//! queue holds only the latest deferrable frame of each stream: a newer state
+//! frame supersedes the one still waiting, so the client is actually sent
+//! less instead of the same data later.  Changes carried only by a dropped
+//! delta reach it with the stream's next full state.
+//!
+//! After every tick the scheduler publishes a [`StreamSaturation`] per stream.
+//! Modules read it through [`StreamSender::saturation`](crate::StreamSender::saturation)
+//! and can lower their send rate while their stream is saturated.  Paced
+//! clients are left out of it: they catch up at their own rate instead of
+//! holding the stream back for every other client.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::log;
+use bytes::Bytes;
+
+use crate::{ClientId, RegisteredStream, StreamDirection};
+
+/// Total bytes of module-stream data sent to one client per network tick
+/// before deferrable frames start queueing (~2 MB/s at 30 Hz).
+pub(crate) const CLIENT_BYTES_PER_TICK: usize = 64 * 1024;
+
+/// Deferrable frames queued per client and stream before new ones are dropped.
+const MAX_QUEUED_FRAMES: usize = 256;
+
+/// Longest a congested client waits between deliveries of deferrable
+/// frames, in network ticks.
+pub(crate) const MAX_SEND_INTERVAL: u32 = 8;
+
+/// Smoothed round trip above which a client counts as congested.
+pub(crate) const CONGESTED_RTT: Duration = Duration::from_millis(250);
+
+/// Ticks without congestion before a paced client's interval halves (~1 s at
+/// 30 Hz).
+pub(crate) const RECOVERY_TICKS: u32 = 30;
+
+/// Release order of deferred frames when several streams compete for a
+/// client's per-tick allowance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StreamPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Replication budget a server→client stream is registered with, or the
+/// frame limit of a client→server one.  See
+/// [`StreamRegistry::register_with_budget`](crate::StreamRegistry::register_with_budget).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamBudget {
+    /// Ignored for client→server streams.
+    pub priority: StreamPriority,
+    /// Bytes per client per network tick; `None` leaves the stream bounded
+    /// only by the client's total allowance.  Ignored for client→server
+    /// streams.
+    pub bytes_per_tick: Option<usize>,
+    /// Longest frame a client may send on a client→server stream; `None`
+    /// leaves it to [`ClientFrameLimits`](crate::ClientFrameLimits).
+    /// Ignored for server→client streams.
+    pub max_frame_bytes: Option<usize>,
+}
+
+impl StreamBudget {
+    /// Normal priority with no per-stream limit.
+    pub const UNLIMITED: Self = Self {
+        priority: StreamPriority::Normal,
+        bytes_per_tick: None,
+        max_frame_bytes: None,
+    };
+
+    /// A stream allowed `bytes_per_tick` bytes per client per tick.
+    pub const fn new(priority: StreamPriority, bytes_per_tick: usize) -> Self {
+        Self {
+            priority,
+            bytes_per_tick: Some(bytes_per_tick),
+            max_frame_bytes: None,
+        }
+    }
+
+    /// This budget, with clients' frames limited to `bytes`.
+    pub const fn with_max_frame(self, bytes: usize) -> Self {
+        Self {
+            max_frame_bytes: Some(bytes),
+            ..self
+        }
+    }
+}
+
+/// Load of one stream over the last network tick, taken over its busiest
+/// client.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StreamSaturation {
+    /// Bytes sent on the stream to the busiest client.
+    pub sent_bytes: usize,
+    /// Deferrable frames waiting across all clients.
+    pub queued_frames: usize,
+    /// Bytes sent plus bytes waiting, relative to the stream's
+    /// [`StreamBudget::bytes_per_tick`].  Always `0.0` for streams without a
+    /// per-stream limit.
+    pub load: f32,
+    /// Clients receiving deferrable frames at a reduced rate because their
+    /// connection is congested.  They are not counted in the figures above.
+    pub paced_clients: usize,
+}
+
+impl StreamSaturation {
+    /// Returns `true` when the stream is using its whole allowance or has
+    /// frames waiting; sending more deferrable data only grows the queue.
+    pub fn is_saturated(&self) -> bool {
+        self.queued_frames > 0 || self.load >= 1.0
+    }
+}
+
+/// Saturation figures published by the server task, shared with every
+/// [`StreamSender`](crate::StreamSender).
+pub(crate) type SharedSaturation = Arc<Mutex<HashMap<u8, StreamSaturation>>>;
+
+/// A deferred frame released for writing: destination client, stream tag and
+/// payload.
+pub(crate) type ReleasedFrame = (ClientId, u8, Bytes);
+
+#[derive(Debug, Default)]
+struct Lane {
+    used: usize,
+    queue: VecDeque<Bytes>,
+    queued_bytes: usize,
+}
+
+/// How often one client gets deferrable frames.
+#[derive(Debug)]
+struct Pace {
+    /// Network ticks between the client's turns; 1 is every tick.
+    interval: u32,
+    /// Ticks until the client's next turn; 0 while this tick is one.
+    wait: u32,
+    /// The client's write channels backed up during this tick.
+    backpressure: bool,
+    /// Congestion was seen since the client's last turn.
+    congested: bool,
+    /// Consecutive ticks without congestion.
+    clear_ticks: u32,
+    /// Smoothed round trip, each sample weighted 1/8.
+    rtt: Option<Duration>,
+}
+
+impl Default for Pace {
+    fn default() -> Self {
+        Self {
+            interval: 1,
+            wait: 0,
+            backpressure: false,
+            congested: false,
+            clear_ticks: 0,
+            rtt: None,
+        }
+    }
+}
+
+impl Pace {
+    /// Whether deferrable frames may go out to the client right now.
+    fn is_due(&self) -> bool {
+        self.wait == 0 && !self.backpressure
+    }
+
+    /// Advances to the next tick, adapting the interval on the client's
+    /// turns.  Returns the new interval when it changed.  Backpressure still
+    /// holds frames back until the caller clears it.
+    fn tick(&mut self) -> Option<u32> {
+        let congested = self.backpressure || self.rtt.is_some_and(|rtt| rtt > CONGESTED_RTT);
+        self.congested |= congested;
+        if congested {
+            self.clear_ticks = 0;
+        } else {
+            self.clear_ticks += 1;
+        }
+
+        if self.wait > 0 {
+            self.wait -= 1;
+            return None;
+        }
+        let previous = self.interval;
+        if self.congested {
+            self.interval = (self.interval * 2).min(MAX_SEND_INTERVAL);
+        } else if self.clear_ticks >= RECOVERY_TICKS {
+            self.interval = (self.interval / 2).max(1);
+            self.clear_ticks = 0;
+        }
+        self.congested = false;
+        self.wait = self.interval - 1;
+        (self.interval != previous).then_some(self.interval)
+    }
+}
+
+/// Returns `true` if `len` more bytes fit in `limit` after `used` bytes.
+/// The first frame of a tick always fits so oversized frames still go out.
+fn fits(used: usize, len: usize, limit: usize) -> bool {
+    used == 0 || used + len <= limit
+}
+
+/// Enforces [`StreamBudget`]s inside the server task.
+pub(crate) struct BudgetScheduler {
+    budgets: HashMap<u8, StreamBudget>,
+    client_bytes_per_tick: usize,
+    lanes: HashMap<(ClientId, u8), Lane>,
+    client_used: HashMap<ClientId, usize>,
+    paces: HashMap<ClientId, Pace>,
+}
+
+impl BudgetScheduler {
+    pub(crate) fn new(streams: &[RegisteredStream], client_bytes_per_tick: usize) -> Self {
+        let budgets = streams
+            .iter()
+            .filter(|s| s.def.direction == StreamDirection::ServerToClient)
+            .map(|s| (s.def.tag, s.budget))
+            .collect();
+        Self {
+            budgets,
+            client_bytes_per_tick,
+            lanes: HashMap::new(),
+            client_used: HashMap::new(),
+            paces: HashMap::new(),
+        }
+    }
+
+    /// Records that a write channel of `client` is backing up: its
+    /// deferrable frames wait for the next tick, and it counts as congested.
+    pub(crate) fn note_backpressure(&mut self, client: ClientId) {
+        self.paces.entry(client).or_default().backpressure = true;
+    }
+
+    /// Folds a round-trip measurement of `client` into its pace.
+    pub(crate) fn note_rtt(&mut self, client: ClientId, rtt: Duration) {
+        let pace = self.paces.entry(client).or_default();
+        pace.rtt = Some(pace.rtt.map_or(rtt, |smoothed| (smoothed * 7 + rtt) / 8));
+    }
+
+    /// Accounts a frame for `client` on stream `tag`.  Returns the frame if it
+    /// may be written now; otherwise it is queued for a later tick,
+    /// replacing the frames queued for a paced client, or dropped when the
+    /// queue is full.
+    pub(crate) fn admit(
+        &mut self,
+        client: ClientId,
+        tag: u8,
+        data: Bytes,
+        deferrable: bool,
+    ) -> Option<Bytes> {
+        let budget = self.budgets.get(&tag).copied().unwrap_or_default();
+        let len = data.len();
+        let pace = self.paces.get(&client);
+        let due = pace.is_none_or(Pace::is_due);
+        let paced = pace.is_some_and(|pace| pace.interval > 1);
+        let client_used = self.client_used.entry(client).or_default();
+        let lane = self.lanes.entry((client, tag)).or_default();
+
+        // A paced client gets one frame per stream on its turn, possibly the
+        // one just released from the queue.
+        let within_budget = due
+            && lane.queue.is_empty()
+            && !(paced && lane.used > 0)
+            && budget
+                .bytes_per_tick
+                .is_none_or(|limit| fits(lane.used, len, limit))
+            && fits(*client_used, len, self.client_bytes_per_tick);
+        if !deferrable || within_budget {
+            lane.used += len;
+            *client_used += len;
+            return Some(data);
+        }
+
+        if paced {
+            lane.queue.clear();
+            lane.queued_bytes = 0;
+        } else if lane.queue.len() >= MAX_QUEUED_FRAMES {
+            log::warn!(
+                "Stream {} to client {}: {} frames already deferred, dropping frame",
+                tag,
+                client.0,
+                lane.queue.len()
+            );
+            return None;
+        }
+        lane.queued_bytes += len;
+        lane.queue.push_back(data);
+        None
+    }
+
+    /// Closes the current tick: records saturation, resets the allowances,
+    /// adapts each client's pace and releases as many deferred frames as the
+    /// new tick allows to the clients whose turn it is, highest priority
+    /// first.
+    pub(crate) fn next_tick(&mut self) -> (HashMap<u8, StreamSaturation>, Vec<ReleasedFrame>) {
+        let saturation = self.saturation();
+
+        for (client, pace) in &mut self.paces {
+            let previous = pace.interval;
+            match pace.tick() {
+                Some(interval) if interval > previous => log::info!(
+                    "Client {} is congested; sending state every {} ticks",
+                    client.0,
+                    interval
+                ),
+                Some(interval) => log::info!(
+                    "Client {} is recovering; sending state every {} ticks",
+                    client.0,
+                    interval
+                ),
+                None => {}
+            }
+        }
+
+        self.lanes.retain(|_, lane| !lane.queue.is_empty());
+        for lane in self.lanes.values_mut() {
+            lane.used = 0;
+        }
+        self.client_used.clear();
+        let paces = &self.paces;
+        let mut keys: Vec<(ClientId, u8)> = self
+            .lanes
+            .keys()
+            .copied()
+            .filter(|(client, _)| paces.get(client).is_none_or(Pace::is_due))
+            .collect();
+        keys.sort_by_key(|&(client, tag)| {
+            let priority = self.budgets.get(&tag).map(|b| b.priority);
+            (Reverse(priority), tag, client.0)
+        });
+
+        let mut released = Vec::new();
+        for key in keys {
+            let (client, tag) = key;
+            let limit = self.budgets.get(&tag).and_then(|b| b.bytes_per_tick);
+            let client_used = self.client_used.entry(client).or_default();
+            let Some(lane) = self.lanes.get_mut(&key) else {
+                continue;
+            };
+            while let Some(len) = lane.queue.front().map(Bytes::len) {
+                if !limit.is_none_or(|limit| fits(lane.used, len, limit))
+                    || !fits(*client_used, len, self.client_bytes_per_tick)
+                {
+                    break;
+                }
+                let Some(data) = lane.queue.pop_front() else {
+                    break;
+                };
+                lane.used += len;
+                lane.queued_bytes -= len;
+                *client_used += len;
+                released.push((client, tag, data));
+            }
+        }
+        for pace in self.paces.values_mut() {
+            pace.backpressure = false;
+        }
+        (saturation, released)
+    }
+
+    /// Forgets the pace of every client `connected` rejects.
+    pub(crate) fn retain_clients(&mut self, connected: impl Fn(ClientId) -> bool) {
+        self.paces.retain(|&client, _| connected(client));
+    }
+
+    /// Forgets everything queued for a client that disconnected.
+    pub(crate) fn remove_client(&mut self, client: ClientId) {
+        self.lanes.retain(|&(c, _), _| c != client);
+        self.client_used.remove(&client);
+        self.paces.remove(&client);
+    }
+
+    fn saturation(&self) -> HashMap<u8, StreamSaturation> {
+        let mut out: HashMap<u8, StreamSaturation> = self
+            .budgets
+            .keys()
+            .map(|&tag| (tag, StreamSaturation::default()))
+            .collect();
+        let paced: Vec<ClientId> = self
+            .paces
+            .iter()
+            .filter(|(_, pace)| pace.interval > 1)
+            .map(|(&client, _)| client)
+            .collect();
+        for entry in out.values_mut() {
+            entry.paced_clients = paced.len();
+        }
+        for (&(client, tag), lane) in &self.lanes {
+            if paced.contains(&client) {
+                continue;
+            }
+            let entry = out.entry(tag).or_default();
+            entry.sent_bytes = entry.sent_bytes.max(lane.used);
+            entry.queued_frames += lane.queue.len();
+            if let Some(limit) = self.budgets.get(&tag).and_then(|b| b.bytes_per_tick) {
+                let load = (lane.used + lane.queued_bytes) as f32 / limit.max(1) as f32;
+                entry.load = entry.load.max(load);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StreamDef;
+
+    const HIGH: u8 = 1;
+    const LOW: u8 = 2;
+
+    fn scheduler(client_bytes_per_tick: usize) -> BudgetScheduler {
+        let def = |tag, priority| RegisteredStream {
+            def: StreamDef {
+                tag,
+                name: "test",
+                direction: StreamDirection::ServerToClient,
+            },
+            budget: StreamBudget::new(priority, 100),
+        };
+        BudgetScheduler::new(
+            &[
+                def(HIGH, StreamPriority::High),
+                def(LOW, StreamPriority::Low),
+            ],
+            client_bytes_per_tick,
+        )
+    }
+
+    fn frame(len: usize) -> Bytes {
+        Bytes::from(vec![0u8; len])
+    }
+
+    #[test]
+    fn deferrable_frames_queue_past_the_stream_budget() {
+        let mut s = scheduler(CLIENT_BYTES_PER_TICK);
+        let client = ClientId(1);
+
+        assert!(s.admit(client, LOW, frame(60), true).is_some());
+        assert!(s.admit(client, LOW, frame(60), true).is_none());
+        // Lifecycle frames are never held back, even over budget.
+        assert!(s.admit(client, LOW, frame(60), false).is_some());
+
+        let (saturation, released) = s.next_tick();
+        let low = saturation[&LOW];
+        assert_eq!(low.sent_bytes, 120);
+        assert_eq!(low.queued_frames, 1);
+        assert!(low.is_saturated());
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].1, LOW);
+
+        let (saturation, released) = s.next_tick();
+        assert!(!saturation[&LOW].is_saturated());
+        assert!(released.is_empty());
+    }
+
+    #[test]
+    fn deferred_frames_release_by_priority_within_client_budget() {
+        let mut s = scheduler(100);
+        let client = ClientId(7);
+
+        // Fill the client's allowance, then queue one frame on each stream.
+        assert!(s.admit(client, HIGH, frame(100), false).is_some());
+        assert!(s.admit(client, LOW, frame(80), true).is_none());
+        assert!(s.admit(client, HIGH, frame(80), true).is_none());
+
+        let (_, released) = s.next_tick();
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].1, HIGH);
+
+        let (_, released) = s.next_tick();
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].1, LOW);
+
+        s.admit(client, LOW, frame(100), false);
+        s.admit(client, LOW, frame(10), true);
+        s.remove_client(client);
+        let (saturation, released) = s.next_tick();
+        assert!(released.is_empty());
+        assert_eq!(saturation[&LOW].queued_frames, 0);
+    }
+
+    #[test]
+    fn congested_clients_are_paced_without_holding_back_others() {
+        let mut s = scheduler(CLIENT_BYTES_PER_TICK);
+        let good = ClientId(1);
+        let bad = ClientId(2);
+
+        // A backed-up client's deferrable frames wait; the others flow.
+        s.note_backpressure(bad);
+        assert!(s.admit(bad, LOW, frame(10), true).is_none());
+        assert!(s.admit(good, LOW, frame(10), true).is_some());
+        let (_, released) = s.next_tick();
+        assert!(released.is_empty());
+        assert_eq!(s.paces[&bad].interval, 2);
+
+        // Off its turn the paced client queues, without saturating the
+        // stream; only its latest frame is kept.
+        assert!(s.admit(bad, LOW, frame(20), true).is_none());
+        assert!(s.admit(good, LOW, frame(10), true).is_some());
+        let (saturation, released) = s.next_tick();
+        assert_eq!(saturation[&LOW].paced_clients, 1);
+        assert!(!saturation[&LOW].is_saturated());
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].0, bad);
+        assert_eq!(released[0].2.len(), 20);
+
+        // A clear connection recovers the full rate.
+        for _ in 0..RECOVERY_TICKS {
+            s.next_tick();
+        }
+        assert_eq!(s.paces[&bad].interval, 1);
+
+        // A slow round trip counts as congestion too.
+        s.note_rtt(bad, CONGESTED_RTT * 2);
+        for _ in 0..2 * MAX_SEND_INTERVAL {
+            s.next_tick();
+        }
+        assert_eq!(s.paces[&bad].interval, MAX_SEND_INTERVAL);
+        s.retain_clients(|client| client == good);
+        assert!(s.paces.is_empty());
+    }
+
+    #[test]
+    fn paced_clients_receive_fewer_bytes() {
+        let mut s = scheduler(CLIENT_BYTES_PER_TICK);
+        let good = ClientId(1);
+        let bad = ClientId(2);
+        s.note_rtt(bad, CONGESTED_RTT * 2);
+
+        // Both clients are sent the same state frame every tick.
+        let mut received: HashMap<ClientId, usize> = HashMap::new();
+        for _ in 0..64 {
+            for client in [good, bad] {
+                if let Some(data) = s.admit(client, LOW, frame(50), true) {
+                    *received.entry(client).or_default() += data.len();
+                }
+            }
+            let (_, released) = s.next_tick();
+            for (client, _, data) in released {
+                *received.entry(client).or_default() += data.len();
+            }
+        }
+
+        assert_eq!(received[&good], 64 * 50);
+        assert!(
+            received[&bad] * 4 < received[&good],
+            "paced client received {} bytes",
+            received[&bad]
+        );
+    }
+}
@@ -0,0 +1,447 @@
+//! Server certificates and how clients come to trust them.
+//!
+//! A server presents a self-signed certificate, ideally the same one every
+//! time: [`ServerIdentity::load_or_generate`] keeps it on disk.  Clients
+//! identify it by its [`CertFingerprint`] and, with
+//! [`CertVerification::TrustOnFirstUse`], pin the fingerprint of each server
+//! in [`KnownServers`] once the player accepts it.  A server presenting an
+//! unknown or changed certificate is refused with
+//! [`ClientEvent::UntrustedCertificate`](crate::ClientEvent::UntrustedCertificate)
+//! so the UI can ask the player.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, SignatureScheme};
+use serde::Deserialize;
+
+/// SHA-256 digest of a certificate's DER encoding.  Displayed and parsed as
+/// colon-separated hex (`AB:CD:…`).
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CertFingerprint([u8; 32]);
+
+impl CertFingerprint {
+    /// Fingerprint of a DER-encoded certificate.
+    pub fn of(cert_der: &[u8]) -> Self {
+        let digest = ring::digest::digest(&ring::digest::SHA256, cert_der);
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(digest.as_ref());
+        Self(bytes)
+    }
+}
+
+impl fmt::Display for CertFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(":")?;
+            }
+            write!(f, "{byte:02X}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for CertFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CertFingerprint({self})")
+    }
+}
+
+impl FromStr for CertFingerprint {
+    type Err = String;
+
+    /// Parses 64 hex digits, with or without colons, in either case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits: Vec<u8> = s.bytes().filter(|b| *b != b':').collect();
+        if digits.len() != 64 {
+            return Err(format!("expected 32 hex bytes, got '{s}'"));
+        }
+        let mut bytes = [0; 32];
+        for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|e| e.to_string())?;
+            *byte = u8::from_str_radix(pair, 16)
+                .map_err(|_| format!("invalid hex byte '{pair}' in '{s}'"))?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+/// The certificate and private key a server hosts with.  Without one,
+/// `NetCommand::Host` generates a throwaway certificate, so clients see a
+/// new fingerprint on every run.
+#[derive(Resource)]
+pub struct ServerIdentity {
+    cert: CertificateDer<'static>,
+    key: PrivateKeyDer<'static>,
+}
+
+impl Clone for ServerIdentity {
+    fn clone(&self) -> Self {
+        Self {
+            cert: self.cert.clone(),
+            key: self.key.clone_key(),
+        }
+    }
+}
+
+impl fmt::Debug for ServerIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerIdentity")
+            .field("fingerprint", &self.fingerprint())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServerIdentity {
+    /// A new self-signed certificate for "localhost".
+    pub fn generate() -> Result<Self, Box<dyn std::error::Error>> {
+        let rcgen::CertifiedKey { cert, signing_key } =
+            rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+        Ok(Self {
+            cert: cert.der().clone(),
+            key: PrivateKeyDer::try_from(signing_key.serialize_der())?,
+        })
+    }
+
+    /// Reads the DER-encoded certificate and key at `cert_path` and
+    /// `key_path`, or generates a pair and writes it there when either file
+    /// is missing.
+    pub fn load_or_generate(
+        cert_path: &Path,
+        key_path: &Path,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if cert_path.exists() && key_path.exists() {
+            let cert = CertificateDer::from(std::fs::read(cert_path)?);
+            let key = PrivateKeyDer::try_from(std::fs::read(key_path)?)
+                .map_err(|e| format!("{}: {e}", key_path.display()))?;
+            return Ok(Self { cert, key });
+        }
+
+        let identity = Self::generate()?;
+        for path in [cert_path, key_path] {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+        }
+        std::fs::write(cert_path, identity.cert.as_ref())?;
+        std::fs::write(key_path, identity.key.secret_der())?;
+        info!(
+            "Generated a server certificate at {} ({})",
+            cert_path.display(),
+            identity.fingerprint()
+        );
+        Ok(identity)
+    }
+
+    pub fn fingerprint(&self) -> CertFingerprint {
+        CertFingerprint::of(&self.cert)
+    }
+
+    pub(crate) fn cert(&self) -> CertificateDer<'static> {
+        self.cert.clone()
+    }
+
+    pub(crate) fn key(&self) -> PrivateKeyDer<'static> {
+        self.key.clone_key()
+    }
+}
+
+/// How a client checks the certificate a QUIC server presents.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertVerification {
+    /// Accept servers pinned in [`KnownServers`]; refuse any other with
+    /// [`ClientEvent::UntrustedCertificate`](crate::ClientEvent::UntrustedCertificate).
+    #[default]
+    TrustOnFirstUse,
+    /// Accept any certificate.  Only for development: anyone on the path can
+    /// impersonate the server.
+    Skip,
+}
+
+/// Certificate fingerprints the client trusts, per server address.  Clones
+/// share the same store.
+#[derive(Resource, Clone, Default)]
+pub struct KnownServers(Arc<Mutex<KnownServersInner>>);
+
+impl fmt::Debug for KnownServers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KnownServers").finish_non_exhaustive()
+    }
+}
+
+#[derive(Default)]
+struct KnownServersInner {
+    /// File pins are saved to; `None` keeps them in memory.
+    path: Option<PathBuf>,
+    pinned: HashMap<SocketAddr, CertFingerprint>,
+    /// Certificates trusted for this run only, next to the pinned ones.
+    allowed: HashSet<(SocketAddr, CertFingerprint)>,
+}
+
+/// What [`KnownServers`] says about a certificate a server presents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Trust {
+    Trusted,
+    Unknown,
+    /// The server's pinned certificate is a different one.
+    Changed {
+        pinned: CertFingerprint,
+    },
+}
+
+impl KnownServers {
+    /// Pins saved in `path`, one `address fingerprint` line each; a missing
+    /// file starts empty.  New pins are saved back there.
+    pub fn load(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let mut pinned = HashMap::new();
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                for (number, line) in contents.lines().enumerate() {
+                    let line = line.trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    match parse_pin(line) {
+                        Some((addr, fingerprint)) => {
+                            pinned.insert(addr, fingerprint);
+                        }
+                        None => warn!(
+                            "{}:{}: ignoring malformed known server '{line}'",
+                            path.display(),
+                            number + 1
+                        ),
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(Self(Arc::new(Mutex::new(KnownServersInner {
+            path: Some(path),
+            pinned,
+            allowed: HashSet::new(),
+        }))))
+    }
+
+    /// The fingerprint pinned for `addr`.
+    pub fn pinned(&self, addr: SocketAddr) -> Option<CertFingerprint> {
+        self.0.lock().ok()?.pinned.get(&addr).copied()
+    }
+
+    /// Trusts `fingerprint` for `addr` from now on, replacing any previous
+    /// pin, and saves the pins.
+    pub fn pin(&self, addr: SocketAddr, fingerprint: CertFingerprint) -> io::Result<()> {
+        let mut inner = self
+            .0
+            .lock()
+            .map_err(|_| io::Error::other("known servers lock poisoned"))?;
+        inner.pinned.insert(addr, fingerprint);
+        inner.save()
+    }
+
+    /// Trusts `fingerprint` for `addr` until the app exits, without pinning
+    /// it; e.g. for a server this process hosts itself.
+    pub fn allow(&self, addr: SocketAddr, fingerprint: CertFingerprint) {
+        if let Ok(mut inner) = self.0.lock() {
+            inner.allowed.insert((addr, fingerprint));
+        }
+    }
+
+    pub(crate) fn check(&self, addr: SocketAddr, fingerprint: CertFingerprint) -> Trust {
+        let Ok(inner) = self.0.lock() else {
+            return Trust::Unknown;
+        };
+        if inner.allowed.contains(&(addr, fingerprint)) {
+            return Trust::Trusted;
+        }
+        match inner.pinned.get(&addr) {
+            Some(pinned) if *pinned == fingerprint => Trust::Trusted,
+            Some(pinned) => Trust::Changed { pinned: *pinned },
+            None => Trust::Unknown,
+        }
+    }
+}
+
+impl KnownServersInner {
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut pins: Vec<_> = self.pinned.iter().collect();
+        pins.sort_by_key(|(addr, _)| **addr);
+        let mut contents = String::from("# Server certificates trusted by this client.\n");
+        for (addr, fingerprint) in pins {
+            contents.push_str(&format!("{addr} {fingerprint}\n"));
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, contents)
+    }
+}
+
+fn parse_pin(line: &str) -> Option<(SocketAddr, CertFingerprint)> {
+    let (addr, fingerprint) = line.split_once(char::is_whitespace)?;
+    Some((addr.parse().ok()?, fingerprint.trim().parse().ok()?))
+}
+
+/// A server certificate the client refused, returned from connecting.
+#[derive(Debug, Clone)]
+pub(crate) struct UntrustedCertificate {
+    pub(crate) addr: SocketAddr,
+    pub(crate) fingerprint: CertFingerprint,
+    pub(crate) pinned: Option<CertFingerprint>,
+}
+
+impl fmt::Display for UntrustedCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pinned {
+            Some(pinned) => write!(
+                f,
+                "certificate of {} changed from {pinned} to {}",
+                self.addr, self.fingerprint
+            ),
+            None => write!(
+                f,
+                "unknown certificate {} for {}",
+                self.fingerprint, self.addr
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UntrustedCertificate {}
+
+/// Accepts the server at `addr` only with a certificate [`KnownServers`]
+/// trusts, remembering the one it refused.
+#[derive(Debug)]
+pub(crate) struct PinnedServerVerification {
+    provider: Arc<CryptoProvider>,
+    addr: SocketAddr,
+    known: KnownServers,
+    refused: Mutex<Option<UntrustedCertificate>>,
+}
+
+impl PinnedServerVerification {
+    pub(crate) fn new(addr: SocketAddr, known: KnownServers) -> Arc<Self> {
+        Arc::new(Self {
+            provider: Arc::new(rustls::crypto::ring::default_provider()),
+            addr,
+            known,
+            refused: Mutex::new(None),
+        })
+    }
+
+    /// The certificate refused during the handshake, if that is why it
+    /// failed.
+    pub(crate) fn take_refused(&self) -> Option<UntrustedCertificate> {
+        self.refused.lock().ok()?.take()
+    }
+}
+
+impl ServerCertVerifier for PinnedServerVerification {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let fingerprint = CertFingerprint::of(end_entity);
+        let (pinned, error) = match self.known.check(self.addr, fingerprint) {
+            Trust::Trusted => return Ok(ServerCertVerified::assertion()),
+            Trust::Unknown => (None, CertificateError::UnknownIssuer),
+            Trust::Changed { pinned } => (
+                Some(pinned),
+                CertificateError::ApplicationVerificationFailure,
+            ),
+        };
+        if let Ok(mut refused) = self.refused.lock() {
+            *refused = Some(UntrustedCertificate {
+                addr: self.addr,
+                fingerprint,
+                pinned,
+            });
+        }
+        Err(rustls::Error::InvalidCertificate(error))
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_round_trip_through_the_known_servers_file() {
+        let dir = std::env::temp_dir().join(format!("known-servers-{}", std::process::id()));
+        let path = dir.join("known_servers.txt");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let addr: SocketAddr = ([10, 0, 0, 5], 7777).into();
+        let first = ServerIdentity::generate().unwrap().fingerprint();
+        let second = ServerIdentity::generate().unwrap().fingerprint();
+        assert_eq!(first.to_string().parse::<CertFingerprint>(), Ok(first));
+
+        let known = KnownServers::load(&path).unwrap();
+        assert_eq!(known.check(addr, first), Trust::Unknown);
+        known.pin(addr, first).unwrap();
+
+        let reloaded = KnownServers::load(&path).unwrap();
+        assert_eq!(reloaded.check(addr, first), Trust::Trusted);
+        assert_eq!(
+            reloaded.check(addr, second),
+            Trust::Changed { pinned: first }
+        );
+        reloaded.allow(addr, second);
+        assert_eq!(reloaded.check(addr, second), Trust::Trusted);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
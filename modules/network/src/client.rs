@@ -1,36 +1,59 @@
-use std::net::SocketAddr;
+use std::time::Instant;
 
 use bevy::log;
 use bytes::Bytes;
-use futures_util::sink::SinkExt;
-use futures_util::stream::StreamExt;
 use tokio::sync::mpsc;
 use tokio::task::JoinSet;
-use tokio_util::codec::{FramedRead, FramedWrite, LengthDelimitedCodec};
 use tokio_util::sync::CancellationToken;
 
 use crate::ClientEvent;
-use crate::config;
-use crate::protocol::{ClientMessage, ServerMessage, StreamReady, decode, encode};
+use crate::certs::UntrustedCertificate;
+use crate::protocol::{
+    ClientMessage, PROTOCOL_VERSION, ServerMessage, StreamManifestEntry, StreamReady, decode,
+    encode,
+};
+use crate::quality;
+use crate::transport::Dial;
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn run_client(
-    addr: SocketAddr,
+    dial: Dial,
     event_tx: mpsc::UnboundedSender<ClientEvent>,
     client_msg_rx: mpsc::Receiver<ClientMessage>,
     cancel_token: CancellationToken,
     name: String,
+    spectator: bool,
+    streams: Vec<StreamManifestEntry>,
     client_stream_rxs: Vec<(u8, mpsc::Receiver<Bytes>)>,
 ) {
     if let Err(e) = run_client_inner(
-        addr,
+        dial,
         &event_tx,
         client_msg_rx,
         cancel_token,
         name,
+        spectator,
+        streams,
         client_stream_rxs,
     )
     .await
     {
+        if let Some(untrusted) = e.downcast_ref::<UntrustedCertificate>() {
+            log::warn!("Refused to connect: {untrusted}");
+            if let Err(err) = event_tx.send(ClientEvent::Disconnected {
+                reason: untrusted.to_string(),
+            }) {
+                log::error!("Failed to send ClientEvent::Disconnected: {}", err);
+            }
+            if let Err(err) = event_tx.send(ClientEvent::UntrustedCertificate {
+                addr: untrusted.addr,
+                fingerprint: untrusted.fingerprint,
+                pinned: untrusted.pinned,
+            }) {
+                log::error!("Failed to send ClientEvent::UntrustedCertificate: {}", err);
+            }
+            return;
+        }
         let reason = format!("Client error: {e}");
         if let Err(err) = event_tx.send(ClientEvent::Error(reason.clone())) {
             log::error!("Failed to send ClientEvent::Error: {}", err);
@@ -41,26 +64,20 @@ pub(crate) async fn run_client(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_client_inner(
-    addr: SocketAddr,
+    dial: Dial,
     event_tx: &mpsc::UnboundedSender<ClientEvent>,
     mut client_msg_rx: mpsc::Receiver<ClientMessage>,
     cancel_token: CancellationToken,
     name: String,
+    spectator: bool,
+    streams: Vec<StreamManifestEntry>,
     client_stream_rxs: Vec<(u8, mpsc::Receiver<Bytes>)>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let client_config = config::build_client_config()?;
-
-    let bind_addr = match addr {
-        SocketAddr::V4(_) => ([0, 0, 0, 0], 0u16).into(),
-        SocketAddr::V6(_) => ([0u16; 8], 0u16).into(),
-    };
-    let mut endpoint = quinn::Endpoint::client(bind_addr)?;
-    endpoint.set_default_client_config(client_config);
-
-    log::info!("Connecting to {addr}...");
-    let connection = endpoint.connect(addr, "localhost")?.await?;
-    log::info!("Connected to {addr}");
+    log::info!("Connecting to {dial}...");
+    let connection = dial.connect().await?;
+    log::info!("Connected to {dial}");
 
     if let Err(err) = event_tx.send(ClientEvent::Connected) {
         log::error!("Failed to send Connected event: {}", err);
@@ -71,7 +88,7 @@ async fn run_client_inner(
         result = connection.open_bi() => result,
         _ = cancel_token.cancelled() => {
             log::info!("Client disconnect requested before opening stream");
-            connection.close(0u32.into(), b"disconnect requested");
+            connection.close(0, "disconnect requested");
             if let Err(err) = event_tx.send(ClientEvent::Disconnected {
                 reason: "Disconnect requested".into(),
             }) {
@@ -90,7 +107,7 @@ async fn run_client_inner(
         }
     };
 
-    let (send_stream, recv_stream) = match open_result {
+    let (mut framed_write, mut framed_read) = match open_result {
         Ok(streams) => streams,
         Err(e) => {
             log::error!("Failed to open bi-directional stream: {}", e);
@@ -103,13 +120,15 @@ async fn run_client_inner(
         }
     };
 
-    // Wrap control stream with LengthDelimitedCodec.
-    let mut framed_read = FramedRead::new(recv_stream, LengthDelimitedCodec::new());
-    let mut framed_write = FramedWrite::new(send_stream, LengthDelimitedCodec::new());
-
     // Send Hello immediately — this makes the bi stream visible to accept_bi()
-    // on the server and delivers the client's display name.
-    let hello = ClientMessage::Hello { name };
+    // on the server and delivers the client's protocol version, display name
+    // and stream table.
+    let hello = ClientMessage::Hello {
+        protocol_version: PROTOCOL_VERSION,
+        name,
+        spectator,
+        streams,
+    };
     if let Ok(bytes) = encode(&hello) {
         if let Err(e) = framed_write.send(Bytes::from(bytes)).await {
             log::error!("Failed to send client hello: {}", e);
@@ -132,16 +151,16 @@ async fn run_client_inner(
     // Create per-client cancellation token to coordinate shutdown.
     let client_cancel = CancellationToken::new();
 
-    // Open client→server unidirectional streams for each registered ClientToServer stream.
-    // For each stream: write 1-byte routing tag, then spawn a write loop that reads from
-    // the stream's channel and writes LengthDelimitedCodec-framed messages to the QUIC stream.
+    // Open client→server unidirectional streams for each registered ClientToServer stream,
+    // announced with their routing tag, then spawn a write loop per stream that forwards the
+    // stream's channel to the connection.
     let mut client_stream_write_tasks: JoinSet<()> = JoinSet::new();
     for (tag, mut rx) in client_stream_rxs {
         let open_result = tokio::select! {
-            result = connection.open_uni() => result,
+            result = connection.open_uni(tag) => result,
             _ = cancel_token.cancelled() => {
                 log::info!("Client disconnect requested before opening client→server stream tag={}", tag);
-                connection.close(0u32.into(), b"disconnect requested");
+                connection.close(0, "disconnect requested");
                 if let Err(err) = event_tx.send(ClientEvent::Disconnected {
                     reason: "Disconnect requested".into(),
                 }) {
@@ -160,11 +179,11 @@ async fn run_client_inner(
             }
         };
 
-        let mut send = match open_result {
+        let mut framed_write = match open_result {
             Ok(s) => s,
             Err(e) => {
                 log::error!("Failed to open client→server uni stream tag={}: {}", tag, e);
-                connection.close(0u32.into(), b"stream setup failed");
+                connection.close(0, "stream setup failed");
                 client_cancel.cancel();
                 client_stream_write_tasks.shutdown().await;
                 if let Err(err) = event_tx.send(ClientEvent::Disconnected {
@@ -176,25 +195,6 @@ async fn run_client_inner(
             }
         };
 
-        // Write routing tag byte so the server can identify this stream.
-        if let Err(e) = send.write_all(&[tag]).await {
-            log::error!(
-                "Failed to write tag byte for client→server stream tag={}: {}",
-                tag,
-                e
-            );
-            connection.close(0u32.into(), b"stream setup failed");
-            client_cancel.cancel();
-            client_stream_write_tasks.shutdown().await;
-            if let Err(err) = event_tx.send(ClientEvent::Disconnected {
-                reason: format!("Failed to write tag byte for client→server stream tag={tag}: {e}"),
-            }) {
-                log::error!("Failed to send Disconnected event: {}", err);
-            }
-            return Ok(());
-        }
-
-        let mut framed_write = FramedWrite::new(send, LengthDelimitedCodec::new());
         let cancel_global = cancel_token.clone();
         let cancel_client = client_cancel.clone();
         client_stream_write_tasks.spawn(async move {
@@ -232,8 +232,7 @@ async fn run_client_inner(
     }
 
     // Uni-stream accept loop: runs concurrently with the control-stream loops.
-    // For each server→client unidirectional stream: read the tag byte, then
-    // route framed messages to ClientEvent::StreamFrame / StreamReady events.
+    // For each server→client unidirectional stream, route its frames to ClientEvent::StreamFrame / StreamReady events.
     // Per-stream tasks are tracked in a JoinSet and awaited before the handle
     // returns, preventing post-disconnect events from leaking out.
     // Pre-compute the canonical StreamReady encoding for exact byte comparison.
@@ -260,15 +259,7 @@ async fn run_client_inner(
                 }
                 result = connection_uni.accept_uni() => {
                     match result {
-                        Ok(mut recv) => {
-                            // Read 1-byte routing tag.
-                            let mut tag_buf = [0u8; 1];
-                            if let Err(e) = recv.read_exact(&mut tag_buf).await {
-                                log::error!("Failed to read stream tag byte: {}", e);
-                                // Tag read failure is stream-local; continue accepting other streams.
-                                continue;
-                            }
-                            let tag = tag_buf[0];
+                        Ok((tag, mut recv)) => {
                             if tag == 0 {
                                 log::warn!("Ignoring uni stream with reserved tag=0");
                                 continue;
@@ -281,8 +272,6 @@ async fn run_client_inner(
                             let client_cancel_stream = client_cancel_uni.clone();
                             let ready_bytes = stream_ready_bytes.clone();
                             stream_tasks.spawn(async move {
-                                let mut framed =
-                                    FramedRead::new(recv, LengthDelimitedCodec::new());
                                 loop {
                                     tokio::select! {
                                         _ = cancel_token_stream.cancelled() => {
@@ -293,7 +282,7 @@ async fn run_client_inner(
                                             log::debug!("Uni-stream reader cancelled (client shutdown), tag={}", tag);
                                             break;
                                         }
-                                        frame = framed.next() => {
+                                        frame = recv.next() => {
                                             match frame {
                                                 Some(Ok(bytes)) => {
                                                     // Detect the StreamReady sentinel via exact
@@ -308,7 +297,7 @@ async fn run_client_inner(
                                                         let _ = frame_tx.send(
                                                             ClientEvent::StreamFrame {
                                                                 tag,
-                                                                data: bytes.freeze(),
+                                                                data: bytes,
                                                             },
                                                         );
                                                     }
@@ -343,6 +332,11 @@ async fn run_client_inner(
         stream_tasks.shutdown().await;
     });
 
+    // Pings on this connection are stamped relative to now.  The read loop
+    // hands Pong answers to the write loop.
+    let epoch = Instant::now();
+    let (pong_tx, mut pong_rx) = mpsc::unbounded_channel::<ClientMessage>();
+
     // Control-stream read loop
     let client_cancel_read = client_cancel.clone();
     let event_tx_read = event_tx.clone();
@@ -362,6 +356,14 @@ async fn run_client_inner(
                     match frame {
                         Some(Ok(bytes)) => {
                             match decode::<ServerMessage>(&bytes) {
+                                Ok(ServerMessage::Ping { stamp }) => {
+                                    let _ = pong_tx.send(ClientMessage::Pong { stamp });
+                                }
+                                Ok(ServerMessage::Pong { stamp }) => {
+                                    let _ = event_tx_read.send(ClientEvent::RttMeasured {
+                                        rtt: quality::round_trip(epoch, stamp),
+                                    });
+                                }
                                 Ok(message) => {
                                     if let Err(err) = event_tx_read.send(ClientEvent::ServerMessageReceived(message)) {
                                         log::error!("Failed to send ServerMessageReceived event: {}", err);
@@ -390,8 +392,9 @@ async fn run_client_inner(
     let client_cancel_write = client_cancel.clone();
     let cancel_token_write = cancel_token.clone();
     let mut write_handle = tokio::spawn(async move {
+        let mut ping_timer = quality::ping_timer();
         loop {
-            tokio::select! {
+            let message = tokio::select! {
                 _ = cancel_token_write.cancelled() => {
                     log::debug!("Write loop cancelled (disconnect requested)");
                     break;
@@ -400,39 +403,42 @@ async fn run_client_inner(
                     log::debug!("Write loop cancelled (client shutdown)");
                     break;
                 }
+                _ = ping_timer.tick() => ClientMessage::Ping {
+                    stamp: quality::ping_stamp(epoch),
+                },
+                Some(pong) = pong_rx.recv() => pong,
                 message = client_msg_rx.recv() => {
                     match message {
-                        Some(message) => {
-                            match encode(&message) {
-                                Ok(bytes) => {
-                                    tokio::select! {
-                                        result = framed_write.send(Bytes::from(bytes)) => {
-                                            if let Err(e) = result {
-                                                log::error!("Failed to send message to host (stream error): {}", e);
-                                                break;
-                                            }
-                                        }
-                                        _ = cancel_token_write.cancelled() => {
-                                            log::debug!("Write loop cancelled during send (disconnect requested)");
-                                            break;
-                                        }
-                                        _ = client_cancel_write.cancelled() => {
-                                            log::debug!("Write loop cancelled during send (client shutdown)");
-                                            break;
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    log::error!("Failed to encode message: {}", e);
-                                }
-                            }
-                        }
+                        Some(message) => message,
                         None => {
                             log::debug!("Write channel closed");
                             break;
                         }
                     }
                 }
+            };
+            match encode(&message) {
+                Ok(bytes) => {
+                    tokio::select! {
+                        result = framed_write.send(Bytes::from(bytes)) => {
+                            if let Err(e) = result {
+                                log::error!("Failed to send message to host (stream error): {}", e);
+                                break;
+                            }
+                        }
+                        _ = cancel_token_write.cancelled() => {
+                            log::debug!("Write loop cancelled during send (disconnect requested)");
+                            break;
+                        }
+                        _ = client_cancel_write.cancelled() => {
+                            log::debug!("Write loop cancelled during send (client shutdown)");
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::error!("Failed to encode message: {}", e);
+                }
             }
         }
     });
@@ -441,7 +447,7 @@ async fn run_client_inner(
     let reason = tokio::select! {
         _ = cancel_token.cancelled() => {
             log::info!("Client disconnect requested");
-            connection.close(0u32.into(), b"disconnect requested");
+            connection.close(0, "disconnect requested");
             "Disconnect requested"
         }
         _ = &mut read_handle => {
@@ -458,6 +464,13 @@ async fn run_client_inner(
         }
     };
 
+    // Prefer the server's explanation when it closed the connection (e.g. an
+    // incompatible stream table).
+    let reason = match connection.peer_close_reason() {
+        Some(close_reason) => format!("Server closed the connection: {close_reason}"),
+        None => reason.to_string(),
+    };
+
     // Cancel all client tasks to ensure they stop cleanly.
     client_cancel.cancel();
 
@@ -471,10 +484,8 @@ async fn run_client_inner(
     // Wait for all client→server stream write tasks to finish cleanly.
     client_stream_write_tasks.shutdown().await;
 
-    log::info!("Disconnected from {addr}");
-    if let Err(err) = event_tx.send(ClientEvent::Disconnected {
-        reason: reason.into(),
-    }) {
+    log::info!("Disconnected from {dial}");
+    if let Err(err) = event_tx.send(ClientEvent::Disconnected { reason }) {
         log::error!("Failed to send ClientEvent::Disconnected: {}", err);
     }
 
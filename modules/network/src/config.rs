@@ -2,45 +2,32 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use quinn::{ClientConfig, IdleTimeout, ServerConfig, TransportConfig};
-use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::client::danger::ServerCertVerifier;
+use rustls::pki_types::CertificateDer;
 
-pub(crate) fn generate_self_signed_cert()
--> Result<(CertificateDer<'static>, PrivateKeyDer<'static>), Box<dyn std::error::Error>> {
-    let rcgen::CertifiedKey { cert, signing_key } =
-        rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
-    let cert_der = cert.der().clone();
-    let key_der = PrivateKeyDer::try_from(signing_key.serialize_der())?;
-    Ok((cert_der, key_der))
-}
+use crate::certs::ServerIdentity;
 
-pub(crate) fn build_server_config() -> Result<ServerConfig, Box<dyn std::error::Error>> {
-    let (cert, key) = generate_self_signed_cert()?;
-    let mut server_config = ServerConfig::with_single_cert(vec![cert], key)?;
+pub(crate) fn build_server_config(
+    identity: &ServerIdentity,
+) -> Result<ServerConfig, Box<dyn std::error::Error>> {
+    let mut server_config = ServerConfig::with_single_cert(vec![identity.cert()], identity.key())?;
     server_config.transport_config(Arc::new(transport_config()));
     Ok(server_config)
 }
 
-pub(crate) fn build_client_config() -> Result<ClientConfig, Box<dyn std::error::Error>> {
-    #[cfg(not(debug_assertions))]
-    {
-        return Err(
-            "Production TLS not yet implemented — use a debug build for development.".into(),
-        );
-    }
-
-    #[cfg(debug_assertions)]
-    {
-        let crypto = rustls::ClientConfig::builder()
-            .dangerous()
-            .with_custom_certificate_verifier(SkipServerVerification::new())
-            .with_no_client_auth();
+pub(crate) fn build_client_config(
+    verifier: Arc<dyn ServerCertVerifier>,
+) -> Result<ClientConfig, Box<dyn std::error::Error>> {
+    let crypto = rustls::ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(verifier)
+        .with_no_client_auth();
 
-        let mut client_config = ClientConfig::new(Arc::new(
-            quinn::crypto::rustls::QuicClientConfig::try_from(crypto)?,
-        ));
-        client_config.transport_config(Arc::new(transport_config()));
-        Ok(client_config)
-    }
+    let mut client_config = ClientConfig::new(Arc::new(
+        quinn::crypto::rustls::QuicClientConfig::try_from(crypto)?,
+    ));
+    client_config.transport_config(Arc::new(transport_config()));
+    Ok(client_config)
 }
 
 fn transport_config() -> TransportConfig {
@@ -53,19 +40,17 @@ fn transport_config() -> TransportConfig {
     transport
 }
 
-/// Dev-only: accepts any server certificate without validation.
-#[cfg(debug_assertions)]
+/// Accepts any server certificate without validation, for
+/// [`CertVerification::Skip`](crate::CertVerification::Skip).
 #[derive(Debug)]
-struct SkipServerVerification(Arc<rustls::crypto::CryptoProvider>);
+pub(crate) struct SkipServerVerification(Arc<rustls::crypto::CryptoProvider>);
 
-#[cfg(debug_assertions)]
 impl SkipServerVerification {
-    fn new() -> Arc<Self> {
+    pub(crate) fn new() -> Arc<Self> {
         Arc::new(Self(Arc::new(rustls::crypto::ring::default_provider())))
     }
 }
 
-#[cfg(debug_assertions)]
 impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
     fn verify_server_cert(
         &self,
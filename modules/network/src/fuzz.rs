@@ -0,0 +1,354 @@
+//! Malformed frames for property tests of the decode paths.
+//!
+//! Whatever bytes a client puts on a stream reach
+//! [`StreamReader::drain_from_client`](crate::StreamReader::drain_from_client),
+//! the wincode decoder and then the systems handling the stream, so none of
+//! them may panic or allocate without bound on bad input.
+//!
+//! [`FrameFuzzer`] derives malformed frames from valid encodings (truncated,
+//! bit-flipped, with inflated lengths, spliced, padded) or from nothing
+//! (noise behind a small enum variant index).  It is seeded, so a failing
+//! case can be replayed.  [`check_decode`] asserts a message type survives
+//! them; [`fuzz_client_streams`] feeds them to every client→server stream
+//! of an app and runs its handler systems.
+//!
+//! The memory bound is only checked in test binaries that install
+//! [`CountingAllocator`] as their global allocator, like this crate's.
+//! Compiled like [`crate::testing`].
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use bevy::prelude::*;
+use bytes::Bytes;
+use wincode::SchemaRead;
+use wincode::config::DefaultConfig;
+
+use crate::{ClientFrameConfig, ClientId, StreamDirection, StreamRegistry, protocol};
+
+/// Most bytes decoding one frame may allocate, on top of eight times the
+/// frame's length.  A length prefix may make wincode preallocate up to its
+/// 4 MiB limit once per nested collection before it finds the frame short.
+pub const MAX_DECODE_ALLOCATION: usize = 16 << 20;
+
+/// Like [`MAX_DECODE_ALLOCATION`], for frames decoded with
+/// [`ClientFrameConfig`], whose preallocation limit is 64 KiB.
+pub const MAX_CLIENT_DECODE_ALLOCATION: usize = 256 << 10;
+
+/// Client that [`fuzz_client_streams`] sends its frames as.
+pub const FUZZ_CLIENT: ClientId = ClientId(u64::MAX - 1);
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Global allocator counting the bytes each thread allocates, for
+/// [`allocated_bytes`].  Install it in a test binary with
+/// `#[global_allocator]`.
+pub struct CountingAllocator;
+
+// SAFETY: forwards every call to the system allocator unchanged.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATED.try_with(|total| total.set(total.get().saturating_add(layout.size())));
+        // SAFETY: the caller upholds `GlobalAlloc::alloc`'s contract.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: `ptr` was allocated by `System` with `layout`.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+/// Bytes allocated by this thread so far; always 0 without
+/// [`CountingAllocator`].
+pub fn allocated_bytes() -> usize {
+    ALLOCATED.with(Cell::get)
+}
+
+/// Seeded generator of malformed frames.
+pub struct FrameFuzzer {
+    state: u64,
+}
+
+impl FrameFuzzer {
+    pub fn new(seed: u64) -> Self {
+        // xorshift must not start at zero.
+        Self {
+            state: seed ^ 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+
+    /// Next pseudo-random number (xorshift64*).
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number in `0..bound`; 0 when `bound` is 0.
+    fn below(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            0
+        } else {
+            (self.next() % bound as u64) as usize
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+
+    /// A malformed variant of `valid`, or noise when it is empty.
+    pub fn mutate(&mut self, valid: &[u8]) -> Vec<u8> {
+        if valid.is_empty() {
+            return self.noise();
+        }
+        let mut frame = valid.to_vec();
+        match self.below(7) {
+            // Truncated.
+            0 => frame.truncate(self.below(valid.len())),
+            // A few bits flipped.
+            1 => {
+                for _ in 0..=self.below(4) {
+                    let bit = self.below(frame.len() * 8);
+                    frame[bit / 8] ^= 1 << (bit % 8);
+                }
+            }
+            // A huge length or variant index: eight bytes of 0xff.
+            2 => {
+                let at = self.below(frame.len());
+                let end = (at + 8).min(frame.len());
+                frame[at..end].fill(0xff);
+            }
+            // A byte inserted or removed.
+            3 => {
+                let at = self.below(frame.len());
+                if self.next().is_multiple_of(2) {
+                    frame.remove(at);
+                } else {
+                    frame.insert(at, self.next() as u8);
+                }
+            }
+            // Garbage after a valid message.
+            4 => {
+                let len = 1 + self.below(16);
+                frame.extend(self.bytes(len));
+            }
+            // The head of one message on the tail of random bytes.
+            5 => {
+                let keep = self.below(frame.len());
+                frame.truncate(keep);
+                let len = self.below(64);
+                frame.extend(self.bytes(len));
+            }
+            _ => frame = self.noise(),
+        }
+        frame
+    }
+
+    /// Random bytes, usually behind a small enum variant index so that
+    /// decoding gets past the first field.
+    pub fn noise(&mut self) -> Vec<u8> {
+        let mut frame = Vec::new();
+        if !self.next().is_multiple_of(4) {
+            frame.extend((self.below(16) as u32).to_le_bytes());
+        }
+        let len = self.below(96);
+        frame.extend(self.bytes(len));
+        frame
+    }
+}
+
+/// Decodes `cases` malformed frames derived from `samples` as `T` and
+/// panics if one of them makes decoding panic or allocate more than
+/// [`MAX_DECODE_ALLOCATION`] plus eight times its length.  Returns how
+/// many decoded anyway.
+pub fn check_decode<T>(samples: &[Vec<u8>], seed: u64, cases: usize) -> usize
+where
+    for<'de> T: SchemaRead<'de, DefaultConfig, Dst = T>,
+{
+    check_with(
+        samples,
+        seed,
+        cases,
+        MAX_DECODE_ALLOCATION,
+        protocol::decode::<T>,
+    )
+}
+
+/// Like [`check_decode`], but decodes the way frames from clients are, with
+/// [`ClientFrameConfig`], and holds them to [`MAX_CLIENT_DECODE_ALLOCATION`].
+pub fn check_client_decode<T>(samples: &[Vec<u8>], seed: u64, cases: usize) -> usize
+where
+    for<'de> T: SchemaRead<'de, ClientFrameConfig, Dst = T>,
+{
+    check_with(
+        samples,
+        seed,
+        cases,
+        MAX_CLIENT_DECODE_ALLOCATION,
+        protocol::decode_from_client::<T>,
+    )
+}
+
+fn check_with<T>(
+    samples: &[Vec<u8>],
+    seed: u64,
+    cases: usize,
+    limit: usize,
+    decode: impl Fn(&[u8]) -> wincode::ReadResult<T>,
+) -> usize {
+    let mut fuzzer = FrameFuzzer::new(seed);
+    let mut decoded = 0;
+    for case in 0..cases {
+        let frame = match samples.get(case % (samples.len() + 1)) {
+            Some(valid) => fuzzer.mutate(valid),
+            None => fuzzer.noise(),
+        };
+        let before = allocated_bytes();
+        let result = decode(&frame);
+        let allocated = allocated_bytes() - before;
+        assert!(
+            allocated <= limit + frame.len() * 8,
+            "decoding {} bytes as {} allocated {allocated} bytes (seed {seed}, case {case}): {frame:02x?}",
+            frame.len(),
+            std::any::type_name::<T>(),
+        );
+        decoded += usize::from(result.is_ok());
+    }
+    decoded
+}
+
+/// Routes `frames_per_stream` malformed frames from [`FUZZ_CLIENT`] to every
+/// client→server stream registered in `app` and runs one update after each
+/// batch, so the handler systems see them.  `samples` gives valid frames
+/// by stream name to derive from; other streams get noise.
+pub fn fuzz_client_streams(
+    app: &mut App,
+    samples: &[(&str, Vec<u8>)],
+    seed: u64,
+    frames_per_stream: usize,
+) {
+    let mut fuzzer = FrameFuzzer::new(seed);
+    let streams: Vec<(u8, String)> = app
+        .world()
+        .resource::<StreamRegistry>()
+        .manifest()
+        .into_iter()
+        .filter(|entry| entry.direction == StreamDirection::ClientToServer)
+        .map(|entry| (entry.tag, entry.name))
+        .collect();
+    for batch in 0..frames_per_stream.div_ceil(8) {
+        for (tag, name) in &streams {
+            let valid: Vec<&Vec<u8>> = samples
+                .iter()
+                .filter(|(stream, _)| stream == name)
+                .map(|(_, frame)| frame)
+                .collect();
+            for i in 0..8 {
+                let frame = match valid.get((batch * 8 + i) % (valid.len() + 1)) {
+                    Some(valid) => fuzzer.mutate(valid),
+                    None => fuzzer.noise(),
+                };
+                app.world()
+                    .resource::<StreamRegistry>()
+                    .route_client_stream_frame(FUZZ_CLIENT, *tag, Bytes::from(frame));
+            }
+        }
+        app.update();
+    }
+}
+
+#[cfg(test)]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ClientMessage, ServerMessage, StreamDef, StreamManifestEntry, StreamReader, StreamSender,
+    };
+
+    const CASES: usize = 20_000;
+
+    fn encoded<T: wincode::SchemaWrite<DefaultConfig, Src = T>>(messages: &[T]) -> Vec<Vec<u8>> {
+        messages
+            .iter()
+            .map(|message| protocol::encode(message).expect("encode"))
+            .collect()
+    }
+
+    #[test]
+    fn control_messages_decode_malformed_frames_without_panicking() {
+        let hello = encoded(&[
+            ClientMessage::Hello {
+                protocol_version: protocol::PROTOCOL_VERSION,
+                name: "Ann".into(),
+                spectator: false,
+                streams: vec![StreamManifestEntry {
+                    tag: 3,
+                    name: "tiles".into(),
+                    direction: StreamDirection::ServerToClient,
+                    schema: 0x1234,
+                }],
+            },
+            ClientMessage::Input {
+                direction: [0.5, 0.0, -1.0],
+                sprint: true,
+                crouch: false,
+            },
+            ClientMessage::Ping { stamp: 99 },
+        ]);
+        check_client_decode::<ClientMessage>(&hello, 1, CASES);
+        let welcome = encoded(&[
+            ServerMessage::InitialStateDone,
+            ServerMessage::Tunable {
+                name: "items.interaction_range".into(),
+                value: 2.0,
+            },
+        ]);
+        check_decode::<ServerMessage>(&welcome, 2, CASES);
+    }
+
+    #[test]
+    fn client_streams_survive_malformed_frames() {
+        #[derive(Resource, Default)]
+        struct Handled(usize);
+
+        fn handle(mut reader: ResMut<StreamReader<ClientMessage>>, mut handled: ResMut<Handled>) {
+            handled.0 += reader.drain_from_client().count();
+        }
+
+        let mut app = App::new();
+        let mut registry = StreamRegistry::default();
+        let (_sender, reader): (StreamSender<ClientMessage>, _) = registry.register(StreamDef {
+            tag: 40,
+            name: "fuzzed",
+            direction: StreamDirection::ClientToServer,
+        });
+        app.insert_resource(registry);
+        app.insert_resource(reader);
+        app.init_resource::<Handled>();
+        app.add_systems(Update, handle);
+
+        let valid = protocol::encode(&ClientMessage::Input {
+            direction: [1.0, 0.0, 0.0],
+            sprint: false,
+            crouch: true,
+        })
+        .expect("encode");
+        fuzz_client_streams(&mut app, &[("fuzzed", valid)], 3, 2_000);
+
+        assert!(
+            app.world()
+                .resource::<StreamRegistry>()
+                .buffered_frames()
+                .is_empty()
+        );
+        assert!(app.world().resource::<Handled>().0 < 2_000);
+    }
+}
@@ -1,7 +1,8 @@
-use std::collections::{HashMap, VecDeque};
-use std::net::SocketAddr;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::{IpAddr, SocketAddr};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use bevy::app::MainScheduleOrder;
 use bevy::ecs::schedule::ScheduleLabel;
@@ -10,19 +11,57 @@ use bevy::{log, prelude::*};
 use bytes::Bytes;
 use tokio::sync::mpsc;
 
+mod budget;
+mod certs;
 mod client;
 mod config;
+#[cfg(any(test, feature = "testing"))]
+pub mod fuzz;
+mod limits;
 mod orchestrate;
 mod protocol;
+mod quality;
+mod roster;
+mod round;
+mod rpc;
 mod runtime;
 mod server;
-
+mod shutdown;
+mod tags;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod tick;
+mod transport;
+mod tunables;
+mod vote;
+
+use budget::SharedSaturation;
+pub use budget::{StreamBudget, StreamPriority, StreamSaturation};
+pub use certs::{CertFingerprint, CertVerification, KnownServers, ServerIdentity};
+pub use limits::ClientFrameLimits;
+use limits::DecodeFailures;
 use protocol::encode as proto_encode;
-pub use protocol::{ClientId, ClientMessage, EntityState, NetId, ServerMessage, StreamReady};
+pub use protocol::{
+    ClientFrameConfig, ClientId, ClientMessage, EntityState, NetId, PROTOCOL_VERSION, RosterEntry,
+    ServerGameState, ServerMessage, StreamManifestEntry, StreamReady,
+};
+pub use quality::{ConnectionQuality, ConnectionWarning, PING_INTERVAL, RttStats};
+pub use roster::{PlayerRoster, ROSTER_REFRESH_INTERVAL};
+pub use round::RoundState;
+pub use rpc::{
+    RpcAppExt, RpcCall, RpcClient, RpcDef, RpcError, RpcId, RpcMessage, RpcRequest, RpcServer,
+};
 use runtime::{
     ClientEventReceiver, ClientEventSender, NetworkRuntime, NetworkTasks, ServerCommand,
     ServerEventReceiver, ServerEventSender,
 };
+pub use shutdown::ShutdownNotice;
+pub use tags::{FIRST_NAMED_TAG, StreamRegistryError, stream_tag};
+pub use tick::{CurrentTick, DEFAULT_TICK_RATE, SimulationTick};
+use transport::{Bind, Dial};
+pub use transport::{LOOPBACK_ADDR, Loopback, local_address};
+pub use tunables::{TunableRequest, Tunables, TunablesAppExt, set_tunable, tunable_values};
+pub use vote::{VOTES_STREAM_TAG, VoteKind, VoteRequest};
 
 /// Bounded channel buffer size for client outbound messages.
 /// Prevents memory exhaustion if game code produces messages faster than network can send.
@@ -67,9 +106,40 @@ pub enum NetworkSet {
 /// Commands sent by game code to control the network layer.
 #[derive(Message, Clone, Debug)]
 pub enum NetCommand {
-    Host { port: u16 },
-    Connect { addr: SocketAddr, name: String },
+    /// Hosts over QUIC on `port` at each of `addresses`; 0 lets the OS pick
+    /// one, reported in [`ServerEvent::HostingStarted`].  No addresses
+    /// listens on every IPv4 and IPv6 interface, skipping IPv6 on hosts
+    /// without it.  The server presents the [`ServerIdentity`] resource's
+    /// certificate if there is one.
+    Host {
+        port: u16,
+        addresses: Vec<IpAddr>,
+    },
+    /// Hosts on an in-process [`Loopback`] instead of a socket.
+    HostLoopback(Loopback),
+    /// Connects to `addr` as `name`; with `spectator` set the client joins
+    /// as an observer.  The server's certificate is checked as the
+    /// [`CertVerification`] resource says.
+    Connect {
+        addr: SocketAddr,
+        name: String,
+        spectator: bool,
+    },
+    /// Like [`NetCommand::Connect`], to the server hosting on `loopback`.
+    ConnectLoopback {
+        loopback: Loopback,
+        name: String,
+        spectator: bool,
+    },
     StopHosting,
+    /// Shuts the server down gracefully: announces `message` to every
+    /// client, refuses new connections, emits [`ServerEvent::ShutdownStarted`]
+    /// so the world can be saved, and after `delay` closes every connection
+    /// with `message` as the reason before hosting stops.
+    ShutdownServer {
+        message: String,
+        delay: Duration,
+    },
     Disconnect,
 }
 
@@ -99,6 +169,11 @@ impl Server {
         self.next_net_id += 1;
         NetId(id)
     }
+
+    /// Every [`NetId`] handed out so far is below this one.
+    pub fn issued_below(&self) -> NetId {
+        NetId(self.next_net_id)
+    }
 }
 
 /// Resource to track the state of the client.
@@ -111,19 +186,42 @@ pub struct Client {
 #[derive(Component, Debug, Clone, Copy)]
 pub struct ControlledByClient(pub ClientId);
 
+/// Component: marks the entity controlled by a spectating client
+/// (server-side only).  Servers reject interaction requests from it.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Spectator;
+
+/// Server resource: spectating clients granted full relevancy, who are
+/// streamed the whole map instead of the area around their observer.
+///
+/// Granted and revoked by the `souls` module on request; modules that limit
+/// what a client is sent by distance consult it.
+#[derive(Resource, Debug, Default)]
+pub struct FullRelevancy(pub HashSet<ClientId>);
+
 /// Server-side lifecycle events for player (client) connections.
 ///
 /// Domain modules (e.g. `tiles`, `things`, `souls`) should listen to this instead of
 /// raw [`ServerEvent`] variants so they are decoupled from the network layer.
 #[derive(Message, Clone, Debug)]
 pub enum PlayerEvent {
-    /// Emitted when a client completes the handshake.
-    Joined { id: ClientId, name: String },
+    /// Emitted when a client completes the handshake.  `spectator` is set
+    /// when the client asked to join as an observer.
+    Joined {
+        id: ClientId,
+        name: String,
+        spectator: bool,
+    },
     /// Emitted when a client disconnects.
     Left { id: ClientId },
+    /// Emitted by gameplay validation when a client's input or movement is
+    /// rejected as impossible.  `count` is the client's running total of
+    /// violations this session, for admin systems to act on.
+    MovementViolation { id: ClientId, count: u32 },
 }
 
-/// Server-side message emitted when a client sends an input direction.
+/// Server-side message emitted when a client sends an input direction and
+/// movement modifiers.
 ///
 /// The orchestration layer decodes [`ClientMessage::Input`] and re-emits it as this
 /// strongly-typed message.  Domain modules (e.g. `souls`) consume it to route input
@@ -132,6 +230,23 @@ pub enum PlayerEvent {
 pub struct ClientInputReceived {
     pub from: ClientId,
     pub direction: [f32; 3],
+    pub sprint: bool,
+    pub crouch: bool,
+}
+
+/// Identifies one inbound client request in the server's logs.  Unique for
+/// the lifetime of the server.
+///
+/// Handed out by the module that receives the request and carried along on
+/// the server-side messages it is forwarded as, so the module that finally
+/// handles it can report back which request it refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CorrelationId(pub u64);
+
+impl std::fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "req-{}", self.0)
+    }
 }
 
 /// Server-side event emitted by a module stream handler after it has sent its initial data
@@ -150,9 +265,24 @@ pub struct ModuleReadySent {
 #[derive(Message, Clone, Debug)]
 pub enum ServerEvent {
     HostingStarted {
+        /// Port actually bound; differs from the requested one when hosting on port 0,
+        /// and is 0 on a [`Loopback`].
         port: u16,
+        /// Every address the server listens on, e.g. one per address family;
+        /// empty on a [`Loopback`].  See [`local_address`] to reach one from
+        /// this machine.
+        endpoints: Vec<SocketAddr>,
+        /// Fingerprint of the certificate clients are presented; `None` on a
+        /// [`Loopback`].
+        fingerprint: Option<CertFingerprint>,
     },
     HostingStopped,
+    /// A graceful shutdown began; connections close after `delay`.  Save
+    /// state in response.
+    ShutdownStarted {
+        message: String,
+        delay: Duration,
+    },
     ClientConnected {
         id: ClientId,
         addr: SocketAddr,
@@ -166,6 +296,11 @@ pub enum ServerEvent {
     ClientDisconnected {
         id: ClientId,
     },
+    /// A client answered a keepalive ping after `rtt`.
+    RttMeasured {
+        id: ClientId,
+        rtt: Duration,
+    },
     /// Raw framed data received from a client on a registered client→server module stream.
     /// Routed internally to per-tag [`StreamReader`] buffers; not emitted as a Bevy message.
     ClientStreamFrame {
@@ -184,6 +319,10 @@ pub enum ClientEvent {
         reason: String,
     },
     ServerMessageReceived(ServerMessage),
+    /// The server answered a keepalive ping after `rtt`.
+    RttMeasured {
+        rtt: Duration,
+    },
     /// Raw framed data received on a module stream (non-control, tag > 0).
     /// Modules subscribe to this event and filter by `tag` to decode their own messages.
     StreamFrame {
@@ -195,6 +334,15 @@ pub enum ClientEvent {
     StreamReady {
         tag: u8,
     },
+    /// The server at `addr` presented a certificate [`KnownServers`] does not
+    /// trust, so the connection was refused; follows the `Disconnected`.
+    /// `pinned` is the certificate trusted before, when it changed.  Pin
+    /// `fingerprint` and connect again to accept it.
+    UntrustedCertificate {
+        addr: SocketAddr,
+        fingerprint: CertFingerprint,
+        pinned: Option<CertFingerprint>,
+    },
     Error(String),
 }
 
@@ -229,6 +377,24 @@ impl NetServerSender {
             log::error!("Failed to broadcast message: {}", e);
         }
     }
+
+    /// Close the connection of a client, which is told `reason`.  Its
+    /// departure follows as a [`ServerEvent::ClientDisconnected`].
+    pub fn kick(&self, client: ClientId, reason: impl Into<String>) {
+        if let Err(e) = self.tx.send(ServerCommand::Kick {
+            client,
+            reason: reason.into(),
+        }) {
+            log::error!("Failed to kick client {}: {}", client.0, e);
+        }
+    }
+
+    /// Start a graceful shutdown of the server task.
+    pub(crate) fn shutdown(&self, message: String, delay: Duration) {
+        if let Err(e) = self.tx.send(ServerCommand::Shutdown { message, delay }) {
+            log::error!("Failed to request server shutdown: {}", e);
+        }
+    }
 }
 
 /// Error type for [`NetClientSender::send`].
@@ -281,7 +447,17 @@ impl NetClientSender {
 // Multi-stream infrastructure
 
 /// Direction of a registered QUIC stream.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    wincode::SchemaRead,
+    wincode::SchemaWrite,
+)]
 pub enum StreamDirection {
     /// Server opens a unidirectional stream that the client accepts.
     ServerToClient,
@@ -293,6 +469,8 @@ pub enum StreamDirection {
 #[derive(Debug, Clone)]
 pub struct StreamDef {
     /// Routing tag byte written as the first byte on every new stream.
+    /// Either a fixed tag below [`FIRST_NAMED_TAG`] or [`stream_tag`] of the
+    /// stream's name.
     pub tag: u8,
     /// Human-readable name (used in log messages).
     pub name: &'static str,
@@ -300,16 +478,30 @@ pub struct StreamDef {
     pub direction: StreamDirection,
 }
 
+/// A [`StreamDef`] as registered, with the [`StreamBudget`] it was given.
+#[derive(Debug, Clone)]
+pub(crate) struct RegisteredStream {
+    pub(crate) def: StreamDef,
+    pub(crate) budget: StreamBudget,
+}
+
 /// Bounded buffer size for the StreamSender → server stream command channel.
 const STREAM_CMD_BUFFER_SIZE: usize = 512;
 
 /// Internal command for writing bytes to a specific module stream.
+///
+/// `deferrable` frames may be held back by the replication budget; all others
+/// are written immediately.
 #[derive(Debug)]
 pub(crate) enum StreamWriteCmd {
     /// Send `data` to one client.
-    SendTo { client: ClientId, data: Bytes },
+    SendTo {
+        client: ClientId,
+        data: Bytes,
+        deferrable: bool,
+    },
     /// Send `data` to all connected clients.
-    Broadcast { data: Bytes },
+    Broadcast { data: Bytes, deferrable: bool },
 }
 
 /// Error type returned by [`StreamSender`] methods.
@@ -354,6 +546,7 @@ pub struct StreamSender<T: Send + Sync + 'static> {
     direction: StreamDirection,
     shared_tx: SharedStreamTx,
     client_tx: SharedClientStreamTx,
+    saturation: SharedSaturation,
     _phantom: std::marker::PhantomData<T>,
 }
 
@@ -408,6 +601,17 @@ impl<T: Send + Sync + 'static> StreamSender<T> {
         }
     }
 
+    /// Load of this stream over the last network tick, as measured by the
+    /// server's replication budget.  All zeros while no server is running.
+    pub fn saturation(&self) -> StreamSaturation {
+        self.saturation
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&self.tag)
+            .copied()
+            .unwrap_or_default()
+    }
+
     /// Send the [`StreamReady`] sentinel to a specific client.
     /// Call this after all initial-burst data has been sent on this stream.
     ///
@@ -431,6 +635,7 @@ impl<T: Send + Sync + 'static> StreamSender<T> {
         self.send_raw(StreamWriteCmd::SendTo {
             client,
             data: Bytes::from(bytes),
+            deferrable: false,
         })
     }
 }
@@ -439,14 +644,12 @@ impl<T> StreamSender<T>
 where
     T: wincode::SchemaWrite<wincode::config::DefaultConfig, Src = T> + Send + Sync + 'static,
 {
-    /// Encode `msg` and send it to a specific client on this stream.
-    ///
-    /// Only valid for streams registered with [`StreamDirection::ServerToClient`].
-    pub fn send_to(&self, client: ClientId, msg: &T) -> Result<(), StreamSendError> {
+    fn encode_server_to_client(&self, method: &str, msg: &T) -> Result<Bytes, StreamSendError> {
         if self.direction != StreamDirection::ServerToClient {
             log::error!(
-                "StreamSender (tag {}): send_to called on a ClientToServer stream",
-                self.tag
+                "StreamSender (tag {}): {} called on a ClientToServer stream",
+                self.tag,
+                method
             );
             return Err(StreamSendError::Closed);
         }
@@ -454,29 +657,54 @@ where
             log::error!("StreamSender (tag {}): encode failed: {}", self.tag, e);
             StreamSendError::Encode
         })?;
+        Ok(Bytes::from(bytes))
+    }
+
+    /// Encode `msg` and send it to a specific client on this stream.
+    ///
+    /// Never deferred by the replication budget; use for lifecycle messages.
+    /// Only valid for streams registered with [`StreamDirection::ServerToClient`].
+    pub fn send_to(&self, client: ClientId, msg: &T) -> Result<(), StreamSendError> {
+        let data = self.encode_server_to_client("send_to", msg)?;
         self.send_raw(StreamWriteCmd::SendTo {
             client,
-            data: Bytes::from(bytes),
+            data,
+            deferrable: false,
         })
     }
 
     /// Encode `msg` and broadcast it to all connected clients on this stream.
     ///
+    /// Never deferred by the replication budget; use for lifecycle messages.
     /// Only valid for streams registered with [`StreamDirection::ServerToClient`].
     pub fn broadcast(&self, msg: &T) -> Result<(), StreamSendError> {
-        if self.direction != StreamDirection::ServerToClient {
-            log::error!(
-                "StreamSender (tag {}): broadcast called on a ClientToServer stream",
-                self.tag
-            );
-            return Err(StreamSendError::Closed);
-        }
-        let bytes = protocol::encode(msg).map_err(|e| {
-            log::error!("StreamSender (tag {}): encode failed: {}", self.tag, e);
-            StreamSendError::Encode
-        })?;
+        let data = self.encode_server_to_client("broadcast", msg)?;
         self.send_raw(StreamWriteCmd::Broadcast {
-            data: Bytes::from(bytes),
+            data,
+            deferrable: false,
+        })
+    }
+
+    /// Like [`send_to`](Self::send_to), but the frame may be deferred to a
+    /// later network tick when the stream's [`StreamBudget`] is used up.
+    /// Deferred frames keep their relative order, but frames sent with
+    /// `send_to`/`broadcast` afterwards may overtake them.
+    pub fn send_state_to(&self, client: ClientId, msg: &T) -> Result<(), StreamSendError> {
+        let data = self.encode_server_to_client("send_state_to", msg)?;
+        self.send_raw(StreamWriteCmd::SendTo {
+            client,
+            data,
+            deferrable: true,
+        })
+    }
+
+    /// Like [`broadcast`](Self::broadcast), but the frame may be deferred to a
+    /// later network tick for clients whose [`StreamBudget`] is used up.
+    pub fn broadcast_state(&self, msg: &T) -> Result<(), StreamSendError> {
+        let data = self.encode_server_to_client("broadcast_state", msg)?;
+        self.send_raw(StreamWriteCmd::Broadcast {
+            data,
+            deferrable: true,
         })
     }
 
@@ -508,11 +736,16 @@ type ClientStreamBuf = HashMap<u8, Arc<Mutex<VecDeque<(ClientId, Bytes)>>>>;
 /// QUIC streams to open/accept on each new connection.
 #[derive(Resource)]
 pub struct StreamRegistry {
-    entries: Vec<StreamDef>,
+    entries: Vec<RegisteredStream>,
+    /// Message-type fingerprint per tag, listed in the handshake manifest.
+    schemas: HashMap<u8, u64>,
     /// Shared with every [`StreamSender`] created from this registry.
     /// Replaced with a live sender each time the server starts; set to `None`
     /// when the server stops.
     shared_tx: SharedStreamTx,
+    /// Replication saturation published by the running server task, shared
+    /// with every [`StreamSender`].
+    saturation: SharedSaturation,
     /// Per-tag receive buffers for server→client frames, shared with [`StreamReader`] instances.
     per_stream_bufs: HashMap<u8, Arc<Mutex<VecDeque<Bytes>>>>,
     /// Per-tag receive buffers for client→server frames, shared with [`StreamReader`] instances.
@@ -528,17 +761,23 @@ pub struct StreamRegistry {
     /// we guarantee at least one full frame of processing before downstream
     /// systems see the ready signal.
     deferred_ready: Vec<u8>,
+    /// Per-client count of frames that failed to decode, shared with every
+    /// [`StreamReader`] and the running server task.
+    decode_failures: Arc<DecodeFailures>,
 }
 
 impl Default for StreamRegistry {
     fn default() -> Self {
         Self {
             entries: Vec::new(),
+            schemas: HashMap::new(),
             shared_tx: Arc::new(Mutex::new(None)),
+            saturation: Arc::new(Mutex::new(HashMap::new())),
             per_stream_bufs: HashMap::new(),
             per_client_stream_bufs: HashMap::new(),
             shared_client_txs: HashMap::new(),
             deferred_ready: Vec::new(),
+            decode_failures: Arc::new(DecodeFailures::default()),
         }
     }
 }
@@ -551,22 +790,58 @@ impl StreamRegistry {
     /// stream.  Insert the reader as a Bevy resource so client-side systems can
     /// receive decoded messages without polling [`ClientEvent`].
     ///
+    /// The stream gets [`StreamBudget::UNLIMITED`]; streams that should yield
+    /// to others on a congested link use [`Self::register_with_budget`].
+    ///
     /// # Panics
-    /// Panics if `def.tag == 0` (reserved for the control stream) or if the
-    /// tag has already been registered.
+    /// Panics with the [`StreamRegistryError`] from [`Self::try_register`] if
+    /// `def.tag == 0` (reserved for the control stream) or if the tag has
+    /// already been registered.
     pub fn register<T: Send + Sync + 'static>(
         &mut self,
         def: StreamDef,
     ) -> (StreamSender<T>, StreamReader<T>) {
-        assert_ne!(
-            def.tag, 0,
-            "stream tag 0 is reserved for the control stream"
-        );
-        assert!(
-            !self.entries.iter().any(|e| e.tag == def.tag),
-            "stream tag {} is already registered",
-            def.tag
-        );
+        self.register_with_budget(def, StreamBudget::UNLIMITED)
+    }
+
+    /// Like [`Self::register`], with the priority and per-client allowance
+    /// for deferrable frames of a server→client stream, or the frame limit of
+    /// a client→server one; see [`StreamBudget`].
+    pub fn register_with_budget<T: Send + Sync + 'static>(
+        &mut self,
+        def: StreamDef,
+        budget: StreamBudget,
+    ) -> (StreamSender<T>, StreamReader<T>) {
+        self.try_register_with_budget(def, budget)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Self::register`], but returns an error naming both streams
+    /// instead of panicking when the tag is taken.
+    pub fn try_register<T: Send + Sync + 'static>(
+        &mut self,
+        def: StreamDef,
+    ) -> Result<(StreamSender<T>, StreamReader<T>), StreamRegistryError> {
+        self.try_register_with_budget(def, StreamBudget::UNLIMITED)
+    }
+
+    /// Like [`Self::register_with_budget`], but returns an error naming both
+    /// streams instead of panicking when the tag is taken.
+    pub fn try_register_with_budget<T: Send + Sync + 'static>(
+        &mut self,
+        def: StreamDef,
+        budget: StreamBudget,
+    ) -> Result<(StreamSender<T>, StreamReader<T>), StreamRegistryError> {
+        if def.tag == 0 {
+            return Err(StreamRegistryError::ReservedTag { name: def.name });
+        }
+        if let Some(existing) = self.entries.iter().find(|e| e.def.tag == def.tag) {
+            return Err(StreamRegistryError::TagConflict {
+                tag: def.tag,
+                existing: existing.def.name,
+                requested: def.name,
+            });
+        }
         let tag = def.tag;
         let def_direction = def.direction;
         log::info!(
@@ -596,20 +871,23 @@ impl StreamRegistry {
             }
         };
 
-        self.entries.push(def);
+        self.entries.push(RegisteredStream { def, budget });
+        self.schemas.insert(tag, tags::schema_hash::<T>());
         let sender = StreamSender {
             tag,
             direction: def_direction,
             shared_tx: self.shared_tx.clone(),
             client_tx,
+            saturation: self.saturation.clone(),
             _phantom: std::marker::PhantomData,
         };
         let reader = StreamReader {
             buf: server_to_client_buf,
             client_buf: client_to_server_buf,
+            decode_failures: self.decode_failures.clone(),
             _phantom: std::marker::PhantomData,
         };
-        (sender, reader)
+        Ok((sender, reader))
     }
 
     /// Route a raw stream frame to the per-tag receive buffer so that the
@@ -643,23 +921,72 @@ impl StreamRegistry {
         }
     }
 
+    /// Frames received on each stream and not yet drained by its
+    /// [`StreamReader`], with the stream's name.  Streams with nothing
+    /// waiting are left out.
+    pub fn buffered_frames(&self) -> Vec<(&'static str, usize)> {
+        self.entries
+            .iter()
+            .map(|entry| &entry.def)
+            .filter_map(|def| {
+                let len = match def.direction {
+                    StreamDirection::ServerToClient => self
+                        .per_stream_bufs
+                        .get(&def.tag)?
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .len(),
+                    StreamDirection::ClientToServer => self
+                        .per_client_stream_bufs
+                        .get(&def.tag)?
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .len(),
+                };
+                (len > 0).then_some((def.name, len))
+            })
+            .collect()
+    }
+
     /// Number of registered server→client streams.
     /// Sent as `expected_streams` in the `Welcome` message.
     pub fn server_to_client_count(&self) -> u8 {
         let count = self
             .entries
             .iter()
-            .filter(|d| d.direction == StreamDirection::ServerToClient)
+            .filter(|e| e.def.direction == StreamDirection::ServerToClient)
             .count();
         u8::try_from(count).expect("too many server→client streams registered; maximum is 255")
     }
 
+    /// Every registered stream, ordered by tag.  Exchanged in the handshake
+    /// so client and server can check that their tables agree.
+    pub fn manifest(&self) -> Vec<StreamManifestEntry> {
+        let mut entries: Vec<StreamManifestEntry> = self
+            .entries
+            .iter()
+            .map(|entry| &entry.def)
+            .map(|def| StreamManifestEntry {
+                tag: def.tag,
+                name: def.name.to_string(),
+                direction: def.direction,
+                schema: self.schemas.get(&def.tag).copied().unwrap_or_default(),
+            })
+            .collect();
+        entries.sort_by_key(|e| e.tag);
+        entries
+    }
+
     /// Called by the server startup path.  Creates a fresh command channel,
-    /// wires `shared_tx` to the new sender, and returns the stream definitions
-    /// and command receiver to pass to the server task.
+    /// wires `shared_tx` to the new sender, and returns the registered streams,
+    /// command receiver and saturation sink to pass to the server task.
     pub(crate) fn prepare_server_start(
         &mut self,
-    ) -> (Vec<StreamDef>, mpsc::Receiver<(u8, StreamWriteCmd)>) {
+    ) -> (
+        Vec<RegisteredStream>,
+        mpsc::Receiver<(u8, StreamWriteCmd)>,
+        SharedSaturation,
+    ) {
         let (tx, rx) = mpsc::channel(STREAM_CMD_BUFFER_SIZE);
         *self.shared_tx.lock().unwrap_or_else(|e| e.into_inner()) = Some(tx);
         let defs = self.entries.clone();
@@ -667,13 +994,28 @@ impl StreamRegistry {
             "StreamRegistry: server started with {} stream(s)",
             defs.len()
         );
-        (defs, rx)
+        (defs, rx, self.saturation.clone())
+    }
+
+    /// Saturation last published for each server→client stream, with the
+    /// stream's tag and name.
+    pub fn saturations(&self) -> Vec<(u8, &'static str, StreamSaturation)> {
+        let published = self.saturation.lock().unwrap_or_else(|e| e.into_inner());
+        self.entries
+            .iter()
+            .map(|entry| &entry.def)
+            .filter_map(|def| Some((def.tag, def.name, *published.get(&def.tag)?)))
+            .collect()
     }
 
     /// Called when the server stops.  Disconnects stream senders so that
     /// [`StreamSender`] calls made while no server is running are rejected.
     pub(crate) fn on_server_stop(&self) {
         *self.shared_tx.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.saturation
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
         log::info!("StreamRegistry: server stopped, stream senders disconnected");
     }
 
@@ -682,7 +1024,7 @@ impl StreamRegistry {
     /// and returns `(tag, receiver)` pairs for the client task to open QUIC streams.
     pub(crate) fn prepare_client_connect(&mut self) -> Vec<(u8, mpsc::Receiver<Bytes>)> {
         let mut receivers = Vec::new();
-        for def in &self.entries {
+        for RegisteredStream { def, .. } in &self.entries {
             if def.direction == StreamDirection::ClientToServer {
                 let (tx, rx) = mpsc::channel(STREAM_CMD_BUFFER_SIZE);
                 if let Some(shared) = self.shared_client_txs.get(&def.tag) {
@@ -739,6 +1081,8 @@ pub struct StreamReader<T: Send + Sync + 'static> {
     buf: Arc<Mutex<VecDeque<Bytes>>>,
     /// Receive buffer for client→server frames (used on server side).
     client_buf: Arc<Mutex<VecDeque<(ClientId, Bytes)>>>,
+    /// Where frames from clients that fail to decode are counted.
+    decode_failures: Arc<DecodeFailures>,
     _phantom: std::marker::PhantomData<T>,
 }
 
@@ -746,7 +1090,8 @@ impl<T: Send + Sync + 'static> bevy::ecs::resource::Resource for StreamReader<T>
 
 impl<T: Send + Sync + 'static> StreamReader<T>
 where
-    for<'de> T: wincode::SchemaRead<'de, wincode::config::DefaultConfig, Dst = T>,
+    for<'de> T: wincode::SchemaRead<'de, wincode::config::DefaultConfig, Dst = T>
+        + wincode::SchemaRead<'de, ClientFrameConfig, Dst = T>,
 {
     /// Drain all buffered frames, decoding each to `T`.
     /// Frames that fail to decode are logged as errors and skipped.
@@ -765,15 +1110,21 @@ where
     /// Drain all buffered client→server frames, decoding each to `(ClientId, T)`.
     ///
     /// Only meaningful for streams registered with [`StreamDirection::ClientToServer`].
-    /// Frames that fail to decode are logged as errors and skipped.
+    /// Frames are decoded with [`ClientFrameConfig`]; those that fail to decode
+    /// are logged as errors, skipped and counted against the sending client
+    /// (see [`ClientFrameLimits`]).
     pub fn drain_from_client(&mut self) -> impl Iterator<Item = (ClientId, T)> {
         let frames: Vec<(ClientId, Bytes)> = {
             let mut guard = self.client_buf.lock().unwrap_or_else(|e| e.into_inner());
             guard.drain(..).collect()
         };
-        frames.into_iter().filter_map(|(from, b)| {
-            protocol::decode::<T>(&b)
-                .map_err(|e| log::error!("StreamReader: decode error: {e}"))
+        let failures = self.decode_failures.clone();
+        frames.into_iter().filter_map(move |(from, b)| {
+            protocol::decode_from_client::<T>(&b)
+                .map_err(|e| {
+                    log::error!("StreamReader: decode error from client {}: {e}", from.0);
+                    failures.record(from);
+                })
                 .ok()
                 .map(|msg| (from, msg))
         })
@@ -792,7 +1143,7 @@ static CAP_WARNING_LOGGED: AtomicBool = AtomicBool::new(false);
 /// game-agnostic while still driving state transitions:
 ///
 /// - `loading`       — entered when `NetCommand::Host` or `NetCommand::Connect`
-///                      is processed (map loading + network sync).
+///   is processed (map loading + network sync).
 /// - `in_game`       — entered once initial sync is complete.
 /// - `disconnected`  — fall-back on disconnect / error.
 ///
@@ -822,6 +1173,9 @@ impl<S: FreelyMutableState + Copy> Plugin for NetworkPlugin<S> {
         app.insert_resource(ClientEventSender(client_event_tx));
         app.insert_resource(ClientEventReceiver(client_event_rx));
         app.init_resource::<StreamRegistry>();
+        app.init_resource::<ClientFrameLimits>();
+        app.init_resource::<CertVerification>();
+        app.init_resource::<KnownServers>();
         app.add_message::<NetCommand>();
         app.add_message::<ServerEvent>();
         app.add_message::<ClientEvent>();
@@ -837,6 +1191,7 @@ impl<S: FreelyMutableState + Copy> Plugin for NetworkPlugin<S> {
             order.insert_after(PreUpdate, NetworkReceive);
             order.insert_after(PostUpdate, NetworkSend);
         }
+        tick::register_tick_schedule(app);
 
         // Within NetworkReceive: Drain runs first, Commands runs last,
         // module systems run between them (no set annotation needed).
@@ -855,6 +1210,11 @@ impl<S: FreelyMutableState + Copy> Plugin for NetworkPlugin<S> {
 
         // Register orchestration systems that manage sync barriers and state transitions.
         orchestrate::register_orchestrate_systems(app, self.loading, self.in_game, self.disconnected);
+        quality::register_quality_systems(app);
+        roster::register_roster_systems(app);
+        round::register_round_systems(app);
+        shutdown::register_shutdown_systems(app);
+        tunables::register_tunable_systems(app);
     }
 }
 
@@ -966,6 +1326,7 @@ fn drain_client_events(
 }
 
 /// Reads NetCommand Bevy messages and spawns async tasks accordingly.
+#[allow(clippy::too_many_arguments)]
 fn process_net_commands<S: FreelyMutableState + Copy>(
     mut commands: Commands,
     mut commands_reader: MessageReader<NetCommand>,
@@ -978,13 +1339,62 @@ fn process_net_commands<S: FreelyMutableState + Copy>(
     state: Res<State<S>>,
     mut next_state: ResMut<NextState<S>>,
     headless: Option<Res<Headless>>,
+    server_sender: Option<Res<NetServerSender>>,
+    identity: Option<Res<ServerIdentity>>,
+    verification: Res<CertVerification>,
+    known: Res<KnownServers>,
+    limits: Res<ClientFrameLimits>,
 ) {
     // Clean up any finished tasks before processing new commands
     tasks.cleanup_finished();
 
     for command in commands_reader.read() {
-        match command {
-            NetCommand::Host { port } => {
+        let start = match command {
+            NetCommand::Host { port, addresses } => Start::Host(Bind::Quic {
+                port: *port,
+                addresses: addresses.clone(),
+                identity: identity.as_deref().cloned(),
+            }),
+            NetCommand::HostLoopback(loopback) => Start::Host(Bind::Loopback(loopback.clone())),
+            NetCommand::Connect {
+                addr,
+                name,
+                spectator,
+            } => {
+                let known = match *verification {
+                    CertVerification::TrustOnFirstUse => Some(known.clone()),
+                    CertVerification::Skip => None,
+                };
+                Start::Connect(Dial::Quic { addr: *addr, known }, name.clone(), *spectator)
+            }
+            NetCommand::ConnectLoopback {
+                loopback,
+                name,
+                spectator,
+            } => Start::Connect(Dial::Loopback(loopback.clone()), name.clone(), *spectator),
+            NetCommand::StopHosting => {
+                tasks.stop_hosting();
+                continue;
+            }
+            NetCommand::ShutdownServer { message, delay } => {
+                match &server_sender {
+                    Some(sender) if tasks.is_hosting() => sender.shutdown(message.clone(), *delay),
+                    _ => {
+                        let _ = server_event_tx
+                            .0
+                            .send(ServerEvent::Error("Not hosting a server".into()));
+                    }
+                }
+                continue;
+            }
+            NetCommand::Disconnect => {
+                tasks.disconnect();
+                continue;
+            }
+        };
+
+        match start {
+            Start::Host(bind) => {
                 // Prevent duplicate hosting
                 if tasks.is_hosting() {
                     let _ = server_event_tx
@@ -999,25 +1409,30 @@ fn process_net_commands<S: FreelyMutableState + Copy>(
                 commands.insert_resource(NetServerSender::new(server_cmd_tx));
 
                 // Prepare per-stream channels from the registry
-                let (stream_defs, stream_cmd_rx) = registry.prepare_server_start();
+                let (stream_defs, stream_cmd_rx, saturation) = registry.prepare_server_start();
+                let manifest = registry.manifest();
 
                 let tx = server_event_tx.0.clone();
                 let cancel_token = tokio_util::sync::CancellationToken::new();
                 let token_clone = cancel_token.clone();
                 let handle = runtime.spawn(server::run_server(
-                    *port,
+                    bind,
                     tx,
                     server_cmd_rx,
                     token_clone,
                     stream_defs,
+                    manifest,
                     stream_cmd_rx,
+                    saturation,
+                    *limits,
+                    registry.decode_failures.clone(),
                 ));
                 tasks.server_task = Some((handle, cancel_token));
 
                 // Transition to Loading (no-op if already in Loading, e.g. dedicated server).
                 states.transition_to_loading(&state, &mut next_state, headless.is_some());
             }
-            NetCommand::Connect { addr, name } => {
+            Start::Connect(dial, name, spectator) => {
                 // Prevent duplicate connections
                 if tasks.is_connected() {
                     let _ = client_event_tx
@@ -1034,18 +1449,19 @@ fn process_net_commands<S: FreelyMutableState + Copy>(
 
                 // Prepare client→server stream channels from the registry.
                 let client_stream_rxs = registry.prepare_client_connect();
+                let streams = registry.manifest();
 
                 let tx = client_event_tx.0.clone();
-                let addr = *addr;
-                let name = name.clone();
                 let cancel_token = tokio_util::sync::CancellationToken::new();
                 let token_clone = cancel_token.clone();
                 let handle = runtime.spawn(client::run_client(
-                    addr,
+                    dial,
                     tx,
                     client_msg_rx,
                     token_clone,
                     name,
+                    spectator,
+                    streams,
                     client_stream_rxs,
                 ));
                 tasks.client_task = Some((handle, cancel_token));
@@ -1053,16 +1469,17 @@ fn process_net_commands<S: FreelyMutableState + Copy>(
                 // Transition to Loading (no-op if already in Loading).
                 states.transition_to_loading(&state, &mut next_state, headless.is_some());
             }
-            NetCommand::StopHosting => {
-                tasks.stop_hosting();
-            }
-            NetCommand::Disconnect => {
-                tasks.disconnect();
-            }
         }
     }
 }
 
+/// A session [`process_net_commands`] starts: hosting, or connecting as a
+/// named client.
+enum Start {
+    Host(Bind),
+    Connect(Dial, String, bool),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1085,6 +1502,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn entity_state_yaw_round_trips() {
+        for radians in [0.0, 1.0, 3.0, -1.0, std::f32::consts::TAU - 1e-4] {
+            let state = EntityState {
+                net_id: NetId(1),
+                position: [0.0; 3],
+                velocity: [0.0; 3],
+                yaw: EntityState::quantize_yaw(radians),
+            };
+            let expected = radians.rem_euclid(std::f32::consts::TAU);
+            let error = (state.yaw_radians() - expected).abs();
+            let error = error.min(std::f32::consts::TAU - error);
+            assert!(error < 1e-3, "{radians} decoded as {}", state.yaw_radians());
+        }
+    }
+
+    #[test]
+    fn hello_protocol_version_is_readable_from_any_hello() {
+        let hello = ClientMessage::Hello {
+            protocol_version: 7,
+            name: "tester".into(),
+            spectator: false,
+            streams: Vec::new(),
+        };
+        let bytes = protocol::encode(&hello).expect("encode");
+        assert_eq!(protocol::hello_protocol_version(&bytes), Some(7));
+        // A Hello from another version whose later fields no longer decode.
+        assert_eq!(protocol::hello_protocol_version(&bytes[..8]), Some(7));
+
+        let input = ClientMessage::Input {
+            direction: [0.0; 3],
+            sprint: false,
+            crouch: false,
+        };
+        let bytes = protocol::encode(&input).expect("encode");
+        assert_eq!(protocol::hello_protocol_version(&bytes), None);
+    }
+
     #[test]
     fn test_max_events_per_frame_cap() {
         // Create a test app
@@ -1139,6 +1594,7 @@ mod tests {
         let message = ServerMessage::Welcome {
             client_id: client,
             expected_streams: 0,
+            protocol_version: PROTOCOL_VERSION,
         };
 
         sender.send_to(client, &message);
@@ -1154,6 +1610,7 @@ mod tests {
                     ServerMessage::Welcome {
                         client_id,
                         expected_streams,
+                        ..
                     } => {
                         assert_eq!(client_id, client);
                         assert_eq!(expected_streams, 0);
@@ -1191,6 +1648,8 @@ mod tests {
 
         let message = ClientMessage::Input {
             direction: [1.0, 0.0, -1.0],
+            sprint: true,
+            crouch: false,
         };
 
         let result = sender.send(&message);
@@ -1198,10 +1657,13 @@ mod tests {
 
         let received = rx.try_recv().expect("Should receive message");
         match received {
-            ClientMessage::Input { direction } => {
+            ClientMessage::Input {
+                direction, sprint, ..
+            } => {
                 assert_eq!(direction, [1.0, 0.0, -1.0]);
+                assert!(sprint);
             }
-            ClientMessage::Hello { .. } => panic!("Expected Input message"),
+            other => panic!("Expected Input message, got {other:?}"),
         }
     }
 
@@ -1212,6 +1674,8 @@ mod tests {
 
         let message = ClientMessage::Input {
             direction: [1.0, 0.0, -1.0],
+            sprint: false,
+            crouch: false,
         };
 
         // First send should succeed
@@ -1244,6 +1708,43 @@ mod tests {
         assert_eq!(registry.server_to_client_count(), 2);
     }
 
+    #[test]
+    fn test_stream_registry_conflicts_name_both_streams() {
+        let mut registry = StreamRegistry::default();
+        let def = |tag, name| StreamDef {
+            tag,
+            name,
+            direction: StreamDirection::ServerToClient,
+        };
+        registry.register::<ServerMessage>(def(1, "tiles"));
+
+        let err = registry
+            .try_register::<ServerMessage>(def(1, "lighting"))
+            .err()
+            .expect("tag 1 is taken");
+        assert_eq!(
+            err,
+            StreamRegistryError::TagConflict {
+                tag: 1,
+                existing: "tiles",
+                requested: "lighting",
+            }
+        );
+        assert!(err.to_string().contains("'tiles'") && err.to_string().contains("'lighting'"));
+        assert_eq!(
+            registry
+                .try_register::<ServerMessage>(def(0, "control"))
+                .err(),
+            Some(StreamRegistryError::ReservedTag { name: "control" })
+        );
+
+        registry.register::<ServerMessage>(def(stream_tag("lighting"), "lighting"));
+        let manifest = registry.manifest();
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(manifest[1].tag, stream_tag("lighting"));
+        assert_eq!(manifest[1].name, "lighting");
+    }
+
     #[test]
     fn test_stream_sender_closed_when_no_server() {
         let mut registry = StreamRegistry::default();
@@ -1267,9 +1768,19 @@ mod tests {
         });
 
         // After prepare_server_start the shared_tx is live
-        let (defs, _rx) = registry.prepare_server_start();
+        let (defs, _rx, saturation) = registry.prepare_server_start();
         assert_eq!(defs.len(), 1);
-        assert_eq!(defs[0].tag, 3);
+        assert_eq!(defs[0].def.tag, 3);
+
+        // Saturation published by the server task is visible through the sender.
+        let published = StreamSaturation {
+            sent_bytes: 10,
+            queued_frames: 2,
+            load: 1.5,
+            paced_clients: 0,
+        };
+        saturation.lock().unwrap().insert(3, published);
+        assert!(sender.saturation().is_saturated());
 
         // send_stream_ready_to should now succeed (channel is open)
         let result = sender.send_stream_ready_to(ClientId(99));
@@ -1279,6 +1790,7 @@ mod tests {
         registry.on_server_stop();
         let result = sender.send_stream_ready_to(ClientId(99));
         assert_eq!(result, Err(StreamSendError::Closed));
+        assert_eq!(sender.saturation(), StreamSaturation::default());
     }
 
     #[test]
@@ -1359,8 +1871,11 @@ mod tests {
         let encoded = Bytes::from(protocol::encode(&msg).expect("encode"));
         registry.route_client_stream_frame(client, 4, encoded);
 
+        assert_eq!(registry.buffered_frames(), vec![("tile-input", 1)]);
+
         // Drain should yield the decoded message with the sender's ClientId
         let frames: Vec<(ClientId, ServerMessage)> = reader.drain_from_client().collect();
+        assert!(registry.buffered_frames().is_empty());
         assert_eq!(frames.len(), 1);
         assert_eq!(frames[0].0, client);
         assert!(matches!(frames[0].1, ServerMessage::InitialStateDone));
@@ -0,0 +1,137 @@
+//! Defenses against clients sending oversized or malformed frames.
+//!
+//! Every frame the server reads from a client is held to a length limit:
+//! its stream's [`StreamBudget::max_frame_bytes`](crate::StreamBudget::max_frame_bytes),
+//! or [`ClientFrameLimits::max_frame_bytes`] on the control stream and on
+//! streams without one.  A longer frame is never buffered; the client is
+//! kicked.
+//!
+//! Frames that fail to decode, on the control stream or in
+//! [`StreamReader::drain_from_client`](crate::StreamReader::drain_from_client),
+//! are counted per client in [`DecodeFailures`].  A client with more than
+//! [`ClientFrameLimits::max_decode_failures`] of them within
+//! [`ClientFrameLimits::decode_failure_window`] is kicked too, so a hostile
+//! client cannot keep the server busy decoding and logging junk.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::Resource;
+
+use crate::ClientId;
+
+/// Limits on what clients may send, read when hosting starts.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct ClientFrameLimits {
+    /// Longest frame, in bytes, on the control stream and on client→server
+    /// streams that do not set their own limit.
+    pub max_frame_bytes: usize,
+    /// Frames failing to decode a client may send within
+    /// [`decode_failure_window`](Self::decode_failure_window) before it is
+    /// kicked.
+    pub max_decode_failures: usize,
+    pub decode_failure_window: Duration,
+}
+
+impl Default for ClientFrameLimits {
+    fn default() -> Self {
+        Self {
+            max_frame_bytes: 64 << 10,
+            max_decode_failures: 20,
+            decode_failure_window: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Times at which each client's frames failed to decode, shared by the
+/// server task and every [`StreamReader`](crate::StreamReader).
+#[derive(Debug, Default)]
+pub(crate) struct DecodeFailures(Mutex<HashMap<ClientId, VecDeque<Instant>>>);
+
+impl DecodeFailures {
+    /// Notes that a frame from `client` failed to decode.
+    pub(crate) fn record(&self, client: ClientId) {
+        self.record_at(client, Instant::now());
+    }
+
+    fn record_at(&self, client: ClientId, at: Instant) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(client)
+            .or_default()
+            .push_back(at);
+    }
+
+    /// Clients with more failures within the window than `limits` allow,
+    /// with their counts.  Their failures are forgotten, and so are all
+    /// failures older than the window.
+    pub(crate) fn offenders(&self, limits: &ClientFrameLimits) -> Vec<(ClientId, usize)> {
+        self.offenders_at(limits, Instant::now())
+    }
+
+    fn offenders_at(&self, limits: &ClientFrameLimits, now: Instant) -> Vec<(ClientId, usize)> {
+        let mut failures = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let mut offenders = Vec::new();
+        failures.retain(|client, times| {
+            while times
+                .front()
+                .is_some_and(|at| now.duration_since(*at) > limits.decode_failure_window)
+            {
+                times.pop_front();
+            }
+            if times.len() > limits.max_decode_failures {
+                offenders.push((*client, times.len()));
+                return false;
+            }
+            !times.is_empty()
+        });
+        offenders
+    }
+
+    /// Forgets a client that has left.
+    pub(crate) fn forget(&self, client: ClientId) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&client);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_bursts_of_decode_failures_offend() {
+        let limits = ClientFrameLimits {
+            max_decode_failures: 3,
+            decode_failure_window: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let failures = DecodeFailures::default();
+        let start = Instant::now();
+        let steady = ClientId(1);
+        let burst = ClientId(2);
+
+        // One failure every four seconds never has more than three in a window.
+        for i in 0..10 {
+            let at = start + Duration::from_secs(4 * i);
+            failures.record_at(steady, at);
+            assert!(failures.offenders_at(&limits, at).is_empty());
+        }
+
+        for _ in 0..4 {
+            failures.record_at(burst, start);
+        }
+        let later = start + Duration::from_secs(36);
+        assert!(failures.offenders_at(&limits, later).is_empty());
+        for _ in 0..4 {
+            failures.record_at(burst, later);
+        }
+        assert_eq!(failures.offenders_at(&limits, later), vec![(burst, 4)]);
+        // Counting starts over after a kick.
+        assert!(failures.offenders_at(&limits, later).is_empty());
+    }
+}
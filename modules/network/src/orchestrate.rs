@@ -140,6 +140,13 @@ fn handle_client_events<S: FreelyMutableState + Copy>(
                 error!("Network error: {msg}");
                 next_state.set(states.disconnected);
             }
+            ClientEvent::UntrustedCertificate { .. } => {
+                // Left to the UI to ask the player; `Disconnected` already
+                // returned us to the disconnected state.
+            }
+            ClientEvent::RttMeasured { .. } => {
+                // Recorded in `ConnectionQuality` by the quality module.
+            }
             ClientEvent::StreamFrame { tag: _, data: _ } => {
                 // Intentionally unhandled here; stream frames are processed in the respective module systems.
             }
@@ -162,10 +169,11 @@ fn handle_client_events<S: FreelyMutableState + Copy>(
                 ServerMessage::Welcome {
                     client_id,
                     expected_streams,
+                    protocol_version,
                 } => {
                     info!(
-                        "Received Welcome, local ClientId assigned: {}, expecting {} module stream(s)",
-                        client_id.0, expected_streams
+                        "Received Welcome (protocol {}), local ClientId assigned: {}, expecting {} module stream(s)",
+                        protocol_version, client_id.0, expected_streams
                     );
                     client.local_id = Some(*client_id);
                     sync.expected_streams = *expected_streams;
@@ -176,11 +184,45 @@ fn handle_client_events<S: FreelyMutableState + Copy>(
                     sync.initial_state_done = true;
                     try_enter_in_game(&sync, &state, &mut next_state, &states);
                 }
-                ServerMessage::Shutdown => {
-                    info!("Server is shutting down");
+                ServerMessage::Shutdown {
+                    message,
+                    delay_secs,
+                } => {
+                    // Stay connected until the server closes the connection;
+                    // `ShutdownNotice` carries the announcement meanwhile.
+                    info!("Server is shutting down in {delay_secs:.0}s: {message}");
+                }
+                ServerMessage::JoinDenied {
+                    server_version,
+                    client_version,
+                    streams,
+                } => {
+                    if server_version != client_version {
+                        error!(
+                            "Server refused to let us join: it speaks protocol {}, we speak {}",
+                            server_version, client_version
+                        );
+                    } else {
+                        error!(
+                            "Server refused to let us join: incompatible streams:\n  {}",
+                            streams.join("\n  ")
+                        );
+                    }
                     net_commands.write(NetCommand::Disconnect);
                     next_state.set(states.disconnected);
                 }
+                ServerMessage::Roster { .. } => {
+                    // Stored in `PlayerRoster` by the roster module.
+                }
+                ServerMessage::GameState { .. } => {
+                    // Stored in `RoundState` by the round module.
+                }
+                ServerMessage::Tunable { .. } => {
+                    // Applied to `Tunables` by the tunables module.
+                }
+                ServerMessage::Ping { .. } | ServerMessage::Pong { .. } => {
+                    // Answered and timed by the client task; never forwarded.
+                }
             },
         }
     }
@@ -209,9 +251,12 @@ fn handle_server_events(
 ) {
     for event in messages.read() {
         match event {
-            ServerEvent::HostingStarted { port } => {
+            ServerEvent::HostingStarted { port, .. } => {
                 info!("Hosting started on port {port}");
             }
+            ServerEvent::ShutdownStarted { message, delay } => {
+                info!("Shutting down in {delay:?}: {message}");
+            }
             ServerEvent::HostingStopped => {
                 // Server resource removal handled by drain_server_events in lib.rs
             }
@@ -229,6 +274,9 @@ fn handle_server_events(
             ServerEvent::ClientMessageReceived { from, message } => {
                 handle_client_message(from, message, &mut player, &mut input, &mut sync_state);
             }
+            ServerEvent::RttMeasured { .. } => {
+                // Recorded in `ConnectionQuality` by the quality module.
+            }
             ServerEvent::ClientStreamFrame { .. } => {
                 // Routed to per-tag StreamReader buffers by drain_server_events;
                 // not processed here.
@@ -245,23 +293,35 @@ fn handle_client_message(
     sync_state: &mut ClientInitSyncState,
 ) {
     match message {
-        ClientMessage::Hello { name } => {
+        ClientMessage::Hello {
+            name, spectator, ..
+        } => {
             info!(
-                "Received client hello from ClientId({}), name: {:?}",
-                from.0, name
+                "Received client hello from ClientId({}), name: {:?}, spectator: {}",
+                from.0, name, spectator
             );
             sync_state.ready_counts.insert(*from, 0);
             player.write(PlayerEvent::Joined {
                 id: *from,
                 name: name.clone(),
+                spectator: *spectator,
             });
         }
-        ClientMessage::Input { direction } => {
+        ClientMessage::Input {
+            direction,
+            sprint,
+            crouch,
+        } => {
             input.write(ClientInputReceived {
                 from: *from,
                 direction: *direction,
+                sprint: *sprint,
+                crouch: *crouch,
             });
         }
+        ClientMessage::Ping { .. } | ClientMessage::Pong { .. } => {
+            // Answered and timed by the server task; never forwarded.
+        }
     }
 }
 
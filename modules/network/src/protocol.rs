@@ -1,14 +1,28 @@
 use bevy::ecs::component::Component;
 use serde::{Deserialize, Serialize};
-use wincode::config::DefaultConfig;
+use wincode::config::{Configuration, DefaultConfig};
 use wincode::{SchemaRead, SchemaWrite};
 
+use crate::StreamDirection;
+
+/// Version of the wire protocol, exchanged in [`ClientMessage::Hello`] and
+/// [`ServerMessage::Welcome`].  Bump it whenever a control or module message
+/// changes shape; clients on another version are refused with
+/// [`ServerMessage::JoinDenied`].
+pub const PROTOCOL_VERSION: u32 = 13;
+
 /// Unique identifier for a client in the network.
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, SchemaRead, SchemaWrite,
 )]
 pub struct ClientId(pub u64);
 
+impl ClientId {
+    /// The player on the machine running the server, connected through a
+    /// [`Loopback`](crate::Loopback).  Remote clients are numbered from 1.
+    pub const LOCAL: ClientId = ClientId(0);
+}
+
 /// Unique identifier for a replicated entity, server-assigned.
 #[derive(
     Component,
@@ -31,6 +45,21 @@ pub struct EntityState {
     pub net_id: NetId,
     pub position: [f32; 3],
     pub velocity: [f32; 3],
+    /// Heading around the vertical axis, quantized by [`EntityState::quantize_yaw`].
+    pub yaw: u16,
+}
+
+impl EntityState {
+    /// Maps a yaw angle in radians onto the full `u16` range (about 0.0055° per step).
+    pub fn quantize_yaw(radians: f32) -> u16 {
+        let turns = (radians / std::f32::consts::TAU).rem_euclid(1.0);
+        (turns * 65536.0).round() as u32 as u16
+    }
+
+    /// The yaw carried by this state, in radians in `0.0..TAU`.
+    pub fn yaw_radians(&self) -> f32 {
+        self.yaw as f32 / 65536.0 * std::f32::consts::TAU
+    }
 }
 
 /// Messages sent from Server to clients.
@@ -40,22 +69,111 @@ pub enum ServerMessage {
     Welcome {
         client_id: ClientId,
         expected_streams: u8,
+        protocol_version: u32,
     },
     /// Signals that the server has finished writing initial data to all module streams.
     /// The client considers initial sync complete when both this message and all expected
     /// `StreamReady` sentinels have been received.
     InitialStateDone,
-    /// Signals that the server is shutting down gracefully. Clients should disconnect.
-    Shutdown,
+    /// Announces that the server shuts down in `delay_secs` seconds, for
+    /// clients to show `message`.  The server then closes every connection
+    /// with `message` as the reason.
+    Shutdown { message: String, delay_secs: f32 },
+    /// Refuses a client whose protocol version or stream table differs from
+    /// the server's; the server closes the connection afterwards.  `streams`
+    /// lists the differing streams and is empty when only the versions differ.
+    ///
+    /// Keep this variant's position and leading fields unchanged across
+    /// versions so that every client can read it.
+    JoinDenied {
+        server_version: u32,
+        client_version: u32,
+        streams: Vec<String>,
+    },
+    /// Every connected player, sent on joins and leaves and refreshed
+    /// periodically; see [`PlayerRoster`](crate::PlayerRoster).
+    Roster { players: Vec<RosterEntry> },
+    /// Keepalive probe; the client's network task answers it with
+    /// [`ClientMessage::Pong`] carrying the same `stamp`.
+    Ping { stamp: u64 },
+    /// Answer to [`ClientMessage::Ping`], echoing its `stamp`.
+    Pong { stamp: u64 },
+    /// The round's phase, sent on joins and whenever it changes; see
+    /// [`RoundState`](crate::RoundState).  `countdown_secs` is the time left
+    /// in the phase when it ends on a timer.
+    GameState {
+        state: ServerGameState,
+        countdown_secs: Option<f32>,
+    },
+    /// The server's value of a replicated tunable, sent on joins and
+    /// whenever it changes; see [`Tunables`](crate::Tunables).
+    Tunable { name: String, value: f32 },
+}
+
+/// Phase of the round the server is running.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, SchemaRead, SchemaWrite)]
+pub enum ServerGameState {
+    /// Waiting for the round to start; players that join now are spawned
+    /// when it does.
+    Lobby,
+    /// A round is being played.
+    #[default]
+    InRound,
+    /// The round is over: interactions are frozen and `summary` is shown
+    /// until the server restarts the round.
+    RoundOver { summary: String },
+}
+
+/// One connected player, as listed in [`ServerMessage::Roster`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SchemaRead, SchemaWrite)]
+pub struct RosterEntry {
+    pub id: ClientId,
+    pub name: String,
+    /// Whole seconds since the player joined, as of the broadcast.
+    pub connected_secs: u32,
+    pub spectator: bool,
+    /// Smoothed round-trip time to the player in milliseconds, once measured.
+    pub rtt_ms: Option<u32>,
+}
+
+/// One registered module stream, as listed in [`ClientMessage::Hello`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SchemaRead, SchemaWrite)]
+pub struct StreamManifestEntry {
+    pub tag: u8,
+    pub name: String,
+    pub direction: StreamDirection,
+    /// Fingerprint of the stream's message type.
+    pub schema: u64,
 }
 
 /// Messages sent from clients to server.
 #[derive(Debug, Clone, Serialize, Deserialize, SchemaRead, SchemaWrite)]
 pub enum ClientMessage {
-    /// Initial handshake sent immediately after stream open.
-    Hello { name: String },
-    /// Input vector from the client.
-    Input { direction: [f32; 3] },
+    /// Initial handshake sent immediately after stream open.  `streams` is
+    /// the client's stream table; the server refuses the client if it or
+    /// `protocol_version` does not match its own.  `spectator` asks to join
+    /// as an observer without a creature.
+    ///
+    /// Must stay the first variant with `protocol_version` as its first
+    /// field; see [`hello_protocol_version`].
+    Hello {
+        protocol_version: u32,
+        name: String,
+        spectator: bool,
+        streams: Vec<StreamManifestEntry>,
+    },
+    /// Input vector from the client, with the sprint and crouch modifiers
+    /// held alongside it.
+    Input {
+        direction: [f32; 3],
+        sprint: bool,
+        crouch: bool,
+    },
+    /// Keepalive probe; the server's network task answers it with
+    /// [`ServerMessage::Pong`] carrying the same `stamp`.
+    Ping { stamp: u64 },
+    /// Answer to [`ServerMessage::Ping`], echoing its `stamp`.
+    Pong { stamp: u64 },
 }
 
 /// Sentinel written by modules to their stream after all initial-burst data has been sent.
@@ -65,6 +183,19 @@ pub enum ClientMessage {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, SchemaRead, SchemaWrite)]
 pub struct StreamReady;
 
+/// Reads the protocol version from an encoded [`ClientMessage::Hello`]
+/// without decoding the rest, so the server can name the client's version
+/// even when the remaining fields come from another protocol version.
+pub(crate) fn hello_protocol_version(bytes: &[u8]) -> Option<u32> {
+    // Variant index 0 (`Hello`), then `protocol_version`, both little-endian u32.
+    let (variant, rest) = bytes.split_first_chunk::<4>()?;
+    if u32::from_le_bytes(*variant) != 0 {
+        return None;
+    }
+    let (version, _) = rest.split_first_chunk::<4>()?;
+    Some(u32::from_le_bytes(*version))
+}
+
 /// Encodes a message using wincode.
 pub(crate) fn encode<T>(msg: &T) -> wincode::WriteResult<Vec<u8>>
 where
@@ -80,3 +211,25 @@ where
 {
     wincode::deserialize(bytes)
 }
+
+/// Most bytes wincode may preallocate for one collection in a frame from a
+/// client.  Client messages are small; under the default 4 MiB limit a few
+/// bytes claiming a long list would allocate megabytes before the frame is
+/// found short.
+pub(crate) const CLIENT_PREALLOCATION_LIMIT: usize = 64 << 10;
+
+/// Wincode configuration for frames from clients: the default one with
+/// [`CLIENT_PREALLOCATION_LIMIT`].
+pub type ClientFrameConfig = Configuration<true, CLIENT_PREALLOCATION_LIMIT>;
+
+/// Decodes a message from a client, which may be hostile, using
+/// [`ClientFrameConfig`].
+pub(crate) fn decode_from_client<T>(bytes: &[u8]) -> wincode::ReadResult<T>
+where
+    for<'de> T: SchemaRead<'de, ClientFrameConfig, Dst = T>,
+{
+    wincode::config::deserialize(
+        bytes,
+        Configuration::default().with_preallocation_size_limit::<CLIENT_PREALLOCATION_LIMIT>(),
+    )
+}
@@ -0,0 +1,257 @@
+//! Round-trip times and connection warnings.
+//!
+//! Both ends of a connection send a [`ServerMessage::Ping`] /
+//! [`ClientMessage::Ping`] on the control stream every [`PING_INTERVAL`],
+//! stamped with the time since the connection started.  The peer's network
+//! task echoes the stamp straight back in a `Pong`, so the measured round
+//! trip does not include either side's frame time.  Pings also keep idle
+//! connections alive.
+//!
+//! Measurements land in [`ConnectionQuality`]: per client on the server, for
+//! the connection to the server on the client.  A round trip far above the
+//! smoothed value, and a server→client stream that starts queueing frames
+//! past its budget, emit a [`ConnectionWarning`].
+//!
+//! [`ServerMessage::Ping`]: crate::ServerMessage::Ping
+//! [`ClientMessage::Ping`]: crate::ClientMessage::Ping
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+
+use crate::{
+    ClientEvent, ClientId, NetworkReceive, NetworkSet, Server, ServerEvent, StreamRegistry,
+};
+
+/// Interval between keepalive pings, in each direction.
+pub const PING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A round trip at least this many times the smoothed RTT is a spike...
+const RTT_SPIKE_FACTOR: u32 = 2;
+
+/// ...provided it also exceeds the smoothed RTT by this much.
+const RTT_SPIKE_MIN_EXCESS: Duration = Duration::from_millis(100);
+
+/// Round-trip times of one connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RttStats {
+    /// Most recent round trip.
+    pub latest: Duration,
+    /// Exponentially smoothed round trip, each sample weighted 1/8.
+    pub smoothed: Duration,
+}
+
+impl RttStats {
+    fn new(sample: Duration) -> Self {
+        Self {
+            latest: sample,
+            smoothed: sample,
+        }
+    }
+
+    /// Returns `true` if `sample` is a spike compared to the smoothed RTT.
+    pub fn is_spike(&self, sample: Duration) -> bool {
+        sample >= self.smoothed * RTT_SPIKE_FACTOR
+            && sample.saturating_sub(self.smoothed) >= RTT_SPIKE_MIN_EXCESS
+    }
+
+    /// Folds `sample` in, returning a warning for `client` if it is a spike.
+    fn record(&mut self, client: Option<ClientId>, sample: Duration) -> Option<ConnectionWarning> {
+        let warning = self
+            .is_spike(sample)
+            .then_some(ConnectionWarning::RttSpike {
+                client,
+                rtt: sample,
+                smoothed: self.smoothed,
+            });
+        self.latest = sample;
+        self.smoothed = (self.smoothed * 7 + sample) / 8;
+        warning
+    }
+}
+
+/// Measured round-trip times.
+///
+/// Cleared when hosting starts or stops and when connecting or
+/// disconnecting.
+#[derive(Resource, Debug, Default)]
+pub struct ConnectionQuality {
+    /// Client-side: the connection to the server, once measured.
+    pub server: Option<RttStats>,
+    /// Server-side: each connected client's connection, once measured.
+    pub clients: HashMap<ClientId, RttStats>,
+}
+
+/// Emitted when a connection degrades.
+#[derive(Message, Clone, Debug, PartialEq)]
+pub enum ConnectionWarning {
+    /// A round trip took far longer than the smoothed RTT.  `client` is
+    /// `None` for the client's own connection to the server.
+    RttSpike {
+        client: Option<ClientId>,
+        rtt: Duration,
+        smoothed: Duration,
+    },
+    /// Server-side: a server→client stream has started queueing deferrable
+    /// frames because it ran out of budget.
+    Backpressure {
+        stream: &'static str,
+        queued_frames: usize,
+    },
+}
+
+/// Stamp for a ping sent now on a connection that started at `epoch`.
+pub(crate) fn ping_stamp(epoch: Instant) -> u64 {
+    epoch.elapsed().as_micros() as u64
+}
+
+/// Round trip of the ping stamped `stamp`, echoed back now.
+pub(crate) fn round_trip(epoch: Instant, stamp: u64) -> Duration {
+    epoch.elapsed().saturating_sub(Duration::from_micros(stamp))
+}
+
+/// Timer for a connection's pings; the first fires one interval from now.
+pub(crate) fn ping_timer() -> tokio::time::Interval {
+    let mut timer =
+        tokio::time::interval_at(tokio::time::Instant::now() + PING_INTERVAL, PING_INTERVAL);
+    timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    timer
+}
+
+pub(crate) fn register_quality_systems(app: &mut App) {
+    app.init_resource::<ConnectionQuality>();
+    app.add_message::<ConnectionWarning>();
+    app.add_systems(
+        NetworkReceive,
+        (
+            record_server_rtt,
+            record_client_rtt,
+            watch_backpressure.run_if(resource_exists::<Server>),
+        )
+            .after(NetworkSet::Drain),
+    );
+}
+
+/// Server system: records each client's round trips.
+fn record_server_rtt(
+    mut events: MessageReader<ServerEvent>,
+    mut quality: ResMut<ConnectionQuality>,
+    mut warnings: MessageWriter<ConnectionWarning>,
+) {
+    for event in events.read() {
+        match event {
+            ServerEvent::HostingStarted { .. } | ServerEvent::HostingStopped => {
+                quality.clients.clear();
+            }
+            ServerEvent::ClientDisconnected { id } => {
+                quality.clients.remove(id);
+            }
+            ServerEvent::RttMeasured { id, rtt } => {
+                let stats = quality
+                    .clients
+                    .entry(*id)
+                    .or_insert_with(|| RttStats::new(*rtt));
+                if let Some(warning) = stats.record(Some(*id), *rtt) {
+                    warn!("Round trip to client {} spiked to {:?}", id.0, rtt);
+                    warnings.write(warning);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Client system: records the round trips to the server.
+fn record_client_rtt(
+    mut events: MessageReader<ClientEvent>,
+    mut quality: ResMut<ConnectionQuality>,
+    mut warnings: MessageWriter<ConnectionWarning>,
+) {
+    for event in events.read() {
+        match event {
+            ClientEvent::Connected | ClientEvent::Disconnected { .. } => {
+                quality.server = None;
+            }
+            ClientEvent::RttMeasured { rtt } => {
+                let stats = quality.server.get_or_insert_with(|| RttStats::new(*rtt));
+                if let Some(warning) = stats.record(None, *rtt) {
+                    warn!("Round trip to the server spiked to {:?}", rtt);
+                    warnings.write(warning);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Server system: warns once each time a server→client stream starts
+/// queueing frames.
+fn watch_backpressure(
+    registry: Res<StreamRegistry>,
+    mut backlogged: Local<HashSet<u8>>,
+    mut warnings: MessageWriter<ConnectionWarning>,
+) {
+    for (tag, stream, saturation) in registry.saturations() {
+        if saturation.queued_frames == 0 {
+            backlogged.remove(&tag);
+        } else if backlogged.insert(tag) {
+            warn!(
+                "Stream '{}' is over budget with {} frame(s) queued",
+                stream, saturation.queued_frames
+            );
+            warnings.write(ConnectionWarning::Backpressure {
+                stream,
+                queued_frames: saturation.queued_frames,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn samples_are_smoothed() {
+        let mut stats = RttStats::new(ms(80));
+        assert_eq!(stats.record(None, ms(80)), None);
+        assert_eq!(stats.smoothed, ms(80));
+
+        assert_eq!(stats.record(None, ms(160)), None);
+        assert_eq!(
+            stats,
+            RttStats {
+                latest: ms(160),
+                smoothed: ms(90),
+            }
+        );
+    }
+
+    #[test]
+    fn spikes_need_both_ratio_and_absolute_excess() {
+        let stats = RttStats::new(ms(20));
+        // Five times the smoothed RTT, but only 80 ms more.
+        assert!(!stats.is_spike(ms(100)));
+        assert!(stats.is_spike(ms(150)));
+
+        let stats = RttStats::new(ms(300));
+        // 150 ms more, but under twice the smoothed RTT.
+        assert!(!stats.is_spike(ms(450)));
+        assert!(stats.is_spike(ms(600)));
+
+        let mut stats = RttStats::new(ms(20));
+        assert_eq!(
+            stats.record(Some(ClientId(3)), ms(400)),
+            Some(ConnectionWarning::RttSpike {
+                client: Some(ClientId(3)),
+                rtt: ms(400),
+                smoothed: ms(20),
+            })
+        );
+    }
+}
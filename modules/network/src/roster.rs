@@ -0,0 +1,210 @@
+//! Player roster: who is connected, replicated from the server.
+//!
+//! The server keeps one [`RosterEntry`] per joined client and broadcasts the
+//! whole list as [`ServerMessage::Roster`] whenever someone joins or leaves,
+//! and every [`ROSTER_REFRESH_INTERVAL`] seconds so connected times stay
+//! current.  Each entry carries the player's smoothed round-trip time from
+//! [`ConnectionQuality`].  Clients store the latest list in [`PlayerRoster`].
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    ClientEvent, ClientId, ConnectionQuality, NetServerSender, NetworkReceive, NetworkSet,
+    PlayerEvent, RosterEntry, ServerEvent, ServerMessage,
+};
+
+/// Seconds between periodic roster broadcasts.
+pub const ROSTER_REFRESH_INTERVAL: f32 = 5.0;
+
+/// Client-side: the players connected to the server, in join order.
+///
+/// Replaced by every [`ServerMessage::Roster`] and cleared on connect and
+/// disconnect.
+#[derive(Resource, Debug, Default)]
+pub struct PlayerRoster {
+    pub players: Vec<RosterEntry>,
+    /// `Time::elapsed` when `players` arrived.
+    pub received_at: Duration,
+}
+
+impl PlayerRoster {
+    /// How long `entry` has been connected at `now` (a `Time::elapsed`),
+    /// counting the time since the roster arrived.
+    pub fn connected_for(&self, entry: &RosterEntry, now: Duration) -> Duration {
+        Duration::from_secs(entry.connected_secs.into()) + now.saturating_sub(self.received_at)
+    }
+}
+
+/// Server-side: joined players and when they joined.
+#[derive(Resource, Default)]
+struct ServerRoster {
+    players: Vec<(ClientId, String, bool, Duration)>,
+    refresh: Timer,
+}
+
+impl ServerRoster {
+    fn entries(&self, now: Duration, quality: &ConnectionQuality) -> Vec<RosterEntry> {
+        self.players
+            .iter()
+            .map(|(id, name, spectator, joined_at)| RosterEntry {
+                id: *id,
+                name: name.clone(),
+                connected_secs: now.saturating_sub(*joined_at).as_secs() as u32,
+                spectator: *spectator,
+                rtt_ms: quality
+                    .clients
+                    .get(id)
+                    .map(|rtt| rtt.smoothed.as_millis() as u32),
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn register_roster_systems(app: &mut App) {
+    app.insert_resource(ServerRoster {
+        players: Vec::new(),
+        refresh: Timer::from_seconds(ROSTER_REFRESH_INTERVAL, TimerMode::Repeating),
+    });
+    app.init_resource::<PlayerRoster>();
+    app.add_systems(
+        NetworkReceive,
+        (update_server_roster, receive_roster).after(NetworkSet::Drain),
+    );
+}
+
+/// Server system: tracks joins and leaves, broadcasting the roster on every
+/// change and every [`ROSTER_REFRESH_INTERVAL`].
+fn update_server_roster(
+    time: Res<Time>,
+    mut roster: ResMut<ServerRoster>,
+    mut server_events: MessageReader<ServerEvent>,
+    mut player_events: MessageReader<PlayerEvent>,
+    quality: Res<ConnectionQuality>,
+    sender: Option<Res<NetServerSender>>,
+) {
+    if server_events
+        .read()
+        .any(|e| matches!(e, ServerEvent::HostingStarted { .. }))
+    {
+        roster.players.clear();
+        roster.refresh.reset();
+    }
+
+    let now = time.elapsed();
+    let mut changed = false;
+    for event in player_events.read() {
+        match event {
+            PlayerEvent::Joined {
+                id,
+                name,
+                spectator,
+            } => {
+                roster.players.retain(|(p, ..)| p != id);
+                roster.players.push((*id, name.clone(), *spectator, now));
+                changed = true;
+            }
+            PlayerEvent::Left { id } => {
+                roster.players.retain(|(p, ..)| p != id);
+                changed = true;
+            }
+            PlayerEvent::MovementViolation { .. } => {}
+        }
+    }
+
+    let refresh = roster.refresh.tick(time.delta()).just_finished();
+    if let Some(sender) = sender
+        && (changed || (refresh && !roster.players.is_empty()))
+    {
+        sender.broadcast(&ServerMessage::Roster {
+            players: roster.entries(now, &quality),
+        });
+    }
+}
+
+/// Client system: stores each received roster in [`PlayerRoster`].
+fn receive_roster(
+    time: Res<Time>,
+    mut events: MessageReader<ClientEvent>,
+    mut roster: ResMut<PlayerRoster>,
+) {
+    for event in events.read() {
+        match event {
+            ClientEvent::Connected | ClientEvent::Disconnected { .. } => {
+                *roster = PlayerRoster::default();
+            }
+            ClientEvent::ServerMessageReceived(ServerMessage::Roster { players }) => {
+                roster.players = players.clone();
+                roster.received_at = time.elapsed();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RttStats;
+
+    #[test]
+    fn server_roster_reports_time_since_join_and_rtt() {
+        let roster = ServerRoster {
+            players: vec![
+                (ClientId(1), "ada".into(), false, Duration::from_secs(10)),
+                (ClientId(2), "bob".into(), true, Duration::from_secs(70)),
+            ],
+            refresh: Timer::default(),
+        };
+
+        let mut quality = ConnectionQuality::default();
+        quality.clients.insert(
+            ClientId(2),
+            RttStats {
+                latest: Duration::from_millis(50),
+                smoothed: Duration::from_millis(42),
+            },
+        );
+
+        let entries = roster.entries(Duration::from_millis(100_500), &quality);
+        assert_eq!(
+            entries,
+            [
+                RosterEntry {
+                    id: ClientId(1),
+                    name: "ada".into(),
+                    connected_secs: 90,
+                    spectator: false,
+                    rtt_ms: None,
+                },
+                RosterEntry {
+                    id: ClientId(2),
+                    name: "bob".into(),
+                    connected_secs: 30,
+                    spectator: true,
+                    rtt_ms: Some(42),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn player_roster_extrapolates_connected_time() {
+        let entry = RosterEntry {
+            id: ClientId(1),
+            name: "ada".into(),
+            connected_secs: 60,
+            spectator: false,
+            rtt_ms: None,
+        };
+        let roster = PlayerRoster {
+            players: vec![entry.clone()],
+            received_at: Duration::from_secs(5),
+        };
+        assert_eq!(
+            roster.connected_for(&entry, Duration::from_secs(8)),
+            Duration::from_secs(63)
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Round phase: lobby, in round or over, replicated from the server.
+//!
+//! Server code (the game mode) sets [`RoundState`]; the server sends it as
+//! [`ServerMessage::GameState`] to every client whenever it changes, and to
+//! each player as they join.  Clients keep the latest one in their own
+//! [`RoundState`], with the countdown turned into a local deadline.  Without
+//! anything setting it the round is always [`ServerGameState::InRound`].
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    ClientEvent, NetServerSender, NetworkReceive, NetworkSet, PlayerEvent, ServerGameState,
+    ServerMessage,
+};
+
+/// The phase of the round and, when it ends on a timer, when.
+///
+/// Authoritative on the server.  On clients it is replaced by every
+/// [`ServerMessage::GameState`] and reset on connect and disconnect.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct RoundState {
+    pub state: ServerGameState,
+    /// `Time::elapsed` at which the phase is over, if it ends on a timer.
+    pub ends_at: Option<Duration>,
+}
+
+impl RoundState {
+    /// Time left in the phase at `now` (a `Time::elapsed`), if it ends on a
+    /// timer.
+    pub fn remaining(&self, now: Duration) -> Option<Duration> {
+        self.ends_at.map(|ends_at| ends_at.saturating_sub(now))
+    }
+
+    /// Whether interactions are frozen, i.e. the round is over.
+    pub fn is_frozen(&self) -> bool {
+        matches!(self.state, ServerGameState::RoundOver { .. })
+    }
+
+    /// Whether players are waiting for the round to start.
+    pub fn is_lobby(&self) -> bool {
+        self.state == ServerGameState::Lobby
+    }
+
+    fn message(&self, now: Duration) -> ServerMessage {
+        ServerMessage::GameState {
+            state: self.state.clone(),
+            countdown_secs: self.remaining(now).map(|left| left.as_secs_f32()),
+        }
+    }
+}
+
+pub(crate) fn register_round_systems(app: &mut App) {
+    app.init_resource::<RoundState>();
+    app.add_systems(
+        NetworkReceive,
+        (send_round_state, receive_round_state).after(NetworkSet::Drain),
+    );
+}
+
+/// Server system: sends the [`RoundState`] to joining players, and to
+/// everyone when it changes.
+fn send_round_state(
+    time: Res<Time>,
+    round: Res<RoundState>,
+    mut player_events: MessageReader<PlayerEvent>,
+    sender: Option<Res<NetServerSender>>,
+) {
+    let Some(sender) = sender else {
+        return;
+    };
+    let message = round.message(time.elapsed());
+    if round.is_changed() {
+        sender.broadcast(&message);
+    }
+    for event in player_events.read() {
+        if let PlayerEvent::Joined { id, .. } = event {
+            sender.send_to(*id, &message);
+        }
+    }
+}
+
+/// Client system: stores each received round phase in [`RoundState`].
+fn receive_round_state(
+    time: Res<Time>,
+    mut events: MessageReader<ClientEvent>,
+    mut round: ResMut<RoundState>,
+) {
+    for event in events.read() {
+        match event {
+            ClientEvent::Connected | ClientEvent::Disconnected { .. } => {
+                *round = RoundState::default();
+            }
+            ClientEvent::ServerMessageReceived(ServerMessage::GameState {
+                state,
+                countdown_secs,
+            }) => {
+                *round = RoundState {
+                    state: state.clone(),
+                    ends_at: countdown_secs.map(|secs| {
+                        time.elapsed() + Duration::try_from_secs_f32(secs).unwrap_or_default()
+                    }),
+                };
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clients_store_the_round_phase_until_disconnected() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_message::<ClientEvent>();
+        app.init_resource::<RoundState>();
+        app.add_systems(Update, receive_round_state);
+
+        app.world_mut()
+            .write_message(ClientEvent::ServerMessageReceived(
+                ServerMessage::GameState {
+                    state: ServerGameState::RoundOver {
+                        summary: "The crew wins".into(),
+                    },
+                    countdown_secs: Some(10.0),
+                },
+            ));
+        app.update();
+        let round = app.world().resource::<RoundState>().clone();
+        assert!(round.is_frozen());
+        let now = app.world().resource::<Time>().elapsed();
+        assert_eq!(round.remaining(now), Some(Duration::from_secs(10)));
+
+        app.world_mut().write_message(ClientEvent::Disconnected {
+            reason: "bye".into(),
+        });
+        app.update();
+        assert_eq!(*app.world().resource::<RoundState>(), RoundState::default());
+    }
+}
@@ -0,0 +1,462 @@
+//! Typed request/response calls on top of module streams.
+//!
+//! An RPC is a pair of streams declared by one [`RpcDef`]: requests travel
+//! client→server on `request_tag`, responses server→client on
+//! `response_tag`.  Every frame starts with a 4-byte little-endian call id
+//! followed by the wincode-encoded payload, so the client can match each
+//! response to the [`RpcCall`] that is waiting for it.
+//!
+//! Register an RPC with [`RpcAppExt::add_rpc`] during plugin `build()`.  This
+//! inserts two resources:
+//!
+//! - [`RpcClient<Req, Resp>`]: [`RpcClient::call`] sends a request and returns
+//!   an [`RpcCall`] handle that the caller polls with [`RpcCall::try_take`].
+//! - [`RpcServer<Req, Resp>`]: a handler system drains [`RpcRequest`]s with
+//!   [`RpcServer::drain`] and answers each with [`RpcServer::respond`].
+//!
+//! `add_rpc` also adds the systems that route responses to their calls, fail
+//! outstanding calls on disconnect, and send the response stream's
+//! [`StreamReady`](crate::StreamReady) to joining clients.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use bevy::{log, prelude::*};
+use bytes::{BufMut, Bytes, BytesMut};
+use wincode::config::DefaultConfig;
+
+use crate::{
+    ClientEvent, ClientId, ModuleReadySent, NetworkReceive, NetworkSet, PlayerEvent, Server,
+    StreamDef, StreamDirection, StreamReader, StreamRegistry, StreamSendError, StreamSender,
+    StreamWriteCmd, protocol,
+};
+
+/// Size of the call-id prefix on every RPC frame.
+const RPC_ID_LEN: usize = 4;
+
+/// Payload types that can travel as an RPC request or response.
+pub trait RpcMessage:
+    wincode::SchemaWrite<DefaultConfig, Src = Self>
+    + for<'de> wincode::SchemaRead<'de, DefaultConfig, Dst = Self>
+    + Send
+    + Sync
+    + 'static
+{
+}
+
+impl<T> RpcMessage for T where
+    T: wincode::SchemaWrite<DefaultConfig, Src = T>
+        + for<'de> wincode::SchemaRead<'de, DefaultConfig, Dst = T>
+        + Send
+        + Sync
+        + 'static
+{
+}
+
+/// Declaration of an RPC registered with [`RpcAppExt::add_rpc`].
+#[derive(Debug, Clone, Copy)]
+pub struct RpcDef {
+    /// Human-readable name (used in log messages and stream names).
+    pub name: &'static str,
+    /// Tag of the client→server request stream.
+    pub request_tag: u8,
+    /// Tag of the server→client response stream.
+    pub response_tag: u8,
+}
+
+impl RpcDef {
+    /// An RPC whose two tags are derived from `name`, like
+    /// [`stream_tag`](crate::stream_tag) does for plain streams.
+    pub const fn named(name: &'static str) -> Self {
+        let (request_tag, response_tag) = crate::tags::rpc_tags(name);
+        Self {
+            name,
+            request_tag,
+            response_tag,
+        }
+    }
+}
+
+/// Correlation id of one call, unique per client connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RpcId(pub u32);
+
+/// Reasons an [`RpcCall`] completes without a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcError {
+    /// The connection closed before the server answered.
+    Disconnected,
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcError::Disconnected => write!(f, "disconnected before a response arrived"),
+        }
+    }
+}
+
+type CallSlot<Resp> = Arc<Mutex<Option<Result<Resp, RpcError>>>>;
+
+/// Handle to an in-flight call returned by [`RpcClient::call`].
+///
+/// Keep it (in a component, resource or `Local`) and poll
+/// [`try_take`](Self::try_take) each frame until it yields.
+pub struct RpcCall<Resp> {
+    id: RpcId,
+    slot: CallSlot<Resp>,
+}
+
+impl<Resp> RpcCall<Resp> {
+    /// Correlation id of this call.
+    pub fn id(&self) -> RpcId {
+        self.id
+    }
+
+    /// Takes the outcome once it has arrived; `None` while still pending.
+    /// Yields at most once.
+    pub fn try_take(&self) -> Option<Result<Resp, RpcError>> {
+        self.slot.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+fn encode_frame<T: RpcMessage>(id: RpcId, msg: &T) -> Result<Bytes, StreamSendError> {
+    let payload = protocol::encode(msg).map_err(|e| {
+        log::error!("RPC: encode failed: {}", e);
+        StreamSendError::Encode
+    })?;
+    let mut frame = BytesMut::with_capacity(RPC_ID_LEN + payload.len());
+    frame.put_u32_le(id.0);
+    frame.put_slice(&payload);
+    Ok(frame.freeze())
+}
+
+fn decode_frame<T: RpcMessage>(name: &str, frame: &[u8]) -> Option<(RpcId, T)> {
+    let Some((id, payload)) = frame.split_first_chunk::<RPC_ID_LEN>() else {
+        log::error!("RPC '{}': frame too short ({} bytes)", name, frame.len());
+        return None;
+    };
+    let id = RpcId(u32::from_le_bytes(*id));
+    protocol::decode::<T>(payload)
+        .map_err(|e| log::error!("RPC '{}': decode error for call {}: {}", name, id.0, e))
+        .ok()
+        .map(|msg| (id, msg))
+}
+
+/// Client-side half of an RPC: sends requests and collects responses.
+pub struct RpcClient<Req: RpcMessage, Resp: RpcMessage> {
+    name: &'static str,
+    requests: StreamSender<Req>,
+    responses: StreamReader<Resp>,
+    next_id: u32,
+    pending: HashMap<RpcId, CallSlot<Resp>>,
+}
+
+impl<Req: RpcMessage, Resp: RpcMessage> bevy::ecs::resource::Resource for RpcClient<Req, Resp> {}
+
+impl<Req: RpcMessage, Resp: RpcMessage> RpcClient<Req, Resp> {
+    /// Sends `request` to the server and returns a handle to its response.
+    ///
+    /// Fails like [`StreamSender::send`] when no client is connected or the
+    /// request stream is full.
+    pub fn call(&mut self, request: &Req) -> Result<RpcCall<Resp>, StreamSendError> {
+        let id = RpcId(self.next_id);
+        let frame = encode_frame(id, request)?;
+        self.requests.send_raw_client(frame)?;
+        self.next_id = self.next_id.wrapping_add(1);
+        let slot: CallSlot<Resp> = Arc::new(Mutex::new(None));
+        self.pending.insert(id, slot.clone());
+        Ok(RpcCall { id, slot })
+    }
+
+    /// Number of calls still waiting for a response.
+    pub fn pending_calls(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Hands every buffered response to the call it answers.
+    fn route_responses(&mut self) {
+        let frames: Vec<Bytes> = {
+            let mut guard = self.responses.buf.lock().unwrap_or_else(|e| e.into_inner());
+            guard.drain(..).collect()
+        };
+        for frame in frames {
+            let Some((id, response)) = decode_frame::<Resp>(self.name, &frame) else {
+                continue;
+            };
+            match self.pending.remove(&id) {
+                Some(slot) => {
+                    *slot.lock().unwrap_or_else(|e| e.into_inner()) = Some(Ok(response));
+                }
+                None => log::warn!(
+                    "RPC '{}': response for unknown call {}, ignoring",
+                    self.name,
+                    id.0
+                ),
+            }
+        }
+    }
+
+    /// Completes every outstanding call with `error`.
+    fn fail_pending(&mut self, error: RpcError) {
+        for (_, slot) in self.pending.drain() {
+            *slot.lock().unwrap_or_else(|e| e.into_inner()) = Some(Err(error));
+        }
+    }
+}
+
+/// A decoded request handed to the server-side handler.
+#[derive(Debug, Clone)]
+pub struct RpcRequest<Req> {
+    pub from: ClientId,
+    pub id: RpcId,
+    pub request: Req,
+}
+
+/// Server-side half of an RPC: receives requests and sends responses.
+pub struct RpcServer<Req: RpcMessage, Resp: RpcMessage> {
+    name: &'static str,
+    requests: StreamReader<Req>,
+    responses: StreamSender<Resp>,
+}
+
+impl<Req: RpcMessage, Resp: RpcMessage> bevy::ecs::resource::Resource for RpcServer<Req, Resp> {}
+
+impl<Req: RpcMessage, Resp: RpcMessage> RpcServer<Req, Resp> {
+    /// Drains all requests received since the last call.  Frames that fail
+    /// to decode are logged, skipped and counted against the sending client
+    /// like those of [`StreamReader::drain_from_client`].
+    ///
+    /// The iterator does not borrow the server, so handlers can
+    /// [`respond`](Self::respond) while iterating.
+    pub fn drain(&mut self) -> impl Iterator<Item = RpcRequest<Req>> + use<Req, Resp> {
+        let frames: Vec<(ClientId, Bytes)> = {
+            let mut guard = self
+                .requests
+                .client_buf
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            guard.drain(..).collect()
+        };
+        let name = self.name;
+        let failures = self.requests.decode_failures.clone();
+        frames.into_iter().filter_map(move |(from, frame)| {
+            let decoded = decode_frame::<Req>(name, &frame);
+            if decoded.is_none() {
+                failures.record(from);
+            }
+            decoded.map(|(id, request)| RpcRequest { from, id, request })
+        })
+    }
+
+    /// Answers call `id` from client `to`.  Responses are never deferred by
+    /// the replication budget.
+    pub fn respond(&self, to: ClientId, id: RpcId, response: &Resp) -> Result<(), StreamSendError> {
+        let frame = encode_frame(id, response)?;
+        self.responses.send_raw(StreamWriteCmd::SendTo {
+            client: to,
+            data: frame,
+            deferrable: false,
+        })
+    }
+
+    /// Queues `request` as if client `from` had sent it as call `id`.
+    ///
+    /// Like [`StreamRegistry::route_client_stream_frame`], intended for tests
+    /// that exercise handler systems without a live connection.
+    #[doc(hidden)]
+    pub fn inject(&self, from: ClientId, id: RpcId, request: &Req) {
+        let Ok(frame) = encode_frame(id, request) else {
+            return;
+        };
+        self.requests
+            .client_buf
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push_back((from, frame));
+    }
+}
+
+impl StreamRegistry {
+    /// Registers the two streams of an RPC and returns both halves.
+    ///
+    /// [`RpcAppExt::add_rpc`] calls this and also adds the routing systems;
+    /// use this directly only when wiring those up by hand (e.g. in tests).
+    ///
+    /// # Panics
+    /// Panics with a [`StreamRegistryError`](crate::StreamRegistryError) if
+    /// either tag is 0 or already registered.
+    pub fn register_rpc<Req: RpcMessage, Resp: RpcMessage>(
+        &mut self,
+        def: RpcDef,
+    ) -> (RpcClient<Req, Resp>, RpcServer<Req, Resp>) {
+        let (request_sender, request_reader) = self.register::<Req>(StreamDef {
+            tag: def.request_tag,
+            name: def.name,
+            direction: StreamDirection::ClientToServer,
+        });
+        let (response_sender, response_reader) = self.register::<Resp>(StreamDef {
+            tag: def.response_tag,
+            name: def.name,
+            direction: StreamDirection::ServerToClient,
+        });
+        let client = RpcClient {
+            name: def.name,
+            requests: request_sender,
+            responses: response_reader,
+            next_id: 0,
+            pending: HashMap::new(),
+        };
+        let server = RpcServer {
+            name: def.name,
+            requests: request_reader,
+            responses: response_sender,
+        };
+        (client, server)
+    }
+}
+
+/// Extension trait for registering RPCs on an [`App`].
+pub trait RpcAppExt {
+    /// Registers the RPC described by `def`, inserts its [`RpcClient`] and
+    /// [`RpcServer`] resources and adds the systems that route responses.
+    ///
+    /// Requires [`NetworkPlugin`](crate::NetworkPlugin) to be added first.
+    fn add_rpc<Req: RpcMessage, Resp: RpcMessage>(&mut self, def: RpcDef) -> &mut Self;
+}
+
+impl RpcAppExt for App {
+    fn add_rpc<Req: RpcMessage, Resp: RpcMessage>(&mut self, def: RpcDef) -> &mut Self {
+        let (client, server) = self
+            .world_mut()
+            .get_resource_mut::<StreamRegistry>()
+            .expect("add_rpc requires NetworkPlugin to be added first (StreamRegistry not found)")
+            .register_rpc::<Req, Resp>(def);
+        self.insert_resource(client);
+        self.insert_resource(server);
+        self.add_systems(
+            NetworkReceive,
+            (
+                route_rpc_responses::<Req, Resp>,
+                send_rpc_stream_ready_on_join::<Req, Resp>.run_if(resource_exists::<Server>),
+            )
+                .after(NetworkSet::Drain),
+        );
+        self
+    }
+}
+
+/// Client system: completes calls whose responses arrived this frame and
+/// fails every outstanding call when the connection drops.
+fn route_rpc_responses<Req: RpcMessage, Resp: RpcMessage>(
+    mut client: ResMut<RpcClient<Req, Resp>>,
+    mut events: MessageReader<ClientEvent>,
+) {
+    client.route_responses();
+    if events
+        .read()
+        .any(|e| matches!(e, ClientEvent::Disconnected { .. }))
+    {
+        client.fail_pending(RpcError::Disconnected);
+    }
+}
+
+/// Server system: the response stream carries no initial state, so it is
+/// ready as soon as a client joins.
+fn send_rpc_stream_ready_on_join<Req: RpcMessage, Resp: RpcMessage>(
+    mut player_events: MessageReader<PlayerEvent>,
+    server: Res<RpcServer<Req, Resp>>,
+    mut module_ready: MessageWriter<ModuleReadySent>,
+) {
+    for event in player_events.read() {
+        let PlayerEvent::Joined { id, .. } = event else {
+            continue;
+        };
+        if let Err(e) = server.responses.send_stream_ready_to(*id) {
+            log::error!(
+                "Failed to send StreamReady for RPC '{}' to ClientId({}): {e}",
+                server.name,
+                id.0
+            );
+        } else {
+            module_ready.write(ModuleReadySent { client: *id });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ECHO: RpcDef = RpcDef {
+        name: "echo",
+        request_tag: 20,
+        response_tag: 21,
+    };
+
+    #[test]
+    fn calls_round_trip_with_correlation_ids() {
+        let mut registry = StreamRegistry::default();
+        let (mut client, mut server) = registry.register_rpc::<u32, String>(ECHO);
+        let (_, mut server_rx, _) = registry.prepare_server_start();
+        let mut client_rxs = registry.prepare_client_connect();
+        let (tag, mut request_rx) = client_rxs.remove(0);
+        assert_eq!(tag, ECHO.request_tag);
+
+        let first = client.call(&1).expect("call");
+        let second = client.call(&2).expect("call");
+        assert_ne!(first.id(), second.id());
+        assert_eq!(client.pending_calls(), 2);
+
+        // Server side: requests arrive tagged with the sending client.
+        let from = ClientId(5);
+        while let Ok(frame) = request_rx.try_recv() {
+            registry.route_client_stream_frame(from, ECHO.request_tag, frame);
+        }
+        let requests: Vec<_> = server.drain().collect();
+        assert_eq!(requests.len(), 2);
+        // Answer out of order; ids keep responses matched to their calls.
+        for request in requests.iter().rev() {
+            server
+                .respond(
+                    request.from,
+                    request.id,
+                    &format!("got {}", request.request),
+                )
+                .expect("respond");
+        }
+
+        // Client side: route what the server task would have written.
+        while let Ok((tag, cmd)) = server_rx.try_recv() {
+            let StreamWriteCmd::SendTo {
+                client: to,
+                data,
+                deferrable,
+            } = cmd
+            else {
+                panic!("responses are sent to one client");
+            };
+            assert_eq!((tag, to, deferrable), (ECHO.response_tag, from, false));
+            registry.route_stream_frame(tag, data);
+        }
+        assert!(first.try_take().is_none(), "not routed yet");
+        client.route_responses();
+
+        assert_eq!(first.try_take(), Some(Ok("got 1".to_string())));
+        assert_eq!(second.try_take(), Some(Ok("got 2".to_string())));
+        assert_eq!(first.try_take(), None, "outcome is taken once");
+        assert_eq!(client.pending_calls(), 0);
+    }
+
+    #[test]
+    fn pending_calls_fail_on_disconnect() {
+        let mut registry = StreamRegistry::default();
+        let (mut client, _server) = registry.register_rpc::<u32, u32>(ECHO);
+        assert_eq!(client.call(&1).err(), Some(StreamSendError::Closed));
+
+        let _client_rxs = registry.prepare_client_connect();
+        let call = client.call(&1).expect("call");
+        client.fail_pending(RpcError::Disconnected);
+        assert_eq!(call.try_take(), Some(Err(RpcError::Disconnected)));
+    }
+}
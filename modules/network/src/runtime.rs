@@ -18,6 +18,17 @@ pub(crate) enum ServerCommand {
     Broadcast {
         message: ServerMessage,
     },
+    /// Starts a graceful shutdown; see [`crate::NetCommand::ShutdownServer`].
+    Shutdown {
+        message: String,
+        delay: std::time::Duration,
+    },
+    /// Closes the connection of `client`, giving `reason`; see
+    /// [`crate::NetServerSender::kick`].
+    Kick {
+        client: crate::ClientId,
+        reason: String,
+    },
 }
 
 #[derive(Resource)]
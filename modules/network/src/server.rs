@@ -1,30 +1,103 @@
 use std::collections::HashMap;
-use std::net::SocketAddr;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 use bevy::log;
 use bytes::Bytes;
-use futures_util::sink::SinkExt;
-use futures_util::stream::StreamExt;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinSet;
-use tokio_util::codec::{FramedRead, FramedWrite, LengthDelimitedCodec};
 use tokio_util::sync::CancellationToken;
 
-use crate::config;
-use crate::protocol::{ClientMessage, ServerMessage, decode, encode};
+use crate::budget::{BudgetScheduler, CLIENT_BYTES_PER_TICK, SharedSaturation};
+use crate::limits::{ClientFrameLimits, DecodeFailures};
+use crate::protocol::{
+    ClientMessage, PROTOCOL_VERSION, ServerMessage, StreamManifestEntry, decode_from_client,
+    encode, hello_protocol_version,
+};
+use crate::quality;
 use crate::runtime::ServerCommand;
-use crate::{ClientId, ServerEvent, StreamDef, StreamDirection, StreamWriteCmd};
+use crate::tags::manifest_mismatches;
+use crate::transport::{Bind, Connection, Listener, SendStream};
+use crate::{
+    ClientId, NETWORK_UPDATE_INTERVAL, RegisteredStream, ServerEvent, StreamDirection,
+    StreamWriteCmd,
+};
 
 /// Bounded channel buffer size per client to prevent memory exhaustion from slow clients.
 /// Allows brief bursts while providing backpressure.
 const PER_PEER_BUFFER_SIZE: usize = 100;
 
+/// A per-client write channel with fewer free slots than this is backing
+/// up: the client is not reading as fast as the server writes.
+const BACKPRESSURE_FREE_SLOTS: usize = PER_PEER_BUFFER_SIZE / 2;
+
+/// How long a refused client gets to read [`ServerMessage::JoinDenied`] and
+/// hang up before the server closes the connection itself.
+const JOIN_DENIED_LINGER: Duration = Duration::from_secs(2);
+
+/// How long the server waits for clients to acknowledge the close of their
+/// connections when it stops hosting.
+const CLOSE_LINGER: Duration = Duration::from_secs(1);
+
+/// Holds [`ClientId::LOCAL`] for a loopback client until its connection task
+/// ends.
+struct LocalIdClaim(Arc<AtomicBool>);
+
+impl Drop for LocalIdClaim {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
 /// Per-stream, per-client write channels: stream_tag → client_id → sender.
 type PerStreamSenders =
     Arc<tokio::sync::Mutex<HashMap<u8, HashMap<ClientId, mpsc::Sender<Bytes>>>>>;
 
+/// Per-client kick channels: client_id → sender of the reason to close its
+/// connection with.
+type ClientKicks = Arc<tokio::sync::Mutex<HashMap<ClientId, oneshot::Sender<String>>>>;
+
+/// Closes `client`'s connection with `reason`, if it is still connected.
+async fn kick_client(client_kicks: &ClientKicks, client: ClientId, reason: String) {
+    match client_kicks.lock().await.remove(&client) {
+        Some(kick) => {
+            let _ = kick.send(reason);
+        }
+        None => {
+            log::warn!(
+                "Client {} not found for kick (already disconnected)",
+                client.0
+            );
+        }
+    }
+}
+
+/// Kicks a client whose frame on stream `tag` (0 for the control stream)
+/// was longer than its limit, then waits for its tasks to be cancelled so
+/// the kick is not taken for an ordinary disconnect.
+async fn kick_oversized(
+    client_kicks: &ClientKicks,
+    client_cancel: &CancellationToken,
+    client: ClientId,
+    tag: u8,
+    error: std::io::Error,
+) {
+    log::warn!(
+        "Client {} sent an oversized frame on stream {}: {}",
+        client.0,
+        tag,
+        error
+    );
+    kick_client(client_kicks, client, "oversized frame".into()).await;
+    client_cancel.cancelled().await;
+}
+
+/// Returns `true` if `sender`'s client is falling behind on the channel.
+fn is_backed_up(sender: &mpsc::Sender<Bytes>) -> bool {
+    sender.capacity() < BACKPRESSURE_FREE_SLOTS
+}
+
 /// Cancel all per-stream writer tasks for a client and remove their senders from the shared map.
 /// Call this on every early-return path after stream setup to prevent task leaks and stale senders.
 async fn cleanup_client_stream_writers(
@@ -41,21 +114,56 @@ async fn cleanup_client_stream_writers(
     }
 }
 
+/// Sends [`ServerMessage::JoinDenied`] on the control stream, then closes the
+/// connection once the client hangs up or [`JOIN_DENIED_LINGER`] has passed.
+async fn deny_join(
+    connection: &Connection,
+    framed_write: &mut SendStream,
+    client_id: ClientId,
+    client_version: u32,
+    streams: Vec<String>,
+) {
+    let denial = ServerMessage::JoinDenied {
+        server_version: PROTOCOL_VERSION,
+        client_version,
+        streams,
+    };
+    match encode(&denial) {
+        Ok(bytes) => {
+            if let Err(e) = framed_write.send(Bytes::from(bytes)).await {
+                log::warn!("Failed to send JoinDenied to client {}: {}", client_id.0, e);
+            }
+        }
+        Err(e) => log::error!("Failed to encode JoinDenied: {}", e),
+    }
+    let _ = tokio::time::timeout(JOIN_DENIED_LINGER, connection.closed()).await;
+    connection.close(1, "join denied");
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn run_server(
-    port: u16,
+    bind: Bind,
     event_tx: mpsc::UnboundedSender<ServerEvent>,
     server_cmd_rx: mpsc::UnboundedReceiver<ServerCommand>,
     cancel_token: CancellationToken,
-    stream_defs: Vec<StreamDef>,
+    stream_defs: Vec<RegisteredStream>,
+    manifest: Vec<StreamManifestEntry>,
     stream_cmd_rx: mpsc::Receiver<(u8, StreamWriteCmd)>,
+    saturation: SharedSaturation,
+    limits: ClientFrameLimits,
+    decode_failures: Arc<DecodeFailures>,
 ) {
     if let Err(e) = run_server_inner(
-        port,
+        bind,
         &event_tx,
         server_cmd_rx,
         cancel_token,
         stream_defs,
+        manifest,
         stream_cmd_rx,
+        saturation,
+        limits,
+        decode_failures,
     )
     .await
     {
@@ -68,27 +176,48 @@ pub(crate) async fn run_server(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_server_inner(
-    port: u16,
+    bind: Bind,
     event_tx: &mpsc::UnboundedSender<ServerEvent>,
     mut server_cmd_rx: mpsc::UnboundedReceiver<ServerCommand>,
     cancel_token: CancellationToken,
-    stream_defs: Vec<StreamDef>,
+    stream_defs: Vec<RegisteredStream>,
+    manifest: Vec<StreamManifestEntry>,
     mut stream_cmd_rx: mpsc::Receiver<(u8, StreamWriteCmd)>,
+    saturation: SharedSaturation,
+    limits: ClientFrameLimits,
+    decode_failures: Arc<DecodeFailures>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let server_config = config::build_server_config()?;
-    let addr: SocketAddr = ([0, 0, 0, 0], port).into();
-    let endpoint = quinn::Endpoint::server(server_config, addr)?;
+    let listener = Listener::bind(&bind)?;
+    // Port 0 asks the OS for a free port; report the one actually bound.
+    let port = listener.port()?;
+
+    let endpoints = listener.local_addrs()?;
+    let fingerprint = listener.fingerprint();
 
-    log::info!("Server listening on {addr}");
-    if let Err(e) = event_tx.send(ServerEvent::HostingStarted { port }) {
+    match (&bind, fingerprint) {
+        (Bind::Quic { .. }, Some(fingerprint)) => {
+            log::info!("Server listening on {endpoints:?} (certificate {fingerprint})")
+        }
+        (Bind::Quic { .. }, None) => log::info!("Server listening on {endpoints:?}"),
+        (Bind::Loopback(_), _) => log::info!("Server listening on loopback"),
+    }
+    if let Err(e) = event_tx.send(ServerEvent::HostingStarted {
+        port,
+        endpoints,
+        fingerprint,
+    }) {
         log::error!("Failed to send HostingStarted event: {}", e);
     }
 
     // Shared state for client ID assignment and per-client control-stream write channels.
+    // The first loopback client is the local player and gets `ClientId::LOCAL`.
     let next_client_id = Arc::new(AtomicU64::new(1));
+    let local_id_taken = Arc::new(AtomicBool::new(false));
     let client_senders: Arc<tokio::sync::Mutex<HashMap<ClientId, mpsc::Sender<Bytes>>>> =
         Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+    let client_kicks: ClientKicks = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
 
     // Per-stream, per-client write channels for registered module streams.
     let per_stream_senders: PerStreamSenders = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
@@ -100,6 +229,7 @@ async fn run_server_inner(
         let mut ps = per_stream_senders.lock().await;
         for def in stream_defs
             .iter()
+            .map(|s| &s.def)
             .filter(|d| d.direction == StreamDirection::ServerToClient)
         {
             ps.entry(def.tag).or_default();
@@ -109,7 +239,7 @@ async fn run_server_inner(
     // Number of server→client module streams (determines expected_streams in Welcome).
     let server_to_client_count_usize = stream_defs
         .iter()
-        .filter(|d| d.direction == StreamDirection::ServerToClient)
+        .filter(|s| s.def.direction == StreamDirection::ServerToClient)
         .count();
     let server_to_client_count = u8::try_from(server_to_client_count_usize).map_err(|_| {
         format!(
@@ -118,31 +248,119 @@ async fn run_server_inner(
         )
     })?;
 
+    // Longest frame clients may send per client→server stream; see `limits.rs`.
+    let max_frames: Arc<HashMap<u8, usize>> = Arc::new(
+        stream_defs
+            .iter()
+            .filter(|s| s.def.direction == StreamDirection::ClientToServer)
+            .map(|s| {
+                let max = s.budget.max_frame_bytes.unwrap_or(limits.max_frame_bytes);
+                (s.def.tag, max)
+            })
+            .collect(),
+    );
+
+    // Replication budget for module streams; see `budget.rs`.  Each tick
+    // resets the per-client allowances and releases deferred frames.
+    let mut budget = BudgetScheduler::new(&stream_defs, CLIENT_BYTES_PER_TICK);
+    // Round trips measured by the connection tasks, pacing congested clients.
+    let (rtt_tx, mut rtt_rx) = mpsc::unbounded_channel::<(ClientId, Duration)>();
+    let mut budget_tick = tokio::time::interval(Duration::from_secs_f32(NETWORK_UPDATE_INTERVAL));
+    budget_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    // Graceful shutdown: once announced, new connections are refused and
+    // `close_timer` fires when the announced delay is up.
+    let mut shutdown_message: Option<String> = None;
+    let close_timer = tokio::time::sleep(Duration::ZERO);
+    tokio::pin!(close_timer);
+
     loop {
         tokio::select! {
             _ = cancel_token.cancelled() => {
                 log::info!("Server cancellation requested");
                 break;
             }
-            incoming = endpoint.accept() => {
+            _ = &mut close_timer, if shutdown_message.is_some() => {
+                log::info!("Shutdown delay elapsed; closing all connections");
+                break;
+            }
+            Some((client, rtt)) = rtt_rx.recv() => {
+                budget.note_rtt(client, rtt);
+            }
+            _ = budget_tick.tick() => {
+                let ps = per_stream_senders.lock().await;
+                budget.retain_clients(|client| ps.values().any(|m| m.contains_key(&client)));
+                // Hold deferred frames back from clients that are already
+                // behind rather than overflowing their channels.
+                for (client, sender) in ps.values().flatten() {
+                    if is_backed_up(sender) {
+                        budget.note_backpressure(*client);
+                    }
+                }
+                for (client, failures) in decode_failures.offenders(&limits) {
+                    log::warn!(
+                        "Client {} sent {} malformed frames within {:?}",
+                        client.0, failures, limits.decode_failure_window
+                    );
+                    kick_client(&client_kicks, client, "too many malformed frames".into()).await;
+                }
+                let (stats, released) = budget.next_tick();
+                *saturation.lock().unwrap_or_else(|e| e.into_inner()) = stats;
+                for (client, tag, data) in released {
+                    match ps.get(&tag).and_then(|m| m.get(&client)) {
+                        Some(sender) => {
+                            if let Err(e) = sender.try_send(data) {
+                                log::error!(
+                                    "Failed to route deferred stream {} data to client {}: {}",
+                                    tag, client.0, e
+                                );
+                            }
+                        }
+                        // Client left while frames were waiting.
+                        None => budget.remove_client(client),
+                    }
+                }
+            }
+            incoming = listener.accept() => {
                 let Some(incoming) = incoming else {
-                    log::info!("Server endpoint closed");
+                    log::info!("Server listener closed");
                     break;
                 };
+                if shutdown_message.is_some() {
+                    log::info!(
+                        "Refusing connection from {} (shutting down)",
+                        incoming.remote_address()
+                    );
+                    incoming.refuse();
+                    continue;
+                }
 
                 let event_tx = event_tx.clone();
+                let rtt_tx = rtt_tx.clone();
                 let cancel_token_clone = cancel_token.clone();
                 let next_client_id = next_client_id.clone();
+                let local_id_taken = local_id_taken.clone();
                 let client_senders = client_senders.clone();
+                let client_kicks = client_kicks.clone();
                 let per_stream_senders = per_stream_senders.clone();
                 let stream_defs_conn = stream_defs.clone();
+                let manifest_conn = manifest.clone();
+                let max_frames = max_frames.clone();
+                let decode_failures = decode_failures.clone();
 
                 tokio::spawn(async move {
-                    match incoming.await {
+                    match incoming.accept().await {
                         Ok(connection) => {
                             let addr = connection.remote_address();
-                            let client_id =
-                                ClientId(next_client_id.fetch_add(1, Ordering::SeqCst));
+                            // Held until this task ends, freeing the local id for a rejoin.
+                            let local_claim = (connection.is_loopback()
+                                && !local_id_taken.swap(true, Ordering::SeqCst))
+                            .then(|| LocalIdClaim(local_id_taken.clone()));
+                            let client_id = if local_claim.is_some() {
+                                ClientId::LOCAL
+                            } else {
+                                ClientId(next_client_id.fetch_add(1, Ordering::SeqCst))
+                            };
                             log::info!(
                                 "Client connected from {} with ClientId {}",
                                 addr,
@@ -152,22 +370,21 @@ async fn run_server_inner(
                             // Create per-client cancellation token to coordinate shutdown.
                             let client_cancel = CancellationToken::new();
 
-                            // Open registered server→client unidirectional streams.
-                            // Each stream: write 1-byte tag, then LengthDelimitedCodec frames.
-                            // Writing the tag byte makes the stream visible to accept_uni() on
-                            // the client (Quinn only delivers a stream once it has data).
+                            // Open registered server→client unidirectional streams, each
+                            // announced with its routing tag.
                             let mut stream_write_handles: JoinSet<()> = JoinSet::new();
                             let mut stream_setup_ok = true;
 
                             for def in stream_defs_conn
                                 .iter()
+                                .map(|s| &s.def)
                                 .filter(|d| d.direction == StreamDirection::ServerToClient)
                             {
                                 let open_result_opt = tokio::select! {
-                                    result = connection.open_uni() => Some(result),
+                                    result = connection.open_uni(def.tag) => Some(result),
                                     _ = cancel_token_clone.cancelled() => {
                                         log::info!("Server shutdown while opening stream {} for client {}", def.tag, client_id.0);
-                                        connection.close(0u32.into(), b"server shutdown");
+                                        connection.close(0, "server shutdown");
                                         stream_setup_ok = false;
                                         None
                                     }
@@ -182,7 +399,7 @@ async fn run_server_inner(
                                     None => break,
                                 };
 
-                                let mut send = match open_result {
+                                let mut framed_write = match open_result {
                                     Ok(s) => s,
                                     Err(e) => {
                                         log::error!(
@@ -194,18 +411,6 @@ async fn run_server_inner(
                                     }
                                 };
 
-                                // Write routing tag byte.
-                                if let Err(e) = send.write_all(&[def.tag]).await {
-                                    log::error!(
-                                        "Failed to write tag byte for stream {} client {}: {}",
-                                        def.tag, client_id.0, e
-                                    );
-                                    stream_setup_ok = false;
-                                    break;
-                                }
-
-                                let mut framed_write =
-                                    FramedWrite::new(send, LengthDelimitedCodec::new());
                                 let (write_tx, mut write_rx) =
                                     mpsc::channel::<Bytes>(PER_PEER_BUFFER_SIZE);
 
@@ -265,7 +470,7 @@ async fn run_server_inner(
                                 result = connection.accept_bi() => result,
                                 _ = cancel_token_clone.cancelled() => {
                                     log::info!("Server shutdown while waiting for bi-directional stream from client {}", client_id.0);
-                                    connection.close(0u32.into(), b"server shutdown");
+                                    connection.close(0, "server shutdown");
                                     cleanup_client_stream_writers(&client_cancel, &mut stream_write_handles, &per_stream_senders, client_id).await;
                                     return;
                                 }
@@ -276,8 +481,11 @@ async fn run_server_inner(
                                 }
                             };
 
-                            let (send_stream, recv_stream) = match accept_result {
-                                Ok(s) => s,
+                            let (mut framed_write, mut framed_read) = match accept_result {
+                                Ok((write, mut read)) => {
+                                    read.limit_frames(limits.max_frame_bytes);
+                                    (write, read)
+                                }
                                 Err(e) => {
                                     log::error!(
                                         "Failed to accept bi-directional stream from {}: {}",
@@ -288,16 +496,11 @@ async fn run_server_inner(
                                 }
                             };
 
-                            let mut framed_read =
-                                FramedRead::new(recv_stream, LengthDelimitedCodec::new());
-                            let mut framed_write =
-                                FramedWrite::new(send_stream, LengthDelimitedCodec::new());
-
                             // Read Hello
                             let hello_frame = tokio::select! {
                                 frame = framed_read.next() => frame,
                                 _ = cancel_token_clone.cancelled() => {
-                                    connection.close(0u32.into(), b"server shutdown");
+                                    connection.close(0, "server shutdown");
                                     cleanup_client_stream_writers(&client_cancel, &mut stream_write_handles, &per_stream_senders, client_id).await;
                                     return;
                                 }
@@ -308,23 +511,40 @@ async fn run_server_inner(
                                 }
                             };
 
-                            let hello_name = match hello_frame {
-                                Some(Ok(bytes)) => match decode::<ClientMessage>(&bytes) {
-                                    Ok(ClientMessage::Hello { name }) => name,
+                            let (hello_version, hello_name, hello_spectator, hello_streams) = match hello_frame {
+                                Some(Ok(bytes)) => match decode_from_client::<ClientMessage>(&bytes) {
+                                    Ok(ClientMessage::Hello {
+                                        protocol_version,
+                                        name,
+                                        spectator,
+                                        streams,
+                                    }) => (protocol_version, name, spectator, streams),
                                     Ok(other) => {
                                         log::error!(
                                             "Expected Hello from client {}, got {:?} — closing connection",
                                             client_id.0, other
                                         );
-                                        connection.close(0u32.into(), b"protocol violation: expected Hello");
+                                        connection.close(0, "protocol violation: expected Hello");
                                         cleanup_client_stream_writers(&client_cancel, &mut stream_write_handles, &per_stream_senders, client_id).await;
                                         return;
                                     }
                                     Err(e) => {
-                                        log::error!(
-                                            "Failed to decode Hello from client {}: {}",
-                                            client_id.0, e
-                                        );
+                                        // A Hello from another protocol version may not
+                                        // decode; its leading version field still does.
+                                        if let Some(version) = hello_protocol_version(&bytes)
+                                            && version != PROTOCOL_VERSION
+                                        {
+                                            log::warn!(
+                                                "Client {} speaks protocol {}, server speaks {} — refusing",
+                                                client_id.0, version, PROTOCOL_VERSION
+                                            );
+                                            deny_join(&connection, &mut framed_write, client_id, version, Vec::new()).await;
+                                        } else {
+                                            log::error!(
+                                                "Failed to decode Hello from client {}: {}",
+                                                client_id.0, e
+                                            );
+                                        }
                                         cleanup_client_stream_writers(&client_cancel, &mut stream_write_handles, &per_stream_senders, client_id).await;
                                         return;
                                     }
@@ -347,10 +567,34 @@ async fn run_server_inner(
                                 }
                             };
 
+                            // Refuse clients on another protocol version or whose stream
+                            // table differs from ours: their frames would be decoded as
+                            // the wrong message types.
+                            let mismatches = if hello_version == PROTOCOL_VERSION {
+                                manifest_mismatches(&manifest_conn, &hello_streams)
+                            } else {
+                                Vec::new()
+                            };
+                            if hello_version != PROTOCOL_VERSION || !mismatches.is_empty() {
+                                log::warn!(
+                                    "Refusing client {} ({:?}): protocol {} (server {}){}{}",
+                                    client_id.0,
+                                    hello_name,
+                                    hello_version,
+                                    PROTOCOL_VERSION,
+                                    if mismatches.is_empty() { "" } else { ", incompatible streams:\n  " },
+                                    mismatches.join("\n  ")
+                                );
+                                deny_join(&connection, &mut framed_write, client_id, hello_version, mismatches).await;
+                                cleanup_client_stream_writers(&client_cancel, &mut stream_write_handles, &per_stream_senders, client_id).await;
+                                return;
+                            }
+
                             // Send Welcome
                             let welcome = ServerMessage::Welcome {
                                 client_id,
                                 expected_streams: server_to_client_count,
+                                protocol_version: PROTOCOL_VERSION,
                             };
                             match encode(&welcome) {
                                 Ok(bytes) => {
@@ -381,10 +625,16 @@ async fn run_server_inner(
                             // Register control-stream write channel.
                             let (write_tx, mut write_rx) =
                                 mpsc::channel::<Bytes>(PER_PEER_BUFFER_SIZE);
+                            let pong_tx = write_tx.clone();
                             {
                                 let mut senders = client_senders.lock().await;
                                 senders.insert(client_id, write_tx);
                             }
+                            let (kick_tx, mut kick_rx) = oneshot::channel::<String>();
+                            client_kicks.lock().await.insert(client_id, kick_tx);
+
+                            // Pings on this connection are stamped relative to now.
+                            let epoch = Instant::now();
 
                             // Emit ClientConnected so game code can react.
                             if let Err(err) = event_tx.send(ServerEvent::ClientConnected {
@@ -401,7 +651,12 @@ async fn run_server_inner(
                             // Forward Hello to game code for domain-specific handling.
                             if let Err(err) = event_tx.send(ServerEvent::ClientMessageReceived {
                                 from: client_id,
-                                message: ClientMessage::Hello { name: hello_name },
+                                message: ClientMessage::Hello {
+                                    protocol_version: hello_version,
+                                    name: hello_name,
+                                    spectator: hello_spectator,
+                                    streams: hello_streams,
+                                },
                             }) {
                                 log::error!(
                                     "Failed to forward Hello event: {}",
@@ -417,6 +672,7 @@ async fn run_server_inner(
                             let cancel_token_uni = cancel_token_clone.clone();
                             let client_cancel_uni = client_cancel.clone();
                             let connection_uni = connection.clone();
+                            let client_kicks_uni = client_kicks.clone();
                             let mut uni_read_handles: JoinSet<()> = JoinSet::new();
                             let uni_accept_handle = tokio::spawn(async move {
                                 loop {
@@ -431,17 +687,7 @@ async fn run_server_inner(
                                         }
                                         result = connection_uni.accept_uni() => {
                                             match result {
-                                                Ok(mut recv) => {
-                                                    // Read 1-byte routing tag.
-                                                    let mut tag_buf = [0u8; 1];
-                                                    if let Err(e) = recv.read_exact(&mut tag_buf).await {
-                                                        log::error!(
-                                                            "Server: failed to read stream tag byte from client {}: {}",
-                                                            client_id.0, e
-                                                        );
-                                                        continue;
-                                                    }
-                                                    let tag = tag_buf[0];
+                                                Ok((tag, mut recv)) => {
                                                     if tag == 0 {
                                                         log::warn!(
                                                             "Server: ignoring client→server uni stream with reserved tag=0 from client {}",
@@ -453,14 +699,16 @@ async fn run_server_inner(
                                                         "Server: accepted client→server uni stream tag={} from client {}",
                                                         tag, client_id.0
                                                     );
+                                                    recv.limit_frames(
+                                                        max_frames.get(&tag).copied().unwrap_or(limits.max_frame_bytes),
+                                                    );
 
                                                     // Spawn an independent read loop for this stream.
                                                     let frame_tx = event_tx_uni.clone();
                                                     let cancel_global = cancel_token_uni.clone();
                                                     let cancel_client = client_cancel_uni.clone();
+                                                    let client_kicks = client_kicks_uni.clone();
                                                     uni_read_handles.spawn(async move {
-                                                        let mut framed =
-                                                            FramedRead::new(recv, LengthDelimitedCodec::new());
                                                         loop {
                                                             tokio::select! {
                                                                 _ = cancel_global.cancelled() => {
@@ -471,7 +719,7 @@ async fn run_server_inner(
                                                                     log::debug!("Server: client→server stream tag={} reader cancelled (client shutdown)", tag);
                                                                     break;
                                                                 }
-                                                                frame = framed.next() => {
+                                                                frame = recv.next() => {
                                                                     match frame {
                                                                         Some(Ok(bytes)) => {
                                                                             log::debug!(
@@ -482,10 +730,14 @@ async fn run_server_inner(
                                                                                 ServerEvent::ClientStreamFrame {
                                                                                     from: client_id,
                                                                                     tag,
-                                                                                    data: bytes.freeze(),
+                                                                                    data: bytes,
                                                                                 },
                                                                             );
                                                                         }
+                                                                        Some(Err(e)) if e.kind() == std::io::ErrorKind::InvalidData => {
+                                                                            kick_oversized(&client_kicks, &cancel_client, client_id, tag, e).await;
+                                                                            break;
+                                                                        }
                                                                         Some(Err(e)) => {
                                                                             log::error!(
                                                                                 "Server: client→server stream tag={} read error from client {}: {}",
@@ -525,6 +777,8 @@ async fn run_server_inner(
                             let per_stream_senders_cleanup = per_stream_senders.clone();
 
                             let client_cancel_read = client_cancel.clone();
+                            let client_kicks_read = client_kicks.clone();
+                            let decode_failures_read = decode_failures.clone();
                             let mut read_handle = tokio::spawn(async move {
                                 loop {
                                     tokio::select! {
@@ -539,7 +793,29 @@ async fn run_server_inner(
                                         frame = framed_read.next() => {
                                             match frame {
                                                 Some(Ok(bytes)) => {
-                                                    match decode::<ClientMessage>(&bytes) {
+                                                    match decode_from_client::<ClientMessage>(&bytes) {
+                                                        Ok(ClientMessage::Ping { stamp }) => {
+                                                            // Answer right away so the client's
+                                                            // RTT excludes our frame time.
+                                                            match encode(&ServerMessage::Pong { stamp }) {
+                                                                Ok(bytes) => {
+                                                                    if let Err(e) = pong_tx.try_send(Bytes::from(bytes)) {
+                                                                        log::debug!("Failed to queue Pong for client {}: {}", client_id.0, e);
+                                                                    }
+                                                                }
+                                                                Err(e) => {
+                                                                    log::error!("Failed to encode Pong for client {}: {}", client_id.0, e);
+                                                                }
+                                                            }
+                                                        }
+                                                        Ok(ClientMessage::Pong { stamp }) => {
+                                                            let rtt = quality::round_trip(epoch, stamp);
+                                                            let _ = rtt_tx.send((client_id, rtt));
+                                                            let _ = event_tx_read.send(ServerEvent::RttMeasured {
+                                                                id: client_id,
+                                                                rtt,
+                                                            });
+                                                        }
                                                         Ok(message) => {
                                                             if let Err(err) = event_tx_read.send(ServerEvent::ClientMessageReceived {
                                                                 from: client_id,
@@ -550,9 +826,14 @@ async fn run_server_inner(
                                                         }
                                                         Err(e) => {
                                                             log::error!("Failed to decode message from client {}: {}", client_id.0, e);
+                                                            decode_failures_read.record(client_id);
                                                         }
                                                     }
                                                 }
+                                                Some(Err(e)) if e.kind() == std::io::ErrorKind::InvalidData => {
+                                                    kick_oversized(&client_kicks_read, &client_cancel_read, client_id, 0, e).await;
+                                                    break;
+                                                }
                                                 Some(Err(e)) => {
                                                     log::error!("Stream error from client {}: {}", client_id.0, e);
                                                     break;
@@ -569,6 +850,7 @@ async fn run_server_inner(
 
                             let client_cancel_write = client_cancel.clone();
                             let mut write_handle = tokio::spawn(async move {
+                                let mut ping_timer = quality::ping_timer();
                                 loop {
                                     tokio::select! {
                                         _ = cancel_token_write.cancelled() => {
@@ -579,6 +861,22 @@ async fn run_server_inner(
                                             log::debug!("Write loop cancelled for client {} (client shutdown)", client_id.0);
                                             break;
                                         }
+                                        _ = ping_timer.tick() => {
+                                            let ping = ServerMessage::Ping {
+                                                stamp: quality::ping_stamp(epoch),
+                                            };
+                                            match encode(&ping) {
+                                                Ok(bytes) => {
+                                                    if let Err(e) = framed_write.send(Bytes::from(bytes)).await {
+                                                        log::error!("Failed to ping client {} (stream error): {}", client_id.0, e);
+                                                        break;
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    log::error!("Failed to encode Ping for client {}: {}", client_id.0, e);
+                                                }
+                                            }
+                                        }
                                         bytes = write_rx.recv() => {
                                             match bytes {
                                                 Some(bytes) => {
@@ -608,6 +906,10 @@ async fn run_server_inner(
                                 _ = connection.closed() => {
                                     log::info!("Connection closed for client {}", client_id.0);
                                 }
+                                Ok(reason) = &mut kick_rx => {
+                                    log::info!("Kicking client {}: {}", client_id.0, reason);
+                                    connection.close(2, &reason);
+                                }
                             }
 
                             // Cancel all per-client tasks.
@@ -633,6 +935,8 @@ async fn run_server_inner(
                                 let mut senders = client_senders_cleanup.lock().await;
                                 senders.remove(&client_id);
                             }
+                            client_kicks.lock().await.remove(&client_id);
+                            decode_failures.forget(client_id);
                             // Cleanup: remove per-stream senders for this client.
                             {
                                 let mut ps = per_stream_senders_cleanup.lock().await;
@@ -691,6 +995,42 @@ async fn run_server_inner(
                             }
                         }
                     }
+                    Some(ServerCommand::Shutdown { message, delay }) => {
+                        if shutdown_message.is_some() {
+                            log::warn!("Server is already shutting down");
+                            continue;
+                        }
+                        log::info!("Shutting down in {:?}: {}", delay, message);
+                        let notice = ServerMessage::Shutdown {
+                            message: message.clone(),
+                            delay_secs: delay.as_secs_f32(),
+                        };
+                        match encode(&notice) {
+                            Ok(bytes) => {
+                                let bytes = Bytes::from(bytes);
+                                let senders = client_senders.lock().await;
+                                for (client_id, sender) in senders.iter() {
+                                    if let Err(e) = sender.try_send(bytes.clone()) {
+                                        log::error!("Failed to announce shutdown to client {}: {}", client_id.0, e);
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                log::error!("Failed to encode shutdown notice: {}", e);
+                            }
+                        }
+                        if let Err(e) = event_tx.send(ServerEvent::ShutdownStarted {
+                            message: message.clone(),
+                            delay,
+                        }) {
+                            log::error!("Failed to send ShutdownStarted event: {}", e);
+                        }
+                        close_timer.as_mut().reset(tokio::time::Instant::now() + delay);
+                        shutdown_message = Some(message);
+                    }
+                    Some(ServerCommand::Kick { client, reason }) => {
+                        kick_client(&client_kicks, client, reason).await;
+                    }
                     None => {
                         log::info!("Server command channel closed");
                         break;
@@ -699,10 +1039,16 @@ async fn run_server_inner(
             }
             stream_cmd = stream_cmd_rx.recv() => {
                 match stream_cmd {
-                    Some((tag, StreamWriteCmd::SendTo { client, data })) => {
+                    Some((tag, StreamWriteCmd::SendTo { client, data, deferrable })) => {
                         let ps = per_stream_senders.lock().await;
                         if let Some(stream_map) = ps.get(&tag) {
                             if let Some(sender) = stream_map.get(&client) {
+                                if is_backed_up(sender) {
+                                    budget.note_backpressure(client);
+                                }
+                                let Some(data) = budget.admit(client, tag, data, deferrable) else {
+                                    continue;
+                                };
                                 if let Err(e) = sender.try_send(data) {
                                     log::error!(
                                         "Failed to route stream {} data to client {}: {}",
@@ -722,11 +1068,19 @@ async fn run_server_inner(
                             );
                         }
                     }
-                    Some((tag, StreamWriteCmd::Broadcast { data })) => {
+                    Some((tag, StreamWriteCmd::Broadcast { data, deferrable })) => {
                         let ps = per_stream_senders.lock().await;
                         if let Some(stream_map) = ps.get(&tag) {
                             for (client_id, sender) in stream_map.iter() {
-                                if let Err(e) = sender.try_send(data.clone()) {
+                                if is_backed_up(sender) {
+                                    budget.note_backpressure(*client_id);
+                                }
+                                let Some(data) =
+                                    budget.admit(*client_id, tag, data.clone(), deferrable)
+                                else {
+                                    continue;
+                                };
+                                if let Err(e) = sender.try_send(data) {
                                     log::error!(
                                         "Failed to broadcast stream {} to client {}: {}",
                                         tag, client_id.0, e
@@ -749,6 +1103,17 @@ async fn run_server_inner(
         }
     }
 
+    // Close every connection explicitly so clients learn why at once instead
+    // of waiting for the idle timeout.
+    let reason = shutdown_message.as_deref().unwrap_or("Server stopped");
+    listener.close(reason);
+    if tokio::time::timeout(CLOSE_LINGER, listener.wait_idle())
+        .await
+        .is_err()
+    {
+        log::debug!("Some clients did not acknowledge the close within {CLOSE_LINGER:?}");
+    }
+
     if let Err(err) = event_tx.send(ServerEvent::HostingStopped) {
         log::error!("Failed to send ServerEvent::HostingStopped: {}", err);
     }
@@ -762,6 +1127,7 @@ mod tests {
     use futures_util::stream::StreamExt;
     use tokio_util::codec::{FramedRead, FramedWrite, LengthDelimitedCodec};
 
+    use crate::certs::ServerIdentity;
     use crate::config;
 
     /// Stream tag bytes matching the plan's server→client stream table.
@@ -779,11 +1145,12 @@ mod tests {
     /// byte and independently framed with `LengthDelimitedCodec`, and that
     /// `StreamReady` sentinels arrive correctly on all streams regardless of
     /// the order in which the client calls `accept_uni()`.
-    #[cfg(debug_assertions)]
     #[tokio::test]
     async fn test_quinn_multi_stream_spike() {
-        let server_config = config::build_server_config().expect("server config");
-        let client_config = config::build_client_config().expect("client config");
+        let identity = ServerIdentity::generate().expect("server identity");
+        let server_config = config::build_server_config(&identity).expect("server config");
+        let client_config = config::build_client_config(config::SkipServerVerification::new())
+            .expect("client config");
 
         let server_addr: std::net::SocketAddr = ([127, 0, 0, 1], 0).into();
         let server_endpoint =
@@ -902,11 +1269,12 @@ mod tests {
     /// each prefixed with a routing tag byte and independently framed with
     /// `LengthDelimitedCodec`, and that the server correctly routes frames from
     /// each stream.
-    #[cfg(debug_assertions)]
     #[tokio::test]
     async fn test_quinn_client_to_server_multi_stream_spike() {
-        let server_config = config::build_server_config().expect("server config");
-        let client_config = config::build_client_config().expect("client config");
+        let identity = ServerIdentity::generate().expect("server identity");
+        let server_config = config::build_server_config(&identity).expect("server config");
+        let client_config = config::build_client_config(config::SkipServerVerification::new())
+            .expect("client config");
 
         /// Client→server stream tags.
         const TAG_INPUT: u8 = 4;
@@ -0,0 +1,65 @@
+//! Client side of a graceful server shutdown.
+//!
+//! When the server runs [`NetCommand::ShutdownServer`](crate::NetCommand::ShutdownServer)
+//! every client receives [`ServerMessage::Shutdown`] and keeps playing until
+//! the server closes the connection.  Meanwhile [`ShutdownNotice`] holds the
+//! announcement for the UI to show.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{ClientEvent, NetworkReceive, NetworkSet, ServerMessage};
+
+/// Client-side: the server announced that it is shutting down.
+///
+/// Inserted on [`ServerMessage::Shutdown`] and removed on connect and
+/// disconnect.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ShutdownNotice {
+    pub message: String,
+    /// `Time::elapsed` at which the server will close the connection.
+    pub closes_at: Duration,
+}
+
+impl ShutdownNotice {
+    /// Time left at `now` (a `Time::elapsed`) before the server closes the
+    /// connection.
+    pub fn remaining(&self, now: Duration) -> Duration {
+        self.closes_at.saturating_sub(now)
+    }
+}
+
+pub(crate) fn register_shutdown_systems(app: &mut App) {
+    app.add_systems(
+        NetworkReceive,
+        receive_shutdown_notice.after(NetworkSet::Drain),
+    );
+}
+
+/// Client system: keeps [`ShutdownNotice`] in step with the server's
+/// announcements.
+fn receive_shutdown_notice(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut events: MessageReader<ClientEvent>,
+) {
+    for event in events.read() {
+        match event {
+            ClientEvent::Connected | ClientEvent::Disconnected { .. } => {
+                commands.remove_resource::<ShutdownNotice>();
+            }
+            ClientEvent::ServerMessageReceived(ServerMessage::Shutdown {
+                message,
+                delay_secs,
+            }) => {
+                commands.insert_resource(ShutdownNotice {
+                    message: message.clone(),
+                    closes_at: time.elapsed()
+                        + Duration::try_from_secs_f32(*delay_secs).unwrap_or_default(),
+                });
+            }
+            _ => {}
+        }
+    }
+}
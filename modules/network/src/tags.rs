@@ -0,0 +1,196 @@
+//! Stream tag allocation and the stream table exchanged in the handshake.
+//!
+//! Tags `1..=127` are fixed tags that modules pick by hand and declare as
+//! constants (tiles = 1, atmospherics = 2, …).  Tags `128..=255` are derived
+//! from a name with [`stream_tag`], so a module can claim a tag without
+//! checking every other module's constants.  Both kinds go through
+//! [`StreamRegistry::try_register`](crate::StreamRegistry::try_register),
+//! which reports a clash as a [`StreamRegistryError`] naming both streams.
+//!
+//! Client and server build their registries independently, so the client
+//! sends its [`StreamManifestEntry`] table in `Hello`.  The server compares it
+//! with its own via [`manifest_mismatches`] and refuses clients whose streams
+//! would decode as something else.
+
+use crate::StreamDirection;
+use crate::protocol::StreamManifestEntry;
+
+/// First tag handed out by [`stream_tag`]; everything below is for fixed tags.
+pub const FIRST_NAMED_TAG: u8 = 128;
+
+/// FNV-1a over `name` followed by `salt`, folded into `128..=255`.
+const fn derive_tag(name: &str, salt: u8) -> u8 {
+    let bytes = name.as_bytes();
+    let mut hash: u32 = 0x811c_9dc5;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    hash ^= salt as u32;
+    hash = hash.wrapping_mul(0x0100_0193);
+    FIRST_NAMED_TAG + (hash % (256 - FIRST_NAMED_TAG as u32)) as u8
+}
+
+/// Tag for the stream called `name`, the same on every build and platform.
+///
+/// Distinct names can still land on the same tag; registration then fails
+/// with [`StreamRegistryError::TagConflict`] and one of the streams needs a
+/// different name.
+pub const fn stream_tag(name: &str) -> u8 {
+    derive_tag(name, 0)
+}
+
+/// Request and response tags of the RPC called `name`; see
+/// [`RpcDef::named`](crate::RpcDef::named).
+pub(crate) const fn rpc_tags(name: &str) -> (u8, u8) {
+    (derive_tag(name, 1), derive_tag(name, 2))
+}
+
+/// Error returned by [`StreamRegistry::try_register`](crate::StreamRegistry::try_register).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamRegistryError {
+    /// Tag 0 belongs to the control stream.
+    ReservedTag { name: &'static str },
+    /// Another stream already owns the tag.
+    TagConflict {
+        tag: u8,
+        existing: &'static str,
+        requested: &'static str,
+    },
+}
+
+impl std::fmt::Display for StreamRegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamRegistryError::ReservedTag { name } => write!(
+                f,
+                "stream '{name}' uses tag 0, which is reserved for the control stream"
+            ),
+            StreamRegistryError::TagConflict {
+                tag,
+                existing,
+                requested,
+            } => write!(
+                f,
+                "stream tag {tag} is claimed by both '{existing}' and '{requested}'"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StreamRegistryError {}
+
+/// Fingerprint of the message type `T` carried by a stream.
+///
+/// Hashes the type's path, so it catches a stream whose message type was
+/// swapped or renamed.  Field changes inside the same type do not alter it;
+/// those must bump [`PROTOCOL_VERSION`](crate::PROTOCOL_VERSION).
+pub(crate) fn schema_hash<T>() -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in std::any::type_name::<T>().bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Returns one line per stream that differs between the `local` and
+/// `remote` tables; empty when they match.
+pub(crate) fn manifest_mismatches(
+    local: &[StreamManifestEntry],
+    remote: &[StreamManifestEntry],
+) -> Vec<String> {
+    let describe = |e: &StreamManifestEntry| {
+        let arrow = match e.direction {
+            StreamDirection::ServerToClient => "server→client",
+            StreamDirection::ClientToServer => "client→server",
+        };
+        format!("'{}' ({arrow})", e.name)
+    };
+    let mut out = Vec::new();
+    for ours in local {
+        let Some(theirs) = remote.iter().find(|theirs| theirs.tag == ours.tag) else {
+            out.push(format!(
+                "tag {}: {} missing on client",
+                ours.tag,
+                describe(ours)
+            ));
+            continue;
+        };
+        if theirs.name != ours.name || theirs.direction != ours.direction {
+            out.push(format!(
+                "tag {}: server has {}, client has {}",
+                ours.tag,
+                describe(ours),
+                describe(theirs)
+            ));
+        } else if theirs.schema != ours.schema {
+            out.push(format!(
+                "tag {}: {} carries a different message type",
+                ours.tag,
+                describe(ours)
+            ));
+        }
+    }
+    for theirs in remote {
+        if !local.iter().any(|ours| ours.tag == theirs.tag) {
+            out.push(format!(
+                "tag {}: {} missing on server",
+                theirs.tag,
+                describe(theirs)
+            ));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tag: u8, name: &str, direction: StreamDirection) -> StreamManifestEntry {
+        StreamManifestEntry {
+            tag,
+            name: name.to_string(),
+            direction,
+            schema: 0,
+        }
+    }
+
+    #[test]
+    fn named_tags_are_stable_and_in_the_named_range() {
+        // Pinned: changing the hash changes every named tag on the wire.
+        const TAG: u8 = stream_tag("lighting");
+        assert_eq!(TAG, 235);
+        assert_eq!(rpc_tags("lighting"), (216, 145));
+        assert!(stream_tag("") >= FIRST_NAMED_TAG);
+    }
+
+    #[test]
+    fn manifest_mismatches_lists_each_differing_stream() {
+        let server = [
+            entry(1, "tiles", StreamDirection::ServerToClient),
+            entry(2, "atmos", StreamDirection::ServerToClient),
+            entry(4, "interactions", StreamDirection::ClientToServer),
+        ];
+        assert!(manifest_mismatches(&server, &server).is_empty());
+
+        let client = [
+            StreamManifestEntry {
+                schema: schema_hash::<u32>(),
+                ..entry(1, "tiles", StreamDirection::ServerToClient)
+            },
+            entry(2, "power", StreamDirection::ServerToClient),
+            entry(5, "items", StreamDirection::ServerToClient),
+        ];
+        let mismatches = manifest_mismatches(&server, &client);
+        assert_eq!(mismatches.len(), 4, "{mismatches:?}");
+        assert!(mismatches[0].contains("'tiles'") && mismatches[0].contains("message type"));
+        assert!(mismatches[1].contains("'atmos'") && mismatches[1].contains("'power'"));
+        assert!(mismatches[2].contains("'interactions'"));
+        assert!(mismatches[3].contains("'items'"));
+        assert_ne!(schema_hash::<u32>(), schema_hash::<u64>());
+    }
+}
@@ -0,0 +1,545 @@
+//! In-process server and client for tests that need the real network path.
+//!
+//! [`NetHarness`] builds two headless Bevy apps, one hosting on an
+//! OS-assigned port and one connecting to it over localhost QUIC, and steps
+//! them side by side.  [`NetHarness::loopback`] connects them through an
+//! in-process [`Loopback`] instead, without sockets or certificates.  Everything between [`StreamSender`](crate::StreamSender)
+//! and [`StreamReader`](crate::StreamReader) is the production code:
+//! handshake, stream table check, budgets, framing and the sync barrier.
+//!
+//! Compiled for this crate's own tests and, for other crates, behind the
+//! `testing` feature:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! network = { path = "../network", features = ["testing"] }
+//! ```
+//!
+//! ```ignore
+//! let mut net = NetHarness::new(|app, _side| {
+//!     app.add_plugins(TilesPlugin::in_state(HarnessState::InGame));
+//! });
+//! net.connect();
+//! net.wait_for_initial_state_done();
+//! net.run_until("tile grid replicated", |net| {
+//!     net.client.world().contains_resource::<TileGrid>()
+//! });
+//! ```
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+
+use crate::{
+    CertFingerprint, Client, ClientId, ClientMessage, Headless, KnownServers, Loopback,
+    NetClientSender, NetCommand, NetworkPlugin, NetworkReceive, NetworkSet, SendError, ServerEvent,
+    local_address,
+};
+
+/// How long [`NetHarness::run_until`] steps the apps before failing the test.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause between steps, so the network tasks get to run.
+const STEP: Duration = Duration::from_millis(1);
+
+/// App state used by both harness apps.
+///
+/// Module plugins that take a state are given [`HarnessState::InGame`] (and
+/// [`HarnessState::Loading`] where they ask for one).
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HarnessState {
+    #[default]
+    Offline,
+    Loading,
+    InGame,
+}
+
+/// Which of the two harness apps is being configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Server,
+    Client,
+}
+
+/// First endpoint and certificate fingerprint reported by
+/// [`ServerEvent::HostingStarted`] in the server app; no endpoint on a
+/// loopback.
+#[derive(Resource, Default)]
+struct HostedPort(Option<(Option<SocketAddr>, Option<CertFingerprint>)>);
+
+fn record_hosted_port(mut events: MessageReader<ServerEvent>, mut hosted: ResMut<HostedPort>) {
+    for event in events.read() {
+        if let ServerEvent::HostingStarted {
+            endpoints,
+            fingerprint,
+            ..
+        } = event
+        {
+            hosted.0 = Some((endpoints.first().copied(), *fingerprint));
+        }
+    }
+}
+
+/// A dedicated server and one client in the same process, talking over
+/// localhost QUIC or a [`Loopback`].
+pub struct NetHarness {
+    /// Headless server app; enters [`HarnessState::InGame`] once hosting.
+    pub server: App,
+    /// Client app; enters [`HarnessState::InGame`] once initial sync is done.
+    pub client: App,
+    /// Set when the apps talk through a loopback instead of QUIC.
+    loopback: Option<Loopback>,
+}
+
+impl NetHarness {
+    /// Builds both apps with `MinimalPlugins`, states and [`NetworkPlugin`],
+    /// then hands each to `configure` to add the module plugins under test.
+    ///
+    /// The server app has [`Headless`] inserted before `configure` runs, as
+    /// on a dedicated server.
+    pub fn new(configure: impl Fn(&mut App, Side)) -> Self {
+        let build = |side| {
+            let mut app = App::new();
+            app.add_plugins((MinimalPlugins, StatesPlugin));
+            if side == Side::Server {
+                app.insert_resource(Headless);
+            }
+            app.add_plugins(NetworkPlugin {
+                loading: HarnessState::Loading,
+                in_game: HarnessState::InGame,
+                disconnected: HarnessState::Offline,
+            });
+            app.init_state::<HarnessState>();
+            configure(&mut app, side);
+            app
+        };
+
+        let mut server = build(Side::Server);
+        server.init_resource::<HostedPort>();
+        server.add_systems(NetworkReceive, record_hosted_port.after(NetworkSet::Drain));
+
+        Self {
+            server,
+            client: build(Side::Client),
+            loopback: None,
+        }
+    }
+
+    /// Like [`NetHarness::new`], with the apps talking through an in-process
+    /// [`Loopback`] instead of QUIC.
+    pub fn loopback(configure: impl Fn(&mut App, Side)) -> Self {
+        Self {
+            loopback: Some(Loopback::new()),
+            ..Self::new(configure)
+        }
+    }
+
+    /// Hosts on an OS-assigned port (or the loopback), connects the client
+    /// to it and waits for the server's `Welcome`.  Returns the client's id.
+    pub fn connect(&mut self) -> ClientId {
+        let host = match &self.loopback {
+            Some(loopback) => NetCommand::HostLoopback(loopback.clone()),
+            None => NetCommand::Host {
+                port: 0,
+                addresses: vec![Ipv4Addr::LOCALHOST.into()],
+            },
+        };
+        self.server.world_mut().write_message(host);
+        self.run_until("server to start hosting", |net| {
+            net.server.world().resource::<HostedPort>().0.is_some()
+        });
+
+        let (endpoint, fingerprint) = self
+            .server
+            .world()
+            .resource::<HostedPort>()
+            .0
+            .expect("hosting started");
+        let name = "harness".to_string();
+        let connect = match &self.loopback {
+            Some(loopback) => NetCommand::ConnectLoopback {
+                loopback: loopback.clone(),
+                name,
+                spectator: false,
+            },
+            None => {
+                let addr = local_address(endpoint.expect("QUIC endpoint"));
+                if let Some(fingerprint) = fingerprint {
+                    self.client
+                        .world()
+                        .resource::<KnownServers>()
+                        .allow(addr, fingerprint);
+                }
+                NetCommand::Connect {
+                    addr,
+                    name,
+                    spectator: false,
+                }
+            }
+        };
+        self.client.world_mut().write_message(connect);
+        self.run_until("client to be welcomed", |net| net.client_id().is_some());
+        self.client_id().expect("welcomed")
+    }
+
+    /// Steps until the client has received `InitialStateDone` and every
+    /// stream's `StreamReady`, i.e. has entered [`HarnessState::InGame`].
+    pub fn wait_for_initial_state_done(&mut self) {
+        self.run_until("client to finish initial sync", |net| {
+            *net.client.world().resource::<State<HarnessState>>().get() == HarnessState::InGame
+        });
+    }
+
+    /// Id the server assigned to the client, once welcomed.
+    pub fn client_id(&self) -> Option<ClientId> {
+        self.client
+            .world()
+            .get_resource::<Client>()
+            .and_then(|client| client.local_id)
+    }
+
+    /// Sends a movement input from the client on the control stream, as the
+    /// input module does.
+    pub fn send_input(&mut self, direction: [f32; 3]) -> Result<(), SendError> {
+        let sender = self
+            .client
+            .world()
+            .get_resource::<NetClientSender>()
+            .ok_or(SendError::Closed)?;
+        sender.send(&ClientMessage::Input {
+            direction,
+            sprint: false,
+            crouch: false,
+        })
+    }
+
+    /// Runs one frame of the server, then one of the client.
+    pub fn update(&mut self) {
+        self.server.update();
+        self.client.update();
+    }
+
+    /// Steps both apps until `done` returns `true`.
+    ///
+    /// # Panics
+    ///
+    /// After ten seconds without `done` holding, naming `what` was awaited.
+    pub fn run_until(&mut self, what: &str, mut done: impl FnMut(&mut Self) -> bool) {
+        let start = Instant::now();
+        loop {
+            self.update();
+            if done(self) {
+                return;
+            }
+            assert!(
+                start.elapsed() < TIMEOUT,
+                "timed out after {TIMEOUT:?} waiting for {what}"
+            );
+            std::thread::sleep(STEP);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use wincode::{SchemaRead, SchemaWrite};
+
+    use super::*;
+    use crate::{
+        ClientEvent, ClientFrameLimits, ClientInputReceived, ConnectionQuality, ModuleReadySent,
+        NetServerSender, PlayerEvent, ShutdownNotice, StreamBudget, StreamDef, StreamDirection,
+        StreamReader, StreamRegistry, StreamSender, stream_tag,
+    };
+
+    #[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+    struct Greeting {
+        text: String,
+    }
+
+    #[derive(Resource, Default)]
+    struct Received {
+        greetings: Vec<String>,
+        inputs: Vec<(ClientId, [f32; 3])>,
+    }
+
+    /// Minimal module: one server→client stream whose initial burst is a
+    /// single greeting.
+    fn add_greeting_stream(app: &mut App, _side: Side) {
+        let (sender, reader): (StreamSender<Greeting>, StreamReader<Greeting>) = app
+            .world_mut()
+            .resource_mut::<StreamRegistry>()
+            .register(StreamDef {
+                tag: stream_tag("harness_greeting"),
+                name: "harness_greeting",
+                direction: StreamDirection::ServerToClient,
+            });
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+        app.init_resource::<Received>();
+        app.add_systems(NetworkReceive, (greet_on_join, record_received));
+    }
+
+    fn greet_on_join(
+        mut players: MessageReader<PlayerEvent>,
+        sender: Res<StreamSender<Greeting>>,
+        mut ready: MessageWriter<ModuleReadySent>,
+    ) {
+        for event in players.read() {
+            if let PlayerEvent::Joined { id, name, .. } = event {
+                let greeting = Greeting {
+                    text: format!("hello {name}"),
+                };
+                sender.send_to(*id, &greeting).expect("send greeting");
+                sender.send_stream_ready_to(*id).expect("send ready");
+                ready.write(ModuleReadySent { client: *id });
+            }
+        }
+    }
+
+    fn record_received(
+        mut reader: ResMut<StreamReader<Greeting>>,
+        mut inputs: MessageReader<ClientInputReceived>,
+        mut received: ResMut<Received>,
+    ) {
+        received
+            .greetings
+            .extend(reader.drain().map(|greeting| greeting.text));
+        received
+            .inputs
+            .extend(inputs.read().map(|input| (input.from, input.direction)));
+    }
+
+    #[test]
+    fn client_syncs_and_inputs_reach_the_server_over_quic() {
+        let mut net = NetHarness::new(add_greeting_stream);
+
+        let id = net.connect();
+        net.wait_for_initial_state_done();
+        assert_eq!(
+            net.client.world().resource::<Received>().greetings,
+            ["hello harness"]
+        );
+
+        net.send_input([1.0, 0.0, -1.0]).expect("client connected");
+        net.run_until("input to reach the server", |net| {
+            !net.server.world().resource::<Received>().inputs.is_empty()
+        });
+        assert_eq!(
+            net.server.world().resource::<Received>().inputs,
+            [(id, [1.0, 0.0, -1.0])]
+        );
+    }
+
+    #[test]
+    fn client_syncs_and_inputs_reach_the_server_over_loopback() {
+        let mut net = NetHarness::loopback(add_greeting_stream);
+
+        let id = net.connect();
+        assert_eq!(id, ClientId::LOCAL);
+        net.wait_for_initial_state_done();
+        assert_eq!(
+            net.client.world().resource::<Received>().greetings,
+            ["hello harness"]
+        );
+
+        net.send_input([0.0, 0.0, 1.0]).expect("client connected");
+        net.run_until("input to reach the server", |net| {
+            !net.server.world().resource::<Received>().inputs.is_empty()
+        });
+        assert_eq!(
+            net.server.world().resource::<Received>().inputs,
+            [(id, [0.0, 0.0, 1.0])]
+        );
+    }
+
+    #[test]
+    fn pings_measure_rtt_on_both_ends() {
+        let mut net = NetHarness::new(|_, _| {});
+
+        let id = net.connect();
+        net.run_until("both ends to measure a round trip", |net| {
+            let client = net.client.world().resource::<ConnectionQuality>();
+            let server = net.server.world().resource::<ConnectionQuality>();
+            client.server.is_some() && server.clients.contains_key(&id)
+        });
+    }
+
+    #[test]
+    fn shutdown_is_announced_before_connections_close() {
+        let mut net = NetHarness::new(|_, _| {});
+        net.connect();
+
+        net.server
+            .world_mut()
+            .write_message(NetCommand::ShutdownServer {
+                message: "maintenance".into(),
+                delay: Duration::from_millis(200),
+            });
+        net.run_until("client to receive the shutdown notice", |net| {
+            net.client.world().contains_resource::<ShutdownNotice>()
+        });
+        assert_eq!(
+            net.client.world().resource::<ShutdownNotice>().message,
+            "maintenance"
+        );
+
+        net.run_until("server to close the connection", |net| {
+            !net.client.world().contains_resource::<Client>()
+                && !net.server.world().contains_resource::<NetServerSender>()
+        });
+        assert!(!net.client.world().contains_resource::<ShutdownNotice>());
+    }
+
+    #[derive(Resource, Default)]
+    struct DisconnectReason(Option<String>);
+
+    fn record_disconnect(
+        mut events: MessageReader<ClientEvent>,
+        mut reason: ResMut<DisconnectReason>,
+    ) {
+        for event in events.read() {
+            if let ClientEvent::Disconnected { reason: why } = event {
+                reason.0 = Some(why.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn kicked_clients_are_told_why() {
+        let mut net = NetHarness::new(|app, side| {
+            if side == Side::Client {
+                app.init_resource::<DisconnectReason>();
+                app.add_systems(NetworkReceive, record_disconnect.after(NetworkSet::Drain));
+            }
+        });
+        let id = net.connect();
+
+        net.server
+            .world()
+            .resource::<NetServerSender>()
+            .kick(id, "kicked by vote");
+        net.run_until("client to be disconnected", |net| {
+            net.client
+                .world()
+                .resource::<DisconnectReason>()
+                .0
+                .is_some()
+        });
+        let reason = net.client.world().resource::<DisconnectReason>().0.clone();
+        assert!(
+            reason
+                .as_deref()
+                .is_some_and(|r| r.contains("kicked by vote")),
+            "unexpected reason {reason:?}"
+        );
+    }
+
+    /// A client→server stream of greetings whose frames may be 64 bytes
+    /// long, drained by the server every frame.  The client records why it
+    /// was disconnected.
+    fn add_upload_stream(app: &mut App, side: Side) {
+        let (sender, reader): (StreamSender<Greeting>, StreamReader<Greeting>) = app
+            .world_mut()
+            .resource_mut::<StreamRegistry>()
+            .register_with_budget(
+                StreamDef {
+                    tag: stream_tag("harness_upload"),
+                    name: "harness_upload",
+                    direction: StreamDirection::ClientToServer,
+                },
+                StreamBudget::UNLIMITED.with_max_frame(64),
+            );
+        match side {
+            Side::Server => {
+                app.insert_resource(ClientFrameLimits {
+                    max_decode_failures: 3,
+                    ..Default::default()
+                });
+                app.insert_resource(reader);
+                app.add_systems(
+                    NetworkReceive,
+                    |mut reader: ResMut<StreamReader<Greeting>>| {
+                        reader.drain_from_client().for_each(drop);
+                    },
+                );
+            }
+            Side::Client => {
+                app.insert_resource(sender);
+                app.init_resource::<DisconnectReason>();
+                app.add_systems(NetworkReceive, record_disconnect.after(NetworkSet::Drain));
+            }
+        }
+    }
+
+    fn wait_for_kick(net: &mut NetHarness) -> String {
+        net.run_until("client to be kicked", |net| {
+            net.client
+                .world()
+                .resource::<DisconnectReason>()
+                .0
+                .is_some()
+        });
+        net.client
+            .world()
+            .resource::<DisconnectReason>()
+            .0
+            .clone()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn clients_sending_oversized_frames_are_kicked() {
+        let mut net = NetHarness::new(add_upload_stream);
+        net.connect();
+        net.run_until("upload stream to open", |net| {
+            net.client
+                .world()
+                .resource::<StreamSender<Greeting>>()
+                .send(&Greeting { text: "hi".into() })
+                .is_ok()
+        });
+
+        net.client
+            .world()
+            .resource::<StreamSender<Greeting>>()
+            .send(&Greeting {
+                text: "x".repeat(100),
+            })
+            .expect("oversized frame");
+
+        let reason = wait_for_kick(&mut net);
+        assert!(
+            reason.contains("oversized frame"),
+            "unexpected reason {reason:?}"
+        );
+    }
+
+    #[test]
+    fn clients_sending_repeated_junk_are_kicked() {
+        let mut net = NetHarness::loopback(add_upload_stream);
+        net.connect();
+
+        // A string length far beyond the frame.
+        let junk = Bytes::from_static(&[0xff; 8]);
+        net.run_until("upload stream to open", |net| {
+            net.client
+                .world()
+                .resource::<StreamSender<Greeting>>()
+                .send_raw_client(junk.clone())
+                .is_ok()
+        });
+        for _ in 0..3 {
+            net.client
+                .world()
+                .resource::<StreamSender<Greeting>>()
+                .send_raw_client(junk.clone())
+                .expect("junk frame");
+        }
+
+        let reason = wait_for_kick(&mut net);
+        assert!(reason.contains("malformed"), "unexpected reason {reason:?}");
+    }
+}
@@ -0,0 +1,91 @@
+//! Fixed-rate simulation tick for server-authoritative gameplay.
+//!
+//! [`SimulationTick`] is a schedule that runs inside Bevy's fixed-timestep
+//! loop, right after `FixedUpdate` and before physics steps in
+//! `FixedPostUpdate`.  Gameplay that must advance at the same rate no matter
+//! how fast a machine renders (applying client requests, timed actions,
+//! routing input) belongs here rather than in `Update`.
+//!
+//! The tick rate is the rate of `Time<Fixed>`, shared with physics so every
+//! tick is followed by exactly one physics step.  Binaries set it from the
+//! `simulation.tick_rate` config key with `Time::<Fixed>::from_hz`; it
+//! defaults to [`DEFAULT_TICK_RATE`].
+//!
+//! Inside the schedule `Res<Time>` reports the fixed timestep, and messages
+//! written in `NetworkReceive` stay readable until a tick has run, even on
+//! frames where none does.
+//!
+//! [`CurrentTick`] counts ticks since startup, for stamping replicated state.
+
+use bevy::app::FixedMainScheduleOrder;
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+
+/// Default tick rate in Hz; matches Bevy's default `Time<Fixed>` rate.
+pub const DEFAULT_TICK_RATE: f64 = 64.0;
+
+/// Schedule for fixed-rate gameplay, run once per fixed timestep between
+/// `FixedUpdate` and `FixedPostUpdate`.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationTick;
+
+/// Number of the simulation tick being run, or of the last one outside
+/// [`SimulationTick`].  Starts at 0 and advances by one before each tick.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CurrentTick(u64);
+
+impl CurrentTick {
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+pub(crate) fn register_tick_schedule(app: &mut App) {
+    app.init_schedule(SimulationTick);
+    app.world_mut()
+        .resource_mut::<FixedMainScheduleOrder>()
+        .insert_after(FixedUpdate, SimulationTick);
+    app.init_resource::<CurrentTick>();
+    app.add_systems(FixedFirst, advance_tick);
+}
+
+fn advance_tick(mut tick: ResMut<CurrentTick>) {
+    tick.0 += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Seen(Vec<u64>);
+
+    fn record(tick: Res<CurrentTick>, mut seen: ResMut<Seen>) {
+        seen.0.push(tick.get());
+    }
+
+    #[test]
+    fn ticks_follow_the_fixed_rate_not_the_frame_rate() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        register_tick_schedule(&mut app);
+        app.insert_resource(Time::<Fixed>::from_hz(10.0));
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            40,
+        )));
+        app.init_resource::<Seen>();
+        app.add_systems(SimulationTick, record);
+
+        // 40 ms frames at 10 Hz: one tick every two or three frames.
+        for _ in 0..12 {
+            app.update();
+        }
+
+        assert_eq!(app.world().resource::<Seen>().0, [1, 2, 3, 4]);
+        assert_eq!(app.world().resource::<CurrentTick>().get(), 4);
+    }
+}
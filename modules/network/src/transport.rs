@@ -0,0 +1,832 @@
+//! The connection layer under the protocol.
+//!
+//! The server and client tasks speak the protocol over a [`Connection`]: a
+//! bidirectional control stream opened by the client, plus one
+//! unidirectional stream per registered module stream, announced with its
+//! routing tag.  Streams carry whole frames.  Two backends provide them:
+//!
+//! * **QUIC** (`quinn`): UDP sockets, TLS, and length-delimited frames.  A
+//!   server may listen on several sockets, by default one per address
+//!   family.
+//! * **Loopback**: in-process channels handed out by a [`Loopback`].  Frames
+//!   move as [`Bytes`] without being copied, framed or encrypted, so tests
+//!   and single-player games run the full protocol without sockets or
+//!   certificates.
+
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use bevy::log;
+use bytes::{Bytes, BytesMut};
+use futures_util::future;
+use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
+use rustls::client::danger::ServerCertVerifier;
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::codec::{FramedRead, FramedWrite, LengthDelimitedCodec};
+use tokio_util::sync::CancellationToken;
+
+use crate::certs::{CertFingerprint, KnownServers, PinnedServerVerification, ServerIdentity};
+use crate::config;
+
+/// Address reported for peers connected through a [`Loopback`].
+pub const LOOPBACK_ADDR: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+
+/// Address a client on this machine dials to reach a server listening on
+/// `endpoint`: a wildcard address stands for the loopback address of its
+/// family.
+pub fn local_address(endpoint: SocketAddr) -> SocketAddr {
+    let ip = match endpoint.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    SocketAddr::new(ip, endpoint.port())
+}
+
+/// Frames a loopback stream buffers before its writer waits, standing in
+/// for QUIC flow control.
+const LOOPBACK_STREAM_BUFFER: usize = 256;
+
+/// In-process rendezvous between one server and its clients.  Host on it
+/// with [`NetCommand::HostLoopback`](crate::NetCommand::HostLoopback) and
+/// join with [`NetCommand::ConnectLoopback`](crate::NetCommand::ConnectLoopback).
+/// Clones share the rendezvous, so the server and the client may run in
+/// different apps or threads.
+#[derive(Clone, Default)]
+pub struct Loopback(Arc<LoopbackShared>);
+
+struct LoopbackShared {
+    /// Whether a server is hosting on the rendezvous.
+    hosting: AtomicBool,
+    connect_tx: mpsc::UnboundedSender<PendingConnection>,
+    connect_rx: tokio::sync::Mutex<mpsc::UnboundedReceiver<PendingConnection>>,
+}
+
+impl Default for LoopbackShared {
+    fn default() -> Self {
+        let (connect_tx, connect_rx) = mpsc::unbounded_channel();
+        Self {
+            hosting: AtomicBool::new(false),
+            connect_tx,
+            connect_rx: tokio::sync::Mutex::new(connect_rx),
+        }
+    }
+}
+
+/// The server half of a connection a client is waiting to have accepted.
+pub(crate) struct PendingConnection {
+    connection: LoopbackConnection,
+    accepted: oneshot::Sender<()>,
+}
+
+impl Loopback {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a server is hosting on this loopback.
+    pub fn is_hosting(&self) -> bool {
+        self.0.hosting.load(Ordering::SeqCst)
+    }
+}
+
+impl fmt::Debug for Loopback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Loopback")
+            .field("hosting", &self.is_hosting())
+            .finish()
+    }
+}
+
+/// Where a server accepts connections.
+#[derive(Debug, Clone)]
+pub(crate) enum Bind {
+    /// A UDP port on each of `addresses`; 0 lets the OS pick one, shared by
+    /// every address.  No addresses means every IPv4 interface, plus every
+    /// IPv6 interface where the host supports it.  Without an identity the
+    /// server presents a freshly generated certificate.
+    Quic {
+        port: u16,
+        addresses: Vec<IpAddr>,
+        identity: Option<ServerIdentity>,
+    },
+    Loopback(Loopback),
+}
+
+/// What a client connects to.
+#[derive(Debug, Clone)]
+pub(crate) enum Dial {
+    /// A QUIC server, whose certificate must be trusted by `known`; `None`
+    /// accepts any.
+    Quic {
+        addr: SocketAddr,
+        known: Option<KnownServers>,
+    },
+    Loopback(Loopback),
+}
+
+impl fmt::Display for Dial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dial::Quic { addr, .. } => write!(f, "{addr}"),
+            Dial::Loopback(_) => f.write_str("loopback"),
+        }
+    }
+}
+
+impl Dial {
+    /// Connects to the server.  A QUIC server whose certificate is not
+    /// trusted fails with an [`UntrustedCertificate`] error.
+    pub(crate) async fn connect(&self) -> Result<Connection, Box<dyn std::error::Error>> {
+        match self {
+            Dial::Quic { addr, known } => {
+                let bind_addr = match addr {
+                    SocketAddr::V4(_) => ([0, 0, 0, 0], 0u16).into(),
+                    SocketAddr::V6(_) => ([0u16; 8], 0u16).into(),
+                };
+                let pinned = known
+                    .clone()
+                    .map(|known| PinnedServerVerification::new(*addr, known));
+                let verifier: Arc<dyn ServerCertVerifier> = match &pinned {
+                    Some(pinned) => pinned.clone(),
+                    None => config::SkipServerVerification::new(),
+                };
+                let mut endpoint = quinn::Endpoint::client(bind_addr)?;
+                endpoint.set_default_client_config(config::build_client_config(verifier)?);
+                // The endpoint keeps running while the connection is open.
+                match endpoint.connect(*addr, "localhost")?.await {
+                    Ok(connection) => Ok(Connection::Quic(connection)),
+                    Err(e) => match pinned.and_then(|pinned| pinned.take_refused()) {
+                        Some(refused) => Err(Box::new(refused)),
+                        None => Err(e.into()),
+                    },
+                }
+            }
+            Dial::Loopback(loopback) => {
+                if !loopback.is_hosting() {
+                    return Err("no server is hosting on this loopback".into());
+                }
+                let (client, server) = LoopbackConnection::pair();
+                let (accepted_tx, accepted_rx) = oneshot::channel();
+                loopback
+                    .0
+                    .connect_tx
+                    .send(PendingConnection {
+                        connection: server,
+                        accepted: accepted_tx,
+                    })
+                    .map_err(|_| "loopback closed")?;
+                accepted_rx
+                    .await
+                    .map_err(|_| "server refused the connection")?;
+                Ok(Connection::Loopback(client))
+            }
+        }
+    }
+}
+
+/// A server's source of incoming connections.
+pub(crate) enum Listener {
+    /// One endpoint per bound address, never empty.
+    Quic {
+        endpoints: Vec<quinn::Endpoint>,
+        fingerprint: CertFingerprint,
+    },
+    Loopback(LoopbackListener),
+}
+
+pub(crate) struct LoopbackListener {
+    loopback: Loopback,
+    /// Close state of every connection handed out, to close them all when
+    /// hosting stops.
+    connections: Mutex<Vec<Arc<CloseState>>>,
+}
+
+impl Drop for LoopbackListener {
+    fn drop(&mut self) {
+        self.loopback.0.hosting.store(false, Ordering::SeqCst);
+        // Refuse clients still waiting to be accepted.
+        if let Ok(mut pending) = self.loopback.0.connect_rx.try_lock() {
+            while pending.try_recv().is_ok() {}
+        }
+    }
+}
+
+impl Listener {
+    pub(crate) fn bind(bind: &Bind) -> Result<Self, Box<dyn std::error::Error>> {
+        match bind {
+            Bind::Quic {
+                port,
+                addresses,
+                identity,
+            } => {
+                let identity = match identity {
+                    Some(identity) => identity.clone(),
+                    None => ServerIdentity::generate()?,
+                };
+                let server_config = config::build_server_config(&identity)?;
+                Ok(Listener::Quic {
+                    endpoints: bind_quic_endpoints(*port, addresses, &server_config)?,
+                    fingerprint: identity.fingerprint(),
+                })
+            }
+            Bind::Loopback(loopback) => {
+                if loopback.0.hosting.swap(true, Ordering::SeqCst) {
+                    return Err("a server is already hosting on this loopback".into());
+                }
+                Ok(Listener::Loopback(LoopbackListener {
+                    loopback: loopback.clone(),
+                    connections: Mutex::new(Vec::new()),
+                }))
+            }
+        }
+    }
+
+    /// Port clients reach the server on; 0 for a loopback.
+    pub(crate) fn port(&self) -> io::Result<u16> {
+        match self {
+            Listener::Quic { endpoints, .. } => Ok(endpoints[0].local_addr()?.port()),
+            Listener::Loopback(_) => Ok(0),
+        }
+    }
+
+    /// Every address the server is bound to; none for a loopback.
+    pub(crate) fn local_addrs(&self) -> io::Result<Vec<SocketAddr>> {
+        match self {
+            Listener::Quic { endpoints, .. } => {
+                endpoints.iter().map(quinn::Endpoint::local_addr).collect()
+            }
+            Listener::Loopback(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Fingerprint of the certificate presented to clients; `None` for a
+    /// loopback, which has none.
+    pub(crate) fn fingerprint(&self) -> Option<CertFingerprint> {
+        match self {
+            Listener::Quic { fingerprint, .. } => Some(*fingerprint),
+            Listener::Loopback(_) => None,
+        }
+    }
+
+    /// The next connection attempt; `None` once the listener is closed.
+    pub(crate) async fn accept(&self) -> Option<Incoming> {
+        match self {
+            Listener::Quic { endpoints, .. } => {
+                // Accepting is cancel-safe: the other endpoints keep their
+                // pending connections for the next call.
+                let accepts = endpoints.iter().map(|endpoint| Box::pin(endpoint.accept()));
+                let (incoming, _, _) = future::select_all(accepts).await;
+                incoming.map(|incoming| Incoming::Quic(Box::new(incoming)))
+            }
+            Listener::Loopback(listener) => {
+                let pending = listener.loopback.0.connect_rx.lock().await.recv().await?;
+                let mut connections = listener
+                    .connections
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                connections.retain(|state| !state.token.is_cancelled());
+                connections.push(pending.connection.state.clone());
+                Some(Incoming::Loopback(pending))
+            }
+        }
+    }
+
+    /// Closes every connection with `reason`.
+    pub(crate) fn close(&self, reason: &str) {
+        match self {
+            Listener::Quic { endpoints, .. } => {
+                for endpoint in endpoints {
+                    endpoint.close(0u32.into(), reason.as_bytes());
+                }
+            }
+            Listener::Loopback(listener) => {
+                let connections = listener
+                    .connections
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                for state in connections.iter() {
+                    state.close(SERVER_SIDE, reason);
+                }
+            }
+        }
+    }
+
+    /// Waits for the peers to acknowledge [`close`](Self::close).
+    pub(crate) async fn wait_idle(&self) {
+        match self {
+            Listener::Quic { endpoints, .. } => {
+                future::join_all(endpoints.iter().map(quinn::Endpoint::wait_idle)).await;
+            }
+            // Frames already sent stay readable; nothing to acknowledge.
+            Listener::Loopback(_) => {}
+        }
+    }
+}
+
+/// Binds a QUIC endpoint on `port` at each of `addresses`, or at the IPv4
+/// and IPv6 wildcard addresses when there are none; see [`Bind::Quic`].
+fn bind_quic_endpoints(
+    port: u16,
+    addresses: &[IpAddr],
+    server_config: &quinn::ServerConfig,
+) -> io::Result<Vec<quinn::Endpoint>> {
+    let wildcard = addresses.is_empty();
+    let addresses = if wildcard {
+        vec![Ipv4Addr::UNSPECIFIED.into(), Ipv6Addr::UNSPECIFIED.into()]
+    } else {
+        addresses.to_vec()
+    };
+
+    let mut endpoints: Vec<quinn::Endpoint> = Vec::new();
+    for ip in addresses {
+        // Once the OS has picked a port, listen on it everywhere.
+        let port = match endpoints.first() {
+            Some(first) if port == 0 => first.local_addr()?.port(),
+            _ => port,
+        };
+        match bind_quic_endpoint(SocketAddr::new(ip, port), server_config.clone()) {
+            Ok(endpoint) => endpoints.push(endpoint),
+            // Hosts without IPv6 still serve IPv4 clients.
+            Err(e) if wildcard && ip.is_ipv6() => {
+                log::warn!("Not listening on IPv6: {e}");
+            }
+            Err(e) => return Err(io::Error::new(e.kind(), format!("binding {ip}: {e}"))),
+        }
+    }
+    Ok(endpoints)
+}
+
+fn bind_quic_endpoint(
+    addr: SocketAddr,
+    server_config: quinn::ServerConfig,
+) -> io::Result<quinn::Endpoint> {
+    let socket = socket2::Socket::new(
+        socket2::Domain::for_address(addr),
+        socket2::Type::DGRAM,
+        Some(socket2::Protocol::UDP),
+    )?;
+    // A dual-stack IPv6 socket would also claim the port for IPv4, clashing
+    // with the IPv4 endpoint next to it.
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    socket.bind(&addr.into())?;
+    let runtime =
+        quinn::default_runtime().ok_or_else(|| io::Error::other("no async runtime found"))?;
+    quinn::Endpoint::new(
+        quinn::EndpointConfig::default(),
+        Some(server_config),
+        socket.into(),
+        runtime,
+    )
+}
+
+/// A connection attempt the server has not accepted yet.
+pub(crate) enum Incoming {
+    Quic(Box<quinn::Incoming>),
+    Loopback(PendingConnection),
+}
+
+impl Incoming {
+    pub(crate) fn remote_address(&self) -> SocketAddr {
+        match self {
+            Incoming::Quic(incoming) => incoming.remote_address(),
+            Incoming::Loopback(_) => LOOPBACK_ADDR,
+        }
+    }
+
+    pub(crate) fn refuse(self) {
+        match self {
+            Incoming::Quic(incoming) => (*incoming).refuse(),
+            // Dropping `accepted` tells the client.
+            Incoming::Loopback(_) => {}
+        }
+    }
+
+    /// Completes the handshake.
+    pub(crate) async fn accept(self) -> io::Result<Connection> {
+        match self {
+            Incoming::Quic(incoming) => (*incoming)
+                .await
+                .map(Connection::Quic)
+                .map_err(io::Error::other),
+            Incoming::Loopback(pending) => {
+                pending.accepted.send(()).map_err(|()| {
+                    io::Error::new(io::ErrorKind::ConnectionAborted, "client gave up")
+                })?;
+                Ok(Connection::Loopback(pending.connection))
+            }
+        }
+    }
+}
+
+/// An established connection.  Clones refer to the same connection.
+#[derive(Clone)]
+pub(crate) enum Connection {
+    Quic(quinn::Connection),
+    Loopback(LoopbackConnection),
+}
+
+impl Connection {
+    /// Whether the peer is in this process, connected through a [`Loopback`].
+    pub(crate) fn is_loopback(&self) -> bool {
+        matches!(self, Connection::Loopback(_))
+    }
+
+    pub(crate) fn remote_address(&self) -> SocketAddr {
+        match self {
+            Connection::Quic(connection) => connection.remote_address(),
+            Connection::Loopback(_) => LOOPBACK_ADDR,
+        }
+    }
+
+    /// Opens a bidirectional stream; the peer sees it once the first frame
+    /// is sent.
+    pub(crate) async fn open_bi(&self) -> io::Result<(SendStream, RecvStream)> {
+        match self {
+            Connection::Quic(connection) => {
+                let (send, recv) = connection.open_bi().await.map_err(io::Error::other)?;
+                Ok((SendStream::quic(send), RecvStream::quic(recv)))
+            }
+            Connection::Loopback(connection) => {
+                let (send, peer_recv) = connection.stream();
+                let (peer_send, recv) = connection.stream();
+                connection
+                    .bi_tx
+                    .send((peer_send, peer_recv))
+                    .map_err(|_| connection.closed_error())?;
+                Ok((send, recv))
+            }
+        }
+    }
+
+    pub(crate) async fn accept_bi(&self) -> io::Result<(SendStream, RecvStream)> {
+        match self {
+            Connection::Quic(connection) => {
+                let (send, recv) = connection.accept_bi().await.map_err(io::Error::other)?;
+                Ok((SendStream::quic(send), RecvStream::quic(recv)))
+            }
+            Connection::Loopback(connection) => {
+                let mut bi_rx = connection.bi_rx.lock().await;
+                tokio::select! {
+                    biased;
+                    _ = connection.state.token.cancelled() => Err(connection.closed_error()),
+                    streams = bi_rx.recv() => streams.ok_or_else(|| connection.closed_error()),
+                }
+            }
+        }
+    }
+
+    /// Opens a unidirectional stream announced to the peer with `tag`.
+    pub(crate) async fn open_uni(&self, tag: u8) -> io::Result<SendStream> {
+        match self {
+            Connection::Quic(connection) => {
+                let mut send = connection.open_uni().await.map_err(io::Error::other)?;
+                // Writing the tag also makes the stream visible to the
+                // peer's accept_uni() (Quinn only delivers a stream once it
+                // has data).
+                send.write_all(&[tag]).await.map_err(io::Error::other)?;
+                Ok(SendStream::quic(send))
+            }
+            Connection::Loopback(connection) => {
+                let (send, recv) = connection.stream();
+                connection
+                    .uni_tx
+                    .send((tag, recv))
+                    .map_err(|_| connection.closed_error())?;
+                Ok(send)
+            }
+        }
+    }
+
+    /// The next unidirectional stream the peer opened, with its tag.
+    pub(crate) async fn accept_uni(&self) -> io::Result<(u8, RecvStream)> {
+        match self {
+            Connection::Quic(connection) => loop {
+                let mut recv = connection.accept_uni().await.map_err(io::Error::other)?;
+                let mut tag = [0u8; 1];
+                if let Err(e) = recv.read_exact(&mut tag).await {
+                    // Stream-local; keep accepting the others.
+                    log::error!("Failed to read stream tag byte: {}", e);
+                    continue;
+                }
+                return Ok((tag[0], RecvStream::quic(recv)));
+            },
+            Connection::Loopback(connection) => {
+                let mut uni_rx = connection.uni_rx.lock().await;
+                tokio::select! {
+                    biased;
+                    _ = connection.state.token.cancelled() => Err(connection.closed_error()),
+                    stream = uni_rx.recv() => stream.ok_or_else(|| connection.closed_error()),
+                }
+            }
+        }
+    }
+
+    /// Resolves once the connection is closed, by either side.
+    pub(crate) async fn closed(&self) {
+        match self {
+            Connection::Quic(connection) => {
+                connection.closed().await;
+            }
+            Connection::Loopback(connection) => connection.state.token.cancelled().await,
+        }
+    }
+
+    /// Closes the connection, telling the peer `reason`.
+    pub(crate) fn close(&self, code: u32, reason: &str) {
+        match self {
+            Connection::Quic(connection) => connection.close(code.into(), reason.as_bytes()),
+            Connection::Loopback(connection) => connection.state.close(connection.side, reason),
+        }
+    }
+
+    /// The reason the peer gave when it closed the connection, if it did
+    /// and gave one.
+    pub(crate) fn peer_close_reason(&self) -> Option<String> {
+        match self {
+            Connection::Quic(connection) => match connection.close_reason()? {
+                quinn::ConnectionError::ApplicationClosed(close) if !close.reason.is_empty() => {
+                    Some(String::from_utf8_lossy(&close.reason).into_owned())
+                }
+                _ => None,
+            },
+            Connection::Loopback(connection) => {
+                let closed_by = connection
+                    .state
+                    .closed_by
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                match &*closed_by {
+                    Some((side, reason)) if *side != connection.side && !reason.is_empty() => {
+                        Some(reason.clone())
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// Which end of a loopback connection: the client's or the server's.
+type Side = u8;
+const CLIENT_SIDE: Side = 0;
+const SERVER_SIDE: Side = 1;
+
+/// Shared by both halves of a loopback connection.
+#[derive(Default)]
+struct CloseState {
+    token: CancellationToken,
+    /// The side that closed the connection first, and why.
+    closed_by: Mutex<Option<(Side, String)>>,
+}
+
+impl CloseState {
+    fn close(&self, side: Side, reason: &str) {
+        let mut closed_by = self.closed_by.lock().unwrap_or_else(|e| e.into_inner());
+        if closed_by.is_none() {
+            *closed_by = Some((side, reason.to_owned()));
+        }
+        self.token.cancel();
+    }
+}
+
+type UniStreams = (u8, RecvStream);
+type BiStreams = (SendStream, RecvStream);
+
+/// One half of an in-process connection.
+#[derive(Clone)]
+pub(crate) struct LoopbackConnection {
+    side: Side,
+    state: Arc<CloseState>,
+    uni_tx: mpsc::UnboundedSender<UniStreams>,
+    uni_rx: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<UniStreams>>>,
+    bi_tx: mpsc::UnboundedSender<BiStreams>,
+    bi_rx: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<BiStreams>>>,
+}
+
+impl LoopbackConnection {
+    /// The client and server halves of a new connection.
+    fn pair() -> (Self, Self) {
+        let state = Arc::new(CloseState::default());
+        let (client_uni_tx, server_uni_rx) = mpsc::unbounded_channel();
+        let (server_uni_tx, client_uni_rx) = mpsc::unbounded_channel();
+        let (client_bi_tx, server_bi_rx) = mpsc::unbounded_channel();
+        let (server_bi_tx, client_bi_rx) = mpsc::unbounded_channel();
+        let half = |side, uni_tx, uni_rx, bi_tx, bi_rx| Self {
+            side,
+            state: state.clone(),
+            uni_tx,
+            uni_rx: Arc::new(tokio::sync::Mutex::new(uni_rx)),
+            bi_tx,
+            bi_rx: Arc::new(tokio::sync::Mutex::new(bi_rx)),
+        };
+        (
+            half(
+                CLIENT_SIDE,
+                client_uni_tx,
+                client_uni_rx,
+                client_bi_tx,
+                client_bi_rx,
+            ),
+            half(
+                SERVER_SIDE,
+                server_uni_tx,
+                server_uni_rx,
+                server_bi_tx,
+                server_bi_rx,
+            ),
+        )
+    }
+
+    /// Both ends of a new one-way stream on this connection.
+    fn stream(&self) -> (SendStream, RecvStream) {
+        let (tx, rx) = mpsc::channel(LOOPBACK_STREAM_BUFFER);
+        (
+            SendStream::Loopback {
+                tx,
+                closed: self.state.token.clone(),
+            },
+            RecvStream::Loopback {
+                rx,
+                closed: self.state.token.clone(),
+                max_frame: usize::MAX,
+            },
+        )
+    }
+
+    fn closed_error(&self) -> io::Error {
+        io::Error::new(io::ErrorKind::ConnectionAborted, "connection closed")
+    }
+}
+
+/// The writing end of a stream.
+pub(crate) enum SendStream {
+    Quic(FramedWrite<quinn::SendStream, LengthDelimitedCodec>),
+    Loopback {
+        tx: mpsc::Sender<Bytes>,
+        closed: CancellationToken,
+    },
+}
+
+impl SendStream {
+    fn quic(send: quinn::SendStream) -> Self {
+        SendStream::Quic(FramedWrite::new(send, LengthDelimitedCodec::new()))
+    }
+
+    /// Sends one frame, waiting while the peer is behind.
+    pub(crate) async fn send(&mut self, frame: Bytes) -> io::Result<()> {
+        match self {
+            SendStream::Quic(framed) => framed.send(frame).await,
+            SendStream::Loopback { tx, closed } => {
+                tokio::select! {
+                    biased;
+                    _ = closed.cancelled() => Err(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "connection closed",
+                    )),
+                    result = tx.send(frame) => result.map_err(|_| {
+                        io::Error::new(io::ErrorKind::BrokenPipe, "stream closed by peer")
+                    }),
+                }
+            }
+        }
+    }
+}
+
+/// The reading end of a stream.
+pub(crate) enum RecvStream {
+    Quic(FramedRead<quinn::RecvStream, LengthDelimitedCodec>),
+    Loopback {
+        rx: mpsc::Receiver<Bytes>,
+        closed: CancellationToken,
+        max_frame: usize,
+    },
+}
+
+impl RecvStream {
+    fn quic(recv: quinn::RecvStream) -> Self {
+        RecvStream::Quic(FramedRead::new(recv, LengthDelimitedCodec::new()))
+    }
+
+    /// Fails the stream with [`io::ErrorKind::InvalidData`] on the first
+    /// frame longer than `bytes`, without buffering it.
+    pub(crate) fn limit_frames(&mut self, bytes: usize) {
+        match self {
+            RecvStream::Quic(framed) => framed.decoder_mut().set_max_frame_length(bytes),
+            RecvStream::Loopback { max_frame, .. } => *max_frame = bytes,
+        }
+    }
+
+    /// The next frame; `None` once the stream or the connection is closed.
+    /// Loopback frames sent before the close are still delivered.
+    pub(crate) async fn next(&mut self) -> Option<io::Result<Bytes>> {
+        match self {
+            RecvStream::Quic(framed) => framed.next().await.map(|r| r.map(BytesMut::freeze)),
+            RecvStream::Loopback {
+                rx,
+                closed,
+                max_frame,
+            } => {
+                let frame = tokio::select! {
+                    biased;
+                    frame = rx.recv() => frame?,
+                    _ = closed.cancelled() => return None,
+                };
+                if frame.len() > *max_frame {
+                    return Some(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "frame of {} bytes exceeds the limit of {}",
+                            frame.len(),
+                            max_frame
+                        ),
+                    )));
+                }
+                Some(Ok(frame))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn loopback_carries_tagged_streams_and_close_reasons() {
+        let loopback = Loopback::new();
+        assert!(Dial::Loopback(loopback.clone()).connect().await.is_err());
+
+        let listener = Listener::bind(&Bind::Loopback(loopback.clone())).expect("bind");
+        assert!(Listener::bind(&Bind::Loopback(loopback.clone())).is_err());
+        let dial = Dial::Loopback(loopback.clone());
+        let client = tokio::spawn(async move { dial.connect().await.expect("connect") });
+        let incoming = listener.accept().await.expect("incoming");
+        assert_eq!(incoming.remote_address(), LOOPBACK_ADDR);
+        let server = incoming.accept().await.expect("accept");
+        let client = client.await.expect("client task");
+
+        let (mut client_send, mut client_recv) = client.open_bi().await.expect("open_bi");
+        let (mut server_send, mut server_recv) = server.accept_bi().await.expect("accept_bi");
+        client_send
+            .send(Bytes::from_static(b"hello"))
+            .await
+            .unwrap();
+        assert_eq!(server_recv.next().await.unwrap().unwrap(), "hello");
+        server_send
+            .send(Bytes::from_static(b"welcome"))
+            .await
+            .unwrap();
+        assert_eq!(client_recv.next().await.unwrap().unwrap(), "welcome");
+
+        let mut uni = server.open_uni(7).await.expect("open_uni");
+        uni.send(Bytes::from_static(b"frame")).await.unwrap();
+        let (tag, mut recv) = client.accept_uni().await.expect("accept_uni");
+        assert_eq!(tag, 7);
+
+        // Frames sent before the close still arrive; then the stream ends.
+        listener.close("maintenance");
+        client.closed().await;
+        assert_eq!(recv.next().await.unwrap().unwrap(), "frame");
+        assert!(recv.next().await.is_none());
+        assert!(uni.send(Bytes::from_static(b"late")).await.is_err());
+        assert_eq!(client.peer_close_reason().as_deref(), Some("maintenance"));
+        assert_eq!(server.peer_close_reason(), None);
+
+        drop(listener);
+        assert!(!loopback.is_hosting());
+    }
+
+    #[tokio::test]
+    async fn wildcard_quic_bind_serves_every_address_family_on_one_port() {
+        let bind = Bind::Quic {
+            port: 0,
+            addresses: Vec::new(),
+            identity: None,
+        };
+        let listener = Listener::bind(&bind).expect("bind");
+        let endpoints = listener.local_addrs().expect("local addrs");
+        let port = listener.port().expect("port");
+        assert!(endpoints.iter().any(SocketAddr::is_ipv4));
+        // IPv6 is skipped on hosts without it.
+        assert!(endpoints.iter().all(|addr| addr.port() == port));
+
+        for endpoint in endpoints {
+            let addr = local_address(endpoint);
+            assert!(addr.ip().is_loopback());
+            let dial = Dial::Quic { addr, known: None };
+            let client = tokio::spawn(async move { dial.connect().await.expect("connect") });
+            let incoming = listener.accept().await.expect("incoming");
+            assert_eq!(incoming.remote_address().is_ipv6(), addr.is_ipv6());
+            incoming.accept().await.expect("accept");
+            client.await.expect("client task");
+        }
+        listener.close("done");
+    }
+}
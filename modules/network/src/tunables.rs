@@ -0,0 +1,247 @@
+//! Named values that can be changed while the game runs.
+//!
+//! Modules register a tunable with [`TunablesAppExt::register_tunable`],
+//! giving a dotted name (by convention the config key it is loaded from,
+//! e.g. `items.interaction_range`) and how to read and write it in the
+//! resource that owns it.  That resource stays the single source of truth:
+//! [`Tunables`] only knows how to reach it, so values loaded from the config
+//! on startup or on reload show up without further ado.
+//!
+//! [`set_tunable`] changes a value, for instance from a [`TunableRequest`]
+//! written by an admin command.  Tunables registered as replicated are sent
+//! to each client as it joins and again whenever the server's value
+//! changes, for client-side prediction and UI that depend on them.
+
+use std::collections::{BTreeMap, HashMap};
+
+use bevy::ecs::message::MessageCursor;
+use bevy::prelude::*;
+
+use crate::{ClientEvent, NetServerSender, NetworkReceive, NetworkSet, PlayerEvent, ServerMessage};
+
+type TunableGetter = Box<dyn Fn(&World) -> Option<f32> + Send + Sync>;
+type TunableSetter = Box<dyn Fn(&mut World, f32) + Send + Sync>;
+
+/// How to reach one registered tunable.
+struct TunableDef {
+    get: TunableGetter,
+    set: TunableSetter,
+    replicated: bool,
+}
+
+/// Registry of every tunable, by name.
+#[derive(Resource, Default)]
+pub struct Tunables {
+    defs: BTreeMap<&'static str, TunableDef>,
+    /// Server-side: the value each replicated tunable was last sent with.
+    sent: HashMap<&'static str, f32>,
+}
+
+impl Tunables {
+    /// Names of the registered tunables, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.defs.keys().copied()
+    }
+
+    /// Whether `name` is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.defs.contains_key(name)
+    }
+}
+
+/// Extension trait for [`App`] that provides `register_tunable`.
+pub trait TunablesAppExt {
+    /// Registers the tunable `name`, read with `get` and written with `set`.
+    /// `get` returns `None` while the owning resource does not exist.
+    ///
+    /// With `replicated` set, clients are kept up to date with the server's
+    /// value; register it on both sides.
+    fn register_tunable(
+        &mut self,
+        name: &'static str,
+        replicated: bool,
+        get: impl Fn(&World) -> Option<f32> + Send + Sync + 'static,
+        set: impl Fn(&mut World, f32) + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl TunablesAppExt for App {
+    fn register_tunable(
+        &mut self,
+        name: &'static str,
+        replicated: bool,
+        get: impl Fn(&World) -> Option<f32> + Send + Sync + 'static,
+        set: impl Fn(&mut World, f32) + Send + Sync + 'static,
+    ) -> &mut Self {
+        let mut tunables = self
+            .world_mut()
+            .get_resource_or_insert_with::<Tunables>(Tunables::default);
+        assert!(
+            !tunables.defs.contains_key(name),
+            "duplicate tunable: \"{name}\" is already registered"
+        );
+        tunables.defs.insert(
+            name,
+            TunableDef {
+                get: Box::new(get),
+                set: Box::new(set),
+                replicated,
+            },
+        );
+        self
+    }
+}
+
+/// The current value of every registered tunable whose owner exists, by
+/// name.
+pub fn tunable_values(world: &World) -> Vec<(&'static str, f32)> {
+    let Some(tunables) = world.get_resource::<Tunables>() else {
+        return Vec::new();
+    };
+    tunables
+        .defs
+        .iter()
+        .filter_map(|(&name, def)| Some((name, (def.get)(world)?)))
+        .collect()
+}
+
+/// Sets the tunable `name` to `value` and returns the value it had.
+pub fn set_tunable(world: &mut World, name: &str, value: f32) -> Result<f32, String> {
+    if !value.is_finite() {
+        return Err(format!("{value} is not a number a tunable can take"));
+    }
+    if !world
+        .get_resource::<Tunables>()
+        .is_some_and(|tunables| tunables.contains(name))
+    {
+        return Err(format!("no tunable called \"{name}\""));
+    }
+    world.resource_scope(|world, tunables: Mut<Tunables>| {
+        let def = &tunables.defs[name];
+        let old = (def.get)(world)
+            .ok_or_else(|| format!("\"{name}\" cannot be changed at the moment"))?;
+        (def.set)(world, value);
+        Ok(old)
+    })
+}
+
+/// Admin request about tunables, answered in the log.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub enum TunableRequest {
+    /// Log every tunable and its value.
+    List,
+    /// Change a tunable.
+    Set { name: String, value: f32 },
+}
+
+pub(crate) fn register_tunable_systems(app: &mut App) {
+    app.init_resource::<Tunables>();
+    app.add_message::<TunableRequest>();
+    app.add_systems(Update, handle_tunable_requests);
+    app.add_systems(
+        NetworkReceive,
+        (send_tunables, receive_tunables).after(NetworkSet::Drain),
+    );
+}
+
+/// Carries out [`TunableRequest`]s.
+fn handle_tunable_requests(world: &mut World) {
+    let requests: Vec<TunableRequest> = world
+        .resource_mut::<Messages<TunableRequest>>()
+        .drain()
+        .collect();
+    for request in requests {
+        match request {
+            TunableRequest::List => {
+                for (name, value) in tunable_values(world) {
+                    info!("{name} = {value}");
+                }
+            }
+            TunableRequest::Set { name, value } => match set_tunable(world, &name, value) {
+                Ok(old) => info!("Tunable {name}: {old} -> {value}"),
+                Err(e) => warn!("Tunable not changed: {e}"),
+            },
+        }
+    }
+}
+
+/// Server system: sends every replicated tunable to joining players, and
+/// those whose value changed to everyone.
+fn send_tunables(world: &mut World, mut joins: Local<MessageCursor<PlayerEvent>>) {
+    let joined: Vec<_> = joins
+        .read(world.resource::<Messages<PlayerEvent>>())
+        .filter_map(|event| match event {
+            PlayerEvent::Joined { id, .. } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    if !world.contains_resource::<NetServerSender>() {
+        return;
+    }
+    world.resource_scope(|world, mut tunables: Mut<Tunables>| {
+        let sender = world.resource::<NetServerSender>();
+        let tunables = &mut *tunables;
+        for (&name, def) in tunables.defs.iter().filter(|(_, def)| def.replicated) {
+            let Some(value) = (def.get)(world) else {
+                continue;
+            };
+            let message = ServerMessage::Tunable {
+                name: name.to_string(),
+                value,
+            };
+            if tunables.sent.insert(name, value) != Some(value) {
+                sender.broadcast(&message);
+            } else {
+                for &client in &joined {
+                    sender.send_to(client, &message);
+                }
+            }
+        }
+    });
+}
+
+/// Client system: applies the replicated tunables the server sends.
+fn receive_tunables(mut commands: Commands, mut events: MessageReader<ClientEvent>) {
+    for event in events.read() {
+        if let ClientEvent::ServerMessageReceived(ServerMessage::Tunable { name, value }) = event {
+            let (name, value) = (name.clone(), *value);
+            commands.queue(move |world: &mut World| {
+                if let Err(e) = set_tunable(world, &name, value) {
+                    warn!("Tunable from the server not applied: {e}");
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource)]
+    struct Range(f32);
+
+    #[test]
+    fn tunables_change_their_owning_resource() {
+        let mut app = App::new();
+        app.register_tunable(
+            "items.interaction_range",
+            true,
+            |world| world.get_resource::<Range>().map(|range| range.0),
+            |world, value| world.insert_resource(Range(value)),
+        );
+        let world = app.world_mut();
+        assert_eq!(tunable_values(world), vec![]);
+        assert!(set_tunable(world, "items.interaction_range", 3.0).is_err());
+
+        world.insert_resource(Range(2.0));
+        assert_eq!(
+            tunable_values(world),
+            vec![("items.interaction_range", 2.0)]
+        );
+        assert_eq!(set_tunable(world, "items.interaction_range", 3.0), Ok(2.0));
+        assert_eq!(world.resource::<Range>().0, 3.0);
+        assert!(set_tunable(world, "items.range", 3.0).is_err());
+        assert!(set_tunable(world, "items.interaction_range", f32::NAN).is_err());
+    }
+}
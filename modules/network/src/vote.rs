@@ -0,0 +1,32 @@
+//! Wire format of the client→server votes stream.
+//!
+//! The stream is registered and served by the game mode's vote plugin; the
+//! types live here so that client code below the game mode can send votes.
+
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{ClientId, stream_tag};
+
+/// Tag of the client→server votes stream, derived from its name.
+pub const VOTES_STREAM_TAG: u8 = stream_tag("votes");
+
+/// What a vote decides.
+#[derive(Debug, Clone, PartialEq, Eq, SchemaRead, SchemaWrite)]
+pub enum VoteKind {
+    /// End the round and reset the station.
+    RestartRound,
+    /// Restart on the map file `map`, one of the maps the server allows
+    /// votes on.
+    ChangeMap { map: String },
+    /// Disconnect `player`.
+    Kick { player: ClientId },
+}
+
+/// Votes stream wire format: client→server requests about votes.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub enum VoteRequest {
+    /// Start a vote on `kind`, voting yes on it.
+    Start(VoteKind),
+    /// Vote on the running vote.
+    Cast { yes: bool },
+}
@@ -1,11 +1,71 @@
+use avian3d::prelude::PhysicsLayer;
 use bevy::prelude::*;
 
 // Re-export only the types other modules need.
 pub use avian3d::prelude::{
-    Collider, ConstantForce, GravityScale, LinearVelocity, LockedAxes, PhysicsDebugPlugin,
-    Restitution, RigidBody, SpatialQuery, SpatialQueryFilter,
+    Collider, CollisionLayers, ConstantForce, DistanceJoint, FixedJoint, GravityScale, LayerMask,
+    LinearVelocity, LockedAxes, Mass, PhysicsDebugPlugin, Restitution, RevoluteJoint, RigidBody,
+    ShapeCastConfig, SpatialQuery, SpatialQueryFilter,
 };
 
+/// Collision layers of the project's bodies.
+///
+/// Bodies join a layer through the [`CollisionLayers`] built by the
+/// constructors below; bodies without one sit on [`GameLayer::Default`] and
+/// collide with everything.
+#[derive(PhysicsLayer, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GameLayer {
+    #[default]
+    Default,
+    Creatures,
+    Items,
+    Tiles,
+    /// Sensor volumes.  Only creatures and items touch them, and pointer
+    /// and movement queries pass through them.
+    Triggers,
+}
+
+impl GameLayer {
+    /// Every layer but [`GameLayer::Triggers`].
+    const SOLID: [GameLayer; 4] = [
+        GameLayer::Default,
+        GameLayer::Creatures,
+        GameLayer::Items,
+        GameLayer::Tiles,
+    ];
+
+    /// Layers of a creature, which collides with everything.
+    pub fn creature() -> CollisionLayers {
+        CollisionLayers::new(GameLayer::Creatures, LayerMask::ALL)
+    }
+
+    /// Layers of an item lying in the world, which collides with
+    /// everything.
+    pub fn item() -> CollisionLayers {
+        CollisionLayers::new(GameLayer::Items, LayerMask::ALL)
+    }
+
+    /// Layers of tile chunks (floors and walls), which triggers pass
+    /// through.
+    pub fn tile() -> CollisionLayers {
+        CollisionLayers::new(GameLayer::Tiles, Self::SOLID)
+    }
+
+    /// Layers of a trigger volume, which only creatures and items enter.
+    pub fn trigger() -> CollisionLayers {
+        CollisionLayers::new(
+            GameLayer::Triggers,
+            [GameLayer::Creatures, GameLayer::Items],
+        )
+    }
+
+    /// Query filter for raycasts and shape casts that should only hit solid
+    /// bodies, e.g. mouse picking and creature movement.
+    pub fn solid_filter() -> SpatialQueryFilter {
+        SpatialQueryFilter::from_mask(Self::SOLID)
+    }
+}
+
 pub struct PhysicsPlugin;
 
 impl Plugin for PhysicsPlugin {
@@ -385,4 +445,45 @@ mod tests {
             vel.y
         );
     }
+
+    /// Solid queries pass through trigger volumes to what lies behind them.
+    #[test]
+    fn solid_filter_skips_triggers() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut app = test_app();
+        app.insert_resource(avian3d::prelude::Gravity(Vec3::ZERO));
+        let trigger = app
+            .world_mut()
+            .spawn((
+                RigidBody::Static,
+                Collider::cuboid(1.0, 1.0, 1.0),
+                GameLayer::trigger(),
+                Transform::from_xyz(0.0, 0.0, -2.0),
+            ))
+            .id();
+        let wall = app
+            .world_mut()
+            .spawn((
+                RigidBody::Static,
+                Collider::cuboid(1.0, 1.0, 1.0),
+                GameLayer::tile(),
+                Transform::from_xyz(0.0, 0.0, -5.0),
+            ))
+            .id();
+        app.update();
+        app.update();
+
+        let mut first_hit = |filter: SpatialQueryFilter| {
+            app.world_mut()
+                .run_system_once(move |query: SpatialQuery| {
+                    query
+                        .cast_ray(Vec3::ZERO, Dir3::NEG_Z, 100.0, true, &filter)
+                        .map(|hit| hit.entity)
+                })
+                .unwrap()
+        };
+        assert_eq!(first_hit(SpatialQueryFilter::default()), Some(trigger));
+        assert_eq!(first_hit(GameLayer::solid_filter()), Some(wall));
+    }
 }
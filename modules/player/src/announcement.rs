@@ -0,0 +1,74 @@
+//! Banner showing the latest server announcement for a few seconds.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use events::Announced;
+use network::Headless;
+use ui::UiTheme;
+
+/// How long an announcement stays on screen.
+const ANNOUNCEMENT_DURATION: Duration = Duration::from_secs(8);
+
+/// Text node showing an announcement, removed at `hide_at` (a
+/// `Time::elapsed`).
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct AnnouncementBanner {
+    pub hide_at: Duration,
+}
+
+pub(crate) fn register_announcement_banner(app: &mut App) {
+    app.register_type::<AnnouncementBanner>();
+    app.add_systems(
+        Update,
+        (show_announcements, hide_announcements)
+            .chain()
+            .run_if(not(resource_exists::<Headless>)),
+    );
+}
+
+/// Replaces the banner with the latest [`Announced`] text.
+fn show_announcements(
+    mut commands: Commands,
+    time: Res<Time>,
+    theme: Res<UiTheme>,
+    mut announced: MessageReader<Announced>,
+    banners: Query<Entity, With<AnnouncementBanner>>,
+) {
+    let Some(latest) = announced.read().last() else {
+        return;
+    };
+    for banner in banners.iter() {
+        commands.entity(banner).despawn();
+    }
+    commands.spawn((
+        Text::new(latest.text.clone()),
+        TextFont::from_font_size(theme.font_size_body),
+        TextColor(theme.text),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(64.0),
+            justify_self: JustifySelf::Center,
+            padding: theme.panel_padding,
+            ..default()
+        },
+        BackgroundColor(theme.surface.with_alpha(0.9)),
+        AnnouncementBanner {
+            hide_at: time.elapsed() + ANNOUNCEMENT_DURATION,
+        },
+    ));
+}
+
+/// Removes banners whose time is up.
+fn hide_announcements(
+    mut commands: Commands,
+    time: Res<Time>,
+    banners: Query<(Entity, &AnnouncementBanner)>,
+) {
+    for (entity, banner) in banners.iter() {
+        if time.elapsed() >= banner.hide_at {
+            commands.entity(entity).despawn();
+        }
+    }
+}
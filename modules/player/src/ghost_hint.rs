@@ -0,0 +1,105 @@
+//! Hint telling a dead player when and how to rejoin as a new character.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use input::{Action, InputMap};
+use network::Headless;
+use souls::RESPAWN_DELAY;
+use things::{GHOST_KIND, PlayerControlled, Thing};
+use ui::UiTheme;
+
+/// Text node shown while the player is a ghost.  `respawn_at` is the
+/// `Time::elapsed` from which the server accepts a respawn.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct GhostHint {
+    pub respawn_at: Duration,
+}
+
+pub(crate) fn register_ghost_hint(app: &mut App) {
+    app.register_type::<GhostHint>();
+    app.add_systems(
+        Update,
+        update_ghost_hint.run_if(not(resource_exists::<Headless>)),
+    );
+}
+
+/// What the hint says `remaining` before a respawn is allowed, with `key`
+/// bound to [`Action::Respawn`].
+fn ghost_hint_text(remaining: Duration, key: Option<String>) -> String {
+    if !remaining.is_zero() {
+        return format!(
+            "You died. You can rejoin as a new character in {} s.",
+            remaining.as_secs_f32().ceil()
+        );
+    }
+    match key {
+        Some(key) => format!("You died. Press {key} to rejoin as a new character."),
+        None => "You died. Bind a key to Respawn to rejoin as a new character.".to_string(),
+    }
+}
+
+/// Shows the [`GhostHint`] while the [`PlayerControlled`] thing is a ghost,
+/// counting down [`RESPAWN_DELAY`] from when it became one, and removes it
+/// afterwards.
+fn update_ghost_hint(
+    mut commands: Commands,
+    time: Res<Time>,
+    theme: Res<UiTheme>,
+    input_map: Res<InputMap>,
+    player: Query<&Thing, With<PlayerControlled>>,
+    mut hints: Query<(Entity, &GhostHint, &mut Text)>,
+) {
+    let ghost = player.iter().any(|thing| thing.kind == GHOST_KIND);
+    let Ok((entity, hint, mut text)) = hints.single_mut() else {
+        if ghost {
+            commands.spawn((
+                Text::default(),
+                TextFont::from_font_size(theme.font_size_body),
+                TextColor(theme.text),
+                Node {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(120.0),
+                    justify_self: JustifySelf::Center,
+                    padding: theme.panel_padding,
+                    ..default()
+                },
+                BackgroundColor(theme.surface.with_alpha(0.9)),
+                GhostHint {
+                    respawn_at: time.elapsed() + RESPAWN_DELAY,
+                },
+            ));
+        }
+        return;
+    };
+    if !ghost {
+        commands.entity(entity).despawn();
+        return;
+    }
+    let key = input_map
+        .bindings(Action::Respawn)
+        .first()
+        .map(ToString::to_string);
+    let content = ghost_hint_text(hint.respawn_at.saturating_sub(time.elapsed()), key);
+    if text.0 != content {
+        text.0 = content;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hint_counts_down_then_names_the_key() {
+        assert_eq!(
+            ghost_hint_text(Duration::from_millis(12_300), Some("R".into())),
+            "You died. You can rejoin as a new character in 13 s."
+        );
+        assert_eq!(
+            ghost_hint_text(Duration::ZERO, Some("R".into())),
+            "You died. Press R to rejoin as a new character."
+        );
+    }
+}
@@ -1,84 +1,259 @@
 use bevy::prelude::*;
-use things::{DisplayName, InputDirection};
+use input::{Action, ActionInput, InputMap};
+use network::Headless;
+use things::{DisplayName, Health, InputDirection, MovementModifiers, MovementState};
 use ui::{OverlayOffset, OverlayTarget, WorldSpaceOverlay};
 
+mod announcement;
+mod ghost_hint;
+mod minimap;
+mod needs_bars;
+mod roster;
+mod round_banner;
+mod shutdown;
+mod sight;
+mod status_icons;
+mod vote_prompt;
+pub use announcement::AnnouncementBanner;
+pub use ghost_hint::GhostHint;
+pub use minimap::{Minimap, MinimapMarker, MinimapSettings};
+pub use needs_bars::NeedsBars;
+pub use roster::RosterOverlay;
+pub use round_banner::RoundBanner;
+pub use shutdown::ShutdownBanner;
+pub use sight::SIGHT_RADIUS;
+pub use status_icons::StatusIconBar;
 pub use things::PlayerControlled;
+pub use tiles::HiddenBySight;
+pub use vote_prompt::VotePrompt;
 
 /// Marker component for nameplate UI overlay nodes.
 ///
-/// Spawned automatically when a [`DisplayName`] component is added to an entity.
+/// Spawned automatically when a [`DisplayName`] component is added to an entity
+/// and despawned when it is removed, including when the entity is despawned.
 /// The [`ui::update_world_space_overlays`] system (registered by [`ui::UiPlugin`])
 /// projects the tracked entity's world position to screen space each frame.
 #[derive(Component, Debug, Clone, Copy, Default, Reflect)]
 #[reflect(Component)]
 pub struct Nameplate;
 
+/// Fill node of a nameplate's health bar, tracking the named entity.
+///
+/// The bar is hidden while the entity has no [`Health`].
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct HealthBarFill(pub Entity);
+
 /// Vertical world-space offset above the tracked entity's origin.
 const NAMEPLATE_WORLD_OFFSET: f32 = 2.0;
 
+/// Colour of a name, and of the name of an unconscious creature.
+const NAME_COLOR: Color = Color::WHITE;
+const UNCONSCIOUS_NAME_COLOR: Color = Color::srgba(0.6, 0.6, 0.6, 0.5);
+
+/// Size of the health bar under the name, in logical pixels.
+const HEALTH_BAR_WIDTH: f32 = 60.0;
+const HEALTH_BAR_HEIGHT: f32 = 6.0;
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Nameplate>();
+        app.register_type::<HealthBarFill>();
+        app.init_resource::<InputMap>();
         app.add_observer(spawn_nameplate);
+        app.add_observer(despawn_nameplate);
         app.add_systems(Update, read_player_input);
+        app.add_systems(
+            Update,
+            (update_health_bars, dim_unconscious_nameplates)
+                .run_if(not(resource_exists::<Headless>)),
+        );
+        announcement::register_announcement_banner(app);
+        ghost_hint::register_ghost_hint(app);
+        minimap::register_minimap(app);
+        needs_bars::register_needs_bars(app);
+        roster::register_roster_overlay(app);
+        round_banner::register_round_banner(app);
+        shutdown::register_shutdown_banner(app);
+        sight::register_sight(app);
+        status_icons::register_status_icons(app);
+        vote_prompt::register_vote_prompt(app);
     }
 }
 
-/// Reads keyboard input and writes InputDirection on PlayerControlled entities.
+/// Reads the movement actions and writes InputDirection and MovementModifiers on
+/// PlayerControlled entities.
 fn read_player_input(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mut query: Query<&mut InputDirection, With<PlayerControlled>>,
+    actions: ActionInput,
+    mut query: Query<(&mut InputDirection, Option<&mut MovementModifiers>), With<PlayerControlled>>,
 ) {
-    for mut input in query.iter_mut() {
+    for (mut input, modifiers) in query.iter_mut() {
         let mut direction = Vec3::ZERO;
-        if keyboard.pressed(KeyCode::KeyW) {
+        if actions.pressed(Action::MoveForward) {
             direction.z -= 1.0;
         }
-        if keyboard.pressed(KeyCode::KeyS) {
+        if actions.pressed(Action::MoveBack) {
             direction.z += 1.0;
         }
-        if keyboard.pressed(KeyCode::KeyA) {
+        if actions.pressed(Action::MoveLeft) {
             direction.x -= 1.0;
         }
-        if keyboard.pressed(KeyCode::KeyD) {
+        if actions.pressed(Action::MoveRight) {
             direction.x += 1.0;
         }
         input.0 = direction;
+        if let Some(mut modifiers) = modifiers {
+            modifiers.set_if_neq(MovementModifiers {
+                sprint: actions.pressed(Action::Sprint),
+                crouch: actions.pressed(Action::Crouch),
+            });
+        }
     }
 }
 
 /// Observer that runs when a [`DisplayName`] component is added to an entity.
 ///
-/// Spawns an absolutely-positioned UI [`Text`] node with a [`Nameplate`] marker,
+/// Spawns an absolutely-positioned UI column with a [`Nameplate`] marker,
 /// a [`WorldSpaceOverlay`] for projection, an [`OverlayTarget`] linking the
 /// node back to the 3D entity, and an [`OverlayOffset`] placing the label
-/// above the entity's origin.  The [`ui::update_world_space_overlays`] system
-/// moves the node to the correct screen position each frame.
+/// above the entity's origin.  The column holds the name [`Text`] and a
+/// health bar that [`update_health_bars`] shows once the entity has
+/// [`Health`].  The [`ui::update_world_space_overlays`] system moves the node
+/// to the correct screen position each frame, so it always faces the camera.
+///
+/// Does nothing on a [`Headless`] server, which has no UI.
 fn spawn_nameplate(
     trigger: On<Add, DisplayName>,
     mut commands: Commands,
     names: Query<&DisplayName>,
+    headless: Option<Res<Headless>>,
 ) {
+    if headless.is_some() {
+        return;
+    }
     let entity = trigger.event_target();
     let display_name = names
         .get(entity)
         .expect("DisplayName missing on trigger target");
 
-    commands.spawn((
-        Text::new(display_name.0.clone()),
-        TextFont::from_font_size(20.0),
-        TextColor(Color::WHITE),
-        Node {
-            position_type: PositionType::Absolute,
-            ..default()
-        },
-        WorldSpaceOverlay::default(),
-        OverlayTarget(entity),
-        OverlayOffset(Vec3::Y * NAMEPLATE_WORLD_OFFSET),
-        Nameplate,
-    ));
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(2.0),
+                ..default()
+            },
+            WorldSpaceOverlay::default(),
+            OverlayTarget(entity),
+            OverlayOffset(Vec3::Y * NAMEPLATE_WORLD_OFFSET),
+            Nameplate,
+        ))
+        .with_children(|plate| {
+            plate.spawn((
+                Text::new(display_name.0.clone()),
+                TextFont::from_font_size(20.0),
+                TextColor(NAME_COLOR),
+            ));
+            plate
+                .spawn((
+                    Node {
+                        width: Val::Px(HEALTH_BAR_WIDTH),
+                        height: Val::Px(HEALTH_BAR_HEIGHT),
+                        display: Display::None,
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                ))
+                .with_child((
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(health_color(1.0)),
+                    HealthBarFill(entity),
+                ));
+        });
+}
+
+/// Observer that despawns an entity's nameplate when its [`DisplayName`] is
+/// removed or the entity itself is despawned.
+fn despawn_nameplate(
+    trigger: On<Remove, DisplayName>,
+    mut commands: Commands,
+    nameplates: Query<(Entity, &OverlayTarget), With<Nameplate>>,
+) {
+    let entity = trigger.event_target();
+    for (nameplate, target) in nameplates.iter() {
+        if target.0 == entity {
+            commands.entity(nameplate).despawn();
+        }
+    }
+}
+
+/// Bar colour for a health fraction: green when full, red when empty.
+fn health_color(fraction: f32) -> Color {
+    Color::srgb(1.0 - fraction, fraction, 0.0)
+}
+
+/// Sizes and colours each [`HealthBarFill`] from its entity's [`Health`],
+/// and hides the bar when the entity has none.
+fn update_health_bars(
+    healths: Query<&Health>,
+    mut fills: Query<(&HealthBarFill, &ChildOf, &mut Node, &mut BackgroundColor)>,
+    mut bars: Query<&mut Node, Without<HealthBarFill>>,
+) {
+    for (fill, child_of, mut node, mut color) in fills.iter_mut() {
+        let health = healths.get(fill.0).ok();
+        if let Ok(mut bar) = bars.get_mut(child_of.parent()) {
+            let display = if health.is_some() {
+                Display::Flex
+            } else {
+                Display::None
+            };
+            if bar.display != display {
+                bar.display = display;
+            }
+        }
+        let Some(health) = health else {
+            continue;
+        };
+        let fraction = health.fraction();
+        let width = Val::Percent(fraction * 100.0);
+        if node.width != width {
+            node.width = width;
+            color.0 = health_color(fraction);
+        }
+    }
+}
+
+/// Greys out the name on the nameplate of every unconscious creature and
+/// restores it once the creature comes to.
+fn dim_unconscious_nameplates(
+    nameplates: Query<(&OverlayTarget, &Children), With<Nameplate>>,
+    states: Query<&MovementState>,
+    mut names: Query<&mut TextColor>,
+) {
+    for (target, children) in nameplates.iter() {
+        let unconscious = states
+            .get(target.0)
+            .is_ok_and(|state| *state == MovementState::Unconscious);
+        let color = if unconscious {
+            UNCONSCIOUS_NAME_COLOR
+        } else {
+            NAME_COLOR
+        };
+        let mut names = names.iter_many_mut(children.iter());
+        while let Some(mut name) = names.fetch_next() {
+            if name.0 != color {
+                name.0 = color;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -130,4 +305,58 @@ mod tests {
             "Offset should be Vec3::Y * NAMEPLATE_WORLD_OFFSET ({NAMEPLATE_WORLD_OFFSET})"
         );
     }
+
+    /// Verifies that the health bar follows [`Health`] and that the nameplate
+    /// is despawned together with its entity.
+    #[test]
+    fn health_bar_tracks_health_and_despawns_with_entity() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_observer(spawn_nameplate);
+        app.add_observer(despawn_nameplate);
+        app.add_systems(Update, update_health_bars);
+
+        let target = app
+            .world_mut()
+            .spawn((DisplayName("Eve".to_string()), Health::new(100.0)))
+            .id();
+        app.update();
+        app.world_mut().get_mut::<Health>(target).unwrap().current = 25.0;
+        app.update();
+
+        let mut fills = app.world_mut().query::<(&HealthBarFill, &Node)>();
+        let (fill, node) = fills.single(app.world()).unwrap();
+        assert_eq!(fill.0, target);
+        assert_eq!(node.width, Val::Percent(25.0));
+
+        app.world_mut().despawn(target);
+        app.update();
+
+        let mut nameplates = app.world_mut().query_filtered::<(), With<Nameplate>>();
+        assert_eq!(nameplates.iter(app.world()).count(), 0);
+        assert_eq!(fills.iter(app.world()).count(), 0);
+    }
+
+    /// Verifies that the name of an unconscious creature is greyed out.
+    #[test]
+    fn unconscious_creatures_have_dimmed_names() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_observer(spawn_nameplate);
+        app.add_systems(Update, dim_unconscious_nameplates);
+
+        let target = app
+            .world_mut()
+            .spawn((DisplayName("Zed".to_string()), MovementState::Unconscious))
+            .id();
+        app.update();
+        app.update();
+
+        let mut names = app.world_mut().query_filtered::<&TextColor, With<Text>>();
+        assert_eq!(names.single(app.world()).unwrap().0, UNCONSCIOUS_NAME_COLOR);
+
+        *app.world_mut().get_mut::<MovementState>(target).unwrap() = MovementState::Walking;
+        app.update();
+        assert_eq!(names.single(app.world()).unwrap().0, NAME_COLOR);
+    }
 }
@@ -0,0 +1,480 @@
+//! Minimap of the station in the corner of the screen.
+//!
+//! The map is a [`canvas_image`] with one pixel per tile, drawn in full from
+//! the replicated [`TileGrid<TileKind>`] when it arrives or its chunks
+//! stream in, and touched up cell by cell on [`TileMutated`].  It scrolls to
+//! keep the player's creature in the middle.  Dots mark the player, the
+//! creatures it can see nearby and things with a [`MinimapMarker`]; the
+//! names of the [`ZoneIndex`] rooms label the map once zoomed in.
+//! [`Action::ToggleMinimap`] shows and hides it, [`Action::MinimapZoomIn`]
+//! and [`Action::MinimapZoomOut`] change its scale.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use creatures::Creature;
+use input::{Action, ActionInput};
+use network::Headless;
+use things::PlayerControlled;
+use tiles::{TileGrid, TileKind, TileMutated, ZoneId, ZoneIndex};
+use ui::{UiTheme, canvas_image};
+
+use crate::HiddenBySight;
+
+/// Width and height of the minimap frame, in logical pixels.
+const MINIMAP_SIZE: f32 = 180.0;
+
+/// Scales the map can be zoomed between, in logical pixels per tile.
+const ZOOM_LEVELS: [f32; 4] = [2.0, 4.0, 6.0, 8.0];
+
+/// How far from the player other creatures are shown, in tiles.
+const CREATURE_RANGE: f32 = 16.0;
+
+/// Diameter of the dots, in logical pixels.
+const DOT_SIZE: f32 = 6.0;
+
+/// Zoom from which rooms are labelled, in logical pixels per tile.
+const LABEL_MIN_ZOOM: f32 = 4.0;
+
+const PLAYER_DOT_COLOR: Color = Color::WHITE;
+const CREATURE_DOT_COLOR: Color = Color::srgb(0.9, 0.25, 0.2);
+
+/// Whether the minimap is shown and how far it is zoomed in.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinimapSettings {
+    pub visible: bool,
+    /// Index into the zoom levels, from the widest view.
+    pub zoom: usize,
+}
+
+impl Default for MinimapSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            zoom: 1,
+        }
+    }
+}
+
+impl MinimapSettings {
+    /// Current scale, in logical pixels per tile.
+    pub fn pixels_per_tile(&self) -> f32 {
+        ZOOM_LEVELS[self.zoom.min(ZOOM_LEVELS.len() - 1)]
+    }
+}
+
+/// Marks a point of interest, shown on the minimap as a dot of this colour
+/// wherever it is.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct MinimapMarker(pub Color);
+
+/// Marker for the minimap frame, which clips the map and the dots.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct Minimap;
+
+/// The map image inside the [`Minimap`] frame.
+#[derive(Component, Debug, Clone, Copy, Default)]
+struct MinimapImage;
+
+/// Dot inside the [`Minimap`] frame marking the entity it holds.
+#[derive(Component, Debug, Clone, Copy)]
+struct MinimapDot(Entity);
+
+/// Name of a zone, centred on its anchor in the [`Minimap`] frame.
+#[derive(Component, Debug, Clone, Copy)]
+struct MinimapLabel(ZoneId);
+
+/// The map image and the size of the grid it was drawn from.
+#[derive(Resource, Debug, Clone)]
+struct MinimapTexture {
+    image: Handle<Image>,
+    size: UVec2,
+}
+
+pub(crate) fn register_minimap(app: &mut App) {
+    app.register_type::<Minimap>();
+    app.register_type::<MinimapMarker>();
+    app.init_resource::<MinimapSettings>();
+    app.add_systems(
+        Update,
+        (
+            control_minimap,
+            draw_minimap,
+            layout_minimap,
+            update_minimap_dots,
+            update_minimap_labels,
+        )
+            .chain()
+            .run_if(not(resource_exists::<Headless>)),
+    );
+}
+
+/// Colour of a tile on the map.
+fn tile_color(kind: TileKind) -> Color {
+    match kind {
+        TileKind::Floor => Color::srgb(0.22, 0.25, 0.30),
+        TileKind::Wall => Color::srgb(0.70, 0.72, 0.75),
+        TileKind::Door { .. } => Color::srgb(0.85, 0.65, 0.20),
+        TileKind::Space => Color::NONE,
+        TileKind::Ladder => Color::srgb(0.30, 0.70, 0.35),
+    }
+}
+
+/// Position in the frame, in logical pixels, of the world point `at`
+/// (its `x` and `z`), with the frame centred on the world point `centre`.
+fn frame_position(at: Vec2, centre: Vec2, pixels_per_tile: f32) -> Vec2 {
+    Vec2::splat(MINIMAP_SIZE / 2.0) + (at - centre) * pixels_per_tile
+}
+
+/// Toggles and zooms the minimap on its actions.
+fn control_minimap(actions: ActionInput, mut settings: ResMut<MinimapSettings>) {
+    if actions.just_pressed(Action::ToggleMinimap) {
+        settings.visible = !settings.visible;
+    }
+    if actions.just_pressed(Action::MinimapZoomIn) && settings.zoom + 1 < ZOOM_LEVELS.len() {
+        settings.zoom += 1;
+    }
+    if actions.just_pressed(Action::MinimapZoomOut) && settings.zoom > 0 {
+        settings.zoom -= 1;
+    }
+}
+
+/// Keeps the [`MinimapTexture`] in step with the tile grid.
+///
+/// Mutations are drawn one cell at a time.  Any other change to the grid
+/// (a new map, streamed chunks) redraws the whole image.
+fn draw_minimap(
+    mut commands: Commands,
+    grid: Option<Res<TileGrid<TileKind>>>,
+    texture: Option<Res<MinimapTexture>>,
+    mut images: ResMut<Assets<Image>>,
+    mut mutations: MessageReader<TileMutated>,
+) {
+    let Some(grid) = grid else {
+        if texture.is_some() {
+            commands.remove_resource::<MinimapTexture>();
+        }
+        mutations.clear();
+        return;
+    };
+    let size = UVec2::new(grid.width(), grid.height());
+    let current = texture.filter(|texture| texture.size == size);
+
+    if let Some(texture) = current.as_ref()
+        && (!grid.is_changed() || !mutations.is_empty())
+    {
+        let Some(image) = images.get_mut(&texture.image) else {
+            return;
+        };
+        for mutation in mutations.read() {
+            let pos = mutation.position;
+            if pos.cmpge(IVec2::ZERO).all() {
+                let _ = image.set_color_at(pos.x as u32, pos.y as u32, tile_color(mutation.kind));
+            }
+        }
+        return;
+    }
+
+    mutations.clear();
+    let mut image = canvas_image(size, Color::NONE);
+    for (pos, kind) in grid.iter() {
+        let _ = image.set_color_at(pos.x as u32, pos.y as u32, tile_color(*kind));
+    }
+    match current {
+        Some(texture) => {
+            if let Some(existing) = images.get_mut(&texture.image) {
+                *existing = image;
+            }
+        }
+        None => commands.insert_resource(MinimapTexture {
+            image: images.add(image),
+            size,
+        }),
+    }
+}
+
+/// Spawns the [`Minimap`] frame once there is a map to show, hides it while
+/// toggled off and scrolls and scales the map around the player.
+#[allow(clippy::type_complexity)]
+fn layout_minimap(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    settings: Res<MinimapSettings>,
+    texture: Option<Res<MinimapTexture>>,
+    player: Query<&Transform, With<PlayerControlled>>,
+    mut frames: Query<(Entity, &mut Node), With<Minimap>>,
+    mut maps: Query<(&mut Node, &mut ImageNode), (With<MinimapImage>, Without<Minimap>)>,
+) {
+    let Some(texture) = texture else {
+        for (frame, _) in frames.iter() {
+            commands.entity(frame).despawn();
+        }
+        return;
+    };
+    let Ok((_, mut frame)) = frames.single_mut() else {
+        commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(16.0),
+                    right: Val::Px(16.0),
+                    width: Val::Px(MINIMAP_SIZE),
+                    height: Val::Px(MINIMAP_SIZE),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                BackgroundColor(theme.surface.with_alpha(0.8)),
+                Minimap,
+            ))
+            .with_child((
+                Node {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ImageNode::new(texture.image.clone()),
+                MinimapImage,
+            ));
+        return;
+    };
+
+    let display = if settings.visible {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    if frame.display != display {
+        frame.display = display;
+    }
+
+    let ppt = settings.pixels_per_tile();
+    let centre = minimap_centre(player.iter().next(), texture.size);
+    // Pixel `x` covers the tile centred on world `x`, i.e. `x - 0.5..x + 0.5`.
+    let corner = frame_position(Vec2::splat(-0.5), centre, ppt);
+    for (mut node, mut map) in maps.iter_mut() {
+        if map.image != texture.image {
+            map.image = texture.image.clone();
+        }
+        let placed = Node {
+            left: Val::Px(corner.x),
+            top: Val::Px(corner.y),
+            width: Val::Px(texture.size.x as f32 * ppt),
+            height: Val::Px(texture.size.y as f32 * ppt),
+            ..node.clone()
+        };
+        if *node != placed {
+            *node = placed;
+        }
+    }
+}
+
+/// World point the minimap is centred on: the player's creature, or the
+/// middle of the map when there is none.
+fn minimap_centre(player: Option<&Transform>, size: UVec2) -> Vec2 {
+    player
+        .map(|transform| transform.translation.xz())
+        .unwrap_or_else(|| size.as_vec2() / 2.0 - 0.5)
+}
+
+/// Places a dot in the [`Minimap`] frame on the player, on every creature
+/// within [`CREATURE_RANGE`] that is not hidden from sight, and on every
+/// [`MinimapMarker`], and removes the dots of the rest.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_minimap_dots(
+    mut commands: Commands,
+    settings: Res<MinimapSettings>,
+    texture: Option<Res<MinimapTexture>>,
+    frames: Query<Entity, With<Minimap>>,
+    player: Query<(Entity, &Transform), With<PlayerControlled>>,
+    creatures: Query<
+        (Entity, &Transform),
+        (
+            With<Creature>,
+            Without<PlayerControlled>,
+            Without<HiddenBySight>,
+        ),
+    >,
+    markers: Query<(Entity, &GlobalTransform, &MinimapMarker)>,
+    mut dots: Query<(Entity, &MinimapDot, &mut Node, &mut BackgroundColor)>,
+) {
+    let (Some(texture), Ok(frame)) = (texture, frames.single()) else {
+        return;
+    };
+    let ppt = settings.pixels_per_tile();
+    let player = player.iter().next();
+    let centre = minimap_centre(player.map(|(_, transform)| transform), texture.size);
+
+    let mut wanted: HashMap<Entity, (Vec2, Color)> = HashMap::new();
+    for (entity, transform) in creatures.iter() {
+        let at = transform.translation.xz();
+        if at.distance(centre) <= CREATURE_RANGE {
+            wanted.insert(entity, (at, CREATURE_DOT_COLOR));
+        }
+    }
+    for (entity, transform, marker) in markers.iter() {
+        wanted.insert(entity, (transform.translation().xz(), marker.0));
+    }
+    // Declared last so the player's dot wins over a marker on the player.
+    if let Some((entity, transform)) = player {
+        wanted.insert(entity, (transform.translation.xz(), PLAYER_DOT_COLOR));
+    }
+
+    for (dot, MinimapDot(target), mut node, mut background) in dots.iter_mut() {
+        let Some((at, color)) = wanted.remove(target) else {
+            commands.entity(dot).despawn();
+            continue;
+        };
+        let corner = frame_position(at, centre, ppt) - DOT_SIZE / 2.0;
+        let (left, top) = (Val::Px(corner.x), Val::Px(corner.y));
+        if node.left != left || node.top != top {
+            node.left = left;
+            node.top = top;
+        }
+        if background.0 != color {
+            background.0 = color;
+        }
+    }
+    for (target, (at, color)) in wanted {
+        let corner = frame_position(at, centre, ppt) - DOT_SIZE / 2.0;
+        commands.entity(frame).with_child((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(corner.x),
+                top: Val::Px(corner.y),
+                width: Val::Px(DOT_SIZE),
+                height: Val::Px(DOT_SIZE),
+                ..default()
+            },
+            BackgroundColor(color),
+            MinimapDot(target),
+        ));
+    }
+}
+
+/// Keeps one [`MinimapLabel`] per zone while zoomed in to at least
+/// [`LABEL_MIN_ZOOM`], following renames and the scrolling map.
+#[allow(clippy::too_many_arguments)]
+fn update_minimap_labels(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    settings: Res<MinimapSettings>,
+    texture: Option<Res<MinimapTexture>>,
+    zones: Option<Res<ZoneIndex>>,
+    frames: Query<Entity, With<Minimap>>,
+    player: Query<&Transform, With<PlayerControlled>>,
+    mut labels: Query<(Entity, &MinimapLabel, &mut Node, &mut Text, &ComputedNode)>,
+) {
+    let (Some(texture), Ok(frame)) = (texture, frames.single()) else {
+        return;
+    };
+    let ppt = settings.pixels_per_tile();
+    let zones = zones.filter(|_| ppt >= LABEL_MIN_ZOOM);
+    let centre = minimap_centre(player.iter().next(), texture.size);
+    let position = |anchor: [i32; 2], size: Vec2| {
+        frame_position(IVec2::from(anchor).as_vec2(), centre, ppt) - size / 2.0
+    };
+
+    let mut unlabelled: HashMap<ZoneId, (String, [i32; 2])> = zones
+        .iter()
+        .flat_map(|zones| zones.iter())
+        .map(|zone| (zone.id, (zone.name.clone(), zone.anchor)))
+        .collect();
+    for (label, MinimapLabel(id), mut node, mut text, computed) in labels.iter_mut() {
+        let Some((name, anchor)) = unlabelled.remove(id) else {
+            commands.entity(label).despawn();
+            continue;
+        };
+        if text.0 != name {
+            text.0 = name;
+        }
+        let corner = position(anchor, computed.size() * computed.inverse_scale_factor());
+        let (left, top) = (Val::Px(corner.x), Val::Px(corner.y));
+        if node.left != left || node.top != top {
+            node.left = left;
+            node.top = top;
+        }
+    }
+    for (id, (name, anchor)) in unlabelled {
+        let corner = position(anchor, Vec2::ZERO);
+        commands.entity(frame).with_child((
+            Text::new(name),
+            TextFont::from_font_size(theme.font_size_small),
+            TextColor(theme.text.with_alpha(0.8)),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(corner.x),
+                top: Val::Px(corner.y),
+                ..default()
+            },
+            MinimapLabel(id),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::color::ColorToPacked;
+
+    use super::*;
+
+    #[test]
+    fn map_is_drawn_then_touched_up_on_mutation() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()));
+        app.init_asset::<Image>();
+        app.add_message::<TileMutated>();
+        app.add_systems(Update, draw_minimap);
+
+        let mut grid = TileGrid::new(4, 3);
+        grid.set(IVec2::new(1, 2), TileKind::Wall);
+        app.insert_resource(grid);
+        app.update();
+
+        let pixel = |app: &App, x, y| {
+            let texture = app.world().resource::<MinimapTexture>();
+            let images = app.world().resource::<Assets<Image>>();
+            let image = images.get(&texture.image).unwrap();
+            image.get_color_at(x, y).unwrap().to_srgba().to_u8_array()
+        };
+        // A colour as the canvas stores it.
+        let drawn = |color: Color| {
+            let mut image = canvas_image(UVec2::ONE, Color::NONE);
+            image.set_color_at(0, 0, color).unwrap();
+            image.get_color_at(0, 0).unwrap().to_srgba().to_u8_array()
+        };
+        let wall = drawn(tile_color(TileKind::Wall));
+        let floor = drawn(tile_color(TileKind::Floor));
+        assert_eq!(
+            app.world().resource::<MinimapTexture>().size,
+            UVec2::new(4, 3)
+        );
+        assert_eq!(pixel(&app, 1, 2), wall);
+        assert_eq!(pixel(&app, 0, 0), floor);
+
+        let door = TileKind::Door { open: false };
+        app.world_mut()
+            .resource_mut::<TileGrid<TileKind>>()
+            .set(IVec2::new(0, 0), door);
+        app.world_mut().write_message(TileMutated {
+            position: IVec2::new(0, 0),
+            kind: door,
+        });
+        app.update();
+        assert_eq!(pixel(&app, 0, 0), drawn(tile_color(door)));
+        assert_eq!(pixel(&app, 1, 2), wall);
+    }
+
+    #[test]
+    fn frame_is_centred_on_the_player() {
+        let centre = Vec2::new(10.0, 4.0);
+        assert_eq!(
+            frame_position(centre, centre, 4.0),
+            Vec2::splat(MINIMAP_SIZE / 2.0)
+        );
+        assert_eq!(
+            frame_position(Vec2::new(12.0, 3.0), centre, 4.0),
+            Vec2::new(MINIMAP_SIZE / 2.0 + 8.0, MINIMAP_SIZE / 2.0 - 4.0)
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Food and water bars for the player's creature.
+
+use bevy::prelude::*;
+use network::Headless;
+use things::{MAX_NEED, Needs, PlayerControlled};
+use ui::UiTheme;
+
+/// Size of each bar, in logical pixels.
+const NEED_BAR_WIDTH: f32 = 120.0;
+const NEED_BAR_HEIGHT: f32 = 8.0;
+
+/// Fraction below which a bar turns red.
+const LOW_NEED: f32 = 0.25;
+
+/// Marker for the column of need bars above the status badges.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct NeedsBars;
+
+pub(crate) fn register_needs_bars(app: &mut App) {
+    app.register_type::<NeedsBars>();
+    app.add_systems(
+        Update,
+        update_needs_bars.run_if(not(resource_exists::<Headless>)),
+    );
+}
+
+/// Label, fill fraction and colour of each bar.
+fn need_rows(needs: &Needs) -> [(&'static str, f32, Color); 2] {
+    let row = |label, level: f32, color| {
+        let fraction = (level / MAX_NEED).clamp(0.0, 1.0);
+        let color = if fraction < LOW_NEED {
+            Color::srgb(0.80, 0.20, 0.15)
+        } else {
+            color
+        };
+        (label, fraction, color)
+    };
+    [
+        row("Food", needs.nutrition, Color::srgb(0.75, 0.55, 0.20)),
+        row("Water", needs.hydration, Color::srgb(0.20, 0.50, 0.85)),
+    ]
+}
+
+/// Rebuilds the [`NeedsBars`] whenever the [`Needs`] of the
+/// [`PlayerControlled`] creature change, and removes them when it has none.
+fn update_needs_bars(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    player: Query<Ref<Needs>, With<PlayerControlled>>,
+    bars: Query<Entity, With<NeedsBars>>,
+) {
+    let needs = player.iter().next();
+    let unchanged = match &needs {
+        Some(needs) => !needs.is_changed(),
+        None => bars.is_empty(),
+    };
+    if unchanged {
+        return;
+    }
+    for bar in bars.iter() {
+        commands.entity(bar).despawn();
+    }
+    let Some(needs) = needs else {
+        return;
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(16.0),
+                bottom: Val::Px(56.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            NeedsBars,
+        ))
+        .with_children(|column| {
+            for (label, fraction, color) in need_rows(&needs) {
+                column
+                    .spawn(Node {
+                        align_items: AlignItems::Center,
+                        column_gap: theme.gap,
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            Text::new(label),
+                            TextFont::from_font_size(theme.font_size_small),
+                            TextColor(theme.text),
+                            Node {
+                                width: Val::Px(48.0),
+                                ..default()
+                            },
+                        ));
+                        row.spawn((
+                            Node {
+                                width: Val::Px(NEED_BAR_WIDTH),
+                                height: Val::Px(NEED_BAR_HEIGHT),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                        ))
+                        .with_child((
+                            Node {
+                                width: Val::Percent(fraction * 100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            BackgroundColor(color),
+                        ));
+                    });
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bars_fill_with_the_need_and_turn_red_when_low() {
+        let [(food, fed, _), (water, watered, color)] = need_rows(&Needs {
+            nutrition: 50.0,
+            hydration: 10.0,
+        });
+        assert_eq!((food, water), ("Food", "Water"));
+        assert_eq!((fed, watered), (0.5, 0.1));
+        assert_eq!(color, Color::srgb(0.80, 0.20, 0.15));
+    }
+}
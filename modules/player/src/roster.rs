@@ -0,0 +1,162 @@
+//! Player list overlay, shown while [`Action::ShowRoster`] is held.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use network::{Client, ConnectionQuality, PlayerRoster};
+use ui::UiTheme;
+
+/// Marker for the player list panel shown while [`Action::ShowRoster`] is held.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct RosterOverlay;
+
+/// Marker for the text node listing the players inside [`RosterOverlay`].
+#[derive(Component)]
+struct RosterText;
+
+pub(crate) fn register_roster_overlay(app: &mut App) {
+    app.register_type::<RosterOverlay>();
+    app.add_systems(
+        Update,
+        (toggle_roster_overlay, update_roster_text)
+            .chain()
+            .run_if(resource_exists::<Client>),
+    );
+}
+
+/// Formats `duration` as `1h 02m`, `3m 07s` or `42s`.
+fn format_connected(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}h {m:02}m")
+    } else if m > 0 {
+        format!("{m}m {s:02}s")
+    } else {
+        format!("{s}s")
+    }
+}
+
+/// One line per player: name, client id, spectator tag, connected time and
+/// round-trip time once the server has measured it.
+fn roster_lines(roster: &PlayerRoster, now: Duration) -> Vec<String> {
+    roster
+        .players
+        .iter()
+        .map(|entry| {
+            let mut line = format!(
+                "{}  #{}{}  {}",
+                entry.name,
+                entry.id.0,
+                if entry.spectator { " (spectating)" } else { "" },
+                format_connected(roster.connected_for(entry, now))
+            );
+            if let Some(rtt_ms) = entry.rtt_ms {
+                line.push_str(&format!("  {rtt_ms} ms"));
+            }
+            line
+        })
+        .collect()
+}
+
+/// Spawns the player list when [`Action::ShowRoster`] is pressed and removes it on
+/// release.
+fn toggle_roster_overlay(
+    mut commands: Commands,
+    input: ActionInput,
+    theme: Res<UiTheme>,
+    overlays: Query<Entity, With<RosterOverlay>>,
+) {
+    if input.just_pressed(Action::ShowRoster) && overlays.is_empty() {
+        commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(15.0),
+                    justify_self: JustifySelf::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(8.0),
+                    padding: theme.panel_padding,
+                    ..default()
+                },
+                BackgroundColor(theme.surface.with_alpha(0.9)),
+                RosterOverlay,
+            ))
+            .with_children(|panel| {
+                panel.spawn((
+                    Text::new("Players"),
+                    TextFont::from_font_size(theme.font_size_body),
+                    TextColor(theme.text),
+                ));
+                panel.spawn((
+                    Text::default(),
+                    TextFont::from_font_size(theme.font_size_small),
+                    TextColor(theme.text_muted),
+                    RosterText,
+                ));
+            });
+    } else if input.just_released(Action::ShowRoster) {
+        for overlay in overlays.iter() {
+            commands.entity(overlay).despawn();
+        }
+    }
+}
+
+/// Rewrites the player list text from [`PlayerRoster`] while it is shown,
+/// followed by our own ping from [`ConnectionQuality`].
+fn update_roster_text(
+    time: Res<Time>,
+    roster: Res<PlayerRoster>,
+    quality: Res<ConnectionQuality>,
+    mut texts: Query<&mut Text, With<RosterText>>,
+) {
+    for mut text in texts.iter_mut() {
+        let lines = roster_lines(&roster, time.elapsed());
+        text.0 = if lines.is_empty() {
+            "No players".to_string()
+        } else {
+            lines.join("\n")
+        };
+        if let Some(rtt) = quality.server {
+            text.0
+                .push_str(&format!("\n\nPing: {} ms", rtt.smoothed.as_millis()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use network::{ClientId, RosterEntry};
+
+    use super::*;
+
+    #[test]
+    fn roster_lines_show_name_id_connected_time_and_rtt() {
+        let roster = PlayerRoster {
+            players: vec![
+                RosterEntry {
+                    id: ClientId(1),
+                    name: "ada".into(),
+                    connected_secs: 3720,
+                    spectator: false,
+                    rtt_ms: Some(42),
+                },
+                RosterEntry {
+                    id: ClientId(4),
+                    name: "bob".into(),
+                    connected_secs: 40,
+                    spectator: true,
+                    rtt_ms: None,
+                },
+            ],
+            received_at: Duration::from_secs(10),
+        };
+
+        assert_eq!(
+            roster_lines(&roster, Duration::from_secs(35)),
+            ["ada  #1  1h 02m  42 ms", "bob  #4 (spectating)  1m 05s"]
+        );
+    }
+}
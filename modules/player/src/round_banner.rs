@@ -0,0 +1,109 @@
+//! Banner showing the round phase while the round is not running: the
+//! lobby countdown, or the outcome of the round until the next lobby.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use network::{RoundState, ServerGameState};
+use ui::UiTheme;
+
+/// Marker for the text node showing the round phase.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct RoundBanner;
+
+pub(crate) fn register_round_banner(app: &mut App) {
+    app.register_type::<RoundBanner>();
+    app.add_systems(Update, (toggle_round_banner, update_round_banner).chain());
+}
+
+/// Text for the current phase, `None` while a round is running.
+fn banner_text(round: &RoundState, now: Duration) -> Option<String> {
+    let secs = round
+        .remaining(now)
+        .map(|left| format!("{:.0}s", left.as_secs_f32().ceil()));
+    match (&round.state, secs) {
+        (ServerGameState::InRound, _) => None,
+        (ServerGameState::Lobby, Some(secs)) => Some(format!("Round starts in {secs}")),
+        (ServerGameState::Lobby, None) => Some("Waiting for the round to start".to_string()),
+        (ServerGameState::RoundOver { summary }, Some(secs)) => {
+            Some(format!("Round over: {summary} (restarting in {secs})"))
+        }
+        (ServerGameState::RoundOver { summary }, None) => Some(format!("Round over: {summary}")),
+    }
+}
+
+/// Spawns the banner when the round stops running and removes it once it
+/// runs again.
+fn toggle_round_banner(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    round: Option<Res<RoundState>>,
+    banners: Query<Entity, With<RoundBanner>>,
+) {
+    let running = round.is_none_or(|round| round.state == ServerGameState::InRound);
+    if running {
+        for banner in banners.iter() {
+            commands.entity(banner).despawn();
+        }
+    } else if banners.is_empty() {
+        commands.spawn((
+            Text::default(),
+            TextFont::from_font_size(theme.font_size_body),
+            TextColor(theme.text),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(64.0),
+                justify_self: JustifySelf::Center,
+                padding: theme.panel_padding,
+                ..default()
+            },
+            BackgroundColor(theme.surface.with_alpha(0.9)),
+            RoundBanner,
+        ));
+    }
+}
+
+/// Rewrites the banner's text and countdown.
+fn update_round_banner(
+    time: Res<Time>,
+    round: Option<Res<RoundState>>,
+    mut banners: Query<&mut Text, With<RoundBanner>>,
+) {
+    let Some(text) = round.and_then(|round| banner_text(&round, time.elapsed())) else {
+        return;
+    };
+    for mut banner in banners.iter_mut() {
+        banner.0.clone_from(&text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banner_shows_the_countdown_outside_rounds() {
+        let lobby = RoundState {
+            state: ServerGameState::Lobby,
+            ends_at: Some(Duration::from_secs(30)),
+        };
+        assert_eq!(
+            banner_text(&lobby, Duration::from_millis(25_200)).as_deref(),
+            Some("Round starts in 5s")
+        );
+
+        let over = RoundState {
+            state: ServerGameState::RoundOver {
+                summary: "The crew wins: the shuttle has arrived".into(),
+            },
+            ends_at: Some(Duration::from_secs(20)),
+        };
+        assert_eq!(
+            banner_text(&over, Duration::from_secs(10)).as_deref(),
+            Some("Round over: The crew wins: the shuttle has arrived (restarting in 10s)")
+        );
+
+        assert_eq!(banner_text(&RoundState::default(), Duration::ZERO), None);
+    }
+}
@@ -0,0 +1,96 @@
+//! Banner announcing a server shutdown, shown while [`ShutdownNotice`] is
+//! present.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use network::ShutdownNotice;
+use ui::UiTheme;
+
+/// Marker for the text node announcing a server shutdown.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct ShutdownBanner;
+
+pub(crate) fn register_shutdown_banner(app: &mut App) {
+    app.register_type::<ShutdownBanner>();
+    app.add_systems(
+        Update,
+        (toggle_shutdown_banner, update_shutdown_banner).chain(),
+    );
+}
+
+/// Announcement followed by a countdown in whole seconds.
+fn banner_text(notice: &ShutdownNotice, now: Duration) -> String {
+    let secs = notice.remaining(now).as_secs_f32().ceil();
+    format!("{} (disconnecting in {secs:.0}s)", notice.message)
+}
+
+/// Spawns the banner when a [`ShutdownNotice`] arrives and removes it once
+/// the notice is gone.
+fn toggle_shutdown_banner(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    notice: Option<Res<ShutdownNotice>>,
+    banners: Query<Entity, With<ShutdownBanner>>,
+) {
+    match notice {
+        Some(_) if banners.is_empty() => {
+            commands.spawn((
+                Text::default(),
+                TextFont::from_font_size(theme.font_size_body),
+                TextColor(theme.text),
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(16.0),
+                    justify_self: JustifySelf::Center,
+                    padding: theme.panel_padding,
+                    ..default()
+                },
+                BackgroundColor(theme.surface.with_alpha(0.9)),
+                ShutdownBanner,
+            ));
+        }
+        None => {
+            for banner in banners.iter() {
+                commands.entity(banner).despawn();
+            }
+        }
+        Some(_) => {}
+    }
+}
+
+/// Rewrites the banner's countdown.
+fn update_shutdown_banner(
+    time: Res<Time>,
+    notice: Option<Res<ShutdownNotice>>,
+    mut banners: Query<&mut Text, With<ShutdownBanner>>,
+) {
+    let Some(notice) = notice else {
+        return;
+    };
+    for mut text in banners.iter_mut() {
+        text.0 = banner_text(&notice, time.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banner_counts_down_in_whole_seconds() {
+        let notice = ShutdownNotice {
+            message: "Server restarting".into(),
+            closes_at: Duration::from_secs(30),
+        };
+        assert_eq!(
+            banner_text(&notice, Duration::from_millis(25_200)),
+            "Server restarting (disconnecting in 5s)"
+        );
+        assert_eq!(
+            banner_text(&notice, Duration::from_secs(31)),
+            "Server restarting (disconnecting in 0s)"
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! What the player's creature can see.
+//!
+//! Walls hide whatever lies behind them: [`update_field_of_view`] keeps a
+//! [`FieldOfView`] around the cell of the [`PlayerControlled`] creature, over
+//! the tiles of the deck it is on.  The `lighting` module shades the tiles
+//! outside it, and [`hide_unseen_things`] marks the things standing on them
+//! [`HiddenBySight`], which `tiles` hides together with what is on other
+//! decks, and hides their nameplates.  Observers and ghosts see everything,
+//! so they have no field of view.
+
+use bevy::prelude::*;
+use creatures::Observer;
+use network::Headless;
+use things::{PlayerControlled, Thing};
+use tiles::{Deck, FieldOfView, HiddenBySight, TileFlags, UpperDecks};
+use ui::OverlayTarget;
+
+use crate::Nameplate;
+
+/// How far the player can see, in tiles.
+pub const SIGHT_RADIUS: i32 = 12;
+
+pub(crate) fn register_sight(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            update_field_of_view,
+            hide_unseen_things,
+            hide_unseen_nameplates,
+        )
+            .chain()
+            .run_if(not(resource_exists::<Headless>)),
+    );
+}
+
+/// Tile cell under a world position.
+fn cell_of(translation: Vec3) -> IVec2 {
+    IVec2::new(translation.x.round() as i32, translation.z.round() as i32)
+}
+
+/// Recomputes the [`FieldOfView`] when the player's creature enters another
+/// cell or deck or that deck's tiles change, and removes it while there is
+/// no map or the player is an [`Observer`].
+#[allow(clippy::type_complexity)]
+fn update_field_of_view(
+    mut commands: Commands,
+    flags: Option<Res<TileFlags>>,
+    upper: Option<Res<UpperDecks>>,
+    fov: Option<Res<FieldOfView>>,
+    mut viewed_deck: Local<Deck>,
+    player: Query<(&Transform, Option<&Deck>, Has<Observer>), With<PlayerControlled>>,
+) {
+    let eye = player
+        .iter()
+        .next()
+        .filter(|(_, _, observer)| !observer)
+        .map(|(transform, deck, _)| {
+            (
+                cell_of(transform.translation),
+                deck.copied().unwrap_or_default(),
+            )
+        });
+    let (Some(flags), Some((eye, deck))) = (flags, eye) else {
+        if fov.is_some() {
+            commands.remove_resource::<FieldOfView>();
+        }
+        return;
+    };
+    let tiles_changed = if deck == Deck::MAIN {
+        flags.is_changed()
+    } else {
+        upper.as_ref().is_some_and(|upper| upper.is_changed())
+    };
+    if fov.is_some_and(|fov| fov.origin() == eye) && *viewed_deck == deck && !tiles_changed {
+        return;
+    }
+    *viewed_deck = deck;
+    let fov = match upper.as_ref().and_then(|upper| upper.get(deck)) {
+        Some(layers) => {
+            FieldOfView::compute(&TileFlags::from_grid(&layers.structures), eye, SIGHT_RADIUS)
+        }
+        None => FieldOfView::compute(&flags, eye, SIGHT_RADIUS),
+    };
+    commands.insert_resource(fov);
+}
+
+/// Marks every thing outside the [`FieldOfView`] [`HiddenBySight`], and
+/// unmarks those back in view.  Things held or carried follow their parent.
+#[allow(clippy::type_complexity)]
+fn hide_unseen_things(
+    mut commands: Commands,
+    fov: Option<Res<FieldOfView>>,
+    things: Query<
+        (Entity, &Transform, Has<HiddenBySight>),
+        (With<Thing>, Without<ChildOf>, Without<PlayerControlled>),
+    >,
+) {
+    for (entity, transform, hidden) in things.iter() {
+        let seen = fov
+            .as_ref()
+            .is_none_or(|fov| fov.is_visible(cell_of(transform.translation)));
+        match (seen, hidden) {
+            (false, false) => {
+                commands.entity(entity).insert(HiddenBySight);
+            }
+            (true, true) => {
+                commands.entity(entity).remove::<HiddenBySight>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Hides the nameplates of things hidden by [`hide_unseen_things`].
+fn hide_unseen_nameplates(
+    hidden: Query<(), With<HiddenBySight>>,
+    mut nameplates: Query<(&OverlayTarget, &mut Node), With<Nameplate>>,
+) {
+    for (target, mut node) in nameplates.iter_mut() {
+        let display = if hidden.contains(target.0) {
+            Display::None
+        } else {
+            Display::Flex
+        };
+        if node.display != display {
+            node.display = display;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tiles::{DeckLayers, OffDeck, TileGrid, TileKind, apply_hiding};
+
+    use super::*;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        register_sight(&mut app);
+        app.add_systems(PostUpdate, apply_hiding);
+        app
+    }
+
+    /// A 9×3 corridor, cut in two by a wall at x = 4 if `walled`.
+    fn corridor(walled: bool) -> TileGrid<TileKind> {
+        let mut grid = TileGrid::new(9, 3);
+        for y in 0..3 {
+            for x in 0..9 {
+                let kind = if walled && x == 4 {
+                    TileKind::Wall
+                } else {
+                    TileKind::Floor
+                };
+                grid.set(IVec2::new(x, y), kind);
+            }
+        }
+        grid
+    }
+
+    fn spawn_thing(app: &mut App, x: f32) -> Entity {
+        app.world_mut()
+            .spawn((
+                Thing { kind: 1 },
+                Transform::from_xyz(x, 0.5, 1.0),
+                Visibility::default(),
+            ))
+            .id()
+    }
+
+    fn visibility(app: &App, entity: Entity) -> Visibility {
+        *app.world().get::<Visibility>(entity).unwrap()
+    }
+
+    #[test]
+    fn things_behind_walls_are_hidden_until_seen() {
+        let mut app = test_app();
+        app.insert_resource(TileFlags::from_grid(&corridor(true)));
+
+        let player = app
+            .world_mut()
+            .spawn((PlayerControlled, Transform::from_xyz(1.0, 0.8, 1.0)))
+            .id();
+        let near = spawn_thing(&mut app, 3.0);
+        let far = spawn_thing(&mut app, 7.0);
+        app.update();
+
+        assert_eq!(visibility(&app, near), Visibility::Inherited);
+        assert_eq!(visibility(&app, far), Visibility::Hidden);
+        assert!(app.world().get::<HiddenBySight>(far).is_some());
+
+        app.world_mut().entity_mut(player).insert(Observer);
+        app.update();
+        assert!(!app.world().contains_resource::<FieldOfView>());
+        assert_eq!(visibility(&app, far), Visibility::Inherited);
+        assert!(app.world().get::<HiddenBySight>(far).is_none());
+    }
+
+    #[test]
+    fn things_stay_hidden_while_off_deck_or_out_of_sight() {
+        let mut app = test_app();
+        app.insert_resource(TileFlags::from_grid(&corridor(true)));
+        app.world_mut()
+            .spawn((PlayerControlled, Transform::from_xyz(1.0, 0.8, 1.0)));
+        let near = spawn_thing(&mut app, 3.0);
+        let far = spawn_thing(&mut app, 7.0);
+        app.world_mut().entity_mut(near).insert(OffDeck);
+        app.world_mut().entity_mut(far).insert(OffDeck);
+        app.update();
+        app.update();
+
+        assert_eq!(
+            visibility(&app, near),
+            Visibility::Hidden,
+            "In sight but on another deck"
+        );
+        assert_eq!(visibility(&app, far), Visibility::Hidden);
+
+        app.world_mut().entity_mut(near).remove::<OffDeck>();
+        app.world_mut().entity_mut(far).remove::<OffDeck>();
+        app.update();
+        assert_eq!(visibility(&app, near), Visibility::Inherited);
+        assert_eq!(
+            visibility(&app, far),
+            Visibility::Hidden,
+            "Back on the deck but still behind the wall"
+        );
+    }
+
+    #[test]
+    fn the_view_is_cast_over_the_players_deck() {
+        let mut app = test_app();
+        app.insert_resource(TileFlags::from_grid(&corridor(false)));
+        let mut upper = DeckLayers::new(9, 3);
+        upper.structures = corridor(true);
+        app.insert_resource(UpperDecks(vec![upper]));
+
+        let player = app
+            .world_mut()
+            .spawn((
+                PlayerControlled,
+                Transform::from_xyz(1.0, 0.8, 1.0),
+                Deck::MAIN,
+            ))
+            .id();
+        let far = spawn_thing(&mut app, 7.0);
+        app.update();
+        assert_eq!(visibility(&app, far), Visibility::Inherited);
+
+        app.world_mut().entity_mut(player).insert(Deck(1));
+        app.update();
+        assert_eq!(
+            visibility(&app, far),
+            Visibility::Hidden,
+            "The upper deck's wall blocks the view"
+        );
+    }
+}
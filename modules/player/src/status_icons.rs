@@ -0,0 +1,102 @@
+//! Row of badges showing the status effects on the player's creature.
+
+use bevy::prelude::*;
+use network::Headless;
+use things::{PlayerControlled, StatusEffect, StatusEffects, StatusKind};
+use ui::UiTheme;
+
+/// Marker for the row of status badges in the bottom-left corner.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct StatusIconBar;
+
+pub(crate) fn register_status_icons(app: &mut App) {
+    app.register_type::<StatusIconBar>();
+    app.add_systems(
+        Update,
+        update_status_icons.run_if(not(resource_exists::<Headless>)),
+    );
+}
+
+/// Badge background for each kind of effect.
+fn badge_color(kind: StatusKind) -> Color {
+    match kind {
+        StatusKind::Stun => Color::srgb(0.75, 0.65, 0.15),
+        StatusKind::Slow => Color::srgb(0.20, 0.45, 0.75),
+        StatusKind::Burning => Color::srgb(0.80, 0.30, 0.10),
+    }
+}
+
+/// Effect name, followed by the stack count when stacked more than once.
+fn badge_text(effect: &StatusEffect) -> String {
+    match effect.stacks {
+        0 | 1 => effect.kind.label().to_string(),
+        stacks => format!("{} x{stacks}", effect.kind.label()),
+    }
+}
+
+/// Rebuilds the [`StatusIconBar`] whenever the [`StatusEffects`] of the
+/// [`PlayerControlled`] creature change, and removes it once none are left.
+fn update_status_icons(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    player: Query<Ref<StatusEffects>, With<PlayerControlled>>,
+    bars: Query<Entity, With<StatusIconBar>>,
+) {
+    let effects = player.iter().next();
+    let unchanged = match &effects {
+        Some(effects) => !effects.is_changed(),
+        None => bars.is_empty(),
+    };
+    if unchanged {
+        return;
+    }
+    for bar in bars.iter() {
+        commands.entity(bar).despawn();
+    }
+    let Some(effects) = effects.filter(|effects| !effects.0.is_empty()) else {
+        return;
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(16.0),
+                bottom: Val::Px(16.0),
+                column_gap: theme.gap,
+                ..default()
+            },
+            StatusIconBar,
+        ))
+        .with_children(|bar| {
+            for effect in &effects.0 {
+                bar.spawn((
+                    Text::new(badge_text(effect)),
+                    TextFont::from_font_size(theme.font_size_small),
+                    TextColor(theme.text),
+                    Node {
+                        padding: theme.button_padding,
+                        ..default()
+                    },
+                    BackgroundColor(badge_color(effect.kind)),
+                ));
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn badges_show_stacks_beyond_the_first() {
+        let effect = |kind, stacks| StatusEffect {
+            kind,
+            stacks,
+            remaining: 1.0,
+        };
+        assert_eq!(badge_text(&effect(StatusKind::Stun, 1)), "Stunned");
+        assert_eq!(badge_text(&effect(StatusKind::Burning, 3)), "Burning x3");
+    }
+}
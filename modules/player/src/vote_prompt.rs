@@ -0,0 +1,219 @@
+//! Prompt for starting and answering votes, opened with [`Action::Vote`].
+//!
+//! Typing there goes to the prompt instead of the key bindings.  Enter
+//! sends the typed command on the votes stream and Escape cancels:
+//!
+//! - `restart`: vote to restart the round,
+//! - `map <file>`: vote to change the map,
+//! - `kick <player>`: vote to kick the player of that name,
+//! - `yes` / `no`: vote on the running vote.
+//!
+//! How the vote goes is announced by the server.
+
+use bevy::input::ButtonState;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use network::{Client, PlayerRoster, StreamSender, VoteKind, VoteRequest};
+use ui::UiTheme;
+
+/// Longest command the prompt accepts, in characters.
+const MAX_COMMAND_LEN: usize = 64;
+
+/// The vote command being typed, while the prompt is open.
+#[derive(Resource, Debug, Default)]
+pub struct VotePrompt {
+    pub open: bool,
+    pub text: String,
+}
+
+/// Marker for the prompt's text node.
+#[derive(Component)]
+struct VotePromptPanel;
+
+pub(crate) fn register_vote_prompt(app: &mut App) {
+    app.init_resource::<VotePrompt>();
+    app.add_systems(
+        PreUpdate,
+        type_vote
+            .after(bevy::input::InputSystems)
+            .run_if(resource_exists::<Client>),
+    );
+    app.add_systems(
+        Update,
+        (open_vote_prompt, update_vote_prompt_panel)
+            .chain()
+            .run_if(resource_exists::<Client>),
+    );
+}
+
+/// The request `command` stands for; `Err` explains what was wrong with it.
+/// Players to kick are looked up by name in `roster`.
+fn parse_vote_command(command: &str, roster: &PlayerRoster) -> Result<VoteRequest, String> {
+    let command = command.trim();
+    let (verb, rest) = command
+        .split_once(char::is_whitespace)
+        .map_or((command, ""), |(verb, rest)| (verb, rest.trim()));
+    match (verb.to_lowercase().as_str(), rest) {
+        ("yes" | "y", "") => Ok(VoteRequest::Cast { yes: true }),
+        ("no" | "n", "") => Ok(VoteRequest::Cast { yes: false }),
+        ("restart", "") => Ok(VoteRequest::Start(VoteKind::RestartRound)),
+        ("map", map) if !map.is_empty() => Ok(VoteRequest::Start(VoteKind::ChangeMap {
+            map: map.to_string(),
+        })),
+        ("kick", name) if !name.is_empty() => roster
+            .players
+            .iter()
+            .find(|entry| entry.name.eq_ignore_ascii_case(name))
+            .map(|entry| VoteRequest::Start(VoteKind::Kick { player: entry.id }))
+            .ok_or_else(|| format!("no player called \"{name}\"")),
+        _ => Err(format!(
+            "unknown vote \"{command}\"; try restart, map <file>, kick <player>, yes or no"
+        )),
+    }
+}
+
+/// Opens the prompt on [`Action::Vote`].
+fn open_vote_prompt(actions: ActionInput, mut prompt: ResMut<VotePrompt>) {
+    if actions.just_pressed(Action::Vote) && !prompt.open {
+        prompt.open = true;
+        prompt.text.clear();
+    }
+}
+
+/// Types into the open prompt and sends the command on Enter.
+///
+/// Runs in `PreUpdate` after input is collected, and releases every key
+/// while the prompt is open so that typing does not also move the player
+/// or trigger other actions.
+fn type_vote(
+    mut prompt: ResMut<VotePrompt>,
+    mut typed: MessageReader<KeyboardInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    roster: Res<PlayerRoster>,
+    sender: Option<Res<StreamSender<VoteRequest>>>,
+) {
+    if !prompt.open {
+        typed.clear();
+        return;
+    }
+    keys.reset_all();
+    for input in typed.read() {
+        if input.state != ButtonState::Pressed {
+            continue;
+        }
+        match &input.logical_key {
+            Key::Escape => {
+                prompt.open = false;
+                return;
+            }
+            Key::Enter => {
+                prompt.open = false;
+                let request = match parse_vote_command(&prompt.text, &roster) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        info!("Vote not sent: {e}");
+                        return;
+                    }
+                };
+                if let Some(sender) = sender.as_ref()
+                    && let Err(e) = sender.send(&request)
+                {
+                    error!("Failed to send vote request: {e}");
+                }
+                return;
+            }
+            Key::Backspace => {
+                prompt.text.pop();
+            }
+            _ => {
+                let Some(text) = &input.text else {
+                    continue;
+                };
+                for c in text.chars().filter(|c| !c.is_control()) {
+                    if prompt.text.chars().count() < MAX_COMMAND_LEN {
+                        prompt.text.push(c);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Shows the prompt at the top of the screen while it is open.
+fn update_vote_prompt_panel(
+    mut commands: Commands,
+    prompt: Res<VotePrompt>,
+    theme: Res<UiTheme>,
+    mut panel_q: Query<(&mut Text, &mut Visibility), With<VotePromptPanel>>,
+) {
+    let Ok((mut text, mut visibility)) = panel_q.single_mut() else {
+        commands.spawn((
+            Text::default(),
+            TextFont::from_font_size(theme.font_size_body),
+            TextColor(theme.text),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(35.0),
+                top: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(theme.background.with_alpha(0.9)),
+            Visibility::Hidden,
+            VotePromptPanel,
+        ));
+        return;
+    };
+    if !prompt.open {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+    let line = format!(
+        "Vote: {}_\nrestart, map <file>, kick <player>, yes or no; Enter to send, Esc to cancel",
+        prompt.text
+    );
+    if text.0 != line {
+        text.0 = line;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use network::{ClientId, RosterEntry};
+
+    use super::*;
+
+    #[test]
+    fn vote_commands_name_players_from_the_roster() {
+        let roster = PlayerRoster {
+            players: vec![RosterEntry {
+                id: ClientId(4),
+                name: "Ann".into(),
+                connected_secs: 0,
+                spectator: false,
+                rtt_ms: None,
+            }],
+            ..default()
+        };
+        assert_eq!(
+            parse_vote_command(" Kick ann ", &roster),
+            Ok(VoteRequest::Start(VoteKind::Kick {
+                player: ClientId(4)
+            }))
+        );
+        assert_eq!(
+            parse_vote_command("map maps/cargo.station.ron", &roster),
+            Ok(VoteRequest::Start(VoteKind::ChangeMap {
+                map: "maps/cargo.station.ron".into()
+            }))
+        );
+        assert_eq!(
+            parse_vote_command("y", &roster),
+            Ok(VoteRequest::Cast { yes: true })
+        );
+        assert!(parse_vote_command("kick bob", &roster).is_err());
+        assert!(parse_vote_command("restart now", &roster).is_err());
+    }
+}
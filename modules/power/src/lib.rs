@@ -0,0 +1,399 @@
+use std::collections::HashSet;
+
+use atmospherics::Vent;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use lighting::LightSource;
+use network::{
+    Client, ClientId, ModuleReadySent, NetworkReceive, NetworkSend, PlayerEvent, Server,
+    StreamBudget, StreamDef, StreamDirection, StreamPriority, StreamReader, StreamRegistry,
+    StreamSender, stream_tag,
+};
+use things::cell_of;
+use wincode::{SchemaRead, SchemaWrite};
+
+mod solver;
+pub use solver::{ConsumerSample, PowerNetworkStats, PowerSolution, solve};
+
+/// Stream tag for the server→client power stream, derived from its name.
+pub const POWER_STREAM_TAG: u8 = stream_tag("power");
+
+/// Per-client allowance on the power stream, in bytes per network tick.
+const POWER_BYTES_PER_TICK: usize = 1024;
+
+/// Seconds between broadcasts of [`PowerGridStats`].
+const STATS_INTERVAL: f32 = 1.0;
+
+/// Wire format for the server→client power stream.
+#[derive(Debug, Clone, SchemaRead, SchemaWrite)]
+pub enum PowerStreamMessage {
+    /// Load figures for every cable network, replacing the previous set.
+    NetworkStats { networks: Vec<PowerNetworkStats> },
+}
+
+/// A cable segment.  Cables in 4-adjacent cells join into one network.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct Cable;
+
+/// Feeds `output` into the network of the cable in its cell.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct PowerGenerator {
+    pub output: f32,
+}
+
+/// A device that needs power to work.
+///
+/// The server sets `powered` every tick from the network of the cable in
+/// the device's cell.  Devices read it to switch themselves on and off;
+/// this module passes it on to the [`LightSource`] or [`Vent`] on the same
+/// entity, and [`DoorPower`] reads it for [`DoorMotor`]s.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct PowerConsumer {
+    /// Power drawn while running.
+    pub demand: f32,
+    /// Consumers with a higher priority are supplied first when a network
+    /// cannot cover all of its demand.
+    pub priority: u8,
+    /// Whether the device received its full demand on the last tick.
+    pub powered: bool,
+}
+
+impl PowerConsumer {
+    /// An unpowered consumer with the default priority.
+    pub fn new(demand: f32) -> Self {
+        Self {
+            demand,
+            priority: 0,
+            powered: false,
+        }
+    }
+}
+
+/// Drives the door in its cell.  Stands in the doorway with a
+/// [`PowerConsumer`]; while the consumer is unpowered the door can be
+/// neither opened nor closed.  Doors without a motor are worked by hand.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct DoorMotor;
+
+/// Whether doors have power to move, for the systems that open and close
+/// them.
+#[derive(SystemParam)]
+pub struct DoorPower<'w, 's> {
+    motors: Query<'w, 's, (&'static PowerConsumer, &'static GlobalTransform), With<DoorMotor>>,
+}
+
+impl DoorPower<'_, '_> {
+    /// Whether the door at `cell` may move: every [`DoorMotor`] in the cell
+    /// is powered.  Doors without a motor always may.
+    pub fn door(&self, cell: IVec2) -> bool {
+        self.motors
+            .iter()
+            .filter(|(_, transform)| cell_of(transform.translation()) == cell)
+            .all(|(consumer, _)| consumer.powered)
+    }
+}
+
+/// Load figures for every cable network, ordered by anchor.
+///
+/// Written by the solver on the server and replaced by each
+/// [`PowerStreamMessage::NetworkStats`] on clients.  Intended for
+/// engineering displays.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct PowerGridStats {
+    pub networks: Vec<PowerNetworkStats>,
+}
+
+/// Server-side system: solves supply and demand for every cable network and
+/// updates each [`PowerConsumer`]'s `powered` flag.
+///
+/// Runs every `FixedUpdate` tick.  Flags and stats are only written when
+/// they change so change detection stays meaningful for devices.
+fn solve_power(
+    cables: Query<&GlobalTransform, With<Cable>>,
+    generators: Query<(&PowerGenerator, &GlobalTransform)>,
+    mut consumers: Query<(Entity, &mut PowerConsumer, &GlobalTransform)>,
+    mut stats: ResMut<PowerGridStats>,
+) {
+    let cable_cells: HashSet<IVec2> = cables.iter().map(|t| cell_of(t.translation())).collect();
+    let generator_cells: Vec<(IVec2, f32)> = generators
+        .iter()
+        .map(|(generator, t)| (cell_of(t.translation()), generator.output))
+        .collect();
+    let samples: Vec<ConsumerSample<Entity>> = consumers
+        .iter()
+        .map(|(entity, consumer, t)| ConsumerSample {
+            key: entity,
+            cell: cell_of(t.translation()),
+            demand: consumer.demand,
+            priority: consumer.priority,
+        })
+        .collect();
+
+    let solution = solve(&cable_cells, &generator_cells, &samples);
+
+    for (entity, mut consumer, _) in consumers.iter_mut() {
+        let powered = solution.powered.contains(&entity);
+        if consumer.powered != powered {
+            consumer.powered = powered;
+        }
+    }
+    if stats.networks != solution.networks {
+        stats.networks = solution.networks;
+    }
+}
+
+/// Passes each [`PowerConsumer`]'s supply on to the light or vent it
+/// powers.  Only their `powered` flags are touched; whether a light is
+/// switched on stays up to its user.
+fn switch_powered_devices(
+    mut lights: Query<(&PowerConsumer, &mut LightSource), Changed<PowerConsumer>>,
+    mut vents: Query<(&PowerConsumer, &mut Vent), Changed<PowerConsumer>>,
+) {
+    for (consumer, mut light) in lights.iter_mut() {
+        if light.powered != consumer.powered {
+            light.powered = consumer.powered;
+        }
+    }
+    for (consumer, mut vent) in vents.iter_mut() {
+        if vent.powered != consumer.powered {
+            vent.powered = consumer.powered;
+        }
+    }
+}
+
+fn cleanup_power(mut commands: Commands) {
+    commands.insert_resource(PowerGridStats::default());
+}
+
+/// Plugin for the power network.
+///
+/// [`Cable`] things form networks; [`PowerGenerator`]s feed them and
+/// [`PowerConsumer`]s draw from them.  The server solves every network each
+/// fixed tick, keeps powered lights and vents in step with their supply
+/// and broadcasts [`PowerGridStats`] once a second on the power stream.
+/// Door systems ask [`DoorPower`] whether a door's [`DoorMotor`] can move
+/// it.
+pub struct PowerPlugin<S: States + Copy> {
+    state: S,
+}
+
+impl<S: States + Copy> PowerPlugin<S> {
+    /// Creates the plugin gated on `state`.
+    pub fn in_state(state: S) -> Self {
+        Self { state }
+    }
+}
+
+impl<S: States + Copy> Plugin for PowerPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Cable>();
+        app.register_type::<PowerGenerator>();
+        app.register_type::<PowerConsumer>();
+        app.register_type::<DoorMotor>();
+        app.init_resource::<PowerGridStats>();
+        app.init_resource::<PendingPowerSyncs>();
+        app.insert_resource(PowerStatsTimer(Timer::from_seconds(
+            STATS_INTERVAL,
+            TimerMode::Repeating,
+        )));
+
+        app.add_systems(
+            FixedUpdate,
+            (solve_power, switch_powered_devices)
+                .chain()
+                .run_if(in_state(self.state))
+                .run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            NetworkReceive,
+            handle_power_updates.run_if(resource_exists::<Client>),
+        );
+        app.add_systems(
+            NetworkReceive,
+            send_power_stats_on_connect.run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            NetworkSend,
+            broadcast_power_stats.run_if(resource_exists::<Server>),
+        );
+
+        // Register the server→client power stream. Requires NetworkPlugin to be added first.
+        let mut registry = app.world_mut().get_resource_mut::<StreamRegistry>().expect(
+            "PowerPlugin requires NetworkPlugin to be added before it (StreamRegistry not found)",
+        );
+        let (sender, reader): (
+            StreamSender<PowerStreamMessage>,
+            StreamReader<PowerStreamMessage>,
+        ) = registry.register_with_budget(
+            StreamDef {
+                tag: POWER_STREAM_TAG,
+                name: "power",
+                direction: StreamDirection::ServerToClient,
+            },
+            StreamBudget::new(StreamPriority::Low, POWER_BYTES_PER_TICK),
+        );
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+
+        app.add_systems(OnExit(self.state), cleanup_power);
+    }
+}
+
+/// Client-side system: stores the latest network stats from the power stream.
+fn handle_power_updates(
+    mut reader: ResMut<StreamReader<PowerStreamMessage>>,
+    mut stats: ResMut<PowerGridStats>,
+) {
+    for msg in reader.drain() {
+        match msg {
+            PowerStreamMessage::NetworkStats { networks } => stats.networks = networks,
+        }
+    }
+}
+
+/// Clients waiting for their initial power stats.
+#[derive(Resource, Default)]
+struct PendingPowerSyncs(Vec<ClientId>);
+
+/// Server-side system: sends the current stats and [`StreamReady`] to each
+/// joining client, then reports [`ModuleReadySent`].
+///
+/// [`StreamReady`]: network::StreamReady
+fn send_power_stats_on_connect(
+    mut events: MessageReader<PlayerEvent>,
+    sender: Option<Res<StreamSender<PowerStreamMessage>>>,
+    stats: Res<PowerGridStats>,
+    mut module_ready: MessageWriter<ModuleReadySent>,
+    mut pending: ResMut<PendingPowerSyncs>,
+) {
+    for event in events.read() {
+        if let PlayerEvent::Joined { id, .. } = event {
+            pending.0.push(*id);
+        }
+    }
+    if pending.0.is_empty() {
+        return;
+    }
+    let Some(sender) = sender.as_deref() else {
+        error!(
+            "No PowerStreamMessage sender available; {} client(s) waiting",
+            pending.0.len()
+        );
+        return;
+    };
+
+    for client in std::mem::take(&mut pending.0) {
+        let msg = PowerStreamMessage::NetworkStats {
+            networks: stats.networks.clone(),
+        };
+        if let Err(e) = sender.send_to(client, &msg) {
+            error!(
+                "Failed to send power stats to ClientId({}): {}",
+                client.0, e
+            );
+            continue;
+        }
+        if let Err(e) = sender.send_stream_ready_to(client) {
+            error!(
+                "Failed to send StreamReady to ClientId({}): {}",
+                client.0, e
+            );
+            continue;
+        }
+        module_ready.write(ModuleReadySent { client });
+    }
+}
+
+/// Drives the periodic [`PowerGridStats`] broadcast.
+#[derive(Resource)]
+struct PowerStatsTimer(Timer);
+
+/// Server-side system: broadcasts [`PowerGridStats`] every
+/// [`STATS_INTERVAL`] seconds as a deferrable state frame.
+fn broadcast_power_stats(
+    time: Res<Time>,
+    mut timer: ResMut<PowerStatsTimer>,
+    sender: Option<Res<StreamSender<PowerStreamMessage>>>,
+    stats: Res<PowerGridStats>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Some(sender) = sender else {
+        return;
+    };
+    let msg = PowerStreamMessage::NetworkStats {
+        networks: stats.networks.clone(),
+    };
+    if let Err(e) = sender.broadcast_state(&msg) {
+        error!("Failed to broadcast power stats: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn devices_follow_their_supply() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<PowerGridStats>();
+        app.add_systems(Update, (solve_power, switch_powered_devices).chain());
+
+        let at = |x: f32| GlobalTransform::from_xyz(x, 0.0, 0.0);
+        for x in 0..3 {
+            app.world_mut().spawn((Cable, at(x as f32)));
+        }
+        let generator = app
+            .world_mut()
+            .spawn((PowerGenerator { output: 100.0 }, at(0.0)))
+            .id();
+        let light = app
+            .world_mut()
+            .spawn((
+                PowerConsumer::new(60.0),
+                LightSource::new(4.0, 1.0),
+                at(2.0),
+            ))
+            .id();
+
+        let vent = app
+            .world_mut()
+            .spawn((PowerConsumer::new(20.0), Vent::new(10.0), at(1.0)))
+            .id();
+        app.world_mut()
+            .spawn((PowerConsumer::new(20.0), DoorMotor, at(0.0)));
+        let door_moves = |app: &mut App, x: i32| {
+            app.world_mut()
+                .run_system_once(move |power: DoorPower| power.door(IVec2::new(x, 0)))
+                .unwrap()
+        };
+
+        app.update();
+        assert!(app.world().get::<LightSource>(light).unwrap().powered);
+        assert!(app.world().get::<Vent>(vent).unwrap().powered);
+        assert!(door_moves(&mut app, 0));
+        let stats = app.world().resource::<PowerGridStats>();
+        assert_eq!(stats.networks.len(), 1);
+        assert_eq!(stats.networks[0].load, 100.0);
+
+        app.world_mut().entity_mut(generator).despawn();
+        app.update();
+        assert!(!app.world().get::<PowerConsumer>(light).unwrap().powered);
+        let source = app.world().get::<LightSource>(light).unwrap();
+        assert!(!source.powered);
+        assert!(source.enabled, "the switch is left to the user");
+        assert!(!app.world().get::<Vent>(vent).unwrap().powered);
+        assert!(!door_moves(&mut app, 0));
+        assert!(
+            door_moves(&mut app, 2),
+            "doors without a motor move by hand"
+        );
+    }
+}
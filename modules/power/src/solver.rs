@@ -0,0 +1,195 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use bevy::prelude::*;
+use wincode::{SchemaRead, SchemaWrite};
+
+/// Load figures for one connected cable network, as replicated to clients.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub struct PowerNetworkStats {
+    /// Lowest cable cell of the network (by row, then column); identifies
+    /// the network for as long as that cable stays.
+    pub anchor: (i32, i32),
+    /// Number of cable cells.
+    pub cables: u32,
+    /// Total generator output.
+    pub supply: f32,
+    /// Total consumer demand.
+    pub demand: f32,
+    /// Demand actually supplied.
+    pub load: f32,
+    /// Number of consumers attached.
+    pub consumers: u32,
+    /// Number of consumers that are powered.
+    pub powered: u32,
+}
+
+/// A device drawing power, as seen by [`solve`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConsumerSample<K> {
+    pub key: K,
+    pub cell: IVec2,
+    pub demand: f32,
+    pub priority: u8,
+}
+
+/// Result of [`solve`].
+#[derive(Debug)]
+pub struct PowerSolution<K: std::hash::Hash + Eq> {
+    /// One entry per cable network, ordered by anchor.
+    pub networks: Vec<PowerNetworkStats>,
+    /// Consumers that receive power.
+    pub powered: HashSet<K>,
+}
+
+/// Row-then-column order for cells, so anchors are stable across runs.
+fn cell_order(cell: &IVec2) -> (i32, i32) {
+    (cell.y, cell.x)
+}
+
+/// Splits `cables` into networks of 4-connected cells and shares each
+/// network's generator output among the consumers on it.
+///
+/// Generators and consumers join the network of the cable in their cell and
+/// are ignored when there is none.  Consumers are supplied in order of
+/// descending priority (ties by ascending `key`); each one is powered only
+/// if all of its demand still fits, so a brown-out switches off whole
+/// devices, lowest priority first.
+pub fn solve<K: Copy + Ord + std::hash::Hash>(
+    cables: &HashSet<IVec2>,
+    generators: &[(IVec2, f32)],
+    consumers: &[ConsumerSample<K>],
+) -> PowerSolution<K> {
+    let mut sorted: Vec<IVec2> = cables.iter().copied().collect();
+    sorted.sort_by_key(cell_order);
+
+    // Flood-fill networks, indexed by the order of their anchors.
+    let mut network_of: HashMap<IVec2, usize> = HashMap::new();
+    let mut networks: Vec<PowerNetworkStats> = Vec::new();
+    for &start in &sorted {
+        if network_of.contains_key(&start) {
+            continue;
+        }
+        let id = networks.len();
+        let mut size = 0;
+        let mut queue = VecDeque::from([start]);
+        network_of.insert(start, id);
+        while let Some(cell) = queue.pop_front() {
+            size += 1;
+            for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                let next = cell + offset;
+                if cables.contains(&next) && !network_of.contains_key(&next) {
+                    network_of.insert(next, id);
+                    queue.push_back(next);
+                }
+            }
+        }
+        networks.push(PowerNetworkStats {
+            anchor: (start.x, start.y),
+            cables: size,
+            supply: 0.0,
+            demand: 0.0,
+            load: 0.0,
+            consumers: 0,
+            powered: 0,
+        });
+    }
+
+    for (cell, output) in generators {
+        if let Some(&id) = network_of.get(cell) {
+            networks[id].supply += output.max(0.0);
+        }
+    }
+
+    let mut queue: BTreeSet<(usize, std::cmp::Reverse<u8>, K)> = BTreeSet::new();
+    let mut demand_of: HashMap<K, f32> = HashMap::new();
+    for consumer in consumers {
+        let Some(&id) = network_of.get(&consumer.cell) else {
+            continue;
+        };
+        let demand = consumer.demand.max(0.0);
+        networks[id].demand += demand;
+        networks[id].consumers += 1;
+        demand_of.insert(consumer.key, demand);
+        queue.insert((id, std::cmp::Reverse(consumer.priority), consumer.key));
+    }
+
+    let mut powered = HashSet::new();
+    for (id, _, key) in queue {
+        let network = &mut networks[id];
+        let demand = demand_of[&key];
+        if network.load + demand <= network.supply {
+            network.load += demand;
+            network.powered += 1;
+            powered.insert(key);
+        }
+    }
+
+    PowerSolution { networks, powered }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(from: i32, to: i32) -> HashSet<IVec2> {
+        (from..=to).map(|x| IVec2::new(x, 0)).collect()
+    }
+
+    fn consumer(key: u32, x: i32, demand: f32, priority: u8) -> ConsumerSample<u32> {
+        ConsumerSample {
+            key,
+            cell: IVec2::new(x, 0),
+            demand,
+            priority,
+        }
+    }
+
+    #[test]
+    fn separate_cable_runs_form_separate_networks() {
+        let mut cables = line(0, 2);
+        cables.extend(line(5, 6));
+        let solution = solve(
+            &cables,
+            &[(IVec2::new(1, 0), 100.0)],
+            &[consumer(1, 2, 40.0, 0), consumer(2, 6, 10.0, 0)],
+        );
+
+        assert_eq!(solution.networks.len(), 2);
+        assert_eq!(solution.networks[0].anchor, (0, 0));
+        assert_eq!(solution.networks[0].cables, 3);
+        assert_eq!(solution.networks[0].load, 40.0);
+        assert_eq!(solution.networks[1].anchor, (5, 0));
+        assert_eq!(solution.networks[1].supply, 0.0);
+        assert_eq!(solution.powered, HashSet::from([1]));
+    }
+
+    #[test]
+    fn brown_outs_drop_low_priority_consumers_first() {
+        let solution = solve(
+            &line(0, 3),
+            &[(IVec2::new(0, 0), 50.0)],
+            &[
+                consumer(1, 1, 30.0, 0),
+                consumer(2, 2, 30.0, 5),
+                consumer(3, 3, 20.0, 0),
+            ],
+        );
+
+        let network = &solution.networks[0];
+        assert_eq!((network.demand, network.load), (80.0, 50.0));
+        assert_eq!((network.consumers, network.powered), (3, 2));
+        assert_eq!(solution.powered, HashSet::from([2, 3]));
+    }
+
+    #[test]
+    fn devices_off_the_cable_get_nothing() {
+        let solution = solve(
+            &line(0, 1),
+            &[(IVec2::new(4, 0), 100.0)],
+            &[consumer(1, 1, 0.0, 0), consumer(2, 4, 1.0, 0)],
+        );
+
+        assert_eq!(solution.networks[0].supply, 0.0);
+        assert_eq!(solution.powered, HashSet::from([1]));
+    }
+}
@@ -0,0 +1,459 @@
+//! Projectiles: bolts, bullets and thrown things.
+//!
+//! Weapons and throwing fire a projectile by writing [`FireProjectile`] on
+//! the server.  A [`ProjectileMode::Hitscan`] shot is resolved at once with
+//! a raycast.  A [`ProjectileMode::Ballistic`] one spawns a thing of the
+//! given kind that flies along a [`Trajectory`], sweeping a ray over the
+//! path of every tick, until it hits something or its lifetime runs out.
+//! A [`ProjectileMode::Thrown`] one flies the same way with a thing that
+//! already exists, such as an item thrown from a hand.
+//! Either way a hit writes [`Damage`] for the target, pushes dynamic bodies
+//! along the shot and writes a [`ProjectileImpact`] for effects.
+//!
+//! Flying projectiles are not part of `StateUpdate` frames: clients get
+//! their spawn and one `TrajectoryStarted`, extrapolate the flight
+//! themselves, and get their despawn, or a `TrajectoryEnded` for thrown
+//! things that drop where they land.
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use network::{NetId, Server, SimulationTick, StreamSender};
+use physics::{GameLayer, LinearVelocity, RigidBody, SpatialQuery};
+use things::{
+    Damage, DamageKind, DespawnReason, NetIdIndex, ThingsStreamMessage, Trajectory, spawn_thing,
+};
+
+/// System sets of the projectiles module, in `SimulationTick`.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProjectilesSet {
+    /// Handles [`FireProjectile`]s.
+    ///
+    /// Systems that write [`FireProjectile`] for a thrown thing run before
+    /// this set, so the thing is loose by the time it is launched.
+    Fire,
+}
+
+/// What a projectile does to whatever it hits.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct ProjectileHit {
+    pub damage: f32,
+    pub kind: DamageKind,
+    /// Speed in m/s added to a dynamic body hit, along the shot.
+    pub knockback: f32,
+}
+
+/// How a projectile travels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectileMode {
+    /// Hits the first solid body along the shot within `range` instantly.
+    Hitscan { range: f32 },
+    /// Spawns a thing of `kind` flying at `speed`, falling with `gravity`
+    /// m/s², for at most `lifetime` seconds.  Thrown things that
+    /// `drop_on_impact` stay in the world as regular physics bodies where
+    /// they land; the others are despawned.
+    Ballistic {
+        kind: u16,
+        speed: f32,
+        gravity: f32,
+        lifetime: f32,
+        drop_on_impact: bool,
+    },
+    /// Launches `thing`, an existing replicated thing lying loose in the
+    /// world, at `speed` like a ballistic projectile that drops on impact.
+    Thrown {
+        thing: Entity,
+        speed: f32,
+        gravity: f32,
+        lifetime: f32,
+    },
+}
+
+/// Request to fire a projectile from `origin` along `direction`.  The
+/// `shooter` is never hit by its own shot.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct FireProjectile {
+    pub shooter: Option<Entity>,
+    pub origin: Vec3,
+    pub direction: Dir3,
+    pub mode: ProjectileMode,
+    pub hit: ProjectileHit,
+}
+
+/// A projectile struck `target` at `point`.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct ProjectileImpact {
+    pub shooter: Option<Entity>,
+    pub target: Entity,
+    pub point: Vec3,
+}
+
+/// A ballistic projectile in flight, on the server.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Projectile {
+    pub shooter: Option<Entity>,
+    pub hit: ProjectileHit,
+    /// Seconds of flight left.
+    pub remaining: f32,
+    pub drop_on_impact: bool,
+}
+
+/// Applies the effects of a projectile hitting a target.
+#[derive(SystemParam)]
+pub(crate) struct Impacts<'w, 's> {
+    damage: MessageWriter<'w, Damage>,
+    impacts: MessageWriter<'w, ProjectileImpact>,
+    bodies: Query<'w, 's, (&'static RigidBody, &'static mut LinearVelocity), Without<Projectile>>,
+}
+
+impl Impacts<'_, '_> {
+    fn strike(
+        &mut self,
+        shooter: Option<Entity>,
+        hit: &ProjectileHit,
+        target: Entity,
+        point: Vec3,
+        direction: Dir3,
+    ) {
+        self.damage.write(Damage {
+            target,
+            amount: hit.damage,
+            kind: hit.kind,
+        });
+        if let Ok((&RigidBody::Dynamic, mut velocity)) = self.bodies.get_mut(target) {
+            velocity.0 += direction * hit.knockback;
+        }
+        self.impacts.write(ProjectileImpact {
+            shooter,
+            target,
+            point,
+        });
+    }
+}
+
+/// Server system: resolves hitscan shots, spawns ballistic projectiles and
+/// launches thrown things for every [`FireProjectile`].  Spawns are
+/// broadcast on the things stream together with their trajectory.
+pub(crate) fn fire_projectiles(
+    mut commands: Commands,
+    mut requests: MessageReader<FireProjectile>,
+    mut server: ResMut<Server>,
+    spatial: SpatialQuery,
+    mut impacts: Impacts,
+    net_ids: Query<&NetId>,
+    things_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+) {
+    for shot in requests.read() {
+        match shot.mode {
+            ProjectileMode::Hitscan { range } => {
+                let filter = GameLayer::solid_filter().with_excluded_entities(shot.shooter);
+                if let Some(hit) =
+                    spatial.cast_ray(shot.origin, shot.direction, range, true, &filter)
+                {
+                    let point = shot.origin + shot.direction * hit.distance;
+                    impacts.strike(shot.shooter, &shot.hit, hit.entity, point, shot.direction);
+                }
+            }
+            ProjectileMode::Ballistic {
+                kind,
+                speed,
+                gravity,
+                lifetime,
+                drop_on_impact,
+            } => {
+                let (entity, net_id) = spawn_thing(&mut commands, &mut server, kind, shot.origin);
+                let trajectory = Trajectory {
+                    velocity: shot.direction * speed,
+                    gravity,
+                };
+                // Moved along the trajectory here, not by physics.
+                commands.entity(entity).insert((
+                    RigidBody::Kinematic,
+                    trajectory,
+                    Projectile {
+                        shooter: shot.shooter,
+                        hit: shot.hit,
+                        remaining: lifetime,
+                        drop_on_impact,
+                    },
+                ));
+                let Some(ref sender) = things_sender else {
+                    continue;
+                };
+                let spawned = ThingsStreamMessage::EntitySpawned {
+                    net_id,
+                    kind,
+                    position: shot.origin.into(),
+                    velocity: trajectory.velocity.into(),
+                    owner: None,
+                    name: None,
+                };
+                for msg in [spawned, trajectory.started(net_id, shot.origin)] {
+                    if let Err(e) = sender.broadcast(&msg) {
+                        error!(
+                            "Failed to broadcast projectile NetId({}) on things stream: {e}",
+                            net_id.0
+                        );
+                    }
+                }
+            }
+            ProjectileMode::Thrown {
+                thing,
+                speed,
+                gravity,
+                lifetime,
+            } => {
+                let Ok(&net_id) = net_ids.get(thing) else {
+                    warn!("FireProjectile: thrown {thing:?} is not a replicated thing");
+                    continue;
+                };
+                let trajectory = Trajectory {
+                    velocity: shot.direction * speed,
+                    gravity,
+                };
+                commands.entity(thing).insert((
+                    Transform::from_translation(shot.origin),
+                    RigidBody::Kinematic,
+                    LinearVelocity::ZERO,
+                    trajectory,
+                    Projectile {
+                        shooter: shot.shooter,
+                        hit: shot.hit,
+                        remaining: lifetime,
+                        drop_on_impact: true,
+                    },
+                ));
+                if let Some(ref sender) = things_sender
+                    && let Err(e) = sender.broadcast(&trajectory.started(net_id, shot.origin))
+                {
+                    error!(
+                        "Failed to broadcast throw of NetId({}) on things stream: {e}",
+                        net_id.0
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Server system: moves ballistic projectiles along their [`Trajectory`]
+/// and strikes the first solid body on the way.  Projectiles that hit
+/// something or run out of time are despawned, or dropped as regular
+/// physics bodies when they `drop_on_impact`; either is broadcast.
+pub(crate) fn advance_projectiles(
+    time: Res<Time>,
+    mut commands: Commands,
+    spatial: SpatialQuery,
+    mut projectiles: Query<(
+        Entity,
+        &NetId,
+        &mut Transform,
+        &mut Trajectory,
+        &mut Projectile,
+    )>,
+    mut impacts: Impacts,
+    mut net_id_index: ResMut<NetIdIndex>,
+    things_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+) {
+    let dt = time.delta_secs();
+    for (entity, &net_id, mut transform, mut trajectory, mut projectile) in projectiles.iter_mut() {
+        let start = transform.translation;
+        let mut end = start;
+        let travel = trajectory.advance(&mut end, dt);
+        projectile.remaining -= dt;
+
+        let filter = GameLayer::solid_filter()
+            .with_excluded_entities(projectile.shooter.into_iter().chain([entity]));
+        let hit = Dir3::new(travel).ok().and_then(|direction| {
+            spatial
+                .cast_ray(start, direction, travel.length(), true, &filter)
+                .map(|hit| (direction, hit))
+        });
+        if let Some((direction, hit)) = hit {
+            transform.translation = start + direction * hit.distance;
+            let point = transform.translation;
+            impacts.strike(
+                projectile.shooter,
+                &projectile.hit,
+                hit.entity,
+                point,
+                direction,
+            );
+        } else {
+            transform.translation = end;
+            if projectile.remaining > 0.0 {
+                continue;
+            }
+        }
+
+        let msg = if projectile.drop_on_impact {
+            commands
+                .entity(entity)
+                .remove::<(Projectile, Trajectory)>()
+                .insert((RigidBody::Dynamic, LinearVelocity::ZERO));
+            ThingsStreamMessage::TrajectoryEnded { net_id }
+        } else {
+            commands.entity(entity).despawn();
+            net_id_index.0.remove(&net_id);
+            ThingsStreamMessage::EntityDespawned {
+                net_id,
+                reason: DespawnReason::Destroyed,
+            }
+        };
+        if let Some(ref sender) = things_sender
+            && let Err(e) = sender.broadcast(&msg)
+        {
+            error!(
+                "Failed to broadcast landing of projectile NetId({}): {e}",
+                net_id.0
+            );
+        }
+    }
+}
+
+/// Registers the projectile messages and runs firing and flight on the
+/// server's simulation tick.
+///
+/// Must be added after `ThingsPlugin` and `PhysicsPlugin`.
+pub struct ProjectilesPlugin;
+
+impl Plugin for ProjectilesPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Projectile>();
+        app.add_message::<FireProjectile>();
+        app.add_message::<ProjectileImpact>();
+        app.add_systems(
+            SimulationTick,
+            (
+                fire_projectiles.in_set(ProjectilesSet::Fire),
+                advance_projectiles,
+            )
+                .chain()
+                .run_if(resource_exists::<Server>),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::time::TimeUpdateStrategy;
+    use physics::{Collider, GravityScale, PhysicsPlugin};
+
+    use super::*;
+
+    const HIT: ProjectileHit = ProjectileHit {
+        damage: 10.0,
+        kind: DamageKind::Brute,
+        knockback: 2.0,
+    };
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            bevy::asset::AssetPlugin::default(),
+            bevy::mesh::MeshPlugin,
+            bevy::scene::ScenePlugin,
+            PhysicsPlugin,
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            1.0 / 60.0,
+        )));
+        app.init_resource::<Server>();
+        app.init_resource::<NetIdIndex>();
+        app.add_message::<Damage>();
+        app.add_message::<FireProjectile>();
+        app.add_message::<ProjectileImpact>();
+        app.finish();
+        app
+    }
+
+    fn drain<M: Message>(app: &mut App) -> Vec<M> {
+        app.world_mut()
+            .resource_mut::<Messages<M>>()
+            .drain()
+            .collect()
+    }
+
+    #[test]
+    fn shots_damage_and_push_the_first_body_hit() {
+        let mut app = test_app();
+        let target = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(5.0, 0.0, 0.0),
+                RigidBody::Dynamic,
+                GravityScale(0.0),
+                Collider::sphere(0.5),
+            ))
+            .id();
+        let shooter = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                RigidBody::Static,
+                Collider::sphere(0.5),
+            ))
+            .id();
+        // The spatial query only sees colliders after a physics step, and
+        // the first update does not advance time.
+        for _ in 0..3 {
+            app.update();
+        }
+
+        app.world_mut().write_message(FireProjectile {
+            shooter: Some(shooter),
+            origin: Vec3::ZERO,
+            direction: Dir3::X,
+            mode: ProjectileMode::Hitscan { range: 20.0 },
+            hit: HIT,
+        });
+        app.world_mut().run_system_once(fire_projectiles).unwrap();
+        let damage = drain::<Damage>(&mut app);
+        assert_eq!(damage.len(), 1);
+        assert_eq!(damage[0].target, target);
+        assert_eq!(app.world().get::<LinearVelocity>(target).unwrap().x, 2.0);
+        let impact = drain::<ProjectileImpact>(&mut app);
+        assert!((impact[0].point - Vec3::new(4.5, 0.0, 0.0)).length() < 1e-3);
+
+        // A bolt flying at 20 m/s reaches the target within a few ticks and
+        // is despawned there.
+        let net_id = app.world_mut().resource_mut::<Server>().next_net_id();
+        let bolt = app
+            .world_mut()
+            .spawn((
+                net_id,
+                Transform::default(),
+                Trajectory {
+                    velocity: Vec3::X * 20.0,
+                    gravity: 0.0,
+                },
+                Projectile {
+                    shooter: Some(shooter),
+                    hit: HIT,
+                    remaining: 1.0,
+                    drop_on_impact: false,
+                },
+            ))
+            .id();
+        for _ in 0..20 {
+            app.world_mut()
+                .run_system_once(advance_projectiles)
+                .unwrap();
+            if app.world().get_entity(bolt).is_err() {
+                break;
+            }
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(50));
+        }
+        assert!(app.world().get_entity(bolt).is_err());
+        let damage = drain::<Damage>(&mut app);
+        assert_eq!(damage.len(), 1);
+        assert_eq!(damage[0].target, target);
+    }
+}
@@ -0,0 +1,441 @@
+//! Research: unlocking structures, floors and items.
+//!
+//! The [`TechTree`] holds [`TechNode`]s, each naming what it [`Unlock`]s,
+//! the nodes it requires and what researching it costs: research points,
+//! and items taken from the researcher's hands and the containers within
+//! reach like a craft's inputs.  Anything no node unlocks is always
+//! available; anything a node unlocks stays locked until that node is
+//! researched.  Client and server register the same nodes in the same
+//! order at startup, so a [`TechId`] means the same node on both.
+//!
+//! Research points are earned by crafting.  Clients ask to research a node
+//! with `InteractionRequest::Research`; the interactions module checks it
+//! and writes [`ResearchRequested`], which [`complete_research`] pays for
+//! and applies.  The server sends its [`ResearchProgress`] to clients on
+//! join and after every change, so their menus grey out what is still
+//! locked.
+
+use std::collections::HashSet;
+use std::mem::discriminant;
+
+use bevy::prelude::*;
+use crafting::{CraftCompleted, InputConsumer, ItemCount, Workshop};
+use network::{
+    Client, ClientId, ModuleReadySent, NetworkReceive, NetworkSend, PlayerEvent, Server,
+    SimulationTick, StreamBudget, StreamDef, StreamDirection, StreamPriority, StreamReader,
+    StreamRegistry, StreamSender, stream_tag,
+};
+use tiles::{FloorKind, TileKind};
+use wincode::{SchemaRead, SchemaWrite};
+
+/// Stream tag for the server→client research stream, derived from its name.
+pub const RESEARCH_STREAM_TAG: u8 = stream_tag("research");
+
+/// Per-client allowance on the research stream, in bytes per network tick.
+const RESEARCH_BYTES_PER_TICK: usize = 512;
+
+/// Research points earned for every finished craft.
+pub const POINTS_PER_CRAFT: u32 = 1;
+
+/// Index of a [`TechNode`] in the [`TechTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, SchemaRead, SchemaWrite)]
+pub struct TechId(pub u16);
+
+/// Something a [`TechNode`] makes available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unlock {
+    /// Building a structure of this kind.  Doors match whether open or not.
+    Structure(TileKind),
+    /// Laying a floor of this kind.
+    Floor(FloorKind),
+    /// Crafting items of this thing kind.
+    Item(u16),
+}
+
+impl Unlock {
+    fn matches(&self, other: &Unlock) -> bool {
+        match (self, other) {
+            (Unlock::Structure(a), Unlock::Structure(b)) => discriminant(a) == discriminant(b),
+            _ => self == other,
+        }
+    }
+}
+
+/// One node of the [`TechTree`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TechNode {
+    /// Name shown in menus.
+    pub name: String,
+    /// Research points spent on it.
+    pub points: u32,
+    /// Items used up researching it.
+    pub inputs: Vec<ItemCount>,
+    /// Nodes that must be researched first.
+    pub requires: Vec<TechId>,
+    pub unlocks: Vec<Unlock>,
+}
+
+/// Why a node cannot be researched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResearchError {
+    /// No node has that id.
+    UnknownNode,
+    /// The node has been researched already.
+    AlreadyUnlocked,
+    /// A node it requires has not been researched yet.
+    MissingPrerequisites,
+    /// Not enough research points are banked.
+    NotEnoughPoints,
+    /// The researcher cannot reach enough of the node's inputs.
+    MissingInputs,
+}
+
+/// The researched nodes and banked points, as sent to clients.
+#[derive(Debug, Clone, Default, PartialEq, Eq, SchemaRead, SchemaWrite)]
+pub struct ResearchProgress {
+    pub unlocked: Vec<TechId>,
+    pub points: u32,
+}
+
+/// Every [`TechNode`] in registration order, which of them have been
+/// researched and the research points banked.  Progress is shared by every
+/// player on the server.
+#[derive(Resource, Debug, Default)]
+pub struct TechTree {
+    nodes: Vec<TechNode>,
+    unlocked: HashSet<TechId>,
+    points: u32,
+}
+
+impl TechTree {
+    /// Adds `node` and returns its id.
+    pub fn register(&mut self, node: TechNode) -> TechId {
+        let id = TechId(self.nodes.len() as u16);
+        self.nodes.push(node);
+        id
+    }
+
+    pub fn get(&self, id: TechId) -> Option<&TechNode> {
+        self.nodes.get(id.0 as usize)
+    }
+
+    /// Every node with its id.
+    pub fn iter(&self) -> impl Iterator<Item = (TechId, &TechNode)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (TechId(i as u16), node))
+    }
+
+    pub fn is_unlocked(&self, id: TechId) -> bool {
+        self.unlocked.contains(&id)
+    }
+
+    /// Research points banked.
+    pub fn points(&self) -> u32 {
+        self.points
+    }
+
+    pub fn add_points(&mut self, points: u32) {
+        self.points = self.points.saturating_add(points);
+    }
+
+    /// Whether `unlock` is available: no node unlocks it, or one that does
+    /// has been researched.
+    pub fn allows(&self, unlock: Unlock) -> bool {
+        let mut gated = false;
+        for (id, node) in self.iter() {
+            if node.unlocks.iter().any(|u| u.matches(&unlock)) {
+                if self.is_unlocked(id) {
+                    return true;
+                }
+                gated = true;
+            }
+        }
+        !gated
+    }
+
+    /// Whether node `id` can be researched once enough points are banked:
+    /// it has not been researched yet and every node it requires has.
+    pub fn is_available(&self, id: TechId) -> bool {
+        matches!(self.check(id), Ok(_) | Err(ResearchError::NotEnoughPoints))
+    }
+
+    /// Checks that node `id` can be researched with the points banked.
+    /// Its inputs are checked separately, against what the researcher can
+    /// reach.
+    pub fn check(&self, id: TechId) -> Result<&TechNode, ResearchError> {
+        let node = self.get(id).ok_or(ResearchError::UnknownNode)?;
+        if self.is_unlocked(id) {
+            return Err(ResearchError::AlreadyUnlocked);
+        }
+        if !node.requires.iter().all(|&req| self.is_unlocked(req)) {
+            return Err(ResearchError::MissingPrerequisites);
+        }
+        if self.points < node.points {
+            return Err(ResearchError::NotEnoughPoints);
+        }
+        Ok(node)
+    }
+
+    /// Spends the points of node `id` and marks it researched.
+    pub fn unlock(&mut self, id: TechId) -> Result<(), ResearchError> {
+        let points = self.check(id)?.points;
+        self.points -= points;
+        self.unlocked.insert(id);
+        Ok(())
+    }
+
+    pub fn progress(&self) -> ResearchProgress {
+        let mut unlocked: Vec<TechId> = self.unlocked.iter().copied().collect();
+        unlocked.sort();
+        ResearchProgress {
+            unlocked,
+            points: self.points,
+        }
+    }
+
+    /// Replaces the research progress with the server's.
+    pub fn apply(&mut self, progress: ResearchProgress) {
+        self.unlocked = progress.unlocked.into_iter().collect();
+        self.points = progress.points;
+    }
+}
+
+/// Server-side: `actor` asked to research `node`.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct ResearchRequested {
+    pub actor: Entity,
+    pub node: TechId,
+}
+
+/// Wire format for the server→client research stream.
+#[derive(Debug, Clone, SchemaRead, SchemaWrite)]
+pub enum ResearchStreamMessage {
+    /// The server's whole [`ResearchProgress`], replacing the client's.
+    Progress(ResearchProgress),
+}
+
+/// Server system that researches every [`ResearchRequested`] node that
+/// still can be: spends its points, consumes its inputs with
+/// [`InputConsumer`] and marks it researched.
+pub fn complete_research(
+    mut requests: MessageReader<ResearchRequested>,
+    mut tree: ResMut<TechTree>,
+    workshop: Workshop,
+    mut consumer: InputConsumer,
+) {
+    for request in requests.read() {
+        let node = match tree.check(request.node) {
+            Ok(node) => node.clone(),
+            Err(e) => {
+                warn!(
+                    "Research of {:?} by {:?} refused: {e:?}",
+                    request.node, request.actor
+                );
+                continue;
+            }
+        };
+        if !workshop.has_inputs(request.actor, &node.inputs) {
+            warn!(
+                "Research of {:?} by {:?} refused: {:?}",
+                request.node,
+                request.actor,
+                ResearchError::MissingInputs
+            );
+            continue;
+        }
+        consumer.consume(workshop.take_inputs(request.actor, &node.inputs));
+        if tree.unlock(request.node).is_ok() {
+            info!("{:?} researched {}", request.actor, node.name);
+        }
+    }
+}
+
+/// Server system that banks [`POINTS_PER_CRAFT`] for every finished craft.
+pub fn award_craft_points(mut crafts: MessageReader<CraftCompleted>, mut tree: ResMut<TechTree>) {
+    let crafts = crafts.read().count() as u32;
+    if crafts > 0 {
+        tree.add_points(crafts * POINTS_PER_CRAFT);
+    }
+}
+
+/// Client-side system: applies the server's progress from the research
+/// stream.
+fn handle_research_updates(
+    mut reader: ResMut<StreamReader<ResearchStreamMessage>>,
+    mut tree: ResMut<TechTree>,
+) {
+    for msg in reader.drain() {
+        match msg {
+            ResearchStreamMessage::Progress(progress) => tree.apply(progress),
+        }
+    }
+}
+
+/// Clients waiting for their initial research progress.
+#[derive(Resource, Default)]
+struct PendingResearchSyncs(Vec<ClientId>);
+
+/// Server-side system: sends the current progress and [`StreamReady`] to
+/// each joining client, then reports [`ModuleReadySent`].
+///
+/// [`StreamReady`]: network::StreamReady
+fn send_research_on_join(
+    mut events: MessageReader<PlayerEvent>,
+    sender: Option<Res<StreamSender<ResearchStreamMessage>>>,
+    tree: Res<TechTree>,
+    mut module_ready: MessageWriter<ModuleReadySent>,
+    mut pending: ResMut<PendingResearchSyncs>,
+) {
+    for event in events.read() {
+        if let PlayerEvent::Joined { id, .. } = event {
+            pending.0.push(*id);
+        }
+    }
+    if pending.0.is_empty() {
+        return;
+    }
+    let Some(sender) = sender.as_deref() else {
+        error!(
+            "No ResearchStreamMessage sender available; {} client(s) waiting",
+            pending.0.len()
+        );
+        return;
+    };
+
+    for client in std::mem::take(&mut pending.0) {
+        let msg = ResearchStreamMessage::Progress(tree.progress());
+        if let Err(e) = sender.send_to(client, &msg) {
+            error!(
+                "Failed to send research progress to ClientId({}): {}",
+                client.0, e
+            );
+            continue;
+        }
+        if let Err(e) = sender.send_stream_ready_to(client) {
+            error!(
+                "Failed to send StreamReady to ClientId({}): {}",
+                client.0, e
+            );
+            continue;
+        }
+        module_ready.write(ModuleReadySent { client });
+    }
+}
+
+/// Server-side system: sends the progress to every client again after it
+/// changed.
+fn broadcast_research_progress(
+    tree: Res<TechTree>,
+    sender: Option<Res<StreamSender<ResearchStreamMessage>>>,
+) {
+    if !tree.is_changed() || tree.is_added() {
+        return;
+    }
+    let Some(sender) = sender else {
+        return;
+    };
+    if let Err(e) = sender.broadcast(&ResearchStreamMessage::Progress(tree.progress())) {
+        error!("Failed to broadcast research progress: {e}");
+    }
+}
+
+/// Registers the [`TechTree`], the research stream and, on servers, the
+/// systems that earn points and research nodes.  Add after `NetworkPlugin`
+/// and `CraftingPlugin`.
+pub struct ResearchPlugin;
+
+impl Plugin for ResearchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ResearchRequested>();
+        app.init_resource::<TechTree>();
+        app.init_resource::<PendingResearchSyncs>();
+        app.add_systems(
+            SimulationTick,
+            (award_craft_points, complete_research).run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            NetworkReceive,
+            handle_research_updates.run_if(resource_exists::<Client>),
+        );
+        app.add_systems(
+            NetworkReceive,
+            send_research_on_join.run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            NetworkSend,
+            broadcast_research_progress.run_if(resource_exists::<Server>),
+        );
+
+        let mut registry = app.world_mut().get_resource_mut::<StreamRegistry>().expect(
+            "ResearchPlugin requires NetworkPlugin to be added before it (StreamRegistry not found)",
+        );
+        let (sender, reader): (
+            StreamSender<ResearchStreamMessage>,
+            StreamReader<ResearchStreamMessage>,
+        ) = registry.register_with_budget(
+            StreamDef {
+                tag: RESEARCH_STREAM_TAG,
+                name: "research",
+                direction: StreamDirection::ServerToClient,
+            },
+            StreamBudget::new(StreamPriority::Low, RESEARCH_BYTES_PER_TICK),
+        );
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nodes_gate_their_unlocks_until_researched() {
+        let mut tree = TechTree::default();
+        let doors = tree.register(TechNode {
+            name: "Doors".into(),
+            points: 2,
+            inputs: Vec::new(),
+            requires: Vec::new(),
+            unlocks: vec![Unlock::Structure(TileKind::Door { open: false })],
+        });
+        let carpet = tree.register(TechNode {
+            name: "Carpet".into(),
+            points: 1,
+            inputs: Vec::new(),
+            requires: vec![doors],
+            unlocks: vec![Unlock::Floor(FloorKind::Carpet)],
+        });
+
+        assert!(tree.allows(Unlock::Structure(TileKind::Wall)));
+        assert!(!tree.allows(Unlock::Structure(TileKind::Door { open: true })));
+        assert_eq!(tree.unlock(doors), Err(ResearchError::NotEnoughPoints));
+        assert!(tree.is_available(doors) && !tree.is_available(carpet));
+        assert_eq!(
+            tree.check(carpet).err(),
+            Some(ResearchError::MissingPrerequisites)
+        );
+
+        tree.add_points(3);
+        assert_eq!(tree.unlock(doors), Ok(()));
+        assert_eq!(tree.unlock(doors), Err(ResearchError::AlreadyUnlocked));
+        assert!(tree.allows(Unlock::Structure(TileKind::Door { open: true })));
+        assert!(!tree.allows(Unlock::Floor(FloorKind::Carpet)));
+        assert!(tree.is_available(carpet));
+        assert_eq!(tree.points(), 1);
+
+        // A client applying the server's progress sees the same state.
+        let bytes = wincode::serialize(&ResearchStreamMessage::Progress(tree.progress()))
+            .expect("serialize");
+        let ResearchStreamMessage::Progress(progress) =
+            wincode::deserialize(&bytes).expect("deserialize");
+        let mut client = TechTree::default();
+        client.register(tree.get(doors).unwrap().clone());
+        client.register(tree.get(carpet).unwrap().clone());
+        client.apply(progress);
+        assert!(client.is_unlocked(doors) && !client.is_unlocked(carpet));
+        assert_eq!(client.points(), 1);
+    }
+}
@@ -0,0 +1,450 @@
+//! Jobs players take on when they join.
+//!
+//! The server's [`RoleBook`] lists the roles of the station (engineer,
+//! medic, captain, ...): their name, the [`AccessLevel`]s they carry and the
+//! equipment they start with.  A client asks for a role with a
+//! [`RoleRequest`] on the roles stream as soon as the server welcomes it;
+//! once its soul is bound to a creature, [`assign_roles`] gives the creature
+//! the requested role, or the first role with a free slot when the request
+//! names none or that role is full.  The creature gets [`Role`] and
+//! [`Access`]; under [`SpawnPolicy::RoleBased`] it is moved to a spawn point
+//! reserved for its role, if the map has one.  The role's equipment is
+//! spawned next to it and handed over on the next tick: into the creature's
+//! hands first, then into a container among the equipment (its backpack),
+//! then onto the floor.  ID cards among the equipment are encoded with the
+//! role's access levels; it is the card, not the creature, that doors and
+//! lockers check.  All of it is [`OwnedBy`] the player's client.
+
+use std::collections::HashMap;
+
+use access::{AccessLevel, IdCard};
+use bevy::prelude::*;
+use items::{Container, Item, ItemInsertRequest, ItemPickupRequest, OwnedBy};
+use network::{
+    Client, ClientEvent, ClientId, NetworkReceive, PlayerEvent, Server, ServerMessage,
+    SimulationTick, Spectator, StreamDef, StreamDirection, StreamReader, StreamRegistry,
+    StreamSender, stream_tag,
+};
+use serde::Deserialize;
+use souls::{Soul, SpawnPolicy, SpawnSelector};
+use things::{HandSlot, LastValidPosition, ThingRegistry, ThingsStreamMessage, spawn_thing};
+use wincode::{SchemaRead, SchemaWrite};
+
+/// Stream tag for the client→server roles stream, derived from its name.
+pub const ROLES_STREAM_TAG: u8 = stream_tag("roles");
+
+/// Template name of the ID card.  Cards among a role's equipment are
+/// encoded with the role's access levels.
+pub const ID_CARD_TEMPLATE: &str = "id_card";
+
+/// A role as configured on the server.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RoleDef {
+    /// Name clients ask for, e.g. `"engineer"`.
+    pub name: String,
+    /// Name shown to players, e.g. `"Station Engineer"`.
+    pub display_name: String,
+    #[serde(default)]
+    pub access: Vec<AccessLevel>,
+    /// Template names of the things the role starts with, in the order they
+    /// are handed over.
+    #[serde(default)]
+    pub equipment: Vec<String>,
+    /// Most players that may hold the role at once; unlimited when absent.
+    #[serde(default)]
+    pub slots: Option<u32>,
+}
+
+/// Every role, in the order they are offered as a fallback.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct RoleBook(pub Vec<RoleDef>);
+
+impl RoleBook {
+    pub fn get(&self, name: &str) -> Option<&RoleDef> {
+        self.0.iter().find(|role| role.name == name)
+    }
+
+    /// The role to give a player who asked for `requested`, given how many
+    /// players hold each role: the requested one if it exists and has a free
+    /// slot, else the first role that does.
+    pub fn choose(&self, requested: Option<&str>, held: impl Fn(&str) -> u32) -> Option<&RoleDef> {
+        let free = |role: &&RoleDef| role.slots.is_none_or(|slots| held(&role.name) < slots);
+        requested
+            .and_then(|name| self.get(name))
+            .filter(free)
+            .or_else(|| self.0.iter().find(free))
+    }
+}
+
+/// The role of a creature, by [`RoleDef::name`].
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Role(pub String);
+
+/// Access levels of a creature's role.  Doors and lockers check the
+/// [`IdCard`] the creature holds rather than this.
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Access(pub Vec<AccessLevel>);
+
+impl Access {
+    pub fn allows(&self, level: AccessLevel) -> bool {
+        self.0.contains(&level)
+    }
+}
+
+/// Client request for a role, sent on the roles stream.  `None` leaves the
+/// choice to the server.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub struct RoleRequest {
+    pub role: Option<String>,
+}
+
+/// Client-side: the role to ask for when joining.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct PreferredRole(pub Option<String>);
+
+/// Server-side: roles asked for by clients whose creature has no role yet.
+#[derive(Resource, Debug, Default)]
+pub struct RoleSelections(HashMap<ClientId, Option<String>>);
+
+/// Marks a piece of starting equipment until [`issue_equipment`] hands it
+/// over.  `order` is its position in the role's equipment list.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct IssuedTo {
+    pub actor: Entity,
+    pub order: usize,
+}
+
+/// Client-side system: asks for the [`PreferredRole`] once the server has
+/// welcomed the client.
+fn send_role_request(
+    mut events: MessageReader<ClientEvent>,
+    preferred: Res<PreferredRole>,
+    sender: Option<Res<StreamSender<RoleRequest>>>,
+) {
+    for event in events.read() {
+        let ClientEvent::ServerMessageReceived(ServerMessage::Welcome { .. }) = event else {
+            continue;
+        };
+        let Some(ref sender) = sender else {
+            continue;
+        };
+        let request = RoleRequest {
+            role: preferred.0.clone(),
+        };
+        if let Err(e) = sender.send(&request) {
+            error!("Failed to send RoleRequest to server: {e}");
+        }
+    }
+}
+
+/// Server-side system: records [`RoleRequest`]s and forgets those of
+/// clients that left.
+fn receive_role_requests(
+    mut reader: ResMut<StreamReader<RoleRequest>>,
+    mut player_events: MessageReader<PlayerEvent>,
+    mut selections: ResMut<RoleSelections>,
+) {
+    for (from, request) in reader.drain_from_client() {
+        debug!("ClientId({}) asked for role {:?}", from.0, request.role);
+        selections.0.insert(from, request.role);
+    }
+    for event in player_events.read() {
+        if let PlayerEvent::Left { id } = event {
+            selections.0.remove(id);
+        }
+    }
+}
+
+/// Server system that gives every bound creature without a [`Role`] the one
+/// its client asked for (see [`RoleBook::choose`]), with its [`Access`],
+/// moves it to a spawn point of its role under [`SpawnPolicy::RoleBased`],
+/// and spawns the role's equipment next to it.  Spawns are broadcast on the
+/// things stream.  Spectators get no role.
+#[allow(clippy::too_many_arguments)]
+pub fn assign_roles(
+    mut commands: Commands,
+    mut selections: ResMut<RoleSelections>,
+    book: Res<RoleBook>,
+    registry: Res<ThingRegistry>,
+    mut server: ResMut<Server>,
+    souls: Query<&Soul>,
+    creatures: Query<(&Transform, Has<Spectator>), Without<Role>>,
+    roles: Query<&Role>,
+    things_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+    mut spawns: SpawnSelector,
+) {
+    let mut taken = Vec::new();
+    let mut held: HashMap<String, u32> = HashMap::new();
+    for role in roles.iter() {
+        *held.entry(role.0.clone()).or_default() += 1;
+    }
+
+    for soul in souls.iter() {
+        let Some(creature) = soul.bound_to else {
+            continue;
+        };
+        let Ok((transform, spectator)) = creatures.get(creature) else {
+            continue;
+        };
+        if spectator {
+            selections.0.remove(&soul.client_id);
+            continue;
+        }
+        let Some(requested) = selections.0.remove(&soul.client_id) else {
+            continue;
+        };
+        let Some(role) = book.choose(requested.as_deref(), |name| {
+            held.get(name).copied().unwrap_or(0)
+        }) else {
+            warn!(
+                "No role left for ClientId({}) '{}'",
+                soul.client_id.0, soul.name
+            );
+            continue;
+        };
+        *held.entry(role.name.clone()).or_default() += 1;
+        info!(
+            "ClientId({}) '{}' joins as {}",
+            soul.client_id.0, soul.name, role.display_name
+        );
+        commands
+            .entity(creature)
+            .insert((Role(role.name.clone()), Access(role.access.clone())));
+
+        let mut translation = transform.translation;
+        if spawns.policy() == SpawnPolicy::RoleBased && spawns.has_role(&role.name) {
+            translation = spawns.choose(Some(&role.name), &taken);
+            taken.push(translation);
+            commands.entity(creature).insert((
+                transform.with_translation(translation),
+                LastValidPosition(translation),
+            ));
+        }
+
+        let position = translation + Vec3::Y * 0.5;
+        for (order, name) in role.equipment.iter().enumerate() {
+            let Some(kind) = registry.kind_by_name(name) else {
+                warn!("Role {}: unknown equipment template {name:?}", role.name);
+                continue;
+            };
+            let (entity, net_id) = spawn_thing(&mut commands, &mut server, kind, position);
+            commands.entity(entity).insert((
+                IssuedTo {
+                    actor: creature,
+                    order,
+                },
+                OwnedBy(soul.client_id),
+            ));
+            if name == ID_CARD_TEMPLATE {
+                commands.entity(entity).insert(IdCard(role.access.clone()));
+            }
+            let Some(ref sender) = things_sender else {
+                continue;
+            };
+            if let Err(e) = sender.broadcast(&ThingsStreamMessage::EntitySpawned {
+                net_id,
+                kind,
+                position: position.into(),
+                velocity: [0.0, 0.0, 0.0],
+                owner: None,
+                name: None,
+            }) {
+                error!(
+                    "Failed to broadcast EntitySpawned for NetId({}): {e}",
+                    net_id.0
+                );
+            }
+        }
+    }
+}
+
+/// Server system that hands issued equipment over once the creature's hands
+/// are ready: items go into free hands in equipment order, then into a
+/// container among the same creature's equipment; whatever does not fit
+/// stays on the floor.
+#[allow(clippy::type_complexity)]
+pub fn issue_equipment(
+    mut commands: Commands,
+    issued: Query<(Entity, &IssuedTo, Has<Item>, Option<&Container>)>,
+    children: Query<&Children>,
+    hands: Query<&Container, With<HandSlot>>,
+    mut pickup_req: MessageWriter<ItemPickupRequest>,
+    mut insert_req: MessageWriter<ItemInsertRequest>,
+) {
+    let free_slots = |container: &Container| container.slots.iter().filter(|s| s.is_none()).count();
+
+    let mut by_actor: HashMap<Entity, Vec<(usize, Entity, bool, Option<&Container>)>> =
+        HashMap::new();
+    for (item, issued_to, is_item, container) in issued.iter() {
+        by_actor.entry(issued_to.actor).or_default().push((
+            issued_to.order,
+            item,
+            is_item,
+            container,
+        ));
+    }
+
+    for (actor, mut equipment) in by_actor {
+        let hand_containers: Vec<&Container> = children
+            .get(actor)
+            .map(|children| children.iter().filter_map(|c| hands.get(c).ok()).collect())
+            .unwrap_or_default();
+        // Hands get their containers a frame after the creature spawns.
+        if hand_containers.is_empty() {
+            continue;
+        }
+        let mut free_hands: usize = hand_containers.into_iter().map(free_slots).sum();
+        equipment.sort_by_key(|&(order, ..)| order);
+        let mut backpacks: Vec<(Entity, usize)> = equipment
+            .iter()
+            .filter(|&&(_, _, is_item, _)| is_item)
+            .filter_map(|&(_, item, _, container)| Some((item, free_slots(container?))))
+            .collect();
+
+        for (_, item, is_item, _) in equipment {
+            commands.entity(item).remove::<IssuedTo>();
+            if !is_item {
+                continue;
+            }
+            if free_hands > 0 {
+                free_hands -= 1;
+                pickup_req.write(ItemPickupRequest {
+                    actor,
+                    item,
+                    correlation: None,
+                });
+            } else if let Some((container, free)) = backpacks
+                .iter_mut()
+                .find(|(container, free)| *container != item && *free > 0)
+            {
+                *free -= 1;
+                insert_req.write(ItemInsertRequest {
+                    item,
+                    container: *container,
+                });
+            }
+        }
+    }
+}
+
+/// Registers the roles stream, the client's role request and, on servers,
+/// role assignment.  The server reads its roles from the [`RoleBook`]
+/// resource.  Add after `NetworkPlugin`, `SoulsPlugin` and `ItemsPlugin`.
+pub struct RolesPlugin;
+
+impl Plugin for RolesPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Role>();
+        app.register_type::<Access>();
+        app.init_resource::<RoleBook>();
+        app.init_resource::<RoleSelections>();
+        app.init_resource::<PreferredRole>();
+        app.add_systems(
+            NetworkReceive,
+            (
+                send_role_request.run_if(resource_exists::<Client>),
+                receive_role_requests.run_if(resource_exists::<Server>),
+            ),
+        );
+        app.add_systems(
+            SimulationTick,
+            (issue_equipment, assign_roles)
+                .chain()
+                .run_if(resource_exists::<Server>),
+        );
+
+        let mut registry = app.world_mut().get_resource_mut::<StreamRegistry>().expect(
+            "RolesPlugin requires NetworkPlugin to be added before it (StreamRegistry not found)",
+        );
+        let (sender, reader): (StreamSender<RoleRequest>, StreamReader<RoleRequest>) = registry
+            .register(StreamDef {
+                tag: ROLES_STREAM_TAG,
+                name: "roles",
+                direction: StreamDirection::ClientToServer,
+            });
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use things::HandSide;
+
+    fn role(name: &str, slots: Option<u32>) -> RoleDef {
+        RoleDef {
+            name: name.into(),
+            display_name: name.into(),
+            access: Vec::new(),
+            equipment: Vec::new(),
+            slots,
+        }
+    }
+
+    #[test]
+    fn choose_falls_back_to_the_first_role_with_a_free_slot() {
+        let book = RoleBook(vec![
+            role("captain", Some(1)),
+            role("engineer", None),
+            role("medic", Some(2)),
+        ]);
+        let nobody = |_: &str| 0;
+        let one_captain = |name: &str| u32::from(name == "captain");
+
+        let chosen = |requested, held: &dyn Fn(&str) -> u32| {
+            book.choose(requested, held).map(|role| role.name.as_str())
+        };
+        assert_eq!(chosen(Some("medic"), &nobody), Some("medic"));
+        assert_eq!(chosen(None, &nobody), Some("captain"));
+        assert_eq!(chosen(Some("captain"), &one_captain), Some("engineer"));
+        assert_eq!(chosen(Some("janitor"), &one_captain), Some("engineer"));
+        assert_eq!(RoleBook::default().choose(None, nobody), None);
+    }
+
+    #[test]
+    fn equipment_fills_the_hands_then_the_backpack() {
+        let mut app = App::new();
+        app.add_message::<ItemPickupRequest>();
+        app.add_message::<ItemInsertRequest>();
+        let world = app.world_mut();
+        let actor = world.spawn_empty().id();
+        for side in [HandSide::Left, HandSide::Right] {
+            world.spawn((
+                HandSlot { side },
+                Container::with_capacity(1),
+                ChildOf(actor),
+            ));
+        }
+        let issue =
+            |world: &mut World, order: usize| world.spawn((Item, IssuedTo { actor, order })).id();
+        let wrench = issue(world, 1);
+        let backpack = issue(world, 0);
+        world
+            .entity_mut(backpack)
+            .insert(Container::with_capacity(4));
+        let plasteel = issue(world, 2);
+        let cable = issue(world, 3);
+
+        app.world_mut().run_system_once(issue_equipment).unwrap();
+
+        let pickups: Vec<Entity> = app
+            .world_mut()
+            .resource_mut::<Messages<ItemPickupRequest>>()
+            .drain()
+            .map(|req| req.item)
+            .collect();
+        assert_eq!(pickups, vec![backpack, wrench]);
+        let inserts: Vec<(Entity, Entity)> = app
+            .world_mut()
+            .resource_mut::<Messages<ItemInsertRequest>>()
+            .drain()
+            .map(|req| (req.item, req.container))
+            .collect();
+        assert_eq!(inserts, vec![(plasteel, backpack), (cable, backpack)]);
+        assert!(app.world().get::<IssuedTo>(cable).is_none());
+    }
+}
@@ -0,0 +1,358 @@
+//! Death, ghosts and rejoining as a new character.
+//!
+//! When the creature a soul is bound to runs out of health, the server
+//! leaves the body where it fell and rebinds the soul to a [`Ghost`]: an
+//! invisible, non-colliding observer named after the player.  Ghosts fly
+//! through walls and see everything the living see, but count as
+//! [`Spectator`]s, so their interaction requests are rejected.  They are
+//! only replicated to clients that are ghosts themselves.
+//!
+//! [`RESPAWN_DELAY`] after dying, the player may press
+//! [`Action::Respawn`] to send [`SoulsRequest::Respawn`], which swaps the
+//! ghost for a fresh creature at a spawn point (see [`SpawnSelector`]).
+//! Game modes may change the delay or forbid respawning through the
+//! [`RespawnRules`] resource.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use network::{
+    ClientId, ControlledByClient, NetId, Server, Spectator, StreamReader, StreamSender, stream_tag,
+};
+use things::{
+    DespawnReason, GHOST_KIND, Health, InputDirection, NetIdIndex, PlayerControlled,
+    ReplicationScope, ReplicationVisibility, Thing, ThingsStreamMessage,
+};
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{Soul, SpawnSelector};
+
+/// Tag of the client→server souls stream.
+pub const SOULS_STREAM_TAG: u8 = stream_tag("souls");
+
+/// How long a dead player haunts the station before it may rejoin.
+pub const RESPAWN_DELAY: Duration = Duration::from_secs(30);
+
+/// Server resource: whether and when dead players may rejoin.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RespawnRules {
+    /// Whether [`SoulsRequest::Respawn`] is honoured at all.
+    pub allowed: bool,
+    /// How long a dead player haunts the station before it may rejoin.
+    pub delay: Duration,
+}
+
+impl Default for RespawnRules {
+    fn default() -> Self {
+        Self {
+            allowed: true,
+            delay: RESPAWN_DELAY,
+        }
+    }
+}
+
+/// Souls stream wire format: client→server requests about the sender's soul.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub enum SoulsRequest {
+    /// The dead sender wants to rejoin as a new character.
+    Respawn,
+}
+
+/// Marks the ghost a dead player's soul is bound to (server-side only).
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Ghost;
+
+/// On the soul entity of a dead player: the `Time::elapsed` from which it
+/// may rejoin as a new character.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deceased {
+    pub respawn_at: Duration,
+}
+
+/// Server system: rebinds the soul of every player whose creature ran out
+/// of health to a new [`Ghost`] where the body lies.
+///
+/// The body keeps its name but loses its [`ControlledByClient`], and every
+/// client is told with [`ThingsStreamMessage::ControlReleased`].  The ghost
+/// is introduced to clients by the `things` visibility rules, see
+/// [`update_ghost_visibility`].
+pub(crate) fn handle_deaths(
+    mut commands: Commands,
+    time: Res<Time>,
+    rules: Res<RespawnRules>,
+    mut server: ResMut<Server>,
+    mut souls: Query<(Entity, &mut Soul), Without<Deceased>>,
+    mut bodies: Query<(&Health, &Transform, Option<&NetId>, &mut InputDirection)>,
+    things_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+) {
+    for (soul_entity, mut soul) in souls.iter_mut() {
+        let Some(body) = soul.bound_to else {
+            continue;
+        };
+        let Ok((health, transform, net_id, mut input)) = bodies.get_mut(body) else {
+            continue;
+        };
+        if health.current > 0.0 {
+            continue;
+        }
+
+        input.0 = Vec3::ZERO;
+        commands.entity(body).remove::<ControlledByClient>();
+        if let (Some(sender), Some(&net_id)) = (&things_sender, net_id)
+            && let Err(e) = sender.broadcast(&ThingsStreamMessage::ControlReleased { net_id })
+        {
+            error!(
+                "Failed to broadcast ControlReleased for NetId({}): {e}",
+                net_id.0
+            );
+        }
+
+        let (ghost, ghost_net_id) = things::spawn_ghost(
+            &mut commands,
+            &mut server,
+            soul.client_id,
+            transform.translation,
+            &soul.name,
+        );
+        commands.entity(ghost).insert((Ghost, Spectator));
+        info!(
+            "ClientId({}) '{}' died: haunting as ghost NetId({})",
+            soul.client_id.0, soul.name, ghost_net_id.0
+        );
+        soul.bound_to = Some(ghost);
+        commands.entity(soul_entity).insert(Deceased {
+            respawn_at: time.elapsed() + rules.delay,
+        });
+    }
+}
+
+/// Server system: replicates every [`Ghost`] to the clients whose souls are
+/// bound to a ghost, and to no one else.
+pub(crate) fn update_ghost_visibility(
+    souls: Query<&Soul>,
+    mut ghosts: Query<&mut ReplicationVisibility, With<Ghost>>,
+) {
+    let haunting: HashSet<ClientId> = souls
+        .iter()
+        .filter(|soul| soul.bound_to.is_some_and(|bound| ghosts.contains(bound)))
+        .map(|soul| soul.client_id)
+        .collect();
+    let visibility = ReplicationVisibility::VisibleTo(haunting);
+    for mut ghost in ghosts.iter_mut() {
+        ghost.set_if_neq(visibility.clone());
+    }
+}
+
+/// Despawns `ghost` on the server and on the clients that see it.
+fn despawn_ghost(
+    commands: &mut Commands,
+    net_id_index: &mut NetIdIndex,
+    scope: &ReplicationScope,
+    things_sender: Option<&StreamSender<ThingsStreamMessage>>,
+    ghost: Entity,
+    net_id: Option<NetId>,
+) {
+    commands.entity(ghost).despawn();
+    let Some(net_id) = net_id else {
+        return;
+    };
+    net_id_index.0.remove(&net_id);
+    if let Some(sender) = things_sender
+        && let Err(e) = scope.send(
+            sender,
+            &[ghost],
+            &ThingsStreamMessage::EntityDespawned {
+                net_id,
+                reason: DespawnReason::Destroyed,
+            },
+        )
+    {
+        error!("Failed to despawn ghost NetId({}): {e}", net_id.0);
+    }
+}
+
+/// Server system: swaps the ghost of every dead player that asked to
+/// respawn, once its [`RespawnRules::delay`] is over, for a new creature at
+/// the spawn point.  Early requests, and every request while the rules
+/// forbid respawning, are ignored.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_respawn_requests(
+    mut commands: Commands,
+    time: Res<Time>,
+    rules: Res<RespawnRules>,
+    mut server: ResMut<Server>,
+    mut reader: ResMut<StreamReader<SoulsRequest>>,
+    mut souls: Query<(Entity, &mut Soul, &Deceased)>,
+    ghosts: Query<Option<&NetId>, With<Ghost>>,
+    mut net_id_index: ResMut<NetIdIndex>,
+    scope: ReplicationScope,
+    things_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+    mut spawns: SpawnSelector,
+) {
+    let mut taken = Vec::new();
+    for (from, SoulsRequest::Respawn) in reader.drain_from_client() {
+        let Some((soul_entity, mut soul, deceased)) =
+            souls.iter_mut().find(|(_, soul, _)| soul.client_id == from)
+        else {
+            debug!(
+                "Respawn request from ClientId({}) ignored: not dead",
+                from.0
+            );
+            continue;
+        };
+        if !rules.allowed {
+            debug!(
+                "Respawn request from ClientId({}) ignored: respawning is disabled",
+                from.0
+            );
+            continue;
+        }
+        if time.elapsed() < deceased.respawn_at {
+            debug!(
+                "Respawn request from ClientId({}) ignored: too early",
+                from.0
+            );
+            continue;
+        }
+
+        if let Some(ghost) = soul.bound_to
+            && let Ok(net_id) = ghosts.get(ghost)
+        {
+            despawn_ghost(
+                &mut commands,
+                &mut net_id_index,
+                &scope,
+                things_sender.as_deref(),
+                ghost,
+                net_id.copied(),
+            );
+        }
+
+        let spawn_pos = spawns.choose(None, &taken);
+        taken.push(spawn_pos);
+        let (creature, net_id) =
+            things::spawn_player_creature(&mut commands, &mut server, from, spawn_pos, &soul.name);
+        info!(
+            "ClientId({}) '{}' rejoined as creature NetId({})",
+            from.0, soul.name, net_id.0
+        );
+        soul.bound_to = Some(creature);
+        commands.entity(soul_entity).remove::<Deceased>();
+
+        if let Some(ref sender) = things_sender
+            && let Err(e) = sender.broadcast(&ThingsStreamMessage::EntitySpawned {
+                net_id,
+                kind: 0,
+                position: spawn_pos.into(),
+                velocity: [0.0, 0.0, 0.0],
+                owner: Some(from),
+                name: Some(soul.name.clone()),
+            })
+        {
+            error!(
+                "Failed to broadcast EntitySpawned for NetId({}): {e}",
+                net_id.0
+            );
+        }
+    }
+}
+
+/// Server system: despawns ghosts no soul is bound to any more, e.g. after
+/// their player left.
+pub(crate) fn despawn_abandoned_ghosts(
+    mut commands: Commands,
+    souls: Query<&Soul>,
+    ghosts: Query<(Entity, Option<&NetId>), With<Ghost>>,
+    mut net_id_index: ResMut<NetIdIndex>,
+    scope: ReplicationScope,
+    things_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+) {
+    for (ghost, net_id) in ghosts.iter() {
+        if souls.iter().any(|soul| soul.bound_to == Some(ghost)) {
+            continue;
+        }
+        despawn_ghost(
+            &mut commands,
+            &mut net_id_index,
+            &scope,
+            things_sender.as_deref(),
+            ghost,
+            net_id.copied(),
+        );
+    }
+}
+
+/// Client system: sends [`SoulsRequest::Respawn`] on [`Action::Respawn`]
+/// while the player is a ghost.
+pub(crate) fn request_respawn(
+    actions: ActionInput,
+    player: Query<&Thing, With<PlayerControlled>>,
+    sender: Option<Res<StreamSender<SoulsRequest>>>,
+) {
+    if !actions.just_pressed(Action::Respawn) {
+        return;
+    }
+    let is_ghost = player.iter().any(|thing| thing.kind == GHOST_KIND);
+    let Some(sender) = sender.filter(|_| is_ghost) else {
+        return;
+    };
+    if let Err(e) = sender.send(&SoulsRequest::Respawn) {
+        error!("Failed to send respawn request: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dying_players_haunt_as_ghosts_seen_by_ghosts_only() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<Server>();
+        app.init_resource::<NetIdIndex>();
+        app.init_resource::<RespawnRules>();
+        app.add_systems(Update, (handle_deaths, update_ghost_visibility).chain());
+
+        let client = ClientId(1);
+        let body = app
+            .world_mut()
+            .spawn((
+                Health::new(100.0),
+                Transform::from_xyz(2.0, 0.81, 4.0),
+                InputDirection(Vec3::X),
+                ControlledByClient(client),
+            ))
+            .id();
+        let soul = app
+            .world_mut()
+            .spawn(Soul {
+                name: "Ann".into(),
+                client_id: client,
+                bound_to: Some(body),
+            })
+            .id();
+        app.update();
+        assert_eq!(app.world().get::<Soul>(soul).unwrap().bound_to, Some(body));
+
+        app.world_mut().get_mut::<Health>(body).unwrap().current = 0.0;
+        app.update();
+
+        let ghost = app.world().get::<Soul>(soul).unwrap().bound_to.unwrap();
+        assert_ne!(ghost, body);
+        assert!(app.world().get::<Ghost>(ghost).is_some());
+        assert!(app.world().get::<Spectator>(ghost).is_some());
+        assert!(app.world().get::<Deceased>(soul).is_some());
+        assert!(app.world().get::<ControlledByClient>(body).is_none());
+        assert_eq!(
+            app.world().get::<InputDirection>(body).unwrap().0,
+            Vec3::ZERO
+        );
+        assert_eq!(
+            app.world().get::<ReplicationVisibility>(ghost),
+            Some(&ReplicationVisibility::visible_to([client]))
+        );
+    }
+}
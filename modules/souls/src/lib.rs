@@ -1,18 +1,52 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
+use creatures::MovementSpeed;
 use network::{
-    Client, ClientId, ClientInputReceived, NETWORK_UPDATE_INTERVAL, NetClientSender,
-    NetworkReceive, PlayerEvent, Server, StreamSender,
+    Client, ClientId, ClientInputReceived, ControlledByClient, FullRelevancy,
+    NETWORK_UPDATE_INTERVAL, NetClientSender, NetworkReceive, PlayerEvent, RoundState, Server,
+    SimulationTick, Spectator, StreamDef, StreamDirection, StreamReader, StreamRegistry,
+    StreamSender,
+};
+use physics::LinearVelocity;
+use things::{
+    InputDirection, LastValidPosition, MovementModifiers, ThingsSet, ThingsStreamMessage,
+};
+use tiles::LobbyStreamAnchors;
+use world::MapLayerRegistryExt;
+
+mod ghost;
+pub use ghost::{Deceased, Ghost, RESPAWN_DELAY, RespawnRules, SOULS_STREAM_TAG, SoulsRequest};
+mod spawn;
+mod spectate;
+pub use spawn::{
+    DEFAULT_SPAWN_POSITION, MAX_SPAWN_PRESSURE, MIN_SPAWN_PRESSURE, PlayerSpawn, PlayerSpawnsLayer,
+    SpawnPoints, SpawnPolicy, SpawnSelector,
 };
-use things::{InputDirection, ThingsSet, ThingsStreamMessage};
+pub use spectate::{FULL_RELEVANCY_DURATION, SPECTATE_STREAM_TAG, SpectateRequest, SpectateRules};
 
 /// The interval (in seconds) at which the client sends input updates to the server.
 const INPUT_SEND_INTERVAL: f32 = NETWORK_UPDATE_INTERVAL;
 
+/// Factor applied to a creature's [`MovementSpeed`] before a per-frame displacement
+/// counts as too fast.  Leaves room for fixed-timestep jitter.
+const SPEED_TOLERANCE: f32 = 1.5;
+
+/// Extra distance (in world units) tolerated per frame on top of the speed bound,
+/// covering collision depenetration and other small physics corrections.
+const DISPLACEMENT_MARGIN: f32 = 0.25;
+
+/// Horizontal displacement in a single frame beyond which a creature's move is
+/// treated as a teleport and rolled back instead of clamped.
+const TELEPORT_DISTANCE: f32 = 2.0;
+
 /// Component placed on a dedicated soul entity to bind a client to a creature.
 ///
 /// A soul is not a world entity — it carries no `Transform`, no physics, and no mesh.
 /// It exists purely as the server-side binding between a [`ClientId`] and the creature
-/// [`Entity`] it controls.
+/// [`Entity`] it controls.  A spectator's soul is bound to an observer instead of a
+/// creature, a dead player's soul to a [`Ghost`], and the soul of a player waiting
+/// in the lobby to nothing.
 #[derive(Component, Debug)]
 pub struct Soul {
     /// Display name sent by the client in its `Hello` message.
@@ -23,6 +57,31 @@ pub struct Soul {
     pub bound_to: Option<Entity>,
 }
 
+/// Server-side count of movement violations per connected client.
+///
+/// Every increment is also announced as [`PlayerEvent::MovementViolation`] so admin
+/// systems can react without polling this resource.
+#[derive(Resource, Debug, Default)]
+pub struct MovementViolations(HashMap<ClientId, u32>);
+
+impl MovementViolations {
+    /// Number of violations recorded for `client` this session.
+    pub fn count(&self, client: ClientId) -> u32 {
+        self.0.get(&client).copied().unwrap_or(0)
+    }
+
+    /// Records one violation for `client`, announces it, and returns the new total.
+    fn record(&mut self, client: ClientId, player_events: &mut MessageWriter<PlayerEvent>) -> u32 {
+        let count = self.0.entry(client).or_default();
+        *count += 1;
+        player_events.write(PlayerEvent::MovementViolation {
+            id: client,
+            count: *count,
+        });
+        *count
+    }
+}
+
 /// The things stream sender resource type, used by the souls module to broadcast
 /// `EntitySpawned` for newly bound creatures.
 type ThingsStreamSenderRes = StreamSender<ThingsStreamMessage>;
@@ -38,18 +97,128 @@ impl Plugin for SoulsPlugin {
                     .run_if(resource_exists::<Server>)
                     .after(ThingsSet::HandleClientJoined),
                 unbind_soul.run_if(resource_exists::<Server>),
-                route_input.run_if(resource_exists::<Server>),
             ),
         );
-        app.add_systems(Update, send_input.run_if(resource_exists::<Client>));
+        app.add_systems(
+            SimulationTick,
+            (
+                route_input,
+                ghost::handle_deaths,
+                ghost::handle_respawn_requests,
+                ghost::despawn_abandoned_ghosts,
+                ghost::update_ghost_visibility,
+                spawn_waiting_players,
+                spectate::handle_spectate_requests,
+                spectate::revoke_full_relevancy,
+            )
+                .chain()
+                .run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            PostUpdate,
+            validate_movement.run_if(resource_exists::<Server>),
+        );
+        // PreUpdate, ahead of the tiles module streaming chunks in Update.
+        app.add_systems(
+            PreUpdate,
+            spawn::update_lobby_stream_anchors
+                .run_if(resource_exists::<Server>)
+                .run_if(resource_changed::<SpawnPoints>),
+        );
+        app.add_systems(
+            Update,
+            (
+                send_input,
+                ghost::request_respawn,
+                spectate::request_full_relevancy,
+            )
+                .run_if(resource_exists::<Client>),
+        );
+        app.init_resource::<MovementViolations>();
+        app.init_resource::<SpawnPoints>();
+        app.init_resource::<LobbyStreamAnchors>();
+        app.init_resource::<SpawnPolicy>();
+        app.init_resource::<RespawnRules>();
+        app.init_resource::<FullRelevancy>();
+        app.init_resource::<SpectateRules>();
+        app.init_resource::<spectate::FullRelevancyExpiry>();
+        app.register_map_layer(PlayerSpawnsLayer);
         app.init_resource::<InputSendTimer>();
-        app.init_resource::<LastSentDirection>();
+        app.init_resource::<LastSentInput>();
+
+        // Register the client→server souls stream.
+        // Requires NetworkPlugin to be added first.
+        let (sender, reader): (StreamSender<SoulsRequest>, StreamReader<SoulsRequest>) = app
+            .world_mut()
+            .get_resource_mut::<StreamRegistry>()
+            .expect("SoulsPlugin requires NetworkPlugin to be added before it (StreamRegistry not found)")
+            .register(StreamDef {
+                tag: SOULS_STREAM_TAG,
+                name: "souls",
+                direction: StreamDirection::ClientToServer,
+            });
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+
+        let (sender, reader): (StreamSender<SpectateRequest>, StreamReader<SpectateRequest>) = app
+            .world_mut()
+            .resource_mut::<StreamRegistry>()
+            .register(StreamDef {
+                tag: SPECTATE_STREAM_TAG,
+                name: "spectate",
+                direction: StreamDirection::ClientToServer,
+            });
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+    }
+}
+
+/// Spawns the creature `name` controls for client `id` at `spawn_pos` and
+/// broadcasts `EntitySpawned` on stream 3 so all clients see it.
+fn spawn_creature(
+    commands: &mut Commands,
+    server: &mut Server,
+    stream_sender: &ThingsStreamSenderRes,
+    id: ClientId,
+    name: &str,
+    spawn_pos: Vec3,
+) -> Entity {
+    // Spawn the creature via the things module (allocates NetId internally).
+    let (creature, net_id) = things::spawn_player_creature(commands, server, id, spawn_pos, name);
+
+    info!(
+        "Binding soul for ClientId({}) '{}': spawning creature NetId({})",
+        id.0, name, net_id.0
+    );
+
+    if let Err(e) = stream_sender.broadcast(&ThingsStreamMessage::EntitySpawned {
+        net_id,
+        kind: 0,
+        position: spawn_pos.into(),
+        velocity: [0.0, 0.0, 0.0],
+        owner: Some(id),
+        name: Some(name.to_string()),
+    }) {
+        error!(
+            "Failed to broadcast EntitySpawned for NetId({}): {e}",
+            net_id.0
+        );
     }
+    creature
 }
 
 /// Server-side system: on [`PlayerEvent::Joined`], spawn a soul entity and a creature entity,
 /// set `DisplayName` and `ControlledByClient` on the creature, then broadcast
 /// `EntitySpawned` on stream 3 so all clients (including the joining one) see the new creature.
+/// The creature appears where the [`SpawnSelector`] puts it; under
+/// [`SpawnPolicy::RoleBased`] the roles module moves it again once its role is known.
+///
+/// Spectators get a free-flying observer marked [`Spectator`] instead.  It is
+/// replicated to the spectator alone, which the `things` module takes care of,
+/// and interaction requests from it are rejected.
+///
+/// While the [`RoundState`] is a lobby, players get an unbound soul instead;
+/// [`spawn_waiting_players`] gives them a creature once the round starts.
 ///
 /// Runs after [`ThingsSet::HandleClientJoined`] so the initial `StreamReady` for stream 3
 /// has already been sent to the joining client before this broadcasts the new entity.
@@ -58,21 +227,59 @@ fn bind_soul(
     mut player_events: MessageReader<PlayerEvent>,
     mut server: ResMut<Server>,
     stream_sender: Res<ThingsStreamSenderRes>,
+    round: Option<Res<RoundState>>,
+    mut spawns: SpawnSelector,
 ) {
+    let mut taken = Vec::new();
     for event in player_events.read() {
-        let PlayerEvent::Joined { id, name } = event else {
+        let PlayerEvent::Joined {
+            id,
+            name,
+            spectator,
+        } = event
+        else {
             continue;
         };
 
-        let spawn_pos = Vec3::new(6.0, 0.81, 3.0);
+        if *spectator {
+            let spawn_pos = spawns.fallback();
+            let (observer, net_id) =
+                things::spawn_observer(&mut commands, &mut server, *id, spawn_pos);
+            commands.entity(observer).insert(Spectator);
+            info!(
+                "Binding soul for ClientId({}) '{}' as spectator: spawning observer NetId({})",
+                id.0, name, net_id.0
+            );
+            commands.spawn(Soul {
+                name: name.clone(),
+                client_id: *id,
+                bound_to: Some(observer),
+            });
+            continue;
+        }
 
-        // Spawn the creature via the things module (allocates NetId internally).
-        let (creature, net_id) =
-            things::spawn_player_creature(&mut commands, &mut server, *id, spawn_pos, name);
+        if round.as_ref().is_some_and(|round| round.is_lobby()) {
+            info!(
+                "ClientId({}) '{}' joined the lobby: waiting for the round to start",
+                id.0, name
+            );
+            commands.spawn(Soul {
+                name: name.clone(),
+                client_id: *id,
+                bound_to: None,
+            });
+            continue;
+        }
 
-        info!(
-            "Binding soul for ClientId({}) '{}': spawning creature NetId({})",
-            id.0, name, net_id.0
+        let spawn_pos = spawns.choose(None, &taken);
+        taken.push(spawn_pos);
+        let creature = spawn_creature(
+            &mut commands,
+            &mut server,
+            &stream_sender,
+            *id,
+            name,
+            spawn_pos,
         );
 
         // Spawn the soul entity that binds this client to the creature.
@@ -81,40 +288,62 @@ fn bind_soul(
             client_id: *id,
             bound_to: Some(creature),
         });
+    }
+}
 
-        // Broadcast EntitySpawned to all clients so they see the new creature.
-        if let Err(e) = stream_sender.broadcast(&ThingsStreamMessage::EntitySpawned {
-            net_id,
-            kind: 0,
-            position: spawn_pos.into(),
-            velocity: [0.0, 0.0, 0.0],
-            owner: Some(*id),
-            name: Some(name.clone()),
-        }) {
-            error!(
-                "Failed to broadcast EntitySpawned for NetId({}): {e}",
-                net_id.0
-            );
+/// Server-side system: once the round is no longer a lobby, gives every
+/// living soul bound to nothing, such as players that joined the lobby, a
+/// creature at a spawn point.
+fn spawn_waiting_players(
+    mut commands: Commands,
+    mut server: ResMut<Server>,
+    stream_sender: Res<ThingsStreamSenderRes>,
+    round: Option<Res<RoundState>>,
+    mut souls: Query<&mut Soul, Without<Deceased>>,
+    mut spawns: SpawnSelector,
+) {
+    if round.is_some_and(|round| round.is_lobby()) {
+        return;
+    }
+    let mut taken = Vec::new();
+    for mut soul in souls.iter_mut() {
+        if soul.bound_to.is_some() {
+            continue;
         }
+        let spawn_pos = spawns.choose(None, &taken);
+        taken.push(spawn_pos);
+        let creature = spawn_creature(
+            &mut commands,
+            &mut server,
+            &stream_sender,
+            soul.client_id,
+            &soul.name,
+            spawn_pos,
+        );
+        soul.bound_to = Some(creature);
     }
 }
 
-/// Server-side system: on [`PlayerEvent::Left`], despawn the soul entity and clear
-/// `InputDirection` on the bound creature so it stops moving.
+/// Server-side system: on [`PlayerEvent::Left`], despawn the soul entity, clear
+/// `InputDirection` on the bound creature so it stops moving, and forget the client's
+/// movement violations.
 ///
 /// The creature entity itself remains in the world — it keeps its `Thing`, `Creature`,
 /// `DisplayName`, `NetId`, and physics components and will continue to appear in
 /// `StateUpdate` broadcasts (standing still because `InputDirection` is zeroed).
+/// A [`Ghost`] is despawned instead, once no soul is bound to it.
 fn unbind_soul(
     mut commands: Commands,
     mut player_events: MessageReader<PlayerEvent>,
     souls: Query<(Entity, &Soul)>,
     mut input_dirs: Query<&mut InputDirection>,
+    mut violations: ResMut<MovementViolations>,
 ) {
     for event in player_events.read() {
         let PlayerEvent::Left { id } = event else {
             continue;
         };
+        violations.0.remove(id);
 
         for (soul_entity, soul) in souls.iter() {
             if soul.client_id == *id {
@@ -137,20 +366,66 @@ fn unbind_soul(
     }
 }
 
+/// Checks a client-supplied movement direction.
+///
+/// Returns the horizontal direction to apply and whether the input was invalid.
+/// Non-finite input is replaced by no movement; input longer than a unit vector is
+/// clamped to unit length.  The vertical component is always dropped since
+/// creatures only walk in the XZ plane.
+fn sanitize_direction(direction: [f32; 3]) -> (Vec3, bool) {
+    let direction = Vec3::from_array(direction);
+    if !direction.is_finite() {
+        return (Vec3::ZERO, true);
+    }
+    let horizontal = direction.with_y(0.0);
+    // Small tolerance for clients normalising in a different precision.
+    if horizontal.length_squared() > 1.0 + 1e-3 {
+        return (horizontal.normalize(), true);
+    }
+    (horizontal, false)
+}
+
 /// Server-side system: routes [`ClientInputReceived`] messages to the `InputDirection`
-/// component on the creature bound to that client's soul.
+/// and `MovementModifiers` components on the creature bound to that client's soul.
+///
+/// Directions are passed through [`sanitize_direction`] first; invalid input is
+/// recorded as a movement violation.
+///
+/// Runs in [`SimulationTick`], so input takes effect on tick boundaries.
 fn route_input(
     mut events: MessageReader<ClientInputReceived>,
     souls: Query<&Soul>,
-    mut input_dirs: Query<&mut InputDirection>,
+    mut inputs: Query<(&mut InputDirection, Option<&mut MovementModifiers>)>,
+    mut violations: ResMut<MovementViolations>,
+    mut player_events: MessageWriter<PlayerEvent>,
 ) {
-    for ClientInputReceived { from, direction } in events.read() {
+    for ClientInputReceived {
+        from,
+        direction,
+        sprint,
+        crouch,
+    } in events.read()
+    {
+        let (sanitized, invalid) = sanitize_direction(*direction);
+        if invalid {
+            let count = violations.record(*from, &mut player_events);
+            warn!(
+                "Invalid input direction {direction:?} from ClientId({}) (violation #{count})",
+                from.0
+            );
+        }
         for soul in souls.iter() {
             if soul.client_id == *from {
                 if let Some(creature) = soul.bound_to
-                    && let Ok(mut input_dir) = input_dirs.get_mut(creature)
+                    && let Ok((mut input_dir, modifiers)) = inputs.get_mut(creature)
                 {
-                    input_dir.0 = Vec3::from_array(*direction);
+                    input_dir.0 = sanitized;
+                    if let Some(mut modifiers) = modifiers {
+                        modifiers.set_if_neq(MovementModifiers {
+                            sprint: *sprint,
+                            crouch: *crouch,
+                        });
+                    }
                 }
                 break;
             }
@@ -158,6 +433,79 @@ fn route_input(
     }
 }
 
+/// Server-side system: checks how far each client-controlled creature moved
+/// horizontally since the last frame.
+///
+/// The allowed distance is the creature's [`MovementSpeed`] (times
+/// [`SPEED_TOLERANCE`]) or its current horizontal velocity, whichever is larger, over
+/// the frame time plus [`DISPLACEMENT_MARGIN`].  Taking the velocity into account keeps
+/// physics pushes such as decompression legal.  A move longer than
+/// [`TELEPORT_DISTANCE`] is rolled back to the [`LastValidPosition`]; a shorter one
+/// that still exceeds the bound is clamped to it.  Both count as a violation.
+///
+/// Runs in `PostUpdate`, after physics has moved the creature for this frame.
+#[allow(clippy::type_complexity)]
+fn validate_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut creatures: Query<
+        (
+            Entity,
+            &ControlledByClient,
+            &MovementSpeed,
+            &mut Transform,
+            Option<&mut LinearVelocity>,
+            Option<&mut LastValidPosition>,
+        ),
+        With<InputDirection>,
+    >,
+    mut violations: ResMut<MovementViolations>,
+    mut player_events: MessageWriter<PlayerEvent>,
+) {
+    let dt = time.delta_secs();
+    for (entity, controller, speed, mut transform, velocity, last_valid) in creatures.iter_mut() {
+        let Some(mut last_valid) = last_valid else {
+            commands
+                .entity(entity)
+                .insert(LastValidPosition(transform.translation));
+            continue;
+        };
+
+        let offset = (transform.translation - last_valid.0).with_y(0.0);
+        let distance = offset.length();
+        let physics_speed = velocity.as_ref().map_or(0.0, |v| v.with_y(0.0).length());
+        let allowed = (speed.speed * SPEED_TOLERANCE).max(physics_speed) * dt + DISPLACEMENT_MARGIN;
+
+        if distance <= allowed {
+            last_valid.0 = transform.translation;
+            continue;
+        }
+
+        let client = controller.0;
+        let count = violations.record(client, &mut player_events);
+        if distance > TELEPORT_DISTANCE {
+            warn!(
+                "Rejected teleport of {distance:.2} units by ClientId({}) (violation #{count})",
+                client.0
+            );
+            transform.translation = last_valid.0.with_y(transform.translation.y);
+            if let Some(mut velocity) = velocity {
+                velocity.x = 0.0;
+                velocity.z = 0.0;
+            }
+        } else {
+            warn!(
+                "Clamped move of {distance:.2} units (allowed {allowed:.2}) by ClientId({}) \
+                 (violation #{count})",
+                client.0
+            );
+            let clamped = last_valid.0 + offset / distance * allowed;
+            transform.translation = clamped.with_y(transform.translation.y);
+        }
+        last_valid.0 = transform.translation;
+    }
+}
+
 /// Timer for throttling outbound `Input` messages from the client.
 #[derive(Resource)]
 struct InputSendTimer(Timer);
@@ -171,20 +519,21 @@ impl Default for InputSendTimer {
     }
 }
 
-/// Last direction sent to avoid redundant `Input` messages.
+/// Last direction and modifiers sent to avoid redundant `Input` messages.
 #[derive(Resource, Default)]
-struct LastSentDirection(Vec3);
+struct LastSentInput(Vec3, MovementModifiers);
 
-/// Client-side system: reads `InputDirection` from the `PlayerControlled` creature and
-/// sends `ClientMessage::Input` to the server via the control stream.
+/// Client-side system: reads `InputDirection` and `MovementModifiers` from the
+/// `PlayerControlled` creature and sends `ClientMessage::Input` to the server via the
+/// control stream.
 ///
-/// Throttled to [`INPUT_SEND_INTERVAL`] and skips sends when the direction is unchanged.
+/// Throttled to [`INPUT_SEND_INTERVAL`] and skips sends when the input is unchanged.
 fn send_input(
     time: Res<Time>,
     mut timer: ResMut<InputSendTimer>,
     client_sender: Option<Res<NetClientSender>>,
-    mut last_sent: ResMut<LastSentDirection>,
-    query: Query<&InputDirection, With<things::PlayerControlled>>,
+    mut last_sent: ResMut<LastSentInput>,
+    query: Query<(&InputDirection, Option<&MovementModifiers>), With<things::PlayerControlled>>,
 ) {
     let Some(sender) = client_sender else {
         return;
@@ -194,19 +543,193 @@ fn send_input(
         return;
     }
 
-    let Ok(input) = query.single() else {
+    let Ok((input, modifiers)) = query.single() else {
         return;
     };
 
     let direction = input.0;
-    if direction == last_sent.0 {
+    let modifiers = modifiers.copied().unwrap_or_default();
+    if direction == last_sent.0 && modifiers == last_sent.1 {
         return;
     }
 
-    last_sent.0 = direction;
+    *last_sent = LastSentInput(direction, modifiers);
     if let Err(e) = sender.send(&network::ClientMessage::Input {
         direction: direction.into(),
+        sprint: modifiers.sprint,
+        crouch: modifiers.crouch,
     }) {
         error!("Failed to send client input: {e}");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    const TICK: f32 = 0.1;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            TICK,
+        )));
+        app.add_message::<PlayerEvent>();
+        app.add_message::<ClientInputReceived>();
+        app.init_resource::<MovementViolations>();
+        app.add_systems(Update, route_input);
+        app.add_systems(PostUpdate, validate_movement);
+        app
+    }
+
+    fn spawn_player(app: &mut App, client: ClientId, position: Vec3) -> Entity {
+        let creature = app
+            .world_mut()
+            .spawn((
+                ControlledByClient(client),
+                MovementSpeed { speed: 3.0 },
+                InputDirection::default(),
+                MovementModifiers::default(),
+                LinearVelocity::default(),
+                Transform::from_translation(position),
+            ))
+            .id();
+        app.world_mut().spawn(Soul {
+            name: "tester".into(),
+            client_id: client,
+            bound_to: Some(creature),
+        });
+        // First update records the starting position.
+        app.update();
+        creature
+    }
+
+    fn violation_events(app: &App) -> Vec<(ClientId, u32)> {
+        app.world()
+            .resource::<Messages<PlayerEvent>>()
+            .iter_current_update_messages()
+            .filter_map(|event| match event {
+                PlayerEvent::MovementViolation { id, count } => Some((*id, *count)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sanitize_direction_clamps_and_rejects() {
+        assert_eq!(
+            sanitize_direction([0.6, 0.0, 0.8]),
+            (Vec3::new(0.6, 0.0, 0.8), false)
+        );
+        assert_eq!(sanitize_direction([0.0, 5.0, 0.0]), (Vec3::ZERO, false));
+        assert_eq!(sanitize_direction([10.0, 0.0, 0.0]), (Vec3::X, true));
+        assert_eq!(sanitize_direction([f32::NAN, 0.0, 0.0]), (Vec3::ZERO, true));
+    }
+
+    #[test]
+    fn oversized_input_is_clamped_and_counted() {
+        let mut app = test_app();
+        let client = ClientId(1);
+        let creature = spawn_player(&mut app, client, Vec3::ZERO);
+
+        app.world_mut().write_message(ClientInputReceived {
+            from: client,
+            direction: [0.0, 0.0, 50.0],
+            sprint: true,
+            crouch: false,
+        });
+        app.update();
+
+        assert_eq!(
+            app.world().get::<InputDirection>(creature).unwrap().0,
+            Vec3::Z
+        );
+        assert!(
+            app.world()
+                .get::<MovementModifiers>(creature)
+                .unwrap()
+                .sprint
+        );
+        assert_eq!(
+            app.world().resource::<MovementViolations>().count(client),
+            1
+        );
+        assert_eq!(violation_events(&app), vec![(client, 1)]);
+    }
+
+    #[test]
+    fn normal_movement_is_accepted() {
+        let mut app = test_app();
+        let client = ClientId(1);
+        let creature = spawn_player(&mut app, client, Vec3::new(0.0, 0.81, 0.0));
+
+        // 3 units/s over a 0.1 s frame.
+        app.world_mut()
+            .get_mut::<Transform>(creature)
+            .unwrap()
+            .translation
+            .x = 0.3;
+        app.update();
+
+        assert_eq!(
+            app.world().get::<Transform>(creature).unwrap().translation,
+            Vec3::new(0.3, 0.81, 0.0)
+        );
+        assert_eq!(
+            app.world().resource::<MovementViolations>().count(client),
+            0
+        );
+    }
+
+    #[test]
+    fn fast_movement_is_clamped() {
+        let mut app = test_app();
+        let client = ClientId(1);
+        let creature = spawn_player(&mut app, client, Vec3::ZERO);
+
+        app.world_mut()
+            .get_mut::<Transform>(creature)
+            .unwrap()
+            .translation
+            .x = 1.5;
+        app.update();
+
+        // 3.0 * 1.5 * 0.1 + 0.25
+        let allowed = 0.7;
+        let translation = app.world().get::<Transform>(creature).unwrap().translation;
+        assert!(
+            (translation.x - allowed).abs() < 1e-4,
+            "got {translation:?}"
+        );
+        assert_eq!(
+            app.world().resource::<MovementViolations>().count(client),
+            1
+        );
+    }
+
+    #[test]
+    fn teleport_is_rolled_back() {
+        let mut app = test_app();
+        let client = ClientId(1);
+        let creature = spawn_player(&mut app, client, Vec3::new(1.0, 0.81, 1.0));
+
+        app.world_mut()
+            .get_mut::<Transform>(creature)
+            .unwrap()
+            .translation = Vec3::new(20.0, 0.81, 1.0);
+        app.update();
+
+        assert_eq!(
+            app.world().get::<Transform>(creature).unwrap().translation,
+            Vec3::new(1.0, 0.81, 1.0)
+        );
+        assert_eq!(
+            app.world().get::<LastValidPosition>(creature),
+            Some(&LastValidPosition(Vec3::new(1.0, 0.81, 1.0)))
+        );
+        assert_eq!(violation_events(&app), vec![(client, 1)]);
+    }
+}
@@ -0,0 +1,363 @@
+//! Where player creatures appear.
+//!
+//! The map lists the station's [`PlayerSpawn`]s in its `"player_spawns"`
+//! layer, loaded into the [`SpawnPoints`] resource and written back when the
+//! world is saved.  Maps without the layer spawn everyone at
+//! [`DEFAULT_SPAWN_POSITION`].  The server's [`SpawnPolicy`] decides which
+//! point a joining or respawning player gets, and [`SpawnSelector`] skips
+//! points whose tile is not walkable or has no breathable air, so nobody
+//! appears inside a wall or in a breached room.  Only the main deck's tiles
+//! and atmosphere are checked.  The points also anchor the
+//! [`LobbyStreamAnchors`], so players waiting in the lobby are streamed the
+//! map around where they will appear.
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use network::{ControlledByClient, Spectator};
+use ron::value::RawValue;
+use serde::{Deserialize, Serialize};
+use tiles::{LobbyStreamAnchors, TileFlags};
+use world::{MapLayer, from_layer_value, to_layer_value};
+
+use atmospherics::{FireGrid, GasGrid};
+
+/// Where players appear when the map has no spawn points, or none of them
+/// is safe.
+pub const DEFAULT_SPAWN_POSITION: Vec3 = Vec3::new(6.0, 0.81, 3.0);
+
+/// Lowest pressure, in moles per cell like [`GasGrid::pressure_at`], a
+/// player may be spawned into.
+pub const MIN_SPAWN_PRESSURE: f32 = 80.0;
+
+/// Highest pressure a player may be spawned into.
+pub const MAX_SPAWN_PRESSURE: f32 = 150.0;
+
+/// A place on the map players can appear at.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSpawn {
+    pub position: [f32; 3],
+    /// Role the point is reserved for under [`SpawnPolicy::RoleBased`], by
+    /// role name; `None` for arrivals of any role.
+    #[serde(default)]
+    pub role: Option<String>,
+}
+
+impl PlayerSpawn {
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position: position.to_array(),
+            role: None,
+        }
+    }
+
+    pub fn for_role(position: Vec3, role: impl Into<String>) -> Self {
+        Self {
+            position: position.to_array(),
+            role: Some(role.into()),
+        }
+    }
+
+    pub fn position(&self) -> Vec3 {
+        Vec3::from_array(self.position)
+    }
+}
+
+/// How the server picks a [`PlayerSpawn`] for a player.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpawnPolicy {
+    /// Cycle through the points in map order.
+    #[default]
+    RoundRobin,
+    /// The point farthest from every player already on the station.
+    FarthestFromOthers,
+    /// Cycle through the points reserved for the player's role, falling
+    /// back to the unreserved ones.  Players are placed once their role is
+    /// assigned.
+    RoleBased,
+}
+
+/// The map's player spawn points.  Server only.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct SpawnPoints {
+    spawns: Vec<PlayerSpawn>,
+    /// Index the next round-robin search starts at.
+    next: usize,
+}
+
+impl SpawnPoints {
+    pub fn new(spawns: Vec<PlayerSpawn>) -> Self {
+        Self { spawns, next: 0 }
+    }
+
+    pub fn spawns(&self) -> &[PlayerSpawn] {
+        &self.spawns
+    }
+
+    /// Whether any point is reserved for `role`.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.spawns.iter().any(|s| s.role.as_deref() == Some(role))
+    }
+
+    /// Where to spawn when [`choose`](Self::choose) finds no safe point:
+    /// the first point, or [`DEFAULT_SPAWN_POSITION`] without any.
+    pub fn fallback(&self) -> Vec3 {
+        self.spawns
+            .first()
+            .map_or(DEFAULT_SPAWN_POSITION, PlayerSpawn::position)
+    }
+
+    /// Picks a point for a player with `role` under `policy`, among those
+    /// `is_safe` accepts.  `others` are the positions of the players already
+    /// there.  `None` when no candidate is safe.
+    pub fn choose(
+        &mut self,
+        policy: SpawnPolicy,
+        role: Option<&str>,
+        others: &[Vec3],
+        is_safe: impl Fn(Vec3) -> bool,
+    ) -> Option<Vec3> {
+        let candidates = match policy {
+            SpawnPolicy::RoleBased => self.reserved_for(role),
+            SpawnPolicy::RoundRobin | SpawnPolicy::FarthestFromOthers => {
+                (0..self.spawns.len()).collect()
+            }
+        };
+        let safe: Vec<usize> = candidates
+            .into_iter()
+            .filter(|&i| is_safe(self.spawns[i].position()))
+            .collect();
+
+        let chosen = match policy {
+            SpawnPolicy::FarthestFromOthers => {
+                let clearance = |i: usize| {
+                    let position = self.spawns[i].position();
+                    others
+                        .iter()
+                        .map(|other| other.distance(position))
+                        .fold(f32::INFINITY, f32::min)
+                };
+                safe.iter().copied().reduce(|best, i| {
+                    if clearance(i) > clearance(best) {
+                        i
+                    } else {
+                        best
+                    }
+                })
+            }
+            SpawnPolicy::RoundRobin | SpawnPolicy::RoleBased => safe
+                .iter()
+                .copied()
+                .find(|&i| i >= self.next)
+                .or(safe.first().copied()),
+        }?;
+        self.next = chosen + 1;
+        Some(self.spawns[chosen].position())
+    }
+
+    /// Indices of the points reserved for `role`, else of the unreserved
+    /// points, else of every point.
+    fn reserved_for(&self, role: Option<&str>) -> Vec<usize> {
+        let matching = |wanted: Option<&str>| -> Vec<usize> {
+            (0..self.spawns.len())
+                .filter(|&i| self.spawns[i].role.as_deref() == wanted)
+                .collect()
+        };
+        [role.map(|role| matching(Some(role))), Some(matching(None))]
+            .into_iter()
+            .flatten()
+            .find(|indices| !indices.is_empty())
+            .unwrap_or_else(|| (0..self.spawns.len()).collect())
+    }
+}
+
+/// System parameter that picks spawn positions with the server's
+/// [`SpawnPolicy`], checking each candidate's tile and air.
+#[derive(SystemParam)]
+pub struct SpawnSelector<'w, 's> {
+    points: ResMut<'w, SpawnPoints>,
+    policy: Res<'w, SpawnPolicy>,
+    flags: Option<Res<'w, TileFlags>>,
+    gas: Option<Res<'w, GasGrid>>,
+    fire: Option<Res<'w, FireGrid>>,
+    players: Query<'w, 's, &'static Transform, (With<ControlledByClient>, Without<Spectator>)>,
+}
+
+impl SpawnSelector<'_, '_> {
+    pub fn policy(&self) -> SpawnPolicy {
+        *self.policy
+    }
+
+    /// Whether any point is reserved for `role`.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.points.has_role(role)
+    }
+
+    /// See [`SpawnPoints::fallback`].
+    pub fn fallback(&self) -> Vec3 {
+        self.points.fallback()
+    }
+
+    /// Whether a player may appear at `position`: its tile is walkable,
+    /// not burning, and its pressure lies between [`MIN_SPAWN_PRESSURE`] and
+    /// [`MAX_SPAWN_PRESSURE`].  Grids that do not exist are not checked.
+    pub fn is_safe(&self, position: Vec3) -> bool {
+        tile_is_safe(
+            self.flags.as_deref(),
+            self.gas.as_deref(),
+            self.fire.as_deref(),
+            position,
+        )
+    }
+
+    /// Spawn position for a player with `role`.  `taken` are positions
+    /// handed out earlier in the same frame, whose creatures the player
+    /// query does not see yet.  Falls back to [`SpawnPoints::fallback`],
+    /// with a warning, when no point is safe.
+    pub fn choose(&mut self, role: Option<&str>, taken: &[Vec3]) -> Vec3 {
+        let mut others: Vec<Vec3> = self.players.iter().map(|t| t.translation).collect();
+        others.extend_from_slice(taken);
+        let (flags, gas, fire) = (
+            self.flags.as_deref(),
+            self.gas.as_deref(),
+            self.fire.as_deref(),
+        );
+        let chosen = self.points.choose(*self.policy, role, &others, |position| {
+            tile_is_safe(flags, gas, fire, position)
+        });
+        chosen.unwrap_or_else(|| {
+            let fallback = self.points.fallback();
+            warn!("No safe spawn point for role {role:?}, spawning at {fallback}");
+            fallback
+        })
+    }
+}
+
+/// Server system: points the [`LobbyStreamAnchors`] at the spawn points, or
+/// at [`SpawnPoints::fallback`] when the map has none.
+pub(crate) fn update_lobby_stream_anchors(
+    points: Res<SpawnPoints>,
+    mut anchors: ResMut<LobbyStreamAnchors>,
+) {
+    let positions: Vec<Vec3> = if points.spawns.is_empty() {
+        vec![points.fallback()]
+    } else {
+        points.spawns.iter().map(PlayerSpawn::position).collect()
+    };
+    let tiles: Vec<IVec2> = positions
+        .into_iter()
+        .map(|p| IVec2::new(p.x.round() as i32, p.z.round() as i32))
+        .collect();
+    anchors.set_if_neq(LobbyStreamAnchors(tiles));
+}
+
+fn tile_is_safe(
+    flags: Option<&TileFlags>,
+    gas: Option<&GasGrid>,
+    fire: Option<&FireGrid>,
+    position: Vec3,
+) -> bool {
+    let cell = IVec2::new(position.x.round() as i32, position.z.round() as i32);
+    let walkable = flags.is_none_or(|f| f.is_walkable(cell));
+    let breathable = gas.is_none_or(|gas| {
+        gas.pressure_at(cell)
+            .is_some_and(|p| (MIN_SPAWN_PRESSURE..=MAX_SPAWN_PRESSURE).contains(&p))
+    });
+    let burning = fire.is_some_and(|f| f.is_burning(cell));
+    walkable && breathable && !burning
+}
+
+/// `MapLayer` implementation for the `"player_spawns"` layer.
+///
+/// **Load:** deserializes a list of [`PlayerSpawn`]s into [`SpawnPoints`];
+/// maps without the layer get an empty one.
+/// **Save:** serializes the current [`SpawnPoints`].
+pub struct PlayerSpawnsLayer;
+
+impl MapLayer for PlayerSpawnsLayer {
+    fn key(&self) -> &'static str {
+        "player_spawns"
+    }
+
+    fn save(
+        &self,
+        world: &World,
+    ) -> Result<Box<RawValue>, Box<dyn std::error::Error + Send + Sync>> {
+        let spawns = world
+            .get_resource::<SpawnPoints>()
+            .map(|points| points.spawns.clone())
+            .unwrap_or_default();
+        to_layer_value(&spawns).map_err(Into::into)
+    }
+
+    fn load(
+        &self,
+        data: &RawValue,
+        world: &mut World,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let spawns: Vec<PlayerSpawn> = from_layer_value(data)?;
+        world.insert_resource(SpawnPoints::new(spawns));
+        Ok(())
+    }
+
+    fn unload(&self, world: &mut World) {
+        world.insert_resource(SpawnPoints::default());
+    }
+
+    fn load_default(
+        &self,
+        world: &mut World,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        world.insert_resource(SpawnPoints::default());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points() -> SpawnPoints {
+        SpawnPoints::new(vec![
+            PlayerSpawn::new(Vec3::new(0.0, 0.0, 0.0)),
+            PlayerSpawn::new(Vec3::new(10.0, 0.0, 0.0)),
+            PlayerSpawn::for_role(Vec3::new(20.0, 0.0, 0.0), "engineer"),
+        ])
+    }
+
+    #[test]
+    fn policies_pick_safe_points() {
+        let safe = |_: Vec3| true;
+        let mut spawns = points();
+        let round_robin: Vec<f32> = (0..4)
+            .map(|_| {
+                spawns
+                    .choose(SpawnPolicy::RoundRobin, None, &[], safe)
+                    .unwrap()
+                    .x
+            })
+            .collect();
+        assert_eq!(round_robin, vec![0.0, 10.0, 20.0, 0.0]);
+
+        let others = [Vec3::new(18.0, 0.0, 0.0)];
+        let farthest = points().choose(SpawnPolicy::FarthestFromOthers, None, &others, safe);
+        assert_eq!(farthest, Some(Vec3::ZERO));
+
+        let mut spawns = points();
+        let engineer = spawns.choose(SpawnPolicy::RoleBased, Some("engineer"), &[], safe);
+        assert_eq!(engineer, Some(Vec3::new(20.0, 0.0, 0.0)));
+        let medic = spawns.choose(SpawnPolicy::RoleBased, Some("medic"), &[], safe);
+        assert_eq!(medic, Some(Vec3::ZERO));
+
+        let not_origin = |position: Vec3| position.x != 0.0;
+        let mut spawns = points();
+        assert_eq!(
+            spawns.choose(SpawnPolicy::RoleBased, None, &[], not_origin),
+            Some(Vec3::new(10.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            points().choose(SpawnPolicy::RoundRobin, None, &[], |_| false),
+            None
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! Full-map view for spectators.
+//!
+//! The map is normally streamed to a client around the entity it controls
+//! (see the `tiles` module).  A spectator may press
+//! [`Action::ToggleFullRelevancy`] to send
+//! [`SpectateRequest::FullRelevancy`] and be streamed the whole map
+//! instead, so its camera can follow players anywhere on the station.
+//!
+//! The server only grants it to the host (see [`SpectateRules::is_admin`])
+//! while their soul is bound to an observer: ghosts are [`Spectator`]s too,
+//! but may rejoin the round and must not scout it, and any player may join
+//! as an observer.  The grant is held in [`FullRelevancy`] and lasts until the spectator asks to
+//! drop it, leaves, stops being an observer, or
+//! [`SpectateRules::grant_duration`] is up.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use input::{Action, ActionInput};
+use network::{ClientId, FullRelevancy, Spectator, StreamReader, StreamSender, stream_tag};
+use things::{OBSERVER_KIND, PlayerControlled, Thing};
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{Ghost, Soul};
+
+/// Tag of the client→server spectate stream.
+pub const SPECTATE_STREAM_TAG: u8 = stream_tag("spectate");
+
+/// How long a full relevancy grant lasts before the spectator has to ask again.
+pub const FULL_RELEVANCY_DURATION: Duration = Duration::from_secs(10 * 60);
+
+/// Server resource: how long a [`FullRelevancy`] grant lasts.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct SpectateRules {
+    /// How long a grant lasts before it is dropped.
+    pub grant_duration: Duration,
+}
+
+impl Default for SpectateRules {
+    fn default() -> Self {
+        Self {
+            grant_duration: FULL_RELEVANCY_DURATION,
+        }
+    }
+}
+
+impl SpectateRules {
+    /// Whether `client` may view the whole map: only the player connected
+    /// over loopback, i.e. on the machine hosting the server, is.  The name
+    /// a client sends in its `Hello` is its own choice and proves nothing.
+    pub fn is_admin(client: ClientId) -> bool {
+        client == ClientId::LOCAL
+    }
+}
+
+/// Server resource: when each [`FullRelevancy`] grant expires, as elapsed
+/// [`Time`].
+#[derive(Resource, Debug, Default)]
+pub(crate) struct FullRelevancyExpiry(HashMap<ClientId, Duration>);
+
+/// Spectate stream wire format: client→server requests of spectators.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub enum SpectateRequest {
+    /// The sender wants the whole map streamed to it, or no longer does.
+    FullRelevancy { enabled: bool },
+}
+
+/// Server system: grants and drops [`FullRelevancy`] on request.  Requests
+/// from clients that are not admins or not bound to an observer are ignored.
+pub(crate) fn handle_spectate_requests(
+    mut reader: ResMut<StreamReader<SpectateRequest>>,
+    mut full_relevancy: ResMut<FullRelevancy>,
+    mut expiry: ResMut<FullRelevancyExpiry>,
+    rules: Res<SpectateRules>,
+    time: Res<Time>,
+    souls: Query<&Soul>,
+    observers: Query<(), (With<Spectator>, Without<Ghost>)>,
+) {
+    for (from, SpectateRequest::FullRelevancy { enabled }) in reader.drain_from_client() {
+        let Some(soul) = souls.iter().find(|soul| soul.client_id == from) else {
+            continue;
+        };
+        if !enabled {
+            expiry.0.remove(&from);
+            if full_relevancy.0.remove(&from) {
+                info!(
+                    "ClientId({}) '{}' dropped full relevancy",
+                    from.0, soul.name
+                );
+            }
+            continue;
+        }
+        if !soul.bound_to.is_some_and(|bound| observers.contains(bound)) {
+            warn!(
+                "Full relevancy request from ClientId({}) '{}' refused: not spectating",
+                from.0, soul.name
+            );
+            continue;
+        }
+        if !SpectateRules::is_admin(from) {
+            warn!(
+                "Full relevancy request from ClientId({}) '{}' refused: not an admin",
+                from.0, soul.name
+            );
+            continue;
+        }
+        expiry.0.insert(from, time.elapsed() + rules.grant_duration);
+        if full_relevancy.0.insert(from) {
+            info!(
+                "ClientId({}) '{}' granted full relevancy for {:?}",
+                from.0, soul.name, rules.grant_duration
+            );
+        }
+    }
+}
+
+/// Server system: drops [`FullRelevancy`] of clients that left, are no
+/// longer bound to an observer, or whose grant expired.
+pub(crate) fn revoke_full_relevancy(
+    mut full_relevancy: ResMut<FullRelevancy>,
+    mut expiry: ResMut<FullRelevancyExpiry>,
+    time: Res<Time>,
+    souls: Query<&Soul>,
+    observers: Query<(), (With<Spectator>, Without<Ghost>)>,
+) {
+    if full_relevancy.0.is_empty() {
+        return;
+    }
+    let now = time.elapsed();
+    full_relevancy.0.retain(|&client| {
+        let expired = expiry.0.get(&client).is_some_and(|&at| at <= now);
+        if expired {
+            info!("ClientId({}) full relevancy expired", client.0);
+        }
+        !expired
+            && souls.iter().any(|soul| {
+                soul.client_id == client
+                    && soul.bound_to.is_some_and(|bound| observers.contains(bound))
+            })
+    });
+    expiry
+        .0
+        .retain(|client, _| full_relevancy.0.contains(client));
+}
+
+/// Client system: toggles full relevancy on [`Action::ToggleFullRelevancy`]
+/// while the player controls an observer.
+pub(crate) fn request_full_relevancy(
+    actions: ActionInput,
+    player: Query<&Thing, With<PlayerControlled>>,
+    sender: Option<Res<StreamSender<SpectateRequest>>>,
+    mut enabled: Local<bool>,
+) {
+    if !actions.just_pressed(Action::ToggleFullRelevancy) {
+        return;
+    }
+    let is_observer = player.iter().any(|thing| thing.kind == OBSERVER_KIND);
+    let Some(sender) = sender.filter(|_| is_observer) else {
+        return;
+    };
+    let request = SpectateRequest::FullRelevancy { enabled: !*enabled };
+    if let Err(e) = sender.send(&request) {
+        error!("Failed to send full relevancy request: {e}");
+        return;
+    }
+    *enabled = !*enabled;
+    info!(
+        "Full map view {}",
+        if *enabled { "requested" } else { "dropped" }
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use network::{StreamDef, StreamDirection, StreamRegistry};
+
+    use super::*;
+
+    fn make_request_app(rules: SpectateRules) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<StreamRegistry>();
+        let (_, reader): (StreamSender<SpectateRequest>, StreamReader<SpectateRequest>) = app
+            .world_mut()
+            .resource_mut::<StreamRegistry>()
+            .register(StreamDef {
+                tag: SPECTATE_STREAM_TAG,
+                name: "spectate",
+                direction: StreamDirection::ClientToServer,
+            });
+        app.insert_resource(reader);
+        app.insert_resource(rules);
+        app.init_resource::<FullRelevancy>();
+        app.init_resource::<FullRelevancyExpiry>();
+        app.add_systems(
+            Update,
+            (handle_spectate_requests, revoke_full_relevancy).chain(),
+        );
+        app
+    }
+
+    /// Spawns an observer and binds a soul named `name` on `client` to it.
+    fn spawn_observer(app: &mut App, client: ClientId, name: &str) {
+        let observer = app.world_mut().spawn(Spectator).id();
+        app.world_mut().spawn(Soul {
+            name: name.to_string(),
+            client_id: client,
+            bound_to: Some(observer),
+        });
+    }
+
+    fn request_full_relevancy_from(app: &mut App, from: ClientId) {
+        let bytes = wincode::serialize(&SpectateRequest::FullRelevancy { enabled: true })
+            .expect("serialize");
+        app.world_mut()
+            .resource_mut::<StreamRegistry>()
+            .route_client_stream_frame(from, SPECTATE_STREAM_TAG, bytes::Bytes::from(bytes));
+    }
+
+    #[test]
+    fn full_relevancy_is_refused_to_non_admin_observers() {
+        let mut app = make_request_app(SpectateRules::default());
+        spawn_observer(&mut app, ClientId::LOCAL, "host");
+        // Naming oneself like the host grants nothing.
+        spawn_observer(&mut app, ClientId(2), "host");
+        request_full_relevancy_from(&mut app, ClientId::LOCAL);
+        request_full_relevancy_from(&mut app, ClientId(2));
+
+        app.update();
+
+        let granted = &app.world().resource::<FullRelevancy>().0;
+        assert_eq!(granted.len(), 1);
+        assert!(granted.contains(&ClientId::LOCAL));
+    }
+
+    #[test]
+    fn full_relevancy_expires_after_grant_duration() {
+        let mut app = make_request_app(SpectateRules {
+            grant_duration: Duration::from_millis(50),
+        });
+        spawn_observer(&mut app, ClientId::LOCAL, "host");
+        request_full_relevancy_from(&mut app, ClientId::LOCAL);
+
+        app.update();
+        assert!(
+            app.world()
+                .resource::<FullRelevancy>()
+                .0
+                .contains(&ClientId::LOCAL)
+        );
+
+        std::thread::sleep(Duration::from_millis(100));
+        app.update();
+        assert!(app.world().resource::<FullRelevancy>().0.is_empty());
+    }
+
+    #[test]
+    fn full_relevancy_is_revoked_from_non_observers() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<FullRelevancy>();
+        app.init_resource::<FullRelevancyExpiry>();
+        app.add_systems(Update, revoke_full_relevancy);
+
+        let observer = app.world_mut().spawn(Spectator).id();
+        let ghost = app.world_mut().spawn((Spectator, Ghost)).id();
+        for (id, bound_to) in [(1, Some(observer)), (2, Some(ghost)), (3, None)] {
+            app.world_mut().spawn(Soul {
+                name: format!("player {id}"),
+                client_id: ClientId(id),
+                bound_to,
+            });
+        }
+        app.world_mut()
+            .resource_mut::<FullRelevancy>()
+            .0
+            .extend([1, 2, 3, 4].map(ClientId));
+
+        app.update();
+
+        let granted = &app.world().resource::<FullRelevancy>().0;
+        assert_eq!(granted.len(), 1);
+        assert!(granted.contains(&ClientId(1)));
+    }
+}
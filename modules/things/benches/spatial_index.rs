@@ -0,0 +1,83 @@
+//! Grid queries on [`SpatialIndex`] against a linear scan over 10k things.
+//!
+//! Run with `cargo bench -p things --bench spatial_index`.
+
+use std::hint::black_box;
+
+use bevy::prelude::*;
+use criterion::{Criterion, criterion_group, criterion_main};
+use things::SpatialIndex;
+
+const ENTITIES: usize = 10_000;
+const QUERIES: usize = 1_000;
+const RADIUS: f32 = 2.0;
+
+/// Deterministic scatter of things and probe points over a 200×200 tile
+/// station.
+fn scatter() -> (SpatialIndex, Vec<(Entity, Vec3)>, Vec<Vec3>) {
+    let mut world = World::new();
+    let mut index = SpatialIndex::default();
+    let mut linear = Vec::with_capacity(ENTITIES);
+    let mut seed = 0x2545_F491_4F6C_DD1Du64;
+    let mut next = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed >> 40) as f32 / (1u64 << 24) as f32 * 200.0
+    };
+    for _ in 0..ENTITIES {
+        let entity = world.spawn_empty().id();
+        let position = Vec3::new(next(), 0.0, next());
+        index.insert(entity, position);
+        linear.push((entity, position));
+    }
+    let probes = (0..QUERIES)
+        .map(|_| Vec3::new(next(), 0.0, next()))
+        .collect();
+    (index, linear, probes)
+}
+
+fn proximity_queries(c: &mut Criterion) {
+    let (index, linear, probes) = scatter();
+    let linear_hits: usize = probes
+        .iter()
+        .map(|&probe| {
+            linear
+                .iter()
+                .filter(|(_, p)| p.distance(probe) <= RADIUS)
+                .count()
+        })
+        .sum();
+    let grid_hits: usize = probes
+        .iter()
+        .map(|&probe| index.query_within(probe, RADIUS).count())
+        .sum();
+    assert_eq!(linear_hits, grid_hits);
+
+    let mut group = c.benchmark_group("spatial_index_10k");
+    group.bench_function("linear_scan", |b| {
+        b.iter(|| {
+            probes
+                .iter()
+                .map(|&probe| {
+                    black_box(&linear)
+                        .iter()
+                        .filter(|(_, p)| p.distance(probe) <= RADIUS)
+                        .count()
+                })
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("query_within", |b| {
+        b.iter(|| {
+            probes
+                .iter()
+                .map(|&probe| black_box(&index).query_within(probe, RADIUS).count())
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, proximity_queries);
+criterion_main!(benches);
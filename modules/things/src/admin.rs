@@ -0,0 +1,223 @@
+//! Looking up and removing live things, for server admins.
+//!
+//! [`ThingQuery`] messages are answered by the server in the log: a
+//! [`ThingQuery::List`] logs a [`ThingSummary`] for every thing matching its
+//! [`ThingFilter`], a [`ThingQuery::Despawn`] removes one thing by
+//! [`NetId`] and tells the clients.  [`list_things`] and [`despawn_thing`]
+//! do the work and may be called directly with the world.
+
+use std::fmt;
+
+use bevy::prelude::*;
+use network::{ClientId, ControlledByClient, NetId, StreamSender};
+
+use crate::{DespawnReason, DisplayName, NetIdIndex, Thing, ThingsStreamMessage};
+
+/// Which things a [`ThingQuery::List`] is about.  Empty matches every
+/// thing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThingFilter {
+    /// Only things of this kind.
+    pub kind: Option<u16>,
+    /// Only free-standing things within this distance, in tiles, of this
+    /// cell.  The distance is measured on the floor.
+    pub near: Option<(Vec2, f32)>,
+}
+
+impl ThingFilter {
+    fn matches(&self, summary: &ThingSummary) -> bool {
+        self.kind.is_none_or(|kind| kind == summary.kind)
+            && self.near.is_none_or(|(cell, radius)| {
+                summary
+                    .position
+                    .is_some_and(|p| Vec2::new(p.x, p.z).distance(cell) <= radius)
+            })
+    }
+}
+
+/// One thing as an admin sees it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThingSummary {
+    pub net_id: NetId,
+    pub kind: u16,
+    pub name: Option<String>,
+    /// Where it stands; `None` while it is held or stored.
+    pub position: Option<Vec3>,
+    /// Client controlling it, if any.
+    pub controlled_by: Option<ClientId>,
+}
+
+impl fmt::Display for ThingSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NetId({}) kind {}", self.net_id.0, self.kind)?;
+        if let Some(name) = &self.name {
+            write!(f, " \"{name}\"")?;
+        }
+        match self.position {
+            Some(p) => write!(f, " at ({:.1}, {:.1}, {:.1})", p.x, p.y, p.z)?,
+            None => write!(f, " held or stored")?,
+        }
+        if let Some(client) = self.controlled_by {
+            write!(f, ", controlled by ClientId({})", client.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Server-side admin request about things, answered in the log.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub enum ThingQuery {
+    /// Log every thing matching the filter.
+    List(ThingFilter),
+    /// Despawn the thing with this NetId.
+    Despawn(NetId),
+}
+
+type SummaryQuery = (
+    &'static NetId,
+    &'static Thing,
+    &'static Transform,
+    Option<&'static DisplayName>,
+    Option<&'static ControlledByClient>,
+    Has<ChildOf>,
+);
+
+type SummaryItem<'a> = (
+    &'a NetId,
+    &'a Thing,
+    &'a Transform,
+    Option<&'a DisplayName>,
+    Option<&'a ControlledByClient>,
+    bool,
+);
+
+fn summarise((net_id, thing, transform, name, controlled, parented): SummaryItem) -> ThingSummary {
+    ThingSummary {
+        net_id: *net_id,
+        kind: thing.kind,
+        name: name.map(|name| name.0.clone()),
+        position: (!parented).then_some(transform.translation),
+        controlled_by: controlled.map(|c| c.0),
+    }
+}
+
+/// Every replicated thing matching `filter`, by NetId.
+pub fn list_things(world: &mut World, filter: &ThingFilter) -> Vec<ThingSummary> {
+    let mut query = world.query::<SummaryQuery>();
+    let mut things: Vec<ThingSummary> = query
+        .iter(world)
+        .map(summarise)
+        .filter(|summary| filter.matches(summary))
+        .collect();
+    things.sort_by_key(|summary| summary.net_id.0);
+    things
+}
+
+/// Despawns the thing replicated as `net_id` on the server and on every
+/// client, with what it held or stored.  Things controlled by a client are
+/// refused; their players should be kicked instead.
+pub fn despawn_thing(world: &mut World, net_id: NetId) -> Result<ThingSummary, String> {
+    let entity = world
+        .resource::<NetIdIndex>()
+        .get(net_id)
+        .ok_or_else(|| format!("no thing has NetId({})", net_id.0))?;
+    let mut query = world.query::<SummaryQuery>();
+    let summary = query
+        .get(world, entity)
+        .map(summarise)
+        .map_err(|_| format!("NetId({}) is not a thing", net_id.0))?;
+    if let Some(client) = summary.controlled_by {
+        return Err(format!(
+            "NetId({}) is controlled by ClientId({}); kick the player instead",
+            net_id.0, client.0
+        ));
+    }
+    if let Some(sender) = world.get_resource::<StreamSender<ThingsStreamMessage>>()
+        && let Err(e) = sender.broadcast(&ThingsStreamMessage::EntityDespawned {
+            net_id,
+            reason: DespawnReason::Destroyed,
+        })
+    {
+        error!(
+            "Failed to broadcast EntityDespawned for NetId({}): {e}",
+            net_id.0
+        );
+    }
+    world.despawn(entity);
+    Ok(summary)
+}
+
+/// Server system answering [`ThingQuery`]s in the log.
+pub(crate) fn handle_thing_queries(world: &mut World) {
+    let queries: Vec<ThingQuery> = world
+        .resource_mut::<Messages<ThingQuery>>()
+        .drain()
+        .collect();
+    for query in queries {
+        match query {
+            ThingQuery::List(filter) => {
+                let things = list_things(world, &filter);
+                for thing in &things {
+                    info!("{thing}");
+                }
+                info!("{} thing(s) match {filter:?}", things.len());
+            }
+            ThingQuery::Despawn(net_id) => match despawn_thing(world, net_id) {
+                Ok(thing) => info!("Despawned {thing}"),
+                Err(e) => warn!("Despawn failed: {e}"),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(world: &mut World, net_id: u64, kind: u16, at: Vec3) -> Entity {
+        let entity = world
+            .spawn((
+                NetId(net_id),
+                Thing { kind },
+                Transform::from_translation(at),
+            ))
+            .id();
+        world
+            .resource_mut::<NetIdIndex>()
+            .0
+            .insert(NetId(net_id), entity);
+        entity
+    }
+
+    #[test]
+    fn things_are_listed_by_kind_and_distance_and_despawned_by_net_id() {
+        let mut world = World::new();
+        world.init_resource::<NetIdIndex>();
+        spawn(&mut world, 3, 2, Vec3::new(4.0, 0.5, 4.0));
+        spawn(&mut world, 1, 2, Vec3::new(30.0, 0.5, 4.0));
+        spawn(&mut world, 2, 1, Vec3::new(5.0, 0.5, 5.0));
+        let player = spawn(&mut world, 4, 0, Vec3::new(4.0, 0.8, 5.0));
+        world
+            .entity_mut(player)
+            .insert(ControlledByClient(ClientId(7)));
+
+        let kind_2 = ThingFilter {
+            kind: Some(2),
+            near: None,
+        };
+        let ids = |things: Vec<ThingSummary>| -> Vec<u64> {
+            things.iter().map(|thing| thing.net_id.0).collect()
+        };
+        assert_eq!(ids(list_things(&mut world, &kind_2)), vec![1, 3]);
+        let near = ThingFilter {
+            near: Some((Vec2::new(4.0, 4.0), 2.0)),
+            ..kind_2.clone()
+        };
+        assert_eq!(ids(list_things(&mut world, &near)), vec![3]);
+
+        assert!(despawn_thing(&mut world, NetId(4)).is_err());
+        assert!(despawn_thing(&mut world, NetId(9)).is_err());
+        assert_eq!(despawn_thing(&mut world, NetId(3)).unwrap().kind, 2);
+        assert_eq!(ids(list_things(&mut world, &kind_2)), vec![1]);
+    }
+}
@@ -0,0 +1,178 @@
+//! NetId liveness: which replicated entities still exist.
+//!
+//! On both sides [`NetIdIndex`] drops an entry as soon as its entity loses
+//! its [`NetId`] or is despawned, so [`NetIdIndex::is_live`] answers for
+//! whatever the world holds right now.  A client can still keep replicas of
+//! entities the server removed without telling it, e.g. things that fell out
+//! of the world.  Every [`NET_ID_AUDIT_INTERVAL`] seconds the server sends
+//! each client [`ThingsStreamMessage::NetIdAudit`] listing the NetIds that
+//! are live for it, and the client despawns replicas missing from the list.
+//!
+//! NetIds are 64-bit and never reused, so a stale mapping cannot be mistaken
+//! for a newer entity.
+
+use bevy::prelude::*;
+use network::{NetId, Server, StreamSender};
+
+use crate::despawn::{DespawnReason, despawn_replica};
+use crate::visibility::ReplicationScope;
+use crate::{NetIdIndex, ThingsStreamMessage};
+
+/// Seconds between two [`ThingsStreamMessage::NetIdAudit`]s to a client.
+pub const NET_ID_AUDIT_INTERVAL: f32 = 10.0;
+
+/// Timer for the periodic NetId audits.
+#[derive(Resource)]
+pub(crate) struct NetIdAuditTimer(pub Timer);
+
+impl Default for NetIdAuditTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(
+            NET_ID_AUDIT_INTERVAL,
+            TimerMode::Repeating,
+        ))
+    }
+}
+
+impl NetIdIndex {
+    /// The entity replicated as `net_id`, if it is live.
+    pub fn get(&self, net_id: NetId) -> Option<Entity> {
+        self.0.get(&net_id).copied()
+    }
+
+    /// Whether an entity is replicated as `net_id` in this world.
+    pub fn is_live(&self, net_id: NetId) -> bool {
+        self.0.contains_key(&net_id)
+    }
+}
+
+/// Folds `ids` into sorted inclusive `[first, last]` runs of consecutive
+/// NetIds.  Replicated things are mostly spawned together, so a few runs
+/// cover many entities.
+pub fn net_id_runs(ids: impl IntoIterator<Item = NetId>) -> Vec<[u64; 2]> {
+    let mut ids: Vec<u64> = ids.into_iter().map(|id| id.0).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    let mut runs: Vec<[u64; 2]> = Vec::new();
+    for id in ids {
+        match runs.last_mut() {
+            Some(run) if run[1] + 1 == id => run[1] = id,
+            _ => runs.push([id, id]),
+        }
+    }
+    runs
+}
+
+/// Whether `net_id` falls in one of the sorted `runs`.
+pub fn runs_contain(runs: &[[u64; 2]], net_id: NetId) -> bool {
+    let after = runs.partition_point(|run| run[1] < net_id.0);
+    runs.get(after).is_some_and(|run| run[0] <= net_id.0)
+}
+
+/// Server system: every [`NET_ID_AUDIT_INTERVAL`], sends each client the
+/// NetIds it may see.
+pub(crate) fn send_net_id_audits(
+    time: Res<Time>,
+    mut timer: ResMut<NetIdAuditTimer>,
+    server: Res<Server>,
+    net_id_index: Res<NetIdIndex>,
+    sender: Res<StreamSender<ThingsStreamMessage>>,
+    scope: ReplicationScope,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    for client in scope.clients() {
+        let live = net_id_runs(
+            net_id_index
+                .0
+                .iter()
+                .filter(|&(_, &entity)| scope.is_visible_to(entity, client))
+                .map(|(&net_id, _)| net_id),
+        );
+        let msg = ThingsStreamMessage::NetIdAudit {
+            live,
+            issued_below: server.issued_below(),
+        };
+        if let Err(e) = sender.send_to(client, &msg) {
+            error!("Failed to send NetIdAudit to ClientId({}): {e}", client.0);
+        }
+    }
+}
+
+/// Client side of [`ThingsStreamMessage::NetIdAudit`]: despawns every replica
+/// issued before the audit but missing from `live`.  Replicas issued after
+/// it may have arrived first and are left alone.
+pub(crate) fn purge_stale_replicas(
+    commands: &mut Commands,
+    net_id_index: &mut NetIdIndex,
+    live: &[[u64; 2]],
+    issued_below: NetId,
+) {
+    let stale: Vec<NetId> = net_id_index
+        .0
+        .keys()
+        .copied()
+        .filter(|&net_id| net_id.0 < issued_below.0 && !runs_contain(live, net_id))
+        .collect();
+    if stale.is_empty() {
+        return;
+    }
+    warn!(
+        "NetIdAudit: despawning {} stale replicas: {stale:?}",
+        stale.len()
+    );
+    for net_id in stale {
+        if let Some(entity) = net_id_index.0.remove(&net_id) {
+            despawn_replica(commands, entity, DespawnReason::OutOfRelevancy);
+        }
+    }
+}
+
+/// Drops the [`NetIdIndex`] entry of an entity that loses its [`NetId`],
+/// including when it is despawned.
+pub(crate) fn on_net_id_removed(
+    trigger: On<Remove, NetId>,
+    net_ids: Query<&NetId>,
+    net_id_index: Option<ResMut<NetIdIndex>>,
+) {
+    let entity = trigger.event_target();
+    let (Ok(net_id), Some(mut net_id_index)) = (net_ids.get(entity), net_id_index) else {
+        return;
+    };
+    if net_id_index.get(*net_id) == Some(entity) {
+        net_id_index.0.remove(net_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_cover_exactly_the_live_ids() {
+        let ids = [5, 1, 2, 3, 9, 10, 2].map(NetId);
+        let runs = net_id_runs(ids);
+        assert_eq!(runs, vec![[1, 3], [5, 5], [9, 10]]);
+        for id in 0..12 {
+            assert_eq!(runs_contain(&runs, NetId(id)), ids.contains(&NetId(id)));
+        }
+        assert!(!runs_contain(&[], NetId(1)));
+    }
+
+    #[test]
+    fn index_forgets_despawned_entities() {
+        let mut world = World::new();
+        world.init_resource::<NetIdIndex>();
+        world.add_observer(on_net_id_removed);
+        let entity = world.spawn(NetId(4)).id();
+        world
+            .resource_mut::<NetIdIndex>()
+            .0
+            .insert(NetId(4), entity);
+        assert!(world.resource::<NetIdIndex>().is_live(NetId(4)));
+
+        world.despawn(entity);
+        assert!(!world.resource::<NetIdIndex>().is_live(NetId(4)));
+    }
+}
@@ -0,0 +1,342 @@
+//! Client authority over non-critical physics props.
+//!
+//! When [`ClientAuthoritySettings::enabled`] is set, the server hands the
+//! simulation of [`Delegable`] props (balls, crates) to the single client
+//! whose creature stands next to them.  That client simulates the prop
+//! locally and streams its state back on stream 8; the server keeps the prop
+//! kinematic, applies the updates that pass [`validate_prop_update`], and
+//! relays them to everyone else through the regular `StateUpdate` broadcast.
+//! Authority returns to the server as soon as a second player comes close
+//! (contested), the owner walks away, or the owner sends an implausible
+//! update.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use network::{
+    ClientId, ControlledByClient, NETWORK_UPDATE_INTERVAL, NetId, PlayerEvent, StreamReader,
+    StreamSender,
+};
+use physics::{GravityScale, LinearVelocity, RigidBody};
+use serde::{Deserialize, Serialize};
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{LastValidPosition, NetIdIndex, SpatialIndex, ThingsStreamMessage};
+
+/// Stream tag for the client→server prop-state stream (stream 8).
+pub const AUTHORITY_STREAM_TAG: u8 = 8;
+
+/// Fastest a client-simulated prop may move, in units per second.
+pub const MAX_PROP_SPEED: f32 = 20.0;
+
+/// Server-side switch and tuning for client authority.
+///
+/// Inserted by `src/main.rs` from `AppConfig`; disabled by default so props
+/// stay fully server-simulated unless a server opts in.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ClientAuthoritySettings {
+    pub enabled: bool,
+    /// Players within this distance of a prop compete for its authority.
+    pub radius: f32,
+}
+
+impl Default for ClientAuthoritySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            radius: 3.0,
+        }
+    }
+}
+
+/// Marker for dynamic props whose simulation may be delegated to a client.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct Delegable;
+
+/// Server-side: the client currently simulating this prop.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthorityHolder(pub ClientId);
+
+/// Client-side: this machine simulates the prop and reports its state.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct LocalAuthority;
+
+/// Stream 8 wire format: client→server state of props the client simulates.
+#[derive(Debug, Clone, Serialize, Deserialize, SchemaRead, SchemaWrite)]
+pub enum AuthorityStreamMessage {
+    PropState {
+        net_id: NetId,
+        position: [f32; 3],
+        velocity: [f32; 3],
+    },
+}
+
+/// Timer throttling authority re-evaluation on the server and prop-state
+/// sends on clients.
+#[derive(Resource)]
+pub(crate) struct AuthorityTimer(pub Timer);
+
+impl Default for AuthorityTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(
+            NETWORK_UPDATE_INTERVAL,
+            TimerMode::Repeating,
+        ))
+    }
+}
+
+/// Reasons a prop update from its authority holder is rejected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PropUpdateError {
+    /// Position or velocity contains NaN or infinity.
+    NonFinite,
+    /// The reported velocity exceeds [`MAX_PROP_SPEED`].
+    TooFast { speed: f32 },
+    /// The prop moved further than its speed limit allows since the last update.
+    Teleported { distance: f32 },
+}
+
+/// Checks a reported prop state against the last accepted position.
+///
+/// `elapsed` is the time in seconds since that position was accepted; a
+/// quarter of a second of slack covers network jitter.
+pub fn validate_prop_update(
+    last: Vec3,
+    position: Vec3,
+    velocity: Vec3,
+    elapsed: f32,
+) -> Result<(), PropUpdateError> {
+    if !position.is_finite() || !velocity.is_finite() {
+        return Err(PropUpdateError::NonFinite);
+    }
+    let speed = velocity.length();
+    if speed > MAX_PROP_SPEED {
+        return Err(PropUpdateError::TooFast { speed });
+    }
+    let distance = last.distance(position);
+    if distance > MAX_PROP_SPEED * (elapsed + 0.25) {
+        return Err(PropUpdateError::Teleported { distance });
+    }
+    Ok(())
+}
+
+/// Grants authority over `entity` to `client`: the server stops simulating
+/// it and tells the client to take over.
+fn grant(
+    commands: &mut Commands,
+    sender: &StreamSender<ThingsStreamMessage>,
+    entity: Entity,
+    net_id: NetId,
+    client: ClientId,
+) {
+    commands
+        .entity(entity)
+        .insert((AuthorityHolder(client), RigidBody::Kinematic));
+    if let Err(e) = sender.send_to(client, &ThingsStreamMessage::AuthorityGranted { net_id }) {
+        error!("Failed to grant authority over NetId({}): {e}", net_id.0);
+    }
+}
+
+/// Takes authority over `entity` back from `holder` and resumes server-side
+/// simulation.
+fn reclaim(
+    commands: &mut Commands,
+    sender: &StreamSender<ThingsStreamMessage>,
+    entity: Entity,
+    net_id: NetId,
+    holder: ClientId,
+) {
+    commands
+        .entity(entity)
+        .remove::<AuthorityHolder>()
+        .insert(RigidBody::Dynamic);
+    if let Err(e) = sender.send_to(holder, &ThingsStreamMessage::AuthorityRevoked { net_id }) {
+        debug!("Failed to revoke authority over NetId({}): {e}", net_id.0);
+    }
+}
+
+/// Server system: grants each free [`Delegable`] prop to the only player
+/// within [`ClientAuthoritySettings::radius`] and reclaims it when nobody
+/// or more than one player is close.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub(crate) fn assign_authority(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut timer: ResMut<AuthorityTimer>,
+    settings: Res<ClientAuthoritySettings>,
+    props: Query<
+        (Entity, &NetId, &Transform, Option<&AuthorityHolder>),
+        (With<Delegable>, Without<ChildOf>),
+    >,
+    spatial: Res<SpatialIndex>,
+    players: Query<&ControlledByClient>,
+    sender: Res<StreamSender<ThingsStreamMessage>>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    for (entity, &net_id, transform, holder) in props.iter() {
+        let mut nearby = spatial
+            .query_within(transform.translation, settings.radius)
+            .filter_map(|(player, _)| players.get(player).ok());
+        let candidate = match (nearby.next(), nearby.next()) {
+            (Some(owner), None) if settings.enabled => Some(owner.0),
+            _ => None,
+        };
+
+        match (holder.map(|h| h.0), candidate) {
+            (Some(current), Some(next)) if current == next => {}
+            (Some(current), _) => reclaim(&mut commands, &sender, entity, net_id, current),
+            (None, Some(next)) => grant(&mut commands, &sender, entity, net_id, next),
+            (None, None) => {}
+        }
+    }
+}
+
+/// Server system: applies prop states reported by their authority holders.
+///
+/// Updates for props the sender does not hold are ignored; implausible ones
+/// (see [`validate_prop_update`]) are discarded and authority is reclaimed.
+pub(crate) fn receive_prop_states(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut reader: ResMut<StreamReader<AuthorityStreamMessage>>,
+    net_id_index: Res<NetIdIndex>,
+    mut props: Query<(
+        &mut Transform,
+        &mut LinearVelocity,
+        &AuthorityHolder,
+        Option<&mut LastValidPosition>,
+    )>,
+    mut last_accepted: Local<HashMap<NetId, f32>>,
+    sender: Res<StreamSender<ThingsStreamMessage>>,
+) {
+    let now = time.elapsed_secs();
+    for (from, msg) in reader.drain_from_client() {
+        let AuthorityStreamMessage::PropState {
+            net_id,
+            position,
+            velocity,
+        } = msg;
+        let Some(&entity) = net_id_index.0.get(&net_id) else {
+            continue;
+        };
+        let Ok((mut transform, mut lin_vel, holder, last_valid)) = props.get_mut(entity) else {
+            continue;
+        };
+        if holder.0 != from {
+            debug!(
+                "PropState for NetId({}) from {:?}, held by {:?}; ignoring",
+                net_id.0, from, holder.0
+            );
+            continue;
+        }
+
+        let position = Vec3::from_array(position);
+        let velocity = Vec3::from_array(velocity);
+        let elapsed = last_accepted
+            .get(&net_id)
+            .map_or(NETWORK_UPDATE_INTERVAL, |t| now - t);
+        if let Err(e) = validate_prop_update(transform.translation, position, velocity, elapsed) {
+            warn!(
+                "Rejected PropState for NetId({}) from {:?}: {:?}; reclaiming authority",
+                net_id.0, from, e
+            );
+            last_accepted.remove(&net_id);
+            reclaim(&mut commands, &sender, entity, net_id, from);
+            continue;
+        }
+        transform.translation = position;
+        lin_vel.0 = velocity;
+        if let Some(mut last_valid) = last_valid {
+            last_valid.0 = position;
+        }
+        last_accepted.insert(net_id, now);
+    }
+}
+
+/// Server system: reclaims every prop held by a client that disconnected.
+pub(crate) fn reclaim_authority_on_leave(
+    mut commands: Commands,
+    mut player_events: MessageReader<PlayerEvent>,
+    props: Query<(Entity, &AuthorityHolder)>,
+) {
+    for event in player_events.read() {
+        let PlayerEvent::Left { id } = event else {
+            continue;
+        };
+        for (entity, holder) in props.iter() {
+            if holder.0 == *id {
+                commands
+                    .entity(entity)
+                    .remove::<AuthorityHolder>()
+                    .insert(RigidBody::Dynamic);
+            }
+        }
+    }
+}
+
+/// Client-side: switches a prop between local simulation and replicated
+/// kinematic playback when authority is granted or revoked.
+pub(crate) fn apply_authority_change(commands: &mut Commands, entity: Entity, granted: bool) {
+    if granted {
+        commands
+            .entity(entity)
+            .insert((LocalAuthority, RigidBody::Dynamic, GravityScale(1.0)));
+    } else {
+        commands
+            .entity(entity)
+            .remove::<LocalAuthority>()
+            .insert((RigidBody::Kinematic, GravityScale(0.0)));
+    }
+}
+
+/// Client system: reports the state of every locally simulated prop.
+pub(crate) fn send_prop_states(
+    time: Res<Time>,
+    mut timer: ResMut<AuthorityTimer>,
+    props: Query<(&NetId, &Transform, &LinearVelocity), With<LocalAuthority>>,
+    sender: Res<StreamSender<AuthorityStreamMessage>>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    for (&net_id, transform, velocity) in props.iter() {
+        let msg = AuthorityStreamMessage::PropState {
+            net_id,
+            position: transform.translation.into(),
+            velocity: velocity.0.into(),
+        };
+        if let Err(e) = sender.send(&msg) {
+            debug!("Failed to send PropState for NetId({}): {e}", net_id.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_prop_update_accepts_plausible_motion() {
+        let result = validate_prop_update(Vec3::ZERO, Vec3::new(0.5, 0.0, 0.0), Vec3::X * 5.0, 0.1);
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn validate_prop_update_rejects_teleports_and_speeding() {
+        assert!(matches!(
+            validate_prop_update(Vec3::ZERO, Vec3::new(50.0, 0.0, 0.0), Vec3::ZERO, 0.1),
+            Err(PropUpdateError::Teleported { .. })
+        ));
+        assert!(matches!(
+            validate_prop_update(Vec3::ZERO, Vec3::ZERO, Vec3::X * 100.0, 0.1),
+            Err(PropUpdateError::TooFast { .. })
+        ));
+        assert_eq!(
+            validate_prop_update(Vec3::ZERO, Vec3::splat(f32::NAN), Vec3::ZERO, 0.1),
+            Err(PropUpdateError::NonFinite)
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Damage events and the health they wear down.
+//!
+//! Anything that hurts a thing (fire, projectiles, melee) writes a
+//! [`Damage`] message instead of touching [`Health`] itself.  The server
+//! applies them once per frame in [`apply_damage`]; things without
+//! [`Health`] are unaffected.  Health changes are replicated to clients on
+//! stream 3 so they can show health bars.
+
+use bevy::prelude::*;
+use network::{NetId, StreamSender};
+
+use crate::ThingsStreamMessage;
+use crate::visibility::ReplicationScope;
+
+/// What kind of harm a [`Damage`] does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum DamageKind {
+    /// Blunt or piercing force.
+    Brute,
+    /// Fire and heat.
+    Burn,
+}
+
+/// Request to take `amount` health from `target`.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct Damage {
+    pub target: Entity,
+    pub amount: f32,
+    pub kind: DamageKind,
+}
+
+/// Hit points of a thing that can be hurt.  Never drops below zero.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    /// Full health of `max`.
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    /// Remaining health as a fraction of the maximum, `0.0..=1.0`.
+    pub fn fraction(&self) -> f32 {
+        if self.max <= 0.0 {
+            0.0
+        } else {
+            (self.current / self.max).clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// Server system: subtracts every [`Damage`] written this frame from its
+/// target's [`Health`].
+pub fn apply_damage(mut damage: MessageReader<Damage>, mut health: Query<&mut Health>) {
+    for hit in damage.read() {
+        let Ok(mut health) = health.get_mut(hit.target) else {
+            continue;
+        };
+        health.current = (health.current - hit.amount.max(0.0)).max(0.0);
+    }
+}
+
+/// Server system: sends [`ThingsStreamMessage::HealthChanged`] for every
+/// replicated thing whose [`Health`] changed this frame.
+///
+/// Runs in `NetworkSend` after `broadcast_state`, so a thing spawned this
+/// frame is already known to clients when its health arrives.
+pub(crate) fn broadcast_health(
+    sender: Res<StreamSender<ThingsStreamMessage>>,
+    changed: Query<(Entity, &NetId, &Health), Changed<Health>>,
+    scope: ReplicationScope,
+) {
+    for (entity, &net_id, health) in changed.iter() {
+        let msg = ThingsStreamMessage::HealthChanged {
+            net_id,
+            current: health.current,
+            max: health.max,
+        };
+        if let Err(e) = scope.send(&sender, &[entity], &msg) {
+            error!("Failed to send HealthChanged for NetId({}): {e}", net_id.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn damage_wears_health_down_to_zero() {
+        let mut app = App::new();
+        app.add_message::<Damage>();
+        app.add_systems(Update, apply_damage);
+        let target = app.world_mut().spawn(Health::new(10.0)).id();
+        let bystander = app.world_mut().spawn(Health::new(10.0)).id();
+
+        app.world_mut().write_message(Damage {
+            target,
+            amount: 4.0,
+            kind: DamageKind::Burn,
+        });
+        app.update();
+        assert_eq!(app.world().get::<Health>(target).unwrap().current, 6.0);
+        assert_eq!(app.world().get::<Health>(bystander).unwrap().current, 10.0);
+
+        app.world_mut().write_message(Damage {
+            target,
+            amount: 50.0,
+            kind: DamageKind::Brute,
+        });
+        app.update();
+        assert_eq!(app.world().get::<Health>(target).unwrap().fraction(), 0.0);
+    }
+}
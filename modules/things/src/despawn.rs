@@ -0,0 +1,159 @@
+//! Why replicated things disappear, and client-side reactions to it.
+//!
+//! The server says why it despawned a thing in
+//! [`ThingsStreamMessage::EntityDespawned`](crate::ThingsStreamMessage::EntityDespawned).
+//! On the client, any plugin can call [`DespawnHookExt::add_despawn_hook`]
+//! to react before the replica goes away: play an effect for a destroyed
+//! thing, or return [`DespawnCleanup::Keep`] and despawn the replica itself
+//! later, e.g. after fading it out.  Without hooks every replica is despawned
+//! at once.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use wincode::{SchemaRead, SchemaWrite};
+
+/// Why the server despawned a replicated thing.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Reflect,
+    Serialize,
+    Deserialize,
+    SchemaRead,
+    SchemaWrite,
+)]
+pub enum DespawnReason {
+    /// Broken, burnt up, fallen apart or otherwise gone from the station.
+    Destroyed,
+    /// Taken into a container as part of something else, e.g. merged into
+    /// a stack already inside it.
+    PickedUpIntoContainer,
+    /// Still exists on the server but is no longer replicated to this
+    /// client.
+    OutOfRelevancy,
+    /// Used up: eaten, drunk or spent by a recipe.
+    Consumed,
+}
+
+/// What a [`DespawnHook`] wants done with the replica.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DespawnCleanup {
+    /// Despawn the replica now.
+    #[default]
+    Despawn,
+    /// Leave the replica; the hook despawns it itself.  It is already gone
+    /// from the [`NetIdIndex`](crate::NetIdIndex).
+    Keep,
+}
+
+/// Callback run on the client for a replica about to be despawned.
+///
+/// Gets the whole world, so it can read the replica's components and spawn
+/// effects next to it.
+pub type DespawnHook =
+    Box<dyn Fn(&mut World, Entity, DespawnReason) -> DespawnCleanup + Send + Sync>;
+
+/// Every registered [`DespawnHook`], run in registration order.  The
+/// replica is kept if any of them asks for it.
+#[derive(Resource, Default)]
+pub struct DespawnHooks {
+    hooks: Vec<DespawnHook>,
+}
+
+impl DespawnHooks {
+    /// Adds `hook` after the ones already registered.
+    pub fn register(
+        &mut self,
+        hook: impl Fn(&mut World, Entity, DespawnReason) -> DespawnCleanup + Send + Sync + 'static,
+    ) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Runs every hook for `entity`, then despawns it unless one of them
+    /// keeps it.
+    pub fn run(&self, world: &mut World, entity: Entity, reason: DespawnReason) {
+        let mut cleanup = DespawnCleanup::Despawn;
+        for hook in &self.hooks {
+            if world.get_entity(entity).is_err() {
+                return;
+            }
+            if hook(world, entity, reason) == DespawnCleanup::Keep {
+                cleanup = DespawnCleanup::Keep;
+            }
+        }
+        if cleanup == DespawnCleanup::Despawn
+            && let Ok(entity) = world.get_entity_mut(entity)
+        {
+            entity.despawn();
+        }
+    }
+}
+
+/// Extension trait for [`App`] that provides `add_despawn_hook`.
+pub trait DespawnHookExt {
+    fn add_despawn_hook(
+        &mut self,
+        hook: impl Fn(&mut World, Entity, DespawnReason) -> DespawnCleanup + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl DespawnHookExt for App {
+    fn add_despawn_hook(
+        &mut self,
+        hook: impl Fn(&mut World, Entity, DespawnReason) -> DespawnCleanup + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_insert_with::<DespawnHooks>(DespawnHooks::default)
+            .register(hook);
+        self
+    }
+}
+
+/// Despawns the replica `entity` for `reason` once commands are applied,
+/// running the [`DespawnHooks`] first.
+pub(crate) fn despawn_replica(commands: &mut Commands, entity: Entity, reason: DespawnReason) {
+    commands.queue(move |world: &mut World| {
+        if world.contains_resource::<DespawnHooks>() {
+            world.resource_scope(|world, hooks: Mut<DespawnHooks>| {
+                hooks.run(world, entity, reason);
+            });
+        } else if let Ok(entity) = world.get_entity_mut(entity) {
+            entity.despawn();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component)]
+    struct Fading;
+
+    #[test]
+    fn hooks_see_the_reason_and_may_keep_the_replica() {
+        let mut app = App::new();
+        app.add_despawn_hook(|world, entity, reason| {
+            if reason == DespawnReason::Destroyed {
+                world.entity_mut(entity).insert(Fading);
+                DespawnCleanup::Keep
+            } else {
+                DespawnCleanup::Despawn
+            }
+        });
+
+        let destroyed = app.world_mut().spawn_empty().id();
+        let eaten = app.world_mut().spawn_empty().id();
+        let mut commands = app.world_mut().commands();
+        despawn_replica(&mut commands, destroyed, DespawnReason::Destroyed);
+        despawn_replica(&mut commands, eaten, DespawnReason::Consumed);
+        app.world_mut().flush();
+
+        assert!(app.world().get::<Fading>(destroyed).is_some());
+        assert!(app.world().get_entity(eaten).is_err());
+    }
+}
@@ -3,18 +3,57 @@ use std::sync::Arc;
 
 use bevy::prelude::*;
 use bevy::state::state_scoped::DespawnOnExit;
-use input::{PointerAction, WorldHit};
+use input::{PointerAction, ViewCamera, WorldHit};
 use network::{
     Client, ClientId, ControlledByClient, EntityState, Headless, ModuleReadySent,
-    NETWORK_UPDATE_INTERVAL, NetId, NetworkReceive, NetworkSend, PlayerEvent, Server, StreamDef,
-    StreamDirection, StreamReader, StreamRegistry, StreamSender,
+    NETWORK_UPDATE_INTERVAL, NetId, NetworkReceive, NetworkSend, PlayerEvent, Server,
+    SimulationTick, StreamBudget, StreamDef, StreamDirection, StreamPriority, StreamReader,
+    StreamRegistry, StreamSender, TunablesAppExt,
+};
+use physics::{
+    DistanceJoint, FixedJoint, GameLayer, GravityScale, LinearVelocity, RevoluteJoint, RigidBody,
+    SpatialQuery,
 };
-use physics::{GravityScale, LinearVelocity, RigidBody, SpatialQuery, SpatialQueryFilter};
 use ron::value::RawValue;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use wincode::{SchemaRead, SchemaWrite};
 use world::{MapLayer, MapLayerRegistryExt, from_layer_value, to_layer_value};
 
+mod admin;
+pub use admin::{ThingFilter, ThingQuery, ThingSummary, despawn_thing, list_things};
+mod audit;
+pub use audit::{NET_ID_AUDIT_INTERVAL, net_id_runs, runs_contain};
+mod authority;
+pub use authority::{
+    AUTHORITY_STREAM_TAG, AuthorityHolder, AuthorityStreamMessage, ClientAuthoritySettings,
+    Delegable, LocalAuthority, MAX_PROP_SPEED, PropUpdateError, validate_prop_update,
+};
+mod damage;
+mod despawn;
+pub use damage::{Damage, DamageKind, Health, apply_damage};
+pub use despawn::{DespawnCleanup, DespawnHook, DespawnHookExt, DespawnHooks, DespawnReason};
+mod movement;
+pub use movement::{MovementModifiers, MovementState};
+mod spatial;
+pub use spatial::{SpatialIndex, cell_of, update_spatial_index};
+mod needs;
+pub use needs::{HUNGER_PER_SECOND, MAX_NEED, Needs, THIRST_PER_SECOND, tick_needs};
+
+mod status;
+pub use status::{
+    ApplyStatus, BURNING_DAMAGE_PER_SECOND, MAX_STATUS_STACKS, SLOW_FACTOR, StatusEffect,
+    StatusEffects, StatusKind, apply_status, tick_status_effects,
+};
+mod sync;
+pub use sync::{
+    ComponentSync, ComponentSyncRegistry, ReplicateComponentExt, SyncAudience, SyncComponent,
+    component_sync_id,
+};
+mod trajectory;
+pub use trajectory::Trajectory;
+mod visibility;
+pub use visibility::{ReplicatedClients, ReplicationScope, ReplicationVisibility};
+
 /// System set for the things module's server-side lifecycle systems.
 /// Other modules can use this for explicit ordering relative to things systems.
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
@@ -38,7 +77,7 @@ pub struct Thing {
     pub kind: u16,
 }
 
-/// Tracks the last position and velocity that was broadcast for this entity.
+/// Tracks the last position, velocity and yaw that was broadcast for this entity.
 /// `broadcast_state` compares current values against these to skip unchanged
 /// entities — Bevy's `Changed<Transform>` cannot be used because the physics
 /// engine writes to `Transform` every frame even for resting bodies.
@@ -46,6 +85,25 @@ pub struct Thing {
 struct LastBroadcast {
     position: Vec3,
     velocity: Vec3,
+    yaw: u16,
+}
+
+/// Client-side heading (in radians) last received for a replicated entity.
+///
+/// `handle_entity_lifecycle` writes it from each `StateUpdate`, and
+/// `interpolate_replicated_yaw` turns the entity's `Transform` towards it so
+/// remote creatures face their movement direction without snapping.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct ReplicatedYaw(pub f32);
+
+/// How quickly replicated entities turn towards their [`ReplicatedYaw`]; the
+/// remaining angle shrinks by a factor of `e` every `1 / YAW_SMOOTHING` seconds.
+const YAW_SMOOTHING: f32 = 15.0;
+
+/// Yaw of `rotation` around the vertical axis, in radians.
+fn yaw_of(rotation: Quat) -> f32 {
+    rotation.to_euler(EulerRot::YXZ).0
 }
 
 /// Which hand a [`HandSlot`] anchor belongs to.
@@ -86,6 +144,14 @@ pub struct DisplayName(pub String);
 #[reflect(Component)]
 pub struct InputDirection(pub Vec3);
 
+/// Last server-accepted position of a client-controlled creature, from which
+/// the souls module measures per-frame displacement.
+///
+/// Server code that moves a creature on purpose (e.g. respawning it) should update
+/// this alongside the `Transform` so the move is not flagged as a teleport.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct LastValidPosition(pub Vec3);
+
 /// Marker component placed on every entity that was spawned by the `"spawns"`
 /// map layer. Used by [`SpawnsLayer::save`] to identify which entities should
 /// be written back out as spawn points.
@@ -254,6 +320,17 @@ impl ThingRegistry {
         self.register(kind, functional);
     }
 
+    /// Remove the template registered for `kind`, along with its name, so the
+    /// kind can be registered again (e.g. when reloading templates).
+    /// Things already spawned from it are left as they are.
+    pub fn unregister(&mut self, kind: u16) {
+        self.templates.remove(&kind);
+        self.visual_builders.remove(&kind);
+        if let Some(name) = self.kind_to_name.remove(&kind) {
+            self.name_to_kind.remove(&name);
+        }
+    }
+
     /// Look up the kind number for a template name, or `None` if unregistered.
     pub fn kind_by_name(&self, name: &str) -> Option<u16> {
         self.name_to_kind.get(name).copied()
@@ -527,9 +604,77 @@ impl MapLayer for SpawnsLayer {
 }
 
 /// Maps NetId to Entity for O(1) lookup during state-update processing.
+///
+/// Entries go away with their entity; see the `audit` module for how clients
+/// catch up with entities the server removed silently.
 #[derive(Resource, Default)]
 pub struct NetIdIndex(pub HashMap<NetId, Entity>);
 
+/// How a [`ReplicatedJoint`] constrains its two bodies.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Serialize, Deserialize, SchemaRead, SchemaWrite, Reflect,
+)]
+pub enum JointKind {
+    /// Locks the bodies together.
+    Fixed,
+    /// Lets the bodies rotate relative to each other around `axis` only.
+    Revolute { axis: [f32; 3] },
+    /// Keeps the bodies between `min` and `max` world units apart.
+    Distance { min: f32, max: f32 },
+}
+
+/// Physics joint between two replicated things, identified by [`NetId`].
+///
+/// Lives on its own entity together with the joint's own [`NetId`].  When
+/// added, [`on_replicated_joint_added`] resolves both ends through
+/// [`NetIdIndex`] and inserts the matching avian joint, so the same
+/// constraint exists on the server and on every client.  Spawn it on the
+/// server with [`spawn_joint`]; despawning the entity removes it everywhere.
+#[derive(
+    Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize, SchemaRead, SchemaWrite,
+)]
+pub struct ReplicatedJoint {
+    pub a: NetId,
+    pub b: NetId,
+    pub kind: JointKind,
+}
+
+/// Per-client allowance on stream 3 for deferrable `StateUpdate` frames, in
+/// bytes per network tick.  Spawns and despawns are never held back.
+const THINGS_BYTES_PER_TICK: usize = 16 * 1024;
+
+/// Most entities sent in one [`ThingsStreamMessage::EntityBatch`], so a
+/// large catch-up burst is still split into frames of bounded size.
+pub const SPAWN_BATCH_SIZE: usize = 64;
+
+/// A replicated entity as introduced to a client, alone in
+/// [`ThingsStreamMessage::EntitySpawned`] or in bulk in
+/// [`ThingsStreamMessage::EntityBatch`].
+#[derive(Debug, Clone, Serialize, Deserialize, SchemaRead, SchemaWrite)]
+pub struct EntitySpawnedData {
+    pub net_id: NetId,
+    pub kind: u16,
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    /// If set, the receiving client with this ID should take control of this entity.
+    pub owner: Option<ClientId>,
+    /// Optional display name for the entity (e.g. player name).
+    pub name: Option<String>,
+}
+
+impl From<EntitySpawnedData> for ThingsStreamMessage {
+    fn from(data: EntitySpawnedData) -> Self {
+        ThingsStreamMessage::EntitySpawned {
+            net_id: data.net_id,
+            kind: data.kind,
+            position: data.position,
+            velocity: data.velocity,
+            owner: data.owner,
+            name: data.name,
+        }
+    }
+}
+
 /// Stream 3 wire format: server→client messages for the things module.
 #[derive(Debug, Clone, Serialize, Deserialize, SchemaRead, SchemaWrite)]
 pub enum ThingsStreamMessage {
@@ -544,10 +689,77 @@ pub enum ThingsStreamMessage {
         /// Optional display name for the entity (e.g. player name).
         name: Option<String>,
     },
-    /// A replicated entity was despawned.
-    EntityDespawned { net_id: NetId },
+    /// Several replicated entities were spawned at once, e.g. the catch-up
+    /// burst of a joining client.  Handled like one `EntitySpawned` each, in
+    /// order.
+    EntityBatch(Vec<EntitySpawnedData>),
+    /// A replicated entity was despawned, or is no longer replicated to the
+    /// receiving client.  Also used for joints.
+    EntityDespawned {
+        net_id: NetId,
+        reason: DespawnReason,
+    },
+    /// A [`ReplicatedJoint`] was created between two replicated entities.
+    JointSpawned {
+        net_id: NetId,
+        joint: ReplicatedJoint,
+    },
     /// Authoritative spatial state update for all replicated things entities.
     StateUpdate { entities: Vec<EntityState> },
+    /// The receiving client now simulates this prop and reports its state on
+    /// stream 8.
+    AuthorityGranted { net_id: NetId },
+    /// The server took simulation of this prop back.
+    AuthorityRevoked { net_id: NetId },
+    /// A thing's [`Health`] changed, or a joining client is catching up.
+    HealthChanged {
+        net_id: NetId,
+        current: f32,
+        max: f32,
+    },
+    /// The [`StatusEffects`] of the receiving client's creature changed.
+    StatusEffectsChanged {
+        net_id: NetId,
+        effects: Vec<StatusEffect>,
+    },
+    /// The [`Needs`] of the receiving client's creature changed, or it was
+    /// just bound to the client.
+    NeedsChanged {
+        net_id: NetId,
+        nutrition: f32,
+        hydration: f32,
+    },
+    /// A thing started flying along a [`Trajectory`] from `position`; it is
+    /// left out of `StateUpdate` frames until the trajectory ends.
+    TrajectoryStarted {
+        net_id: NetId,
+        position: [f32; 3],
+        velocity: [f32; 3],
+        gravity: f32,
+    },
+    /// A thing stopped flying and is replicated through `StateUpdate` again.
+    TrajectoryEnded { net_id: NetId },
+    /// No client controls this thing any more, e.g. the body of a player
+    /// who died and now haunts the station as a ghost.
+    ControlReleased { net_id: NetId },
+    /// A component registered with
+    /// [`ReplicateComponentExt::replicate_component`] was added or changed,
+    /// or a joining client is catching up.  `component` is its
+    /// [`component_sync_id`] and `value` its wincode encoding.
+    ComponentChanged {
+        net_id: NetId,
+        component: u32,
+        value: Vec<u8>,
+    },
+    /// A synced component was removed from a thing that still exists.
+    ComponentRemoved { net_id: NetId, component: u32 },
+    /// Periodic liveness audit: the NetIds live for the receiving client, as
+    /// sorted inclusive `[first, last]` runs (see [`net_id_runs`]).  Replicas
+    /// below `issued_below` and outside `live` are stale.
+    NetIdAudit {
+        live: Vec<[u64; 2]>,
+        issued_below: NetId,
+    },
 }
 
 /// Timer for throttling state broadcasts from the server.
@@ -604,9 +816,25 @@ pub fn spawn_thing(
         kind,
         position,
     });
-    // Register in NetIdIndex so that a listen-server's client-side
-    // handle_entity_lifecycle sees this entity as already spawned and
-    // skips the duplicate EntitySpawned from the catch-up burst.
+    // Register in NetIdIndex so server systems can resolve the NetId
+    // before the spawn commands are applied.
+    commands.queue(move |world: &mut World| {
+        world.resource_mut::<NetIdIndex>().0.insert(net_id, entity);
+    });
+    (entity, net_id)
+}
+
+/// Spawns a [`ReplicatedJoint`] entity with a server-assigned [`NetId`].
+///
+/// The joint is broadcast to clients by `broadcast_joint_spawns` and removed
+/// from every machine when the returned entity is despawned.
+pub fn spawn_joint(
+    commands: &mut Commands,
+    server: &mut Server,
+    joint: ReplicatedJoint,
+) -> (Entity, NetId) {
+    let net_id = server.next_net_id();
+    let entity = commands.spawn((net_id, joint)).id();
     commands.queue(move |world: &mut World| {
         world.resource_mut::<NetIdIndex>().0.insert(net_id, entity);
     });
@@ -614,7 +842,8 @@ pub fn spawn_thing(
 }
 
 /// Spawns a player-controlled thing entity with a server-assigned [`NetId`],
-/// [`ControlledByClient`], [`InputDirection`], and [`DisplayName`], then triggers
+/// [`ControlledByClient`], [`InputDirection`], [`MovementModifiers`], and
+/// [`DisplayName`], then triggers
 /// [`SpawnThing`] so that the registered template (kind 0 = creature) adds physics
 /// and type-specific components.
 ///
@@ -632,11 +861,63 @@ pub fn spawn_player_creature(
     commands.entity(creature).insert((
         ControlledByClient(owner),
         InputDirection::default(),
+        MovementModifiers::default(),
         DisplayName(display_name.to_string()),
     ));
     (creature, net_id)
 }
 
+/// Thing kind of the free-flying observer a spectating client controls.
+pub const OBSERVER_KIND: u16 = 7;
+
+/// Spawns the observer entity for a spectating client: like
+/// [`spawn_player_creature`] but of kind [`OBSERVER_KIND`], without a
+/// [`DisplayName`], and replicated to `owner` only.
+///
+/// Returns the spawned [`Entity`] and its assigned [`NetId`].
+pub fn spawn_observer(
+    commands: &mut Commands,
+    server: &mut Server,
+    owner: ClientId,
+    position: Vec3,
+) -> (Entity, NetId) {
+    let (observer, net_id) = spawn_thing(commands, server, OBSERVER_KIND, position);
+    commands.entity(observer).insert((
+        ControlledByClient(owner),
+        InputDirection::default(),
+        MovementModifiers::default(),
+        ReplicationVisibility::visible_to([owner]),
+    ));
+    (observer, net_id)
+}
+
+/// Thing kind of the ghost a dead player's soul is bound to until it
+/// rejoins as a new character.
+pub const GHOST_KIND: u16 = 16;
+
+/// Spawns the ghost of a dead player: like [`spawn_observer`] but of kind
+/// [`GHOST_KIND`] and named after the player.  Replicated to `owner` only;
+/// the `souls` module widens that to every other ghost.
+///
+/// Returns the spawned [`Entity`] and its assigned [`NetId`].
+pub fn spawn_ghost(
+    commands: &mut Commands,
+    server: &mut Server,
+    owner: ClientId,
+    position: Vec3,
+    display_name: &str,
+) -> (Entity, NetId) {
+    let (ghost, net_id) = spawn_thing(commands, server, GHOST_KIND, position);
+    commands.entity(ghost).insert((
+        ControlledByClient(owner),
+        InputDirection::default(),
+        MovementModifiers::default(),
+        DisplayName(display_name.to_string()),
+        ReplicationVisibility::visible_to([owner]),
+    ));
+    (ghost, net_id)
+}
+
 /// Plugin that registers the thing spawning system and shared entity primitives.
 ///
 /// Must be added before any plugin that calls [`ThingRegistry::register`]
@@ -664,28 +945,99 @@ impl<S: States + Copy> Plugin for ThingsPlugin<S> {
         app.register_type::<PlayerControlled>();
         app.register_type::<InputDirection>();
         app.register_type::<DisplayName>();
+        app.register_type::<ReplicatedYaw>();
         app.register_type::<SpawnMarker>();
+        app.register_type::<Delegable>();
+        app.register_type::<JointKind>();
+        app.register_type::<Health>();
+        app.register_type::<MovementModifiers>();
+        app.register_type::<MovementState>();
+        app.register_type::<StatusEffects>();
+        app.register_type::<Needs>();
+        app.register_type::<Trajectory>();
+        app.add_message::<Damage>();
+        app.add_message::<ApplyStatus>();
+        app.add_message::<ThingQuery>();
+        app.add_message::<visibility::SightGained>();
         app.init_resource::<ThingRegistry>();
         app.init_resource::<ThingPropertyRegistry>();
         app.init_resource::<NetIdIndex>();
+        app.init_resource::<SpatialIndex>();
         app.init_resource::<StateBroadcastTimer>();
+        app.register_tunable(
+            "things.state_broadcast_interval",
+            false,
+            |world| {
+                world
+                    .get_resource::<StateBroadcastTimer>()
+                    .map(|timer| timer.0.duration().as_secs_f32())
+            },
+            |world, value| {
+                if let Some(mut timer) = world.get_resource_mut::<StateBroadcastTimer>() {
+                    let interval = std::time::Duration::from_secs_f32(value.max(0.01));
+                    timer.0.set_duration(interval);
+                }
+            },
+        );
+        app.init_resource::<ClientAuthoritySettings>();
+        app.init_resource::<authority::AuthorityTimer>();
+        app.init_resource::<ReplicatedClients>();
+        app.init_resource::<ComponentSyncRegistry>();
+        app.init_resource::<audit::NetIdAuditTimer>();
         app.insert_resource(ThingsActiveState(state));
         app.add_observer(on_spawn_thing);
         app.add_observer(on_spawn_thing_visual);
         app.register_map_layer(SpawnsLayer);
         app.add_observer(on_net_id_added::<S>);
-        app.add_systems(OnExit(state), clear_net_id_index);
+        app.add_observer(audit::on_net_id_removed);
+        app.add_observer(on_replicated_joint_added);
+        app.add_observer(on_replicated_joint_removed);
+        app.add_systems(
+            OnExit(state),
+            (
+                clear_net_id_index,
+                spatial::clear_spatial_index,
+                visibility::clear_replicated_clients,
+            ),
+        );
+        app.add_systems(PreUpdate, update_spatial_index);
+        app.add_systems(PostUpdate, apply_damage.run_if(resource_exists::<Server>));
+        app.add_systems(
+            Update,
+            admin::handle_thing_queries.run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            SimulationTick,
+            ((apply_status, tick_status_effects).chain(), tick_needs)
+                .run_if(resource_exists::<Server>),
+        );
 
         // Register stream 3 (server→client) with StreamRegistry.
         let (sender, reader) = app
             .world_mut()
             .get_resource_mut::<StreamRegistry>()
             .expect("ThingsPlugin requires NetworkPlugin to be added before it (StreamRegistry not found)")
-            .register::<ThingsStreamMessage>(StreamDef {
-                tag: 3,
-                name: "things",
-                direction: StreamDirection::ServerToClient,
-            });
+            .register_with_budget::<ThingsStreamMessage>(
+                StreamDef {
+                    tag: 3,
+                    name: "things",
+                    direction: StreamDirection::ServerToClient,
+                },
+                StreamBudget::new(StreamPriority::High, THINGS_BYTES_PER_TICK),
+            );
+        app.insert_resource(sender);
+        app.insert_resource(reader);
+        app.replicate_component::<MovementState, ()>(ComponentSync::named("movement_state"));
+
+        // Register stream 8 (client→server prop state under client authority).
+        let (sender, reader) = app
+            .world_mut()
+            .resource_mut::<StreamRegistry>()
+            .register::<AuthorityStreamMessage>(StreamDef {
+            tag: AUTHORITY_STREAM_TAG,
+            name: "authority",
+            direction: StreamDirection::ClientToServer,
+        });
         app.insert_resource(sender);
         app.insert_resource(reader);
 
@@ -698,17 +1050,43 @@ impl<S: States + Copy> Plugin for ThingsPlugin<S> {
             NetworkReceive,
             (
                 handle_entity_lifecycle.run_if(resource_exists::<Client>),
+                visibility::track_replicated_clients
+                    .run_if(resource_exists::<Server>)
+                    .before(ThingsSet::HandleClientJoined),
                 handle_client_joined
                     .run_if(resource_exists::<Server>)
                     .in_set(ThingsSet::HandleClientJoined),
                 send_stream_ready_on_join
                     .run_if(resource_exists::<Server>)
                     .in_set(ThingsSet::SendStreamReady),
+                (
+                    authority::receive_prop_states,
+                    authority::reclaim_authority_on_leave,
+                )
+                    .run_if(resource_exists::<Server>),
             ),
         );
         app.add_systems(
             NetworkSend,
-            broadcast_state.run_if(resource_exists::<Server>),
+            (
+                broadcast_joint_spawns,
+                visibility::apply_visibility_changes.before(broadcast_state),
+                broadcast_state,
+                damage::broadcast_health.after(broadcast_state),
+                status::send_status_effects.after(broadcast_state),
+                needs::send_needs.after(broadcast_state),
+                audit::send_net_id_audits.after(broadcast_state),
+            )
+                .run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            Update,
+            (
+                interpolate_replicated_yaw,
+                trajectory::follow_trajectories,
+                authority::send_prop_states,
+            )
+                .run_if(resource_exists::<Client>),
         );
 
         // Register the messages raycast_things reads/writes so the resources
@@ -727,6 +1105,8 @@ impl<S: States + Copy> Plugin for ThingsPlugin<S> {
             (
                 assign_missing_net_ids,
                 despawn_fallen_things,
+                despawn_orphaned_joints,
+                authority::assign_authority,
             )
                 .run_if(resource_exists::<Server>),
         );
@@ -739,6 +1119,7 @@ impl<S: States + Copy> Plugin for ThingsPlugin<S> {
 /// debug aid — the info log makes it easy to spot physics-setup ordering issues.
 const FALLEN_THRESHOLD_Y: f32 = -50.0;
 
+#[allow(clippy::type_complexity)]
 fn despawn_fallen_things(
     mut commands: Commands,
     things: Query<(Entity, &Thing, &Transform, Option<&NetId>, Option<&Name>)>,
@@ -763,6 +1144,7 @@ fn despawn_fallen_things(
 /// [`Server`] resource existed (e.g. items from map load on headless startup).
 ///
 /// Runs every frame but is a no-op once all things have ids.
+#[allow(clippy::type_complexity)]
 fn assign_missing_net_ids(
     mut server: ResMut<Server>,
     mut net_index: ResMut<NetIdIndex>,
@@ -792,6 +1174,93 @@ fn on_net_id_added<S: States + Copy>(
         .insert(DespawnOnExit(active_state.0));
 }
 
+/// Inserts the avian joint described by a newly added [`ReplicatedJoint`],
+/// resolving both ends through [`NetIdIndex`].
+fn on_replicated_joint_added(
+    trigger: On<Add, ReplicatedJoint>,
+    mut commands: Commands,
+    joints: Query<&ReplicatedJoint>,
+    net_id_index: Res<NetIdIndex>,
+) {
+    let entity = trigger.event_target();
+    let Ok(joint) = joints.get(entity) else {
+        return;
+    };
+    let (Some(&a), Some(&b)) = (net_id_index.0.get(&joint.a), net_id_index.0.get(&joint.b)) else {
+        warn!(
+            "ReplicatedJoint on {entity:?} links unknown NetId({}) or NetId({})",
+            joint.a.0, joint.b.0
+        );
+        return;
+    };
+    let mut entity_commands = commands.entity(entity);
+    match joint.kind {
+        JointKind::Fixed => {
+            entity_commands.insert(FixedJoint::new(a, b));
+        }
+        JointKind::Revolute { axis } => {
+            entity_commands.insert(RevoluteJoint::new(a, b).with_hinge_axis(Vec3::from(axis)));
+        }
+        JointKind::Distance { min, max } => {
+            entity_commands.insert(DistanceJoint::new(a, b).with_limits(min, max));
+        }
+    }
+}
+
+/// Tells clients about a server-side [`ReplicatedJoint`] that is going away.
+fn on_replicated_joint_removed(
+    trigger: On<Remove, ReplicatedJoint>,
+    net_ids: Query<&NetId>,
+    server: Option<Res<Server>>,
+    stream_sender: Option<Res<StreamSender<ThingsStreamMessage>>>,
+    mut net_id_index: ResMut<NetIdIndex>,
+) {
+    let Ok(&net_id) = net_ids.get(trigger.event_target()) else {
+        return;
+    };
+    if server.is_none() {
+        return;
+    }
+    net_id_index.0.remove(&net_id);
+    if let Some(sender) = stream_sender
+        && let Err(e) = sender.broadcast(&ThingsStreamMessage::EntityDespawned {
+            net_id,
+            reason: DespawnReason::Destroyed,
+        })
+    {
+        error!("Failed to broadcast joint despawn NetId({}): {e}", net_id.0);
+    }
+}
+
+/// Despawns joints whose bodies no longer exist, e.g. after one of them fell
+/// out of the world.
+fn despawn_orphaned_joints(
+    mut commands: Commands,
+    joints: Query<(Entity, &ReplicatedJoint)>,
+    net_id_index: Res<NetIdIndex>,
+) {
+    for (entity, joint) in joints.iter() {
+        if !net_id_index.0.contains_key(&joint.a) || !net_id_index.0.contains_key(&joint.b) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Broadcasts [`ThingsStreamMessage::JointSpawned`] for every joint created
+/// this frame.
+fn broadcast_joint_spawns(
+    joints: Query<(&NetId, &ReplicatedJoint), Added<ReplicatedJoint>>,
+    stream_sender: Res<StreamSender<ThingsStreamMessage>>,
+) {
+    for (&net_id, &joint) in joints.iter() {
+        if let Err(e) =
+            stream_sender.broadcast(&ThingsStreamMessage::JointSpawned { net_id, joint })
+        {
+            error!("Failed to broadcast JointSpawned NetId({}): {e}", net_id.0);
+        }
+    }
+}
+
 /// Clears the [`NetIdIndex`] when leaving the active game state.
 ///
 /// Entities are already despawned via [`DespawnOnExit`]; this removes the now-stale
@@ -855,116 +1324,279 @@ fn on_spawn_thing_visual(
 /// Handles client-side entity lifecycle and state updates from stream 3.
 ///
 /// Processes all [`ThingsStreamMessage`] frames:
-/// - [`ThingsStreamMessage::EntitySpawned`]: spawns replica entity via [`SpawnThing`],
-///   inserts [`DisplayName`], and tracks it in [`NetIdIndex`].
+/// - [`ThingsStreamMessage::EntitySpawned`] / [`ThingsStreamMessage::EntityBatch`]:
+///   spawns each replica entity via [`SpawnThing`], inserts [`DisplayName`], and
+///   tracks it in [`NetIdIndex`].
 /// - [`ThingsStreamMessage::EntityDespawned`]: despawns the entity and removes it from
 ///   the index. [`DespawnOnExit`] provides additional state-transition cleanup.
-/// - [`ThingsStreamMessage::StateUpdate`]: applies authoritative position updates.
+/// - [`ThingsStreamMessage::StateUpdate`]: applies authoritative position updates and
+///   records the heading as [`ReplicatedYaw`] for `interpolate_replicated_yaw`.
+/// - [`ThingsStreamMessage::JointSpawned`]: spawns the [`ReplicatedJoint`] entity;
+///   [`on_replicated_joint_added`] then inserts the physics joint.
+/// - [`ThingsStreamMessage::AuthorityGranted`] / [`ThingsStreamMessage::AuthorityRevoked`]:
+///   toggles [`LocalAuthority`] and local simulation of the prop.  State updates
+///   for props under local authority are ignored.
+/// - [`ThingsStreamMessage::HealthChanged`]: replaces the replica's [`Health`].
+/// - [`ThingsStreamMessage::StatusEffectsChanged`]: replaces the
+///   [`StatusEffects`] of the client's own creature.
+/// - [`ThingsStreamMessage::NeedsChanged`]: replaces the [`Needs`] of the
+///   client's own creature.
+/// - [`ThingsStreamMessage::TrajectoryStarted`] /
+///   [`ThingsStreamMessage::TrajectoryEnded`]: inserts or removes the
+///   [`Trajectory`] the replica follows between the two.
+/// - [`ThingsStreamMessage::ControlReleased`]: removes [`PlayerControlled`]
+///   and [`ControlledByClient`] from the replica.
+/// - [`ThingsStreamMessage::ComponentChanged`] /
+///   [`ThingsStreamMessage::ComponentRemoved`]: inserts or removes a synced
+///   component through the [`ComponentSyncRegistry`].
+/// - [`ThingsStreamMessage::NetIdAudit`]: despawns replicas the server no
+///   longer has.
+#[allow(clippy::too_many_arguments)]
 fn handle_entity_lifecycle(
     mut commands: Commands,
     mut reader: ResMut<StreamReader<ThingsStreamMessage>>,
     mut net_id_index: ResMut<NetIdIndex>,
     client: Res<Client>,
-    server: Option<Res<Server>>,
-    mut entities: Query<&mut Transform, With<Thing>>,
+    synced: Res<ComponentSyncRegistry>,
+    mut entities: Query<&mut Transform, (With<Thing>, Without<LocalAuthority>)>,
 ) {
-    let is_listen_server = server.is_some();
     for msg in reader.drain() {
         match msg {
             ThingsStreamMessage::EntitySpawned {
                 net_id,
                 kind,
                 position,
-                velocity: _,
+                velocity,
                 owner,
                 name,
             } => {
-                let controlled = owner.is_some() && owner == client.local_id;
-
-                // On a listen-server the entity was already spawned server-side
-                // and pre-registered in NetIdIndex. Skip the spawn but still
-                // apply client-only components to the existing entity.
-                if let Some(&existing) = net_id_index.0.get(&net_id) {
-                    debug!(
-                        "EntitySpawned for NetId({}) already exists, applying client components",
-                        net_id.0
-                    );
-                    if let Some(n) = name.as_deref().filter(|n| !n.is_empty()) {
-                        commands.entity(existing).insert(DisplayName(n.to_string()));
-                    }
-                    if controlled {
-                        commands.entity(existing).insert(PlayerControlled);
-                    }
-                    if let Some(owner_id) = owner {
-                        commands
-                            .entity(existing)
-                            .insert(ControlledByClient(owner_id));
-                    }
-                    continue;
-                }
-
-                let pos = Vec3::from_array(position);
-                info!("Spawning entity NetId({}) at {pos}", net_id.0);
-
-                let entity = commands.spawn(net_id).id();
-                commands.trigger(SpawnThing {
-                    entity,
+                let data = EntitySpawnedData {
+                    net_id,
                     kind,
-                    position: pos,
-                });
-
-                // The server is the physics authority — override the
-                // template's Dynamic body so client-side gravity can't
-                // pull replicated entities through the floor before tile
-                // colliders are ready.
-                commands.entity(entity).insert((
-                    RigidBody::Kinematic,
-                    GravityScale(0.0),
-                ));
-
-                if let Some(n) = name.as_deref().filter(|n| !n.is_empty()) {
-                    commands.entity(entity).insert(DisplayName(n.to_string()));
+                    position,
+                    velocity,
+                    owner,
+                    name,
+                };
+                spawn_replica(&mut commands, &mut net_id_index, &client, data);
+            }
+            ThingsStreamMessage::EntityBatch(batch) => {
+                debug!("Spawning a batch of {} entities", batch.len());
+                for data in batch {
+                    spawn_replica(&mut commands, &mut net_id_index, &client, data);
                 }
-
-                if controlled {
-                    commands.entity(entity).insert(PlayerControlled);
+            }
+            ThingsStreamMessage::EntityDespawned { net_id, reason } => {
+                info!("Despawning entity NetId({}) ({reason:?})", net_id.0);
+                if let Some(entity) = net_id_index.0.remove(&net_id) {
+                    despawn::despawn_replica(&mut commands, entity, reason);
                 }
-
-                if let Some(owner_id) = owner {
-                    commands.entity(entity).insert(ControlledByClient(owner_id));
+            }
+            ThingsStreamMessage::JointSpawned { net_id, joint } => {
+                if net_id_index.0.contains_key(&net_id) {
+                    continue;
                 }
-
+                let entity = commands.spawn((net_id, joint)).id();
                 net_id_index.0.insert(net_id, entity);
             }
-            ThingsStreamMessage::EntityDespawned { net_id } => {
-                info!("Despawning entity NetId({})", net_id.0);
-                if let Some(entity) = net_id_index.0.remove(&net_id) {
-                    commands.entity(entity).despawn();
+            ThingsStreamMessage::AuthorityGranted { net_id } => {
+                if let Some(&entity) = net_id_index.0.get(&net_id) {
+                    authority::apply_authority_change(&mut commands, entity, true);
                 }
             }
-            ThingsStreamMessage::StateUpdate { entities: states } => {
-                // On a listen-server the transforms are already authoritative;
-                // re-applying them would trigger Changed<Transform> and re-dirty
-                // every entity each frame.
-                if is_listen_server {
-                    continue;
+            ThingsStreamMessage::AuthorityRevoked { net_id } => {
+                if let Some(&entity) = net_id_index.0.get(&net_id) {
+                    authority::apply_authority_change(&mut commands, entity, false);
                 }
+            }
+            ThingsStreamMessage::HealthChanged {
+                net_id,
+                current,
+                max,
+            } => {
+                if let Some(&entity) = net_id_index.0.get(&net_id) {
+                    commands.entity(entity).insert(Health { current, max });
+                }
+            }
+            ThingsStreamMessage::StatusEffectsChanged { net_id, effects } => {
+                if let Some(&entity) = net_id_index.0.get(&net_id) {
+                    commands.entity(entity).insert(StatusEffects(effects));
+                }
+            }
+            ThingsStreamMessage::NeedsChanged {
+                net_id,
+                nutrition,
+                hydration,
+            } => {
+                if let Some(&entity) = net_id_index.0.get(&net_id) {
+                    commands.entity(entity).insert(Needs {
+                        nutrition,
+                        hydration,
+                    });
+                }
+            }
+            ThingsStreamMessage::TrajectoryStarted {
+                net_id,
+                position,
+                velocity,
+                gravity,
+            } => {
+                if let Some(&entity) = net_id_index.0.get(&net_id) {
+                    commands.entity(entity).insert((
+                        Transform::from_translation(Vec3::from_array(position)),
+                        Trajectory {
+                            velocity: Vec3::from_array(velocity),
+                            gravity,
+                        },
+                    ));
+                }
+            }
+            ThingsStreamMessage::TrajectoryEnded { net_id } => {
+                if let Some(&entity) = net_id_index.0.get(&net_id) {
+                    commands.entity(entity).remove::<Trajectory>();
+                }
+            }
+            ThingsStreamMessage::ControlReleased { net_id } => {
+                if let Some(&entity) = net_id_index.0.get(&net_id)
+                    && let Ok(mut entity) = commands.get_entity(entity)
+                {
+                    entity.remove::<(PlayerControlled, ControlledByClient)>();
+                }
+            }
+            ThingsStreamMessage::ComponentChanged {
+                net_id,
+                component,
+                value,
+            } => {
+                if let Some(&entity) = net_id_index.0.get(&net_id) {
+                    let mut entity = commands.entity(entity);
+                    if let Err(e) = synced.insert(&mut entity, component, &value) {
+                        error!("ComponentChanged for NetId({}): {e}", net_id.0);
+                    }
+                }
+            }
+            ThingsStreamMessage::NetIdAudit { live, issued_below } => {
+                audit::purge_stale_replicas(&mut commands, &mut net_id_index, &live, issued_below);
+            }
+            ThingsStreamMessage::ComponentRemoved { net_id, component } => {
+                if let Some(&entity) = net_id_index.0.get(&net_id) {
+                    let mut entity = commands.entity(entity);
+                    if let Err(e) = synced.remove(&mut entity, component) {
+                        error!("ComponentRemoved for NetId({}): {e}", net_id.0);
+                    }
+                }
+            }
+            ThingsStreamMessage::StateUpdate { entities: states } => {
                 for state in &states {
-                    if let Some(&entity) = net_id_index.0.get(&state.net_id)
-                        && let Ok(mut transform) = entities.get_mut(entity)
-                    {
+                    let Some(&entity) = net_id_index.0.get(&state.net_id) else {
+                        continue;
+                    };
+                    // Entities spawned earlier in this drain are not in the
+                    // world yet; their position came with EntitySpawned.
+                    if let Ok(mut transform) = entities.get_mut(entity) {
                         transform.translation = Vec3::from_array(state.position);
                     }
+                    commands
+                        .entity(entity)
+                        .insert(ReplicatedYaw(state.yaw_radians()));
                 }
             }
         }
     }
 }
 
+/// Spawns the replica introduced by `data`, unless it already exists.
+fn spawn_replica(
+    commands: &mut Commands,
+    net_id_index: &mut NetIdIndex,
+    client: &Client,
+    data: EntitySpawnedData,
+) {
+    let EntitySpawnedData {
+        net_id,
+        kind,
+        position,
+        velocity: _,
+        owner,
+        name,
+    } = data;
+    // A thing can be introduced twice, e.g. by a broadcast racing the join
+    // catch-up.
+    if net_id_index.0.contains_key(&net_id) {
+        debug!("EntitySpawned for NetId({}) already exists", net_id.0);
+        return;
+    }
+    let controlled = owner.is_some() && owner == client.local_id;
+
+    let pos = Vec3::from_array(position);
+    info!("Spawning entity NetId({}) at {pos}", net_id.0);
+
+    let entity = commands.spawn(net_id).id();
+    commands.trigger(SpawnThing {
+        entity,
+        kind,
+        position: pos,
+    });
+
+    // The server is the physics authority — override the
+    // template's Dynamic body so client-side gravity can't
+    // pull replicated entities through the floor before tile
+    // colliders are ready.
+    commands.entity(entity).insert((
+        RigidBody::Kinematic,
+        GravityScale(0.0),
+    ));
+
+    if let Some(n) = name.as_deref().filter(|n| !n.is_empty()) {
+        commands.entity(entity).insert(DisplayName(n.to_string()));
+    }
+
+    if controlled {
+        commands.entity(entity).insert(PlayerControlled);
+    }
+
+    if let Some(owner_id) = owner {
+        commands.entity(entity).insert(ControlledByClient(owner_id));
+    }
+
+    net_id_index.0.insert(net_id, entity);
+}
+
+/// Builds the [`EntitySpawnedData`] for an entity as `client` should see it:
+/// `owner` is set only on the client controlling it.
+pub(crate) fn entity_spawned(
+    net_id: NetId,
+    thing: &Thing,
+    transform: &Transform,
+    velocity: Option<&LinearVelocity>,
+    name: Option<&DisplayName>,
+    controlled: Option<&ControlledByClient>,
+    client: ClientId,
+) -> EntitySpawnedData {
+    EntitySpawnedData {
+        net_id,
+        kind: thing.kind,
+        position: transform.translation.into(),
+        velocity: velocity
+            .map(|lv| [lv.x, lv.y, lv.z])
+            .unwrap_or([0.0, 0.0, 0.0]),
+        owner: controlled
+            .map(|c| c.0)
+            .filter(|&owner_id| owner_id == client),
+        name: name.map(|n| n.0.clone()),
+    }
+}
+
 /// Handles server-side catch-up on client join for stream 3.
 ///
-/// Sends catch-up [`ThingsStreamMessage::EntitySpawned`] messages for all currently
-/// tracked entities to the joining client.
+/// Sends every currently tracked entity the joining client may see in
+/// [`ThingsStreamMessage::EntityBatch`]es of up to [`SPAWN_BATCH_SIZE`],
+/// followed by one [`ThingsStreamMessage::HealthChanged`] per hurt entity,
+/// one [`ThingsStreamMessage::TrajectoryStarted`] per thing in flight, one
+/// [`ThingsStreamMessage::JointSpawned`] per existing [`ReplicatedJoint`] and
+/// one [`ThingsStreamMessage::StateUpdate`] carrying the heading of every
+/// entity that is not facing the default direction (spawns have no
+/// rotation, and `broadcast_state` only resends entities that change).
 ///
 /// The [`StreamReady`] sentinel is sent separately by [`send_stream_ready_on_join`]
 /// in [`ThingsSet::SendStreamReady`], which runs after both this system *and* any
@@ -978,45 +1610,155 @@ fn handle_client_joined(
     mut messages: MessageReader<PlayerEvent>,
     stream_sender: Res<StreamSender<ThingsStreamMessage>>,
     entities: Query<(
+        Entity,
         &NetId,
         Option<&ControlledByClient>,
         &Transform,
         Option<&LinearVelocity>,
         Option<&DisplayName>,
         &Thing,
+        Has<ChildOf>,
+        Option<&Health>,
+        Option<&Trajectory>,
     )>,
+    joints: Query<(Entity, &NetId, &ReplicatedJoint)>,
+    net_id_index: Res<NetIdIndex>,
+    scope: ReplicationScope,
 ) {
     for event in messages.read() {
         let PlayerEvent::Joined { id: from, .. } = event else {
             continue;
         };
 
-        // Catch-up: send EntitySpawned on stream 3 for every existing Thing entity.
-        for (net_id, opt_controlled_by, transform, opt_velocity, opt_name, thing) in entities.iter()
+        // Catch-up: batch the spawn of every Thing entity the joining client
+        // may see, and send what else it needs to know about them after.
+        let mut spawns = Vec::new();
+        let mut follow_ups = Vec::new();
+        let mut headings = Vec::new();
+        for (
+            entity,
+            net_id,
+            opt_controlled_by,
+            transform,
+            opt_velocity,
+            opt_name,
+            thing,
+            is_child,
+            opt_health,
+            opt_trajectory,
+        ) in entities.iter()
         {
-            let owner = opt_controlled_by
-                .map(|c| c.0)
-                .filter(|&owner_id| owner_id == *from);
-            let vel = opt_velocity
-                .map(|lv| [lv.x, lv.y, lv.z])
-                .unwrap_or([0.0, 0.0, 0.0]);
-            if let Err(e) = stream_sender.send_to(
+            if !scope.is_visible_to(entity, *from) {
+                continue;
+            }
+            spawns.push(entity_spawned(
+                *net_id,
+                thing,
+                transform,
+                opt_velocity,
+                opt_name,
+                opt_controlled_by,
                 *from,
-                &ThingsStreamMessage::EntitySpawned {
+            ));
+
+            // Replicas start at full health from their template.
+            if let Some(health) = opt_health.filter(|h| h.current < h.max) {
+                follow_ups.push(ThingsStreamMessage::HealthChanged {
+                    net_id: *net_id,
+                    current: health.current,
+                    max: health.max,
+                });
+            }
+
+            if let Some(trajectory) = opt_trajectory {
+                follow_ups.push(trajectory.started(*net_id, transform.translation));
+                continue;
+            }
+
+            let yaw = EntityState::quantize_yaw(yaw_of(transform.rotation));
+            if !is_child && yaw != 0 {
+                let vel = opt_velocity
+                    .map(|lv| [lv.x, lv.y, lv.z])
+                    .unwrap_or([0.0, 0.0, 0.0]);
+                headings.push(EntityState {
                     net_id: *net_id,
-                    kind: thing.kind,
                     position: transform.translation.into(),
                     velocity: vel,
-                    owner,
-                    name: opt_name.map(|n| n.0.clone()),
-                },
-            ) {
+                    yaw,
+                });
+            }
+        }
+
+        for batch in spawns.chunks(SPAWN_BATCH_SIZE) {
+            let msg = ThingsStreamMessage::EntityBatch(batch.to_vec());
+            if let Err(e) = stream_sender.send_to(*from, &msg) {
                 error!(
-                    "Failed to send EntitySpawned catch-up to ClientId({}): {e}",
+                    "Failed to send EntityBatch catch-up to ClientId({}): {e}",
                     from.0
                 );
             }
         }
+        for msg in &follow_ups {
+            if let Err(e) = stream_sender.send_to(*from, msg) {
+                error!(
+                    "Failed to send entity catch-up to ClientId({}): {e}",
+                    from.0
+                );
+            }
+        }
+
+        // Joints go after all entities so both ends resolve on the client.
+        // A joint with a hidden end would never resolve, so it is skipped too.
+        for (entity, &net_id, &joint) in joints.iter() {
+            let visible = |id: NetId| {
+                net_id_index
+                    .0
+                    .get(&id)
+                    .is_none_or(|&e| scope.is_visible_to(e, *from))
+            };
+            if !scope.is_visible_to(entity, *from) || !visible(joint.a) || !visible(joint.b) {
+                continue;
+            }
+            if let Err(e) =
+                stream_sender.send_to(*from, &ThingsStreamMessage::JointSpawned { net_id, joint })
+            {
+                error!(
+                    "Failed to send JointSpawned catch-up to ClientId({}): {e}",
+                    from.0
+                );
+            }
+        }
+
+        if !headings.is_empty()
+            && let Err(e) = stream_sender.send_to(
+                *from,
+                &ThingsStreamMessage::StateUpdate { entities: headings },
+            )
+        {
+            error!(
+                "Failed to send heading catch-up to ClientId({}): {e}",
+                from.0
+            );
+        }
+    }
+}
+
+/// Client-side system: turns replicated entities towards their [`ReplicatedYaw`].
+///
+/// Snaps on the first update so freshly spawned entities do not spin into place.
+/// The local player's creature is skipped; it faces its predicted movement instead.
+fn interpolate_replicated_yaw(
+    time: Res<Time>,
+    mut entities: Query<(&mut Transform, Ref<ReplicatedYaw>), Without<PlayerControlled>>,
+) {
+    let blend = 1.0 - (-YAW_SMOOTHING * time.delta_secs()).exp();
+    for (mut transform, yaw) in entities.iter_mut() {
+        let target = Quat::from_rotation_y(yaw.0);
+        transform.rotation = if yaw.is_added() {
+            target
+        } else {
+            transform.rotation.slerp(target, blend)
+        };
     }
 }
 
@@ -1051,60 +1793,97 @@ fn send_stream_ready_on_join(
 /// Broadcasts authoritative position updates on stream 3 for entities whose
 /// state has changed since the last broadcast.
 ///
-/// Throttled to [`NETWORK_UPDATE_INTERVAL`] to reduce bandwidth.
-/// Compares current position/velocity against [`LastBroadcast`] to skip
-/// unchanged entities.
+/// Throttled to [`NETWORK_UPDATE_INTERVAL`] to reduce bandwidth, and skipped
+/// entirely while stream 3 is saturated: [`LastBroadcast`] is left untouched,
+/// so the changes go out in the first update after the backlog clears.
+/// Compares current position/velocity/yaw against [`LastBroadcast`] to skip
+/// unchanged entities.  Things flying along a [`Trajectory`] are skipped;
+/// clients extrapolate them.
 const POSITION_EPSILON_SQ: f32 = 1e-6;
 const VELOCITY_EPSILON_SQ: f32 = 1e-6;
 
+#[allow(clippy::type_complexity)]
 fn broadcast_state(
     time: Res<Time>,
     mut timer: ResMut<StateBroadcastTimer>,
     stream_sender: Res<StreamSender<ThingsStreamMessage>>,
     mut entities: Query<
         (
+            Entity,
             &NetId,
             &Transform,
             Option<&LinearVelocity>,
             &mut LastBroadcast,
         ),
-        Without<ChildOf>,
+        (Without<ChildOf>, Without<Trajectory>),
     >,
+    scope: ReplicationScope,
 ) {
-    if !timer.0.tick(time.delta()).just_finished() {
+    if !timer.0.tick(time.delta()).just_finished() || stream_sender.saturation().is_saturated() {
         return;
     }
 
-    let states: Vec<EntityState> = entities
-        .iter_mut()
-        .filter_map(|(net_id, transform, velocity, mut last)| {
-            let pos = transform.translation;
-            let vel = velocity.map(|lv| lv.0).unwrap_or(Vec3::ZERO);
+    // Entities with a ReplicationVisibility go out per client, the rest in
+    // one broadcast.
+    let mut states = Vec::new();
+    let mut restricted = Vec::new();
+    for (entity, net_id, transform, velocity, mut last) in entities.iter_mut() {
+        let pos = transform.translation;
+        let vel = velocity.map(|lv| lv.0).unwrap_or(Vec3::ZERO);
+        let yaw = EntityState::quantize_yaw(yaw_of(transform.rotation));
 
-            let pos_changed = (pos - last.position).length_squared() > POSITION_EPSILON_SQ;
-            let vel_changed = (vel - last.velocity).length_squared() > VELOCITY_EPSILON_SQ;
+        let pos_changed = (pos - last.position).length_squared() > POSITION_EPSILON_SQ;
+        let vel_changed = (vel - last.velocity).length_squared() > VELOCITY_EPSILON_SQ;
 
-            if !pos_changed && !vel_changed {
-                return None;
-            }
+        if !pos_changed && !vel_changed && yaw == last.yaw {
+            continue;
+        }
 
-            last.position = pos;
-            last.velocity = vel;
+        last.position = pos;
+        last.velocity = vel;
+        last.yaw = yaw;
 
-            Some(EntityState {
-                net_id: *net_id,
-                position: pos.into(),
-                velocity: [vel.x, vel.y, vel.z],
-            })
-        })
-        .collect();
+        let state = EntityState {
+            net_id: *net_id,
+            position: pos.into(),
+            velocity: [vel.x, vel.y, vel.z],
+            yaw,
+        };
+        if scope.is_public(entity) {
+            states.push(state);
+        } else {
+            restricted.push((entity, state));
+        }
+    }
 
     if !states.is_empty()
         && let Err(e) =
-            stream_sender.broadcast(&ThingsStreamMessage::StateUpdate { entities: states })
+            stream_sender.broadcast_state(&ThingsStreamMessage::StateUpdate { entities: states })
     {
         error!("Failed to broadcast entity state on things stream: {e}");
     }
+
+    if restricted.is_empty() {
+        return;
+    }
+    for client in scope.clients() {
+        let visible: Vec<EntityState> = restricted
+            .iter()
+            .filter(|(entity, _)| scope.is_visible_to(*entity, client))
+            .map(|(_, state)| state.clone())
+            .collect();
+        if !visible.is_empty()
+            && let Err(e) = stream_sender.send_state_to(
+                client,
+                &ThingsStreamMessage::StateUpdate { entities: visible },
+            )
+        {
+            error!(
+                "Failed to send entity state to ClientId({}) on things stream: {e}",
+                client.0
+            );
+        }
+    }
 }
 
 /// Listens for left-click and right-click [`PointerAction`] events, raycasts against entity
@@ -1112,20 +1891,17 @@ fn broadcast_state(
 fn raycast_things(
     mut pointer_action_reader: MessageReader<PointerAction>,
     spatial_query: SpatialQuery,
-    camera: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    view_camera: ViewCamera,
     things: Query<&Thing>,
     mut hit_writer: MessageWriter<WorldHit>,
 ) {
-    let Ok((camera, camera_transform)) = camera.single() else {
-        return;
-    };
 
     for action in pointer_action_reader.read() {
         if !matches!(action.button, MouseButton::Left | MouseButton::Right) {
             continue;
         }
 
-        let Ok(ray) = camera.viewport_to_world(camera_transform, action.screen_pos) else {
+        let Some(ray) = view_camera.ray(action.screen_pos) else {
             continue;
         };
 
@@ -1134,7 +1910,7 @@ fn raycast_things(
             ray.direction,
             f32::MAX,
             true,
-            &SpatialQueryFilter::default(),
+            &GameLayer::solid_filter(),
         ) && things.get(hit.entity).is_ok()
         {
             let world_pos = ray.origin + *ray.direction * hit.distance;
@@ -1198,6 +1974,31 @@ mod tests {
         assert_eq!(slot.side, HandSide::Right, "HandSlot side should be Right");
     }
 
+    /// Verifies that a replicated entity snaps to its first [`ReplicatedYaw`]
+    /// and turns only part of the way towards later ones.
+    #[test]
+    fn replicated_yaw_snaps_on_spawn_then_interpolates() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_systems(Update, interpolate_replicated_yaw);
+
+        let entity = app
+            .world_mut()
+            .spawn((Transform::default(), ReplicatedYaw(1.0)))
+            .id();
+        app.update();
+        let yaw = yaw_of(app.world().get::<Transform>(entity).unwrap().rotation);
+        assert!((yaw - 1.0).abs() < 1e-4, "expected snap, got {yaw}");
+
+        app.world_mut().get_mut::<ReplicatedYaw>(entity).unwrap().0 = 2.0;
+        app.update();
+        let yaw = yaw_of(app.world().get::<Transform>(entity).unwrap().rotation);
+        assert!(
+            yaw > 1.0 - 1e-4 && yaw < 2.0,
+            "expected partial turn, got {yaw}"
+        );
+    }
+
     /// Verifies that SpawnsLayer::load deserializes spawn points and triggers
     /// SpawnThing for each, producing entities with SpawnMarker + Thing +
     /// Transform at the correct positions.
@@ -1310,6 +2111,48 @@ mod tests {
         );
     }
 
+    /// Verifies that adding a [`ReplicatedJoint`] resolves both ends through
+    /// [`NetIdIndex`] and inserts the matching physics joint.
+    #[test]
+    fn replicated_joint_inserts_physics_joint() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<NetIdIndex>();
+        app.add_observer(on_replicated_joint_added);
+
+        let a = app.world_mut().spawn(NetId(1)).id();
+        let b = app.world_mut().spawn(NetId(2)).id();
+        {
+            let mut index = app.world_mut().resource_mut::<NetIdIndex>();
+            index.0.insert(NetId(1), a);
+            index.0.insert(NetId(2), b);
+        }
+
+        let joint = app
+            .world_mut()
+            .spawn(ReplicatedJoint {
+                a: NetId(1),
+                b: NetId(2),
+                kind: JointKind::Fixed,
+            })
+            .id();
+        let unresolved = app
+            .world_mut()
+            .spawn(ReplicatedJoint {
+                a: NetId(1),
+                b: NetId(99),
+                kind: JointKind::Fixed,
+            })
+            .id();
+        app.update();
+
+        assert!(app.world().get::<FixedJoint>(joint).is_some());
+        assert!(
+            app.world().get::<FixedJoint>(unresolved).is_none(),
+            "a joint with an unknown end must not be created"
+        );
+    }
+
     #[test]
     fn named_templates_returns_registered_name_kind_pairs() {
         let mut registry = ThingRegistry::default();
@@ -1334,4 +2177,17 @@ mod tests {
         assert_eq!(named.len(), 1, "only named templates should appear");
         assert_eq!(named[0], ("named", 1));
     }
+
+    #[test]
+    fn unregister_frees_the_name_and_kind() {
+        let mut registry = ThingRegistry::default();
+
+        registry.register_named("foo", 1, |_, _| {}, |_, _| {});
+        registry.unregister(1);
+        assert_eq!(registry.kind_by_name("foo"), None);
+        assert_eq!(registry.name_by_kind(1), None);
+
+        registry.register_named("foo", 2, |_, _| {}, |_, _| {});
+        assert_eq!(registry.kind_by_name("foo"), Some(2));
+    }
 }
@@ -0,0 +1,49 @@
+//! Movement modifiers and the movement state they lead to.
+//!
+//! The controlling client holds sprint or crouch as
+//! [`MovementModifiers`] next to its [`InputDirection`](crate::InputDirection)
+//! and sends both to the server.  The server decides the resulting
+//! [`MovementState`] (a sprint needs stamina, see the `creatures` module)
+//! and replicates it as a synced component (see the `sync` module) so every
+//! client can animate the creature and the controlling one can predict its
+//! speed.  A creature too hurt, hungry or thirsty to stay awake is
+//! [`MovementState::Unconscious`] whatever its modifiers.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use wincode::{SchemaRead, SchemaWrite};
+
+/// Movement modifiers currently held for an entity.  Written by input
+/// systems (player module) or from received network messages (server).
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct MovementModifiers {
+    pub sprint: bool,
+    pub crouch: bool,
+}
+
+/// How a creature is moving, as decided by the server.
+#[derive(
+    Component,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Reflect,
+    Serialize,
+    Deserialize,
+    SchemaRead,
+    SchemaWrite,
+)]
+#[reflect(Component)]
+pub enum MovementState {
+    #[default]
+    Walking,
+    Sprinting,
+    Crouching,
+    /// Collapsed: ignores its input, lies down and can be dragged.
+    Unconscious,
+}
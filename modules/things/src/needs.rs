@@ -0,0 +1,122 @@
+//! Hunger and thirst.
+//!
+//! Creatures with [`Needs`] grow hungrier and thirstier on the server every
+//! simulation tick in [`tick_needs`], and eating or drinking (the consumable
+//! items of the `items` module) fills them up again through
+//! [`Needs::restore`].
+//!
+//! The needs of a creature are replicated on stream 3 to the client
+//! controlling it, for its HUD.  Ticking down only marks them changed when
+//! a level crosses a whole point, so a creature costs one message every few
+//! seconds rather than one per tick.
+
+use bevy::prelude::*;
+use network::{ControlledByClient, NetId, StreamSender};
+
+use crate::ThingsStreamMessage;
+
+/// Level of a need that is completely satisfied.
+pub const MAX_NEED: f32 = 100.0;
+
+/// Nutrition lost per second: a full stomach lasts 45 minutes.
+pub const HUNGER_PER_SECOND: f32 = MAX_NEED / (45.0 * 60.0);
+
+/// Hydration lost per second: a full creature goes thirsty in 30 minutes.
+pub const THIRST_PER_SECOND: f32 = MAX_NEED / (30.0 * 60.0);
+
+/// How fed and watered a creature is, each `0.0..=MAX_NEED`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Needs {
+    pub nutrition: f32,
+    pub hydration: f32,
+}
+
+impl Default for Needs {
+    fn default() -> Self {
+        Self {
+            nutrition: MAX_NEED,
+            hydration: MAX_NEED,
+        }
+    }
+}
+
+impl Needs {
+    /// Adds `nutrition` and `hydration`, up to [`MAX_NEED`].
+    pub fn restore(&mut self, nutrition: f32, hydration: f32) {
+        self.nutrition = (self.nutrition + nutrition).clamp(0.0, MAX_NEED);
+        self.hydration = (self.hydration + hydration).clamp(0.0, MAX_NEED);
+    }
+
+    /// Counts both needs down for `dt` seconds.  Returns whether either
+    /// crossed a whole point.
+    pub fn tick(&mut self, dt: f32) -> bool {
+        let before = (self.nutrition.ceil(), self.hydration.ceil());
+        self.nutrition = (self.nutrition - HUNGER_PER_SECOND * dt).max(0.0);
+        self.hydration = (self.hydration - THIRST_PER_SECOND * dt).max(0.0);
+        (self.nutrition.ceil(), self.hydration.ceil()) != before
+    }
+}
+
+/// Server system: makes every creature with [`Needs`] hungrier and
+/// thirstier.
+pub fn tick_needs(time: Res<Time>, mut needs: Query<&mut Needs>) {
+    let dt = time.delta_secs();
+    for mut needs in needs.iter_mut() {
+        if needs.bypass_change_detection().tick(dt) {
+            needs.set_changed();
+        }
+    }
+}
+
+/// Server system: sends [`ThingsStreamMessage::NeedsChanged`] to the
+/// controlling client of every creature whose [`Needs`] changed or that was
+/// just bound to a client.
+#[allow(clippy::type_complexity)]
+pub(crate) fn send_needs(
+    sender: Res<StreamSender<ThingsStreamMessage>>,
+    changed: Query<
+        (&NetId, &Needs, &ControlledByClient),
+        Or<(Changed<Needs>, Changed<ControlledByClient>)>,
+    >,
+) {
+    for (&net_id, needs, controller) in changed.iter() {
+        let msg = ThingsStreamMessage::NeedsChanged {
+            net_id,
+            nutrition: needs.nutrition,
+            hydration: needs.hydration,
+        };
+        if let Err(e) = sender.send_to(controller.0, &msg) {
+            error!(
+                "Failed to send NeedsChanged for NetId({}) to ClientId({}): {e}",
+                net_id.0, controller.0.0
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needs_run_down_and_refill_up_to_the_maximum() {
+        let mut needs = Needs::default();
+        // A second costs a fraction of a point: nothing worth sending yet.
+        assert!(!needs.tick(1.0));
+        assert!(needs.tick(60.0));
+        assert!((needs.nutrition - (MAX_NEED - HUNGER_PER_SECOND * 61.0)).abs() < 1e-3);
+        assert!(needs.hydration < needs.nutrition);
+
+        needs.tick(3600.0);
+        assert_eq!((needs.nutrition, needs.hydration), (0.0, 0.0));
+        needs.restore(30.0, 150.0);
+        assert_eq!(
+            needs,
+            Needs {
+                nutrition: 30.0,
+                hydration: MAX_NEED,
+            }
+        );
+    }
+}
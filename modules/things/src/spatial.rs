@@ -0,0 +1,226 @@
+//! Uniform-grid spatial index over free-standing things.
+//!
+//! [`SpatialIndex`] buckets every [`Thing`] that lives in the world (not
+//! parented to a hand or container) by the tile it stands on, so "who is
+//! near this point" queries touch a handful of cells instead of every entity.
+//! The index is maintained incrementally from `Changed<Transform>` in
+//! `PreUpdate`; positions seen by `Update` systems are therefore at most one
+//! frame old.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::Thing;
+
+/// Maps a world-space translation to the tile-grid cell it stands on.
+pub fn cell_of(translation: Vec3) -> IVec2 {
+    IVec2::new(translation.x.round() as i32, translation.z.round() as i32)
+}
+
+/// Grid of things keyed by tile coordinates.
+#[derive(Resource, Debug, Default)]
+pub struct SpatialIndex {
+    cells: HashMap<IVec2, Vec<Entity>>,
+    entries: HashMap<Entity, (IVec2, Vec3)>,
+}
+
+impl SpatialIndex {
+    /// Inserts `entity` at `position`, or moves it there if already indexed.
+    pub fn insert(&mut self, entity: Entity, position: Vec3) {
+        let cell = cell_of(position);
+        match self.entries.insert(entity, (cell, position)) {
+            Some((old_cell, _)) if old_cell == cell => {}
+            Some((old_cell, _)) => {
+                self.remove_from_cell(entity, old_cell);
+                self.cells.entry(cell).or_default().push(entity);
+            }
+            None => self.cells.entry(cell).or_default().push(entity),
+        }
+    }
+
+    /// Removes `entity` from the index; does nothing if it is not indexed.
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some((cell, _)) = self.entries.remove(&entity) {
+            self.remove_from_cell(entity, cell);
+        }
+    }
+
+    fn remove_from_cell(&mut self, entity: Entity, cell: IVec2) {
+        if let Some(bucket) = self.cells.get_mut(&cell) {
+            bucket.retain(|&e| e != entity);
+            if bucket.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /// Last indexed position of `entity`.
+    pub fn position(&self, entity: Entity) -> Option<Vec3> {
+        self.entries.get(&entity).map(|&(_, position)| position)
+    }
+
+    /// Number of indexed entities.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if nothing is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes every entity from the index.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+
+    /// Indexed entities standing on `cell`.
+    pub fn in_cell(&self, cell: IVec2) -> impl Iterator<Item = Entity> + '_ {
+        self.cells.get(&cell).into_iter().flatten().copied()
+    }
+
+    /// Every indexed entity within `radius` of `pos` (3D distance), together
+    /// with its indexed position.  Order is unspecified.
+    pub fn query_within(
+        &self,
+        pos: Vec3,
+        radius: f32,
+    ) -> impl Iterator<Item = (Entity, Vec3)> + '_ {
+        let min = cell_of(pos - Vec3::splat(radius));
+        let max = cell_of(pos + Vec3::splat(radius));
+        let radius_sq = radius * radius;
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |z| IVec2::new(x, z)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter_map(move |&entity| {
+                let &(_, position) = self.entries.get(&entity)?;
+                (position.distance_squared(pos) <= radius_sq).then_some((entity, position))
+            })
+    }
+}
+
+/// Keeps [`SpatialIndex`] in sync with free-standing things: moved things and
+/// things let go of by a hand or container are (re-)bucketed, things picked
+/// up and despawned things are dropped.
+///
+/// Public so tests in other modules can maintain the index without the full
+/// `ThingsPlugin`.
+#[allow(clippy::type_complexity)]
+pub fn update_spatial_index(
+    mut index: ResMut<SpatialIndex>,
+    mut removed: RemovedComponents<Thing>,
+    mut unparented: RemovedComponents<ChildOf>,
+    parented: Query<Entity, (With<Thing>, Added<ChildOf>)>,
+    free: Query<&Transform, (With<Thing>, Without<ChildOf>)>,
+    moved: Query<(Entity, &Transform), (With<Thing>, Without<ChildOf>, Changed<Transform>)>,
+) {
+    for entity in removed.read() {
+        index.remove(entity);
+    }
+    for entity in parented.iter() {
+        index.remove(entity);
+    }
+    for entity in unparented.read() {
+        if let Ok(transform) = free.get(entity) {
+            index.insert(entity, transform.translation);
+        }
+    }
+    for (entity, transform) in moved.iter() {
+        index.insert(entity, transform.translation);
+    }
+}
+
+pub(crate) fn clear_spatial_index(mut index: ResMut<SpatialIndex>) {
+    index.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_within_filters_by_exact_distance() {
+        let mut world = World::new();
+        let near = world.spawn_empty().id();
+        let edge = world.spawn_empty().id();
+        let far = world.spawn_empty().id();
+        let mut index = SpatialIndex::default();
+        index.insert(near, Vec3::new(0.4, 0.0, 0.4));
+        index.insert(edge, Vec3::new(2.0, 0.0, 0.0));
+        index.insert(far, Vec3::new(1.9, 0.0, 1.9));
+
+        let mut found: Vec<Entity> = index
+            .query_within(Vec3::ZERO, 2.0)
+            .map(|(e, _)| e)
+            .collect();
+        found.sort();
+        let mut expected = vec![near, edge];
+        expected.sort();
+        assert_eq!(found, expected);
+
+        index.insert(far, Vec3::new(0.5, 0.0, -0.5));
+        index.remove(near);
+        let mut found: Vec<Entity> = index
+            .query_within(Vec3::ZERO, 2.0)
+            .map(|(e, _)| e)
+            .collect();
+        found.sort();
+        let mut expected = vec![edge, far];
+        expected.sort();
+        assert_eq!(found, expected);
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn index_follows_moves_pickups_and_despawns() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<SpatialIndex>();
+        app.add_systems(Update, update_spatial_index);
+
+        let thing = app
+            .world_mut()
+            .spawn((Thing { kind: 1 }, Transform::from_xyz(5.0, 0.0, 5.0)))
+            .id();
+        app.update();
+        let index = app.world().resource::<SpatialIndex>();
+        assert_eq!(index.position(thing), Some(Vec3::new(5.0, 0.0, 5.0)));
+
+        app.world_mut()
+            .entity_mut(thing)
+            .insert(Transform::from_xyz(-3.0, 0.0, 2.0));
+        app.update();
+        let index = app.world().resource::<SpatialIndex>();
+        assert_eq!(index.query_within(Vec3::new(5.0, 0.0, 5.0), 1.0).count(), 0);
+        assert_eq!(
+            index.query_within(Vec3::new(-3.0, 0.0, 2.0), 0.5).count(),
+            1
+        );
+
+        let holder = app.world_mut().spawn(Transform::default()).id();
+        app.world_mut().entity_mut(thing).insert(ChildOf(holder));
+        app.update();
+        assert!(app.world().resource::<SpatialIndex>().is_empty());
+
+        app.world_mut().entity_mut(thing).remove::<ChildOf>();
+        app.update();
+        assert_eq!(
+            app.world().resource::<SpatialIndex>().position(thing),
+            Some(Vec3::new(-3.0, 0.0, 2.0)),
+            "Unparented things are indexed even if they did not move"
+        );
+
+        app.world_mut()
+            .entity_mut(thing)
+            .insert(Transform::from_xyz(1.0, 0.0, 1.0));
+        app.update();
+        assert_eq!(app.world().resource::<SpatialIndex>().len(), 1);
+
+        app.world_mut().entity_mut(thing).despawn();
+        app.update();
+        assert!(app.world().resource::<SpatialIndex>().is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+//! Timed status effects on creatures: stunned, slowed, burning.
+//!
+//! Systems that hurt or hinder a creature (fire, combat) write an
+//! [`ApplyStatus`] message instead of touching [`StatusEffects`] themselves.
+//! The server applies them in [`apply_status`], stacking repeated
+//! applications of the same kind, and counts them down every simulation tick
+//! in [`tick_status_effects`], which also deals the damage of burning.  What
+//! an effect does to movement is up to the `creatures` module, through
+//! [`StatusEffects::speed_multiplier`] and [`StatusEffects::is_stunned`].
+//!
+//! The effects of a creature are replicated on stream 3 to the client
+//! controlling it, which shows them as icons and predicts its movement with
+//! them.  Only applications and expiries are sent; the client does not
+//! count down.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use network::{ControlledByClient, NetId, StreamSender};
+use serde::{Deserialize, Serialize};
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::ThingsStreamMessage;
+use crate::damage::{Damage, DamageKind};
+
+/// Most stacks of one kind a creature can carry at once.
+pub const MAX_STATUS_STACKS: u8 = 3;
+
+/// Speed kept per stack of [`StatusKind::Slow`].
+pub const SLOW_FACTOR: f32 = 0.6;
+
+/// Health per second taken by each stack of [`StatusKind::Burning`].
+pub const BURNING_DAMAGE_PER_SECOND: f32 = 2.0;
+
+/// What a status effect does.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Reflect,
+    Serialize,
+    Deserialize,
+    SchemaRead,
+    SchemaWrite,
+)]
+pub enum StatusKind {
+    /// Cannot move or sprint.
+    Stun,
+    /// Moves at [`SLOW_FACTOR`] of its speed per stack.
+    Slow,
+    /// On fire: takes [`BURNING_DAMAGE_PER_SECOND`] per stack.
+    Burning,
+}
+
+impl StatusKind {
+    /// Name shown for the effect.
+    pub fn label(self) -> &'static str {
+        match self {
+            StatusKind::Stun => "Stunned",
+            StatusKind::Slow => "Slowed",
+            StatusKind::Burning => "Burning",
+        }
+    }
+}
+
+/// One active effect: its kind, how many times it has been stacked and the
+/// seconds left until it wears off.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Reflect, Serialize, Deserialize, SchemaRead, SchemaWrite,
+)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub stacks: u8,
+    pub remaining: f32,
+}
+
+/// The active effects of a creature, at most one entry per kind.
+#[derive(Component, Debug, Clone, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    /// Stacks of `kind` currently active; 0 when not affected.
+    pub fn stacks(&self, kind: StatusKind) -> u8 {
+        self.0
+            .iter()
+            .find(|effect| effect.kind == kind)
+            .map_or(0, |effect| effect.stacks)
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.stacks(StatusKind::Stun) > 0
+    }
+
+    /// Factor the creature's speed is scaled by.
+    pub fn speed_multiplier(&self) -> f32 {
+        SLOW_FACTOR.powi(self.stacks(StatusKind::Slow).into())
+    }
+
+    /// Adds `stacks` of `kind`, up to [`MAX_STATUS_STACKS`], lasting at
+    /// least `duration` more seconds.
+    pub fn apply(&mut self, kind: StatusKind, stacks: u8, duration: f32) {
+        match self.0.iter_mut().find(|effect| effect.kind == kind) {
+            Some(effect) => {
+                effect.stacks = effect.stacks.saturating_add(stacks).min(MAX_STATUS_STACKS);
+                effect.remaining = effect.remaining.max(duration);
+            }
+            None => self.0.push(StatusEffect {
+                kind,
+                stacks: stacks.min(MAX_STATUS_STACKS),
+                remaining: duration,
+            }),
+        }
+    }
+
+    /// Counts every effect down by `dt` seconds and drops those that wore
+    /// off.  Returns whether any did.
+    pub fn tick(&mut self, dt: f32) -> bool {
+        let before = self.0.len();
+        for effect in &mut self.0 {
+            effect.remaining -= dt;
+        }
+        self.0.retain(|effect| effect.remaining > 0.0);
+        self.0.len() != before
+    }
+}
+
+/// Request to put `stacks` of `kind` on `target` for `duration` seconds.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct ApplyStatus {
+    pub target: Entity,
+    pub kind: StatusKind,
+    pub stacks: u8,
+    pub duration: f32,
+}
+
+/// Server system: adds every [`ApplyStatus`] written since the last tick to
+/// its target's [`StatusEffects`], inserting the component if needed.
+pub fn apply_status(
+    mut commands: Commands,
+    mut requests: MessageReader<ApplyStatus>,
+    mut effects: Query<&mut StatusEffects>,
+) {
+    // Effects for targets that have none yet, gathered so that several
+    // requests in one tick stack instead of each inserting its own.
+    let mut inserted: HashMap<Entity, StatusEffects> = HashMap::new();
+    for request in requests.read() {
+        if request.stacks == 0 || request.duration <= 0.0 {
+            continue;
+        }
+        if let Ok(mut effects) = effects.get_mut(request.target) {
+            effects.apply(request.kind, request.stacks, request.duration);
+        } else {
+            inserted.entry(request.target).or_default().apply(
+                request.kind,
+                request.stacks,
+                request.duration,
+            );
+        }
+    }
+    for (target, effects) in inserted {
+        if let Ok(mut entity) = commands.get_entity(target) {
+            entity.insert(effects);
+        }
+    }
+}
+
+/// Server system: counts [`StatusEffects`] down, removes those that wore
+/// off and writes the [`Damage`] of burning.  Counting down alone does not
+/// mark the component changed, so it is only replicated when an effect is
+/// applied or wears off.
+pub fn tick_status_effects(
+    time: Res<Time>,
+    mut effects: Query<(Entity, &mut StatusEffects)>,
+    mut damage: MessageWriter<Damage>,
+) {
+    let dt = time.delta_secs();
+    for (entity, mut effects) in effects.iter_mut() {
+        let burning = effects.stacks(StatusKind::Burning);
+        if burning > 0 {
+            damage.write(Damage {
+                target: entity,
+                amount: BURNING_DAMAGE_PER_SECOND * f32::from(burning) * dt,
+                kind: DamageKind::Burn,
+            });
+        }
+        if effects.bypass_change_detection().tick(dt) {
+            effects.set_changed();
+        }
+    }
+}
+
+/// Server system: sends [`ThingsStreamMessage::StatusEffectsChanged`] to the
+/// controlling client of every creature whose [`StatusEffects`] changed.
+pub(crate) fn send_status_effects(
+    sender: Res<StreamSender<ThingsStreamMessage>>,
+    changed: Query<(&NetId, &StatusEffects, &ControlledByClient), Changed<StatusEffects>>,
+) {
+    for (&net_id, effects, controller) in changed.iter() {
+        let msg = ThingsStreamMessage::StatusEffectsChanged {
+            net_id,
+            effects: effects.0.clone(),
+        };
+        if let Err(e) = sender.send_to(controller.0, &msg) {
+            error!(
+                "Failed to send StatusEffectsChanged for NetId({}) to ClientId({}): {e}",
+                net_id.0, controller.0.0
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn effects_stack_burn_and_wear_off() {
+        let mut app = App::new();
+        app.add_message::<ApplyStatus>();
+        app.add_message::<Damage>();
+        app.init_resource::<Time>();
+        app.add_systems(Update, (apply_status, tick_status_effects).chain());
+        let target = app.world_mut().spawn_empty().id();
+        let apply = |app: &mut App, kind, duration| {
+            app.world_mut().write_message(ApplyStatus {
+                target,
+                kind,
+                stacks: 2,
+                duration,
+            });
+        };
+
+        apply(&mut app, StatusKind::Slow, 1.0);
+        apply(&mut app, StatusKind::Slow, 5.0);
+        apply(&mut app, StatusKind::Burning, 2.0);
+        app.update();
+        let effects = app.world().get::<StatusEffects>(target).unwrap();
+        assert_eq!(effects.stacks(StatusKind::Slow), MAX_STATUS_STACKS);
+        assert!((effects.speed_multiplier() - SLOW_FACTOR.powi(3)).abs() < 1e-6);
+        assert!(!effects.is_stunned());
+
+        let mut effects = effects.clone();
+
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        app.update();
+        let burns: Vec<f32> = app
+            .world_mut()
+            .resource_mut::<Messages<Damage>>()
+            .drain()
+            .map(|hit| hit.amount)
+            .collect();
+        assert_eq!(burns, vec![0.0, 2.0 * BURNING_DAMAGE_PER_SECOND]);
+
+        assert!(effects.tick(2.0));
+        assert_eq!(effects.0.len(), 1);
+        assert_eq!(effects.stacks(StatusKind::Burning), 0);
+        assert!(!effects.tick(2.0));
+        assert!(effects.tick(1.0));
+        assert_eq!(effects, StatusEffects::default());
+    }
+}
@@ -0,0 +1,529 @@
+//! Generic replication of component state.
+//!
+//! A module that only needs a component's value mirrored on clients
+//! registers it with [`ReplicateComponentExt::replicate_component`] instead
+//! of writing its own stream messages, change detection and join catch-up:
+//!
+//! ```ignore
+//! app.replicate_component::<MovementState, ()>(ComponentSync::named("movement_state"));
+//! ```
+//!
+//! The server then sends [`ThingsStreamMessage::ComponentChanged`] on
+//! stream 3 whenever the component is added or changes on a replicated
+//! entity matching the filter, [`ThingsStreamMessage::ComponentRemoved`]
+//! when it is removed from one that still exists, and the current value of
+//! every such entity to joining clients and to clients that gain sight of it
+//! (see [`ReplicationVisibility`](crate::ReplicationVisibility)).  Riding on
+//! stream 3 keeps these after the `EntitySpawned` of their entity.  Clients
+//! decode the value with the [`ComponentSyncRegistry`] and insert it on the
+//! replica.
+//!
+//! Both sides must register the same components under the same names, which
+//! is the case when the registering plugin is shared by client and server.
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use bevy::ecs::query::QueryFilter;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use network::{
+    ClientId, ControlledByClient, NetId, NetworkReceive, NetworkSend, PlayerEvent, Server,
+    StreamSender,
+};
+use wincode::config::DefaultConfig;
+
+use crate::visibility::{self, ReplicationScope, SightGained};
+use crate::{ThingsSet, ThingsStreamMessage, broadcast_state};
+
+/// Which clients receive a replicated component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncAudience {
+    /// Every client the entity is replicated to.
+    Everyone,
+    /// Only the client controlling the entity, e.g. for the needs of its own
+    /// creature.  Also sent when the entity is bound to a client.
+    Controller,
+}
+
+/// How a component registered with
+/// [`ReplicateComponentExt::replicate_component`] is replicated.
+#[derive(Debug, Clone, Copy)]
+pub struct ComponentSync {
+    /// Name the component goes by on the wire, unique among synced
+    /// components.
+    pub name: &'static str,
+    /// Changes are sent at most this often; every frame when `None`.
+    pub interval: Option<Duration>,
+    pub audience: SyncAudience,
+}
+
+impl ComponentSync {
+    /// Replicates the component to every client on every change.
+    pub const fn named(name: &'static str) -> Self {
+        Self {
+            name,
+            interval: None,
+            audience: SyncAudience::Everyone,
+        }
+    }
+
+    /// Sends changes at most once per `interval`; a value changed several
+    /// times in between is only sent once.
+    pub const fn every(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Replicates the component only to the client controlling the entity.
+    pub const fn to_controller(mut self) -> Self {
+        self.audience = SyncAudience::Controller;
+        self
+    }
+}
+
+/// Wire id of a synced component: FNV-1a of its name, the same on every
+/// build.
+pub const fn component_sync_id(name: &str) -> u32 {
+    let bytes = name.as_bytes();
+    let mut hash: u32 = 0x811c_9dc5;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    hash
+}
+
+/// Client-side handlers of one synced component.
+struct SyncedComponent {
+    name: &'static str,
+    insert: fn(&mut EntityCommands, &[u8]) -> Result<(), String>,
+    remove: fn(&mut EntityCommands),
+}
+
+/// Every component registered for replication, by wire id.
+#[derive(Resource, Default)]
+pub struct ComponentSyncRegistry {
+    components: HashMap<u32, SyncedComponent>,
+}
+
+impl ComponentSyncRegistry {
+    /// Name of the component synced as `id`, if any.
+    pub fn name(&self, id: u32) -> Option<&'static str> {
+        self.components.get(&id).map(|synced| synced.name)
+    }
+
+    /// Decodes `value` as the component synced as `id` and inserts it on
+    /// `entity`.
+    pub(crate) fn insert(
+        &self,
+        entity: &mut EntityCommands,
+        id: u32,
+        value: &[u8],
+    ) -> Result<(), String> {
+        let synced = self
+            .components
+            .get(&id)
+            .ok_or_else(|| format!("unknown synced component {id:#010x}"))?;
+        (synced.insert)(entity, value).map_err(|e| format!("{}: {e}", synced.name))
+    }
+
+    /// Removes the component synced as `id` from `entity`.
+    pub(crate) fn remove(&self, entity: &mut EntityCommands, id: u32) -> Result<(), String> {
+        let synced = self
+            .components
+            .get(&id)
+            .ok_or_else(|| format!("unknown synced component {id:#010x}"))?;
+        (synced.remove)(entity);
+        Ok(())
+    }
+}
+
+/// Components that can be replicated with
+/// [`ReplicateComponentExt::replicate_component`].
+pub trait SyncComponent:
+    Component
+    + wincode::SchemaWrite<DefaultConfig, Src = Self>
+    + for<'de> wincode::SchemaRead<'de, DefaultConfig, Dst = Self>
+{
+}
+
+impl<C> SyncComponent for C where
+    C: Component
+        + wincode::SchemaWrite<DefaultConfig, Src = C>
+        + for<'de> wincode::SchemaRead<'de, DefaultConfig, Dst = C>
+{
+}
+
+pub trait ReplicateComponentExt {
+    /// Replicates component `C` of the replicated entities matching `F`
+    /// from the server to clients, as described by `sync`.
+    ///
+    /// # Panics
+    ///
+    /// If another component is already synced under the same name or id.
+    fn replicate_component<C: SyncComponent, F: QueryFilter + 'static>(
+        &mut self,
+        sync: ComponentSync,
+    ) -> &mut Self;
+}
+
+impl ReplicateComponentExt for App {
+    fn replicate_component<C: SyncComponent, F: QueryFilter + 'static>(
+        &mut self,
+        sync: ComponentSync,
+    ) -> &mut Self {
+        let id = component_sync_id(sync.name);
+        let mut registry = self
+            .world_mut()
+            .get_resource_or_init::<ComponentSyncRegistry>();
+        if let Some(existing) = registry.components.get(&id) {
+            panic!(
+                "synced component {:?} clashes with {:?} (id {id:#010x})",
+                sync.name, existing.name
+            );
+        }
+        registry.components.insert(
+            id,
+            SyncedComponent {
+                name: sync.name,
+                insert: insert_component::<C>,
+                remove: remove_component::<C>,
+            },
+        );
+
+        self.insert_resource(SyncRule::<C, F> {
+            id,
+            audience: sync.audience,
+            marker: PhantomData,
+        });
+        let send = send_component_changes::<C, F>
+            .after(broadcast_state)
+            .run_if(resource_exists::<Server>);
+        match sync.interval {
+            Some(interval) => self.add_systems(NetworkSend, send.run_if(on_timer(interval))),
+            None => self.add_systems(NetworkSend, send),
+        };
+        self.add_systems(
+            NetworkSend,
+            send_component_removals::<C, F>
+                .after(broadcast_state)
+                .run_if(resource_exists::<Server>),
+        );
+        self.add_systems(
+            NetworkReceive,
+            send_components_on_join::<C, F>
+                .run_if(resource_exists::<Server>)
+                .after(ThingsSet::HandleClientJoined)
+                .before(ThingsSet::SendStreamReady),
+        );
+        self.add_message::<SightGained>();
+        self.add_systems(
+            NetworkSend,
+            send_components_on_sight::<C, F>
+                .after(visibility::apply_visibility_changes)
+                .run_if(resource_exists::<Server>),
+        )
+    }
+}
+
+/// Wire id and audience of component `C` synced for entities matching `F`.
+#[derive(Resource)]
+struct SyncRule<C, F> {
+    id: u32,
+    audience: SyncAudience,
+    marker: PhantomData<fn() -> (C, F)>,
+}
+
+fn insert_component<C: SyncComponent>(
+    entity: &mut EntityCommands,
+    value: &[u8],
+) -> Result<(), String> {
+    let component: C = wincode::deserialize(value).map_err(|e| e.to_string())?;
+    entity.insert(component);
+    Ok(())
+}
+
+fn remove_component<C: SyncComponent>(entity: &mut EntityCommands) {
+    entity.remove::<C>();
+}
+
+/// Sends `msg` about `entity` to `audience`.
+fn send_synced(
+    sender: &StreamSender<ThingsStreamMessage>,
+    scope: &ReplicationScope,
+    audience: SyncAudience,
+    entity: Entity,
+    controller: Option<&ControlledByClient>,
+    msg: &ThingsStreamMessage,
+) -> Result<(), String> {
+    match audience {
+        SyncAudience::Everyone => scope.send(sender, &[entity], msg),
+        SyncAudience::Controller => match controller {
+            Some(controller) => sender.send_to(controller.0, msg),
+            None => Ok(()),
+        },
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Server system: sends [`ThingsStreamMessage::ComponentChanged`] for every
+/// replicated entity matching `F` whose `C` was added or changed since the
+/// last run.  Controller-only components are also sent when the entity gets
+/// a new controller.
+#[allow(clippy::type_complexity)]
+fn send_component_changes<C: SyncComponent, F: QueryFilter + 'static>(
+    rule: Res<SyncRule<C, F>>,
+    sender: Res<StreamSender<ThingsStreamMessage>>,
+    scope: ReplicationScope,
+    changed: Query<
+        (Entity, &NetId, Ref<C>, Option<Ref<ControlledByClient>>),
+        (F, Or<(Changed<C>, Changed<ControlledByClient>)>),
+    >,
+) {
+    for (entity, &net_id, component, controller) in changed.iter() {
+        let new_controller = rule.audience == SyncAudience::Controller
+            && controller.as_ref().is_some_and(|c| c.is_changed());
+        if !component.is_changed() && !new_controller {
+            continue;
+        }
+        let value = match wincode::serialize(&*component) {
+            Ok(value) => value,
+            Err(e) => {
+                error!(
+                    "Failed to encode synced component for NetId({}): {e}",
+                    net_id.0
+                );
+                continue;
+            }
+        };
+        let msg = ThingsStreamMessage::ComponentChanged {
+            net_id,
+            component: rule.id,
+            value,
+        };
+        let controller = controller.as_deref();
+        if let Err(e) = send_synced(&sender, &scope, rule.audience, entity, controller, &msg) {
+            error!(
+                "Failed to send ComponentChanged for NetId({}): {e}",
+                net_id.0
+            );
+        }
+    }
+}
+
+/// Server system: sends [`ThingsStreamMessage::ComponentRemoved`] for every
+/// replicated entity that lost its `C`.  Runs every frame whatever the
+/// interval of the component, since removals are only kept for two frames.
+fn send_component_removals<C: SyncComponent, F: QueryFilter + 'static>(
+    rule: Res<SyncRule<C, F>>,
+    sender: Res<StreamSender<ThingsStreamMessage>>,
+    scope: ReplicationScope,
+    mut removed: RemovedComponents<C>,
+    remaining: Query<(&NetId, Option<&ControlledByClient>)>,
+) {
+    for entity in removed.read() {
+        // Despawned entities are removed on clients with their replica.
+        let Ok((&net_id, controller)) = remaining.get(entity) else {
+            continue;
+        };
+        let msg = ThingsStreamMessage::ComponentRemoved {
+            net_id,
+            component: rule.id,
+        };
+        if let Err(e) = send_synced(&sender, &scope, rule.audience, entity, controller, &msg) {
+            error!(
+                "Failed to send ComponentRemoved for NetId({}): {e}",
+                net_id.0
+            );
+        }
+    }
+}
+
+/// Server system: sends the `C` of every replicated entity matching `F` to
+/// joining clients that receive it.
+///
+/// Runs between [`ThingsSet::HandleClientJoined`] and
+/// [`ThingsSet::SendStreamReady`], so the values follow the `EntitySpawned`
+/// catch-up and precede the ready signal.
+fn send_components_on_join<C: SyncComponent, F: QueryFilter + 'static>(
+    rule: Res<SyncRule<C, F>>,
+    mut player_events: MessageReader<PlayerEvent>,
+    sender: Res<StreamSender<ThingsStreamMessage>>,
+    scope: ReplicationScope,
+    entities: Query<(Entity, &NetId, &C, Option<&ControlledByClient>), F>,
+) {
+    for event in player_events.read() {
+        let PlayerEvent::Joined { id, .. } = event else {
+            continue;
+        };
+        for (entity, &net_id, component, controller) in entities.iter() {
+            let receives = match rule.audience {
+                SyncAudience::Everyone => scope.is_visible_to(entity, *id),
+                SyncAudience::Controller => controller.is_some_and(|c| c.0 == *id),
+            };
+            if receives {
+                send_current(&sender, rule.id, net_id, component, *id);
+            }
+        }
+    }
+}
+
+/// Server system: sends the `C` of replicated entities matching `F` to the
+/// clients they were just shown to by
+/// [`apply_visibility_changes`](visibility::apply_visibility_changes), which
+/// got their `EntitySpawned` but none of the changes sent while hidden.
+///
+/// Controller-only components reach their controller whatever the entity's
+/// visibility, so only those synced to everyone are resent.
+fn send_components_on_sight<C: SyncComponent, F: QueryFilter + 'static>(
+    rule: Res<SyncRule<C, F>>,
+    mut sight_gained: MessageReader<SightGained>,
+    sender: Res<StreamSender<ThingsStreamMessage>>,
+    entities: Query<(&NetId, &C), F>,
+) {
+    for &SightGained { entity, client } in sight_gained.read() {
+        if rule.audience != SyncAudience::Everyone {
+            continue;
+        }
+        if let Ok((&net_id, component)) = entities.get(entity) {
+            send_current(&sender, rule.id, net_id, component, client);
+        }
+    }
+}
+
+/// Sends `client` the current `component` of the entity `net_id` as
+/// [`ThingsStreamMessage::ComponentChanged`].
+fn send_current<C: SyncComponent>(
+    sender: &StreamSender<ThingsStreamMessage>,
+    id: u32,
+    net_id: NetId,
+    component: &C,
+    client: ClientId,
+) {
+    let msg = match wincode::serialize(component) {
+        Ok(value) => ThingsStreamMessage::ComponentChanged {
+            net_id,
+            component: id,
+            value,
+        },
+        Err(e) => {
+            error!(
+                "Failed to encode synced component for NetId({}): {e}",
+                net_id.0
+            );
+            return;
+        }
+    };
+    if let Err(e) = sender.send_to(client, &msg) {
+        error!(
+            "Failed to send ComponentChanged catch-up to ClientId({}): {e}",
+            client.0
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use network::testing::{HarnessState, NetHarness};
+    use wincode::{SchemaRead, SchemaWrite};
+
+    use super::*;
+    use crate::{MovementState, NetIdIndex, ReplicationVisibility, Thing, ThingsPlugin};
+
+    #[derive(Component, Debug, Clone, Copy, PartialEq, SchemaRead, SchemaWrite)]
+    struct Charge(u8);
+
+    #[test]
+    fn registered_components_are_decoded_onto_entities() {
+        let mut app = App::new();
+        app.replicate_component::<Charge, ()>(ComponentSync::named("charge"));
+        let id = component_sync_id("charge");
+        let entity = app.world_mut().spawn_empty().id();
+
+        let value = wincode::serialize(&Charge(7)).unwrap();
+        app.world_mut()
+            .resource_scope(|world, registry: Mut<ComponentSyncRegistry>| {
+                let mut commands = world.commands();
+                let mut entity = commands.entity(entity);
+                registry.insert(&mut entity, id, &value).unwrap();
+                assert!(
+                    registry
+                        .insert(&mut entity, id.wrapping_add(1), &value)
+                        .is_err()
+                );
+            });
+        app.world_mut().flush();
+        assert_eq!(app.world().get::<Charge>(entity), Some(&Charge(7)));
+        assert_eq!(
+            app.world().resource::<ComponentSyncRegistry>().name(id),
+            Some("charge")
+        );
+
+        app.world_mut()
+            .resource_scope(|world, registry: Mut<ComponentSyncRegistry>| {
+                registry
+                    .remove(&mut world.commands().entity(entity), id)
+                    .unwrap();
+            });
+        app.world_mut().flush();
+        assert!(app.world().get::<Charge>(entity).is_none());
+    }
+
+    #[test]
+    fn clients_that_gain_sight_receive_the_current_components() {
+        let mut net = NetHarness::loopback(|app, _side| {
+            app.add_plugins((
+                TransformPlugin,
+                bevy::asset::AssetPlugin::default(),
+                bevy::mesh::MeshPlugin,
+                bevy::scene::ScenePlugin,
+                physics::PhysicsPlugin,
+            ));
+            app.add_plugins(ThingsPlugin::in_state(HarnessState::InGame));
+            // Avian sets up its resources in `Plugin::finish`.
+            app.finish();
+        });
+        let client = net.connect();
+        net.wait_for_initial_state_done();
+
+        // Crouching is added while hidden, so the client never receives it.
+        let world = net.server.world_mut();
+        let net_id = world.resource_mut::<Server>().next_net_id();
+        let entity = world
+            .spawn((
+                net_id,
+                Thing { kind: 1 },
+                Transform::default(),
+                MovementState::Crouching,
+                ReplicationVisibility::hidden_from([client]),
+            ))
+            .id();
+        world.resource_mut::<NetIdIndex>().0.insert(net_id, entity);
+        for _ in 0..5 {
+            net.update();
+        }
+        let replica_state = |net: &mut NetHarness| {
+            let world = net.client.world_mut();
+            world
+                .query_filtered::<Option<&MovementState>, With<NetId>>()
+                .iter(world)
+                .next()
+                .map(|state| state.copied())
+        };
+        assert_eq!(replica_state(&mut net), None);
+
+        net.server
+            .world_mut()
+            .entity_mut(entity)
+            .remove::<ReplicationVisibility>();
+        net.run_until("replica to be shown", |net| replica_state(net).is_some());
+        net.run_until("movement state to follow the replica", |net| {
+            replica_state(net) == Some(Some(MovementState::Crouching))
+        });
+    }
+}
@@ -0,0 +1,79 @@
+//! Ballistic motion that clients extrapolate instead of receiving
+//! per-tick state.
+//!
+//! A thing carrying a [`Trajectory`] flies along it under its own gravity,
+//! not under physics.  The server leaves such things out of `StateUpdate`
+//! frames and sends one [`ThingsStreamMessage::TrajectoryStarted`] instead;
+//! clients then move the thing themselves with [`follow_trajectories`] until
+//! it is despawned or [`ThingsStreamMessage::TrajectoryEnded`] hands it back
+//! to regular replication.  Whoever moves the thing on the server (the
+//! `projectiles` module) calls [`Trajectory::advance`] and sends both
+//! messages.
+
+use bevy::prelude::*;
+use network::NetId;
+
+use crate::ThingsStreamMessage;
+
+/// Velocity and downward acceleration of a thing in free flight.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Trajectory {
+    pub velocity: Vec3,
+    /// Downward acceleration in m/s²; 0 for a straight line.
+    pub gravity: f32,
+}
+
+impl Trajectory {
+    /// Moves `position` along the trajectory for `dt` seconds and returns the
+    /// displacement.
+    pub fn advance(&mut self, position: &mut Vec3, dt: f32) -> Vec3 {
+        let start_velocity = self.velocity;
+        self.velocity.y -= self.gravity * dt;
+        let displacement = (start_velocity + self.velocity) * 0.5 * dt;
+        *position += displacement;
+        displacement
+    }
+
+    /// The [`ThingsStreamMessage::TrajectoryStarted`] for the thing `net_id`
+    /// at `position`.
+    pub fn started(&self, net_id: NetId, position: Vec3) -> ThingsStreamMessage {
+        ThingsStreamMessage::TrajectoryStarted {
+            net_id,
+            position: position.into(),
+            velocity: self.velocity.into(),
+            gravity: self.gravity,
+        }
+    }
+}
+
+/// Client system: moves replicated things along their [`Trajectory`].
+pub(crate) fn follow_trajectories(
+    time: Res<Time>,
+    mut flying: Query<(&mut Transform, &mut Trajectory)>,
+) {
+    let dt = time.delta_secs();
+    for (mut transform, mut trajectory) in flying.iter_mut() {
+        trajectory.advance(&mut transform.translation, dt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trajectories_fall_under_their_own_gravity() {
+        let mut trajectory = Trajectory {
+            velocity: Vec3::new(10.0, 5.0, 0.0),
+            gravity: 10.0,
+        };
+        let mut position = Vec3::ZERO;
+        for _ in 0..10 {
+            trajectory.advance(&mut position, 0.1);
+        }
+        // x = vx·t, y = vy·t − g·t²/2, exact for piecewise-constant acceleration.
+        assert!((position - Vec3::new(10.0, 0.0, 0.0)).length() < 1e-4);
+        assert!((trajectory.velocity.y + 5.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,353 @@
+//! Per-client replication visibility.
+//!
+//! Replicated entities reach every client by default.  A
+//! [`ReplicationVisibility`] component on the server restricts an entity to
+//! some clients, e.g. for stealth or observer features.  Hidden clients get
+//! no `EntitySpawned` catch-up, no `StateUpdate` entries and no item events
+//! about it.
+//!
+//! Replication code sends through [`ReplicationScope`], which broadcasts when
+//! none of the entities involved restrict visibility and otherwise sends to
+//! each client allowed to see all of them.
+//!
+//! Changing or removing the component takes effect on the next
+//! `NetworkSend`: clients that lose sight of the entity receive
+//! `EntityDespawned`, clients that gain it receive `EntitySpawned` followed by
+//! the current value of its components synced with
+//! [`replicate_component`](crate::ReplicateComponentExt::replicate_component).
+//! An entity spawned with the component already in place is introduced the same
+//! way to the clients it allows, so its spawner must not broadcast it.  Item
+//! state of a re-shown entity (held, stored, stack count) is only resent on
+//! the next item event for it.
+
+use std::collections::HashSet;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use network::{ClientId, ControlledByClient, NetId, PlayerEvent, StreamSendError, StreamSender};
+use physics::LinearVelocity;
+use wincode::config::DefaultConfig;
+
+use crate::{DespawnReason, DisplayName, Thing, ThingsStreamMessage, entity_spawned};
+
+/// Server-side: which clients an entity is replicated to.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub enum ReplicationVisibility {
+    /// Replicated to every client except these.
+    HiddenFrom(HashSet<ClientId>),
+    /// Replicated only to these clients.
+    VisibleTo(HashSet<ClientId>),
+}
+
+impl ReplicationVisibility {
+    pub fn hidden_from(clients: impl IntoIterator<Item = ClientId>) -> Self {
+        Self::HiddenFrom(clients.into_iter().collect())
+    }
+
+    pub fn visible_to(clients: impl IntoIterator<Item = ClientId>) -> Self {
+        Self::VisibleTo(clients.into_iter().collect())
+    }
+
+    pub fn is_visible_to(&self, client: ClientId) -> bool {
+        match self {
+            Self::HiddenFrom(hidden) => !hidden.contains(&client),
+            Self::VisibleTo(visible) => visible.contains(&client),
+        }
+    }
+}
+
+/// Server-side: clients that have joined and receive replication, in join
+/// order.
+#[derive(Resource, Debug, Default)]
+pub struct ReplicatedClients(Vec<ClientId>);
+
+impl ReplicatedClients {
+    pub fn iter(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+/// Visibility-aware sending for replication systems.
+#[derive(SystemParam)]
+pub struct ReplicationScope<'w, 's> {
+    clients: Res<'w, ReplicatedClients>,
+    visibility: Query<'w, 's, &'static ReplicationVisibility>,
+}
+
+impl ReplicationScope<'_, '_> {
+    /// Returns `true` if `entity` is replicated to `client`.
+    pub fn is_visible_to(&self, entity: Entity, client: ClientId) -> bool {
+        self.visibility
+            .get(entity)
+            .ok()
+            .is_none_or(|v| v.is_visible_to(client))
+    }
+
+    /// Returns `true` if `entity` has no visibility restriction.
+    pub fn is_public(&self, entity: Entity) -> bool {
+        !self.visibility.contains(entity)
+    }
+
+    /// Clients that receive replication.
+    pub fn clients(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.clients.iter()
+    }
+
+    /// Sends `msg` about `entities` to every client that may see all of them.
+    ///
+    /// Broadcasts when none of them restrict visibility.  Returns the last
+    /// send error, if any.
+    pub fn send<T>(
+        &self,
+        sender: &StreamSender<T>,
+        entities: &[Entity],
+        msg: &T,
+    ) -> Result<(), StreamSendError>
+    where
+        T: wincode::SchemaWrite<DefaultConfig, Src = T> + Send + Sync + 'static,
+    {
+        if entities.iter().all(|&e| self.is_public(e)) {
+            return sender.broadcast(msg);
+        }
+        let mut result = Ok(());
+        for client in self.clients() {
+            if entities.iter().all(|&e| self.is_visible_to(e, client))
+                && let Err(e) = sender.send_to(client, msg)
+            {
+                result = Err(e);
+            }
+        }
+        result
+    }
+}
+
+/// Server-side: `client` was just sent `EntitySpawned` for `entity` after
+/// gaining sight of it, so it still lacks the entity's synced components.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SightGained {
+    pub entity: Entity,
+    pub client: ClientId,
+}
+
+/// Visibility last applied to an entity's replicas, to diff against on
+/// change.
+#[derive(Component)]
+pub(crate) struct AppliedVisibility(ReplicationVisibility);
+
+/// Clients that lose and gain sight of an entity when its visibility goes
+/// from `before` to `after`; `None` means visible to everyone.  A `before` of
+/// `VisibleTo` an empty set describes an entity no client has seen yet.
+pub(crate) fn visibility_transitions(
+    before: Option<&ReplicationVisibility>,
+    after: Option<&ReplicationVisibility>,
+    clients: impl IntoIterator<Item = ClientId>,
+) -> (Vec<ClientId>, Vec<ClientId>) {
+    let sees = |v: Option<&ReplicationVisibility>, c| v.is_none_or(|v| v.is_visible_to(c));
+    let mut lost = Vec::new();
+    let mut gained = Vec::new();
+    for client in clients {
+        match (sees(before, client), sees(after, client)) {
+            (true, false) => lost.push(client),
+            (false, true) => gained.push(client),
+            _ => {}
+        }
+    }
+    (lost, gained)
+}
+
+/// Server system: keeps [`ReplicatedClients`] in step with joins and leaves.
+pub(crate) fn track_replicated_clients(
+    mut player_events: MessageReader<PlayerEvent>,
+    mut clients: ResMut<ReplicatedClients>,
+) {
+    for event in player_events.read() {
+        match event {
+            PlayerEvent::Joined { id, .. } => {
+                if !clients.0.contains(id) {
+                    clients.0.push(*id);
+                }
+            }
+            PlayerEvent::Left { id } => clients.0.retain(|c| c != id),
+            PlayerEvent::MovementViolation { .. } => {}
+        }
+    }
+}
+
+pub(crate) fn clear_replicated_clients(mut clients: ResMut<ReplicatedClients>) {
+    clients.0.clear();
+}
+
+type VisibilityChangeQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        Ref<'static, NetId>,
+        &'static ReplicationVisibility,
+        Option<&'static AppliedVisibility>,
+    ),
+    Changed<ReplicationVisibility>,
+>;
+
+type ReplicaQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static NetId,
+        &'static Thing,
+        &'static Transform,
+        Option<&'static LinearVelocity>,
+        Option<&'static DisplayName>,
+        Option<&'static ControlledByClient>,
+    ),
+>;
+
+/// Server system: despawns an entity's replica on clients that lost sight of
+/// it and spawns it on clients that gained sight, whenever its
+/// [`ReplicationVisibility`] is inserted, changed or removed.  Entities
+/// spawned this frame count as seen by no one.  Writes [`SightGained`] for
+/// every client the entity was shown to, so its synced components follow.
+///
+/// Runs in `NetworkSend` before `broadcast_state`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_visibility_changes(
+    mut commands: Commands,
+    changed: VisibilityChangeQuery,
+    mut removed: RemovedComponents<ReplicationVisibility>,
+    applied: Query<&AppliedVisibility>,
+    replicas: ReplicaQuery,
+    clients: Res<ReplicatedClients>,
+    sender: Res<StreamSender<ThingsStreamMessage>>,
+    mut sight_gained: MessageWriter<SightGained>,
+) {
+    let unseen = ReplicationVisibility::visible_to([]);
+    let mut transitions = Vec::new();
+    for (entity, net_id, visibility, previous) in changed.iter() {
+        let before = if net_id.is_added() {
+            Some(&unseen)
+        } else {
+            previous.map(|p| &p.0)
+        };
+        if before == Some(visibility) {
+            continue;
+        }
+        transitions.push((
+            entity,
+            visibility_transitions(before, Some(visibility), clients.iter()),
+        ));
+        commands
+            .entity(entity)
+            .insert(AppliedVisibility(visibility.clone()));
+    }
+    for entity in removed.read() {
+        let Ok(previous) = applied.get(entity) else {
+            continue;
+        };
+        transitions.push((
+            entity,
+            visibility_transitions(Some(&previous.0), None, clients.iter()),
+        ));
+        commands.entity(entity).remove::<AppliedVisibility>();
+    }
+
+    for (entity, (lost, gained)) in transitions {
+        let Ok((&net_id, thing, transform, velocity, name, controlled)) = replicas.get(entity)
+        else {
+            continue;
+        };
+        for client in lost {
+            let msg = ThingsStreamMessage::EntityDespawned {
+                net_id,
+                reason: DespawnReason::OutOfRelevancy,
+            };
+            if let Err(e) = sender.send_to(client, &msg) {
+                error!(
+                    "Failed to hide NetId({}) from ClientId({}): {e}",
+                    net_id.0, client.0
+                );
+            }
+        }
+        for client in gained {
+            let msg = ThingsStreamMessage::from(entity_spawned(
+                net_id, thing, transform, velocity, name, controlled, client,
+            ));
+            match sender.send_to(client, &msg) {
+                Ok(()) => {
+                    sight_gained.write(SightGained { entity, client });
+                }
+                Err(e) => error!(
+                    "Failed to show NetId({}) to ClientId({}): {e}",
+                    net_id.0, client.0
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies_decide_visibility_per_client() {
+        let hidden = ReplicationVisibility::hidden_from([ClientId(2)]);
+        assert!(hidden.is_visible_to(ClientId(1)));
+        assert!(!hidden.is_visible_to(ClientId(2)));
+
+        let whitelist = ReplicationVisibility::visible_to([ClientId(2)]);
+        assert!(!whitelist.is_visible_to(ClientId(1)));
+        assert!(whitelist.is_visible_to(ClientId(2)));
+    }
+
+    #[test]
+    fn transitions_list_clients_that_lose_and_gain_sight() {
+        let clients = [ClientId(1), ClientId(2), ClientId(3)];
+        let hide_2 = ReplicationVisibility::hidden_from([ClientId(2)]);
+        let only_2 = ReplicationVisibility::visible_to([ClientId(2)]);
+
+        assert_eq!(
+            visibility_transitions(None, Some(&hide_2), clients),
+            (vec![ClientId(2)], vec![])
+        );
+        assert_eq!(
+            visibility_transitions(Some(&hide_2), Some(&only_2), clients),
+            (vec![ClientId(1), ClientId(3)], vec![ClientId(2)])
+        );
+        assert_eq!(
+            visibility_transitions(Some(&only_2), None, clients),
+            (vec![], vec![ClientId(1), ClientId(3)])
+        );
+        assert_eq!(
+            visibility_transitions(
+                Some(&ReplicationVisibility::visible_to([])),
+                Some(&only_2),
+                clients
+            ),
+            (vec![], vec![ClientId(2)])
+        );
+    }
+
+    #[test]
+    fn replicated_clients_follow_joins_and_leaves() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_message::<PlayerEvent>();
+        app.init_resource::<ReplicatedClients>();
+        app.add_systems(Update, track_replicated_clients);
+
+        for id in [1, 2] {
+            app.world_mut().write_message(PlayerEvent::Joined {
+                id: ClientId(id),
+                name: String::new(),
+                spectator: false,
+            });
+        }
+        app.update();
+        app.world_mut()
+            .write_message(PlayerEvent::Left { id: ClientId(1) });
+        app.update();
+
+        let clients: Vec<_> = app.world().resource::<ReplicatedClients>().iter().collect();
+        assert_eq!(clients, [ClientId(2)]);
+    }
+}
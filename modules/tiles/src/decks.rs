@@ -0,0 +1,248 @@
+//! Decks stacked above the main tilemap.
+//!
+//! Deck 0 is the main deck, held in the [`TileGrid`] resources every module
+//! reads.  The decks above it share its size and are kept in [`UpperDecks`];
+//! each sits [`DECK_HEIGHT`] above the one below.  [`TileKind::Ladder`] cells
+//! connect a deck to the same cell on the deck above.
+//!
+//! Every entity with a [`Deck`] component has it kept in step with its
+//! height, and clients only show the deck their own creature is on: what is
+//! on other decks is marked [`OffDeck`], and [`apply_hiding`] hides it.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use network::{Client, ControlledByClient};
+
+use crate::{FloorKind, HiddenBySight, TileChunk, TileGrid, TileKind};
+
+/// Vertical distance between the floors of two neighbouring decks.
+pub const DECK_HEIGHT: f32 = 4.0;
+
+/// Index of the deck an entity or tile chunk is on, counting up from the
+/// main deck.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+#[reflect(Component, Debug, PartialEq)]
+pub struct Deck(pub u8);
+
+impl Deck {
+    /// The main deck, backed by the [`TileGrid`] resources.
+    pub const MAIN: Deck = Deck(0);
+
+    /// The deck whose space contains world height `y`.  Anything below the
+    /// main deck counts as on it.
+    pub fn of_height(y: f32) -> Deck {
+        let index = (y / DECK_HEIGHT).floor().clamp(0.0, u8::MAX as f32);
+        Deck(index as u8)
+    }
+
+    /// World height of this deck's floor surface.
+    pub fn floor_height(self) -> f32 {
+        self.0 as f32 * DECK_HEIGHT
+    }
+}
+
+/// Both tile layers of one deck above the main deck.
+#[derive(Debug, Clone)]
+pub struct DeckLayers {
+    pub structures: TileGrid<TileKind>,
+    pub floors: TileGrid<FloorKind>,
+}
+
+impl DeckLayers {
+    /// An open deck of bare plating.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            structures: TileGrid::new(width, height),
+            floors: TileGrid::new(width, height),
+        }
+    }
+}
+
+/// The decks above the main deck, bottom first: `UpperDecks.0[0]` is deck 1.
+/// Absent or empty on single-deck maps.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct UpperDecks(pub Vec<DeckLayers>);
+
+impl UpperDecks {
+    /// Layers of `deck`, or `None` for the main deck and decks that do not
+    /// exist.
+    pub fn get(&self, deck: Deck) -> Option<&DeckLayers> {
+        let index = (deck.0 as usize).checked_sub(1)?;
+        self.0.get(index)
+    }
+
+    /// Mutable counterpart of [`UpperDecks::get`].
+    pub fn get_mut(&mut self, deck: Deck) -> Option<&mut DeckLayers> {
+        let index = (deck.0 as usize).checked_sub(1)?;
+        self.0.get_mut(index)
+    }
+
+    /// Every upper deck with its index.
+    pub fn iter(&self) -> impl Iterator<Item = (Deck, &DeckLayers)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(index, layers)| (Deck(index as u8 + 1), layers))
+    }
+}
+
+/// The structure at `pos` on `deck`, looking in the main grid for deck 0.
+fn structure_on(
+    main: &TileGrid<TileKind>,
+    upper: Option<&UpperDecks>,
+    deck: Deck,
+    pos: IVec2,
+) -> Option<TileKind> {
+    if deck == Deck::MAIN {
+        main.get_copy(pos)
+    } else {
+        upper?.get(deck)?.structures.get_copy(pos)
+    }
+}
+
+/// The deck a creature at `pos` on `deck` reaches by climbing, if any: up a
+/// [`TileKind::Ladder`] it stands on, or down one whose top it stands on.
+/// Up wins when both are possible.
+pub fn climb_target(
+    main: &TileGrid<TileKind>,
+    upper: Option<&UpperDecks>,
+    deck: Deck,
+    pos: IVec2,
+) -> Option<Deck> {
+    let above = Deck(deck.0.checked_add(1)?);
+    if structure_on(main, upper, deck, pos) == Some(TileKind::Ladder)
+        && structure_on(main, upper, above, pos).is_some_and(|kind| kind.is_walkable())
+    {
+        return Some(above);
+    }
+    let below = Deck(deck.0.checked_sub(1)?);
+    (structure_on(main, upper, below, pos) == Some(TileKind::Ladder)).then_some(below)
+}
+
+/// System that keeps each entity's [`Deck`] in step with its height.
+pub(crate) fn update_entity_decks(mut entities: Query<(&Transform, &mut Deck)>) {
+    for (transform, mut deck) in entities.iter_mut() {
+        deck.set_if_neq(Deck::of_height(transform.translation.y));
+    }
+}
+
+/// Marks a tile chunk or entity on another deck than the local player's
+/// creature.  Kept up to date by [`mark_off_deck`]; [`apply_hiding`] hides
+/// what it marks.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct OffDeck;
+
+/// Client-side system that marks tile chunks and entities on other decks
+/// than the local player's creature [`OffDeck`].  Entities without a
+/// [`Deck`] are left alone.
+#[allow(clippy::type_complexity)]
+pub(crate) fn mark_off_deck(
+    mut commands: Commands,
+    client: Res<Client>,
+    players: Query<(&ControlledByClient, &Deck)>,
+    on_decks: Query<
+        (Entity, Option<&Deck>, Option<&TileChunk>, Has<OffDeck>),
+        Or<(With<Deck>, With<TileChunk>)>,
+    >,
+) {
+    let Some(local) = client.local_id else {
+        return;
+    };
+    let Some(current) = players
+        .iter()
+        .find(|(controller, _)| controller.0 == local)
+        .map(|(_, &deck)| deck)
+    else {
+        return;
+    };
+    for (entity, deck, chunk, marked) in on_decks.iter() {
+        let deck = chunk.map_or(deck.copied().unwrap_or_default(), |chunk| chunk.deck);
+        match (deck != current, marked) {
+            (true, false) => {
+                commands.entity(entity).insert(OffDeck);
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<OffDeck>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Client-side system that owns the [`Visibility`] of whatever [`OffDeck`]
+/// or [`HiddenBySight`] marks: hidden while either marker is on it, and
+/// shown again once both are gone.  Only entities whose markers changed are
+/// touched, so things hidden for other reasons stay hidden.
+///
+/// Registered in `PostUpdate` before visibility propagation, after the
+/// markers for the frame are in place.
+#[allow(clippy::type_complexity)]
+pub fn apply_hiding(
+    marked: Query<Entity, Or<(Added<OffDeck>, Added<HiddenBySight>)>>,
+    mut off_deck_removed: RemovedComponents<OffDeck>,
+    mut unseen_removed: RemovedComponents<HiddenBySight>,
+    mut visibilities: Query<(&mut Visibility, Has<OffDeck>, Has<HiddenBySight>)>,
+) {
+    let changed: HashSet<Entity> = marked
+        .iter()
+        .chain(off_deck_removed.read())
+        .chain(unseen_removed.read())
+        .collect();
+    for entity in changed {
+        let Ok((mut visibility, off_deck, unseen)) = visibilities.get_mut(entity) else {
+            continue;
+        };
+        let wanted = if off_deck || unseen {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        visibility.set_if_neq(wanted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decks_follow_height() {
+        assert_eq!(Deck::of_height(-0.5), Deck::MAIN);
+        assert_eq!(Deck::of_height(0.81), Deck::MAIN);
+        assert_eq!(Deck::of_height(DECK_HEIGHT + 0.81), Deck(1));
+        assert_eq!(Deck(2).floor_height(), 2.0 * DECK_HEIGHT);
+    }
+
+    #[test]
+    fn ladders_lead_up_and_back_down() {
+        let ladder = IVec2::new(1, 1);
+        let mut main = TileGrid::<TileKind>::new(3, 3);
+        main.set(ladder, TileKind::Ladder);
+        let mut upper = UpperDecks(vec![DeckLayers::new(3, 3)]);
+
+        assert_eq!(
+            climb_target(&main, Some(&upper), Deck::MAIN, ladder),
+            Some(Deck(1))
+        );
+        assert_eq!(
+            climb_target(&main, Some(&upper), Deck(1), ladder),
+            Some(Deck::MAIN)
+        );
+        // Nothing to climb next to the ladder, nor without a deck above.
+        assert_eq!(
+            climb_target(&main, Some(&upper), Deck::MAIN, IVec2::ZERO),
+            None
+        );
+        assert_eq!(climb_target(&main, None, Deck::MAIN, ladder), None);
+
+        // A wall on top of the ladder blocks the way up.
+        upper
+            .get_mut(Deck(1))
+            .unwrap()
+            .structures
+            .set(ladder, TileKind::Wall);
+        assert_eq!(climb_target(&main, Some(&upper), Deck::MAIN, ladder), None);
+    }
+}
@@ -0,0 +1,139 @@
+//! Server-side history of recent tile edits, for undoing them.
+//!
+//! Every structure or floor change made through gameplay is recorded in
+//! [`TileHistory`] with the client that made it, when, and what the cell
+//! held before.  A [`TileRollbackRequest`] reverts the most recent edits,
+//! either everyone's (cleaning up after griefing) or one client's (undo).
+//! The history is bounded by [`TILE_HISTORY_LIMIT`]; older edits are
+//! forgotten and can no longer be reverted.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use network::ClientId;
+
+use crate::LayerTile;
+
+/// Most edits [`TileHistory`] remembers.
+pub const TILE_HISTORY_LIMIT: usize = 256;
+
+/// One recorded change to a single layer of a cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileEdit {
+    pub position: IVec2,
+    /// What the changed layer held before the edit.
+    pub previous: LayerTile,
+    /// The client whose creature made the edit, if any.
+    pub by: Option<ClientId>,
+    /// Server time of the edit, in seconds since startup.
+    pub at: f64,
+}
+
+/// Recent tile edits, oldest first.  Server only; reset with every map.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct TileHistory {
+    edits: VecDeque<TileEdit>,
+}
+
+impl TileHistory {
+    /// Remembers `edit`, forgetting the oldest one once more than
+    /// [`TILE_HISTORY_LIMIT`] are held.
+    pub fn record(&mut self, edit: TileEdit) {
+        if self.edits.len() == TILE_HISTORY_LIMIT {
+            self.edits.pop_front();
+        }
+        self.edits.push_back(edit);
+    }
+
+    /// Number of edits remembered.
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    /// Whether no edits are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Every remembered edit, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &TileEdit> + '_ {
+        self.edits.iter()
+    }
+
+    /// Removes and returns up to `count` of the most recent edits, newest
+    /// first, or only those made by `by` if given.  Reverting them in the
+    /// returned order restores the cells as they were before.
+    pub fn take_recent(&mut self, count: usize, by: Option<ClientId>) -> Vec<TileEdit> {
+        let mut taken = Vec::new();
+        let mut index = self.edits.len();
+        while index > 0 && taken.len() < count {
+            index -= 1;
+            if by.is_none() || self.edits[index].by == by {
+                taken.extend(self.edits.remove(index));
+            }
+        }
+        taken
+    }
+
+    /// Forgets every edit.
+    pub fn clear(&mut self) {
+        self.edits.clear();
+    }
+}
+
+/// Server-side request to revert the `count` most recent edits in
+/// [`TileHistory`], or only those made by `by` if given.  Applied by the
+/// interactions module, which broadcasts the restored tiles.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct TileRollbackRequest {
+    pub count: usize,
+    pub by: Option<ClientId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloorKind, TileKind};
+
+    fn edit(x: i32, by: Option<ClientId>) -> TileEdit {
+        TileEdit {
+            position: IVec2::new(x, 0),
+            previous: LayerTile::Structure(TileKind::Floor),
+            by,
+            at: x as f64,
+        }
+    }
+
+    #[test]
+    fn history_is_bounded_and_forgets_the_oldest_edits() {
+        let mut history = TileHistory::default();
+        for x in 0..TILE_HISTORY_LIMIT as i32 + 2 {
+            history.record(edit(x, None));
+        }
+        assert_eq!(history.len(), TILE_HISTORY_LIMIT);
+        assert_eq!(history.iter().next().unwrap().position, IVec2::new(2, 0));
+    }
+
+    #[test]
+    fn take_recent_returns_newest_first_and_filters_by_client() {
+        let alice = Some(ClientId(1));
+        let bob = Some(ClientId(2));
+        let mut history = TileHistory::default();
+        history.record(edit(0, alice));
+        history.record(edit(1, bob));
+        history.record(TileEdit {
+            previous: LayerTile::Floor(FloorKind::Carpet),
+            ..edit(2, alice)
+        });
+        history.record(edit(3, bob));
+
+        let undone = history.take_recent(5, alice);
+        let positions: Vec<i32> = undone.iter().map(|e| e.position.x).collect();
+        assert_eq!(positions, vec![2, 0]);
+        assert_eq!(undone[0].previous, LayerTile::Floor(FloorKind::Carpet));
+
+        let rolled_back = history.take_recent(1, None);
+        assert_eq!(rolled_back[0].position.x, 3);
+        assert_eq!(history.len(), 1);
+    }
+}
@@ -1,23 +1,37 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use base64::Engine as _;
+use bevy::camera::visibility::VisibilitySystems;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bitflags::bitflags;
-use input::{PointerAction, WorldHit};
+use input::{PointerAction, ViewCamera, WorldHit};
 use network::{
-    ClientId, Headless, ModuleReadySent, NetworkReceive, PlayerEvent, Server, StreamDef,
-    StreamDirection, StreamReader, StreamRegistry, StreamSender,
+    Client, ClientId, ControlledByClient, FullRelevancy, Headless, ModuleReadySent, NetworkReceive,
+    PlayerEvent, Server, StreamDef, StreamDirection, StreamReader, StreamRegistry, StreamSender,
 };
-use physics::{Collider, RigidBody};
+use physics::{Collider, GameLayer, RigidBody};
 use serde::{Deserialize, Serialize};
 use wincode::{SchemaRead, SchemaWrite};
 use world::{MapLayer, MapLayerRegistryExt, from_layer_value, to_layer_value};
 
+mod decks;
+pub use decks::{DECK_HEIGHT, Deck, DeckLayers, OffDeck, UpperDecks, apply_hiding, climb_target};
+mod history;
+pub use history::{TILE_HISTORY_LIMIT, TileEdit, TileHistory, TileRollbackRequest};
+mod vision;
+pub use vision::{FieldOfView, HiddenBySight};
+mod zones;
+pub use zones::{Zone, ZoneId, ZoneIndex, ZoneRenameRequest};
+
 /// System set for the tiles module's server-side lifecycle systems.
 /// Other modules can use this for explicit ordering relative to tiles systems.
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TilesSet {
-    /// Sends the full tile grid snapshot and [`StreamReady`] sentinel to a joining client.
+    /// Sends the tilemap header to a joining client and subscribes it to chunk
+    /// streaming.  The [`StreamReady`] sentinel follows from [`stream_tile_chunks`]
+    /// once the chunks around the client's controlled entity (or around the
+    /// [`LobbyStreamAnchors`], for a client without one) have been sent.
     ///
     /// Runs in `PreUpdate` so that ordering constraints against other modules'
     /// on-connect sends (e.g. [`things::ThingsSet::HandleClientJoined`]) can be
@@ -44,21 +58,122 @@ pub enum TilesSet {
 pub enum TileKind {
     Floor,
     Wall,
+    /// A door that blocks movement and gas while closed and behaves like
+    /// floor while open.
+    Door {
+        open: bool,
+    },
+    /// A hull breach: the cell is open to space and vents into it.  Left
+    /// behind when a structure on the hull is removed.
+    Space,
+    /// A ladder up to the same cell on the deck above.  Walkable; creatures
+    /// climb it with an interaction (see [`climb_target`]).
+    Ladder,
+}
+
+/// Floor covering stored on the floor layer of a cell.
+///
+/// Independent of the structure layer ([`TileKind`]), so building a wall or
+/// door on a cell keeps the floor underneath it.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Reflect,
+    SchemaRead,
+    SchemaWrite,
+    Serialize,
+    Deserialize,
+)]
+#[reflect(Debug, PartialEq)]
+pub enum FloorKind {
+    #[default]
+    Plating,
+    Carpet,
+}
+
+impl FloorKind {
+    pub fn is_plating(&self) -> bool {
+        *self == FloorKind::Plating
+    }
+}
+
+/// Layer of a cell.  Each layer is stored in its own [`TileGrid<T>`] and
+/// rendered by its own tile entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Debug, PartialEq)]
+pub enum TileLayer {
+    /// Floor covering, backed by [`TileGrid<FloorKind>`].  Every cell has one.
+    Floor,
+    /// Walls, doors and other structures, backed by [`TileGrid<TileKind>`].
+    /// [`TileKind::Floor`] means the cell has no structure.
+    Structure,
 }
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
 #[reflect(Component, Debug, PartialEq)]
 pub struct Tile {
     pub position: IVec2,
+    pub layer: TileLayer,
 }
 
 impl TileKind {
     pub fn is_walkable(&self) -> bool {
         match self {
-            TileKind::Floor => true,
+            TileKind::Floor | TileKind::Space | TileKind::Ladder => true,
+            TileKind::Wall => false,
+            TileKind::Door { open } => *open,
+        }
+    }
+
+    /// Whether gas can flow through this tile.  Closed doors seal like walls.
+    pub fn is_gas_passable(&self) -> bool {
+        match self {
+            TileKind::Floor | TileKind::Space | TileKind::Ladder => true,
+            TileKind::Wall => false,
+            TileKind::Door { open } => *open,
+        }
+    }
+
+    /// Whether light shines through this tile.  Closed doors block it like
+    /// walls.
+    pub fn is_light_passable(&self) -> bool {
+        match self {
+            TileKind::Floor | TileKind::Space | TileKind::Ladder => true,
             TileKind::Wall => false,
+            TileKind::Door { open } => *open,
         }
     }
+
+    /// Whether this tile is open to space, an endless sink for gas.
+    pub fn is_vacuum(&self) -> bool {
+        *self == TileKind::Space
+    }
+
+    /// Derives the [`TileFlag`] bitmask for this tile kind.
+    pub fn flags(&self) -> TileFlag {
+        let mut flag = TileFlag::empty();
+        flag.set(TileFlag::WALKABLE, self.is_walkable());
+        flag.set(TileFlag::GAS_PASS, self.is_gas_passable());
+        flag.set(TileFlag::LIGHT_PASS, self.is_light_passable());
+        flag.set(TileFlag::VACUUM, self.is_vacuum());
+        flag
+    }
+
+    /// Whether this tile is a door (open or closed).
+    pub fn is_door(&self) -> bool {
+        matches!(self, TileKind::Door { .. })
+    }
+
+    /// Whether the cell holds no structure, so one can be built on it.
+    /// Hull breaches count as free: building there patches them.
+    pub fn is_free(&self) -> bool {
+        matches!(self, TileKind::Floor | TileKind::Space)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -77,6 +192,12 @@ impl TileData for TileKind {
     }
 }
 
+impl TileData for FloorKind {
+    fn empty() -> Self {
+        FloorKind::Plating
+    }
+}
+
 /// Shared grid dimensions.  All [`TileGrid<T>`] resources must match.
 #[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
 #[reflect(Debug, Resource)]
@@ -180,6 +301,16 @@ impl<T: TileData> TileGrid<T> {
     pub fn cells(&self) -> &[T] {
         &self.cells
     }
+
+    /// Whether `pos` is on the hull: the outermost ring of cells, with open
+    /// space beyond.
+    pub fn is_hull(&self, pos: IVec2) -> bool {
+        self.coord_to_index(pos).is_some()
+            && (pos.x == 0
+                || pos.y == 0
+                || pos.x == self.width as i32 - 1
+                || pos.y == self.height as i32 - 1)
+    }
 }
 
 impl<T: TileData + Copy> TileGrid<T> {
@@ -189,6 +320,42 @@ impl<T: TileData + Copy> TileGrid<T> {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Tilemap — layered view over the per-layer grids
+// ---------------------------------------------------------------------------
+
+/// Contents of a single layer of a cell, as returned by [`Tilemap::get_layer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerTile {
+    Floor(FloorKind),
+    Structure(TileKind),
+}
+
+/// Read-only view over every tile layer.
+///
+/// Each layer lives in its own [`TileGrid<T>`] resource; this parameter lets
+/// callers address a cell by [`TileLayer`] without knowing which grid backs it.
+#[derive(SystemParam)]
+pub struct Tilemap<'w> {
+    floors: Option<Res<'w, TileGrid<FloorKind>>>,
+    structures: Option<Res<'w, TileGrid<TileKind>>>,
+}
+
+impl Tilemap<'_> {
+    /// Returns the contents of `layer` at `pos`, or `None` if the layer's grid
+    /// does not exist yet or `pos` is out of bounds.
+    pub fn get_layer(&self, pos: IVec2, layer: TileLayer) -> Option<LayerTile> {
+        match layer {
+            TileLayer::Floor => self.floors.as_ref()?.get_copy(pos).map(LayerTile::Floor),
+            TileLayer::Structure => self
+                .structures
+                .as_ref()?
+                .get_copy(pos)
+                .map(LayerTile::Structure),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // TileFlags — derived bitmask cache
 // ---------------------------------------------------------------------------
@@ -198,6 +365,8 @@ bitflags! {
     pub struct TileFlag: u8 {
         const WALKABLE = 0b0001;
         const GAS_PASS = 0b0010;
+        const LIGHT_PASS = 0b0100;
+        const VACUUM = 0b1000;
     }
 }
 
@@ -223,6 +392,15 @@ impl TileFlags {
         }
     }
 
+    /// Flags of every cell of `grid`, e.g. of an upper deck's structures.
+    pub fn from_grid(grid: &TileGrid<TileKind>) -> Self {
+        let mut flags = Self::new(grid.width(), grid.height());
+        for (pos, kind) in grid.iter() {
+            flags.set(pos, kind.flags());
+        }
+        flags
+    }
+
     fn coord_to_index(&self, pos: IVec2) -> Option<usize> {
         if pos.x >= 0 && pos.x < self.width as i32 && pos.y >= 0 && pos.y < self.height as i32 {
             Some((pos.y * self.width as i32 + pos.x) as usize)
@@ -251,6 +429,15 @@ impl TileFlags {
             .is_some_and(|f| f.contains(TileFlag::GAS_PASS))
     }
 
+    pub fn is_light_passable(&self, pos: IVec2) -> bool {
+        self.get(pos)
+            .is_some_and(|f| f.contains(TileFlag::LIGHT_PASS))
+    }
+
+    pub fn is_vacuum(&self, pos: IVec2) -> bool {
+        self.get(pos).is_some_and(|f| f.contains(TileFlag::VACUUM))
+    }
+
     pub fn width(&self) -> u32 {
         self.width
     }
@@ -274,15 +461,7 @@ fn rebuild_tile_flags(
         return;
     }
 
-    let mut flags = TileFlags::new(grid.width(), grid.height());
-    for (pos, kind) in grid.iter() {
-        let flag = match kind {
-            TileKind::Floor => TileFlag::WALKABLE | TileFlag::GAS_PASS,
-            TileKind::Wall => TileFlag::empty(),
-        };
-        flags.set(pos, flag);
-    }
-    commands.insert_resource(flags);
+    commands.insert_resource(TileFlags::from_grid(&grid));
 }
 
 // ---------------------------------------------------------------------------
@@ -303,12 +482,20 @@ pub struct TilesLayerData {
     /// Each chunk stores `chunk_size * chunk_size` u16 values in
     /// row-major order (y * chunk_size + x), encoded as little-endian bytes.
     pub chunks: BTreeMap<(i32, i32), String>,
+    /// Chunks of the decks above the main deck, bottom first, encoded like
+    /// `chunks` with the same keys.  Omitted from single-deck maps.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decks: Vec<BTreeMap<(i32, i32), String>>,
 }
 
 /// Per-tile configuration stored in the key dictionary.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TileDef {
+    /// Structure layer.
     pub kind: TileKind,
+    /// Floor layer.  Omitted from the file when it is the default plating.
+    #[serde(default, skip_serializing_if = "FloorKind::is_plating")]
+    pub floor: FloorKind,
 }
 
 // ---------------------------------------------------------------------------
@@ -320,9 +507,11 @@ const SAVE_CHUNK_SIZE: u32 = 32;
 
 /// `MapLayer` implementation for the `"tiles"` layer.
 ///
-/// **load**: Decodes key-dictionary + base64 chunks → inserts [`TileGrid<TileKind>`]
-/// and [`GridSize`] resources.
-/// **save**: Reads [`TileGrid<TileKind>`] resource → builds key-dictionary + base64 chunks.
+/// **load**: Decodes key-dictionary + base64 chunks → inserts [`TileGrid<TileKind>`],
+/// [`TileGrid<FloorKind>`] and [`GridSize`] resources, plus an empty [`TileHistory`].
+/// Loaded over a running world, it first despawns the old tile entities and
+/// drops the [`ZoneIndex`].
+/// **save**: Reads both layer grids → builds key-dictionary + base64 chunks.
 pub struct TilesLayer;
 
 impl MapLayer for TilesLayer {
@@ -352,11 +541,16 @@ impl MapLayer for TilesLayer {
 
         // Handle the empty-map case before doing any chunk work.
         if layer.chunks.is_empty() {
+            despawn_tile_entities_world(world);
+            world.remove_resource::<ZoneIndex>();
             world.insert_resource(GridSize {
                 width: 0,
                 height: 0,
             });
             world.insert_resource(TileGrid::<TileKind>::new(0, 0));
+            world.insert_resource(TileGrid::<FloorKind>::new(0, 0));
+            world.insert_resource(UpperDecks::default());
+            world.insert_resource(TileHistory::default());
             return Ok(());
         }
 
@@ -399,54 +593,48 @@ impl MapLayer for TilesLayer {
         let _ = total_tiles;
 
         let mut grid = TileGrid::<TileKind>::new_fill(width, height, TileKind::Floor);
-
-        // Decode each chunk and write directly into the grid.
-        for (&(chunk_x, chunk_y), b64) in &layer.chunks {
-            let bytes = base64::engine::general_purpose::STANDARD
-                .decode(b64)
-                .map_err(|e| {
-                    format!("tiles layer: chunk ({chunk_x},{chunk_y}) base64 decode failed: {e}")
-                })?;
-
-            if bytes.len() != expected_bytes {
-                return Err(format!(
-                    "tiles layer: chunk ({chunk_x},{chunk_y}) has {} bytes, expected {expected_bytes} \
-                     (chunk_size={chunk_size})",
-                    bytes.len()
-                )
-                .into());
-            }
-
-            for local_y in 0..chunk_size {
-                for local_x in 0..chunk_size {
-                    let offset = (local_y as usize * chunk_size as usize + local_x as usize) * 2;
-                    let key = u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
-
-                    let tile_def = layer.keys.get(&key).ok_or_else(|| {
-                        format!(
-                            "tiles layer: chunk ({chunk_x},{chunk_y}) references unknown key {key}"
-                        )
-                    })?;
-
-                    let global_x = chunk_x * chunk_size as i32 + local_x as i32;
-                    let global_y = chunk_y * chunk_size as i32 + local_y as i32;
-                    let pos = IVec2::new(global_x, global_y);
-                    grid.set(pos, tile_def.kind);
-                }
-            }
+        let mut floors = TileGrid::<FloorKind>::new(width, height);
+        decode_chunks(
+            &layer.keys,
+            &layer.chunks,
+            chunk_size,
+            expected_bytes,
+            &mut grid,
+            &mut floors,
+        )?;
+
+        // Upper decks have the size of the main deck; chunks beyond it are
+        // dropped.
+        let mut upper = UpperDecks::default();
+        for chunks in &layer.decks {
+            let mut deck = DeckLayers::new(width, height);
+            decode_chunks(
+                &layer.keys,
+                chunks,
+                chunk_size,
+                expected_bytes,
+                &mut deck.structures,
+                &mut deck.floors,
+            )?;
+            upper.0.push(deck);
         }
 
+        // A map loaded again over a running world replaces the old tiles, and
+        // its zones are detected afresh.
+        despawn_tile_entities_world(world);
+        world.remove_resource::<ZoneIndex>();
         world.insert_resource(GridSize { width, height });
         world.insert_resource(grid);
+        world.insert_resource(floors);
+        world.insert_resource(upper);
+        world.insert_resource(TileHistory::default());
 
         // Spawn tile entities immediately so colliders exist before the first
         // physics step.  Later map layers (spawns) create dynamic bodies that
         // would fall through the floor if colliders were deferred to Update.
-        if world.contains_resource::<Headless>() {
-            spawn_tile_colliders_world(world);
-        } else {
-            spawn_tile_entities_world(world);
-        }
+        // Worlds without render assets (headless server, tests) only need
+        // colliders.
+        spawn_tile_entities_world(world);
 
         Ok(())
     }
@@ -454,6 +642,9 @@ impl MapLayer for TilesLayer {
     fn unload(&self, world: &mut World) {
         world.remove_resource::<GridSize>();
         world.remove_resource::<TileGrid<TileKind>>();
+        world.remove_resource::<TileGrid<FloorKind>>();
+        world.remove_resource::<UpperDecks>();
+        world.remove_resource::<TileHistory>();
     }
 
     fn save(
@@ -463,85 +654,202 @@ impl MapLayer for TilesLayer {
         let grid = world
             .get_resource::<TileGrid<TileKind>>()
             .ok_or("tiles layer: TileGrid<TileKind> resource not found")?;
-
-        let chunk_size = SAVE_CHUNK_SIZE;
+        // A missing floor grid saves as bare plating.
+        let floors = world.get_resource::<TileGrid<FloorKind>>();
 
         if grid.width() > i32::MAX as u32 || grid.height() > i32::MAX as u32 {
             return Err("tiles layer: map dimensions exceed i32 range and cannot be saved".into());
         }
 
-        let num_chunks_x = grid.width().div_ceil(chunk_size);
-        let num_chunks_y = grid.height().div_ceil(chunk_size);
-        let tiles_per_chunk = chunk_size as usize * chunk_size as usize;
-
-        let mut key_dict: BTreeMap<u16, TileDef> = BTreeMap::new();
-        let mut def_to_key: HashMap<TileDef, u16> = HashMap::new();
-        // Use u32 so we can represent values 0..=65536 without wrapping,
-        // allowing all 65536 valid u16 keys (0..=65535) to be assigned.
-        let mut next_key: u32 = 0;
-        let mut chunks: BTreeMap<(i32, i32), String> = BTreeMap::new();
-
-        // Single pass: assign keys on first occurrence and encode each chunk in one loop.
-        for chunk_y in 0..num_chunks_y {
-            for chunk_x in 0..num_chunks_x {
-                let mut buf: Vec<u8> = Vec::with_capacity(tiles_per_chunk * 2);
-                for local_y in 0..chunk_size {
-                    for local_x in 0..chunk_size {
-                        let pos = IVec2::new(
-                            chunk_x as i32 * chunk_size as i32 + local_x as i32,
-                            chunk_y as i32 * chunk_size as i32 + local_y as i32,
-                        );
-                        // Pad out-of-bounds positions with Floor (the grid default fill).
-                        let kind = grid.get_copy(pos).unwrap_or(TileKind::Floor);
-                        let def = TileDef { kind };
-                        let key = match def_to_key.entry(def.clone()) {
-                            std::collections::hash_map::Entry::Occupied(e) => *e.get(),
-                            std::collections::hash_map::Entry::Vacant(e) => {
-                                if next_key > u16::MAX as u32 {
-                                    return Err(
-                                        "tiles layer: too many unique tile configurations (limit: 65536)"
-                                            .into(),
-                                    );
-                                }
-                                let k = next_key as u16;
-                                next_key += 1;
-                                key_dict.insert(k, def);
-                                *e.insert(k)
-                            }
-                        };
-                        buf.extend_from_slice(&key.to_le_bytes());
-                    }
-                }
-                chunks.insert(
-                    (chunk_x as i32, chunk_y as i32),
-                    base64::engine::general_purpose::STANDARD.encode(&buf),
-                );
-            }
-        }
+        let mut keys = TileKeys::default();
+        let chunks = encode_chunks(grid, floors, &mut keys)?;
+        let decks = world
+            .get_resource::<UpperDecks>()
+            .map(|upper| {
+                upper
+                    .0
+                    .iter()
+                    .map(|deck| encode_chunks(&deck.structures, Some(&deck.floors), &mut keys))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
 
         Ok(to_layer_value(&TilesLayerData {
-            chunk_size,
-            keys: key_dict,
+            chunk_size: SAVE_CHUNK_SIZE,
+            keys: keys.dict,
             chunks,
+            decks,
         })?)
     }
 }
 
+/// Decodes base64 `chunks` of [`TilesLayerData`] into the layer grids of one
+/// deck.
+fn decode_chunks(
+    keys: &BTreeMap<u16, TileDef>,
+    chunks: &BTreeMap<(i32, i32), String>,
+    chunk_size: u32,
+    expected_bytes: usize,
+    grid: &mut TileGrid<TileKind>,
+    floors: &mut TileGrid<FloorKind>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    for (&(chunk_x, chunk_y), b64) in chunks {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(b64)
+            .map_err(|e| {
+                format!("tiles layer: chunk ({chunk_x},{chunk_y}) base64 decode failed: {e}")
+            })?;
+
+        if bytes.len() != expected_bytes {
+            return Err(format!(
+                "tiles layer: chunk ({chunk_x},{chunk_y}) has {} bytes, expected {expected_bytes} \
+                 (chunk_size={chunk_size})",
+                bytes.len()
+            )
+            .into());
+        }
+
+        for local_y in 0..chunk_size {
+            for local_x in 0..chunk_size {
+                let offset = (local_y as usize * chunk_size as usize + local_x as usize) * 2;
+                let key = u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+
+                let tile_def = keys.get(&key).ok_or_else(|| {
+                    format!("tiles layer: chunk ({chunk_x},{chunk_y}) references unknown key {key}")
+                })?;
+
+                let global_x = chunk_x * chunk_size as i32 + local_x as i32;
+                let global_y = chunk_y * chunk_size as i32 + local_y as i32;
+                let pos = IVec2::new(global_x, global_y);
+                grid.set(pos, tile_def.kind);
+                floors.set(pos, tile_def.floor);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Key dictionary built while saving, shared by every deck of the map.
+#[derive(Default)]
+struct TileKeys {
+    dict: BTreeMap<u16, TileDef>,
+    lookup: HashMap<TileDef, u16>,
+    // Use u32 so we can represent values 0..=65536 without wrapping,
+    // allowing all 65536 valid u16 keys (0..=65535) to be assigned.
+    next: u32,
+}
+
+impl TileKeys {
+    /// Key of `def`, assigned on first occurrence.
+    fn key(&mut self, def: TileDef) -> Result<u16, Box<dyn std::error::Error + Send + Sync>> {
+        match self.lookup.entry(def.clone()) {
+            std::collections::hash_map::Entry::Occupied(e) => Ok(*e.get()),
+            std::collections::hash_map::Entry::Vacant(e) => {
+                if self.next > u16::MAX as u32 {
+                    return Err(
+                        "tiles layer: too many unique tile configurations (limit: 65536)".into(),
+                    );
+                }
+                let k = self.next as u16;
+                self.next += 1;
+                self.dict.insert(k, def);
+                Ok(*e.insert(k))
+            }
+        }
+    }
+}
+
+/// Encodes the layer grids of one deck into base64 chunks of
+/// [`SAVE_CHUNK_SIZE`], assigning keys in `keys` on first occurrence.
+fn encode_chunks(
+    grid: &TileGrid<TileKind>,
+    floors: Option<&TileGrid<FloorKind>>,
+    keys: &mut TileKeys,
+) -> Result<BTreeMap<(i32, i32), String>, Box<dyn std::error::Error + Send + Sync>> {
+    let chunk_size = SAVE_CHUNK_SIZE;
+    let num_chunks_x = grid.width().div_ceil(chunk_size);
+    let num_chunks_y = grid.height().div_ceil(chunk_size);
+    let tiles_per_chunk = chunk_size as usize * chunk_size as usize;
+    let mut chunks: BTreeMap<(i32, i32), String> = BTreeMap::new();
+
+    for chunk_y in 0..num_chunks_y {
+        for chunk_x in 0..num_chunks_x {
+            let mut buf: Vec<u8> = Vec::with_capacity(tiles_per_chunk * 2);
+            for local_y in 0..chunk_size {
+                for local_x in 0..chunk_size {
+                    let pos = IVec2::new(
+                        chunk_x as i32 * chunk_size as i32 + local_x as i32,
+                        chunk_y as i32 * chunk_size as i32 + local_y as i32,
+                    );
+                    // Pad out-of-bounds positions with Floor (the grid default fill).
+                    let kind = grid.get_copy(pos).unwrap_or(TileKind::Floor);
+                    let floor = floors.and_then(|f| f.get_copy(pos)).unwrap_or_default();
+                    let key = keys.key(TileDef { kind, floor })?;
+                    buf.extend_from_slice(&key.to_le_bytes());
+                }
+            }
+            chunks.insert(
+                (chunk_x as i32, chunk_y as i32),
+                base64::engine::general_purpose::STANDARD.encode(&buf),
+            );
+        }
+    }
+    Ok(chunks)
+}
+
 /// Wire format for stream 1 (server→client tiles stream).
 #[derive(Debug, Clone, SchemaRead, SchemaWrite)]
 pub enum TilesStreamMessage {
-    /// Full tilemap snapshot sent once on connect.
-    TilemapData {
+    /// Map header sent once on connect.  Cell contents follow as
+    /// [`TilesStreamMessage::ChunkData`] for the chunks near the client.
+    TilemapInfo {
         width: u32,
         height: u32,
+        chunk_size: u32,
+    },
+    /// Contents of one chunk, sent the first time it comes within
+    /// [`CHUNK_STREAM_RADIUS`] of the client's controlled entity.
+    ///
+    /// Cells are row-major and clipped to the map bounds (see
+    /// [`TileGrid::chunk_cells`]).  `tiles` is the structure layer; `floors` is
+    /// the floor layer, or empty when every cell in the chunk is
+    /// [`FloorKind::Plating`].
+    ChunkData {
+        chunk: [i32; 2],
         tiles: Vec<TileKind>,
+        floors: Vec<FloorKind>,
     },
-    /// Incremental mutation broadcast to all clients after the server applies a toggle.
+    /// Incremental structure-layer mutation broadcast to all clients after the
+    /// server applies a toggle.
     TileMutated { position: [i32; 2], kind: TileKind },
+    /// Incremental floor-layer mutation broadcast to all clients.
+    FloorMutated {
+        position: [i32; 2],
+        floor: FloorKind,
+    },
+    /// Both layers of an upper deck (see [`UpperDecks`]), sent whole after
+    /// the header.  Upper decks are static, so no mutations follow.
+    DeckData {
+        deck: u8,
+        tiles: Vec<TileKind>,
+        floors: Vec<FloorKind>,
+    },
+    /// The whole [`ZoneIndex`], sent after the header and again whenever
+    /// the rooms change.  `runs` covers the cells row-major as
+    /// `[count, zone id]` pairs, id 0 meaning no zone.
+    Zones {
+        width: u32,
+        height: u32,
+        zones: Vec<Zone>,
+        runs: Vec<[u32; 2]>,
+    },
+    /// A zone was renamed.
+    ZoneRenamed { zone: ZoneId, name: String },
 }
 
 /// Bevy event fired when a tile mutation arrives from the server (or is applied locally
-/// on a listen-server). Consumed by [`apply_tile_mutation`] to update the visual
+/// by the server or the editor). Consumed by [`apply_tile_mutation`] to update the visual
 /// representation incrementally.
 #[derive(Message, Debug, Clone, Copy)]
 pub struct TileMutated {
@@ -549,55 +857,133 @@ pub struct TileMutated {
     pub kind: TileKind,
 }
 
+/// Floor-layer counterpart of [`TileMutated`], consumed by
+/// [`apply_tile_mutation`] to rebuild the floor entity of a single cell.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct FloorMutated {
+    pub position: IVec2,
+    pub floor: FloorKind,
+}
+
 /// Stream tag for the server→client tiles stream (stream 1).
 pub const TILES_STREAM_TAG: u8 = 1;
 
+/// Edge length, in tiles, of the chunks the map is streamed and rendered in.
+pub const TILE_CHUNK_SIZE: u32 = 32;
+
+/// Chebyshev radius, in chunks, around a client's controlled entity within
+/// which the server streams chunk contents.
+pub const CHUNK_STREAM_RADIUS: i32 = 2;
+
+/// Chebyshev radius, in chunks, around the camera within which a client keeps
+/// tile entities spawned.  Matches [`CHUNK_STREAM_RADIUS`] so every chunk the
+/// server streams is also rendered.
+pub const CHUNK_VIEW_RADIUS: i32 = CHUNK_STREAM_RADIUS;
+
 /// Decode a [`TilesStreamMessage`] from raw stream-frame bytes.
 pub fn decode_tiles_message(bytes: &[u8]) -> Result<TilesStreamMessage, String> {
     wincode::deserialize(bytes).map_err(|e| e.to_string())
 }
 
-impl From<&TileGrid<TileKind>> for TilesStreamMessage {
-    fn from(grid: &TileGrid<TileKind>) -> Self {
-        TilesStreamMessage::TilemapData {
-            width: grid.width,
-            height: grid.height,
-            tiles: grid.cells.clone(),
-        }
-    }
+/// Returns the coordinate of the chunk containing tile `pos`.
+pub fn chunk_of(pos: IVec2, chunk_size: u32) -> IVec2 {
+    pos.div_euclid(IVec2::splat(chunk_size as i32))
 }
 
-impl TryFrom<TilesStreamMessage> for TileGrid<TileKind> {
-    type Error = String;
+/// Returns the chunk coordinates within Chebyshev distance `radius` of `center`.
+pub fn chunks_in_radius(center: IVec2, radius: i32) -> impl Iterator<Item = IVec2> {
+    (-radius..=radius)
+        .flat_map(move |dy| (-radius..=radius).map(move |dx| center + IVec2::new(dx, dy)))
+}
 
-    fn try_from(msg: TilesStreamMessage) -> Result<Self, Self::Error> {
-        match msg {
-            TilesStreamMessage::TilemapData {
-                width,
-                height,
-                tiles,
-            } => TileGrid::from_cells(width, height, tiles),
-            TilesStreamMessage::TileMutated { .. } => {
-                Err("TileMutated is not a full tilemap snapshot".to_string())
-            }
-        }
+impl<T: TileData> TileGrid<T> {
+    /// Tile bounds of `chunk` clipped to the grid as `(min, max_exclusive)`, or
+    /// `None` if the chunk lies entirely outside the grid.
+    fn chunk_bounds(&self, chunk: IVec2, chunk_size: u32) -> Option<(IVec2, IVec2)> {
+        let size = chunk_size as i32;
+        let min = chunk * size;
+        let max = (min + IVec2::splat(size)).min(IVec2::new(self.width as i32, self.height as i32));
+        (min.x >= 0 && min.y >= 0 && min.x < max.x && min.y < max.y).then_some((min, max))
+    }
+
+    /// Returns the coordinates of every chunk overlapping the grid, row by row.
+    pub fn chunks(&self, chunk_size: u32) -> impl Iterator<Item = IVec2> + use<T> {
+        let chunks_x = self.width.div_ceil(chunk_size) as i32;
+        let chunks_y = self.height.div_ceil(chunk_size) as i32;
+        (0..chunks_y).flat_map(move |cy| (0..chunks_x).map(move |cx| IVec2::new(cx, cy)))
     }
-}
 
-impl TileGrid<TileKind> {
-    /// Serialize the grid to bytes using the `TilesStreamMessage::TilemapData` wire format.
-    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
-        wincode::serialize(&TilesStreamMessage::from(self)).map_err(|e| {
-            format!(
-                "Failed to serialize TileGrid ({}×{}): {e}",
+    /// Whether `chunk` overlaps the grid.
+    pub fn contains_chunk(&self, chunk: IVec2, chunk_size: u32) -> bool {
+        self.chunk_bounds(chunk, chunk_size).is_some()
+    }
+
+    /// Returns the positions covered by `chunk`, clipped to the grid bounds,
+    /// in row-major order.
+    pub fn chunk_positions(&self, chunk: IVec2, chunk_size: u32) -> Vec<IVec2> {
+        let Some((min, max)) = self.chunk_bounds(chunk, chunk_size) else {
+            return Vec::new();
+        };
+        (min.y..max.y)
+            .flat_map(|y| (min.x..max.x).map(move |x| IVec2::new(x, y)))
+            .collect()
+    }
+
+    /// Copies the cells of `chunk` in the order of [`TileGrid::chunk_positions`].
+    pub fn chunk_cells(&self, chunk: IVec2, chunk_size: u32) -> Vec<T> {
+        self.chunk_positions(chunk, chunk_size)
+            .into_iter()
+            .filter_map(|pos| self.get(pos).cloned())
+            .collect()
+    }
+
+    /// Writes cells produced by [`TileGrid::chunk_cells`] back into `chunk`,
+    /// validating the length against the clipped chunk bounds.
+    pub fn set_chunk_cells(
+        &mut self,
+        chunk: IVec2,
+        chunk_size: u32,
+        cells: Vec<T>,
+    ) -> Result<(), String> {
+        let positions = self.chunk_positions(chunk, chunk_size);
+        if positions.is_empty() {
+            return Err(format!(
+                "chunk {chunk:?} lies outside the {}×{} grid",
                 self.width, self.height
-            )
-        })
+            ));
+        }
+        if positions.len() != cells.len() {
+            return Err(format!(
+                "chunk {chunk:?} cell data length mismatch: expected {}, got {}",
+                positions.len(),
+                cells.len()
+            ));
+        }
+        for (pos, cell) in positions.into_iter().zip(cells) {
+            self.set(pos, cell);
+        }
+        Ok(())
     }
+}
 
-    /// Deserialize a grid from bytes produced by [`TileGrid::to_bytes`].
-    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
-        decode_tiles_message(bytes).and_then(TileGrid::try_from)
+impl TilesStreamMessage {
+    /// Builds the [`TilesStreamMessage::ChunkData`] for `chunk` from both layer
+    /// grids.  A missing floor grid is sent as all plating.
+    pub fn chunk_data(
+        structures: &TileGrid<TileKind>,
+        floors: Option<&TileGrid<FloorKind>>,
+        chunk: IVec2,
+        chunk_size: u32,
+    ) -> Self {
+        let floors = floors
+            .map(|f| f.chunk_cells(chunk, chunk_size))
+            .filter(|cells| cells.iter().any(|floor| !floor.is_plating()))
+            .unwrap_or_default();
+        TilesStreamMessage::ChunkData {
+            chunk: [chunk.x, chunk.y],
+            tiles: structures.chunk_cells(chunk, chunk_size),
+            floors,
+        }
     }
 }
 
@@ -615,11 +1001,20 @@ impl<S: States + Copy> Plugin for TilesPlugin<S> {
     fn build(&self, app: &mut App) {
         let state = self.state;
         app.register_type::<TileKind>();
+        app.register_type::<FloorKind>();
+        app.register_type::<TileLayer>();
         app.register_map_layer(TilesLayer);
         app.register_type::<GridSize>();
         app.register_type::<Tile>();
+        app.register_type::<TileChunk>();
+        app.register_type::<Deck>();
+        app.register_type::<OffDeck>();
+        app.register_type::<HiddenBySight>();
 
         app.add_message::<TileMutated>();
+        app.add_message::<FloorMutated>();
+        app.add_message::<TileRollbackRequest>();
+        app.add_message::<ZoneRenameRequest>();
 
         // Register messages that raycast_tiles read/write
         // so the resources exist even when InputPlugin is not added (e.g. headless tests).
@@ -631,34 +1026,62 @@ impl<S: States + Copy> Plugin for TilesPlugin<S> {
 
         let headless = app.world().contains_resource::<Headless>();
         if !headless {
-            // Visual client / listen-server: tile meshes used by TilesLayer::load
-            // (listen-server), handle_tiles_stream (client), and apply_tile_mutation.
+            // Visual client / editor: tile meshes used by TilesLayer::load
+            // (editor), handle_tiles_stream (client), and apply_tile_mutation.
+            // Without them (dedicated server) tile entities get colliders only.
             app.init_resource::<TileMeshes>();
-            // On a listen-server, TileMutated events are written by dispatch_interaction
-            // (interactions module, Update schedule).  Running apply_tile_mutation in PostUpdate
-            // guarantees it executes after dispatch_interaction has written the events.
+            app.add_systems(Update, raycast_tiles);
             app.add_systems(
-                PostUpdate,
-                apply_tile_mutation.run_if(resource_exists::<Server>),
+                Update,
+                update_visible_chunks
+                    .after(apply_tile_mutation)
+                    .run_if(resource_exists::<Client>),
             );
-            // On a dedicated client, TileMutated events come from handle_tiles_stream
-            // (PreUpdate), so no intra-Update ordering is needed.
             app.add_systems(
                 Update,
-                apply_tile_mutation.run_if(not(resource_exists::<Server>)),
+                decks::mark_off_deck
+                    .after(decks::update_entity_decks)
+                    .run_if(resource_exists::<Client>),
+            );
+            app.add_systems(
+                PostUpdate,
+                apply_hiding.before(VisibilitySystems::VisibilityPropagate),
             );
-            app.add_systems(Update, raycast_tiles);
         }
+        app.add_systems(Update, decks::update_entity_decks);
+        // On a server, mutation events are written by dispatch_interaction
+        // (interactions module, Update schedule).  Running apply_tile_mutation in PostUpdate
+        // guarantees it executes after dispatch_interaction has written the events.
+        app.add_systems(
+            PostUpdate,
+            apply_tile_mutation.run_if(resource_exists::<Server>),
+        );
+        app.add_systems(
+            PostUpdate,
+            (zones::update_zones, zones::apply_zone_renames)
+                .chain()
+                .after(apply_tile_mutation)
+                .run_if(resource_exists::<Server>),
+        );
+        // On a client, mutation events come from handle_tiles_stream
+        // (PreUpdate), so no intra-Update ordering is needed.  The editor has
+        // neither `Client` nor `Server` and paints through the same system.
+        app.add_systems(
+            Update,
+            apply_tile_mutation.run_if(not(resource_exists::<Server>)),
+        );
 
         app.add_systems(
             NetworkReceive,
-            handle_tiles_stream.run_if(not(resource_exists::<Server>)),
+            handle_tiles_stream.run_if(resource_exists::<Client>),
         );
         // Runs in NetworkReceive (after Drain) so PlayerEvent::Joined is
         // readable.  If the TileGrid resource is not yet available (e.g.
         // listen-server: setup_world hasn't run yet) the client is queued in
         // PendingTilesSyncs and retried each frame.
         app.init_resource::<PendingTilesSyncs>();
+        app.init_resource::<ChunkSubscriptions>();
+        app.init_resource::<LobbyStreamAnchors>();
         app.configure_sets(NetworkReceive, TilesSet::SendOnConnect);
         app.add_systems(
             NetworkReceive,
@@ -666,6 +1089,13 @@ impl<S: States + Copy> Plugin for TilesPlugin<S> {
                 .run_if(resource_exists::<Server>)
                 .in_set(TilesSet::SendOnConnect),
         );
+        // Chunk contents follow the header as controlled entities move.
+        app.add_systems(
+            Update,
+            (resend_tilemap_on_reload, stream_tile_chunks)
+                .chain()
+                .run_if(resource_exists::<Server>),
+        );
 
         // Register streams. Requires NetworkPlugin to be added first.
         let mut registry = app.world_mut().get_resource_mut::<StreamRegistry>().expect(
@@ -686,212 +1116,756 @@ impl<S: States + Copy> Plugin for TilesPlugin<S> {
     }
 }
 
-fn cleanup_tiles(mut commands: Commands, tiles: Query<Entity, With<Tile>>) {
-    for entity in &tiles {
+fn cleanup_tiles(
+    mut commands: Commands,
+    tiles: Query<Entity, With<Tile>>,
+    tile_chunks: Query<Entity, With<TileChunk>>,
+    mut subscriptions: ResMut<ChunkSubscriptions>,
+) {
+    for entity in tiles.iter().chain(&tile_chunks) {
         commands.entity(entity).despawn();
     }
+    subscriptions.0.clear();
+    commands.remove_resource::<ClientTileChunks>();
     commands.remove_resource::<TileGrid<TileKind>>();
+    commands.remove_resource::<TileGrid<FloorKind>>();
     commands.remove_resource::<GridSize>();
     commands.remove_resource::<TileFlags>();
+    commands.remove_resource::<UpperDecks>();
+    commands.remove_resource::<TileHistory>();
+    commands.remove_resource::<ZoneIndex>();
 }
 
 #[derive(Resource)]
 struct TileMeshes {
-    floor_mesh: Handle<Mesh>,
-    wall_mesh: Handle<Mesh>,
-    floor_material: Handle<StandardMaterial>,
+    /// CPU-side templates copied into the merged chunk meshes.
+    floor_mesh: Mesh,
+    wall_mesh: Mesh,
+    plating_material: Handle<StandardMaterial>,
+    carpet_material: Handle<StandardMaterial>,
     wall_material: Handle<StandardMaterial>,
+    door_material: Handle<StandardMaterial>,
+    space_material: Handle<StandardMaterial>,
+    ladder_material: Handle<StandardMaterial>,
+}
+
+/// Mesh template used to render a tile layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TileShape {
+    /// Unit plane facing +Y.
+    Plane,
+    /// Unit cube.
+    Cube,
+}
+
+/// Mesh template and material of a visible tile.
+type TileVisual = (TileShape, Handle<StandardMaterial>);
+
+impl TileMeshes {
+    /// Mesh and material used to render the floor layer of a cell.
+    fn floor_visual(&self, floor: FloorKind) -> TileVisual {
+        let material = match floor {
+            FloorKind::Plating => &self.plating_material,
+            FloorKind::Carpet => &self.carpet_material,
+        };
+        (TileShape::Plane, material.clone())
+    }
+
+    /// Mesh and material used to render the structure layer of a cell, or
+    /// `None` for [`TileKind::Floor`] (no structure).
+    ///
+    /// Closed doors reuse the wall cube and open doors the floor plane, both
+    /// tinted with the door material so they stand out from the surrounding
+    /// structure.
+    fn structure_visual(&self, kind: TileKind) -> Option<TileVisual> {
+        match kind {
+            TileKind::Floor => None,
+            TileKind::Wall => Some((TileShape::Cube, self.wall_material.clone())),
+            TileKind::Door { open: false } => Some((TileShape::Cube, self.door_material.clone())),
+            TileKind::Door { open: true } => Some((TileShape::Plane, self.door_material.clone())),
+            TileKind::Space => Some((TileShape::Plane, self.space_material.clone())),
+            TileKind::Ladder => Some((TileShape::Plane, self.ladder_material.clone())),
+        }
+    }
+
+    fn template(&self, shape: TileShape) -> &Mesh {
+        match shape {
+            TileShape::Plane => &self.floor_mesh,
+            TileShape::Cube => &self.wall_mesh,
+        }
+    }
 }
 
 impl FromWorld for TileMeshes {
     fn from_world(world: &mut World) -> Self {
-        let mut meshes = world.resource_mut::<Assets<Mesh>>();
-        let floor_mesh = meshes.add(Plane3d::new(Vec3::Y, Vec2::splat(0.5)));
-        let wall_mesh = meshes.add(Cuboid::new(1.0, 1.0, 1.0));
+        let floor_mesh = Mesh::from(Plane3d::new(Vec3::Y, Vec2::splat(0.5)));
+        let wall_mesh = Mesh::from(Cuboid::new(1.0, 1.0, 1.0));
 
         let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
-        // Dark grey for floors, lighter grey for walls
-        let floor_material = materials.add(StandardMaterial {
+        // Dark grey for plating, dull red for carpet, lighter grey for walls,
+        // amber for doors, near-black for hull breaches, yellow for ladders
+        let plating_material = materials.add(StandardMaterial {
             base_color: Color::srgb(0.3, 0.3, 0.3),
             ..default()
         });
+        let carpet_material = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.45, 0.15, 0.15),
+            ..default()
+        });
         let wall_material = materials.add(StandardMaterial {
             base_color: Color::srgb(0.6, 0.6, 0.6),
             ..default()
         });
+        let door_material = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.75, 0.55, 0.2),
+            ..default()
+        });
+        let space_material = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.02, 0.02, 0.05),
+            ..default()
+        });
+        let ladder_material = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.85, 0.75, 0.1),
+            ..default()
+        });
 
         Self {
             floor_mesh,
             wall_mesh,
-            floor_material,
+            plating_material,
+            carpet_material,
             wall_material,
+            door_material,
+            space_material,
+            ladder_material,
         }
     }
 }
 
-/// Spawns a single tile entity for the given grid position and kind.
-/// Used by [`spawn_tile_entities_world`] (initial load on listen-server),
-/// [`handle_tiles_stream`] (initial load on client), and
-/// [`apply_tile_mutation`] (incremental updates) to guarantee identical
-/// visual and physics setup.
-fn spawn_tile_entity(
-    commands: &mut Commands,
-    position: IVec2,
-    kind: TileKind,
-    tile_meshes: &TileMeshes,
-) {
+/// Merged render meshes and compound collider for every cell of one chunk.
+///
+/// Per-cell [`Tile`] entities still exist as lightweight interaction targets,
+/// but carry no mesh or collider of their own; each chunk is drawn with one
+/// mesh per material and collides as a single static body.  Meshes are
+/// attached as children, only when [`TileMeshes`] exists.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component, Debug, PartialEq)]
+pub struct TileChunk {
+    pub chunk: IVec2,
+    pub deck: Deck,
+}
+
+/// Placement and physics for a single tile.  The [`Tile`] entity gets the
+/// transform; the collider is folded into its chunk's compound collider.
+struct TileSpawn {
+    tile: Tile,
+    transform: Transform,
+    collider: Option<Collider>,
+}
+
+/// Floor-layer tile at `position`: a thin slab whose top surface sits at
+/// y = 0.  Every cell has one, so walking is supported regardless of structure.
+fn floor_spawn(position: IVec2) -> TileSpawn {
+    // Collider is 0.1 tall (full dim), centered on transform.
+    // Offset y by -0.05 so the top surface sits at y=0.0.
+    TileSpawn {
+        tile: Tile {
+            position,
+            layer: TileLayer::Floor,
+        },
+        transform: Transform::from_xyz(position.x as f32, -0.05, position.y as f32),
+        collider: Some(Collider::cuboid(1.0, 0.1, 1.0)),
+    }
+}
+
+/// Structure-layer tile at `position`, or `None` when the cell has no
+/// structure.  Blocking structures (walls, closed doors) get a full 1×1×1
+/// cube; walkable ones (open doors, hull breaches) are visual only and rest on
+/// the floor slab.
+fn structure_spawn(position: IVec2, kind: TileKind) -> Option<TileSpawn> {
+    if kind == TileKind::Floor {
+        return None;
+    }
+    let tile = Tile {
+        position,
+        layer: TileLayer::Structure,
+    };
     let world_x = position.x as f32;
     let world_z = position.y as f32;
-    match kind {
-        TileKind::Floor => {
-            // Collider is 0.1 tall (full dim), centered on transform.
-            // Offset y by -0.05 so the top surface sits at y=0.0.
-            commands.spawn((
-                Mesh3d(tile_meshes.floor_mesh.clone()),
-                MeshMaterial3d(tile_meshes.floor_material.clone()),
-                Transform::from_xyz(world_x, -0.05, world_z),
-                Tile { position },
-                RigidBody::Static,
-                Collider::cuboid(1.0, 0.1, 1.0),
-            ));
+    Some(if kind.is_walkable() {
+        // Sit just above the floor plane to avoid z-fighting.
+        TileSpawn {
+            tile,
+            transform: Transform::from_xyz(world_x, -0.04, world_z),
+            collider: None,
         }
-        TileKind::Wall => {
-            commands.spawn((
-                Mesh3d(tile_meshes.wall_mesh.clone()),
-                MeshMaterial3d(tile_meshes.wall_material.clone()),
-                Transform::from_xyz(world_x, 0.5, world_z),
-                Tile { position },
-                RigidBody::Static,
-                // avian3d Collider::cuboid takes full dimensions, not half-extents
-                Collider::cuboid(1.0, 1.0, 1.0),
-            ));
+    } else {
+        // avian3d Collider::cuboid takes full dimensions, not half-extents
+        TileSpawn {
+            tile,
+            transform: Transform::from_xyz(world_x, 0.5, world_z),
+            collider: Some(Collider::cuboid(1.0, 1.0, 1.0)),
         }
-    }
+    })
 }
 
-/// Spawns tile collider entities (no meshes) directly via `&mut World`.
-///
-/// Called from [`TilesLayer::load`] on a headless server so colliders exist
-/// before later map layers spawn dynamic bodies.
-fn spawn_tile_colliders_world(world: &mut World) {
-    let grid = world.resource::<TileGrid<TileKind>>();
-    let spawns: Vec<_> = grid
-        .iter()
-        .map(|(pos, kind)| {
-            let world_x = pos.x as f32;
-            let world_z = pos.y as f32;
-            match kind {
-                TileKind::Floor => (
-                    Transform::from_xyz(world_x, -0.05, world_z),
-                    Tile { position: pos },
-                    RigidBody::Static,
-                    Collider::cuboid(1.0, 0.1, 1.0),
-                ),
-                TileKind::Wall => (
-                    Transform::from_xyz(world_x, 0.5, world_z),
-                    Tile { position: pos },
-                    RigidBody::Static,
-                    Collider::cuboid(1.0, 1.0, 1.0),
-                ),
-            }
-        })
-        .collect();
-    for bundle in spawns {
-        world.spawn(bundle);
+/// Spawns the [`Tile`] entity for one layer of a cell.  Used by
+/// [`apply_tile_mutation`] and [`spawn_chunk_tiles`]; [`spawn_tile_entities_world`]
+/// shares [`floor_spawn`] and [`structure_spawn`] to guarantee identical setup.
+fn spawn_tile_entity(commands: &mut Commands, spawn: TileSpawn) {
+    commands.spawn((spawn.transform, spawn.tile));
+}
+
+/// Spawns the floor entity and, if present, the structure entity of every
+/// cell in `chunk`.
+fn spawn_chunk_tiles(
+    commands: &mut Commands,
+    grid: &TileGrid<TileKind>,
+    chunk: IVec2,
+    chunk_size: u32,
+) {
+    for pos in grid.chunk_positions(chunk, chunk_size) {
+        spawn_tile_entity(commands, floor_spawn(pos));
+        let kind = grid.get_copy(pos).unwrap_or(TileKind::Floor);
+        if let Some(spawn) = structure_spawn(pos, kind) {
+            spawn_tile_entity(commands, spawn);
+        }
     }
 }
 
-/// Spawns full tile entities (mesh + collider) directly via `&mut World`.
-///
-/// Called from [`TilesLayer::load`] on a listen-server (visual + physics).
-fn spawn_tile_entities_world(world: &mut World) {
-    let grid = world.resource::<TileGrid<TileKind>>();
-    let tile_meshes = world.resource::<TileMeshes>();
-    let spawns: Vec<_> = grid
-        .iter()
-        .map(|(pos, kind)| {
-            let world_x = pos.x as f32;
-            let world_z = pos.y as f32;
-            match kind {
-                TileKind::Floor => (
-                    Mesh3d(tile_meshes.floor_mesh.clone()),
-                    MeshMaterial3d(tile_meshes.floor_material.clone()),
-                    Transform::from_xyz(world_x, -0.05, world_z),
-                    Tile { position: pos },
-                    RigidBody::Static,
-                    Collider::cuboid(1.0, 0.1, 1.0),
-                ),
-                TileKind::Wall => (
-                    Mesh3d(tile_meshes.wall_mesh.clone()),
-                    MeshMaterial3d(tile_meshes.wall_material.clone()),
-                    Transform::from_xyz(world_x, 0.5, world_z),
-                    Tile { position: pos },
-                    RigidBody::Static,
-                    Collider::cuboid(1.0, 1.0, 1.0),
-                ),
+/// Contents of a [`TileChunk`] entity, built by [`build_chunk`].
+struct ChunkBuild {
+    chunk: IVec2,
+    deck: Deck,
+    collider: Collider,
+    /// One merged mesh per material, in world space.  Empty without
+    /// [`TileMeshes`].
+    meshes: Vec<(Mesh, Handle<StandardMaterial>)>,
+}
+
+/// Merges the colliders and meshes of every cell in `chunk` of `deck` into a
+/// single [`ChunkBuild`], or returns `None` if the chunk lies outside the grid.
+fn build_chunk(
+    grid: &TileGrid<TileKind>,
+    floors: Option<&TileGrid<FloorKind>>,
+    deck: Deck,
+    chunk: IVec2,
+    chunk_size: u32,
+    tile_meshes: Option<&TileMeshes>,
+) -> Option<ChunkBuild> {
+    let positions = grid.chunk_positions(chunk, chunk_size);
+    if positions.is_empty() {
+        return None;
+    }
+
+    let mut shapes: Vec<(Vec3, Quat, Collider)> = Vec::new();
+    let mut meshes: Vec<(Mesh, Handle<StandardMaterial>)> = Vec::new();
+    let mut add = |mut spawn: TileSpawn, visual: Option<TileVisual>| {
+        spawn.transform.translation.y += deck.floor_height();
+        if let Some(collider) = spawn.collider {
+            shapes.push((spawn.transform.translation, Quat::IDENTITY, collider));
+        }
+        let (Some(tile_meshes), Some((shape, material))) = (tile_meshes, visual) else {
+            return;
+        };
+        let mesh = tile_meshes
+            .template(shape)
+            .clone()
+            .transformed_by(spawn.transform);
+        match meshes.iter_mut().find(|(_, m)| *m == material) {
+            Some((merged, _)) => {
+                if let Err(e) = merged.merge(&mesh) {
+                    error!("Failed to merge tile mesh into chunk {chunk:?}: {e}");
+                }
             }
-        })
-        .collect();
-    for bundle in spawns {
-        world.spawn(bundle);
+            None => meshes.push((mesh, material)),
+        }
+    };
+
+    for pos in positions {
+        let kind = grid.get_copy(pos).unwrap_or(TileKind::Floor);
+        let floor = floors.and_then(|f| f.get_copy(pos)).unwrap_or_default();
+        add(floor_spawn(pos), tile_meshes.map(|m| m.floor_visual(floor)));
+        if let Some(spawn) = structure_spawn(pos, kind) {
+            add(spawn, tile_meshes.and_then(|m| m.structure_visual(kind)));
+        }
     }
+
+    Some(ChunkBuild {
+        chunk,
+        deck,
+        collider: Collider::compound(shapes),
+        meshes,
+    })
 }
 
-/// Bevy system that handles incoming tile grid messages from the server on stream 1.
-/// Drains [`StreamReader<TilesStreamMessage>`], explicitly matches on each variant:
-/// - [`TilesStreamMessage::TilemapData`]: validates dimensions via [`TryFrom`] and
-///   inserts the [`TileGrid<TileKind>`] + [`GridSize`] resources (initial full snapshot).
-/// - [`TilesStreamMessage::TileMutated`]: applies the set for the affected cell and
-///   fires a [`TileMutated`] Bevy event so [`apply_tile_mutation`] can update the
-///   visual representation incrementally.
-fn handle_tiles_stream(
-    mut commands: Commands,
+/// Components of a [`TileChunk`] entity.  It sits at the origin so the
+/// world-space collider shapes and meshes need no offset.
+fn chunk_bundle(chunk: IVec2, deck: Deck, collider: Collider) -> impl Bundle {
+    (
+        TileChunk { chunk, deck },
+        Transform::default(),
+        Visibility::default(),
+        RigidBody::Static,
+        collider,
+        GameLayer::tile(),
+    )
+}
+
+/// Spawns a [`TileChunk`] entity with one child per merged mesh.
+fn spawn_chunk_entity(
+    commands: &mut Commands,
+    build: ChunkBuild,
+    mesh_assets: Option<&mut Assets<Mesh>>,
+) {
+    let mut entity = commands.spawn(chunk_bundle(build.chunk, build.deck, build.collider));
+    let Some(mesh_assets) = mesh_assets else {
+        return;
+    };
+    entity.with_children(|parent| {
+        for (mesh, material) in build.meshes {
+            parent.spawn((Mesh3d(mesh_assets.add(mesh)), MeshMaterial3d(material)));
+        }
+    });
+}
+
+/// Despawns the [`TileChunk`] entity for `chunk` of the main deck (if any),
+/// along with its mesh children.
+fn despawn_chunk_entity(
+    commands: &mut Commands,
+    chunk_query: &Query<(Entity, &TileChunk)>,
+    chunk: IVec2,
+) {
+    for (entity, tile_chunk) in chunk_query {
+        if tile_chunk.chunk == chunk && tile_chunk.deck == Deck::MAIN {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Despawns every [`Tile`] and [`TileChunk`] entity, meshes included, via
+/// `&mut World`.
+fn despawn_tile_entities_world(world: &mut World) {
+    let mut query = world.query_filtered::<Entity, Or<(With<Tile>, With<TileChunk>)>>();
+    let entities: Vec<Entity> = query.iter(world).collect();
+    for entity in entities {
+        if let Ok(entity) = world.get_entity_mut(entity) {
+            entity.despawn();
+        }
+    }
+}
+
+/// Spawns tile and chunk entities for both layers directly via `&mut World`,
+/// plus the chunk entities of every [`UpperDecks`] deck.  Only the main deck
+/// gets per-cell [`Tile`] entities.
+///
+/// Called from [`TilesLayer::load`] so colliders exist before later map
+/// layers spawn dynamic bodies.  Meshes are attached only when
+/// [`TileMeshes`] exists (editor).
+fn spawn_tile_entities_world(world: &mut World) {
+    let grid = world.resource::<TileGrid<TileKind>>();
+    let floors = world.get_resource::<TileGrid<FloorKind>>();
+    let upper = world.get_resource::<UpperDecks>();
+    let tile_meshes = world.get_resource::<TileMeshes>();
+    let mut tiles: Vec<TileSpawn> = Vec::new();
+    for (pos, &kind) in grid.iter() {
+        tiles.push(floor_spawn(pos));
+        tiles.extend(structure_spawn(pos, kind));
+    }
+    let mut builds: Vec<ChunkBuild> = grid
+        .chunks(TILE_CHUNK_SIZE)
+        .filter_map(|chunk| {
+            build_chunk(
+                grid,
+                floors,
+                Deck::MAIN,
+                chunk,
+                TILE_CHUNK_SIZE,
+                tile_meshes,
+            )
+        })
+        .collect();
+    for (deck, layers) in upper.iter().flat_map(|upper| upper.iter()) {
+        builds.extend(deck_chunks(layers, deck, tile_meshes));
+    }
+
+    for spawn in tiles {
+        world.spawn((spawn.transform, spawn.tile));
+    }
+    for build in builds {
+        let meshes: Vec<(Handle<Mesh>, Handle<StandardMaterial>)> =
+            match world.get_resource_mut::<Assets<Mesh>>() {
+                Some(mut assets) => build
+                    .meshes
+                    .into_iter()
+                    .map(|(mesh, material)| (assets.add(mesh), material))
+                    .collect(),
+                None => Vec::new(),
+            };
+        world
+            .spawn(chunk_bundle(build.chunk, build.deck, build.collider))
+            .with_children(|parent| {
+                for (mesh, material) in meshes {
+                    parent.spawn((Mesh3d(mesh), MeshMaterial3d(material)));
+                }
+            });
+    }
+}
+
+/// Builds every chunk of the upper deck `deck`.
+fn deck_chunks(
+    layers: &DeckLayers,
+    deck: Deck,
+    tile_meshes: Option<&TileMeshes>,
+) -> Vec<ChunkBuild> {
+    layers
+        .structures
+        .chunks(TILE_CHUNK_SIZE)
+        .filter_map(|chunk| {
+            build_chunk(
+                &layers.structures,
+                Some(&layers.floors),
+                deck,
+                chunk,
+                TILE_CHUNK_SIZE,
+                tile_meshes,
+            )
+        })
+        .collect()
+}
+
+/// Client-side chunk streaming state, inserted when the
+/// [`TilesStreamMessage::TilemapInfo`] header arrives.
+#[derive(Resource, Debug)]
+pub struct ClientTileChunks {
+    chunk_size: u32,
+    /// Chunks whose contents have arrived and are present in the layer grids.
+    received: HashSet<IVec2>,
+    /// Chunks whose tile entities are currently spawned.
+    spawned: HashSet<IVec2>,
+}
+
+impl ClientTileChunks {
+    fn new(chunk_size: u32) -> Self {
+        Self {
+            chunk_size,
+            received: HashSet::new(),
+            spawned: HashSet::new(),
+        }
+    }
+
+    /// Whether the tile entities for the chunk containing `pos` are spawned.
+    fn is_spawned(&self, pos: IVec2) -> bool {
+        self.spawned.contains(&chunk_of(pos, self.chunk_size))
+    }
+
+    /// Edge length, in tiles, of the chunks announced by the header.
+    pub fn chunk_size(&self) -> u32 {
+        self.chunk_size
+    }
+
+    /// Chunks whose contents have arrived.
+    pub fn received(&self) -> &HashSet<IVec2> {
+        &self.received
+    }
+}
+
+/// Writes a [`TilesStreamMessage::ChunkData`] payload into both layer grids.
+fn apply_chunk_data(
+    grid: &mut TileGrid<TileKind>,
+    floors: &mut TileGrid<FloorKind>,
+    chunk: IVec2,
+    chunk_size: u32,
+    tiles: Vec<TileKind>,
+    chunk_floors: Vec<FloorKind>,
+) -> Result<(), String> {
+    let cell_count = tiles.len();
+    grid.set_chunk_cells(chunk, chunk_size, tiles)?;
+    let chunk_floors = if chunk_floors.is_empty() {
+        vec![FloorKind::Plating; cell_count]
+    } else {
+        chunk_floors
+    };
+    floors
+        .set_chunk_cells(chunk, chunk_size, chunk_floors)
+        .map_err(|e| format!("floor layer: {e}"))
+}
+
+/// Bevy system that handles incoming tile grid messages from the server on stream 1.
+/// Drains [`StreamReader<TilesStreamMessage>`], explicitly matches on each variant:
+/// - [`TilesStreamMessage::TilemapInfo`]: inserts empty [`TileGrid<TileKind>`],
+///   [`TileGrid<FloorKind>`] + [`GridSize`] resources sized to the map, and
+///   resets the received-chunk bookkeeping.  Tile entities of a map already in
+///   place are despawned.
+/// - [`TilesStreamMessage::ChunkData`]: validates and writes the chunk into
+///   both layer grids.  Tile entities are spawned later by
+///   [`update_visible_chunks`] once the chunk is near the camera.
+/// - [`TilesStreamMessage::TileMutated`] / [`TilesStreamMessage::FloorMutated`]:
+///   applies the set for the affected cell and layer and fires a [`TileMutated`] /
+///   [`FloorMutated`] Bevy event so [`apply_tile_mutation`] can update the
+///   visual representation incrementally.
+/// - [`TilesStreamMessage::DeckData`]: stores the deck in [`UpperDecks`] and
+///   spawns all of its chunk entities at once.
+/// - [`TilesStreamMessage::Zones`] / [`TilesStreamMessage::ZoneRenamed`]:
+///   replaces or updates the [`ZoneIndex`].
+#[allow(clippy::too_many_arguments)]
+fn handle_tiles_stream(
+    mut commands: Commands,
     mut reader: ResMut<StreamReader<TilesStreamMessage>>,
     mut grid: Option<ResMut<TileGrid<TileKind>>>,
-    mut mutation_events: MessageWriter<TileMutated>,
+    mut floors: Option<ResMut<TileGrid<FloorKind>>>,
+    mut chunks: Option<ResMut<ClientTileChunks>>,
+    mut upper: Option<ResMut<UpperDecks>>,
+    mut zone_index: Option<ResMut<ZoneIndex>>,
     tile_meshes: Option<Res<TileMeshes>>,
+    mut mesh_assets: Option<ResMut<Assets<Mesh>>>,
+    mut mutation_events: MessageWriter<TileMutated>,
+    mut floor_events: MessageWriter<FloorMutated>,
+    tiles: Query<Entity, With<Tile>>,
+    tile_chunks: Query<Entity, With<TileChunk>>,
 ) {
+    // The header and the first chunks usually arrive in the same frame, before
+    // the header's resources exist; stage them locally until the end of the drain.
+    let mut fresh: Option<(TileGrid<TileKind>, TileGrid<FloorKind>, ClientTileChunks)> = None;
+    let mut received_decks: Vec<(Deck, DeckLayers)> = Vec::new();
+    let mut received_zones: Option<ZoneIndex> = None;
+
     for msg in reader.drain() {
         match msg {
-            variant @ TilesStreamMessage::TilemapData { .. } => {
-                match TileGrid::<TileKind>::try_from(variant) {
-                    Ok(g) => {
-                        info!(
-                            "Received tile grid {}×{} from server",
-                            g.width(),
-                            g.height()
-                        );
-                        // Spawn tile entities before inserting the grid as a
-                        // resource so they materialise together at ApplyDeferred.
-                        if let Some(ref meshes) = tile_meshes {
-                            for (pos, &kind) in g.iter() {
-                                spawn_tile_entity(&mut commands, pos, kind, meshes);
-                            }
-                        }
-                        commands.insert_resource(GridSize {
-                            width: g.width(),
-                            height: g.height(),
-                        });
-                        commands.insert_resource(g);
+            TilesStreamMessage::TilemapInfo {
+                width,
+                height,
+                chunk_size,
+            } => {
+                if chunk_size == 0 {
+                    error!("Invalid tilemap header on stream {TILES_STREAM_TAG}: chunk_size is 0");
+                    continue;
+                }
+                info!(
+                    "Received tilemap header {width}×{height} (chunk size {chunk_size}) from server"
+                );
+                // A header for a map already in place (the server reloaded
+                // it) replaces the spawned tiles.
+                if grid.is_some() {
+                    for entity in tiles.iter().chain(&tile_chunks) {
+                        commands.entity(entity).despawn();
                     }
-                    Err(e) => error!("Invalid tilemap data on stream {TILES_STREAM_TAG}: {e}"),
                 }
+                fresh = Some((
+                    TileGrid::new(width, height),
+                    TileGrid::new(width, height),
+                    ClientTileChunks::new(chunk_size),
+                ));
+            }
+            TilesStreamMessage::ChunkData {
+                chunk,
+                tiles,
+                floors: chunk_floors,
+            } => {
+                let chunk = IVec2::new(chunk[0], chunk[1]);
+                let (g, f, c) = match fresh.as_mut() {
+                    Some((g, f, c)) => (g, f, c),
+                    None => match (
+                        grid.as_deref_mut(),
+                        floors.as_deref_mut(),
+                        chunks.as_deref_mut(),
+                    ) {
+                        (Some(g), Some(f), Some(c)) => (g, f, c),
+                        _ => {
+                            warn!(
+                                "Received tile chunk {chunk:?} before the tilemap header, dropping"
+                            );
+                            continue;
+                        }
+                    },
+                };
+                if let Err(e) = apply_chunk_data(g, f, chunk, c.chunk_size, tiles, chunk_floors) {
+                    error!("Invalid tile chunk data on stream {TILES_STREAM_TAG}: {e}");
+                    continue;
+                }
+                c.received.insert(chunk);
             }
             TilesStreamMessage::TileMutated { position, kind } => {
                 let pos = IVec2::new(position[0], position[1]);
-                if let Some(ref mut g) = grid {
+                let target = match fresh.as_mut() {
+                    Some((g, _, _)) => Some(g),
+                    None => grid.as_deref_mut(),
+                };
+                // Only emit the mutation event once the grid exists.  This
+                // prevents spawning tile entities before the header arrives.
+                if let Some(g) = target {
                     g.set(pos, kind);
-                    // Only emit the mutation event once the grid resource exists.
-                    // This prevents spawning partial tile entities before the initial
-                    // TilemapData snapshot arrives.
                     mutation_events.write(TileMutated {
                         position: pos,
                         kind,
                     });
                 }
             }
+            TilesStreamMessage::FloorMutated { position, floor } => {
+                let pos = IVec2::new(position[0], position[1]);
+                let target = match fresh.as_mut() {
+                    Some((_, f, _)) => Some(f),
+                    None => floors.as_deref_mut(),
+                };
+                if let Some(f) = target {
+                    f.set(pos, floor);
+                    floor_events.write(FloorMutated {
+                        position: pos,
+                        floor,
+                    });
+                }
+            }
+            TilesStreamMessage::DeckData {
+                deck,
+                tiles,
+                floors: deck_floors,
+            } => {
+                let size = match fresh.as_ref() {
+                    Some((g, _, _)) => Some((g.width(), g.height())),
+                    None => grid.as_deref().map(|g| (g.width(), g.height())),
+                };
+                let Some((width, height)) = size else {
+                    warn!("Received deck {deck} before the tilemap header, dropping");
+                    continue;
+                };
+                if deck == Deck::MAIN.0 {
+                    error!(
+                        "Invalid deck data on stream {TILES_STREAM_TAG}: deck 0 comes as chunks"
+                    );
+                    continue;
+                }
+                let layers = match (
+                    TileGrid::from_cells(width, height, tiles),
+                    TileGrid::from_cells(width, height, deck_floors),
+                ) {
+                    (Ok(structures), Ok(floors)) => DeckLayers { structures, floors },
+                    (Err(e), _) | (_, Err(e)) => {
+                        error!("Invalid deck {deck} data on stream {TILES_STREAM_TAG}: {e}");
+                        continue;
+                    }
+                };
+                for build in deck_chunks(&layers, Deck(deck), tile_meshes.as_deref()) {
+                    spawn_chunk_entity(&mut commands, build, mesh_assets.as_deref_mut());
+                }
+                received_decks.push((Deck(deck), layers));
+            }
+            TilesStreamMessage::Zones {
+                width,
+                height,
+                zones,
+                runs,
+            } => match ZoneIndex::from_message(width, height, zones, &runs) {
+                Ok(index) => received_zones = Some(index),
+                Err(e) => error!("Invalid zones on stream {TILES_STREAM_TAG}: {e}"),
+            },
+            TilesStreamMessage::ZoneRenamed { zone, name } => {
+                let target = match received_zones.as_mut() {
+                    Some(index) => Some(index),
+                    None => zone_index.as_deref_mut(),
+                };
+                if !target.is_some_and(|index| index.rename(zone, name)) {
+                    warn!("Received a rename of unknown zone {}, dropping", zone.0);
+                }
+            }
         }
     }
+
+    if let Some(index) = received_zones {
+        commands.insert_resource(index);
+    }
+
+    // A new header starts the upper decks over.
+    if fresh.is_some() || !received_decks.is_empty() {
+        let mut decks = match (&fresh, upper.as_deref_mut()) {
+            (None, Some(existing)) => std::mem::take(existing),
+            _ => UpperDecks::default(),
+        };
+        for (deck, layers) in received_decks {
+            let index = deck.0 as usize - 1;
+            if decks.0.len() <= index {
+                let (width, height) = (layers.structures.width(), layers.structures.height());
+                decks.0.resize(index + 1, DeckLayers::new(width, height));
+            }
+            decks.0[index] = layers;
+        }
+        commands.insert_resource(decks);
+    }
+
+    if let Some((g, f, c)) = fresh {
+        commands.insert_resource(GridSize {
+            width: g.width(),
+            height: g.height(),
+        });
+        commands.insert_resource(g);
+        commands.insert_resource(f);
+        commands.insert_resource(c);
+    }
+}
+
+/// Client-side system that keeps tile entities spawned only for received
+/// chunks within [`CHUNK_VIEW_RADIUS`] of the camera, spawning and despawning
+/// the [`TileChunk`] batch and [`Tile`] entities a whole chunk at a time.
+///
+/// Runs in `Update` after [`apply_tile_mutation`], so a chunk spawned here
+/// already reflects this frame's mutations.
+#[allow(clippy::too_many_arguments)]
+fn update_visible_chunks(
+    mut commands: Commands,
+    chunks: Option<ResMut<ClientTileChunks>>,
+    grid: Option<Res<TileGrid<TileKind>>>,
+    floors: Option<Res<TileGrid<FloorKind>>>,
+    tile_meshes: Res<TileMeshes>,
+    mut mesh_assets: ResMut<Assets<Mesh>>,
+    view_camera: ViewCamera,
+    tile_query: Query<(Entity, &Tile)>,
+    chunk_query: Query<(Entity, &TileChunk)>,
+) {
+    let (Some(mut chunks), Some(grid)) = (chunks, grid) else {
+        return;
+    };
+    let Some(cam) = view_camera.translation() else {
+        return;
+    };
+    let chunk_size = chunks.chunk_size;
+    let center = chunk_of(
+        IVec2::new(cam.x.round() as i32, cam.z.round() as i32),
+        chunk_size,
+    );
+    let wanted: HashSet<IVec2> = chunks_in_radius(center, CHUNK_VIEW_RADIUS)
+        .filter(|c| chunks.received.contains(c))
+        .collect();
+
+    let stale: Vec<IVec2> = chunks.spawned.difference(&wanted).copied().collect();
+    if !stale.is_empty() {
+        for (entity, tile) in &tile_query {
+            if stale.contains(&chunk_of(tile.position, chunk_size)) {
+                commands.entity(entity).despawn();
+            }
+        }
+        for chunk in stale {
+            despawn_chunk_entity(&mut commands, &chunk_query, chunk);
+            chunks.spawned.remove(&chunk);
+        }
+    }
+
+    for chunk in wanted {
+        if chunks.spawned.contains(&chunk) {
+            continue;
+        }
+        let Some(build) = build_chunk(
+            &grid,
+            floors.as_deref(),
+            Deck::MAIN,
+            chunk,
+            chunk_size,
+            Some(&tile_meshes),
+        ) else {
+            continue;
+        };
+        spawn_chunk_tiles(&mut commands, &grid, chunk, chunk_size);
+        spawn_chunk_entity(&mut commands, build, Some(&mut mesh_assets));
+        chunks.spawned.insert(chunk);
+    }
 }
 
 /// Clients that joined before the [`TileGrid<TileKind>`] resource was available
@@ -900,9 +1874,34 @@ fn handle_tiles_stream(
 #[derive(Resource, Default)]
 struct PendingTilesSyncs(Vec<ClientId>);
 
-/// Server-side system: sends a full tile grid snapshot + [`StreamReady`] to each
-/// joining client.  Listens to [`PlayerEvent::Joined`] so `TilesPlugin` is
-/// decoupled from internal network events ([`ServerEvent`]).
+/// Chunk streaming state for one client.
+#[derive(Debug, Default)]
+struct ChunkSubscription {
+    /// Chunks already streamed to the client.
+    sent: HashSet<IVec2>,
+    /// Whether [`StreamReady`] has been sent, ending the initial burst.
+    ready: bool,
+}
+
+/// Server-side record of the chunks already streamed to each client.
+///
+/// An entry is created when the tilemap header is sent and removed on
+/// [`PlayerEvent::Left`]; [`stream_tile_chunks`] only serves clients with an entry.
+#[derive(Resource, Default)]
+pub struct ChunkSubscriptions(HashMap<ClientId, ChunkSubscription>);
+
+impl ChunkSubscriptions {
+    /// Chunks of [`TILE_CHUNK_SIZE`] streamed to `client` since its last
+    /// tilemap header, or `None` if it is not subscribed.
+    pub fn sent_to(&self, client: ClientId) -> Option<&HashSet<IVec2>> {
+        self.0.get(&client).map(|subscription| &subscription.sent)
+    }
+}
+
+/// Server-side system: sends the [`TilesStreamMessage::TilemapInfo`] header to
+/// each joining client and subscribes it to chunk streaming.  Listens to
+/// [`PlayerEvent::Joined`] so `TilesPlugin` is decoupled from internal network
+/// events ([`ServerEvent`]).
 ///
 /// If the [`TileGrid<TileKind>`] resource does not exist yet (listen-server
 /// startup), the client ID is queued in [`PendingTilesSyncs`] and retried each
@@ -911,15 +1910,21 @@ fn send_tilemap_on_connect(
     mut events: MessageReader<PlayerEvent>,
     tiles_sender: Option<Res<StreamSender<TilesStreamMessage>>>,
     grid: Option<Res<TileGrid<TileKind>>>,
-    mut module_ready: MessageWriter<ModuleReadySent>,
+    upper: Option<Res<UpperDecks>>,
+    zones: Option<Res<ZoneIndex>>,
     mut pending: ResMut<PendingTilesSyncs>,
+    mut subscriptions: ResMut<ChunkSubscriptions>,
 ) {
-    // Collect newly joined clients.
+    // Collect newly joined clients and forget departed ones.
     for event in events.read() {
-        let PlayerEvent::Joined { id: from, .. } = event else {
-            continue;
-        };
-        pending.0.push(*from);
+        match event {
+            PlayerEvent::Joined { id, .. } => pending.0.push(*id),
+            PlayerEvent::Left { id } => {
+                pending.0.retain(|c| c != id);
+                subscriptions.0.remove(id);
+            }
+            PlayerEvent::MovementViolation { .. } => {}
+        }
     }
 
     // Nothing to do if no clients are waiting.
@@ -941,52 +1946,230 @@ fn send_tilemap_on_connect(
         return;
     };
 
+    let (header, decks) = tilemap_header(grid, upper.as_deref());
+    let zones = zones.map(|zones| zones.to_message());
     let clients = std::mem::take(&mut pending.0);
     for from in clients {
-        if let Err(e) = ts.send_to(from, &TilesStreamMessage::from(grid)) {
-            error!("Failed to send TilemapData to ClientId({}): {}", from.0, e);
+        if !send_tilemap_header(ts, from, &header, &decks) {
             continue;
         }
-
-        if let Err(e) = ts.send_stream_ready_to(from) {
-            error!("Failed to send StreamReady to ClientId({}): {}", from.0, e);
-            continue;
+        if let Some(msg) = &zones
+            && let Err(e) = ts.send_to(from, msg)
+        {
+            error!("Failed to send Zones to ClientId({}): {}", from.0, e);
         }
 
+        subscriptions.0.insert(from, ChunkSubscription::default());
         info!(
-            "Sent tile grid snapshot {}×{} + StreamReady to ClientId({})",
+            "Sent tilemap header {}×{} to ClientId({})",
             grid.width(),
             grid.height(),
             from.0
         );
-        module_ready.write(ModuleReadySent { client: from });
+    }
+}
+
+/// The [`TilesStreamMessage::TilemapInfo`] header of `grid` and the
+/// [`TilesStreamMessage::DeckData`] of every upper deck.
+fn tilemap_header(
+    grid: &TileGrid<TileKind>,
+    upper: Option<&UpperDecks>,
+) -> (TilesStreamMessage, Vec<TilesStreamMessage>) {
+    let header = TilesStreamMessage::TilemapInfo {
+        width: grid.width(),
+        height: grid.height(),
+        chunk_size: TILE_CHUNK_SIZE,
+    };
+    let decks = upper
+        .iter()
+        .flat_map(|upper| upper.iter())
+        .map(|(deck, layers)| TilesStreamMessage::DeckData {
+            deck: deck.0,
+            tiles: layers.structures.iter().map(|(_, &kind)| kind).collect(),
+            floors: layers.floors.iter().map(|(_, &floor)| floor).collect(),
+        })
+        .collect();
+    (header, decks)
+}
+
+/// Sends `header` and `decks` to `client`; `false` if the header could not be
+/// sent.
+fn send_tilemap_header(
+    ts: &StreamSender<TilesStreamMessage>,
+    client: ClientId,
+    header: &TilesStreamMessage,
+    decks: &[TilesStreamMessage],
+) -> bool {
+    if let Err(e) = ts.send_to(client, header) {
+        error!(
+            "Failed to send TilemapInfo to ClientId({}): {}",
+            client.0, e
+        );
+        return false;
+    }
+    for msg in decks {
+        if let Err(e) = ts.send_to(client, msg) {
+            error!("Failed to send DeckData to ClientId({}): {}", client.0, e);
+        }
+    }
+    true
+}
+
+/// Server-side system: when the map is loaded again over the running world
+/// (see `world::reload_map`), sends every subscribed client the new header
+/// and upper decks and streams its chunks from scratch.  Clients keep their
+/// [`StreamReady`]; they are past the initial sync.
+fn resend_tilemap_on_reload(
+    tiles_sender: Option<Res<StreamSender<TilesStreamMessage>>>,
+    grid: Option<Res<TileGrid<TileKind>>>,
+    upper: Option<Res<UpperDecks>>,
+    mut subscriptions: ResMut<ChunkSubscriptions>,
+) {
+    let (Some(ts), Some(grid)) = (tiles_sender.as_deref(), grid) else {
+        return;
+    };
+    if !grid.is_added() || subscriptions.0.is_empty() {
+        return;
+    }
+    let (header, decks) = tilemap_header(&grid, upper.as_deref());
+    for (&client, subscription) in subscriptions.0.iter_mut() {
+        if send_tilemap_header(ts, client, &header, &decks) {
+            subscription.sent.clear();
+        }
+    }
+    info!(
+        "Sent reloaded tilemap header {}×{} to {} client(s)",
+        grid.width(),
+        grid.height(),
+        subscriptions.0.len()
+    );
+}
+
+/// Server resource: tiles around which clients without a controlled entity
+/// (e.g. players waiting in the lobby) are sent their initial burst, so they
+/// arrive with the area they will spawn into.  Kept up to date by the module
+/// that owns the spawn points (`souls`); empty sends such clients no chunks.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct LobbyStreamAnchors(pub Vec<IVec2>);
+
+/// Chunks a client should have been sent: every chunk of `grid` with
+/// [`FullRelevancy`], those within [`CHUNK_STREAM_RADIUS`] of its controlled
+/// entity at `actor_pos`, or, for the initial burst of a client without one,
+/// those within the radius of the [`LobbyStreamAnchors`].
+fn relevant_chunks(
+    grid: &TileGrid<TileKind>,
+    actor_pos: Option<Vec3>,
+    full: bool,
+    initial: bool,
+    anchors: &[IVec2],
+) -> Vec<IVec2> {
+    let centres: Vec<IVec2> = match actor_pos {
+        Some(_) if full => return grid.chunks(TILE_CHUNK_SIZE).collect(),
+        Some(pos) => vec![IVec2::new(pos.x.round() as i32, pos.z.round() as i32)],
+        None if initial => anchors.to_vec(),
+        None => Vec::new(),
+    };
+    let mut chunks: Vec<IVec2> = centres
+        .into_iter()
+        .flat_map(|tile| chunks_in_radius(chunk_of(tile, TILE_CHUNK_SIZE), CHUNK_STREAM_RADIUS))
+        .filter(|&chunk| grid.contains_chunk(chunk, TILE_CHUNK_SIZE))
+        .collect();
+    chunks.sort_by_key(|chunk| (chunk.y, chunk.x));
+    chunks.dedup();
+    chunks
+}
+
+/// Server-side system: streams [`TilesStreamMessage::ChunkData`] for every
+/// chunk within [`CHUNK_STREAM_RADIUS`] of each subscribed client's controlled
+/// entity that the client has not been sent yet.  Clients with
+/// [`FullRelevancy`] are sent every chunk of the map instead.
+///
+/// The first pass for a client is its initial burst and is followed by
+/// [`StreamReady`] + [`ModuleReadySent`].  A client without a controlled entity
+/// at that point (e.g. a player waiting in the lobby) is sent the chunks around
+/// the [`LobbyStreamAnchors`]; the rest follow once an entity exists.
+/// Chunks are sent once; later changes reach clients through the
+/// `TileMutated` / `FloorMutated` broadcasts.
+#[allow(clippy::too_many_arguments)]
+fn stream_tile_chunks(
+    tiles_sender: Option<Res<StreamSender<TilesStreamMessage>>>,
+    grid: Option<Res<TileGrid<TileKind>>>,
+    floors: Option<Res<TileGrid<FloorKind>>>,
+    mut subscriptions: ResMut<ChunkSubscriptions>,
+    full_relevancy: Option<Res<FullRelevancy>>,
+    anchors: Res<LobbyStreamAnchors>,
+    actors: Query<(&ControlledByClient, &Transform)>,
+    mut module_ready: MessageWriter<ModuleReadySent>,
+) {
+    let (Some(ts), Some(grid)) = (tiles_sender.as_deref(), grid.as_deref()) else {
+        return;
+    };
+    for (&client, subscription) in subscriptions.0.iter_mut() {
+        let actor_pos = actors
+            .iter()
+            .find(|(ctrl, _)| ctrl.0 == client)
+            .map(|(_, t)| t.translation);
+        let full = full_relevancy
+            .as_ref()
+            .is_some_and(|full| full.0.contains(&client));
+        let chunks = relevant_chunks(grid, actor_pos, full, !subscription.ready, &anchors.0);
+        for chunk in chunks {
+            if subscription.sent.contains(&chunk) {
+                continue;
+            }
+            let msg =
+                TilesStreamMessage::chunk_data(grid, floors.as_deref(), chunk, TILE_CHUNK_SIZE);
+            if let Err(e) = ts.send_to(client, &msg) {
+                error!(
+                    "Failed to send tile chunk {chunk:?} to ClientId({}): {}",
+                    client.0, e
+                );
+                continue;
+            }
+            subscription.sent.insert(chunk);
+        }
+
+        if !subscription.ready {
+            if let Err(e) = ts.send_stream_ready_to(client) {
+                error!(
+                    "Failed to send StreamReady to ClientId({}): {}",
+                    client.0, e
+                );
+                continue;
+            }
+            subscription.ready = true;
+            info!(
+                "Sent {} tile chunk(s) + StreamReady to ClientId({})",
+                subscription.sent.len(),
+                client.0
+            );
+            module_ready.write(ModuleReadySent { client });
+        }
     }
 }
 
 /// System that listens for left-click and right-click [`PointerAction`] events, raycasts from the
 /// camera through the screen position to the ground plane (y = 0), and emits a
 /// [`WorldHit`] event carrying the hit tile entity and world position if a valid
-/// tile exists at the resulting grid coordinate.
+/// tile exists at the resulting grid coordinate.  The structure entity of the
+/// cell wins over its floor entity, so the context menu targets the topmost layer.
 ///
 /// Runs in `Update`, gated on absence of [`Headless`].
 fn raycast_tiles(
     mut pointer_events: MessageReader<PointerAction>,
-    camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    view_camera: ViewCamera,
     tile_query: Query<(Entity, &Tile)>,
     grid: Option<Res<TileGrid<TileKind>>>,
     mut hit_events: MessageWriter<WorldHit>,
 ) {
     let Some(grid) = grid else { return };
-    let Ok((camera, cam_transform)) = camera_query.single() else {
-        return;
-    };
 
     for action in pointer_events.read() {
         if !matches!(action.button, MouseButton::Left | MouseButton::Right) {
             continue;
         }
 
-        let Ok(ray) = camera.viewport_to_world(cam_transform, action.screen_pos) else {
+        let Some(ray) = view_camera.ray(action.screen_pos) else {
             continue;
         };
 
@@ -1006,8 +2189,12 @@ fn raycast_tiles(
         // Grid coordinates: world X → column, world Z → row.
         let grid_pos = IVec2::new(world_pos.x.round() as i32, world_pos.z.round() as i32);
 
+        let topmost = tile_query
+            .iter()
+            .filter(|(_, t)| t.position == grid_pos)
+            .max_by_key(|(_, t)| t.layer == TileLayer::Structure);
         if grid.get(grid_pos).is_some()
-            && let Some((entity, _)) = tile_query.iter().find(|(_, t)| t.position == grid_pos)
+            && let Some((entity, _)) = topmost
         {
             hit_events.write(WorldHit {
                 button: action.button,
@@ -1018,32 +2205,85 @@ fn raycast_tiles(
     }
 }
 
-/// Client-side system that handles [`TileMutated`] events (fired by both
-/// [`handle_tiles_stream`] and, on listen-servers, by `dispatch_interaction` in the
-/// interactions module).
+/// System that handles [`TileMutated`] and [`FloorMutated`] events (fired by
+/// [`handle_tiles_stream`] on clients and by `dispatch_interaction` in the
+/// interactions module on servers).
 ///
-/// Despawns the existing tile entity at the affected grid position and spawns a new
-/// one with the updated mesh, material, and collider via [`spawn_tile_entity`].
-/// This provides incremental rendering — only the changed tile is rebuilt.
+/// Replaces the structure [`Tile`] entity of each mutated cell via
+/// [`spawn_tile_entity`], then rebuilds the [`TileChunk`] of every affected
+/// chunk from the layer grids, so a mutation costs one chunk rebuild rather
+/// than a full map respawn.  On a streaming client, cells in chunks that are
+/// not currently spawned are skipped.  On a headless server, where
+/// [`TileMeshes`] is absent, only the chunk collider is rebuilt so that
+/// server-authoritative physics follows tile changes (e.g. a door closing must
+/// start blocking movement).
+#[allow(clippy::too_many_arguments)]
 fn apply_tile_mutation(
     mut commands: Commands,
     mut events: MessageReader<TileMutated>,
+    mut floor_events: MessageReader<FloorMutated>,
     tile_query: Query<(Entity, &Tile)>,
-    tile_meshes: Res<TileMeshes>,
+    chunk_query: Query<(Entity, &TileChunk)>,
+    grid: Option<Res<TileGrid<TileKind>>>,
+    floors: Option<Res<TileGrid<FloorKind>>>,
+    tile_meshes: Option<Res<TileMeshes>>,
+    mut mesh_assets: Option<ResMut<Assets<Mesh>>>,
+    chunks: Option<Res<ClientTileChunks>>,
 ) {
+    // On a streaming client, cells outside spawned chunks have no entities;
+    // update_visible_chunks builds them from the grid when they come into view.
+    let is_spawned = |pos: IVec2| chunks.as_deref().is_none_or(|c| c.is_spawned(pos));
+    let chunk_size = chunks.as_deref().map_or(TILE_CHUNK_SIZE, |c| c.chunk_size);
+    let mut dirty: HashSet<IVec2> = HashSet::new();
     for event in events.read() {
         let TileMutated { position, kind } = *event;
+        if !is_spawned(position) {
+            continue;
+        }
 
-        // Despawn the existing tile entity at this grid position (if any).
-        for (entity, tile) in &tile_query {
-            if tile.position == position {
-                commands.entity(entity).despawn();
-                break;
-            }
+        despawn_tile_at(&mut commands, &tile_query, position, TileLayer::Structure);
+        if let Some(spawn) = structure_spawn(position, kind) {
+            spawn_tile_entity(&mut commands, spawn);
+        }
+        dirty.insert(chunk_of(position, chunk_size));
+    }
+    for event in floor_events.read() {
+        // The floor Tile entity itself is unchanged; only the chunk mesh is.
+        if is_spawned(event.position) {
+            dirty.insert(chunk_of(event.position, chunk_size));
         }
+    }
 
-        // Spawn a replacement tile entity with the new kind.
-        spawn_tile_entity(&mut commands, position, kind, &tile_meshes);
+    let Some(grid) = grid else {
+        return;
+    };
+    for chunk in dirty {
+        despawn_chunk_entity(&mut commands, &chunk_query, chunk);
+        if let Some(build) = build_chunk(
+            &grid,
+            floors.as_deref(),
+            Deck::MAIN,
+            chunk,
+            chunk_size,
+            tile_meshes.as_deref(),
+        ) {
+            spawn_chunk_entity(&mut commands, build, mesh_assets.as_deref_mut());
+        }
+    }
+}
+
+/// Despawns the existing tile entity for `layer` at `position` (if any).
+fn despawn_tile_at(
+    commands: &mut Commands,
+    tile_query: &Query<(Entity, &Tile)>,
+    position: IVec2,
+    layer: TileLayer,
+) {
+    for (entity, tile) in tile_query {
+        if tile.position == position && tile.layer == layer {
+            commands.entity(entity).despawn();
+            break;
+        }
     }
 }
 
@@ -1055,6 +2295,48 @@ mod tests {
     fn test_tile_kind_walkability() {
         assert!(TileKind::Floor.is_walkable());
         assert!(!TileKind::Wall.is_walkable());
+        assert!(TileKind::Door { open: true }.is_walkable());
+        assert!(!TileKind::Door { open: false }.is_walkable());
+    }
+
+    #[test]
+    fn test_space_is_an_open_vacuum_on_the_hull() {
+        let space = TileKind::Space.flags();
+        assert!(space.contains(TileFlag::WALKABLE | TileFlag::GAS_PASS | TileFlag::VACUUM));
+        assert!(!TileKind::Floor.flags().contains(TileFlag::VACUUM));
+
+        let grid = TileGrid::<TileKind>::new(4, 3);
+        assert!(grid.is_hull(IVec2::new(0, 1)));
+        assert!(grid.is_hull(IVec2::new(3, 2)));
+        assert!(!grid.is_hull(IVec2::new(1, 1)));
+        assert!(!grid.is_hull(IVec2::new(4, 1)));
+    }
+
+    #[test]
+    fn test_door_flags_follow_open_state() {
+        let closed = TileKind::Door { open: false }.flags();
+        assert!(!closed.contains(TileFlag::WALKABLE));
+        assert!(!closed.contains(TileFlag::GAS_PASS));
+        assert!(!closed.contains(TileFlag::LIGHT_PASS));
+
+        let open = TileKind::Door { open: true }.flags();
+        assert_eq!(open, TileKind::Floor.flags());
+    }
+
+    #[test]
+    fn test_door_mutated_message_roundtrip() {
+        let msg = TilesStreamMessage::TileMutated {
+            position: [2, 3],
+            kind: TileKind::Door { open: true },
+        };
+        let bytes = wincode::serialize(&msg).expect("serialize should succeed");
+        match decode_tiles_message(&bytes).expect("decode should succeed") {
+            TilesStreamMessage::TileMutated { position, kind } => {
+                assert_eq!(position, [2, 3]);
+                assert_eq!(kind, TileKind::Door { open: true });
+            }
+            other => panic!("expected TileMutated, got {other:?}"),
+        }
     }
 
     #[test]
@@ -1153,70 +2435,94 @@ mod tests {
     }
 
     #[test]
-    fn test_grid_to_from_bytes_roundtrip() {
+    fn test_chunk_of_handles_negative_positions() {
+        assert_eq!(chunk_of(IVec2::new(0, 0), 32), IVec2::new(0, 0));
+        assert_eq!(chunk_of(IVec2::new(31, 32), 32), IVec2::new(0, 1));
+        assert_eq!(chunk_of(IVec2::new(-1, -33), 32), IVec2::new(-1, -2));
+    }
+
+    #[test]
+    fn test_chunks_in_radius() {
+        let chunks: Vec<IVec2> = chunks_in_radius(IVec2::new(3, 3), 1).collect();
+        assert_eq!(chunks.len(), 9);
+        assert!(chunks.contains(&IVec2::new(2, 2)));
+        assert!(chunks.contains(&IVec2::new(4, 4)));
+        assert!(!chunks.contains(&IVec2::new(5, 3)));
+    }
+
+    #[test]
+    fn test_relevant_chunks_of_lobby_client_stay_near_anchors() {
+        // 10×10 chunks, well beyond the stream radius around either corner.
+        let size = TILE_CHUNK_SIZE * 10;
+        let grid = TileGrid::<TileKind>::new_fill(size, size, TileKind::Floor);
+        let anchors = [IVec2::new(1, 1)];
+
+        let chunks = relevant_chunks(&grid, None, false, true, &anchors);
+        let side = CHUNK_STREAM_RADIUS as usize + 1;
+        assert_eq!(chunks.len(), side * side);
+        assert!(
+            chunks
+                .iter()
+                .all(|chunk| chunk.max_element() <= CHUNK_STREAM_RADIUS)
+        );
+        assert!(!chunks.contains(&IVec2::new(9, 9)));
+
+        assert!(relevant_chunks(&grid, None, false, false, &anchors).is_empty());
+        assert!(relevant_chunks(&grid, None, false, true, &[]).is_empty());
+        assert_eq!(
+            relevant_chunks(&grid, Some(Vec3::ZERO), true, false, &[]).len(),
+            100
+        );
+    }
+
+    #[test]
+    fn test_chunk_cells_roundtrip() {
         let mut original = TileGrid::<TileKind>::new_fill(16, 10, TileKind::Floor);
         for x in 0..16 {
             original.set(IVec2::new(x, 0), TileKind::Wall);
             original.set(IVec2::new(x, 9), TileKind::Wall);
         }
-        for y in 0..10 {
-            original.set(IVec2::new(0, y), TileKind::Wall);
-            original.set(IVec2::new(15, y), TileKind::Wall);
+        let mut restored = TileGrid::<TileKind>::new(16, 10);
+        for cy in 0..3 {
+            for cx in 0..4 {
+                let chunk = IVec2::new(cx, cy);
+                let cells = original.chunk_cells(chunk, 4);
+                restored
+                    .set_chunk_cells(chunk, 4, cells)
+                    .expect("chunk should fit");
+            }
         }
-        let bytes = original.to_bytes().expect("to_bytes should succeed");
-        let restored = TileGrid::<TileKind>::from_bytes(&bytes).expect("from_bytes should succeed");
-
-        assert_eq!(restored.width(), original.width());
-        assert_eq!(restored.height(), original.height());
         for (pos, &kind) in original.iter() {
             assert_eq!(restored.get_copy(pos), Some(kind));
         }
     }
 
     #[test]
-    fn test_grid_to_from_bytes_small() {
-        let mut grid = TileGrid::<TileKind>::new_fill(3, 2, TileKind::Floor);
-        grid.set(IVec2::new(0, 0), TileKind::Wall);
-        grid.set(IVec2::new(2, 1), TileKind::Wall);
-
-        let bytes = grid.to_bytes().expect("to_bytes should succeed");
-        let restored = TileGrid::<TileKind>::from_bytes(&bytes).expect("from_bytes should succeed");
-
-        assert_eq!(restored.width(), 3);
-        assert_eq!(restored.height(), 2);
-        assert_eq!(restored.get_copy(IVec2::new(0, 0)), Some(TileKind::Wall));
-        assert_eq!(restored.get_copy(IVec2::new(1, 0)), Some(TileKind::Floor));
-        assert_eq!(restored.get_copy(IVec2::new(2, 1)), Some(TileKind::Wall));
+    fn test_chunk_cells_clipped_to_grid() {
+        // A 5×3 grid with chunk size 4: chunk (1, 0) covers x = 4 only.
+        let grid = TileGrid::<TileKind>::new_fill(5, 3, TileKind::Floor);
+        assert_eq!(grid.chunk_cells(IVec2::new(0, 0), 4).len(), 12);
+        assert_eq!(grid.chunk_cells(IVec2::new(1, 0), 4).len(), 3);
+        assert!(grid.contains_chunk(IVec2::new(1, 0), 4));
+        assert!(!grid.contains_chunk(IVec2::new(2, 0), 4));
+        assert!(!grid.contains_chunk(IVec2::new(-1, 0), 4));
+        assert!(grid.chunk_cells(IVec2::new(0, 1), 4).is_empty());
     }
 
     #[test]
-    fn test_from_bytes_invalid() {
-        let result = TileGrid::<TileKind>::from_bytes(&[0xFF, 0x00]);
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_try_from_dimension_mismatch() {
-        let msg = TilesStreamMessage::TilemapData {
-            width: u32::MAX,
-            height: 2,
-            tiles: vec![TileKind::Floor; 4],
-        };
-        let result = TileGrid::<TileKind>::try_from(msg);
-        assert!(result.is_err(), "should reject mismatched dimensions");
-    }
-
-    #[test]
-    fn test_try_from_tile_mutated_returns_error() {
-        let msg = TilesStreamMessage::TileMutated {
-            position: [2, 3],
-            kind: TileKind::Floor,
-        };
-        let result = TileGrid::<TileKind>::try_from(msg);
+    fn test_set_chunk_cells_rejects_bad_input() {
+        let mut grid = TileGrid::<TileKind>::new_fill(5, 3, TileKind::Floor);
         assert!(
-            result.is_err(),
-            "TileMutated should not convert to a TileGrid"
+            grid.set_chunk_cells(IVec2::new(0, 0), 4, vec![TileKind::Wall; 3])
+                .is_err(),
+            "length mismatch must be rejected"
         );
+        assert!(
+            grid.set_chunk_cells(IVec2::new(3, 0), 4, vec![TileKind::Wall; 16])
+                .is_err(),
+            "chunk outside the grid must be rejected"
+        );
+        assert_eq!(grid.get_copy(IVec2::new(0, 0)), Some(TileKind::Floor));
     }
 
     #[test]
@@ -1237,6 +2543,120 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_chunk_data_carries_floor_layer() {
+        let mut structures = TileGrid::<TileKind>::new_fill(6, 4, TileKind::Floor);
+        structures.set(IVec2::new(5, 0), TileKind::Wall);
+        let mut floors = TileGrid::<FloorKind>::new(6, 4);
+        floors.set(IVec2::new(5, 0), FloorKind::Carpet);
+
+        let chunk = IVec2::new(1, 0);
+        let msg = TilesStreamMessage::chunk_data(&structures, Some(&floors), chunk, 4);
+        let bytes = wincode::serialize(&msg).expect("serialize should succeed");
+        let TilesStreamMessage::ChunkData {
+            chunk: sent_chunk,
+            tiles,
+            floors: sent_floors,
+        } = decode_tiles_message(&bytes).expect("decode should succeed")
+        else {
+            panic!("expected ChunkData");
+        };
+        assert_eq!(sent_chunk, [1, 0]);
+
+        let mut grid = TileGrid::<TileKind>::new(6, 4);
+        let mut floor_grid = TileGrid::<FloorKind>::new(6, 4);
+        apply_chunk_data(&mut grid, &mut floor_grid, chunk, 4, tiles, sent_floors)
+            .expect("apply should succeed");
+
+        // The wall did not replace the carpet underneath it.
+        assert_eq!(grid.get_copy(IVec2::new(5, 0)), Some(TileKind::Wall));
+        assert_eq!(
+            floor_grid.get_copy(IVec2::new(5, 0)),
+            Some(FloorKind::Carpet)
+        );
+        assert_eq!(
+            floor_grid.get_copy(IVec2::new(4, 0)),
+            Some(FloorKind::Plating)
+        );
+    }
+
+    #[test]
+    fn test_chunk_data_all_plating_omits_floors() {
+        let structures = TileGrid::<TileKind>::new_fill(4, 4, TileKind::Floor);
+        let floors = TileGrid::<FloorKind>::new(4, 4);
+        let TilesStreamMessage::ChunkData { floors: sent, .. } =
+            TilesStreamMessage::chunk_data(&structures, Some(&floors), IVec2::ZERO, 4)
+        else {
+            panic!("expected ChunkData");
+        };
+        assert!(sent.is_empty());
+    }
+
+    #[test]
+    fn test_apply_chunk_data_floor_length_mismatch_errors() {
+        let mut grid = TileGrid::<TileKind>::new(2, 2);
+        let mut floors = TileGrid::<FloorKind>::new(2, 2);
+        let result = apply_chunk_data(
+            &mut grid,
+            &mut floors,
+            IVec2::ZERO,
+            2,
+            vec![TileKind::Floor; 4],
+            vec![FloorKind::Carpet; 3],
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_build_chunk_merges_meshes_per_material() {
+        let mut world = World::new();
+        world.init_resource::<Assets<StandardMaterial>>();
+        let tile_meshes = TileMeshes::from_world(&mut world);
+
+        let mut grid = TileGrid::<TileKind>::new_fill(4, 4, TileKind::Floor);
+        grid.set(IVec2::new(1, 1), TileKind::Wall);
+        grid.set(IVec2::new(2, 1), TileKind::Wall);
+        let build = build_chunk(&grid, None, Deck::MAIN, IVec2::ZERO, 4, Some(&tile_meshes))
+            .expect("chunk lies inside the grid");
+
+        // Sixteen plating floors in one batch, two walls in another.
+        assert_eq!(build.meshes.len(), 2);
+        assert_eq!(
+            build.meshes[0].0.count_vertices(),
+            tile_meshes.floor_mesh.count_vertices() * 16
+        );
+        assert_eq!(
+            build.meshes[1].0.count_vertices(),
+            tile_meshes.wall_mesh.count_vertices() * 2
+        );
+
+        assert!(build_chunk(&grid, None, Deck::MAIN, IVec2::new(1, 0), 4, None).is_none());
+    }
+
+    #[test]
+    fn test_tilemap_get_layer() {
+        let mut world = World::new();
+        let mut structures = TileGrid::<TileKind>::new_fill(2, 2, TileKind::Floor);
+        structures.set(IVec2::new(0, 1), TileKind::Door { open: false });
+        let mut floors = TileGrid::<FloorKind>::new(2, 2);
+        floors.set(IVec2::new(0, 1), FloorKind::Carpet);
+        world.insert_resource(structures);
+        world.insert_resource(floors);
+
+        let mut state = bevy::ecs::system::SystemState::<Tilemap>::new(&mut world);
+        let tilemap = state.get(&world);
+        let pos = IVec2::new(0, 1);
+        assert_eq!(
+            tilemap.get_layer(pos, TileLayer::Structure),
+            Some(LayerTile::Structure(TileKind::Door { open: false }))
+        );
+        assert_eq!(
+            tilemap.get_layer(pos, TileLayer::Floor),
+            Some(LayerTile::Floor(FloorKind::Carpet))
+        );
+        assert_eq!(tilemap.get_layer(IVec2::new(5, 5), TileLayer::Floor), None);
+    }
+
     // ---------------------------------------------------------------------------
     // TilesLayer (MapLayer) tests
     // ---------------------------------------------------------------------------
@@ -1278,6 +2698,128 @@ mod tests {
         }
     }
 
+    /// Floor and structure layers round-trip independently through TilesLayer.
+    #[test]
+    fn tiles_layer_roundtrip_floor_layer() {
+        let mut structures = TileGrid::<TileKind>::new_fill(2, 2, TileKind::Floor);
+        structures.set(IVec2::new(1, 1), TileKind::Wall);
+        let mut floors = TileGrid::<FloorKind>::new(2, 2);
+        floors.set(IVec2::new(1, 1), FloorKind::Carpet);
+        floors.set(IVec2::new(0, 0), FloorKind::Carpet);
+
+        let mut world = world_with_grid(structures);
+        world.insert_resource(floors);
+        let raw = TilesLayer.save(&world).expect("save must succeed");
+
+        let mut load_world = World::new();
+        TilesLayer
+            .load(&raw, &mut load_world)
+            .expect("load must succeed");
+
+        let loaded_floors = load_world.resource::<TileGrid<FloorKind>>();
+        assert_eq!(
+            loaded_floors.get_copy(IVec2::new(1, 1)),
+            Some(FloorKind::Carpet)
+        );
+        assert_eq!(
+            loaded_floors.get_copy(IVec2::new(0, 0)),
+            Some(FloorKind::Carpet)
+        );
+        assert_eq!(
+            loaded_floors.get_copy(IVec2::new(1, 0)),
+            Some(FloorKind::Plating)
+        );
+        assert_eq!(
+            load_world
+                .resource::<TileGrid<TileKind>>()
+                .get_copy(IVec2::new(1, 1)),
+            Some(TileKind::Wall)
+        );
+
+        // One floor entity per cell plus one structure entity for the wall.
+        let tiles: Vec<Tile> = load_world
+            .query::<&Tile>()
+            .iter(&load_world)
+            .copied()
+            .collect();
+        let structure_count = tiles
+            .iter()
+            .filter(|t| t.layer == TileLayer::Structure)
+            .count();
+        assert_eq!(structure_count, 1);
+        assert_eq!(tiles.len() - structure_count, 32 * 32);
+    }
+
+    /// Upper decks round-trip through TilesLayer and get chunk entities of
+    /// their own.
+    #[test]
+    fn tiles_layer_roundtrip_upper_decks() {
+        let mut main = TileGrid::<TileKind>::new_fill(2, 2, TileKind::Floor);
+        main.set(IVec2::new(0, 0), TileKind::Ladder);
+        let mut deck = DeckLayers::new(2, 2);
+        deck.structures.set(IVec2::new(1, 1), TileKind::Wall);
+        deck.floors.set(IVec2::new(0, 1), FloorKind::Carpet);
+
+        let mut world = world_with_grid(main);
+        world.insert_resource(UpperDecks(vec![deck]));
+        let raw = TilesLayer.save(&world).expect("save must succeed");
+
+        let mut load_world = World::new();
+        TilesLayer
+            .load(&raw, &mut load_world)
+            .expect("load must succeed");
+
+        let upper = load_world.resource::<UpperDecks>();
+        let loaded = upper.get(Deck(1)).expect("deck 1 must be loaded");
+        assert_eq!(
+            loaded.structures.get_copy(IVec2::new(1, 1)),
+            Some(TileKind::Wall)
+        );
+        assert_eq!(
+            loaded.floors.get_copy(IVec2::new(0, 1)),
+            Some(FloorKind::Carpet)
+        );
+        assert_eq!(
+            load_world
+                .resource::<TileGrid<TileKind>>()
+                .get_copy(IVec2::new(0, 0)),
+            Some(TileKind::Ladder)
+        );
+
+        let mut chunks = load_world.query::<&TileChunk>();
+        assert!(chunks.iter(&load_world).any(|c| c.deck == Deck(1)));
+    }
+
+    /// Loading spawns one collider-bearing chunk entity per chunk; the per-cell
+    /// tile entities carry no collider of their own.
+    #[test]
+    fn tiles_layer_load_spawns_chunk_entities() {
+        let mut original = TileGrid::<TileKind>::new_fill(40, 10, TileKind::Floor);
+        original.set(IVec2::new(35, 2), TileKind::Wall);
+        let raw = TilesLayer
+            .save(&world_with_grid(original))
+            .expect("save must succeed");
+
+        let mut load_world = World::new();
+        TilesLayer
+            .load(&raw, &mut load_world)
+            .expect("load must succeed");
+
+        let mut chunks: Vec<IVec2> = load_world
+            .query_filtered::<&TileChunk, With<Collider>>()
+            .iter(&load_world)
+            .map(|c| c.chunk)
+            .collect();
+        chunks.sort_by_key(|c| (c.y, c.x));
+        assert_eq!(chunks, vec![IVec2::new(0, 0), IVec2::new(1, 0)]);
+
+        let tile_colliders = load_world
+            .query_filtered::<(), (With<Tile>, With<Collider>)>()
+            .iter(&load_world)
+            .count();
+        assert_eq!(tile_colliders, 0);
+    }
+
     /// A grid with only Floor tiles serializes to a single key and
     /// round-trips correctly.
     #[test]
@@ -1334,6 +2876,7 @@ mod tests {
                     0,
                     TileDef {
                         kind: TileKind::Floor,
+                        floor: FloorKind::Plating,
                     },
                 );
                 m
@@ -1343,6 +2886,7 @@ mod tests {
                 m.insert((0, 0), "!!!not-valid-base64!!!".to_owned());
                 m
             },
+            decks: Vec::new(),
         };
         let raw = world::to_layer_value(&data).expect("serialize");
         let mut w = World::new();
@@ -1364,6 +2908,7 @@ mod tests {
                     0,
                     TileDef {
                         kind: TileKind::Floor,
+                        floor: FloorKind::Plating,
                     },
                 );
                 m
@@ -1375,6 +2920,7 @@ mod tests {
                 m.insert((0, 0), short);
                 m
             },
+            decks: Vec::new(),
         };
         let raw = world::to_layer_value(&data).expect("serialize");
         let mut w = World::new();
@@ -1402,6 +2948,7 @@ mod tests {
                     0,
                     TileDef {
                         kind: TileKind::Floor,
+                        floor: FloorKind::Plating,
                     },
                 );
                 m
@@ -1411,6 +2958,7 @@ mod tests {
                 m.insert((0, 0), encoded);
                 m
             },
+            decks: Vec::new(),
         };
         let raw = world::to_layer_value(&data).expect("serialize");
         let mut w = World::new();
@@ -1427,6 +2975,7 @@ mod tests {
             chunk_size: 32,
             keys: BTreeMap::new(),
             chunks: BTreeMap::new(),
+            decks: Vec::new(),
         };
         let raw = world::to_layer_value(&data).expect("serialize");
         let mut w = World::new();
@@ -1447,6 +2996,7 @@ mod tests {
             chunk_size: 0,
             keys: BTreeMap::new(),
             chunks: BTreeMap::new(),
+            decks: Vec::new(),
         };
         let raw = world::to_layer_value(&data).expect("serialize");
         let mut w = World::new();
@@ -1473,6 +3023,7 @@ mod tests {
                     0,
                     TileDef {
                         kind: TileKind::Floor,
+                        floor: FloorKind::Plating,
                     },
                 );
                 m
@@ -1483,6 +3034,7 @@ mod tests {
                 m.insert((-1, 0), encoded);
                 m
             },
+            decks: Vec::new(),
         };
         let raw = world::to_layer_value(&data).expect("serialize");
         let mut w = World::new();
@@ -0,0 +1,211 @@
+//! Line of sight over the tile grid.
+//!
+//! Sight is blocked by the same tiles that block light (see
+//! [`TileFlags::is_light_passable`]).  [`TileFlags::is_visible_from`]
+//! answers whether one cell can see another, e.g. for light or for the
+//! server deciding what is relevant to a client.  [`FieldOfView`] holds
+//! every cell visible from one origin, computed by recursive shadowcasting
+//! over the eight octants around it.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::TileFlags;
+
+/// Maps the octant-local `(dx, dy)` of [`cast_shadows`] onto the grid, one
+/// `[xx, xy, yx, yy]` matrix per octant.
+const OCTANTS: [[i32; 4]; 8] = [
+    [1, 0, 0, 1],
+    [0, 1, 1, 0],
+    [0, -1, 1, 0],
+    [-1, 0, 0, 1],
+    [-1, 0, 0, -1],
+    [0, -1, -1, 0],
+    [0, 1, -1, 0],
+    [1, 0, 0, -1],
+];
+
+impl TileFlags {
+    /// Whether `b` can be seen from `a`: every cell strictly between them
+    /// along a Bresenham line must let light through.  Opaque cells at
+    /// either end do not matter, so walls facing `a` are visible.
+    pub fn is_visible_from(&self, a: IVec2, b: IVec2) -> bool {
+        let delta = (b - a).abs();
+        let step = (b - a).signum();
+        let mut error = delta.x - delta.y;
+        let mut cell = a;
+        while cell != b {
+            if cell != a && !self.is_light_passable(cell) {
+                return false;
+            }
+            let doubled = 2 * error;
+            if doubled > -delta.y {
+                error -= delta.y;
+                cell.x += step.x;
+            }
+            if doubled < delta.x {
+                error += delta.x;
+                cell.y += step.y;
+            }
+        }
+        true
+    }
+}
+
+/// Marks a thing hidden because it is out of the player's sight.  Set by
+/// the `player` module from the [`FieldOfView`];
+/// [`apply_hiding`](crate::apply_hiding) hides what it marks.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct HiddenBySight;
+
+/// The cells visible from `origin` within `radius` tiles.
+///
+/// On clients this is the view of the player's creature, kept up to date by
+/// the `player` module; it is absent while the player sees everything.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct FieldOfView {
+    origin: IVec2,
+    radius: i32,
+    cells: HashSet<IVec2>,
+}
+
+impl FieldOfView {
+    /// Shadowcasts the view from `origin` over `flags`.
+    pub fn compute(flags: &TileFlags, origin: IVec2, radius: i32) -> Self {
+        let mut cells = HashSet::from([origin]);
+        for octant in OCTANTS {
+            cast_shadows(flags, origin, radius, 1, 1.0, 0.0, octant, &mut cells);
+        }
+        Self {
+            origin,
+            radius,
+            cells,
+        }
+    }
+
+    pub fn origin(&self) -> IVec2 {
+        self.origin
+    }
+
+    pub fn radius(&self) -> i32 {
+        self.radius
+    }
+
+    /// Whether `cell` is in view.
+    pub fn is_visible(&self, cell: IVec2) -> bool {
+        self.cells.contains(&cell)
+    }
+}
+
+/// Scans one octant row by row from `row`, between the slopes `start` and
+/// `end`, recursing past every run of opaque cells.
+#[allow(clippy::too_many_arguments)]
+fn cast_shadows(
+    flags: &TileFlags,
+    origin: IVec2,
+    radius: i32,
+    row: i32,
+    mut start: f32,
+    end: f32,
+    [xx, xy, yx, yy]: [i32; 4],
+    cells: &mut HashSet<IVec2>,
+) {
+    if start < end {
+        return;
+    }
+    let mut next_start = start;
+    for distance in row..=radius {
+        let dy = -distance;
+        let mut blocked = false;
+        for dx in -distance..=0 {
+            let left = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+            let right = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+            if start < right {
+                continue;
+            }
+            if end > left {
+                break;
+            }
+            let cell = origin + IVec2::new(dx * xx + dy * xy, dx * yx + dy * yy);
+            if dx * dx + dy * dy <= radius * radius {
+                cells.insert(cell);
+            }
+            let opaque = !flags.is_light_passable(cell);
+            if blocked {
+                if opaque {
+                    next_start = right;
+                } else {
+                    blocked = false;
+                    start = next_start;
+                }
+            } else if opaque && distance < radius {
+                blocked = true;
+                let octant = [xx, xy, yx, yy];
+                cast_shadows(
+                    flags,
+                    origin,
+                    radius,
+                    distance + 1,
+                    start,
+                    left,
+                    octant,
+                    cells,
+                );
+                next_start = right;
+            }
+        }
+        if blocked {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TileKind;
+
+    /// A 9×9 room of floor split by a wall along x = 6, open at y = 0.
+    fn room() -> TileFlags {
+        let mut flags = TileFlags::new(9, 9);
+        for y in 0..9 {
+            for x in 0..9 {
+                let kind = if x == 6 && y > 0 {
+                    TileKind::Wall
+                } else {
+                    TileKind::Floor
+                };
+                flags.set(IVec2::new(x, y), kind.flags());
+            }
+        }
+        flags
+    }
+
+    #[test]
+    fn walls_block_sight_but_are_seen() {
+        let flags = room();
+        let eye = IVec2::new(3, 5);
+        let fov = FieldOfView::compute(&flags, eye, 8);
+
+        assert!(fov.is_visible(eye));
+        assert!(fov.is_visible(IVec2::new(0, 8)));
+        assert!(fov.is_visible(IVec2::new(6, 5)));
+        assert!(!fov.is_visible(IVec2::new(7, 5)));
+        assert!(!fov.is_visible(IVec2::new(8, 8)));
+
+        assert!(flags.is_visible_from(eye, IVec2::new(6, 5)));
+        assert!(!flags.is_visible_from(eye, IVec2::new(8, 5)));
+        assert!(flags.is_visible_from(IVec2::new(3, 0), IVec2::new(8, 0)));
+    }
+
+    #[test]
+    fn sight_ends_at_the_radius() {
+        let fov = FieldOfView::compute(&room(), IVec2::new(0, 0), 3);
+        assert!(fov.is_visible(IVec2::new(3, 0)));
+        assert!(fov.is_visible(IVec2::new(2, 2)));
+        assert!(!fov.is_visible(IVec2::new(4, 0)));
+        assert!(!fov.is_visible(IVec2::new(3, 3)));
+    }
+}
@@ -0,0 +1,366 @@
+//! Rooms of the station, found by flood fill over the structure layer.
+//!
+//! A [`Zone`] is a connected area of floor and ladders, bounded by walls,
+//! doors and open space.  The server keeps the [`ZoneIndex`] up to date as
+//! the tile map changes and replicates it on the tiles stream; clients hold
+//! the same resource.  New zones are named "Room N" and keep their id and
+//! name while they are rebuilt, as long as they overlap their old area.
+//! A [`ZoneRenameRequest`] gives a zone a proper name such as "Medbay".
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use bevy::prelude::*;
+use network::StreamSender;
+use wincode::{SchemaRead, SchemaWrite};
+
+use crate::{TileGrid, TileKind, TilesStreamMessage};
+
+/// Identifies a [`Zone`]; stable across rebuilds of the [`ZoneIndex`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect, SchemaRead, SchemaWrite,
+)]
+#[reflect(Debug, PartialEq)]
+pub struct ZoneId(pub u32);
+
+/// One room.
+#[derive(Debug, Clone, PartialEq, SchemaRead, SchemaWrite)]
+pub struct Zone {
+    pub id: ZoneId,
+    pub name: String,
+    /// The cell of the zone nearest its centre, where labels go.
+    pub anchor: [i32; 2],
+    /// Number of cells in the zone.
+    pub area: u32,
+}
+
+/// Every [`Zone`] and which cell belongs to which.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct ZoneIndex {
+    width: u32,
+    height: u32,
+    cells: Vec<Option<ZoneId>>,
+    zones: BTreeMap<ZoneId, Zone>,
+    next_id: u32,
+}
+
+/// Server-side request to rename a zone, written by the server console's
+/// `zone rename` command.  Names are trimmed; empty ones are ignored.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct ZoneRenameRequest {
+    pub zone: ZoneId,
+    pub name: String,
+}
+
+/// Whether a cell of this kind belongs to a room.
+fn is_room_cell(kind: TileKind) -> bool {
+    matches!(kind, TileKind::Floor | TileKind::Ladder)
+}
+
+impl ZoneIndex {
+    /// Finds the zones of `grid`.  Zones that overlap one in `previous` keep
+    /// its id and name; the largest overlap wins when several do.
+    pub fn detect(grid: &TileGrid<TileKind>, previous: Option<&ZoneIndex>) -> Self {
+        let (width, height) = (grid.width(), grid.height());
+        let mut regions: Vec<Vec<IVec2>> = Vec::new();
+        let mut region_of: Vec<Option<usize>> = vec![None; grid.cells().len()];
+        let index = |pos: IVec2| (pos.y as u32 * width + pos.x as u32) as usize;
+
+        for (start, &kind) in grid.iter() {
+            if !is_room_cell(kind) || region_of[index(start)].is_some() {
+                continue;
+            }
+            let region = regions.len();
+            let mut cells = Vec::new();
+            let mut queue = VecDeque::from([start]);
+            region_of[index(start)] = Some(region);
+            while let Some(cell) = queue.pop_front() {
+                cells.push(cell);
+                for step in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                    let next = cell + step;
+                    if grid.get(next).is_some_and(|&kind| is_room_cell(kind))
+                        && region_of[index(next)].is_none()
+                    {
+                        region_of[index(next)] = Some(region);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            regions.push(cells);
+        }
+
+        // Hand the old ids to the regions that overlap them most.
+        let mut ids: Vec<Option<ZoneId>> = vec![None; regions.len()];
+        let mut next_id = previous.map_or(1, |previous| previous.next_id);
+        if let Some(previous) = previous {
+            let mut overlaps: HashMap<(usize, ZoneId), u32> = HashMap::new();
+            for (region, cells) in regions.iter().enumerate() {
+                for &cell in cells {
+                    if let Some(id) = previous.id_at(cell) {
+                        *overlaps.entry((region, id)).or_default() += 1;
+                    }
+                }
+            }
+            let mut overlaps: Vec<_> = overlaps.into_iter().collect();
+            overlaps.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            for ((region, id), _) in overlaps {
+                if ids[region].is_none() && !ids.contains(&Some(id)) {
+                    ids[region] = Some(id);
+                }
+            }
+        }
+
+        let mut zones = BTreeMap::new();
+        let mut cells = vec![None; grid.cells().len()];
+        for (region, members) in regions.iter().enumerate() {
+            let id = *ids[region].get_or_insert_with(|| {
+                next_id += 1;
+                ZoneId(next_id - 1)
+            });
+            let name = previous
+                .and_then(|previous| previous.zones.get(&id))
+                .map_or_else(|| format!("Room {}", id.0), |zone| zone.name.clone());
+            let centre =
+                members.iter().map(|cell| cell.as_vec2()).sum::<Vec2>() / members.len() as f32;
+            let anchor = members
+                .iter()
+                .min_by(|a, b| {
+                    let a = a.as_vec2().distance_squared(centre);
+                    let b = b.as_vec2().distance_squared(centre);
+                    a.total_cmp(&b)
+                })
+                .copied()
+                .unwrap_or_default();
+            for &cell in members {
+                cells[index(cell)] = Some(id);
+            }
+            zones.insert(
+                id,
+                Zone {
+                    id,
+                    name,
+                    anchor: anchor.into(),
+                    area: members.len() as u32,
+                },
+            );
+        }
+
+        Self {
+            width,
+            height,
+            cells,
+            zones,
+            next_id,
+        }
+    }
+
+    fn id_at(&self, pos: IVec2) -> Option<ZoneId> {
+        if pos.x < 0 || pos.y < 0 || pos.x >= self.width as i32 || pos.y >= self.height as i32 {
+            return None;
+        }
+        self.cells[(pos.y as u32 * self.width + pos.x as u32) as usize]
+    }
+
+    /// The zone the cell at `pos` belongs to, if any.
+    pub fn zone_at(&self, pos: IVec2) -> Option<&Zone> {
+        self.id_at(pos).and_then(|id| self.zones.get(&id))
+    }
+
+    /// Every cell of the zone with `id`, row by row.
+    pub fn cells_of(&self, id: ZoneId) -> impl Iterator<Item = IVec2> + '_ {
+        let width = self.width.max(1) as usize;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(move |(_, cell)| **cell == Some(id))
+            .map(move |(index, _)| IVec2::new((index % width) as i32, (index / width) as i32))
+    }
+
+    /// The zone with `id`, if it still exists.
+    pub fn zone(&self, id: ZoneId) -> Option<&Zone> {
+        self.zones.get(&id)
+    }
+
+    /// Every zone, by id.
+    pub fn iter(&self) -> impl Iterator<Item = &Zone> + '_ {
+        self.zones.values()
+    }
+
+    /// Renames the zone with `id`.  Returns `false` if there is none.
+    pub fn rename(&mut self, id: ZoneId, name: impl Into<String>) -> bool {
+        let Some(zone) = self.zones.get_mut(&id) else {
+            return false;
+        };
+        zone.name = name.into();
+        true
+    }
+
+    /// Whether both hold the same zones over the same cells, ignoring which
+    /// id the next new zone would get.
+    fn same_zones(&self, other: &ZoneIndex) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self.cells == other.cells
+            && self.zones == other.zones
+    }
+
+    /// The [`TilesStreamMessage::Zones`] replicating this index.  Cells are
+    /// run-length encoded as `[count, id]` pairs, id 0 meaning no zone.
+    pub fn to_message(&self) -> TilesStreamMessage {
+        let mut runs: Vec<[u32; 2]> = Vec::new();
+        for cell in &self.cells {
+            let id = cell.map_or(0, |id| id.0);
+            match runs.last_mut() {
+                Some([count, last]) if *last == id => *count += 1,
+                _ => runs.push([1, id]),
+            }
+        }
+        TilesStreamMessage::Zones {
+            width: self.width,
+            height: self.height,
+            zones: self.zones.values().cloned().collect(),
+            runs,
+        }
+    }
+
+    /// Rebuilds the index from the fields of a
+    /// [`TilesStreamMessage::Zones`].
+    pub fn from_message(
+        width: u32,
+        height: u32,
+        zones: Vec<Zone>,
+        runs: &[[u32; 2]],
+    ) -> Result<Self, String> {
+        let cells: Vec<Option<ZoneId>> = runs
+            .iter()
+            .flat_map(|&[count, id]| {
+                std::iter::repeat_n((id != 0).then_some(ZoneId(id)), count as usize)
+            })
+            .collect();
+        let expected = width as usize * height as usize;
+        if cells.len() != expected {
+            return Err(format!(
+                "zone runs cover {} cells, expected {expected}",
+                cells.len()
+            ));
+        }
+        let zones: BTreeMap<ZoneId, Zone> = zones.into_iter().map(|zone| (zone.id, zone)).collect();
+        let next_id = zones.keys().next_back().map_or(1, |id| id.0 + 1);
+        Ok(Self {
+            width,
+            height,
+            cells,
+            zones,
+            next_id,
+        })
+    }
+}
+
+/// Server system: rebuilds the [`ZoneIndex`] when the structure layer
+/// changes and broadcasts it to clients if the zones changed.
+pub(crate) fn update_zones(
+    mut commands: Commands,
+    grid: Option<Res<TileGrid<TileKind>>>,
+    zones: Option<Res<ZoneIndex>>,
+    sender: Option<Res<StreamSender<TilesStreamMessage>>>,
+) {
+    let Some(grid) = grid else {
+        return;
+    };
+    if zones.is_some() && !grid.is_changed() {
+        return;
+    }
+    let detected = ZoneIndex::detect(&grid, zones.as_deref());
+    if zones.is_some_and(|zones| zones.same_zones(&detected)) {
+        return;
+    }
+    info!("Detected {} zone(s)", detected.zones.len());
+    if let Some(sender) = sender
+        && let Err(e) = sender.broadcast(&detected.to_message())
+    {
+        error!("Failed to broadcast zones: {e}");
+    }
+    commands.insert_resource(detected);
+}
+
+/// Server system: applies [`ZoneRenameRequest`]s and tells clients with
+/// [`TilesStreamMessage::ZoneRenamed`].
+pub(crate) fn apply_zone_renames(
+    mut requests: MessageReader<ZoneRenameRequest>,
+    zones: Option<ResMut<ZoneIndex>>,
+    sender: Option<Res<StreamSender<TilesStreamMessage>>>,
+) {
+    let Some(mut zones) = zones else {
+        requests.clear();
+        return;
+    };
+    for request in requests.read() {
+        let name = request.name.trim();
+        if name.is_empty() || !zones.rename(request.zone, name) {
+            warn!("Ignoring rename of zone {} to {name:?}", request.zone.0);
+            continue;
+        }
+        info!("Renamed zone {} to {name:?}", request.zone.0);
+        if let Some(sender) = sender.as_deref()
+            && let Err(e) = sender.broadcast(&TilesStreamMessage::ZoneRenamed {
+                zone: request.zone,
+                name: name.to_string(),
+            })
+        {
+            error!("Failed to broadcast zone rename: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two rooms side by side: x 0..3 and x 4..7, split by a wall at x = 3
+    /// with a door at y = 1.
+    fn station() -> TileGrid<TileKind> {
+        let mut grid = TileGrid::new(7, 3);
+        for y in 0..3 {
+            grid.set(IVec2::new(3, y), TileKind::Wall);
+        }
+        grid.set(IVec2::new(3, 1), TileKind::Door { open: true });
+        grid
+    }
+
+    #[test]
+    fn rooms_are_split_by_walls_and_doors_and_keep_their_names() {
+        let mut zones = ZoneIndex::detect(&station(), None);
+        assert_eq!(zones.iter().count(), 2);
+        let left = zones.zone_at(IVec2::new(0, 0)).unwrap().clone();
+        let right = zones.zone_at(IVec2::new(6, 2)).unwrap().clone();
+        assert_ne!(left.id, right.id);
+        assert_eq!(left.name, "Room 1");
+        assert_eq!(left.area, 9);
+        assert_eq!(left.anchor, [1, 1]);
+        assert!(zones.zone_at(IVec2::new(3, 1)).is_none());
+        assert_eq!(zones.cells_of(left.id).count(), 9);
+
+        assert!(zones.rename(right.id, "Medbay"));
+
+        // Walling off a corner of the right room splits it; the larger part
+        // keeps the name, the corner becomes a new room.
+        let mut grid = station();
+        grid.set(IVec2::new(5, 0), TileKind::Wall);
+        grid.set(IVec2::new(6, 1), TileKind::Wall);
+        let rebuilt = ZoneIndex::detect(&grid, Some(&zones));
+        assert_eq!(rebuilt.zone_at(IVec2::new(4, 2)).unwrap().name, "Medbay");
+        assert_eq!(rebuilt.zone_at(IVec2::new(0, 0)).unwrap().id, left.id);
+        assert_eq!(rebuilt.zone_at(IVec2::new(6, 0)).unwrap().name, "Room 3");
+
+        let TilesStreamMessage::Zones {
+            width,
+            height,
+            zones: list,
+            runs,
+        } = rebuilt.to_message()
+        else {
+            panic!("expected Zones");
+        };
+        let replicated = ZoneIndex::from_message(width, height, list, &runs).unwrap();
+        assert!(replicated.same_zones(&rebuilt));
+    }
+}
@@ -40,6 +40,11 @@ impl ButtonBuilder {
         self
     }
 
+    pub fn with_text_color(mut self, color: Color) -> Self {
+        self.text_color = color;
+        self
+    }
+
     pub fn with_colors(mut self, normal: Color, hovered: Color, pressed: Color) -> Self {
         self.colors = ButtonColors {
             normal,
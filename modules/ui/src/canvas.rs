@@ -0,0 +1,54 @@
+//! Textures drawn on the CPU for widgets such as the minimap.
+//!
+//! A canvas is an [`Image`] kept in main-world memory, so systems can change
+//! single pixels with [`Image::set_color_at`] through
+//! `Assets<Image>::get_mut`; Bevy uploads it to the GPU again after every
+//! change.  Show it with an [`ImageNode`].
+
+use bevy::asset::RenderAssetUsages;
+use bevy::color::ColorToPacked;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+/// A `size` pixel canvas filled with `fill`.  It is sampled without
+/// filtering, so each pixel stays a crisp square when a node scales it up.
+/// Pixels are stored as linear values rather than sRGB, so a colour read
+/// back is the one written, without a lossy gamma round trip.
+pub fn canvas_image(size: UVec2, fill: Color) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: size.x.max(1),
+            height: size.y.max(1),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &fill.to_linear().to_u8_array(),
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canvas_is_filled_and_drawable() {
+        let mut image = canvas_image(UVec2::new(4, 2), Color::BLACK);
+        assert_eq!(image.size(), UVec2::new(4, 2));
+        assert_eq!(
+            image.get_color_at(3, 1).unwrap().to_srgba().to_u8_array(),
+            [0, 0, 0, 255]
+        );
+
+        image.set_color_at(3, 1, Color::WHITE).unwrap();
+        assert_eq!(
+            image.get_color_at(3, 1).unwrap().to_srgba().to_u8_array(),
+            [255, 255, 255, 255]
+        );
+        assert!(image.set_color_at(4, 0, Color::WHITE).is_err());
+    }
+}
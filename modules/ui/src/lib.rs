@@ -2,10 +2,12 @@ use bevy::prelude::*;
 use bevy::transform::TransformSystems;
 
 pub mod button;
+pub mod canvas;
 pub mod overlay;
 pub mod theme;
 
 pub use button::build_button;
+pub use canvas::canvas_image;
 pub use overlay::{OverlayOffset, OverlayTarget, WorldSpaceOverlay, update_world_space_overlays};
 pub use theme::UiTheme;
 
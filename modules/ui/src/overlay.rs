@@ -51,8 +51,9 @@ pub struct OverlayOffset(pub Vec3);
 /// so that entity [`GlobalTransform`]s are fully propagated before projection.
 ///
 /// The system:
-/// 1. Fetches the active [`Camera3d`] once. Hides all overlay nodes and returns
-///    early if no camera is present (e.g. headless server or camera despawned).
+/// 1. Fetches the active [`Camera3d`] with the lowest order once. Hides all
+///    overlay nodes if no camera is active (e.g. headless server or camera
+///    despawned).
 /// 2. If the overlay has an [`OverlayTarget`], resolves the entity's
 ///    [`GlobalTransform`] (+ optional [`OverlayOffset`]) and writes it into
 ///    [`WorldSpaceOverlay::world_pos`].
@@ -73,7 +74,10 @@ pub fn update_world_space_overlays(
     )>,
 ) {
     // Fetch the camera once before iterating overlays.
-    let camera_result = camera_query.single();
+    let camera_result = camera_query
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .min_by_key(|(camera, _)| camera.order);
 
     for (mut node, mut visibility, computed, mut overlay, maybe_target, maybe_offset) in
         overlay_query.iter_mut()
@@ -89,7 +93,7 @@ pub fn update_world_space_overlays(
         }
 
         // Need an active 3D camera to project through.
-        let Ok((camera, camera_gt)) = camera_result else {
+        let Some((camera, camera_gt)) = camera_result else {
             *visibility = Visibility::Hidden;
             continue;
         };
@@ -1,13 +1,17 @@
 pub mod lifecycle;
 pub mod loader;
 pub mod map_file;
+pub mod saver;
 
 pub use lifecycle::{WorldLoading, WorldReady, WorldTeardown};
-pub use loader::MapPath;
+pub use loader::{MapPath, reload_map};
 pub use map_file::{
     CURRENT_MAP_VERSION, MapFile, MapLayer, MapLayerRegistry, MapLayerRegistryExt,
     from_layer_value, to_layer_value,
 };
+pub use saver::{
+    SaveBackups, SavePath, WorldSaveRequested, backup_path, crash_path, save_world, save_world_to,
+};
 
 use bevy::prelude::*;
 
@@ -23,6 +27,12 @@ use bevy::prelude::*;
 ///
 /// [`MapPath`] is cleaned up on `OnExit(in_game)` so a subsequent host
 /// session picks up fresh config.
+///
+/// A [`WorldSaveRequested`] message writes the live world to [`SavePath`]
+/// via [`saver::save_map`] in `PostUpdate`; without a [`SavePath`] the request
+/// is ignored.  Saves are written atomically with a checksum, keeping
+/// [`SaveBackups`] earlier ones, and a damaged map file is loaded from its
+/// latest good backup.
 pub struct WorldPlugin<S: States + Copy> {
     pub loading: S,
     pub in_game: S,
@@ -34,7 +44,12 @@ impl<S: States + Copy> Plugin for WorldPlugin<S> {
         app.add_message::<WorldLoading>();
         app.add_message::<WorldReady>();
         app.add_message::<WorldTeardown>();
+        app.add_message::<WorldSaveRequested>();
         app.add_systems(OnEnter(self.loading), loader::load_map);
+        app.add_systems(
+            PostUpdate,
+            saver::save_map.run_if(on_message::<WorldSaveRequested>),
+        );
         app.add_systems(OnExit(self.in_game), cleanup_map_path);
     }
 }
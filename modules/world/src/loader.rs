@@ -1,7 +1,10 @@
+use std::path::Path;
+
 use bevy::prelude::*;
 
 use crate::lifecycle::{WorldLoading, WorldReady};
 use crate::map_file::{CURRENT_MAP_VERSION, MapFile, MapLayerRegistry};
+use crate::saver::{backup_path, unseal};
 
 /// Resource that specifies the path to the `.station.ron` map file to load on
 /// startup.
@@ -27,7 +30,10 @@ impl MapPath {
 ///
 /// 1. Reads [`MapPath`] to locate the map file on disk. If absent, logs an
 ///    info message and returns early — the app continues in an uninitialised state.
-/// 2. Reads and parses the RON map file into a [`MapFile`].
+/// 2. Reads the map file, checks its checksum if it has one (see
+///    [`crate::SavePath`]) and parses it into a [`MapFile`].  If that fails,
+///    the most recent backup (`<file>.1`, `<file>.2`, ...) that passes is
+///    loaded instead.
 /// 3. Validates that `file.version <= CURRENT_MAP_VERSION`. If the file was
 ///    written by a newer build (unknown version), loading is aborted to avoid
 ///    silent misloads.
@@ -45,34 +51,14 @@ pub fn load_map(world: &mut World) {
         return;
     };
 
-    let contents = match std::fs::read_to_string(&map_path) {
-        Ok(c) => c,
-        Err(e) => {
-            error!("WorldPlugin: failed to read map file {:?}: {}", map_path, e);
-            return;
-        }
-    };
-
-    let file: MapFile = match ron::from_str(&contents) {
+    let file = match read_loadable_map(&map_path) {
         Ok(f) => f,
         Err(e) => {
-            error!(
-                "WorldPlugin: failed to parse map file {:?}: {}",
-                map_path, e
-            );
+            error!("WorldPlugin: {e}");
             return;
         }
     };
 
-    if file.version > CURRENT_MAP_VERSION {
-        error!(
-            "WorldPlugin: map file {:?} has version {} which is newer than the \
-             supported version {}; refusing to load to avoid silent misloads",
-            map_path, file.version, CURRENT_MAP_VERSION
-        );
-        return;
-    }
-
     world.write_message(WorldLoading);
 
     let result = world
@@ -92,6 +78,76 @@ pub fn load_map(world: &mut World) {
     }
 }
 
+/// Loads every layer of the map at [`MapPath`] again over the running world,
+/// e.g. to reset the station between rounds or to switch to another map.
+///
+/// Reads the file the way [`load_map`] does, then calls
+/// [`MapLayerRegistry::load_all`], so each layer replaces what it loaded
+/// before.  Entities a layer spawned that are not resources (things, say) are
+/// the caller's to clear first.  Writes neither [`WorldLoading`] nor
+/// [`WorldReady`]: the world stays in game throughout.
+pub fn reload_map(world: &mut World) -> Result<(), String> {
+    let map_path = world
+        .get_resource::<MapPath>()
+        .map(|r| r.0.clone())
+        .ok_or("no MapPath resource")?;
+    let file = read_loadable_map(&map_path)?;
+    world
+        .resource_scope(|world, registry: Mut<MapLayerRegistry>| registry.load_all(&file, world))
+        .map_err(|e| format!("layer loading failed for {map_path:?}: {e}"))?;
+    info!("WorldPlugin: map reloaded from {:?}", map_path);
+    Ok(())
+}
+
+/// Reads the map file at `map_path`, falling back to its latest good backup,
+/// and checks that this build can load its version.
+fn read_loadable_map(map_path: &str) -> Result<MapFile, String> {
+    let file = match read_map_file(Path::new(map_path)) {
+        Ok(f) => f,
+        Err(e) => {
+            error!("WorldPlugin: failed to read map file {:?}: {}", map_path, e);
+            read_latest_backup(Path::new(map_path))
+                .ok_or_else(|| format!("no usable map file at {map_path:?}"))?
+        }
+    };
+
+    if file.version > CURRENT_MAP_VERSION {
+        return Err(format!(
+            "map file {:?} has version {} which is newer than the supported version {}; \
+             refusing to load to avoid silent misloads",
+            map_path, file.version, CURRENT_MAP_VERSION
+        ));
+    }
+    Ok(file)
+}
+
+/// Reads, checks and parses the map file at `path`.
+fn read_map_file(path: &Path) -> Result<MapFile, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let contents = unseal(&text)?;
+    ron::from_str(contents).map_err(|e| format!("parse error: {e}"))
+}
+
+/// The most recent backup of the map file at `path` that reads, checks and
+/// parses; `None` when there is none.
+fn read_latest_backup(path: &Path) -> Option<MapFile> {
+    let mut n = 1;
+    loop {
+        let backup = backup_path(path, n);
+        if !backup.exists() {
+            return None;
+        }
+        match read_map_file(&backup) {
+            Ok(file) => {
+                warn!("WorldPlugin: falling back to backup {:?}", backup);
+                return Some(file);
+            }
+            Err(e) => error!("WorldPlugin: backup {:?} is unusable too: {}", backup, e),
+        }
+        n += 1;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
@@ -272,6 +328,25 @@ mod tests {
         );
     }
 
+    /// A save whose checksum does not match is passed over for its most
+    /// recent backup that does.
+    #[test]
+    fn load_map_falls_back_to_the_latest_valid_backup() {
+        let file = TempFile::new("// checksum: 0000000000000000\n(version: 1, layers: {})");
+        let path = std::path::PathBuf::from(file.path_str());
+        let torn = TempFile(backup_path(&path, 1));
+        std::fs::write(&torn.0, "(version: 1, lay").unwrap();
+        let good = TempFile(backup_path(&path, 2));
+        std::fs::write(&good.0, "(version: 1, layers: { \"stub\": () })").unwrap();
+
+        let mut world = make_world();
+        world.resource_mut::<MapLayerRegistry>().register(StubLayer);
+        world.insert_resource(MapPath::new(file.path_str()));
+        load_map(&mut world);
+        assert_eq!(world_ready_count(&world), 1);
+        assert!(world.contains_resource::<StubLoaded>());
+    }
+
     /// A valid map file with no registered layers still emits [`WorldReady`].
     #[test]
     fn load_map_emits_world_ready_with_no_layers() {
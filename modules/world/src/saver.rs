@@ -0,0 +1,321 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+use crate::map_file::{MapFile, MapLayerRegistry};
+
+/// Resource naming the `.station.ron` file that the running world is saved
+/// to.
+///
+/// Without it, [`WorldSaveRequested`] is ignored.  Kept separate from
+/// [`crate::MapPath`] so that saving never overwrites the map the server
+/// started from unless both point at the same file.
+#[derive(Resource, Debug, Clone)]
+pub struct SavePath(pub String);
+
+impl SavePath {
+    /// Create a new `SavePath` from any string-like value.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self(path.into())
+    }
+}
+
+/// Resource: how many earlier saves are kept next to [`SavePath`], as
+/// `<file>.1` (the most recent) to `<file>.<n>`.  Without it none are kept.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveBackups(pub usize);
+
+/// Message asking for the live world to be written to [`SavePath`], e.g.
+/// before a server shuts down.  Handled by [`save_map`].
+#[derive(Message, Debug, Clone)]
+pub struct WorldSaveRequested;
+
+/// Start of the first line of a save file, followed by the checksum of the
+/// rest of the file in hex.  A RON comment, so saves stay readable as maps.
+const CHECKSUM_PREFIX: &str = "// checksum: ";
+
+/// Exclusive system that, on [`WorldSaveRequested`], saves the world with
+/// [`save_world`].
+///
+/// Several requests in one frame produce a single save.  Errors are logged;
+/// the world is left untouched either way.
+///
+/// Registered in `PostUpdate` by [`crate::WorldPlugin`], which only runs it
+/// in frames with a request, so it does not hold the world exclusively every
+/// frame.
+pub fn save_map(world: &mut World) {
+    let requested = world
+        .resource_mut::<Messages<WorldSaveRequested>>()
+        .drain()
+        .count()
+        > 0;
+    if !requested {
+        return;
+    }
+    if !world.contains_resource::<SavePath>() {
+        info!("WorldPlugin: world save requested but no SavePath is set, skipping");
+        return;
+    }
+    match save_world(world) {
+        Ok(path) => info!("WorldPlugin: world saved to {:?}", path),
+        Err(e) => error!("WorldPlugin: failed to save the world: {}", e),
+    }
+}
+
+/// Serializes every registered layer via [`MapLayerRegistry::save_all`] and
+/// writes the result to [`SavePath`], keeping [`SaveBackups`] earlier saves.
+/// Returns the path written.
+///
+/// Also called outside the schedule, e.g. by a server that panicked, so
+/// missing resources are reported rather than assumed.
+pub fn save_world(world: &mut World) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let save_path = world
+        .get_resource::<SavePath>()
+        .map(|r| r.0.clone())
+        .ok_or("no SavePath is set")?;
+    let backups = world.get_resource::<SaveBackups>().map_or(0, |b| b.0);
+    save_world_to(world, Path::new(&save_path), backups)?;
+    Ok(save_path)
+}
+
+/// Like [`save_world`], but writes to `path` instead of [`SavePath`],
+/// keeping `backups` earlier saves of `path`.
+pub fn save_world_to(
+    world: &mut World,
+    path: &Path,
+    backups: usize,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if !world.contains_resource::<MapLayerRegistry>() {
+        return Err("the map layer registry is gone".into());
+    }
+    let file =
+        world.resource_scope(|world, registry: Mut<MapLayerRegistry>| registry.save_all(world))?;
+    write_map_file(path, &file, backups)
+}
+
+/// Writes `file` to `path` as pretty-printed RON under a checksum line,
+/// creating parent directories as needed.
+///
+/// The file is written to `<path>.tmp` and renamed over `path`, so a crash
+/// mid-write never leaves a truncated save behind.  The save it replaces is
+/// first copied to `<path>.1`, shifting older backups up to `backups`.
+fn write_map_file(
+    path: &Path,
+    file: &MapFile,
+    backups: usize,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let contents = ron::ser::to_string_pretty(file, ron::ser::PrettyConfig::default())?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp = suffixed(path, "tmp");
+    {
+        use std::io::Write;
+        let mut out = std::fs::File::create(&temp)?;
+        out.write_all(seal(&contents).as_bytes())?;
+        out.sync_all()?;
+    }
+    rotate_backups(path, backups)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// Shifts `<path>.1` .. `<path>.<backups - 1>` up by one, dropping the
+/// oldest, and copies `path` to `<path>.1`.  Does nothing without backups
+/// or an existing save.
+fn rotate_backups(path: &Path, backups: usize) -> std::io::Result<()> {
+    if backups == 0 || !path.exists() {
+        return Ok(());
+    }
+    for n in (1..backups).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+    std::fs::copy(path, backup_path(path, 1))?;
+    Ok(())
+}
+
+/// `<path>.<n>`, the `n`th most recent backup of the save at `path`.
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    suffixed(path, &n.to_string())
+}
+
+/// `<path>.crash`, where a server that panicked saves the world instead of
+/// over the last good save at `path`.
+pub fn crash_path(path: &Path) -> PathBuf {
+    suffixed(path, "crash")
+}
+
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// FNV-1a hash of `contents`: cheap, and enough to tell a torn or
+/// hand-damaged save from a good one.
+fn checksum(contents: &str) -> u64 {
+    contents.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// `contents` under a line holding its checksum.
+fn seal(contents: &str) -> String {
+    format!("{CHECKSUM_PREFIX}{:016x}\n{contents}", checksum(contents))
+}
+
+/// The contents of a file written by [`seal`], once its checksum matched.
+/// Files without a checksum line, like hand-written maps, are taken as
+/// they are.
+pub(crate) fn unseal(text: &str) -> Result<&str, String> {
+    let Some(sealed) = text.strip_prefix(CHECKSUM_PREFIX) else {
+        return Ok(text);
+    };
+    let (stored, contents) = sealed
+        .split_once('\n')
+        .ok_or("the file ends after its checksum")?;
+    let stored =
+        u64::from_str_radix(stored.trim(), 16).map_err(|_| format!("bad checksum {stored:?}"))?;
+    if stored != checksum(contents) {
+        return Err("checksum mismatch: the file is damaged or incomplete".into());
+    }
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use ron::value::RawValue;
+
+    use super::*;
+    use crate::map_file::{MapLayer, to_layer_value};
+
+    #[derive(Resource)]
+    struct Counter(u32);
+
+    struct CounterLayer;
+
+    impl MapLayer for CounterLayer {
+        fn key(&self) -> &'static str {
+            "counter"
+        }
+
+        fn save(
+            &self,
+            world: &World,
+        ) -> Result<Box<RawValue>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(to_layer_value(&world.resource::<Counter>().0)?)
+        }
+
+        fn load(
+            &self,
+            _data: &RawValue,
+            _world: &mut World,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+
+        fn unload(&self, _world: &mut World) {}
+    }
+
+    fn make_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<MapLayerRegistry>();
+        world.init_resource::<Messages<WorldSaveRequested>>();
+        world
+            .resource_mut::<MapLayerRegistry>()
+            .register(CounterLayer);
+        world.insert_resource(Counter(7));
+        world
+    }
+
+    /// A requested save writes the live layer state to [`SavePath`].
+    #[test]
+    fn save_map_writes_live_layers_to_save_path() {
+        let path =
+            std::env::temp_dir().join(format!("saver_test_{}.station.ron", std::process::id()));
+        let mut world = make_world();
+        world.insert_resource(SavePath::new(path.to_string_lossy()));
+
+        save_map(&mut world);
+        assert!(!path.exists(), "nothing is saved without a request");
+
+        world.write_message(WorldSaveRequested);
+        save_map(&mut world);
+        let contents = std::fs::read_to_string(&path).expect("save file written");
+        let _ = std::fs::remove_file(&path);
+
+        let file: MapFile =
+            ron::from_str(unseal(&contents).expect("checksum matches")).expect("save file parses");
+        assert_eq!(file.layers["counter"].get_ron().trim(), "7");
+    }
+
+    /// Each save moves the one before it into the backups, keeping only as
+    /// many as [`SaveBackups`] asks for.
+    #[test]
+    fn saves_rotate_backups_and_detect_damage() {
+        let path = std::env::temp_dir().join(format!(
+            "saver_backup_test_{}.station.ron",
+            std::process::id()
+        ));
+        let mut world = make_world();
+        world.insert_resource(SavePath::new(path.to_string_lossy()));
+        world.insert_resource(SaveBackups(2));
+        for count in 1..=4 {
+            world.insert_resource(Counter(count));
+            save_world(&mut world).expect("saved");
+        }
+        let read = |path: &Path| std::fs::read_to_string(path).unwrap();
+        let saved = read(&path);
+        let backups = [read(&backup_path(&path, 1)), read(&backup_path(&path, 2))];
+        let third_exists = backup_path(&path, 3).exists();
+        for file in [path.clone(), backup_path(&path, 1), backup_path(&path, 2)] {
+            let _ = std::fs::remove_file(file);
+        }
+
+        assert!(!third_exists);
+        assert!(unseal(&saved).unwrap().contains('4'));
+        assert!(unseal(&backups[0]).unwrap().contains('3'));
+        assert!(unseal(&backups[1]).unwrap().contains('2'));
+        let torn = &saved[..saved.len() - 4];
+        assert!(unseal(torn).is_err());
+    }
+
+    /// A save to the crash file leaves the save and its backups as they were.
+    #[test]
+    fn crash_saves_leave_the_last_save_alone() {
+        let path = std::env::temp_dir().join(format!(
+            "saver_crash_test_{}.station.ron",
+            std::process::id()
+        ));
+        let mut world = make_world();
+        world.insert_resource(SavePath::new(path.to_string_lossy()));
+        world.insert_resource(SaveBackups(2));
+        save_world(&mut world).expect("saved");
+        world.insert_resource(Counter(8));
+        let crash = crash_path(&path);
+        save_world_to(&mut world, &crash, 0).expect("crash saved");
+        let saved = std::fs::read_to_string(&path).unwrap();
+        let crashed = std::fs::read_to_string(&crash).unwrap();
+        let backup_exists = backup_path(&path, 1).exists();
+        for file in [path.clone(), crash] {
+            let _ = std::fs::remove_file(file);
+        }
+
+        assert!(!backup_exists);
+        assert!(unseal(&saved).unwrap().contains('7'));
+        assert!(unseal(&crashed).unwrap().contains('8'));
+    }
+
+    /// Without a [`SavePath`] a request is consumed and nothing is written.
+    #[test]
+    fn save_map_skips_without_save_path() {
+        let mut world = make_world();
+        world.write_message(WorldSaveRequested);
+        save_map(&mut world);
+        assert!(world.resource::<Messages<WorldSaveRequested>>().is_empty());
+    }
+}